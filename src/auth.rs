@@ -0,0 +1,46 @@
+// ============================================================================
+// API KEYS - Who may change state over HTTP
+// ============================================================================
+// Mutating HTTP requests (POST and DELETE) must carry one of the configured
+// keys, either as `X-API-Key: <key>` or `Authorization: Bearer <key>`. Reads
+// stay open. With no keys configured nothing is checked, as before.
+
+use std::collections::HashSet;
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ApiKeys {
+    keys: HashSet<String>,
+}
+
+impl ApiKeys {
+    pub fn new<I: IntoIterator<Item = S>, S: Into<String>>(keys: I) -> Self {
+        ApiKeys { keys: keys.into_iter().map(Into::into).collect() }
+    }
+
+    /// No keys: every request is allowed
+    pub fn is_empty(&self) -> bool {
+        self.keys.is_empty()
+    }
+
+    pub fn len(&self) -> usize {
+        self.keys.len()
+    }
+
+    /// Whether a request with these header values may mutate state
+    pub fn authorize(&self, api_key: Option<&str>, authorization: Option<&str>) -> bool {
+        if self.is_empty() {
+            return true;
+        }
+        let bearer = authorization.and_then(|value| value.trim().strip_prefix("Bearer "));
+        [api_key, bearer]
+            .into_iter()
+            .flatten()
+            .any(|presented| self.keys.iter().any(|key| constant_time_eq(key.as_bytes(), presented.trim().as_bytes())))
+    }
+}
+
+/// Compares without stopping at the first differing byte, so response
+/// timing does not give away how much of a guessed key was right
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0u8, |diff, (x, y)| diff | (x ^ y)) == 0
+}
@@ -0,0 +1,199 @@
+// ============================================================================
+// BBO PUBLISHER - Throttled best-bid-offer sampling
+// ============================================================================
+// Consumers that don't need every tick get at most one BBO per interval.
+// Changes inside an interval are coalesced and the latest values win.
+
+use crate::clock::Clock;
+use crate::events::{BookEvent, EventBus};
+use crate::matching_engine::OrderBook;
+use crate::price_units::Price;
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+
+/// Default sampling cadence
+pub const DEFAULT_BBO_INTERVAL_NS: u64 = 10_000_000;
+
+/// Spread samples kept unless configured otherwise
+pub const DEFAULT_SPREAD_HISTORY_CAPACITY: usize = 1000;
+
+/// Best `(price, displayed_quantity)` on each side
+type Top = (Option<(Price, u64)>, Option<(Price, u64)>);
+
+/// The spread at one published BBO
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct SpreadSample {
+    pub timestamp_ns: u64,
+    pub bid: Price,
+    pub ask: Price,
+    /// Spread in ticks at the bid's tick size
+    pub spread_ticks: u64,
+    /// Spread relative to the mid, in basis points
+    pub spread_bps: f64,
+}
+
+/// Bounded series of spread samples, oldest first
+pub struct SpreadHistory {
+    samples: VecDeque<SpreadSample>,
+    capacity: usize,
+}
+
+impl Default for SpreadHistory {
+    fn default() -> Self {
+        Self::new(DEFAULT_SPREAD_HISTORY_CAPACITY)
+    }
+}
+
+impl SpreadHistory {
+    pub fn new(capacity: usize) -> Self {
+        SpreadHistory { samples: VecDeque::new(), capacity }
+    }
+
+    /// Changes the bound, dropping the oldest samples that no longer fit.
+    pub fn set_capacity(&mut self, capacity: usize) {
+        self.capacity = capacity;
+        while self.samples.len() > capacity {
+            self.samples.pop_front();
+        }
+    }
+
+    /// Samples a two-sided top of book; one-sided books have no spread.
+    pub fn record(&mut self, timestamp_ns: u64, bid: Option<Price>, ask: Option<Price>, tick_size: u64) {
+        let (Some(bid), Some(ask)) = (bid, ask) else {
+            return;
+        };
+        if self.capacity == 0 {
+            return;
+        }
+        if self.samples.len() == self.capacity {
+            self.samples.pop_front();
+        }
+        let spread = ask.units().saturating_sub(bid.units());
+        let mid = (bid.units() + ask.units()) as f64 / 2.0;
+        self.samples.push_back(SpreadSample {
+            timestamp_ns,
+            bid,
+            ask,
+            spread_ticks: spread / tick_size.max(1),
+            spread_bps: spread as f64 / mid * 10_000.0,
+        });
+    }
+
+    /// The last `n` samples, oldest first.
+    pub fn recent(&self, n: usize) -> Vec<SpreadSample> {
+        let skip = self.samples.len().saturating_sub(n);
+        self.samples.iter().skip(skip).copied().collect()
+    }
+
+    pub fn len(&self) -> usize {
+        self.samples.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.samples.is_empty()
+    }
+}
+
+#[derive(Clone)]
+pub struct BboPublisher {
+    /// 0 means publish on every change
+    interval_ns: u64,
+    clock: Arc<dyn Clock>,
+    bus: Arc<EventBus>,
+    last_published: Option<Top>,
+    pending: Option<Top>,
+    window_start_ns: u64,
+    /// Records the spread of every published BBO
+    spread_history: Option<Arc<Mutex<SpreadHistory>>>,
+    /// Tick at the best bid, for the spread in ticks
+    tick_size: u64,
+    /// Tags every published BBO
+    symbol: Option<String>,
+}
+
+impl BboPublisher {
+    pub fn new(interval_ns: u64, clock: Arc<dyn Clock>, bus: Arc<EventBus>) -> Self {
+        BboPublisher {
+            interval_ns,
+            clock,
+            bus,
+            last_published: None,
+            pending: None,
+            window_start_ns: 0,
+            spread_history: None,
+            tick_size: 1,
+            symbol: None,
+        }
+    }
+
+    /// Also samples the spread into `history` whenever a BBO goes out.
+    pub fn with_spread_history(mut self, history: Arc<Mutex<SpreadHistory>>) -> Self {
+        self.spread_history = Some(history);
+        self
+    }
+
+    /// Tags every BBO with the symbol of the book it follows.
+    pub fn with_symbol(mut self, symbol: &str) -> Self {
+        self.symbol = Some(symbol.to_string());
+        self
+    }
+
+    /// Stops sampling the spread, for a publisher whose book is not the
+    /// one the spread history describes.
+    pub fn without_spread_history(mut self) -> Self {
+        self.spread_history = None;
+        self
+    }
+
+    /// Call after every book mutation.
+    pub fn on_book_change(&mut self, book: &OrderBook) {
+        if let (Some(schedule), Some(bid)) = (book.tick_schedule(), book.best_bid()) {
+            self.tick_size = schedule.tick_for(bid);
+        }
+        self.update(book.walk_bids().next(), book.walk_asks().next());
+    }
+
+    /// `bid` and `ask` are `(price, displayed_quantity)`.
+    pub fn update(&mut self, bid: Option<(Price, u64)>, ask: Option<(Price, u64)>) {
+        let top = (bid, ask);
+        if self.pending.is_none() {
+            if self.last_published == Some(top) {
+                return;
+            }
+            // First change of a new window
+            self.window_start_ns = self.clock.now_ns();
+        }
+        self.pending = Some(top);
+        self.poll();
+    }
+
+    /// Publishes the coalesced BBO once its interval has elapsed. Call this
+    /// when idle too, so a quiet book still flushes its last change.
+    pub fn poll(&mut self) {
+        let Some((bid, ask)) = self.pending else {
+            return;
+        };
+        let now = self.clock.now_ns();
+        if now.saturating_sub(self.window_start_ns) < self.interval_ns {
+            return;
+        }
+        self.pending = None;
+        if self.last_published == Some((bid, ask)) {
+            // Changed and changed back within the window
+            return;
+        }
+        self.last_published = Some((bid, ask));
+        if let Some(history) = &self.spread_history {
+            history.lock().unwrap().record(now, bid.map(|(price, _)| price), ask.map(|(price, _)| price), self.tick_size);
+        }
+        self.bus.publish(BookEvent::Bbo {
+            bid: bid.map(|(price, _)| price),
+            ask: ask.map(|(price, _)| price),
+            bid_quantity: bid.map_or(0, |(_, quantity)| quantity),
+            ask_quantity: ask.map_or(0, |(_, quantity)| quantity),
+            timestamp_ns: now,
+            symbol: self.symbol.clone(),
+        });
+    }
+}
@@ -1,5 +1,24 @@
 // Real-world benchmark to measure actual order processing speed
-use std::time::Instant;
+use std::io::{BufRead, BufReader, Write};
+use std::net::TcpStream;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+use hft_ringbuffer::blocking_ring::blocking_ring;
+use hft_ringbuffer::clock::thread_cpu_time;
+use hft_ringbuffer::config::CliArgs;
+use hft_ringbuffer::histogram::LatencySamples;
+use hft_ringbuffer::engine::{spawn_engine, EngineHooks, DEFAULT_ENGINE_BATCH};
+use hft_ringbuffer::funnel::{spawn_funnel, FunnelConfig};
+use hft_ringbuffer::gateway::{binary_frame, bind_gateway, spawn_gateway, GatewayRoutes, BINARY_MAGIC};
+use hft_ringbuffer::metrics::Metrics;
+use hft_ringbuffer::order_generator::{GeneratorParams, OrderGenerator};
+use hft_ringbuffer::order_results::OrderResults;
+use hft_ringbuffer::matching_engine::{Order as BookOrder, OrderBook, OrderSide, Packet, DEFAULT_BOOK_SYMBOL};
+use hft_ringbuffer::self_bench::run_self_bench;
+use hft_ringbuffer::trade_history::{TradeHistory, TradeSink};
+use hft_ringbuffer::wait_strategy::{Idler, WaitStrategy, DEFAULT_IDLE_SLEEP};
 use rtrb::RingBuffer;
 use serde::{Deserialize, Serialize};
 
@@ -15,25 +34,34 @@ fn main() {
     println!("🔬 HFT ENGINE BENCHMARK - Real Speed Test");
     println!("{}", "=".repeat(60));
     
+    // --buffer-size and --orders override these
     const BUFFER_SIZE: usize = 1024;
     const NUM_ORDERS: usize = 1_000_000;
+    let args = CliArgs::parse(std::env::args().skip(1)).unwrap_or_else(|e| {
+        eprintln!("❌ {}", e);
+        std::process::exit(2);
+    });
+    let buffer_size = args.buffer_size.unwrap_or(BUFFER_SIZE);
+    let num_orders = args.orders.unwrap_or(NUM_ORDERS);
     
     // Create ring buffer
-    let (mut producer, mut consumer) = RingBuffer::<Order>::new(BUFFER_SIZE);
+    let (mut producer, mut consumer) = RingBuffer::<Order>::new(buffer_size);
     
     println!("\n📊 Test Configuration:");
-    println!("   Orders to process: {}", NUM_ORDERS);
-    println!("   Buffer size: {}", BUFFER_SIZE);
+    println!("   Orders to process: {}", num_orders);
+    println!("   Buffer size: {}", buffer_size);
     println!("\n⏱️  Starting benchmark...\n");
     
+    // One sample per order, sized up front so the loop never allocates for it
+    let mut samples = LatencySamples::with_capacity(num_orders);
+    
     // Benchmark: Order processing
     let start = Instant::now();
     
-    let mut orders_sent = 0;
     let mut orders_received = 0;
     
     // Simulate real trading: push and pop orders
-    for i in 0..NUM_ORDERS {
+    for i in 0..num_orders {
         let order = Order {
             id: i as u64,
             side: if i % 2 == 0 { "Buy".to_string() } else { "Sell".to_string() },
@@ -41,23 +69,26 @@ fn main() {
             quantity: 1,
         };
         
+        // Time the push and the drain behind it, not building the order
+        let op_start = Instant::now();
+        
         // Try to push
         while producer.push(order.clone()).is_err() {
             // Buffer full, consume some
-            if let Ok(_) = consumer.pop() {
+            if consumer.pop().is_ok() {
                 orders_received += 1;
             }
         }
-        orders_sent += 1;
         
         // Consume remaining
-        while let Ok(_) = consumer.pop() {
+        while consumer.pop().is_ok() {
             orders_received += 1;
         }
+        samples.record(op_start.elapsed().as_nanos() as u64);
     }
     
     // Consume any remaining orders
-    while let Ok(_) = consumer.pop() {
+    while consumer.pop().is_ok() {
         orders_received += 1;
     }
     
@@ -65,8 +96,8 @@ fn main() {
     
     // Calculate statistics
     let total_nanos = duration.as_nanos();
-    let nanos_per_order = total_nanos / NUM_ORDERS as u128;
-    let orders_per_second = (NUM_ORDERS as f64 / duration.as_secs_f64()) as u64;
+    let nanos_per_order = total_nanos / num_orders as u128;
+    let orders_per_second = (num_orders as f64 / duration.as_secs_f64()) as u64;
     
     println!("✅ BENCHMARK RESULTS");
     println!("{}", "=".repeat(60));
@@ -75,6 +106,22 @@ fn main() {
     println!("   Average time per order: {} nanoseconds", nanos_per_order);
     println!("   Throughput: {} orders/second", orders_per_second);
     println!("   Throughput: {} million orders/second", orders_per_second / 1_000_000);
+    if let Some(summary) = samples.summary() {
+        println!("   Per-order latency: p50 {} ns, p99 {} ns, p99.9 {} ns, max {} ns",
+                 summary.p50_ns, summary.p99_ns, summary.p999_ns, summary.max_ns);
+    }
+    if let Some(path) = &args.csv {
+        let written = std::fs::File::create(path)
+            .map(std::io::BufWriter::new)
+            .and_then(|mut file| {
+                samples.write_csv(&mut file)?;
+                std::io::Write::flush(&mut file)
+            });
+        match written {
+            Ok(()) => println!("   Samples: {} written to {}", samples.samples_ns().len(), path),
+            Err(e) => eprintln!("   ❌ Could not write {}: {}", path, e),
+        }
+    }
     
     println!("\n🎯 PERFORMANCE RATING:");
     if nanos_per_order < 50 {
@@ -102,5 +149,303 @@ fn main() {
         println!("   Your engine is competitive but could be optimized further.");
     }
     
+    bench_trade_history();
+    bench_generated_flow();
+    bench_idle_wait();
+    bench_engine_batch();
+    bench_gateway_burst();
+    bench_binary_vs_json();
+    
     println!("\n{}", "=".repeat(60));
 }
+
+/// Match latency with trade history appended inside vs outside the book lock,
+/// on a workload where every other order trades.
+fn bench_trade_history() {
+    const PAIRS: u64 = 200_000;
+    
+    println!("\n📜 TRADE HISTORY: inline vs offloaded ({} trades)", PAIRS);
+    
+    for offloaded in [false, true] {
+        let book = Mutex::new(OrderBook::new());
+        let history = Arc::new(Mutex::new(TradeHistory::new(10_000)));
+        let (mut sink, writer) = if offloaded {
+            let (sink, writer) = TradeSink::offloaded(history.clone(), None).unwrap();
+            (sink, Some(writer))
+        } else {
+            (TradeSink::Inline(history.clone()), None)
+        };
+        
+        let start = Instant::now();
+        for i in 0..PAIRS {
+            for (id, side) in [(2 * i, OrderSide::Sell), (2 * i + 1, OrderSide::Buy)] {
+                let mut guard = book.lock().unwrap();
+                let executions = guard.add_limit_order(BookOrder::new(id, side, 100, 1));
+                if offloaded {
+                    drop(guard);
+                }
+                sink.record(&executions);
+            }
+        }
+        let duration = start.elapsed();
+        
+        drop(sink);
+        if let Some(writer) = writer {
+            writer.join().unwrap();
+        }
+        
+        println!("   {:<10} {} ns/order",
+            if offloaded { "offloaded" } else { "inline" },
+            duration.as_nanos() / (2 * PAIRS) as u128);
+    }
+}
+
+/// Matching cost on a seeded, realistic-looking flow, identical every run
+fn bench_generated_flow() {
+    const ORDERS: usize = 200_000;
+    
+    println!("\n🎲 GENERATED FLOW: {} orders", ORDERS);
+    
+    let report = run_self_bench(ORDERS);
+    
+    println!("   {} ns/order (p50 {} ns, p99 {} ns), {} trades",
+        report.total_ns / ORDERS as u64, report.p50_ns, report.p99_ns, report.trades);
+}
+
+/// Consumer CPU time on a trickle of orders: spinning on `pop` versus
+/// sleeping in `pop_blocking` between arrivals, then each engine
+/// `WaitStrategy` waiting out the gaps
+fn bench_idle_wait() {
+    const ITEMS: u64 = 100;
+    const GAP: Duration = Duration::from_millis(2);
+    const BUFFER_SIZE: usize = 1024;
+    
+    println!("\n💤 IDLE WAIT: {} items, one every {:?}", ITEMS, GAP);
+    
+    for blocking in [false, true] {
+        let (mut producer, mut consumer) = blocking_ring::<u64>(BUFFER_SIZE);
+        let usage = idle_cpu(ITEMS, GAP, |i| producer.push(i).unwrap(), move || {
+            let mut received = 0;
+            while received < ITEMS {
+                let item = if blocking {
+                    consumer.pop_blocking(Duration::from_millis(100))
+                } else {
+                    consumer.pop().or_else(|| {
+                        std::hint::spin_loop();
+                        None
+                    })
+                };
+                received += item.is_some() as u64;
+            }
+        });
+        print_idle_cpu(if blocking { "blocking" } else { "spinning" }, usage);
+    }
+    
+    // The engine loop: poll the ring, idle as the strategy says when it is empty
+    for (name, wait) in [
+        ("busy-spin", WaitStrategy::BusySpin),
+        ("yield", WaitStrategy::Yield),
+        ("sleep", WaitStrategy::Sleep(DEFAULT_IDLE_SLEEP)),
+        ("backoff", WaitStrategy::by_name("backoff", DEFAULT_IDLE_SLEEP).unwrap()),
+    ] {
+        let (mut producer, mut consumer) = RingBuffer::<u64>::new(BUFFER_SIZE);
+        let usage = idle_cpu(ITEMS, GAP, |i| producer.push(i).unwrap(), move || {
+            let mut idler = Idler::new(wait);
+            let mut received = 0;
+            while received < ITEMS {
+                match consumer.pop() {
+                    Ok(_) => {
+                        received += 1;
+                        idler.reset();
+                    }
+                    Err(_) => {
+                        idler.idle();
+                    }
+                }
+            }
+        });
+        print_idle_cpu(name, usage);
+    }
+}
+
+/// Runs `consume` on its own thread while `push` feeds it `items`, one
+/// every `gap`; returns the wall time and the consumer's CPU time
+fn idle_cpu(
+    items: u64,
+    gap: Duration,
+    mut push: impl FnMut(u64),
+    consume: impl FnOnce() + Send + 'static,
+) -> (Duration, Option<Duration>) {
+    let reader = thread::spawn(move || {
+        let start = Instant::now();
+        let cpu_start = thread_cpu_time();
+        consume();
+        let cpu = thread_cpu_time().zip(cpu_start).map(|(end, start)| end - start);
+        (start.elapsed(), cpu)
+    });
+    for i in 0..items {
+        thread::sleep(gap);
+        push(i);
+    }
+    reader.join().unwrap()
+}
+
+fn print_idle_cpu(label: &str, (wall, cpu): (Duration, Option<Duration>)) {
+    match cpu {
+        Some(cpu) => println!("   {:<10} {:.0?} CPU over {:.0?} ({:.0}% of a core)",
+            label, cpu, wall, 100.0 * cpu.as_secs_f64() / wall.as_secs_f64()),
+        None => println!("   {:<10} CPU time unavailable on this platform", label),
+    }
+}
+
+/// Engine throughput through the real ring and engine thread, one book lock
+/// per order versus one per batch, with a reader polling the book meanwhile
+fn bench_engine_batch() {
+    const ORDERS: usize = 200_000;
+    
+    println!("\n📦 ENGINE BATCH: {} orders through the ring", ORDERS);
+    
+    let flow: Vec<BookOrder> = OrderGenerator::new(42, GeneratorParams::default())
+        .new_orders()
+        .take(ORDERS)
+        .collect();
+    for max_batch in [1, DEFAULT_ENGINE_BATCH] {
+        let (mut producer, consumer) = RingBuffer::<Packet>::new(4096);
+        let book = Arc::new(Mutex::new(OrderBook::new()));
+        let shutdown = Arc::new(AtomicBool::new(false));
+        let hooks = EngineHooks { max_batch: Some(max_batch), drain_on_shutdown: true, ..Default::default() };
+        let engine = spawn_engine(consumer, book.clone(), shutdown.clone(), Arc::new(Metrics::new()), hooks).unwrap();
+        // Stands in for the HTTP API reading the book
+        let reader = {
+            let (book, shutdown) = (book.clone(), shutdown.clone());
+            thread::spawn(move || {
+                while !shutdown.load(Ordering::Relaxed) {
+                    let _ = book.lock().unwrap().best_bid();
+                }
+            })
+        };
+        
+        let start = Instant::now();
+        for order in &flow {
+            let mut packet = Packet::new(order.clone());
+            while let Err(rtrb::PushError::Full(rejected)) = producer.push(packet) {
+                packet = rejected;
+                std::hint::spin_loop();
+            }
+        }
+        shutdown.store(true, Ordering::Relaxed);
+        engine.join().unwrap();
+        let duration = start.elapsed();
+        reader.join().unwrap();
+        
+        println!("   batch {:<4} {:.0} orders/second",
+            max_batch, ORDERS as f64 / duration.as_secs_f64());
+    }
+}
+
+/// Gateway, funnel and engine on loopback, wired as in `main.rs`
+struct LoopbackGateway {
+    addr: String,
+    shutdown: Arc<AtomicBool>,
+    threads: Vec<JoinHandle<()>>,
+}
+
+impl LoopbackGateway {
+    fn start() -> Self {
+        let (listener, addr) = bind_gateway("127.0.0.1:0").unwrap();
+        let (producer, consumer) = RingBuffer::<Packet>::new(4096);
+        let metrics = Arc::new(Metrics::new());
+        let shutdown = Arc::new(AtomicBool::new(false));
+        let results = Arc::new(OrderResults::new());
+        let hooks = EngineHooks { results: Some(results.clone()), ..Default::default() };
+        let book = Arc::new(Mutex::new(OrderBook::new()));
+        let engine = spawn_engine(consumer, book, shutdown.clone(), metrics.clone(), hooks).unwrap();
+        let (funnel, forwarder) = spawn_funnel(producer, FunnelConfig::default(), metrics.funnel().clone(), shutdown.clone()).unwrap();
+        let routes = GatewayRoutes::new(DEFAULT_BOOK_SYMBOL, funnel).with_results(results);
+        let gateway = spawn_gateway(listener, routes, metrics.ingress().clone(), shutdown.clone()).unwrap();
+        LoopbackGateway { addr: addr.to_string(), shutdown, threads: vec![gateway, forwarder, engine] }
+    }
+
+    fn stop(self) {
+        self.shutdown.store(true, Ordering::Relaxed);
+        for thread in self.threads {
+            thread.join().unwrap();
+        }
+    }
+}
+
+/// Time for pipelining clients to get every ack back, acks going out in
+/// bursts per socket read
+fn bench_gateway_burst() {
+    const CLIENTS: u64 = 8;
+    const ORDERS_PER_CLIENT: u64 = 500;
+    
+    println!("\n📨 GATEWAY BURST: {} clients pipelining {} orders each", CLIENTS, ORDERS_PER_CLIENT);
+    
+    let gateway = LoopbackGateway::start();
+    let clients: Vec<_> = (0..CLIENTS)
+        .map(|client| {
+            let addr = gateway.addr.clone();
+            thread::spawn(move || {
+                let mut stream = TcpStream::connect(&addr).unwrap();
+                // Bids only, one price per order, so every order rests
+                let burst: String = (0..ORDERS_PER_CLIENT)
+                    .map(|i| {
+                        let id = client * ORDERS_PER_CLIENT + i + 1;
+                        format!("{{\"id\": {}, \"side\": \"Buy\", \"price\": {}, \"quantity\": 1}}\n", id, id)
+                    })
+                    .collect();
+                let started = Instant::now();
+                stream.write_all(burst.as_bytes()).unwrap();
+                let mut reader = BufReader::new(stream);
+                let mut ack = String::new();
+                for _ in 0..ORDERS_PER_CLIENT {
+                    ack.clear();
+                    reader.read_line(&mut ack).unwrap();
+                }
+                started.elapsed()
+            })
+        })
+        .collect();
+    let times: Vec<Duration> = clients.into_iter().map(|client| client.join().unwrap()).collect();
+    gateway.stop();
+    
+    println!("   slowest client {:.2?}, fastest {:.2?}",
+        times.iter().max().unwrap(), times.iter().min().unwrap());
+}
+
+/// Time to get every ack back for the same pipelined orders sent as packed
+/// binary frames and as JSON lines
+fn bench_binary_vs_json() {
+    const PIPELINED: u64 = 2_000;
+    
+    println!("\n📦 BINARY VS JSON: {} pipelined orders each", PIPELINED);
+    
+    let gateway = LoopbackGateway::start();
+    let pipeline = |handshake: &[u8], id_base: u64, encode: &dyn Fn(&BookOrder) -> Vec<u8>| {
+        let mut stream = TcpStream::connect(&gateway.addr).unwrap();
+        stream.write_all(handshake).unwrap();
+        // Bids only, one price per order, so every order rests
+        let payload: Vec<u8> = (1..=PIPELINED)
+            .flat_map(|i| encode(&BookOrder::new(id_base + i, OrderSide::Buy, i, 1)))
+            .collect();
+        let started = Instant::now();
+        stream.write_all(&payload).unwrap();
+        let mut reader = BufReader::new(stream);
+        let mut ack = String::new();
+        for _ in 0..PIPELINED {
+            ack.clear();
+            reader.read_line(&mut ack).unwrap();
+        }
+        started.elapsed()
+    };
+    let binary = pipeline(&[BINARY_MAGIC], 0, &binary_frame);
+    let json = pipeline(&[], PIPELINED, &|order| {
+        let mut line = serde_json::to_vec(order).unwrap();
+        line.push(b'\n');
+        line
+    });
+    gateway.stop();
+    
+    println!("   binary {:.2?}, json {:.2?}", binary, json);
+}
@@ -0,0 +1,195 @@
+// ============================================================================
+// BLOCKING RING - An rtrb ring whose ends can sleep instead of spin
+// ============================================================================
+// The plain ring leaves waiting to the caller, who usually busy-spins and
+// burns a core while the buffer is empty (or full). These wrappers keep the
+// lock-free fast path (`push` / `pop` never touch a lock) and only fall back
+// to a `Condvar` once the ring can't make progress. A side that is about to
+// sleep raises a flag first; the other side only takes the lock to notify
+// when it sees that flag, so an uncontended handoff costs a fence and one load.
+
+use rtrb::{Consumer, PopError, Producer, PushError, RingBuffer};
+use std::sync::atomic::{fence, AtomicBool, Ordering};
+use std::sync::{Arc, Condvar, Mutex};
+use std::time::{Duration, Instant};
+
+/// Wakeups shared by both ends of one ring
+#[derive(Default)]
+struct Signal {
+    lock: Mutex<()>,
+    not_empty: Condvar,
+    not_full: Condvar,
+    consumer_waiting: AtomicBool,
+    producer_waiting: AtomicBool,
+    /// One end has been dropped
+    closed: AtomicBool,
+}
+
+impl Signal {
+    /// Wakes `condvar`'s sleeper if `waiting` says there is one.
+    fn notify(&self, waiting: &AtomicBool, condvar: &Condvar) {
+        // Pairs with the fence in `wait`: either the sleeper sees our ring
+        // update on its re-check, or we see its flag here.
+        fence(Ordering::SeqCst);
+        if waiting.load(Ordering::Relaxed) {
+            let _guard = self.lock.lock().unwrap();
+            condvar.notify_one();
+        }
+    }
+
+    /// Sleeps on `condvar` until notified or `deadline`, unless `ready`
+    /// already holds once `waiting` is raised. Returns false on timeout.
+    fn wait(&self, waiting: &AtomicBool, condvar: &Condvar, deadline: Instant, mut ready: impl FnMut() -> bool) -> bool {
+        let mut guard = self.lock.lock().unwrap();
+        waiting.store(true, Ordering::Relaxed);
+        fence(Ordering::SeqCst);
+        let woke = loop {
+            if ready() {
+                break true;
+            }
+            let Some(left) = deadline.checked_duration_since(Instant::now()).filter(|left| !left.is_zero()) else {
+                break false;
+            };
+            guard = condvar.wait_timeout(guard, left).unwrap().0;
+        };
+        waiting.store(false, Ordering::Relaxed);
+        woke
+    }
+
+    /// Wakes whichever side is asleep so it notices its peer is gone.
+    fn close(&self) {
+        let _guard = self.lock.lock().unwrap();
+        self.closed.store(true, Ordering::Relaxed);
+        self.not_empty.notify_all();
+        self.not_full.notify_all();
+    }
+
+    fn is_closed(&self) -> bool {
+        self.closed.load(Ordering::Relaxed)
+    }
+}
+
+/// Creates a ring of `capacity` slots with blocking ends.
+pub fn blocking_ring<T>(capacity: usize) -> (BlockingProducer<T>, BlockingConsumer<T>) {
+    let (producer, consumer) = RingBuffer::new(capacity);
+    let signal = Arc::new(Signal::default());
+    (
+        BlockingProducer { inner: producer, signal: signal.clone() },
+        BlockingConsumer { inner: consumer, signal },
+    )
+}
+
+/// The writing end; wakes a consumer sleeping in `pop_blocking`
+pub struct BlockingProducer<T> {
+    inner: Producer<T>,
+    signal: Arc<Signal>,
+}
+
+impl<T> BlockingProducer<T> {
+    /// Pushes without waiting; hands the item back if the ring is full.
+    pub fn push(&mut self, item: T) -> Result<(), T> {
+        match self.inner.push(item) {
+            Ok(()) => {
+                self.signal.notify(&self.signal.consumer_waiting, &self.signal.not_empty);
+                Ok(())
+            }
+            Err(PushError::Full(item)) => Err(item),
+        }
+    }
+
+    /// Pushes, sleeping while the ring is full. Hands the item back if there
+    /// is still no room after `timeout` or the consumer has been dropped.
+    pub fn push_blocking(&mut self, mut item: T, timeout: Duration) -> Result<(), T> {
+        let deadline = Instant::now() + timeout;
+        loop {
+            item = match self.push(item) {
+                Ok(()) => return Ok(()),
+                Err(item) => item,
+            };
+            if self.is_abandoned() {
+                return Err(item);
+            }
+            let inner = &self.inner;
+            let signal = &self.signal;
+            if !signal.wait(&signal.producer_waiting, &signal.not_full, deadline, || {
+                !inner.is_full() || signal.is_closed()
+            }) {
+                return self.push(item);
+            }
+        }
+    }
+
+    /// Free slots right now
+    pub fn slots(&self) -> usize {
+        self.inner.slots()
+    }
+
+    /// True once the consumer has been dropped
+    pub fn is_abandoned(&self) -> bool {
+        self.signal.is_closed() || self.inner.is_abandoned()
+    }
+}
+
+impl<T> Drop for BlockingProducer<T> {
+    fn drop(&mut self) {
+        // A consumer asleep on an empty ring should give up now, not at its deadline
+        self.signal.close();
+    }
+}
+
+/// The reading end; wakes a producer sleeping in `push_blocking`
+pub struct BlockingConsumer<T> {
+    inner: Consumer<T>,
+    signal: Arc<Signal>,
+}
+
+impl<T> BlockingConsumer<T> {
+    /// Pops without waiting.
+    pub fn pop(&mut self) -> Option<T> {
+        match self.inner.pop() {
+            Ok(item) => {
+                self.signal.notify(&self.signal.producer_waiting, &self.signal.not_full);
+                Some(item)
+            }
+            Err(PopError::Empty) => None,
+        }
+    }
+
+    /// Pops, sleeping while the ring is empty. None if nothing arrived
+    /// within `timeout`, or the producer is gone and the ring is drained.
+    pub fn pop_blocking(&mut self, timeout: Duration) -> Option<T> {
+        let deadline = Instant::now() + timeout;
+        loop {
+            if let Some(item) = self.pop() {
+                return Some(item);
+            }
+            if self.is_abandoned() {
+                // Its last push may have landed after our pop
+                return self.pop();
+            }
+            let inner = &self.inner;
+            let signal = &self.signal;
+            if !signal.wait(&signal.consumer_waiting, &signal.not_empty, deadline, || {
+                !inner.is_empty() || signal.is_closed()
+            }) {
+                return self.pop();
+            }
+        }
+    }
+
+    /// Items waiting right now
+    pub fn slots(&self) -> usize {
+        self.inner.slots()
+    }
+
+    /// True once the producer has been dropped
+    pub fn is_abandoned(&self) -> bool {
+        self.signal.is_closed() || self.inner.is_abandoned()
+    }
+}
+
+impl<T> Drop for BlockingConsumer<T> {
+    fn drop(&mut self) {
+        self.signal.close();
+    }
+}
@@ -0,0 +1,56 @@
+// ============================================================================
+// BOOK CHECKSUM - CRC32 of the top of book, for clients to check their copy
+// ============================================================================
+// A streaming client rebuilds the book from diffs; comparing its own
+// checksum with the one served next to `/api/depth` tells it whether it has
+// drifted. The checksum is over a canonical string that a client rebuilds
+// from the same levels it holds:
+//
+//   * asks first, best (lowest) price first, then bids, best (highest) first
+//   * each level as `<price>:<qty>`, both base-10 integers with no sign,
+//     padding or separators; `price` is in engine units (the integer the API
+//     serves) and `qty` is the level's visible quantity
+//   * levels joined by `|`, with nothing before, after or between the sides
+//
+// So asks 101x5, 102x2 and bids 99x3 give `101:5|102:2|99:3`; an empty book
+// gives the empty string. The CRC is CRC-32/ISO-HDLC (zlib's `crc32`,
+// reflected polynomial 0xEDB88320, initial value and final XOR 0xFFFFFFFF)
+// over the string's ASCII bytes.
+
+use crate::matching_engine::DepthLevel;
+
+const CRC32_TABLE: [u32; 256] = crc32_table();
+
+const fn crc32_table() -> [u32; 256] {
+    let mut table = [0u32; 256];
+    let mut i = 0;
+    while i < 256 {
+        let mut crc = i as u32;
+        let mut bit = 0;
+        while bit < 8 {
+            crc = if crc & 1 == 1 { (crc >> 1) ^ 0xEDB8_8320 } else { crc >> 1 };
+            bit += 1;
+        }
+        table[i] = crc;
+        i += 1;
+    }
+    table
+}
+
+/// CRC-32 as zlib computes it
+pub fn crc32(bytes: &[u8]) -> u32 {
+    !bytes.iter().fold(!0u32, |crc, &byte| CRC32_TABLE[((crc ^ byte as u32) & 0xFF) as usize] ^ (crc >> 8))
+}
+
+/// The canonical `price:qty|...` string, asks then bids, best first.
+pub fn canonical_levels(asks: &[DepthLevel], bids: &[DepthLevel]) -> String {
+    asks.iter()
+        .chain(bids)
+        .map(|level| format!("{}:{}", level.price.units(), level.qty))
+        .collect::<Vec<_>>()
+        .join("|")
+}
+
+pub fn levels_checksum(asks: &[DepthLevel], bids: &[DepthLevel]) -> u32 {
+    crc32(canonical_levels(asks, bids).as_bytes())
+}
@@ -0,0 +1,102 @@
+// ============================================================================
+// BOOK DIFF - Level-by-level changes between two views of the book
+// ============================================================================
+// A client that reconnects with a stale snapshot only needs what changed.
+// Both sides are walked from the touch outward in lockstep, so a diff costs
+// O(levels) rather than a lookup per level.
+
+use crate::matching_engine::OrderSide;
+use crate::price_units::Price;
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+
+/// Aggregated levels as `(price, total_quantity)`, best price first
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OrderBookSnapshot {
+    /// Book sequence the snapshot was taken at
+    pub sequence: u64,
+    pub bids: Vec<(Price, u64)>,
+    pub asks: Vec<(Price, u64)>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum LevelChange {
+    Added { side: OrderSide, price: Price, quantity: u64 },
+    Removed { side: OrderSide, price: Price },
+    Resized { side: OrderSide, price: Price, quantity: u64 },
+}
+
+impl OrderBookSnapshot {
+    /// Changes that turn `self` into `newer`.
+    pub fn diff(&self, newer: &OrderBookSnapshot) -> Vec<LevelChange> {
+        let mut changes = Vec::new();
+        diff_side(OrderSide::Buy, &self.bids, &newer.bids, &mut changes);
+        diff_side(OrderSide::Sell, &self.asks, &newer.asks, &mut changes);
+        changes
+    }
+
+    /// Applies changes produced by `diff`.
+    pub fn apply(&mut self, changes: &[LevelChange]) {
+        for change in changes {
+            match *change {
+                LevelChange::Added { side, price, quantity } | LevelChange::Resized { side, price, quantity } => {
+                    let levels = self.side_mut(side);
+                    match levels.binary_search_by(|&(p, _)| touch_order(side, p, price)) {
+                        Ok(i) => levels[i].1 = quantity,
+                        Err(i) => levels.insert(i, (price, quantity)),
+                    }
+                }
+                LevelChange::Removed { side, price } => {
+                    let levels = self.side_mut(side);
+                    if let Ok(i) = levels.binary_search_by(|&(p, _)| touch_order(side, p, price)) {
+                        levels.remove(i);
+                    }
+                }
+            }
+        }
+    }
+
+    fn side_mut(&mut self, side: OrderSide) -> &mut Vec<(Price, u64)> {
+        match side {
+            OrderSide::Buy => &mut self.bids,
+            OrderSide::Sell => &mut self.asks,
+        }
+    }
+}
+
+/// How `a` sorts against `b` walking `side` from the touch outward
+fn touch_order(side: OrderSide, a: Price, b: Price) -> Ordering {
+    match side {
+        OrderSide::Buy => b.cmp(&a),
+        OrderSide::Sell => a.cmp(&b),
+    }
+}
+
+fn diff_side(side: OrderSide, old: &[(Price, u64)], new: &[(Price, u64)], changes: &mut Vec<LevelChange>) {
+    let (mut i, mut j) = (0, 0);
+    while i < old.len() || j < new.len() {
+        let order = match (old.get(i), new.get(j)) {
+            (Some(&(old_price, _)), Some(&(new_price, _))) => touch_order(side, old_price, new_price),
+            (Some(_), None) => Ordering::Less,
+            _ => Ordering::Greater,
+        };
+        match order {
+            Ordering::Less => {
+                changes.push(LevelChange::Removed { side, price: old[i].0 });
+                i += 1;
+            }
+            Ordering::Greater => {
+                changes.push(LevelChange::Added { side, price: new[j].0, quantity: new[j].1 });
+                j += 1;
+            }
+            Ordering::Equal => {
+                if old[i].1 != new[j].1 {
+                    changes.push(LevelChange::Resized { side, price: new[j].0, quantity: new[j].1 });
+                }
+                i += 1;
+                j += 1;
+            }
+        }
+    }
+}
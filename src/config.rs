@@ -0,0 +1,684 @@
+// ============================================================================
+// CONFIG - Startup settings from a JSON file and the environment
+// ============================================================================
+// Every server setting, from the ring size and listen addresses to market
+// rules, feeds and persistence. A `CONFIG_FILE` supplies the baseline, each
+// setting's environment variable (its field name in upper case) overrides
+// it, command line flags override both, and anything set in none keeps its
+// default. Settings with a syntax of their own (tick schedules, policies,
+// wait strategies) are kept as written and checked on load.
+
+use crate::auth::ApiKeys;
+use crate::bbo::{DEFAULT_BBO_INTERVAL_NS, DEFAULT_SPREAD_HISTORY_CAPACITY};
+use crate::clock::MonotonicClock;
+use crate::decision_bridge::DecisionBridgeConfig;
+use crate::engine::DEFAULT_ENGINE_BATCH;
+use crate::events::{FillNotificationMode, DEFAULT_EVENT_RETENTION};
+use crate::fees::FeeSchedule;
+use crate::funnel::{FunnelConfig, OverflowPolicy, DEFAULT_MAX_IN_FLIGHT};
+use crate::gateway::DEFAULT_GATEWAY_ADDR;
+use crate::http_server::DEFAULT_HTTP_ADDR;
+use crate::iceberg_detection::IcebergDetectorConfig;
+use crate::match_policy::{policy_by_name, MatchPolicy};
+use crate::matching_engine::{OrderBook, DEFAULT_BOOK_SYMBOL, DEFAULT_RECENT_TRADES, DEFAULT_TRAILING_PRICES};
+use crate::order_ids::DEFAULT_DUPLICATE_WINDOW;
+use crate::price_band::PriceBand;
+use crate::replica::{StaleAction, StalenessGuard};
+use crate::self_bench::DEFAULT_SELF_BENCH_ORDERS;
+use crate::settlement::SettlementMethod;
+use crate::shards::ShardConfig;
+use crate::tick_size::TickSchedule;
+use crate::wait_strategy::{WaitStrategy, DEFAULT_IDLE_SLEEP};
+use crate::wal::DEFAULT_CHECKPOINT_EVERY;
+use crate::warm_start::MismatchAction;
+use crate::wash_trade::WashTradeConfig;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::Duration;
+
+pub const DEFAULT_RING_CAPACITY: usize = 4096;
+
+/// Window self-crosses are counted over unless `wash_trade_window_ns` says otherwise
+pub const DEFAULT_WASH_TRADE_WINDOW_NS: u64 = 1_000_000_000;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    /// Slots in the ring buffer between the funnel and the engine
+    pub ring_capacity: usize,
+    pub gateway_addr: String,
+    pub http_addr: String,
+    /// Basis points of notional; negative is a rebate
+    pub maker_fee_bps: i64,
+    pub taker_fee_bps: i64,
+    /// One tick for every price; `None` accepts any price
+    pub tick_size: Option<u64>,
+    /// Keys accepted on mutating HTTP requests; empty leaves them open
+    pub api_keys: Vec<String>,
+
+    /// e.g. `0:1,1000:5`; takes precedence over `tick_size`
+    pub tick_schedule: Option<String>,
+    /// Refuses orders priced more than this percentage from the last trade
+    pub price_band_pct: Option<f64>,
+    /// Lets HTTP clients send `"price": 100.5` for 10050 with 2
+    pub price_decimals: u32,
+    /// Delays when new quotes become matchable (0 = off)
+    pub speed_bump_ns: u64,
+    /// `fifo` or `pro-rata`
+    pub match_policy: String,
+    /// `last`, `mid` or `vwap:<minutes>`; prices the session at close
+    pub settlement_method: String,
+    /// Tracks distinct accounts and first-seen time per level
+    pub level_metadata: bool,
+    /// Trade prices kept for /api/pricing-inputs
+    pub trailing_prices: usize,
+    /// Executions kept for /api/trades
+    pub recent_trades: usize,
+    /// Accepted order ids remembered per book; a resent id is refused as
+    /// a duplicate. 0 turns the check off
+    pub duplicate_window: usize,
+    /// Flags accounts crossing themselves this often per `wash_trade_window_ns`
+    pub wash_trade_max_crosses: Option<usize>,
+    pub wash_trade_window_ns: u64,
+    /// Also refuses a flagged account's orders for this long
+    pub wash_trade_throttle_ns: u64,
+    /// Debug builds only: sweep the book invariants every N operations
+    pub book_check_every: u64,
+
+    /// Name of the default book in multi-market views like /api/orderbook/all
+    pub book_symbol: String,
+    /// Further books, matched by a sharded engine
+    pub symbols: Vec<String>,
+    /// Matches on this many engine threads, each symbol hashed to one
+    pub engine_shards: Option<usize>,
+    /// Ring size for the symbols listed; the rest get `ring_capacity`
+    pub symbol_ring_capacities: BTreeMap<String, usize>,
+    /// Most orders the engine matches per book lock
+    pub engine_batch: usize,
+    /// `busy-spin`, `yield`, `sleep` or `backoff`
+    pub engine_wait: String,
+    /// How long `sleep` and `backoff` sleep for
+    pub engine_idle_sleep_us: u64,
+    /// Match accepted orders before shutdown completes
+    pub drain_on_shutdown: bool,
+    /// Cap on orders queued ahead of the ring
+    pub funnel_max_in_flight: usize,
+    /// `reject` answers "backpressure" when full, `block` makes producers wait
+    pub funnel_overflow: String,
+
+    /// 0 publishes a BBO on every book change
+    pub bbo_interval_ns: u64,
+    /// Spread samples kept for /api/spread-history
+    pub spread_history: usize,
+    /// Bus messages kept for consumers resyncing after a gap
+    pub event_retention: usize,
+    /// One fill notification per order instead of per execution
+    pub coalesce_fills: bool,
+    /// Write the trade history on the engine thread instead of a helper
+    pub trade_history_inline: bool,
+    /// Publishes and logs one in N rejections per reason
+    pub rejection_sample: u64,
+    /// Logs levels that refill this many times from the public feed
+    pub iceberg_detection: Option<u32>,
+    /// Refuses gateway clients past this many open at once
+    pub max_connections: Option<usize>,
+    /// Orders timed through the matcher by `--self-bench`
+    pub self_bench_orders: usize,
+
+    /// Trades BUY/SELL AI decisions
+    pub ai_execution: bool,
+    /// Lots per AI order
+    pub ai_order_size: u64,
+    /// Price units past the mid
+    pub ai_price_offset: u64,
+    /// Account AI orders are booked to
+    pub ai_account: Option<u64>,
+
+    /// Writes a CSV row per applied order and execution
+    pub tick_dump: Option<String>,
+    /// Records accepted orders for `replay_file`
+    pub record_orders: Option<String>,
+    /// Loads the book from this snapshot, replaying `warm_start_journal`
+    pub warm_start_snapshot: Option<String>,
+    pub warm_start_journal: Option<String>,
+    /// What the warm-started book must hash to; the snapshot's own if `None`
+    pub warm_start_checksum: Option<u64>,
+    /// `fail` or `warn` when the warm-started book hashes differently
+    pub warm_start_on_mismatch: String,
+    /// Journals every accepted order before matching and recovers from it
+    pub wal_path: Option<String>,
+    /// Snapshot recovery starts from; `<wal_path>.snapshot` if `None`
+    pub wal_snapshot: Option<String>,
+    /// Rewrites the snapshot every N journal entries (0 = never)
+    pub wal_snapshot_every: u64,
+    /// Serves /api/replica/*
+    pub read_replica: bool,
+    /// Flags replica reads this many sequences behind
+    pub replica_max_lag: Option<u64>,
+    /// `warn` flags stale replica reads, `reject` refuses them
+    pub replica_stale_action: String,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            ring_capacity: DEFAULT_RING_CAPACITY,
+            gateway_addr: DEFAULT_GATEWAY_ADDR.to_string(),
+            http_addr: DEFAULT_HTTP_ADDR.to_string(),
+            maker_fee_bps: 0,
+            taker_fee_bps: 0,
+            tick_size: None,
+            api_keys: Vec::new(),
+            tick_schedule: None,
+            price_band_pct: None,
+            price_decimals: 0,
+            speed_bump_ns: 0,
+            match_policy: "fifo".to_string(),
+            settlement_method: "last".to_string(),
+            level_metadata: false,
+            trailing_prices: DEFAULT_TRAILING_PRICES,
+            recent_trades: DEFAULT_RECENT_TRADES,
+            duplicate_window: DEFAULT_DUPLICATE_WINDOW,
+            wash_trade_max_crosses: None,
+            wash_trade_window_ns: DEFAULT_WASH_TRADE_WINDOW_NS,
+            wash_trade_throttle_ns: 0,
+            book_check_every: 0,
+            book_symbol: DEFAULT_BOOK_SYMBOL.to_string(),
+            symbols: Vec::new(),
+            engine_shards: None,
+            symbol_ring_capacities: BTreeMap::new(),
+            engine_batch: DEFAULT_ENGINE_BATCH,
+            engine_wait: "busy-spin".to_string(),
+            engine_idle_sleep_us: DEFAULT_IDLE_SLEEP.as_micros() as u64,
+            drain_on_shutdown: true,
+            funnel_max_in_flight: DEFAULT_MAX_IN_FLIGHT,
+            funnel_overflow: "reject".to_string(),
+            bbo_interval_ns: DEFAULT_BBO_INTERVAL_NS,
+            spread_history: DEFAULT_SPREAD_HISTORY_CAPACITY,
+            event_retention: DEFAULT_EVENT_RETENTION,
+            coalesce_fills: false,
+            trade_history_inline: false,
+            rejection_sample: 1,
+            iceberg_detection: None,
+            max_connections: None,
+            self_bench_orders: DEFAULT_SELF_BENCH_ORDERS,
+            ai_execution: false,
+            ai_order_size: DecisionBridgeConfig::default().quantity,
+            ai_price_offset: 0,
+            ai_account: None,
+            tick_dump: None,
+            record_orders: None,
+            warm_start_snapshot: None,
+            warm_start_journal: None,
+            warm_start_checksum: None,
+            warm_start_on_mismatch: "fail".to_string(),
+            wal_path: None,
+            wal_snapshot: None,
+            wal_snapshot_every: DEFAULT_CHECKPOINT_EVERY,
+            read_replica: false,
+            replica_max_lag: None,
+            replica_stale_action: "warn".to_string(),
+        }
+    }
+}
+
+impl Config {
+    /// Settings present in `json` replace the defaults.
+    pub fn from_json(json: &str) -> Result<Config, String> {
+        let config: Config = serde_json::from_str(json).map_err(|e| format!("invalid config: {}", e))?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// `path`'s settings (defaults if `None`), then the process environment.
+    pub fn load(path: Option<&str>) -> Result<Config, String> {
+        let mut config = match path {
+            Some(path) => {
+                let json = fs::read_to_string(path).map_err(|e| format!("could not read {}: {}", path, e))?;
+                Config::from_json(&json)?
+            }
+            None => Config::default(),
+        };
+        config.apply_env(|name| std::env::var(name).ok())?;
+        Ok(config)
+    }
+
+    /// Overrides each setting with its variable where `lookup` has it.
+    /// Lists (`API_KEYS`, `SYMBOLS`) are comma-separated, switches are on
+    /// for `1` (`DRAIN_ON_SHUTDOWN` is off for `0`), `SYMBOL_RING_CAPACITIES`
+    /// reads `BTC:8192,ETH:1024` and `WARM_START_CHECKSUM` may be `0x` hex.
+    pub fn apply_env(&mut self, lookup: impl Fn(&str) -> Option<String>) -> Result<(), String> {
+        let list = |value: String| -> Vec<String> {
+            value.split(',').map(str::trim).filter(|item| !item.is_empty()).map(str::to_string).collect()
+        };
+        if let Some(value) = lookup("RING_CAPACITY") {
+            self.ring_capacity = parse("RING_CAPACITY", &value)?;
+        }
+        if let Some(value) = lookup("GATEWAY_ADDR") {
+            self.gateway_addr = value;
+        }
+        if let Some(value) = lookup("HTTP_ADDR") {
+            self.http_addr = value;
+        }
+        if let Some(value) = lookup("MAKER_FEE_BPS") {
+            self.maker_fee_bps = parse("MAKER_FEE_BPS", &value)?;
+        }
+        if let Some(value) = lookup("TAKER_FEE_BPS") {
+            self.taker_fee_bps = parse("TAKER_FEE_BPS", &value)?;
+        }
+        if let Some(value) = lookup("TICK_SIZE") {
+            self.tick_size = Some(parse("TICK_SIZE", &value)?);
+        }
+        if let Some(value) = lookup("API_KEYS") {
+            self.api_keys = list(value);
+        }
+
+        if let Some(value) = lookup("TICK_SCHEDULE") {
+            self.tick_schedule = Some(value);
+        }
+        if let Some(value) = lookup("PRICE_BAND_PCT") {
+            self.price_band_pct = Some(parse("PRICE_BAND_PCT", &value)?);
+        }
+        if let Some(value) = lookup("PRICE_DECIMALS") {
+            self.price_decimals = parse("PRICE_DECIMALS", &value)?;
+        }
+        if let Some(value) = lookup("SPEED_BUMP_NS") {
+            self.speed_bump_ns = parse("SPEED_BUMP_NS", &value)?;
+        }
+        if let Some(value) = lookup("MATCH_POLICY") {
+            self.match_policy = value;
+        }
+        if let Some(value) = lookup("SETTLEMENT_METHOD") {
+            self.settlement_method = value;
+        }
+        if let Some(value) = lookup("LEVEL_METADATA") {
+            self.level_metadata = value == "1";
+        }
+        if let Some(value) = lookup("TRAILING_PRICES") {
+            self.trailing_prices = parse("TRAILING_PRICES", &value)?;
+        }
+        if let Some(value) = lookup("RECENT_TRADES") {
+            self.recent_trades = parse("RECENT_TRADES", &value)?;
+        }
+        if let Some(value) = lookup("DUPLICATE_WINDOW") {
+            self.duplicate_window = parse("DUPLICATE_WINDOW", &value)?;
+        }
+        if let Some(value) = lookup("WASH_TRADE_MAX_CROSSES") {
+            self.wash_trade_max_crosses = Some(parse("WASH_TRADE_MAX_CROSSES", &value)?);
+        }
+        if let Some(value) = lookup("WASH_TRADE_WINDOW_NS") {
+            self.wash_trade_window_ns = parse("WASH_TRADE_WINDOW_NS", &value)?;
+        }
+        if let Some(value) = lookup("WASH_TRADE_THROTTLE_NS") {
+            self.wash_trade_throttle_ns = parse("WASH_TRADE_THROTTLE_NS", &value)?;
+        }
+        if let Some(value) = lookup("BOOK_CHECK_EVERY") {
+            self.book_check_every = parse("BOOK_CHECK_EVERY", &value)?;
+        }
+
+        if let Some(value) = lookup("BOOK_SYMBOL") {
+            self.book_symbol = value;
+        }
+        if let Some(value) = lookup("SYMBOLS") {
+            self.symbols = list(value);
+        }
+        if let Some(value) = lookup("ENGINE_SHARDS") {
+            self.engine_shards = Some(parse("ENGINE_SHARDS", &value)?);
+        }
+        if let Some(value) = lookup("SYMBOL_RING_CAPACITIES") {
+            self.symbol_ring_capacities = parse::<ShardConfig>("SYMBOL_RING_CAPACITIES", &value)?.capacities;
+        }
+        if let Some(value) = lookup("ENGINE_BATCH") {
+            self.engine_batch = parse("ENGINE_BATCH", &value)?;
+        }
+        if let Some(value) = lookup("ENGINE_WAIT") {
+            self.engine_wait = value;
+        }
+        if let Some(value) = lookup("ENGINE_IDLE_SLEEP_US") {
+            self.engine_idle_sleep_us = parse("ENGINE_IDLE_SLEEP_US", &value)?;
+        }
+        if let Some(value) = lookup("DRAIN_ON_SHUTDOWN") {
+            self.drain_on_shutdown = value != "0";
+        }
+        if let Some(value) = lookup("FUNNEL_MAX_IN_FLIGHT") {
+            self.funnel_max_in_flight = parse("FUNNEL_MAX_IN_FLIGHT", &value)?;
+        }
+        if let Some(value) = lookup("FUNNEL_OVERFLOW") {
+            self.funnel_overflow = value;
+        }
+
+        if let Some(value) = lookup("BBO_INTERVAL_NS") {
+            self.bbo_interval_ns = parse("BBO_INTERVAL_NS", &value)?;
+        }
+        if let Some(value) = lookup("SPREAD_HISTORY") {
+            self.spread_history = parse("SPREAD_HISTORY", &value)?;
+        }
+        if let Some(value) = lookup("EVENT_RETENTION") {
+            self.event_retention = parse("EVENT_RETENTION", &value)?;
+        }
+        if let Some(value) = lookup("COALESCE_FILLS") {
+            self.coalesce_fills = value == "1";
+        }
+        if let Some(value) = lookup("TRADE_HISTORY_INLINE") {
+            self.trade_history_inline = value == "1";
+        }
+        if let Some(value) = lookup("REJECTION_SAMPLE") {
+            self.rejection_sample = parse("REJECTION_SAMPLE", &value)?;
+        }
+        if let Some(value) = lookup("ICEBERG_DETECTION") {
+            self.iceberg_detection = Some(parse("ICEBERG_DETECTION", &value)?);
+        }
+        if let Some(value) = lookup("MAX_CONNECTIONS") {
+            self.max_connections = Some(parse("MAX_CONNECTIONS", &value)?);
+        }
+        if let Some(value) = lookup("SELF_BENCH_ORDERS") {
+            self.self_bench_orders = parse("SELF_BENCH_ORDERS", &value)?;
+        }
+
+        if let Some(value) = lookup("AI_EXECUTION") {
+            self.ai_execution = value == "1";
+        }
+        if let Some(value) = lookup("AI_ORDER_SIZE") {
+            self.ai_order_size = parse("AI_ORDER_SIZE", &value)?;
+        }
+        if let Some(value) = lookup("AI_PRICE_OFFSET") {
+            self.ai_price_offset = parse("AI_PRICE_OFFSET", &value)?;
+        }
+        if let Some(value) = lookup("AI_ACCOUNT") {
+            self.ai_account = Some(parse("AI_ACCOUNT", &value)?);
+        }
+
+        if let Some(value) = lookup("TICK_DUMP") {
+            self.tick_dump = Some(value);
+        }
+        if let Some(value) = lookup("RECORD_ORDERS") {
+            self.record_orders = Some(value);
+        }
+        if let Some(value) = lookup("WARM_START_SNAPSHOT") {
+            self.warm_start_snapshot = Some(value);
+        }
+        if let Some(value) = lookup("WARM_START_JOURNAL") {
+            self.warm_start_journal = Some(value);
+        }
+        if let Some(value) = lookup("WARM_START_CHECKSUM") {
+            self.warm_start_checksum = Some(match value.strip_prefix("0x") {
+                Some(hex) => u64::from_str_radix(hex, 16).map_err(|e| format!("WARM_START_CHECKSUM={:?}: {}", value, e))?,
+                None => parse("WARM_START_CHECKSUM", &value)?,
+            });
+        }
+        if let Some(value) = lookup("WARM_START_ON_MISMATCH") {
+            self.warm_start_on_mismatch = value;
+        }
+        if let Some(value) = lookup("WAL_PATH") {
+            self.wal_path = Some(value);
+        }
+        if let Some(value) = lookup("WAL_SNAPSHOT") {
+            self.wal_snapshot = Some(value);
+        }
+        if let Some(value) = lookup("WAL_SNAPSHOT_EVERY") {
+            self.wal_snapshot_every = parse("WAL_SNAPSHOT_EVERY", &value)?;
+        }
+        if let Some(value) = lookup("READ_REPLICA") {
+            self.read_replica = value == "1";
+        }
+        if let Some(value) = lookup("REPLICA_MAX_LAG") {
+            self.replica_max_lag = Some(parse("REPLICA_MAX_LAG", &value)?);
+        }
+        if let Some(value) = lookup("REPLICA_STALE_ACTION") {
+            self.replica_stale_action = value;
+        }
+        self.validate()
+    }
+
+    /// Overrides with whichever of `args`' flags were given.
+    pub fn apply_args(&mut self, args: &CliArgs) -> Result<(), String> {
+        if let Some(buffer_size) = args.buffer_size {
+            self.ring_capacity = buffer_size;
+        }
+        if let Some(port) = args.http_port {
+            self.http_addr = with_port(&self.http_addr, port);
+        }
+        if let Some(port) = args.gateway_port {
+            self.gateway_addr = with_port(&self.gateway_addr, port);
+        }
+        if let Some(orders) = args.orders {
+            self.self_bench_orders = orders;
+        }
+        self.validate()
+    }
+
+    pub fn fee_schedule(&self) -> FeeSchedule {
+        FeeSchedule::new(self.maker_fee_bps, self.taker_fee_bps)
+    }
+
+    /// `tick_schedule` if set, else one `tick_size` for every price
+    pub fn tick_schedule(&self) -> Result<Option<TickSchedule>, String> {
+        match &self.tick_schedule {
+            Some(spec) => spec.parse().map(Some).map_err(|e| format!("tick_schedule {:?}: {}", spec, e)),
+            None => Ok(self.tick_size.map(TickSchedule::uniform)),
+        }
+    }
+
+    pub fn price_band(&self) -> Result<Option<PriceBand>, String> {
+        self.price_band_pct.map(PriceBand::new).transpose()
+    }
+
+    pub fn match_policy(&self) -> Result<Arc<dyn MatchPolicy>, String> {
+        policy_by_name(&self.match_policy).map_err(|e| format!("match_policy: {}", e))
+    }
+
+    pub fn settlement_method(&self) -> Result<SettlementMethod, String> {
+        self.settlement_method.parse().map_err(|e| format!("settlement_method: {}", e))
+    }
+
+    pub fn wash_trade_detection(&self) -> Option<WashTradeConfig> {
+        self.wash_trade_max_crosses.map(|max_self_crosses| WashTradeConfig {
+            window_ns: self.wash_trade_window_ns,
+            max_self_crosses,
+            throttle_ns: self.wash_trade_throttle_ns,
+        })
+    }
+
+    /// Puts every market rule on `book`: fees, ticks, band, matching,
+    /// settlement and the rest. Fees are only set when they differ from
+    /// the book's, so a default config adds no fee version.
+    ///
+    /// Panics on a setting `validate` refuses; every way of building a
+    /// `Config` here runs it.
+    pub fn configure_book(&self, book: &mut OrderBook) {
+        const CHECKED: &str = "checked when the config was loaded";
+        if book.fee_history().current().schedule != self.fee_schedule() {
+            book.set_fee_schedule(self.fee_schedule());
+        }
+        book.set_tick_schedule(self.tick_schedule().expect(CHECKED));
+        book.set_price_band(self.price_band().expect(CHECKED));
+        book.set_match_policy(self.match_policy().expect(CHECKED));
+        book.set_price_decimals(self.price_decimals);
+        book.set_trailing_prices_capacity(self.trailing_prices);
+        book.set_recent_trades_capacity(self.recent_trades);
+        book.set_duplicate_window(self.duplicate_window);
+        book.set_wash_trade_detection(self.wash_trade_detection(), Arc::new(MonotonicClock::new()));
+        book.set_speed_bump(self.speed_bump_ns, Arc::new(MonotonicClock::new()));
+        book.set_level_metadata(self.level_metadata, Arc::new(MonotonicClock::new()));
+        book.set_settlement_method(self.settlement_method().expect(CHECKED), Arc::new(MonotonicClock::new()));
+        book.set_invariant_check_interval(self.book_check_every);
+    }
+
+    /// Per-symbol rings for a sharded engine, `ring_capacity` for the rest
+    pub fn symbol_rings(&self) -> ShardConfig {
+        ShardConfig { default_capacity: self.ring_capacity, capacities: self.symbol_ring_capacities.clone() }
+    }
+
+    pub fn engine_wait(&self) -> Result<WaitStrategy, String> {
+        WaitStrategy::by_name(&self.engine_wait, Duration::from_micros(self.engine_idle_sleep_us))
+            .map_err(|e| format!("engine_wait: {}", e))
+    }
+
+    pub fn funnel_config(&self) -> FunnelConfig {
+        FunnelConfig {
+            max_in_flight: self.funnel_max_in_flight,
+            overflow: if self.funnel_overflow == "block" { OverflowPolicy::Block } else { OverflowPolicy::Reject },
+            drain_on_shutdown: self.drain_on_shutdown,
+        }
+    }
+
+    pub fn fill_notifications(&self) -> FillNotificationMode {
+        if self.coalesce_fills {
+            FillNotificationMode::Coalesced
+        } else {
+            FillNotificationMode::PerExecution
+        }
+    }
+
+    pub fn iceberg_detector(&self) -> Option<IcebergDetectorConfig> {
+        self.iceberg_detection.map(|min_refills| IcebergDetectorConfig { min_refills })
+    }
+
+    pub fn decision_bridge(&self) -> Option<DecisionBridgeConfig> {
+        self.ai_execution.then_some(DecisionBridgeConfig {
+            quantity: self.ai_order_size,
+            price_offset: self.ai_price_offset,
+            account_id: self.ai_account,
+        })
+    }
+
+    pub fn warm_start_action(&self) -> MismatchAction {
+        if self.warm_start_on_mismatch == "warn" {
+            MismatchAction::Warn
+        } else {
+            MismatchAction::Fail
+        }
+    }
+
+    /// `wal_snapshot`, or `<wal_path>.snapshot` next to the journal
+    pub fn wal_snapshot_path(&self) -> Option<String> {
+        self.wal_snapshot.clone().or_else(|| self.wal_path.as_ref().map(|path| format!("{}.snapshot", path)))
+    }
+
+    pub fn replica_guard(&self) -> Option<StalenessGuard> {
+        self.replica_max_lag.map(|max_lag_sequences| StalenessGuard {
+            max_lag_sequences,
+            max_lag_ns: 0,
+            action: if self.replica_stale_action == "reject" { StaleAction::Reject } else { StaleAction::Warn },
+        })
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        if self.ring_capacity == 0 {
+            return Err("ring_capacity must be at least 1".to_string());
+        }
+        if self.tick_size == Some(0) {
+            return Err("tick_size must be at least 1".to_string());
+        }
+        if self.api_keys.iter().any(|key| key.trim().is_empty()) {
+            return Err("api_keys must not contain blank keys".to_string());
+        }
+        self.tick_schedule()?;
+        self.price_band()?;
+        self.match_policy()?;
+        self.settlement_method()?;
+        self.engine_wait()?;
+        if let Some((symbol, capacity)) = self.symbol_ring_capacities.iter().find(|(_, capacity)| !capacity.is_power_of_two()) {
+            return Err(format!("symbol_ring_capacities: {} for {} is not a power of two", capacity, symbol));
+        }
+        if self.rejection_sample == 0 {
+            return Err("rejection_sample must be at least 1".to_string());
+        }
+        let choices = [
+            ("funnel_overflow", &self.funnel_overflow, ["reject", "block"]),
+            ("warm_start_on_mismatch", &self.warm_start_on_mismatch, ["fail", "warn"]),
+            ("replica_stale_action", &self.replica_stale_action, ["warn", "reject"]),
+        ];
+        for (name, value, allowed) in choices {
+            if !allowed.contains(&value.as_str()) {
+                return Err(format!("{} must be {} or {}, got {}", name, allowed[0], allowed[1], value));
+            }
+        }
+        if self.wal_path.is_some() && self.warm_start_snapshot.is_some() {
+            return Err("wal_path and warm_start_snapshot both choose the starting book; set only one".to_string());
+        }
+        // These follow the one book the unsharded engine matches; a sharded
+        // engine would leave them silently incomplete
+        if self.engine_shards.is_some() {
+            let single_book = [
+                ("wal_path", self.wal_path.is_some()),
+                ("read_replica", self.read_replica),
+                ("record_orders", self.record_orders.is_some()),
+                ("tick_dump", self.tick_dump.is_some()),
+            ];
+            if let Some((name, _)) = single_book.iter().find(|(_, set)| *set) {
+                return Err(format!("{} is not supported with engine_shards yet; set only one", name));
+            }
+        }
+        Ok(())
+    }
+
+    pub fn api_keys(&self) -> ApiKeys {
+        ApiKeys::new(self.api_keys.iter().cloned())
+    }
+}
+
+// ============================================================================
+// COMMAND LINE - `--flag value` pairs for the server and the benchmark
+// ============================================================================
+
+pub const CLI_USAGE: &str =
+    "flags: [--buffer-size N] [--orders N] [--http-port P] [--gateway-port P] [--self-bench] [--csv FILE]";
+
+/// Flags given on the command line; `None` leaves the setting to the file,
+/// the environment or the default
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CliArgs {
+    /// Ring buffer slots
+    pub buffer_size: Option<usize>,
+    /// Orders to push through a benchmark run
+    pub orders: Option<usize>,
+    pub http_port: Option<u16>,
+    pub gateway_port: Option<u16>,
+    pub self_bench: bool,
+    /// Where a benchmark writes its raw latency samples
+    pub csv: Option<String>,
+}
+
+impl CliArgs {
+    /// Parses `args` without the program name, e.g. `std::env::args().skip(1)`.
+    pub fn parse(args: impl IntoIterator<Item = String>) -> Result<CliArgs, String> {
+        let mut parsed = CliArgs::default();
+        let mut args = args.into_iter();
+        while let Some(flag) = args.next() {
+            let mut value = || args.next().ok_or_else(|| format!("{} needs a value", flag));
+            match flag.as_str() {
+                "--buffer-size" => parsed.buffer_size = Some(parse(&flag, &value()?)?),
+                "--orders" => parsed.orders = Some(parse(&flag, &value()?)?),
+                "--http-port" => parsed.http_port = Some(parse(&flag, &value()?)?),
+                "--gateway-port" => parsed.gateway_port = Some(parse(&flag, &value()?)?),
+                "--self-bench" => parsed.self_bench = true,
+                "--csv" => parsed.csv = Some(value()?),
+                other => return Err(format!("unknown argument: {} ({})", other, CLI_USAGE)),
+            }
+        }
+        if parsed.buffer_size == Some(0) {
+            return Err("--buffer-size must be at least 1".to_string());
+        }
+        if parsed.orders == Some(0) {
+            return Err("--orders must be at least 1".to_string());
+        }
+        Ok(parsed)
+    }
+}
+
+/// `addr` with its port swapped for `port`, keeping the host.
+fn with_port(addr: &str, port: u16) -> String {
+    let host = addr.rsplit_once(':').map_or(addr, |(host, _)| host);
+    format!("{}:{}", host, port)
+}
+
+fn parse<T: FromStr>(name: &str, value: &str) -> Result<T, String>
+where
+    T::Err: std::fmt::Display,
+{
+    value.parse().map_err(|e| format!("{}={:?}: {}", name, value, e))
+}
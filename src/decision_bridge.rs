@@ -0,0 +1,113 @@
+// ============================================================================
+// DECISION BRIDGE - Turns posted AI decisions into orders on the book
+// ============================================================================
+// Opt-in: with `AI_EXECUTION=1` a decision whose signal is BUY/LONG or
+// SELL/SHORT becomes a limit order priced off the current mid and submitted
+// through the gateway's funnel, so it queues and matches like any client
+// order. Other signals (NEUTRAL, HOLD, strategy names) place nothing.
+
+use crate::funnel::{FunnelSender, SubmitError};
+use crate::matching_engine::{Order, OrderSide, Packet};
+use crate::price_units::Price;
+use crate::signal_store::AiDecision;
+use serde::Serialize;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// Bridge orders take ids from here up, clear of client-chosen ids
+pub const DECISION_ORDER_ID_BASE: u64 = 1 << 62;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DecisionBridgeConfig {
+    /// Quantity of each order
+    pub quantity: u64,
+    /// Price units past the mid: buys bid `mid + offset`, sells offer
+    /// `mid - offset`, so a positive offset crosses the spread
+    pub price_offset: u64,
+    /// Account the orders are booked to, if any
+    pub account_id: Option<u64>,
+}
+
+impl Default for DecisionBridgeConfig {
+    fn default() -> Self {
+        DecisionBridgeConfig { quantity: 1, price_offset: 0, account_id: None }
+    }
+}
+
+/// The order a decision was turned into
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct BridgedOrder {
+    pub id: u64,
+    pub side: OrderSide,
+    pub price: Price,
+    pub quantity: u64,
+}
+
+pub struct DecisionBridge {
+    config: DecisionBridgeConfig,
+    funnel: FunnelSender,
+    next_id: AtomicU64,
+}
+
+impl DecisionBridge {
+    pub fn new(config: DecisionBridgeConfig, funnel: FunnelSender) -> Self {
+        DecisionBridge { config, funnel, next_id: AtomicU64::new(DECISION_ORDER_ID_BASE) }
+    }
+
+    /// Numbers orders on from the highest bridge id among `ids`, the ids a
+    /// previous run accepted, so a restart never reissues one of them
+    pub fn continuing_after(self, ids: &[u64]) -> Self {
+        if let Some(last) = ids.iter().copied().filter(|&id| id >= DECISION_ORDER_ID_BASE).max() {
+            self.next_id.store(last + 1, Ordering::Relaxed);
+        }
+        self
+    }
+
+    pub fn config(&self) -> &DecisionBridgeConfig {
+        &self.config
+    }
+
+    /// Side for a signal, `None` when it calls for no trade
+    pub fn side_for(signal: &str) -> Option<OrderSide> {
+        match signal.trim().to_ascii_uppercase().as_str() {
+            "BUY" | "LONG" => Some(OrderSide::Buy),
+            "SELL" | "SHORT" => Some(OrderSide::Sell),
+            _ => None,
+        }
+    }
+
+    /// Limit price for `side` around `mid`. Buys round the mid down and
+    /// sells round it up, then `price_offset` moves them toward the other
+    /// side. Never below 1.
+    pub fn price_for(&self, side: OrderSide, mid: f64) -> Price {
+        let units = match side {
+            OrderSide::Buy => (mid.floor() as u64).saturating_add(self.config.price_offset),
+            OrderSide::Sell => (mid.ceil() as u64).saturating_sub(self.config.price_offset),
+        };
+        Price(units.max(1))
+    }
+
+    /// Submits the order for `decision`, priced off `mid`. `Ok(None)` when
+    /// the signal calls for no trade; an error when there is no price to
+    /// work from or the funnel refused the order.
+    pub fn execute(&self, decision: &AiDecision, mid: Option<f64>) -> Result<Option<BridgedOrder>, String> {
+        let Some(side) = Self::side_for(&decision.signal) else {
+            return Ok(None);
+        };
+        let mid = mid.ok_or("no mid price to trade the decision against")?;
+        let bridged = BridgedOrder {
+            id: self.next_id.fetch_add(1, Ordering::Relaxed),
+            side,
+            price: self.price_for(side, mid),
+            quantity: self.config.quantity,
+        };
+        let mut order = Order::new(bridged.id, side, bridged.price, bridged.quantity);
+        if let Some(account_id) = self.config.account_id {
+            order = order.with_account(account_id);
+        }
+        match self.funnel.submit(Packet::new(order)) {
+            Ok(()) => Ok(Some(bridged)),
+            Err(SubmitError::Backpressure(_)) => Err("funnel full".to_string()),
+            Err(SubmitError::Closed(_)) => Err("shutting down".to_string()),
+        }
+    }
+}
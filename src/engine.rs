@@ -0,0 +1,485 @@
+// ============================================================================
+// ENGINE LOOP - Ring Buffer Consumer
+// ============================================================================
+
+use crate::bbo::BboPublisher;
+use crate::clock::{process_clock, Clock};
+use crate::events::FillNotifier;
+use crate::matching_engine::{BookAction, Order, OrderBook, Packet, TradeExecution};
+use crate::metrics::Metrics;
+use crate::order_results::{OrderOutcome, OrderResults};
+use crate::rejections::{EntryError, RejectReason, RejectionLog};
+use crate::replay::Recorder;
+use crate::replica::ReplicaFeed;
+use crate::tick_dump::TickDump;
+use crate::wait_strategy::{Idler, WaitStrategy};
+use crate::wal::WriteAheadLog;
+use crate::trade_history::TradeSink;
+use rtrb::Consumer;
+use log::{error, info, warn};
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::Instant;
+
+/// Name of the matching thread, as seen in panics, thread dumps and perf
+pub const ENGINE_THREAD_NAME: &str = "engine";
+
+/// Most packets the engine matches per book lock unless `EngineHooks::max_batch` says otherwise
+pub const DEFAULT_ENGINE_BATCH: usize = 64;
+
+/// Optional pieces the engine loop drives alongside matching.
+#[derive(Default)]
+pub struct EngineHooks {
+    /// Sees every book change and gets polled while idle
+    pub bbo: Option<BboPublisher>,
+    /// Receives every execution for the trade history and the `trades` log
+    pub trades: Option<TradeSink>,
+    /// Tells clients how their incoming orders filled
+    pub fills: Option<FillNotifier>,
+    /// Forwards every order the book took, and every cancel and command, to
+    /// a read replica
+    pub replica: Option<ReplicaFeed>,
+    /// Writes a CSV row for every applied order and execution
+    pub tick_dump: Option<TickDump>,
+    /// Records orders the book refused (closed market, tick size, wash-trade throttle)
+    pub rejections: Option<Arc<RejectionLog>>,
+    /// Answers gateway clients waiting on their order's outcome
+    pub results: Option<Arc<OrderResults>>,
+    /// Journals every order that passes entry checks before it is matched,
+    /// and every cancel or book-wide command before it is applied, and
+    /// writes the periodic snapshots recovery starts from
+    pub wal: Option<WriteAheadLog>,
+    /// Records every order that passes entry checks, with its receive time,
+    /// for `replay_file`
+    pub recorder: Option<Recorder>,
+    /// On shutdown, match whatever is still in the ring before exiting.
+    /// Stop the producers first (see `PhasedShutdown`) or the drain races them.
+    pub drain_on_shutdown: bool,
+    /// Most packets matched per book lock (`DEFAULT_ENGINE_BATCH` if None).
+    /// Readers such as the HTTP API wait up to one batch for the lock.
+    pub max_batch: Option<usize>,
+    /// What the engine does when it finds the ring empty
+    pub wait: WaitStrategy,
+}
+
+/// Starts `run_engine` on a dedicated thread named `engine`.
+pub fn spawn_engine(
+    consumer: Consumer<Packet>,
+    order_book: Arc<Mutex<OrderBook>>,
+    shutdown: Arc<AtomicBool>,
+    metrics: Arc<Metrics>,
+    hooks: EngineHooks,
+) -> std::io::Result<JoinHandle<()>> {
+    thread::Builder::new()
+        .name(ENGINE_THREAD_NAME.to_string())
+        .spawn(move || run_engine(consumer, order_book, shutdown, metrics, hooks))
+}
+
+/// Starts `run_engine` for one symbol's shard on a thread named
+/// `engine-<symbol>`.
+pub fn spawn_shard_engine(
+    symbol: &str,
+    consumer: Consumer<Packet>,
+    order_book: Arc<Mutex<OrderBook>>,
+    shutdown: Arc<AtomicBool>,
+    metrics: Arc<Metrics>,
+    hooks: EngineHooks,
+) -> std::io::Result<JoinHandle<()>> {
+    thread::Builder::new()
+        .name(format!("{}-{}", ENGINE_THREAD_NAME, symbol))
+        .spawn(move || run_engine(consumer, order_book, shutdown, metrics, hooks))
+}
+
+/// Books by symbol, for an engine that serves several
+pub type SymbolBooks = BTreeMap<String, Arc<Mutex<OrderBook>>>;
+
+/// Starts `run_symbol_engine` for shard `index` on a thread named
+/// `engine-shard-<index>`.
+pub fn spawn_symbol_engine(
+    index: usize,
+    rings: Vec<Consumer<Packet>>,
+    books: SymbolBooks,
+    default_symbol: &str,
+    shutdown: Arc<AtomicBool>,
+    metrics: Arc<Metrics>,
+    hooks: EngineHooks,
+) -> std::io::Result<JoinHandle<()>> {
+    let default_symbol = default_symbol.to_string();
+    thread::Builder::new()
+        .name(format!("{}-shard-{}", ENGINE_THREAD_NAME, index))
+        .spawn(move || run_symbol_engine(rings, books, &default_symbol, shutdown, metrics, hooks))
+}
+
+/// Drains packets from the ring buffer into the shared order book until
+/// `shutdown` is raised.
+pub fn run_engine(
+    consumer: Consumer<Packet>,
+    order_book: Arc<Mutex<OrderBook>>,
+    shutdown: Arc<AtomicBool>,
+    metrics: Arc<Metrics>,
+    hooks: EngineHooks,
+) {
+    drive(vec![consumer], &shutdown, &metrics, hooks, |batch, metrics, hooks| {
+        if batch.is_empty() {
+            if let Some(bbo) = hooks.bbo.as_mut() {
+                bbo.poll();
+            }
+            return;
+        }
+        process_batch(batch, &order_book, metrics, hooks)
+    });
+}
+
+/// Like `run_engine` for several symbols, on one ring or one per symbol.
+/// Each batch is split by symbol, arrival order kept within each, and
+/// matched against that symbol's book; orders with no symbol go to
+/// `default_symbol`'s. Orders for a symbol this engine has no book for are
+/// rejected.
+///
+/// A BBO follows one book, so `hooks.bbo` is copied for each, tagged with
+/// its symbol; only `default_symbol`'s copy samples the spread history.
+/// Every order is named after its book, which tags its executions too.
+pub fn run_symbol_engine(
+    rings: Vec<Consumer<Packet>>,
+    books: SymbolBooks,
+    default_symbol: &str,
+    shutdown: Arc<AtomicBool>,
+    metrics: Arc<Metrics>,
+    mut hooks: EngineHooks,
+) {
+    let mut bbos: BTreeMap<&str, Option<BboPublisher>> = match hooks.bbo.take() {
+        Some(bbo) => books
+            .keys()
+            .map(|symbol| {
+                let publisher = bbo.clone().with_symbol(symbol);
+                let publisher = if symbol == default_symbol { publisher } else { publisher.without_spread_history() };
+                (symbol.as_str(), Some(publisher))
+            })
+            .collect(),
+        None => BTreeMap::new(),
+    };
+    drive(rings, &shutdown, &metrics, hooks, |batch, metrics, hooks| {
+        if batch.is_empty() {
+            bbos.values_mut().flatten().for_each(BboPublisher::poll);
+            return;
+        }
+        for (symbol, mut packets) in split_by_symbol(batch, default_symbol) {
+            match books.get(&symbol) {
+                Some(book) => {
+                    for packet in &mut packets {
+                        packet.order.symbol.get_or_insert_with(|| symbol.clone());
+                    }
+                    // Lend the book's publisher to the hooks for this batch
+                    if let Some(bbo) = bbos.get_mut(symbol.as_str()) {
+                        std::mem::swap(&mut hooks.bbo, bbo);
+                        process_batch(packets, book, metrics, hooks);
+                        std::mem::swap(&mut hooks.bbo, bbo);
+                    } else {
+                        process_batch(packets, book, metrics, hooks);
+                    }
+                }
+                None => {
+                    for packet in packets {
+                        let error = EntryError::new(RejectReason::UnknownSymbol, format!("no book for {} on this engine", symbol));
+                        reject(Some(&packet.order), error, packet.ticket, hooks);
+                    }
+                }
+            }
+        }
+    });
+}
+
+/// Groups a batch by symbol, symbols in order of first appearance and each
+/// group in arrival order
+fn split_by_symbol(batch: Vec<Packet>, default_symbol: &str) -> Vec<(String, Vec<Packet>)> {
+    let mut groups: Vec<(String, Vec<Packet>)> = Vec::new();
+    for packet in batch {
+        let symbol = packet.order.symbol.as_deref().unwrap_or(default_symbol);
+        match groups.iter_mut().find(|(group, _)| group == symbol) {
+            Some((_, packets)) => packets.push(packet),
+            None => groups.push((symbol.to_string(), vec![packet])),
+        }
+    }
+    groups
+}
+
+/// The engine loop: hands each batch to `process`, and an empty one while
+/// idle so it can poll the hooks, and drains on shutdown if asked to.
+fn drive(
+    mut rings: Vec<Consumer<Packet>>,
+    shutdown: &AtomicBool,
+    metrics: &Metrics,
+    mut hooks: EngineHooks,
+    mut process: impl FnMut(Vec<Packet>, &Metrics, &mut EngineHooks),
+) {
+    let max_batch = hooks.max_batch.unwrap_or(DEFAULT_ENGINE_BATCH).max(1);
+    let mut idler = Idler::new(hooks.wait);
+    let mut first_ring = 0;
+    while !shutdown.load(Ordering::Relaxed) {
+        metrics.record_engine_heartbeat();
+        let batch = drain_rings(&mut rings, &mut first_ring, max_batch);
+        if batch.is_empty() {
+            process(batch, metrics, &mut hooks);
+            idler.idle();
+        } else {
+            idler.reset();
+            metrics.record_ring_occupancy(rings.iter().map(Consumer::slots).sum());
+            process(batch, metrics, &mut hooks);
+        }
+    }
+
+    if hooks.drain_on_shutdown {
+        let mut drained = 0;
+        loop {
+            let batch = drain_rings(&mut rings, &mut first_ring, max_batch);
+            if batch.is_empty() {
+                break;
+            }
+            drained += batch.len() as u64;
+            process(batch, metrics, &mut hooks);
+        }
+        metrics.record_drained_on_shutdown(drained);
+        info!("🧹 [ENGINE] Drained {} buffered orders before stopping", drained);
+    }
+}
+
+/// Takes up to `max` packets across `rings`, each ring's oldest first,
+/// starting one ring further along each time so a busy ring cannot starve
+/// the others.
+fn drain_rings(rings: &mut [Consumer<Packet>], first: &mut usize, max: usize) -> Vec<Packet> {
+    if let [ring] = rings {
+        return drain_batch(ring, max);
+    }
+    let count = rings.len().max(1);
+    let mut batch = Vec::new();
+    for offset in 0..rings.len() {
+        if batch.len() == max {
+            break;
+        }
+        batch.extend(drain_batch(&mut rings[(*first + offset) % count], max - batch.len()));
+    }
+    *first = (*first + 1) % count;
+    batch
+}
+
+/// Takes up to `max` packets off the ring in one read, oldest first.
+pub fn drain_batch(consumer: &mut Consumer<Packet>, max: usize) -> Vec<Packet> {
+    let available = consumer.slots().min(max);
+    match consumer.read_chunk(available) {
+        Ok(chunk) => chunk.into_iter().collect(),
+        Err(_) => Vec::new(),
+    }
+}
+
+/// What happened to one packet while the book was locked, for the work
+/// that can wait until it is released
+enum Applied {
+    /// `order` is kept only if the rejection log wants it
+    Rejected { order_id: u64, order: Option<Order>, error: EntryError, ticket: Option<u64> },
+    Matched {
+        taker_account: Option<u64>,
+        taker_id: u64,
+        executions: Vec<TradeExecution>,
+        rested_quantity: u64,
+        ticket: Option<u64>,
+    },
+    /// A cancel or book-wide command went through
+    Commanded { executions: Vec<TradeExecution>, outcome: OrderOutcome, ticket: Option<u64> },
+}
+
+/// Matches a batch under one book lock, then publishes the results in
+/// arrival order.
+fn process_batch(batch: Vec<Packet>, order_book: &Mutex<OrderBook>, metrics: &Metrics, hooks: &mut EngineHooks) {
+    let mut applied = Vec::with_capacity(batch.len());
+    let mut checkpoint = None;
+    {
+        let mut book = order_book.lock().unwrap();
+        for packet in batch {
+            applied.push(apply_packet(packet, &mut book, metrics, hooks));
+            if let Some(snapshot) = hooks.wal.as_ref().and_then(|wal| wal.checkpoint_due(&book)) {
+                checkpoint = Some(snapshot);
+            }
+        }
+    }
+    if let (Some(wal), Some(snapshot)) = (hooks.wal.as_ref(), checkpoint) {
+        if let Err(error) = wal.save_checkpoint(&snapshot) {
+            error!("❌ [ENGINE] Snapshot at journal sequence {} failed: {}", snapshot.sequence, error);
+        }
+    }
+    for outcome in applied {
+        publish(outcome, metrics, hooks);
+    }
+}
+
+fn apply_packet(packet: Packet, book: &mut OrderBook, metrics: &Metrics, hooks: &mut EngineHooks) -> Applied {
+    if !packet.action.is_submit() {
+        return apply_command(packet, book, metrics, hooks);
+    }
+    let taker_account = packet.order.account_id;
+    let taker_id = packet.order.id;
+    let recv_ns = packet.recv_ns;
+    let ticket = packet.ticket;
+
+    if let Err(error) = book.check_order(&packet.order) {
+        return Applied::Rejected { order_id: taker_id, order: Some(packet.order), error, ticket };
+    }
+    // The book can still refuse the order on arrival; only the rejection
+    // log needs it back for that
+    let logged = hooks.rejections.as_ref().map(|_| packet.order.clone());
+    let replicated = hooks.replica.as_ref().map(|_| packet.order.clone());
+    let dumped = hooks.tick_dump.as_ref().map(|_| packet.order.clone());
+    if let Some(wal) = hooks.wal.as_mut() {
+        // Matching goes ahead regardless; the order is only missing from recovery
+        if let Err(error) = wal.append(&packet.order) {
+            error!("❌ [ENGINE] Order {} not journaled: {}", packet.order.id, error);
+        }
+    }
+    if let Some(recorder) = hooks.recorder.as_mut() {
+        if let Err(error) = recorder.record(recv_ns, &packet.order) {
+            error!("❌ [ENGINE] Order {} not recorded: {}", packet.order.id, error);
+        }
+    }
+    let clock = process_clock();
+    metrics.queue_latency().record(clock.now_ns().saturating_sub(recv_ns));
+    let match_start = Instant::now();
+    let placed = book.place_order(packet.order);
+    metrics.match_latency().record(match_start.elapsed().as_nanos() as u64);
+    metrics.end_to_end_latency().record(clock.now_ns().saturating_sub(recv_ns));
+    let executions = match placed {
+        Ok(executions) => executions,
+        Err(error) => return Applied::Rejected { order_id: taker_id, order: logged, error, ticket },
+    };
+    metrics.record_order_processed(executions.len());
+    // The replica only sees what the book took, or it would book the
+    // refused order itself
+    if let (Some(feed), Some(order)) = (hooks.replica.as_ref(), replicated) {
+        feed.publish(book.sequence(), order);
+    }
+    if let (Some(dump), Some(order)) = (hooks.tick_dump.as_mut(), dumped) {
+        dump.record(&order, &executions, book.best_bid(), book.best_ask());
+    }
+    if let Some(bbo) = hooks.bbo.as_mut() {
+        bbo.on_book_change(book);
+    }
+    if let Some(sink @ TradeSink::Inline(_)) = hooks.trades.as_mut() {
+        sink.record(&executions);
+    }
+    let rested_quantity = hooks.results.as_ref().map_or(0, |_| book.resting_quantity(taker_id).unwrap_or(0));
+    Applied::Matched { taker_account, taker_id, executions, rested_quantity, ticket }
+}
+
+/// Cancels and book-wide commands: journaled, replicated and fed to the BBO
+/// and trade sink like orders, but with no entry checks or order latencies
+fn apply_command(packet: Packet, book: &mut OrderBook, metrics: &Metrics, hooks: &mut EngineHooks) -> Applied {
+    let Packet { order, action, ticket, .. } = packet;
+    if let Some(wal) = hooks.wal.as_mut() {
+        if let Err(error) = wal.append_action(&order, action) {
+            error!("❌ [ENGINE] {:?} for order {} not journaled: {}", action, order.id, error);
+        }
+    }
+    let (executions, outcome) = match action {
+        BookAction::Cancel => (Vec::new(), OrderOutcome::Cancelled(book.cancel_order(order.id))),
+        BookAction::ReferencePrice(price) => {
+            let executions = book.update_reference_price(price);
+            (executions.clone(), OrderOutcome::Executed { executions, rested_quantity: 0 })
+        }
+        BookAction::TradingState(state) => (Vec::new(), OrderOutcome::TradingStateSet(book.set_trading_state(state))),
+        BookAction::Submit => unreachable!("orders go through apply_packet"),
+    };
+    metrics.record_trades(executions.len());
+    if let Some(feed) = hooks.replica.as_ref() {
+        feed.publish_action(book.sequence(), order.clone(), action);
+    }
+    if let Some(bbo) = hooks.bbo.as_mut() {
+        bbo.on_book_change(book);
+    }
+    if let Some(sink @ TradeSink::Inline(_)) = hooks.trades.as_mut() {
+        sink.record(&executions);
+    }
+    Applied::Commanded { executions, outcome, ticket }
+}
+
+fn publish(applied: Applied, metrics: &Metrics, hooks: &mut EngineHooks) {
+    let (taker_account, taker_id, executions, rested_quantity, ticket) = match applied {
+        Applied::Rejected { order_id, order, error, ticket } => {
+            warn!("❌ [ENGINE] Order {} rejected: {}", order_id, error);
+            reject(order.as_ref(), error, ticket, hooks);
+            return;
+        }
+        Applied::Matched { taker_account, taker_id, executions, rested_quantity, ticket } => {
+            (taker_account, taker_id, executions, rested_quantity, ticket)
+        }
+        Applied::Commanded { executions, outcome, ticket } => {
+            if let Some(sink @ TradeSink::Offloaded(_)) = hooks.trades.as_mut() {
+                sink.record(&executions);
+            }
+            if let (Some(results), Some(ticket)) = (hooks.results.as_ref(), ticket) {
+                results.complete(ticket, outcome);
+            }
+            return;
+        }
+    };
+    if let Some(sink @ TradeSink::Offloaded(_)) = hooks.trades.as_mut() {
+        sink.record(&executions);
+    }
+    metrics.record_price_improvement(taker_account, &executions);
+    if let (Some(results), Some(ticket)) = (hooks.results.as_ref(), ticket) {
+        // Only this order's own fills; stops it set off report their own
+        let own = executions.iter().filter(|e| e.taker_order_id == taker_id).cloned().collect();
+        results.complete(ticket, OrderOutcome::Executed { executions: own, rested_quantity });
+    }
+    if let Some(fills) = hooks.fills.as_ref() {
+        fills.notify(taker_id, &executions);
+    }
+}
+
+fn reject(order: Option<&Order>, error: EntryError, ticket: Option<u64>, hooks: &EngineHooks) {
+    if let Some(log) = hooks.rejections.as_ref() {
+        log.record(error.reason, order, &error.detail);
+    }
+    if let (Some(results), Some(ticket)) = (hooks.results.as_ref(), ticket) {
+        results.complete(ticket, OrderOutcome::Rejected(error));
+    }
+}
+
+// ============================================================================
+// PHASED SHUTDOWN - Stop ingress, drain, then stop the engine
+// ============================================================================
+
+/// Separate stop flags for the producers (gateway, funnel) and for the
+/// engine, so accepted orders reach the book before the engine exits.
+#[derive(Clone, Default)]
+pub struct PhasedShutdown {
+    ingress: Arc<AtomicBool>,
+    engine: Arc<AtomicBool>,
+}
+
+impl PhasedShutdown {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Hand to the gateway and funnel
+    pub fn ingress_flag(&self) -> Arc<AtomicBool> {
+        self.ingress.clone()
+    }
+
+    /// Hand to the engine (and anything that should outlive ingress)
+    pub fn engine_flag(&self) -> Arc<AtomicBool> {
+        self.engine.clone()
+    }
+
+    /// Stops ingress and waits for those threads (the funnel flushes into
+    /// the ring on its way out), then stops the engine and waits for it to
+    /// drain.
+    pub fn run(&self, ingress_threads: Vec<JoinHandle<()>>, engine: JoinHandle<()>) -> thread::Result<()> {
+        self.ingress.store(true, Ordering::Relaxed);
+        for handle in ingress_threads {
+            handle.join()?;
+        }
+        self.engine.store(true, Ordering::Relaxed);
+        engine.join()
+    }
+}
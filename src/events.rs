@@ -0,0 +1,297 @@
+// ============================================================================
+// EVENT BUS - Fan-out of engine events to any number of subscribers
+// ============================================================================
+
+use crate::matching_engine::TradeExecution;
+use crate::price_units::Price;
+use crate::rejections::Rejection;
+use crossbeam_channel::{unbounded, Receiver, Sender};
+use serde::Serialize;
+use std::collections::{BTreeMap, VecDeque};
+use std::sync::{Arc, Mutex};
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "type")]
+pub enum BookEvent {
+    /// Sampled top of book
+    Bbo {
+        bid: Option<Price>,
+        ask: Option<Price>,
+        /// Displayed quantity at the best bid (0 with no bid)
+        #[serde(default)]
+        bid_quantity: u64,
+        #[serde(default)]
+        ask_quantity: u64,
+        timestamp_ns: u64,
+        /// Book this is for; only set by a sharded engine, whose feed
+        /// carries several
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        symbol: Option<String>,
+    },
+    Trade(TradeExecution),
+    /// Progress of one incoming order, see `FillNotifier`
+    Fill(FillNotification),
+    /// A sampled order rejection, see `RejectionLog`
+    Rejection(Rejection),
+}
+
+/// One event with its place in the engine's total order. Sequences start at
+/// 1 and go up by exactly one per event, so a consumer can spot a gap.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct BusMessage {
+    pub sequence: u64,
+    pub event: BookEvent,
+}
+
+/// Recent messages kept for `replay_since` unless configured otherwise
+pub const DEFAULT_EVENT_RETENTION: usize = 1024;
+
+/// A resync asked for messages the bus no longer retains
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ResyncTooOld {
+    /// Oldest sequence still available, if any
+    pub oldest_retained: Option<u64>,
+}
+
+impl std::fmt::Display for ResyncTooOld {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.oldest_retained {
+            Some(oldest) => write!(f, "events before sequence {} are no longer retained", oldest),
+            None => write!(f, "no events are retained"),
+        }
+    }
+}
+
+impl std::error::Error for ResyncTooOld {}
+
+struct BusState {
+    subscribers: Vec<Sender<BusMessage>>,
+    /// Next sequence handed out by `reserve`
+    next_reserved: u64,
+    /// Next sequence to go out to subscribers
+    next_delivered: u64,
+    /// Published ahead of an earlier reserved sequence, waiting for it
+    held: BTreeMap<u64, BookEvent>,
+    retained: VecDeque<BusMessage>,
+    retention: usize,
+}
+
+/// Every subscriber gets its own unbounded channel; a subscriber that drops
+/// its receiver is pruned on the next publish.
+///
+/// Ordering: sequences are stamped when an event is produced, in the order
+/// the engine produced it, and subscribers always receive messages in
+/// sequence order with no gaps. A producer that hands events to another
+/// thread (the offloaded trade writer) reserves their sequences up front; any
+/// later event is held back until the reserved ones are published.
+pub struct EventBus {
+    state: Mutex<BusState>,
+}
+
+impl Default for EventBus {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl EventBus {
+    pub fn new() -> Self {
+        Self::with_retention(DEFAULT_EVENT_RETENTION)
+    }
+
+    /// Keeps the last `retention` messages for consumers that need to resync.
+    pub fn with_retention(retention: usize) -> Self {
+        EventBus {
+            state: Mutex::new(BusState {
+                subscribers: Vec::new(),
+                next_reserved: 1,
+                next_delivered: 1,
+                held: BTreeMap::new(),
+                retained: VecDeque::new(),
+                retention,
+            }),
+        }
+    }
+
+    pub fn subscribe(&self) -> Receiver<BusMessage> {
+        let (tx, rx) = unbounded();
+        self.state.lock().unwrap().subscribers.push(tx);
+        rx
+    }
+
+    /// Live subscribers; a dropped receiver is only noticed on the next publish
+    pub fn subscriber_count(&self) -> usize {
+        self.state.lock().unwrap().subscribers.len()
+    }
+
+    /// Stamps `event` with the next sequence and delivers it.
+    pub fn publish(&self, event: BookEvent) {
+        let sequence = self.reserve(1);
+        self.publish_reserved(sequence, event);
+    }
+
+    /// Claims `count` consecutive sequences for events that will be
+    /// published later with `publish_reserved`; returns the first.
+    pub fn reserve(&self, count: u64) -> u64 {
+        let mut state = self.state.lock().unwrap();
+        let first = state.next_reserved;
+        state.next_reserved += count;
+        first
+    }
+
+    /// Publishes an event under a sequence from `reserve`. Delivery waits
+    /// until every earlier sequence has been published.
+    pub fn publish_reserved(&self, sequence: u64, event: BookEvent) {
+        let mut state = self.state.lock().unwrap();
+        state.held.insert(sequence, event);
+        loop {
+            let next = state.next_delivered;
+            let Some(event) = state.held.remove(&next) else {
+                break;
+            };
+            let message = BusMessage { sequence: next, event };
+            state.subscribers.retain(|tx| tx.send(message.clone()).is_ok());
+            if state.retention > 0 {
+                if state.retained.len() == state.retention {
+                    state.retained.pop_front();
+                }
+                state.retained.push_back(message);
+            }
+            state.next_delivered += 1;
+        }
+    }
+
+    /// Messages after `sequence`, for a consumer that saw a gap or fell
+    /// behind. Fails when some of them have already aged out; the consumer
+    /// then needs a full book snapshot instead.
+    pub fn replay_since(&self, sequence: u64) -> Result<Vec<BusMessage>, ResyncTooOld> {
+        let state = self.state.lock().unwrap();
+        let oldest_retained = state.retained.front().map(|m| m.sequence);
+        let missing_from = sequence + 1;
+        if missing_from < state.next_delivered && oldest_retained.is_none_or(|oldest| oldest > missing_from) {
+            return Err(ResyncTooOld { oldest_retained });
+        }
+        Ok(state.retained.iter().filter(|m| m.sequence > sequence).cloned().collect())
+    }
+}
+
+/// What a consumer makes of the next message it receives
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SequenceCheck {
+    InOrder,
+    /// Messages `expected..received` never arrived; resync from `expected - 1`
+    Gap { expected: u64, received: u64 },
+    /// Already seen (or older); drop it
+    Stale { expected: u64, received: u64 },
+}
+
+/// Consumer-side check that messages arrive strictly in sequence.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SequenceTracker {
+    expected: u64,
+}
+
+impl Default for SequenceTracker {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SequenceTracker {
+    pub fn new() -> Self {
+        SequenceTracker { expected: 1 }
+    }
+
+    /// Starts after a snapshot or resync that covered up to `sequence`.
+    pub fn resume_after(sequence: u64) -> Self {
+        SequenceTracker { expected: sequence + 1 }
+    }
+
+    /// Last sequence applied in order
+    pub fn last_applied(&self) -> u64 {
+        self.expected - 1
+    }
+
+    pub fn observe(&mut self, sequence: u64) -> SequenceCheck {
+        let expected = self.expected;
+        if sequence == expected {
+            self.expected += 1;
+            SequenceCheck::InOrder
+        } else if sequence > expected {
+            SequenceCheck::Gap { expected, received: sequence }
+        } else {
+            SequenceCheck::Stale { expected, received: sequence }
+        }
+    }
+}
+
+// ============================================================================
+// FILL NOTIFICATIONS - Per-order progress for clients
+// ============================================================================
+
+/// What one incoming order got out of the book
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct FillNotification {
+    pub order_id: u64,
+    pub filled_quantity: u64,
+    /// Quantity-weighted across all legs
+    pub average_price: f64,
+    /// Executions folded into this notification
+    pub legs: usize,
+}
+
+impl FillNotification {
+    /// Folds an order's executions into one notification; `None` if nothing traded.
+    pub fn coalesce(order_id: u64, executions: &[TradeExecution]) -> Option<Self> {
+        let filled_quantity: u64 = executions.iter().map(|e| e.quantity).sum();
+        if filled_quantity == 0 {
+            return None;
+        }
+        let notional: u128 = executions.iter().map(|e| e.price.units() as u128 * e.quantity as u128).sum();
+        Some(FillNotification {
+            order_id,
+            filled_quantity,
+            average_price: notional as f64 / filled_quantity as f64,
+            legs: executions.len(),
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum FillNotificationMode {
+    /// One notification per execution
+    #[default]
+    PerExecution,
+    /// One notification per incoming order, however many levels it swept
+    Coalesced,
+}
+
+/// Publishes `BookEvent::Fill` for each incoming order that traded. Per-leg
+/// trades still go to the trade history either way.
+pub struct FillNotifier {
+    bus: Arc<EventBus>,
+    mode: FillNotificationMode,
+}
+
+impl FillNotifier {
+    pub fn new(bus: Arc<EventBus>, mode: FillNotificationMode) -> Self {
+        FillNotifier { bus, mode }
+    }
+
+    pub fn notify(&self, order_id: u64, executions: &[TradeExecution]) {
+        match self.mode {
+            FillNotificationMode::PerExecution => {
+                for execution in executions {
+                    if let Some(fill) = FillNotification::coalesce(order_id, std::slice::from_ref(execution)) {
+                        self.bus.publish(BookEvent::Fill(fill));
+                    }
+                }
+            }
+            FillNotificationMode::Coalesced => {
+                if let Some(fill) = FillNotification::coalesce(order_id, executions) {
+                    self.bus.publish(BookEvent::Fill(fill));
+                }
+            }
+        }
+    }
+}
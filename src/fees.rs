@@ -0,0 +1,79 @@
+// ============================================================================
+// FEE SCHEDULES - Versioned maker/taker fees
+// ============================================================================
+// Fees are charged in basis points of notional (`price * quantity`).
+// Negative rates are rebates. Every schedule change gets a new version and
+// the book sequence it takes effect from, so any execution can be traced
+// back to the schedule that priced it.
+
+use crate::price_units::Price;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FeeSchedule {
+    pub maker_fee_bps: i64,
+    pub taker_fee_bps: i64,
+}
+
+impl FeeSchedule {
+    pub fn new(maker_fee_bps: i64, taker_fee_bps: i64) -> Self {
+        FeeSchedule { maker_fee_bps, taker_fee_bps }
+    }
+
+    pub fn maker_fee(&self, price: Price, quantity: u64) -> i64 {
+        fee(price, quantity, self.maker_fee_bps)
+    }
+
+    pub fn taker_fee(&self, price: Price, quantity: u64) -> i64 {
+        fee(price, quantity, self.taker_fee_bps)
+    }
+}
+
+/// Truncates toward zero, so fractional fees and rebates are never rounded up
+fn fee(price: Price, quantity: u64, bps: i64) -> i64 {
+    (price.units() as i128 * quantity as i128 * bps as i128 / 10_000) as i64
+}
+
+/// A schedule together with when it became active
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct FeeScheduleVersion {
+    /// 0 is the fee-free schedule every book starts with
+    pub version: u64,
+    /// First book sequence number priced with this schedule
+    pub effective_from_sequence: u64,
+    pub schedule: FeeSchedule,
+}
+
+/// Every schedule a book has used, oldest first
+#[derive(Debug, Clone)]
+pub struct FeeHistory {
+    versions: Vec<FeeScheduleVersion>,
+}
+
+impl Default for FeeHistory {
+    fn default() -> Self {
+        FeeHistory {
+            versions: vec![FeeScheduleVersion { version: 0, effective_from_sequence: 1, schedule: FeeSchedule::default() }],
+        }
+    }
+}
+
+impl FeeHistory {
+    pub fn current(&self) -> &FeeScheduleVersion {
+        self.versions.last().expect("fee history always holds version 0")
+    }
+
+    pub fn versions(&self) -> &[FeeScheduleVersion] {
+        &self.versions
+    }
+
+    pub fn version(&self, version: u64) -> Option<&FeeScheduleVersion> {
+        self.versions.get(version as usize)
+    }
+
+    pub(crate) fn push(&mut self, schedule: FeeSchedule, effective_from_sequence: u64) -> u64 {
+        let version = self.versions.len() as u64;
+        self.versions.push(FeeScheduleVersion { version, effective_from_sequence, schedule });
+        version
+    }
+}
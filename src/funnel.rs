@@ -0,0 +1,234 @@
+// ============================================================================
+// ORDER FUNNEL - Many producers into the single-producer ring buffer
+// ============================================================================
+// The ring buffer is SPSC, so every gateway connection hands its orders to
+// a `FunnelSender`; one forwarder thread owns the real `Producer` and feeds
+// the ring. A global cap on orders in flight (accepted by the funnel but not
+// yet in the ring) keeps a flood of producers from queueing without bound
+// ahead of the engine. The ring is the engine's own, or one symbol's
+// shard when the engine is sharded.
+
+use crate::matching_engine::Packet;
+use crossbeam_channel::{unbounded, Receiver, RecvTimeoutError, Sender};
+use rtrb::{Producer, PushError};
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+/// Name of the forwarder thread
+pub const FUNNEL_THREAD_NAME: &str = "funnel";
+
+pub const DEFAULT_MAX_IN_FLIGHT: usize = 65_536;
+
+/// How long the forwarder waits for work before re-checking shutdown
+const FUNNEL_POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+/// What a producer does when the in-flight cap is reached
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum OverflowPolicy {
+    /// Wait until the engine catches up
+    Block,
+    /// Hand the order back with `SubmitError::Backpressure`
+    #[default]
+    Reject,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FunnelConfig {
+    pub max_in_flight: usize,
+    pub overflow: OverflowPolicy,
+    /// On shutdown, push everything already accepted into the ring before
+    /// exiting instead of dropping it. The engine must still be consuming.
+    pub drain_on_shutdown: bool,
+}
+
+impl Default for FunnelConfig {
+    fn default() -> Self {
+        FunnelConfig { max_in_flight: DEFAULT_MAX_IN_FLIGHT, overflow: OverflowPolicy::Reject, drain_on_shutdown: false }
+    }
+}
+
+#[derive(Debug)]
+pub enum SubmitError {
+    /// The in-flight cap is reached; try again later
+    Backpressure(Packet),
+    /// The forwarder has stopped
+    Closed(Packet),
+}
+
+/// Shared depth counters, also read by metrics
+#[derive(Debug, Default)]
+pub struct FunnelStats {
+    in_flight: AtomicUsize,
+    rejected: AtomicU64,
+}
+
+impl FunnelStats {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Orders accepted but not yet handed to the ring buffer
+    pub fn in_flight(&self) -> usize {
+        self.in_flight.load(Ordering::Relaxed)
+    }
+
+    /// Submissions turned away by the cap
+    pub fn rejected(&self) -> u64 {
+        self.rejected.load(Ordering::Relaxed)
+    }
+}
+
+/// The ring a forwarder feeds
+pub trait RingInput: Send + 'static {
+    /// Hands the packet back if the ring is full.
+    #[allow(clippy::result_large_err)]
+    fn push(&mut self, packet: Packet) -> Result<(), Packet>;
+
+    /// The engine end has gone away.
+    fn is_abandoned(&self) -> bool;
+}
+
+impl RingInput for Producer<Packet> {
+    fn push(&mut self, packet: Packet) -> Result<(), Packet> {
+        Producer::push(self, packet).map_err(|PushError::Full(packet)| packet)
+    }
+
+    fn is_abandoned(&self) -> bool {
+        Producer::is_abandoned(self)
+    }
+}
+
+/// Cheap to clone; one per producer thread.
+#[derive(Clone)]
+pub struct FunnelSender {
+    tx: Sender<Packet>,
+    config: FunnelConfig,
+    stats: Arc<FunnelStats>,
+    closed: Arc<AtomicBool>,
+    /// Submits between their `closed` check and their send; a draining
+    /// forwarder waits for these to land before it stops
+    submitting: Arc<AtomicUsize>,
+}
+
+impl FunnelSender {
+    // Handing the packet back by value keeps rejection allocation-free
+    #[allow(clippy::result_large_err)]
+    pub fn submit(&self, packet: Packet) -> Result<(), SubmitError> {
+        self.submitting.fetch_add(1, Ordering::SeqCst);
+        let submitted = self.send(packet);
+        self.submitting.fetch_sub(1, Ordering::SeqCst);
+        submitted
+    }
+
+    #[allow(clippy::result_large_err)]
+    fn send(&self, packet: Packet) -> Result<(), SubmitError> {
+        loop {
+            if self.closed.load(Ordering::SeqCst) {
+                return Err(SubmitError::Closed(packet));
+            }
+            let reserved = self.stats.in_flight.fetch_update(Ordering::AcqRel, Ordering::Acquire, |depth| {
+                (depth < self.config.max_in_flight).then_some(depth + 1)
+            });
+            if reserved.is_ok() {
+                break;
+            }
+            match self.config.overflow {
+                OverflowPolicy::Block => thread::yield_now(),
+                OverflowPolicy::Reject => {
+                    self.stats.rejected.fetch_add(1, Ordering::Relaxed);
+                    return Err(SubmitError::Backpressure(packet));
+                }
+            }
+        }
+        self.tx.send(packet).map_err(|e| {
+            self.stats.in_flight.fetch_sub(1, Ordering::AcqRel);
+            SubmitError::Closed(e.0)
+        })
+    }
+
+    pub fn stats(&self) -> &Arc<FunnelStats> {
+        &self.stats
+    }
+}
+
+/// Starts the forwarder on a thread named `funnel`. It runs until
+/// `shutdown` is raised or every sender is dropped.
+pub fn spawn_funnel(
+    producer: impl RingInput,
+    config: FunnelConfig,
+    stats: Arc<FunnelStats>,
+    shutdown: Arc<AtomicBool>,
+) -> std::io::Result<(FunnelSender, JoinHandle<()>)> {
+    let (tx, rx) = unbounded();
+    let closed = Arc::new(AtomicBool::new(false));
+    let submitting = Arc::new(AtomicUsize::new(0));
+    let sender = FunnelSender { tx, config, stats: stats.clone(), closed: closed.clone(), submitting: submitting.clone() };
+    let handle = thread::Builder::new()
+        .name(FUNNEL_THREAD_NAME.to_string())
+        .spawn(move || {
+            run_funnel(rx, producer, &stats, &shutdown, &closed, &submitting, config.drain_on_shutdown);
+            closed.store(true, Ordering::Release);
+        })?;
+    Ok((sender, handle))
+}
+
+fn run_funnel(
+    rx: Receiver<Packet>,
+    mut producer: impl RingInput,
+    stats: &FunnelStats,
+    shutdown: &AtomicBool,
+    closed: &AtomicBool,
+    submitting: &AtomicUsize,
+    drain: bool,
+) {
+    loop {
+        let packet = if !shutdown.load(Ordering::Relaxed) {
+            match rx.recv_timeout(FUNNEL_POLL_INTERVAL) {
+                Ok(packet) => packet,
+                Err(RecvTimeoutError::Timeout) => continue,
+                Err(RecvTimeoutError::Disconnected) => return,
+            }
+        } else if drain {
+            // Refuse new orders, then flush what was already accepted. An
+            // empty channel is only final once no submit that got past the
+            // `closed` check is still on its way in.
+            closed.store(true, Ordering::SeqCst);
+            match rx.try_recv() {
+                Ok(packet) => packet,
+                Err(_) if submitting.load(Ordering::SeqCst) == 0 => match rx.try_recv() {
+                    Ok(packet) => packet,
+                    Err(_) => return,
+                },
+                Err(_) => {
+                    thread::yield_now();
+                    continue;
+                }
+            }
+        } else {
+            return;
+        };
+        if !forward(&mut producer, packet, shutdown, drain) {
+            return;
+        }
+        stats.in_flight.fetch_sub(1, Ordering::AcqRel);
+    }
+}
+
+/// The ring is the engine's queue; wait for room rather than drop. Gives up
+/// (false) once the engine is gone, or on shutdown unless draining.
+fn forward(producer: &mut impl RingInput, mut packet: Packet, shutdown: &AtomicBool, drain: bool) -> bool {
+    loop {
+        match producer.push(packet) {
+            Ok(()) => return true,
+            Err(rejected) => {
+                if producer.is_abandoned() || (!drain && shutdown.load(Ordering::Relaxed)) {
+                    return false;
+                }
+                packet = rejected;
+                std::hint::spin_loop();
+            }
+        }
+    }
+}
@@ -1,66 +1,443 @@
-use std::net::{TcpListener, TcpStream};
-use std::io::{BufRead, BufReader, Write};
-use std::thread;
-use std::sync::{Arc, Mutex};
-use crate::matching_engine::{Order, Packet};
-use rtrb::Producer;
-
-pub fn run_gateway(producer: Producer<Packet>) -> Result<(), Box<dyn std::error::Error>> {
-    let listener = TcpListener::bind("127.0.0.1:8083")?;
-    println!("🌐 [GATEWAY] Listening on 127.0.0.1:8083");
-
-    // Wrap producer in Arc<Mutex> to share across threads
-    let producer = Arc::new(Mutex::new(producer));
-
-    for stream in listener.incoming() {
-        match stream {
-            Ok(stream) => {
-                let producer = producer.clone();
-                thread::spawn(move || {
-                    handle_client(stream, producer);
-                });
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::io::{BufRead, BufReader, Read, Write};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::collections::HashMap;
+use crate::funnel::{FunnelSender, SubmitError};
+use crate::ingress::{ConnectionGuard, Disconnect, IngressStats};
+use crate::matching_engine::{Order, Packet, ORDER_WIRE_LEN};
+use crate::order_results::{executed_ack, OrderOutcome, OrderResults, PendingResult};
+use crate::rejections::{EntryError, RejectReason};
+use serde_json::json;
+use log::{debug, error, info, warn};
+
+/// Bind address used when none is configured
+pub const DEFAULT_GATEWAY_ADDR: &str = "127.0.0.1:8083";
+
+/// Name of the accept-loop thread; connections get `conn-<peer>`
+pub const GATEWAY_THREAD_NAME: &str = "gateway-accept";
+
+/// How long the accept loop sleeps between polls of the shutdown flag
+const ACCEPT_POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+/// Most orders acknowledged with one socket write. A client that pipelines
+/// gets its acks in bursts of up to this many instead of one syscall each.
+pub const MAX_ACK_BURST: usize = 64;
+
+/// How long an accepted order's ack waits for the engine's outcome before
+/// going out as a plain `accepted`
+pub const RESULT_TIMEOUT: Duration = Duration::from_secs(1);
+
+const ACCEPTED_ACK: &str = r#"{"status":"accepted"}"#;
+
+/// First byte of a connection that speaks the binary protocol: frames of a
+/// little-endian `u32` length followed by `Order::to_bytes`. Acks stay JSON
+/// lines either way. Any other first byte means newline-delimited JSON.
+pub const BINARY_MAGIC: u8 = 0xB1;
+
+/// Longest frame the binary reader will skip over; past this the stream
+/// cannot be trusted to resynchronise and the connection is dropped.
+pub const MAX_FRAME_LEN: usize = 1024;
+
+/// Longest JSON line the gateway will buffer, newline excluded. A longer
+/// line is discarded up to its newline and rejected; the connection carries on.
+pub const MAX_LINE_LEN: usize = 4096;
+
+/// Length-prefixed frame for one order on a binary connection
+pub fn binary_frame(order: &Order) -> Vec<u8> {
+    let mut frame = Vec::with_capacity(4 + ORDER_WIRE_LEN);
+    frame.extend_from_slice(&(ORDER_WIRE_LEN as u32).to_le_bytes());
+    frame.extend_from_slice(&order.to_bytes());
+    frame
+}
+
+/// One ack in write order. Submitted orders may still be waiting on the engine.
+enum Ack {
+    Ready(String),
+    Awaiting { pending: PendingResult, quantity: u64 },
+    /// A JSON array message, acked as one array line in element order
+    Batch(Vec<Ack>),
+}
+
+/// One message read off a client connection
+enum Inbound {
+    Order(Order),
+    /// A JSON array of orders on one line; bad elements fail on their own
+    Batch(Vec<Result<Order, EntryError>>),
+    /// Nothing to process (a blank JSON line)
+    Blank,
+    /// Could not be decoded; the connection carries on
+    Malformed(EntryError),
+    /// Could not be decoded and the stream is no longer framed
+    Corrupt(EntryError),
+    Closed(Disconnect),
+}
+
+/// Which funnel feeds each symbol's engine. Orders that name no symbol go
+/// to the default symbol's.
+#[derive(Clone)]
+pub struct GatewayRoutes {
+    default_symbol: String,
+    funnels: HashMap<String, FunnelSender>,
+    /// Where engines report outcomes; without it accepted orders ack at once
+    results: Option<Arc<OrderResults>>,
+}
+
+impl GatewayRoutes {
+    pub fn new(default_symbol: &str, funnel: FunnelSender) -> Self {
+        GatewayRoutes {
+            default_symbol: default_symbol.to_string(),
+            funnels: HashMap::from([(default_symbol.to_string(), funnel)]),
+            results: None,
+        }
+    }
+
+    /// Acks carry each order's fills (or the engine's rejection) instead of
+    /// a bare `accepted`. The engines must complete into the same `results`.
+    pub fn with_results(mut self, results: Arc<OrderResults>) -> Self {
+        self.results = Some(results);
+        self
+    }
+
+    pub fn results(&self) -> Option<&Arc<OrderResults>> {
+        self.results.as_ref()
+    }
+
+    pub fn with_symbol(mut self, symbol: &str, funnel: FunnelSender) -> Self {
+        self.funnels.insert(symbol.to_string(), funnel);
+        self
+    }
+
+    /// Funnel for an order's symbol; `None` if no engine trades it
+    pub fn funnel_for(&self, symbol: Option<&str>) -> Option<&FunnelSender> {
+        self.funnels.get(symbol.unwrap_or(&self.default_symbol))
+    }
+}
+
+/// Binds the gateway listener and returns it with the address actually bound,
+/// which differs from `addr` when asking for port 0.
+pub fn bind_gateway(addr: &str) -> std::io::Result<(TcpListener, SocketAddr)> {
+    let listener = TcpListener::bind(addr)?;
+    let local_addr = listener.local_addr()?;
+    Ok((listener, local_addr))
+}
+
+/// Starts `run_gateway` on a thread named `gateway-accept`.
+pub fn spawn_gateway(
+    listener: TcpListener,
+    routes: GatewayRoutes,
+    ingress: Arc<IngressStats>,
+    shutdown: Arc<AtomicBool>,
+) -> std::io::Result<JoinHandle<()>> {
+    thread::Builder::new()
+        .name(GATEWAY_THREAD_NAME.to_string())
+        .spawn(move || {
+            if let Err(e) = run_gateway(listener, routes, ingress, shutdown) {
+                error!("❌ [GATEWAY] Error: {}", e);
+            }
+        })
+}
+
+pub fn run_gateway(
+    listener: TcpListener,
+    routes: GatewayRoutes,
+    ingress: Arc<IngressStats>,
+    shutdown: Arc<AtomicBool>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    // Non-blocking so the accept loop can notice `shutdown`
+    listener.set_nonblocking(true)?;
+    info!("🌐 [GATEWAY] Listening on {}", listener.local_addr()?);
+
+    while !shutdown.load(Ordering::Relaxed) {
+        match listener.accept() {
+            Ok((stream, peer)) => {
+                // Client sockets go back to blocking reads
+                stream.set_nonblocking(false)?;
+                let Some(connection) = ingress.try_open(&peer.to_string()) else {
+                    refuse(stream, ingress.connection_limit().unwrap_or_default());
+                    continue;
+                };
+                let routes = routes.clone();
+                let spawned = thread::Builder::new()
+                    .name(format!("conn-{}", peer))
+                    .spawn(move || {
+                        handle_client(stream, routes, connection);
+                    });
+                if let Err(e) = spawned {
+                    error!("❌ [GATEWAY] Could not start a thread for {}: {}", peer, e);
+                }
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::WouldBlock => {
+                thread::sleep(ACCEPT_POLL_INTERVAL);
             }
             Err(e) => {
-                eprintln!("❌ Connection failed: {}", e);
+                warn!("❌ [GATEWAY] Connection failed: {}", e);
             }
         }
     }
     Ok(())
 }
 
-fn handle_client(mut stream: TcpStream, producer: Arc<Mutex<Producer<Packet>>>) {
-    let peer_addr = stream.peer_addr().unwrap_or_else(|_| "unknown".parse().unwrap());
-    // println!("🔌 New connection from {}", peer_addr); // IO is slow, maybe skip logging
+/// Tells a client over the connection limit why it is being dropped. The
+/// write is best effort; the accept loop must not wait on a slow client.
+fn refuse(mut stream: TcpStream, limit: usize) {
+    let error = EntryError::new(RejectReason::TooManyConnections, format!("limit of {} connections reached", limit));
+    let _ = stream.set_nonblocking(true);
+    let _ = stream.write_all(format!("{}\n", error_ack(&error)).as_bytes());
+}
 
-    let reader = BufReader::new(stream.try_clone().expect("Failed to clone stream"));
-    let mut lines = reader.lines();
+/// Connections share no lock: each submits through its own `FunnelSender`
+/// clone, and acks for every order already buffered from one read go out
+/// in a single write.
+fn handle_client(mut stream: TcpStream, routes: GatewayRoutes, connection: ConnectionGuard) {
+    debug!("🔌 [GATEWAY] New connection from {:?}", stream.peer_addr());
 
-    while let Some(Ok(line)) = lines.next() {
-        if line.trim().is_empty() { continue; }
+    let mut reader = BufReader::new(stream.try_clone().expect("Failed to clone stream"));
+    let binary = match reader.fill_buf() {
+        Ok([BINARY_MAGIC, ..]) => {
+            reader.consume(1);
+            connection.record_bytes(1);
+            true
+        }
+        Ok(_) => false,
+        Err(_) => return connection.close(Disconnect::ReadError),
+    };
+    let mut lines = LineState::default();
+    let mut acks: Vec<Ack> = Vec::new();
+    let mut burst = 0;
 
-        match serde_json::from_str::<Order>(&line) {
-            Ok(order) => {
-                let packet = Packet::new(order);
-                
-                // Push to ring buffer
-                let push_result = {
-                    let mut p = producer.lock().unwrap();
-                    p.push(packet)
-                };
+    let disconnect = loop {
+        // Flush once the buffered messages run out, before blocking on the socket
+        let buffered = if binary { has_buffered_frame(reader.buffer()) } else { reader.buffer().contains(&b'\n') };
+        if burst >= MAX_ACK_BURST || (burst > 0 && !buffered) {
+            flush_acks(&mut stream, &mut acks);
+            burst = 0;
+        }
+        let inbound = if binary { read_frame(&mut reader, &connection) } else { read_json_line(&mut reader, &mut lines, &connection) };
+        match inbound {
+            Inbound::Closed(how) => break how,
+            Inbound::Blank => continue,
+            _ => burst += 1,
+        }
 
-                match push_result {
-                    Ok(_) => {
-                        let _ = stream.write_all(b"{\"status\":\"accepted\"}\n");
-                    }
-                    Err(_) => {
-                        let _ = stream.write_all(b"{\"status\":\"dropped\",\"reason\":\"buffer_full\"}\n");
+        match inbound {
+            Inbound::Order(order) => acks.push(submit(order, &routes, &connection)),
+            Inbound::Batch(items) => {
+                let batch = items.into_iter().map(|item| match item {
+                    Ok(order) => submit(order, &routes, &connection),
+                    Err(error) => {
+                        connection.record_parse_error();
+                        connection.record_rejection(error.reason, None, &error.detail);
+                        Ack::Ready(error_ack(&error))
                     }
-                }
+                }).collect();
+                acks.push(Ack::Batch(batch));
             }
-            Err(e) => {
-                let error_msg = format!("{{\"status\":\"error\",\"reason\":\"{}\"}}\n", e);
-                let _ = stream.write_all(error_msg.as_bytes());
+            Inbound::Malformed(error) => {
+                connection.record_parse_error();
+                connection.record_rejection(error.reason, None, &error.detail);
+                write_error(&mut acks, &error);
             }
+            Inbound::Corrupt(error) => {
+                connection.record_parse_error();
+                connection.record_rejection(error.reason, None, &error.detail);
+                write_error(&mut acks, &error);
+                break Disconnect::Corrupt;
+            }
+            Inbound::Blank | Inbound::Closed(_) => unreachable!("handled above"),
+        }
+    };
+    flush_acks(&mut stream, &mut acks);
+    connection.close(disconnect);
+}
+
+/// Checks and routes one order into its funnel. The ack says whether it was
+/// accepted (or waits on the engine for its outcome), refused or dropped.
+fn submit(order: Order, routes: &GatewayRoutes, connection: &ConnectionGuard) -> Ack {
+    connection.record_order();
+    if let Err(error) = order.check_fields() {
+        connection.record_rejection(error.reason, Some(&order), &error.detail);
+        return Ack::Ready(error_ack(&error));
+    }
+    let Some(funnel) = routes.funnel_for(order.symbol.as_deref()) else {
+        let error = EntryError::new(RejectReason::UnknownSymbol, "no book for symbol");
+        connection.record_rejection(error.reason, Some(&order), &error.detail);
+        return Ack::Ready(error_ack(&error));
+    };
+    // Registered before submitting so the engine cannot answer first
+    let pending = routes.results.as_ref().map(|results| results.register());
+    let quantity = order.quantity;
+    let mut packet = Packet::new(order);
+    packet.ticket = pending.as_ref().map(PendingResult::ticket);
+
+    let dropped = match funnel.submit(packet) {
+        Ok(_) => {
+            return match pending {
+                Some(pending) => Ack::Awaiting { pending, quantity },
+                None => Ack::Ready(ACCEPTED_ACK.to_string()),
+            };
         }
+        Err(SubmitError::Backpressure(packet)) => {
+            connection.record_rejection(RejectReason::Backpressure, Some(&packet.order), "funnel full");
+            r#"{"status":"dropped","reason":"backpressure"}"#
+        }
+        Err(SubmitError::Closed(packet)) => {
+            connection.record_rejection(RejectReason::ShuttingDown, Some(&packet.order), "gateway shutting down");
+            r#"{"status":"dropped","reason":"shutting_down"}"#
+        }
+    };
+    if let (Some(results), Some(pending)) = (routes.results.as_ref(), pending) {
+        results.forget(pending);
+    }
+    Ack::Ready(dropped.to_string())
+}
+
+/// Writes every queued ack in one go, waiting on the engine for those that
+/// need an outcome. The burst shares one `RESULT_TIMEOUT` deadline; an order
+/// the engine has not answered by then is acked as plainly accepted.
+fn flush_acks(stream: &mut TcpStream, acks: &mut Vec<Ack>) {
+    let deadline = Instant::now() + RESULT_TIMEOUT;
+    let mut out = Vec::new();
+    for ack in acks.drain(..) {
+        out.extend_from_slice(resolve(ack, deadline).as_bytes());
+        out.push(b'\n');
+    }
+    let _ = stream.write_all(&out);
+}
+
+/// The ack's JSON, once the engine has answered or `deadline` has passed
+fn resolve(ack: Ack, deadline: Instant) -> String {
+    match ack {
+        Ack::Ready(line) => line,
+        Ack::Awaiting { pending, quantity } => match pending.receiver().recv_deadline(deadline) {
+            Ok(outcome) => outcome_ack(&outcome, quantity),
+            Err(_) => ACCEPTED_ACK.to_string(),
+        },
+        Ack::Batch(acks) => format!("[{}]", acks.into_iter().map(|ack| resolve(ack, deadline)).collect::<Vec<_>>().join(",")),
+    }
+}
+
+fn outcome_ack(outcome: &OrderOutcome, quantity: u64) -> String {
+    match outcome {
+        OrderOutcome::Executed { executions, rested_quantity } => executed_ack(executions, quantity, *rested_quantity).to_string(),
+        OrderOutcome::Rejected(error) => error_ack(error),
+        // Only HTTP sends cancels and commands; the gateway never waits on one
+        OrderOutcome::Cancelled(_) | OrderOutcome::TradingStateSet(_) => ACCEPTED_ACK.to_string(),
+    }
+}
+
+/// The JSON reader's buffer, kept across reads
+#[derive(Default)]
+struct LineState {
+    line: Vec<u8>,
+    /// The last read stopped without a newline, so the peer hung up mid-line
+    partial: bool,
+}
+
+/// Reads one line of at most `MAX_LINE_LEN` bytes
+fn read_json_line(reader: &mut BufReader<TcpStream>, state: &mut LineState, connection: &ConnectionGuard) -> Inbound {
+    let line = &mut state.line;
+    line.clear();
+    match reader.by_ref().take(MAX_LINE_LEN as u64 + 1).read_until(b'\n', line) {
+        Ok(0) if state.partial => return Inbound::Closed(Disconnect::MidMessage),
+        Ok(0) => return Inbound::Closed(Disconnect::Clean),
+        Err(_) => return Inbound::Closed(Disconnect::ReadError),
+        Ok(bytes) => connection.record_bytes(bytes as u64),
+    }
+    state.partial = !line.ends_with(b"\n");
+    if state.partial && line.len() > MAX_LINE_LEN {
+        let (skipped, terminated) = skip_line(reader);
+        connection.record_bytes(skipped);
+        state.partial = !terminated;
+        let detail = format!("line exceeds {} bytes", MAX_LINE_LEN);
+        return Inbound::Malformed(EntryError::new(RejectReason::Malformed, detail));
+    }
+    if line.trim_ascii().is_empty() {
+        return Inbound::Blank;
     }
+    if line.trim_ascii_start().starts_with(b"[") {
+        return match serde_json::from_slice::<Vec<serde_json::Value>>(line) {
+            Ok(items) => Inbound::Batch(items.into_iter().map(|item| {
+                serde_json::from_value::<Order>(item).map_err(|e| EntryError::new(RejectReason::Malformed, e.to_string()))
+            }).collect()),
+            Err(e) => Inbound::Malformed(EntryError::new(RejectReason::Malformed, e.to_string())),
+        };
+    }
+    match serde_json::from_slice::<Order>(line) {
+        Ok(order) => Inbound::Order(order),
+        Err(e) => Inbound::Malformed(EntryError::new(RejectReason::Malformed, e.to_string())),
+    }
+}
+
+/// Consumes the rest of the current line, newline included, without
+/// buffering it; returns the bytes skipped and whether a newline was found
+fn skip_line(reader: &mut BufReader<TcpStream>) -> (u64, bool) {
+    let mut skipped = 0;
+    loop {
+        let (used, done) = match reader.fill_buf() {
+            Ok([]) | Err(_) => return (skipped, false),
+            Ok(buffer) => match buffer.iter().position(|&byte| byte == b'\n') {
+                Some(newline) => (newline + 1, true),
+                None => (buffer.len(), false),
+            },
+        };
+        reader.consume(used);
+        skipped += used as u64;
+        if done {
+            return (skipped, true);
+        }
+    }
+}
+
+/// How a failed `read_exact` ended the connection
+fn read_failure(error: std::io::Error) -> Inbound {
+    match error.kind() {
+        std::io::ErrorKind::UnexpectedEof => Inbound::Closed(Disconnect::MidMessage),
+        _ => Inbound::Closed(Disconnect::ReadError),
+    }
+}
+
+fn read_frame(reader: &mut BufReader<TcpStream>, connection: &ConnectionGuard) -> Inbound {
+    match reader.fill_buf() {
+        Ok([]) => return Inbound::Closed(Disconnect::Clean),
+        Err(_) => return Inbound::Closed(Disconnect::ReadError),
+        Ok(_) => {}
+    }
+    let mut header = [0u8; 4];
+    if let Err(error) = reader.read_exact(&mut header) {
+        return read_failure(error);
+    }
+    let len = u32::from_le_bytes(header) as usize;
+    if len > MAX_FRAME_LEN {
+        let detail = format!("frame of {} bytes exceeds {}", len, MAX_FRAME_LEN);
+        return Inbound::Corrupt(EntryError::new(RejectReason::Malformed, detail));
+    }
+    let mut payload = [0u8; MAX_FRAME_LEN];
+    if let Err(error) = reader.read_exact(&mut payload[..len]) {
+        return read_failure(error);
+    }
+    connection.record_bytes(4 + len as u64);
+    match Order::from_bytes(&payload[..len]) {
+        Ok(order) => Inbound::Order(order),
+        Err(error) => Inbound::Malformed(error),
+    }
+}
+
+/// Whether `buffer` already holds a whole frame, so reading it cannot block
+fn has_buffered_frame(buffer: &[u8]) -> bool {
+    match buffer.get(..4) {
+        Some(header) => buffer.len() >= 4 + u32::from_le_bytes(header.try_into().expect("4-byte header")) as usize,
+        None => false,
+    }
+}
+
+/// Acks a refused order with its typed reason
+fn write_error(acks: &mut Vec<Ack>, error: &EntryError) {
+    acks.push(Ack::Ready(error_ack(error)));
+}
+
+fn error_ack(error: &EntryError) -> String {
+    json!({"status": error.reason.ack_status(), "reason": error.reason, "detail": error.detail}).to_string()
 }
@@ -0,0 +1,209 @@
+// ============================================================================
+// LATENCY HISTOGRAM - Lock-free fixed-bucket histogram
+// ============================================================================
+// Recorded from the engine thread, read by the HTTP server. Buckets hold
+// non-cumulative counts; the Prometheus exposition accumulates them.
+
+use std::fmt::Write;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// Upper bounds in nanoseconds, 100ns to 10ms: the range that matters for
+/// an in-process matching engine.
+pub const DEFAULT_LATENCY_BUCKETS_NS: &[u64] = &[
+    100, 250, 500,
+    1_000, 2_500, 5_000,
+    10_000, 25_000, 50_000,
+    100_000, 250_000, 500_000,
+    1_000_000, 2_500_000, 5_000_000, 10_000_000,
+];
+
+/// Bounds from `min_ns` to `max_ns` at 1, 1.2, 1.5, 2, 2.5, 3, 4, 5, 6 and
+/// 8 per decade, so a percentile read from a bucket is off by at most ~25%.
+pub fn fine_buckets_ns(min_ns: u64, max_ns: u64) -> Vec<u64> {
+    const STEPS: [u64; 10] = [10, 12, 15, 20, 25, 30, 40, 50, 60, 80];
+    let mut bounds = Vec::new();
+    let mut decade = 1u64;
+    while decade <= max_ns {
+        bounds.extend(STEPS.iter().map(|step| step * decade / 10).filter(|b| (min_ns..=max_ns).contains(b)));
+        decade *= 10;
+    }
+    bounds
+}
+
+pub struct LatencyHistogram {
+    bounds_ns: Vec<u64>,
+    /// One slot per bound plus a final +Inf slot
+    counts: Vec<AtomicU64>,
+    sum_ns: AtomicU64,
+    count: AtomicU64,
+    max_ns: AtomicU64,
+}
+
+impl LatencyHistogram {
+    /// `bounds_ns` are sorted and deduplicated.
+    pub fn new(bounds_ns: &[u64]) -> Self {
+        let mut bounds_ns = bounds_ns.to_vec();
+        bounds_ns.sort_unstable();
+        bounds_ns.dedup();
+        let counts = (0..=bounds_ns.len()).map(|_| AtomicU64::new(0)).collect();
+        LatencyHistogram { bounds_ns, counts, sum_ns: AtomicU64::new(0), count: AtomicU64::new(0), max_ns: AtomicU64::new(0) }
+    }
+
+    pub fn record(&self, latency_ns: u64) {
+        let bucket = self.bounds_ns.partition_point(|&bound| bound < latency_ns);
+        self.counts[bucket].fetch_add(1, Ordering::Relaxed);
+        self.sum_ns.fetch_add(latency_ns, Ordering::Relaxed);
+        self.count.fetch_add(1, Ordering::Relaxed);
+        self.max_ns.fetch_max(latency_ns, Ordering::Relaxed);
+    }
+
+    pub fn count(&self) -> u64 {
+        self.count.load(Ordering::Relaxed)
+    }
+
+    pub fn sum_ns(&self) -> u64 {
+        self.sum_ns.load(Ordering::Relaxed)
+    }
+
+    pub fn max_ns(&self) -> u64 {
+        self.max_ns.load(Ordering::Relaxed)
+    }
+
+    /// Upper bound of the bucket holding the `quantile` (0.0..=1.0) sample,
+    /// capped at the largest value seen; `None` until something is recorded.
+    pub fn percentile(&self, quantile: f64) -> Option<u64> {
+        let buckets = self.cumulative_buckets();
+        let total = buckets.last().map_or(0, |&(_, cumulative)| cumulative);
+        if total == 0 {
+            return None;
+        }
+        let rank = ((quantile * total as f64).ceil() as u64).clamp(1, total);
+        let max = self.max_ns();
+        buckets
+            .iter()
+            .find(|&&(_, cumulative)| cumulative >= rank)
+            .map(|&(bound, _)| bound.map_or(max, |bound| bound.min(max)))
+    }
+
+    pub fn bounds_ns(&self) -> &[u64] {
+        &self.bounds_ns
+    }
+
+    /// `(upper_bound_ns, cumulative_count)` per bucket; `None` is +Inf.
+    pub fn cumulative_buckets(&self) -> Vec<(Option<u64>, u64)> {
+        let mut running = 0;
+        self.counts
+            .iter()
+            .enumerate()
+            .map(|(i, count)| {
+                running += count.load(Ordering::Relaxed);
+                (self.bounds_ns.get(i).copied(), running)
+            })
+            .collect()
+    }
+
+    /// Prometheus text exposition, in seconds as Prometheus convention asks.
+    pub fn render_prometheus(&self, name: &str, help: &str) -> String {
+        let mut out = String::new();
+        let _ = writeln!(out, "# HELP {} {}", name, help);
+        let _ = writeln!(out, "# TYPE {} histogram", name);
+        let buckets = self.cumulative_buckets();
+        for (bound, cumulative) in &buckets {
+            let le = match bound {
+                Some(ns) => format!("{}", *ns as f64 / 1e9),
+                None => "+Inf".to_string(),
+            };
+            let _ = writeln!(out, "{}_bucket{{le=\"{}\"}} {}", name, le, cumulative);
+        }
+        // Count comes from the +Inf bucket so the exposition is self-consistent
+        // even while the engine records concurrently
+        let total = buckets.last().map_or(0, |&(_, cumulative)| cumulative);
+        let _ = writeln!(out, "{}_sum {}", name, self.sum_ns() as f64 / 1e9);
+        let _ = writeln!(out, "{}_count {}", name, total);
+        out
+    }
+}
+
+impl Default for LatencyHistogram {
+    fn default() -> Self {
+        Self::new(DEFAULT_LATENCY_BUCKETS_NS)
+    }
+}
+
+// ============================================================================
+// LATENCY SAMPLES - Every measurement, for exact percentiles
+// ============================================================================
+
+/// Rank of `quantile` (0.0..=1.0) in `sorted_ns`, by nearest rank like
+/// `LatencyHistogram::percentile`; `None` if there are no samples.
+pub fn percentile_of_sorted(sorted_ns: &[u64], quantile: f64) -> Option<u64> {
+    if sorted_ns.is_empty() {
+        return None;
+    }
+    let rank = ((quantile * sorted_ns.len() as f64).ceil() as usize).clamp(1, sorted_ns.len());
+    Some(sorted_ns[rank - 1])
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LatencySummary {
+    pub count: usize,
+    pub min_ns: u64,
+    pub p50_ns: u64,
+    pub p99_ns: u64,
+    pub p999_ns: u64,
+    pub max_ns: u64,
+}
+
+/// Raw samples in recording order. The buffer is sized up front so
+/// `record` never allocates inside a timed loop; samples past the capacity
+/// are counted as dropped rather than grown into.
+pub struct LatencySamples {
+    samples_ns: Vec<u64>,
+    dropped: u64,
+}
+
+impl LatencySamples {
+    pub fn with_capacity(capacity: usize) -> Self {
+        LatencySamples { samples_ns: Vec::with_capacity(capacity), dropped: 0 }
+    }
+
+    #[inline]
+    pub fn record(&mut self, latency_ns: u64) {
+        if self.samples_ns.len() < self.samples_ns.capacity() {
+            self.samples_ns.push(latency_ns);
+        } else {
+            self.dropped += 1;
+        }
+    }
+
+    pub fn samples_ns(&self) -> &[u64] {
+        &self.samples_ns
+    }
+
+    pub fn dropped(&self) -> u64 {
+        self.dropped
+    }
+
+    /// Sorts a copy, so the recorded order survives for `write_csv`.
+    pub fn summary(&self) -> Option<LatencySummary> {
+        let mut sorted = self.samples_ns.clone();
+        sorted.sort_unstable();
+        Some(LatencySummary {
+            count: sorted.len(),
+            min_ns: *sorted.first()?,
+            p50_ns: percentile_of_sorted(&sorted, 0.50)?,
+            p99_ns: percentile_of_sorted(&sorted, 0.99)?,
+            p999_ns: percentile_of_sorted(&sorted, 0.999)?,
+            max_ns: *sorted.last()?,
+        })
+    }
+
+    /// `sample,latency_ns` rows in recording order.
+    pub fn write_csv(&self, out: &mut impl std::io::Write) -> std::io::Result<()> {
+        writeln!(out, "sample,latency_ns")?;
+        for (i, ns) in self.samples_ns.iter().enumerate() {
+            writeln!(out, "{},{}", i, ns)?;
+        }
+        Ok(())
+    }
+}
@@ -1,179 +1,718 @@
 use tiny_http::{Server, Request, Response, Header, Method};
-use std::sync::{Arc, Mutex};
+use std::io::{BufWriter, Cursor, Write};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard};
 use std::thread;
+use std::time::Duration;
 use std::fs;
-use crate::matching_engine::OrderBook;
+use std::net::SocketAddr;
+use crate::events::{BookEvent, BusMessage};
+use crate::funnel::SubmitError;
+use crate::gateway::RESULT_TIMEOUT;
+use crate::matching_engine::{write_ndjson, BookAction, OrderBook, OrderSide, Packet, TradingState};
+use crate::metrics::{render_book_prometheus, Metrics, ENGINE_STALL_THRESHOLD};
+use crate::order_results::{executed_ack, OrderOutcome};
+use crate::price_units::{order_from_json, scale_price, Price};
+use crate::rejections::{EntryError, RejectReason};
+use crate::replica::{Replica, StaleAction};
+use crate::signal_store::{AiDecision, CryptoDecision, SignalStore};
+use crate::websocket::{accept_key, stream_depth};
 use serde_json::json;
-use lazy_static::lazy_static;
+use crossbeam_channel::RecvTimeoutError;
+use log::{error, info, warn};
 
-lazy_static! {
-    static ref AI_DECISION: Mutex<String> = Mutex::new(
-        r#"{"signal": "NEUTRAL", "reasoning": "Waiting for AI analysis..."}"#.to_string()
-    );
-    static ref CRYPTO_DECISION: Mutex<String> = Mutex::new(
-        r#"{"signals": {"btc": "HOLD", "eth": "HOLD", "sol": "HOLD"}, "reasoning": "Waiting for crypto analysis..."}"#.to_string()
-    );
+/// Bind address used when none is configured
+pub const DEFAULT_HTTP_ADDR: &str = "0.0.0.0:8082";
+
+/// Levels per side in `/api/depth-curve` and `/api/depth-levels` unless `levels` says otherwise
+const DEFAULT_DEPTH_CURVE_LEVELS: usize = 50;
+
+/// Levels per side in `/api/depth` unless `levels` says otherwise
+const DEFAULT_DEPTH_LEVELS: usize = 10;
+
+/// Levels per side in `/api/orderbook/all` unless `depth` says otherwise
+const DEFAULT_SNAPSHOT_ALL_DEPTH: usize = 10;
+
+/// Sampled rejections returned by `/api/rejections` unless `limit` says otherwise
+const DEFAULT_REJECTIONS_LIMIT: usize = 100;
+
+/// Trades returned by `/api/trades` unless `n` says otherwise
+const DEFAULT_TRADES_LIMIT: usize = 50;
+
+/// Samples returned by `/api/spread-history` unless `limit` says otherwise
+const DEFAULT_SPREAD_HISTORY_LIMIT: usize = 100;
+
+/// Trades averaged by `/api/vwap` unless `n` says otherwise
+const DEFAULT_VWAP_TRADES: usize = 100;
+
+/// Levels per side behind `/api/signals` imbalance unless `levels` says otherwise
+const DEFAULT_SIGNAL_LEVELS: usize = 5;
+
+/// WebSocket endpoint pushing a depth snapshot, then level diffs
+pub const DEPTH_STREAM_PATH: &str = "/ws/depth";
+
+/// Server-Sent Events stream with one `data:` event per execution
+pub const TRADE_STREAM_PATH: &str = "/api/stream/trades";
+
+/// Every resting order as NDJSON, for bulk export into data tools
+pub const EXPORT_PATH: &str = "/api/export";
+
+/// Quiet time after which the trade stream sends a comment line, so a
+/// client that has gone away is noticed and unsubscribed
+const TRADE_STREAM_KEEPALIVE: Duration = Duration::from_secs(15);
+
+/// `DELETE` on this prefix plus an order id cancels that order
+const ORDER_PATH_PREFIX: &str = "/api/order/";
+
+/// How long `recv_timeout` blocks before re-checking the shutdown flag
+const RECV_POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+/// Binds the HTTP server and returns it with the address actually bound,
+/// which differs from `addr` when asking for port 0.
+pub fn bind_http_server(addr: &str) -> Result<(Server, SocketAddr), Box<dyn std::error::Error + Send + Sync>> {
+    let server = Server::http(addr)?;
+    let local_addr = server.server_addr().to_ip().ok_or("HTTP server is not bound to an IP address")?;
+    Ok((server, local_addr))
 }
 
-pub fn start_http_server(order_book: Arc<Mutex<OrderBook>>) -> Result<(), Box<dyn std::error::Error>> {
-    let server = Server::http("0.0.0.0:8082").unwrap();
-    println!("🌐 [HTTP] Server listening on http://0.0.0.0:8082");
+/// `replica`, when given, serves `/api/replica/*` reads; `signals` holds
+/// the traders' posted decisions.
+pub fn start_http_server(
+    server: Server,
+    order_book: Arc<Mutex<OrderBook>>,
+    signals: Arc<SignalStore>,
+    metrics: Arc<Metrics>,
+    replica: Option<Arc<Replica>>,
+    shutdown: Arc<AtomicBool>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    info!("🌐 [HTTP] Server listening on http://{}", server.server_addr());
 
-    for request in server.incoming_requests() {
-        let order_book = order_book.clone();
-        thread::spawn(move || {
-            handle_request(request, order_book);
-        });
+    let mut next_worker = 0u64;
+    while !shutdown.load(Ordering::Relaxed) {
+        if let Some(request) = server.recv_timeout(RECV_POLL_INTERVAL)? {
+            let order_book = order_book.clone();
+            let signals = signals.clone();
+            let metrics = metrics.clone();
+            let replica = replica.clone();
+            next_worker += 1;
+            let spawned = thread::Builder::new()
+                .name(format!("http-worker-{}", next_worker))
+                .spawn(move || {
+                    handle_request(request, order_book, signals, metrics, replica);
+                });
+            if let Err(e) = spawned {
+                error!("❌ [HTTP] Could not start a worker thread: {}", e);
+            }
+        }
     }
 
     Ok(())
 }
 
-fn handle_request(mut request: Request, order_book: Arc<Mutex<OrderBook>>) {
+// ============================================================================
+// RESPONSES AND ERRORS
+// ============================================================================
+
+type HttpResponse = Response<Cursor<Vec<u8>>>;
+
+/// Everything a handler can fail with. Client errors are the caller's fault
+/// (4xx); `Internal` is ours (500) and gets logged and counted.
+#[derive(Debug)]
+enum HttpError {
+    BadRequest(String),
+    NotFound(String),
+    Internal(String),
+    /// Temporarily unable to serve, e.g. a replica that is too stale (503)
+    Unavailable(String),
+    /// A mutating request without a valid API key (401)
+    Unauthorized(String),
+    /// Order entry refused the order (400, or 409 for a duplicate id, with the typed reason)
+    Rejected(EntryError),
+}
+
+impl HttpError {
+    fn status(&self) -> u16 {
+        match self {
+            HttpError::Rejected(error) if error.reason == RejectReason::Duplicate => 409,
+            // The order was fine; the book just could not fill it in full
+            HttpError::Rejected(error) if error.reason == RejectReason::Killed => 200,
+            HttpError::BadRequest(_) | HttpError::Rejected(_) => 400,
+            HttpError::Unauthorized(_) => 401,
+            HttpError::NotFound(_) => 404,
+            HttpError::Internal(_) => 500,
+            HttpError::Unavailable(_) => 503,
+        }
+    }
+
+    fn into_response(self) -> HttpResponse {
+        let status = self.status();
+        let reason = match self {
+            HttpError::BadRequest(reason)
+            | HttpError::NotFound(reason)
+            | HttpError::Internal(reason)
+            | HttpError::Unavailable(reason)
+            | HttpError::Unauthorized(reason) => reason,
+            HttpError::Rejected(error) => {
+                return json_response(status, &json!({"status": error.reason.ack_status(), "reason": error.reason, "detail": error.detail}));
+            }
+        };
+        json_response(status, &json!({"status": "error", "reason": reason}))
+    }
+}
+
+fn header(name: &str, value: &str) -> Header {
+    Header::from_bytes(name.as_bytes(), value.as_bytes()).expect("static header is valid")
+}
+
+fn json_response(status: u16, body: &serde_json::Value) -> HttpResponse {
+    Response::from_string(body.to_string())
+        .with_status_code(status)
+        .with_header(header("Content-Type", "application/json"))
+        .with_header(header("Access-Control-Allow-Origin", "*"))
+}
+
+fn raw_json_response(body: String) -> HttpResponse {
+    Response::from_string(body)
+        .with_header(header("Content-Type", "application/json"))
+        .with_header(header("Access-Control-Allow-Origin", "*"))
+}
+
+/// Locks shared state, turning a poisoned mutex into a 500 instead of a
+/// panicking worker thread and a hung client.
+fn lock<'a, T>(mutex: &'a Mutex<T>, what: &str) -> Result<MutexGuard<'a, T>, HttpError> {
+    mutex.lock().map_err(|_| HttpError::Internal(format!("{} is unavailable", what)))
+}
+
+/// Value of `name` in the URL's query string, if present
+fn query_param<'a>(url: &'a str, name: &str) -> Option<&'a str> {
+    let (_, query) = url.split_once('?')?;
+    query.split('&')
+        .filter_map(|pair| pair.split_once('='))
+        .find(|(key, _)| *key == name)
+        .map(|(_, value)| value)
+}
+
+/// Runs `f` on the book registered for `symbol`, or on the default book
+/// when no symbol is given.
+fn with_book<R>(
+    order_book: &Mutex<OrderBook>,
+    metrics: &Metrics,
+    symbol: Option<&str>,
+    f: impl FnOnce(&mut OrderBook) -> Result<R, HttpError>,
+) -> Result<R, HttpError> {
+    match symbol {
+        Some(symbol) => {
+            let book = metrics.symbol_book(symbol)
+                .ok_or_else(|| HttpError::NotFound(format!("no book for symbol {}", symbol)))?;
+            let mut book = lock(&book, "order book")?;
+            f(&mut book)
+        }
+        None => f(&mut *lock(order_book, "order book")?),
+    }
+}
+
+/// Hands `packet` to the engine through the order-entry funnels and waits
+/// for its outcome, so HTTP changes are journaled, replicated and published
+/// like gateway orders.
+fn through_engine(metrics: &Metrics, packet: Packet) -> Result<OrderOutcome, HttpError> {
+    let routes = metrics.order_entry()
+        .ok_or_else(|| HttpError::Unavailable("order entry is not running".to_string()))?;
+    let results = routes.results()
+        .ok_or_else(|| HttpError::Unavailable("engine outcomes are not reported".to_string()))?;
+    let funnel = routes.funnel_for(packet.order.symbol.as_deref()).ok_or_else(|| {
+        HttpError::NotFound(format!("no book for symbol {}", packet.order.symbol.as_deref().unwrap_or_default()))
+    })?;
+    // Registered before submitting so the engine cannot answer first
+    let pending = results.register();
+    let packet = Packet { ticket: Some(pending.ticket()), ..packet };
+    if let Err(error) = funnel.submit(packet) {
+        results.forget(pending);
+        return Err(HttpError::Unavailable(match error {
+            SubmitError::Backpressure(_) => "order entry is full, try again".to_string(),
+            SubmitError::Closed(_) => "shutting down".to_string(),
+        }));
+    }
+    pending.receiver().recv_timeout(RESULT_TIMEOUT).map_err(|_| {
+        HttpError::Unavailable(format!("no answer from the engine within {:?}; it may still apply", RESULT_TIMEOUT))
+    })
+}
+
+/// An outcome that does not belong to the packet that was sent
+fn unexpected(outcome: OrderOutcome) -> HttpError {
+    HttpError::Internal(format!("unexpected engine outcome {:?}", outcome))
+}
+
+/// Parses an optional numeric query parameter, 400 if it is malformed
+fn numeric_param(url: &str, name: &str) -> Result<Option<u64>, HttpError> {
+    query_param(url, name)
+        .map(|value| value.parse().map_err(|_| HttpError::BadRequest(format!("{} must be a number, got {:?}", name, value))))
+        .transpose()
+}
+
+fn read_body(request: &mut Request) -> Result<String, HttpError> {
+    let mut content = String::new();
+    request.as_reader().read_to_string(&mut content)
+        .map_err(|e| HttpError::BadRequest(format!("could not read request body: {}", e)))?;
+    Ok(content)
+}
+
+// ============================================================================
+// ROUTING
+// ============================================================================
+
+fn handle_request(
+    mut request: Request,
+    order_book: Arc<Mutex<OrderBook>>,
+    signals: Arc<SignalStore>,
+    metrics: Arc<Metrics>,
+    replica: Option<Arc<Replica>>,
+) {
+    let method = request.method().clone();
+    let url = request.url().to_string();
+
+    // Takes over the connection (and this worker) for as long as the client stays
+    if method == Method::Get && url.split('?').next() == Some(DEPTH_STREAM_PATH) {
+        serve_depth_stream(request, &order_book, &metrics);
+        return;
+    }
+    if method == Method::Get && url.split('?').next() == Some(TRADE_STREAM_PATH) {
+        serve_trade_stream(request, &metrics);
+        return;
+    }
+    if method == Method::Get && url.split('?').next() == Some(EXPORT_PATH) {
+        serve_export(request, &order_book, &metrics);
+        return;
+    }
+
+    let response = match route(&mut request, &order_book, &signals, &metrics, replica.as_deref()) {
+        Ok(response) => response,
+        Err(error) => {
+            match &error {
+                HttpError::Internal(reason) => {
+                    metrics.record_http_server_error();
+                    error!("❌ [HTTP] {} {} failed: {}", method, url, reason);
+                }
+                _ => metrics.record_http_client_error(),
+            }
+            error.into_response()
+        }
+    };
+
+    if let Err(e) = request.respond(response) {
+        metrics.record_http_respond_failure();
+        warn!("❌ [HTTP] Could not send response for {} {}: {}", method, url, e);
+    }
+}
+
+/// Completes the WebSocket handshake and streams depth until the client
+/// goes away. Anything but a WebSocket upgrade gets a 400.
+fn serve_depth_stream(request: Request, order_book: &Mutex<OrderBook>, metrics: &Metrics) {
+    let header_value = |name: &'static str| {
+        request.headers().iter().find(|h| h.field.equiv(name)).map(|h| h.value.as_str().to_string())
+    };
+    let upgrade = header_value("Upgrade").is_some_and(|value| value.eq_ignore_ascii_case("websocket"));
+    let Some(key) = header_value("Sec-WebSocket-Key").filter(|_| upgrade) else {
+        metrics.record_http_client_error();
+        let _ = request.respond(HttpError::BadRequest("expected a WebSocket upgrade".to_string()).into_response());
+        return;
+    };
+    let response = Response::empty(101).with_header(header("Sec-WebSocket-Accept", &accept_key(&key)));
+    let stream = request.upgrade("websocket", response);
+    stream_depth(stream, order_book);
+}
+
+/// Holds the response open and writes each trade as an SSE event, its bus
+/// sequence as the event id. Returning drops the subscription.
+fn serve_trade_stream(request: Request, metrics: &Metrics) {
+    let Some(bus) = metrics.event_bus() else {
+        metrics.record_http_client_error();
+        let _ = request.respond(HttpError::NotFound("trade stream is not enabled".to_string()).into_response());
+        return;
+    };
+    let trades = bus.subscribe();
+    // Written by hand: tiny_http would buffer a streamed body
+    let mut writer = request.into_writer();
+    let head = "HTTP/1.1 200 OK\r\nContent-Type: text/event-stream\r\nCache-Control: no-cache\r\nConnection: close\r\n\r\n";
+    if writer.write_all(head.as_bytes()).and_then(|_| writer.flush()).is_err() {
+        return;
+    }
+    loop {
+        let event = match trades.recv_timeout(TRADE_STREAM_KEEPALIVE) {
+            Ok(BusMessage { sequence, event: BookEvent::Trade(trade) }) => {
+                format!("id: {}\ndata: {}\n\n", sequence, json!(trade))
+            }
+            Ok(_) => continue,
+            Err(RecvTimeoutError::Timeout) => ": keep-alive\n\n".to_string(),
+            Err(RecvTimeoutError::Disconnected) => return,
+        };
+        if writer.write_all(event.as_bytes()).and_then(|_| writer.flush()).is_err() {
+            return;
+        }
+    }
+}
+
+/// Streams the resting orders of the book named by `symbol` (default book
+/// otherwise) as NDJSON. The book is locked only to copy its orders out;
+/// the JSON is written line by line as the client reads it.
+fn serve_export(request: Request, order_book: &Mutex<OrderBook>, metrics: &Metrics) {
+    let url = request.url().to_string();
+    let orders = match with_book(order_book, metrics, query_param(&url, "symbol"), |book| Ok(book.resting_orders())) {
+        Ok(orders) => orders,
+        Err(error) => {
+            metrics.record_http_client_error();
+            let _ = request.respond(error.into_response());
+            return;
+        }
+    };
+    // Written by hand, like the trade stream, so the body is never buffered
+    let mut writer = BufWriter::new(request.into_writer());
+    let head = "HTTP/1.1 200 OK\r\nContent-Type: application/x-ndjson\r\nAccess-Control-Allow-Origin: *\r\nConnection: close\r\n\r\n";
+    if let Err(e) = writer.write_all(head.as_bytes()).and_then(|_| write_ndjson(&orders, &mut writer)) {
+        metrics.record_http_respond_failure();
+        warn!("❌ [HTTP] Export of {} orders failed: {}", orders.len(), e);
+    }
+}
+
+/// POST and DELETE need one of the configured API keys; other methods pass
+fn authorize(request: &Request, metrics: &Metrics) -> Result<(), HttpError> {
+    if !matches!(request.method(), Method::Post | Method::Delete) {
+        return Ok(());
+    }
+    let header_value = |name: &'static str| {
+        request.headers().iter().find(|h| h.field.equiv(name)).map(|h| h.value.as_str())
+    };
+    if metrics.api_keys().authorize(header_value("X-API-Key"), header_value("Authorization")) {
+        Ok(())
+    } else {
+        Err(HttpError::Unauthorized("missing or invalid API key".to_string()))
+    }
+}
+
+fn route(
+    request: &mut Request,
+    order_book: &Mutex<OrderBook>,
+    signals: &SignalStore,
+    metrics: &Metrics,
+    replica: Option<&Replica>,
+) -> Result<HttpResponse, HttpError> {
     let url = request.url().to_string();
-    
-    match (request.method(), url.as_str()) {
+    let path = url.split('?').next().unwrap_or("");
+    authorize(request, metrics)?;
+
+    match (request.method(), path) {
         (Method::Get, "/") | (Method::Get, "/index.html") => {
-            serve_file(request, "web/index.html", "text/html");
+            serve_file("web/index.html", "text/html")
         }
         
         (Method::Get, "/app.js") => {
-            serve_file(request, "web/app.js", "application/javascript");
+            serve_file("web/app.js", "application/javascript")
         }
         
         (Method::Get, "/styles.css") => {
-            serve_file(request, "web/styles.css", "text/css");
+            serve_file("web/styles.css", "text/css")
+        }
+        
+        // Liveness: answering at all is the signal
+        (Method::Get, "/healthz") => {
+            Ok(json_response(200, &json!({"status": "ok"})))
+        }
+        
+        // Readiness: an engine is draining the ring and has not stalled
+        (Method::Get, "/readyz") => {
+            match metrics.engine_heartbeat_age() {
+                Some(age) if age <= ENGINE_STALL_THRESHOLD => {
+                    Ok(json_response(200, &json!({"status": "ready", "heartbeat_age_ms": age.as_millis() as u64})))
+                }
+                Some(age) => Err(HttpError::Unavailable(format!("engine heartbeat is {}ms old", age.as_millis()))),
+                None => Err(HttpError::Unavailable("engine has not started".to_string())),
+            }
         }
         
         (Method::Get, "/api/orderbook") => {
-            let book = order_book.lock().unwrap();
-            let json_data = book.to_json();
-            
-            let response = Response::from_string(json_data)
-                .with_header(Header::from_bytes(&b"Content-Type"[..], &b"application/json"[..]).unwrap())
-                .with_header(Header::from_bytes(&b"Access-Control-Allow-Origin"[..], &b"*"[..]).unwrap());
-            
-            let _ = request.respond(response);
+            with_book(order_book, metrics, query_param(&url, "symbol"), |book| Ok(raw_json_response(book.to_json())))
+        }
+        
+        (Method::Get, "/api/top-of-book") => {
+            let book = lock(order_book, "order book")?;
+            Ok(json_response(200, &json!({
+                "best_bid": book.best_bid(),
+                "best_ask": book.best_ask(),
+                "spread": book.spread()
+            })))
+        }
+        
+        (Method::Get, "/api/orderbook/all") => {
+            let depth = numeric_param(&url, "depth")?.unwrap_or(DEFAULT_SNAPSHOT_ALL_DEPTH as u64) as usize;
+            Ok(json_response(200, &json!(metrics.snapshot_all_symbols(depth))))
         }
         
         (Method::Post, "/api/order") => {
-            // Read request body
-            let mut content = String::new();
-            if let Err(e) = request.as_reader().read_to_string(&mut content) {
-                let response = Response::from_string(format!("{{\"status\":\"error\",\"reason\":\"{}\"}}",  e))
-                    .with_header(Header::from_bytes(&b"Content-Type"[..], &b"application/json"[..]).unwrap());
-                let _ = request.respond(response);
-                return;
-            }
-            
-            match serde_json::from_str::<crate::matching_engine::Order>(&content) {
-                Ok(order) => {
-                    let mut book = order_book.lock().unwrap();
-                    let _executions = book.add_limit_order(order);
-                    
-                    let response = Response::from_string("{\"status\":\"accepted\"}")
-                        .with_header(Header::from_bytes(&b"Content-Type"[..], &b"application/json"[..]).unwrap())
-                        .with_header(Header::from_bytes(&b"Access-Control-Allow-Origin"[..], &b"*"[..]).unwrap());
-                    
-                    let _ = request.respond(response);
+            let content = read_body(request)?;
+            let symbol: Option<String> = serde_json::from_str::<serde_json::Value>(&content).ok()
+                .and_then(|body| body.get("symbol")?.as_str().map(str::to_string));
+            let decimals = with_book(order_book, metrics, symbol.as_deref(), |book| Ok(book.price_decimals()))?;
+            let order = order_from_json(&content, decimals).map_err(HttpError::BadRequest)?;
+            // Malformed orders never reach the ring, as on the gateway
+            order.check_fields().map_err(HttpError::Rejected)?;
+            let quantity = order.quantity;
+            match through_engine(metrics, Packet::new(order))? {
+                OrderOutcome::Executed { executions, rested_quantity } => {
+                    Ok(json_response(200, &executed_ack(&executions, quantity, rested_quantity)))
                 }
-                Err(e) => {
-                    let response = Response::from_string(format!("{{\"status\":\"error\",\"reason\":\"{}\"}}",  e))
-                        .with_header(Header::from_bytes(&b"Content-Type"[..], &b"application/json"[..]).unwrap())
-                        .with_header(Header::from_bytes(&b"Access-Control-Allow-Origin"[..], &b"*"[..]).unwrap());
-                    let _ = request.respond(response);
+                OrderOutcome::Rejected(error) => Err(HttpError::Rejected(error)),
+                other => Err(unexpected(other)),
+            }
+        }
+        
+        (Method::Post, "/api/reference-price") => {
+            let content = read_body(request)?;
+            let body: serde_json::Value = serde_json::from_str(&content)
+                .map_err(|e| HttpError::BadRequest(e.to_string()))?;
+            let decimals = lock(order_book, "order book")?.price_decimals();
+            let price = match body.get("price") {
+                Some(serde_json::Value::Number(number)) => scale_price(&number.to_string(), decimals)
+                    .map_err(|e| HttpError::BadRequest(e.to_string()))?,
+                _ => return Err(HttpError::BadRequest("body needs a numeric price".to_string())),
+            };
+            match through_engine(metrics, Packet::command(BookAction::ReferencePrice(Price(price)), 0, None))? {
+                OrderOutcome::Executed { executions, .. } => {
+                    Ok(json_response(200, &json!({"status": "accepted", "executions": executions})))
                 }
+                other => Err(unexpected(other)),
             }
         }
         
         (Method::Get, "/api/metrics") => {
+            let latency = metrics.match_latency();
             let metrics = json!({
-                "latency": 29,
-                "throughput": 33543877,
-                "uptime": 12345
+                // Mean match latency in nanoseconds
+                "latency": latency.sum_ns().checked_div(latency.count()).unwrap_or(0),
+                "throughput": metrics.orders_per_second().round() as u64,
+                "uptime": metrics.uptime().as_secs(),
+                "orders_processed": metrics.orders_processed(),
+                "trades_executed": metrics.trades_executed(),
+                "price_improvement": metrics.price_improvement_by_account(),
+                "http_client_errors": metrics.http_client_errors(),
+                "http_server_errors": metrics.http_server_errors(),
+                "http_respond_failures": metrics.http_respond_failures(),
+                "ingress": metrics.ingress().totals(),
+                "funnel": {
+                    "in_flight": metrics.funnel().in_flight(),
+                    "rejected": metrics.funnel().rejected()
+                },
+                "shards": metrics.shard_occupancy()
             });
-            
-            let response = Response::from_string(metrics.to_string())
-                .with_header(Header::from_bytes(&b"Content-Type"[..], &b"application/json"[..]).unwrap())
-                .with_header(Header::from_bytes(&b"Access-Control-Allow-Origin"[..], &b"*"[..]).unwrap());
-            
-            let _ = request.respond(response);
+            Ok(json_response(200, &metrics))
+        }
+        
+        (Method::Get, "/api/latency") => {
+            // Gateway ingest to the end of matching, in nanoseconds
+            let histogram = metrics.end_to_end_latency();
+            Ok(json_response(200, &json!({
+                "count": histogram.count(),
+                "mean_ns": histogram.sum_ns().checked_div(histogram.count()),
+                "p50_ns": histogram.percentile(0.50),
+                "p90_ns": histogram.percentile(0.90),
+                "p99_ns": histogram.percentile(0.99),
+                "p999_ns": histogram.percentile(0.999),
+                "max_ns": histogram.max_ns(),
+                // Share of that spent waiting in the funnel and ring buffer
+                "queue": {
+                    "p50_ns": metrics.queue_latency().percentile(0.50),
+                    "p99_ns": metrics.queue_latency().percentile(0.99),
+                    "max_ns": metrics.queue_latency().max_ns()
+                }
+            })))
+        }
+
+        (Method::Get, "/api/connections") => {
+            Ok(json_response(200, &json!({
+                "totals": metrics.ingress().totals(),
+                "connections": metrics.ingress().connections()
+            })))
+        }
+        
+        (Method::Get, "/api/pnl") => {
+            let account = numeric_param(&url, "account")?
+                .ok_or_else(|| HttpError::BadRequest("account is required".to_string()))?;
+            let mark = numeric_param(&url, "mark")?.map(Price);
+            let book = lock(order_book, "order book")?;
+            let report = book.pnl(account, mark)
+                .ok_or_else(|| HttpError::NotFound(format!("account {} has no fills", account)))?;
+            Ok(json_response(200, &json!(report)))
+        }
+        
+        (Method::Get, "/api/depth-curve") => {
+            let levels = numeric_param(&url, "levels")?.unwrap_or(DEFAULT_DEPTH_CURVE_LEVELS as u64) as usize;
+            let book = lock(order_book, "order book")?;
+            match query_param(&url, "side") {
+                None => Ok(json_response(200, &json!(book.depth_curves(levels)))),
+                Some("bid") => Ok(json_response(200, &json!(book.depth_curve(OrderSide::Buy, levels)))),
+                Some("ask") => Ok(json_response(200, &json!(book.depth_curve(OrderSide::Sell, levels)))),
+                Some(other) => Err(HttpError::BadRequest(format!("side must be bid or ask, got {}", other))),
+            }
+        }
+        
+        (Method::Get, "/api/depth") => {
+            let levels = numeric_param(&url, "levels")?.unwrap_or(DEFAULT_DEPTH_LEVELS as u64) as usize;
+            let book = lock(order_book, "order book")?;
+            Ok(json_response(200, &json!(book.depth(levels))))
+        }
+        
+        (Method::Get, "/api/depth-levels") => {
+            let levels = numeric_param(&url, "levels")?.unwrap_or(DEFAULT_DEPTH_CURVE_LEVELS as u64) as usize;
+            let book = lock(order_book, "order book")?;
+            Ok(json_response(200, &json!(book.level_depth(levels))))
+        }
+        
+        (Method::Get, "/api/pricing-inputs") => {
+            let book = lock(order_book, "order book")?;
+            Ok(json_response(200, &json!(book.pricing_inputs())))
+        }
+        
+        (Method::Get, "/api/signals") => {
+            let levels = numeric_param(&url, "levels")?.unwrap_or(DEFAULT_SIGNAL_LEVELS as u64) as usize;
+            let book = lock(order_book, "order book")?;
+            Ok(json_response(200, &json!(book.signals(levels))))
+        }
+        
+        (Method::Get, "/api/rejections") => {
+            let limit = numeric_param(&url, "limit")?.unwrap_or(DEFAULT_REJECTIONS_LIMIT as u64) as usize;
+            let log = metrics.ingress().rejection_log()
+                .ok_or_else(|| HttpError::NotFound("rejection logging is not configured".to_string()))?;
+            Ok(json_response(200, &json!({"counts": log.counts(), "recent": log.recent(limit)})))
+        }
+        
+        (Method::Get, "/api/settlement") => {
+            let book = lock(order_book, "order book")?;
+            let settlement = book.settlement()
+                .ok_or_else(|| HttpError::NotFound("no session has settled yet".to_string()))?;
+            Ok(json_response(200, &json!(settlement)))
+        }
+        
+        (Method::Post, "/api/trading-state") => {
+            let content = read_body(request)?;
+            let body: serde_json::Value = serde_json::from_str(&content)
+                .map_err(|e| HttpError::BadRequest(e.to_string()))?;
+            let state: TradingState = serde_json::from_value(body["state"].clone())
+                .map_err(|_| HttpError::BadRequest("state must be \"open\" or \"closed\"".to_string()))?;
+            match through_engine(metrics, Packet::command(BookAction::TradingState(state), 0, None))? {
+                OrderOutcome::TradingStateSet(settlement) => {
+                    Ok(json_response(200, &json!({"state": state, "settlement": settlement})))
+                }
+                other => Err(unexpected(other)),
+            }
+        }
+        
+        (Method::Get, "/api/ohlcv") => {
+            let book = lock(order_book, "order book")?;
+            Ok(json_response(200, &json!({"last": book.last_trade_price(), "session": book.ohlcv()})))
+        }
+        
+        (Method::Get, "/api/trades") => {
+            let n = numeric_param(&url, "n")?.unwrap_or(DEFAULT_TRADES_LIMIT as u64) as usize;
+            let book = lock(order_book, "order book")?;
+            Ok(json_response(200, &json!(book.recent_trades(n))))
+        }
+        
+        (Method::Get, "/api/vwap") => {
+            let n = numeric_param(&url, "n")?.unwrap_or(DEFAULT_VWAP_TRADES as u64) as usize;
+            let book = lock(order_book, "order book")?;
+            Ok(json_response(200, &json!({"n": n, "vwap": book.vwap(n)})))
+        }
+        
+        (Method::Get, "/api/spread-history") => {
+            let limit = numeric_param(&url, "limit")?.unwrap_or(DEFAULT_SPREAD_HISTORY_LIMIT as u64) as usize;
+            let samples = lock(metrics.spread_history(), "spread history")?.recent(limit);
+            Ok(json_response(200, &json!(samples)))
+        }
+        
+        (Method::Get, "/api/replica/lag") => {
+            let replica = replica.ok_or_else(|| HttpError::NotFound("no read replica configured".to_string()))?;
+            Ok(json_response(200, &json!({
+                "applied_sequence": replica.applied_sequence(),
+                "lag": replica.lag(),
+                "stale": replica.stale_action().is_some()
+            })))
+        }
+        
+        (Method::Get, "/api/replica/orderbook") => {
+            let replica = replica.ok_or_else(|| HttpError::NotFound("no read replica configured".to_string()))?;
+            let lag = replica.lag();
+            match replica.stale_action() {
+                Some(StaleAction::Reject) => Err(HttpError::Unavailable(format!(
+                    "replica is {} sequences / {} ns behind", lag.sequences, lag.ns
+                ))),
+                Some(StaleAction::Warn) => Ok(raw_json_response(replica.book().to_json())
+                    .with_header(header("Warning", &format!("110 - \"replica is {} sequences behind\"", lag.sequences)))),
+                None => Ok(raw_json_response(replica.book().to_json())),
+            }
+        }
+        
+        (Method::Get, "/metrics") => {
+            let mut exposition = metrics.render_prometheus();
+            exposition.push_str(&render_book_prometheus(&*lock(order_book, "order book")?));
+            Ok(Response::from_string(exposition)
+                .with_header(header("Content-Type", "text/plain; version=0.0.4")))
         }
         
         (Method::Get, "/api/ai-decision") => {
             // Return current AI decision state
-            let ai_state = AI_DECISION.lock().unwrap();
-            let response = Response::from_string(ai_state.clone())
-                .with_header(Header::from_bytes(&b"Content-Type"[..], &b"application/json"[..]).unwrap())
-                .with_header(Header::from_bytes(&b"Access-Control-Allow-Origin"[..], &b"*"[..]).unwrap());
-            let _ = request.respond(response);
+            Ok(raw_json_response(signals.ai_decision()))
         }
         
         (Method::Post, "/api/ai-decision") => {
-            // Store AI decision from Python trader
-            let mut content = String::new();
-            if request.as_reader().read_to_string(&mut content).is_ok() {
-                let mut ai_state = AI_DECISION.lock().unwrap();
-                *ai_state = content;
-            }
-            
-            let response = Response::from_string("{\"status\":\"ok\"}")
-                .with_header(Header::from_bytes(&b"Content-Type"[..], &b"application/json"[..]).unwrap())
-                .with_header(Header::from_bytes(&b"Access-Control-Allow-Origin"[..], &b"*"[..]).unwrap());
-            let _ = request.respond(response);
+            // Store AI decision from Python trader, and trade it if the bridge is on
+            let content = read_body(request)?;
+            let decision = AiDecision::parse(&content).map_err(HttpError::BadRequest)?;
+            let mid = match signals.bridge() {
+                Some(_) => lock(order_book, "order book")?.pricing_inputs().mid,
+                None => None,
+            };
+            let order = signals.record_ai_decision(&decision, mid)
+                .map_err(|e| HttpError::Unavailable(format!("decision stored but not traded: {}", e)))?;
+            Ok(json_response(200, &json!({"status": "ok", "order": order})))
         }
         
         (Method::Get, "/api/crypto-decision") => {
             // Return current crypto decision state
-            let crypto_state = CRYPTO_DECISION.lock().unwrap();
-            let response = Response::from_string(crypto_state.clone())
-                .with_header(Header::from_bytes(&b"Content-Type"[..], &b"application/json"[..]).unwrap())
-                .with_header(Header::from_bytes(&b"Access-Control-Allow-Origin"[..], &b"*"[..]).unwrap());
-            let _ = request.respond(response);
+            Ok(raw_json_response(signals.crypto_decision()))
         }
         
         (Method::Post, "/api/crypto-decision") => {
             // Store crypto decision from Python trader
-            let mut content = String::new();
-            if request.as_reader().read_to_string(&mut content).is_ok() {
-                let mut crypto_state = CRYPTO_DECISION.lock().unwrap();
-                *crypto_state = content;
+            let content = read_body(request)?;
+            signals.set_crypto_decision(&CryptoDecision::parse(&content).map_err(HttpError::BadRequest)?);
+            Ok(json_response(200, &json!({"status": "ok"})))
+        }
+        
+        (Method::Delete, _) if path.starts_with(ORDER_PATH_PREFIX) => {
+            let id = &path[ORDER_PATH_PREFIX.len()..];
+            let order_id: u64 = id.parse()
+                .map_err(|_| HttpError::BadRequest(format!("order id must be a number, got {:?}", id)))?;
+            match through_engine(metrics, Packet::command(BookAction::Cancel, order_id, query_param(&url, "symbol")))? {
+                OrderOutcome::Cancelled(Some(order)) => Ok(json_response(200, &json!({"status": "cancelled", "order": order}))),
+                OrderOutcome::Cancelled(None) => Err(HttpError::NotFound(format!("order {} is not on the book", order_id))),
+                other => Err(unexpected(other)),
             }
-            
-            let response = Response::from_string("{\"status\":\"ok\"}")
-                .with_header(Header::from_bytes(&b"Content-Type"[..], &b"application/json"[..]).unwrap())
-                .with_header(Header::from_bytes(&b"Access-Control-Allow-Origin"[..], &b"*"[..]).unwrap());
-            let _ = request.respond(response);
         }
         
         // Handle CORS preflight
         (Method::Options, _) => {
-            let response = Response::from_string("")
-                .with_header(Header::from_bytes(&b"Access-Control-Allow-Origin"[..], &b"*"[..]).unwrap())
-                .with_header(Header::from_bytes(&b"Access-Control-Allow-Methods"[..], &b"GET, POST, OPTIONS"[..]).unwrap())
-                .with_header(Header::from_bytes(&b"Access-Control-Allow-Headers"[..], &b"Content-Type"[..]).unwrap());
-            let _ = request.respond(response);
+            Ok(Response::from_string("")
+                .with_header(header("Access-Control-Allow-Origin", "*"))
+                .with_header(header("Access-Control-Allow-Methods", "GET, POST, DELETE, OPTIONS"))
+                .with_header(header("Access-Control-Allow-Headers", "Content-Type, Authorization, X-API-Key")))
         }
         
-        _ => {
-            let response = Response::from_string("404 Not Found")
-                .with_status_code(404);
-            let _ = request.respond(response);
-        }
+        _ => Err(HttpError::NotFound(format!("no route for {}", path))),
     }
 }
 
-fn serve_file(request: Request, path: &str, content_type: &str) {
+fn serve_file(path: &str, content_type: &str) -> Result<HttpResponse, HttpError> {
     match fs::read_to_string(path) {
-        Ok(content) => {
-            let response = Response::from_string(content)
-                .with_header(Header::from_bytes(&b"Content-Type"[..], content_type.as_bytes()).unwrap());
-            let _ = request.respond(response);
-        }
-        Err(_) => {
-            let response = Response::from_string("404 Not Found")
-                .with_status_code(404);
-            let _ = request.respond(response);
-        }
+        Ok(content) => Ok(Response::from_string(content).with_header(header("Content-Type", content_type))),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Err(HttpError::NotFound(format!("{} not found", path))),
+        Err(e) => Err(HttpError::Internal(format!("could not read {}: {}", path, e))),
     }
 }
@@ -0,0 +1,149 @@
+// ============================================================================
+// ICEBERG REFRESH - Replenishing the visible slice from the hidden reserve
+// ============================================================================
+// A resting iceberg shows `quantity` and keeps the rest in `hidden_quantity`.
+// When the visible slice trades out, a new slice is carved from the reserve.
+// Slices can be jittered around the nominal display size and refreshes can
+// be rate-limited, so the pattern is harder to spot from the tape.
+
+use crate::clock::Clock;
+use crate::matching_engine::Order;
+use crate::rng::SplitMix64;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// How an order book refreshes iceberg slices
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct IcebergRefresh {
+    /// Each refreshed slice is drawn uniformly from
+    /// `display_quantity ± slice_variance` (never below 1)
+    pub slice_variance: u64,
+    /// Minimum time between two refreshes of the same order; a slice that
+    /// trades out sooner stays off the book until the interval has passed
+    pub min_refresh_interval_ns: u64,
+    /// Seed for slice sizes, so a run can be reproduced exactly
+    pub seed: u64,
+}
+
+/// Refresh bookkeeping owned by an order book
+pub(crate) struct IcebergState {
+    config: IcebergRefresh,
+    rng: SplitMix64,
+    clock: Option<Arc<dyn Clock>>,
+    /// Last refresh time per resting iceberg
+    last_refresh_ns: HashMap<u64, u64>,
+    /// Depleted icebergs waiting out the refresh interval, as `(due_ns, order)`
+    pending: Vec<(u64, Order)>,
+}
+
+impl Default for IcebergState {
+    fn default() -> Self {
+        IcebergState {
+            config: IcebergRefresh::default(),
+            rng: SplitMix64::new(0),
+            clock: None,
+            last_refresh_ns: HashMap::new(),
+            pending: Vec::new(),
+        }
+    }
+}
+
+impl IcebergState {
+    pub(crate) fn configure(&mut self, config: IcebergRefresh, clock: Option<Arc<dyn Clock>>) {
+        self.rng = SplitMix64::new(config.seed);
+        self.config = config;
+        self.clock = clock;
+    }
+
+    fn now_ns(&self) -> u64 {
+        self.clock.as_ref().map_or(0, |clock| clock.now_ns())
+    }
+
+    /// Splits an order about to rest into its visible slice and reserve.
+    pub(crate) fn on_rest(&mut self, order: &mut Order) {
+        let Some(display) = order.display_quantity.filter(|&d| d > 0) else {
+            return;
+        };
+        if order.quantity > display {
+            order.hidden_quantity += order.quantity - display;
+            order.quantity = display;
+        }
+        if order.hidden_quantity > 0 {
+            let now = self.now_ns();
+            self.last_refresh_ns.insert(order.id, now);
+        }
+    }
+
+    /// Called when a resting order's visible slice has fully traded. Returns
+    /// the order with a fresh slice if it should re-queue right away; parks
+    /// it if the refresh interval has not passed yet.
+    pub(crate) fn on_depleted(&mut self, mut order: Order) -> Option<Order> {
+        if order.hidden_quantity == 0 {
+            self.last_refresh_ns.remove(&order.id);
+            return None;
+        }
+        let now = self.now_ns();
+        let last = self.last_refresh_ns.get(&order.id).copied().unwrap_or(0);
+        let due = last.saturating_add(self.config.min_refresh_interval_ns);
+        if self.config.min_refresh_interval_ns > 0 && now < due {
+            self.pending.push((due, order));
+            return None;
+        }
+        self.refresh(&mut order, now);
+        Some(order)
+    }
+
+    /// Forgets a cancelled order's refresh timing
+    pub(crate) fn on_cancel(&mut self, order_id: u64) {
+        self.last_refresh_ns.remove(&order_id);
+    }
+
+    /// Parked icebergs whose interval has elapsed, each with a fresh slice
+    pub(crate) fn take_due(&mut self) -> Vec<Order> {
+        if self.pending.is_empty() {
+            return Vec::new();
+        }
+        let now = self.now_ns();
+        let (due, waiting): (Vec<_>, Vec<_>) = self.pending.drain(..).partition(|&(due, _)| due <= now);
+        self.pending = waiting;
+        due.into_iter()
+            .map(|(_, mut order)| {
+                self.refresh(&mut order, now);
+                order
+            })
+            .collect()
+    }
+
+    /// Number of icebergs waiting out their refresh interval
+    pub(crate) fn pending_len(&self) -> usize {
+        self.pending.len()
+    }
+
+    /// Icebergs waiting out their refresh interval, in the order they parked
+    pub(crate) fn pending_orders(&self) -> impl Iterator<Item = &Order> {
+        self.pending.iter().map(|(_, order)| order)
+    }
+
+    /// Parks `order` as already due, so it comes back on the next release.
+    /// Meant for loading snapshots, which do not keep refresh times.
+    pub(crate) fn park_due(&mut self, order: Order) {
+        self.pending.push((0, order));
+    }
+
+    /// Moves the next slice from reserve to visible; the two always add up
+    /// to what was left before.
+    fn refresh(&mut self, order: &mut Order, now: u64) {
+        let display = order.display_quantity.unwrap_or(order.hidden_quantity).max(1);
+        let variance = self.config.slice_variance;
+        let slice = if variance == 0 {
+            display
+        } else {
+            let low = display.saturating_sub(variance).max(1);
+            self.rng.range(low, display.saturating_add(variance))
+        };
+        let slice = slice.min(order.hidden_quantity);
+        order.hidden_quantity -= slice;
+        order.quantity = slice;
+        self.last_refresh_ns.insert(order.id, now);
+    }
+}
@@ -0,0 +1,163 @@
+// ============================================================================
+// ICEBERG DETECTION - Inferring hidden size from the public feed
+// ============================================================================
+// Surveillance-side heuristic, independent of the engine's own iceberg
+// bookkeeping: it only sees what any feed consumer sees. When more trades at
+// the touch than the displayed size there and the level is still standing,
+// something refilled it. Each refill raises the confidence that a hidden
+// order sits there.
+//
+// Displayed sizes come from BBO events, so the estimate is sharpest with an
+// unthrottled BBO (`BBO_INTERVAL_NS=0`); coalesced samples can miss new
+// orders joining the level and overstate the hidden size.
+
+use crate::events::{BookEvent, BusMessage};
+use crate::matching_engine::OrderSide;
+use crate::price_units::Price;
+use crossbeam_channel::{Receiver, RecvTimeoutError};
+use serde::Serialize;
+use log::info;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+pub const ICEBERG_DETECTOR_THREAD_NAME: &str = "iceberg-detector";
+
+/// How long the detector thread waits for a message before re-checking shutdown
+const POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IcebergDetectorConfig {
+    /// Refills seen at a level before it is reported
+    pub min_refills: u32,
+}
+
+impl Default for IcebergDetectorConfig {
+    fn default() -> Self {
+        IcebergDetectorConfig { min_refills: 2 }
+    }
+}
+
+/// A level that looks like it is backed by hidden size
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct IcebergSignal {
+    /// Side of the resting (suspected iceberg) order
+    pub side: OrderSide,
+    pub price: Price,
+    pub refills: u32,
+    /// Quantity traded at the level beyond what was displayed
+    pub hidden_quantity: u64,
+    /// 0..1, rising with every refill
+    pub confidence: f64,
+}
+
+/// What is known about the level at the touch on one side
+#[derive(Debug, Clone, Copy)]
+struct LevelWatch {
+    price: Price,
+    /// Size last displayed at the level
+    visible: u64,
+    /// Traded since `visible` was displayed
+    traded: u64,
+    /// Refills already counted against the current display
+    counted: u64,
+    refills: u32,
+    hidden_quantity: u64,
+}
+
+impl LevelWatch {
+    fn new(price: Price, visible: u64) -> Self {
+        LevelWatch { price, visible, traded: 0, counted: 0, refills: 0, hidden_quantity: 0 }
+    }
+}
+
+#[derive(Default)]
+pub struct IcebergDetector {
+    config: IcebergDetectorConfig,
+    bid: Option<LevelWatch>,
+    ask: Option<LevelWatch>,
+}
+
+impl IcebergDetector {
+    pub fn new(config: IcebergDetectorConfig) -> Self {
+        IcebergDetector { config, bid: None, ask: None }
+    }
+
+    /// Feeds one bus event; returns a signal when it reveals another refill
+    /// at a level that has reached `min_refills`.
+    pub fn observe(&mut self, event: &BookEvent) -> Option<IcebergSignal> {
+        match *event {
+            BookEvent::Bbo { bid, ask, bid_quantity, ask_quantity, .. } => {
+                Self::redisplay(&mut self.bid, bid, bid_quantity);
+                Self::redisplay(&mut self.ask, ask, ask_quantity);
+                None
+            }
+            BookEvent::Trade(ref trade) => {
+                let (side, watch) = match (&mut self.bid, &mut self.ask) {
+                    (_, Some(ask)) if ask.price == trade.price => (OrderSide::Sell, ask),
+                    (Some(bid), _) if bid.price == trade.price => (OrderSide::Buy, bid),
+                    _ => return None,
+                };
+                if watch.visible == 0 {
+                    return None;
+                }
+                let shown_left = watch.visible.saturating_sub(watch.traded);
+                watch.hidden_quantity += trade.quantity.saturating_sub(shown_left);
+                watch.traded += trade.quantity;
+
+                let crossings = (watch.traded - 1) / watch.visible;
+                if crossings <= watch.counted {
+                    return None;
+                }
+                watch.refills += (crossings - watch.counted) as u32;
+                watch.counted = crossings;
+                (watch.refills >= self.config.min_refills).then(|| IcebergSignal {
+                    side,
+                    price: watch.price,
+                    refills: watch.refills,
+                    hidden_quantity: watch.hidden_quantity,
+                    confidence: 1.0 - 1.0 / (1.0 + watch.refills as f64),
+                })
+            }
+            BookEvent::Fill(_) | BookEvent::Rejection(_) => None,
+        }
+    }
+
+    /// A new BBO: the same price keeps its refill history, a new one starts over.
+    fn redisplay(watch: &mut Option<LevelWatch>, price: Option<Price>, quantity: u64) {
+        *watch = match (*watch, price) {
+            (Some(level), Some(price)) if level.price == price => {
+                Some(LevelWatch { visible: quantity, traded: 0, counted: 0, ..level })
+            }
+            (_, Some(price)) => Some(LevelWatch::new(price, quantity)),
+            (_, None) => None,
+        };
+    }
+}
+
+/// Runs a detector over `feed` on its own thread, logging every signal,
+/// until `shutdown` is raised or the bus goes away.
+pub fn spawn_iceberg_detector(
+    feed: Receiver<BusMessage>,
+    config: IcebergDetectorConfig,
+    shutdown: Arc<AtomicBool>,
+) -> std::io::Result<JoinHandle<()>> {
+    thread::Builder::new()
+        .name(ICEBERG_DETECTOR_THREAD_NAME.to_string())
+        .spawn(move || {
+            let mut detector = IcebergDetector::new(config);
+            while !shutdown.load(Ordering::Relaxed) {
+                match feed.recv_timeout(POLL_INTERVAL) {
+                    Ok(message) => {
+                        if let Some(signal) = detector.observe(&message.event) {
+                            info!("🧊 [SURVEILLANCE] Likely iceberg: {:?} @ {} ({} refills, {} hidden, confidence {:.2})",
+                                signal.side, signal.price, signal.refills, signal.hidden_quantity, signal.confidence);
+                        }
+                    }
+                    Err(RecvTimeoutError::Timeout) => {}
+                    Err(RecvTimeoutError::Disconnected) => return,
+                }
+            }
+        })
+}
@@ -0,0 +1,243 @@
+// ============================================================================
+// INGRESS ACCOUNTING - Bytes, orders and parse errors per gateway connection
+// ============================================================================
+
+use crate::matching_engine::Order;
+use crate::rejections::{RejectReason, RejectionLog};
+use serde::Serialize;
+use log::info;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::Instant;
+
+#[derive(Default)]
+struct IngressCounters {
+    bytes_read: AtomicU64,
+    orders_parsed: AtomicU64,
+    parse_errors: AtomicU64,
+}
+
+impl IngressCounters {
+    fn snapshot(&self) -> IngressSnapshot {
+        IngressSnapshot {
+            bytes_read: self.bytes_read.load(Ordering::Relaxed),
+            orders_parsed: self.orders_parsed.load(Ordering::Relaxed),
+            parse_errors: self.parse_errors.load(Ordering::Relaxed),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct IngressSnapshot {
+    pub bytes_read: u64,
+    pub orders_parsed: u64,
+    pub parse_errors: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ConnectionSnapshot {
+    pub id: u64,
+    pub peer: String,
+    pub connected_ms: u64,
+    #[serde(flatten)]
+    pub stats: IngressSnapshot,
+}
+
+/// How a gateway connection ended
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Disconnect {
+    /// The peer closed between messages
+    Clean,
+    /// The peer closed partway through a line or frame
+    MidMessage,
+    /// The socket read failed (reset, timeout)
+    ReadError,
+    /// The gateway hung up after a frame it could not resync from
+    Corrupt,
+}
+
+impl Disconnect {
+    /// Whether the peer went away without finishing what it was sending
+    pub fn is_abrupt(self) -> bool {
+        matches!(self, Disconnect::MidMessage | Disconnect::ReadError)
+    }
+}
+
+impl fmt::Display for Disconnect {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Disconnect::Clean => "clean",
+            Disconnect::MidMessage => "mid-message",
+            Disconnect::ReadError => "read error",
+            Disconnect::Corrupt => "corrupt stream",
+        })
+    }
+}
+
+struct ConnectionInfo {
+    peer: String,
+    opened_at: Instant,
+    counters: IngressCounters,
+}
+
+/// Gateway-wide totals plus a registry of the currently open connections.
+#[derive(Default)]
+pub struct IngressStats {
+    totals: IngressCounters,
+    active: Mutex<BTreeMap<u64, Arc<ConnectionInfo>>>,
+    next_id: AtomicU64,
+    abrupt_disconnects: AtomicU64,
+    refused_connections: AtomicU64,
+    /// Most connections open at once, once configured
+    connection_limit: OnceLock<usize>,
+    /// Where connections report rejected messages, once configured
+    rejections: OnceLock<Arc<RejectionLog>>,
+}
+
+impl IngressStats {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers a connection; it stays listed until the guard is dropped.
+    pub fn open(self: &Arc<Self>, peer: &str) -> ConnectionGuard {
+        let mut active = self.active.lock().unwrap();
+        self.register(&mut active, peer)
+    }
+
+    /// Like `open`, unless `connection_limit` connections are already open;
+    /// a refused connection is counted and gets no guard.
+    pub fn try_open(self: &Arc<Self>, peer: &str) -> Option<ConnectionGuard> {
+        let mut active = self.active.lock().unwrap();
+        if self.connection_limit.get().is_some_and(|&limit| active.len() >= limit) {
+            self.refused_connections.fetch_add(1, Ordering::Relaxed);
+            return None;
+        }
+        Some(self.register(&mut active, peer))
+    }
+
+    fn register(self: &Arc<Self>, active: &mut BTreeMap<u64, Arc<ConnectionInfo>>, peer: &str) -> ConnectionGuard {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed) + 1;
+        let info = Arc::new(ConnectionInfo {
+            peer: peer.to_string(),
+            opened_at: Instant::now(),
+            counters: IngressCounters::default(),
+        });
+        active.insert(id, info.clone());
+        ConnectionGuard { id, stats: self.clone(), info }
+    }
+
+    /// Caps open connections for `try_open`; only the first call counts.
+    pub fn set_connection_limit(&self, limit: usize) {
+        let _ = self.connection_limit.set(limit);
+    }
+
+    pub fn connection_limit(&self) -> Option<usize> {
+        self.connection_limit.get().copied()
+    }
+
+    /// Sends gateway rejections to `log` from now on; only the first call counts.
+    pub fn set_rejection_log(&self, log: Arc<RejectionLog>) {
+        let _ = self.rejections.set(log);
+    }
+
+    pub fn rejection_log(&self) -> Option<&Arc<RejectionLog>> {
+        self.rejections.get()
+    }
+
+    pub fn totals(&self) -> IngressSnapshot {
+        self.totals.snapshot()
+    }
+
+    /// Connections whose peer vanished mid-message or whose read failed
+    pub fn abrupt_disconnects(&self) -> u64 {
+        self.abrupt_disconnects.load(Ordering::Relaxed)
+    }
+
+    /// Connections turned away by the connection limit
+    pub fn refused_connections(&self) -> u64 {
+        self.refused_connections.load(Ordering::Relaxed)
+    }
+
+    pub fn active_connections(&self) -> usize {
+        self.active.lock().unwrap().len()
+    }
+
+    /// Open connections, oldest first
+    pub fn connections(&self) -> Vec<ConnectionSnapshot> {
+        self.active.lock().unwrap()
+            .iter()
+            .map(|(&id, info)| ConnectionSnapshot {
+                id,
+                peer: info.peer.clone(),
+                connected_ms: info.opened_at.elapsed().as_millis() as u64,
+                stats: info.counters.snapshot(),
+            })
+            .collect()
+    }
+}
+
+/// Per-connection handle; every update also lands in the global totals.
+pub struct ConnectionGuard {
+    id: u64,
+    stats: Arc<IngressStats>,
+    info: Arc<ConnectionInfo>,
+}
+
+impl ConnectionGuard {
+    pub fn id(&self) -> u64 {
+        self.id
+    }
+
+    pub fn record_bytes(&self, bytes: u64) {
+        self.info.counters.bytes_read.fetch_add(bytes, Ordering::Relaxed);
+        self.stats.totals.bytes_read.fetch_add(bytes, Ordering::Relaxed);
+    }
+
+    pub fn record_order(&self) {
+        self.info.counters.orders_parsed.fetch_add(1, Ordering::Relaxed);
+        self.stats.totals.orders_parsed.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn record_parse_error(&self) {
+        self.info.counters.parse_errors.fetch_add(1, Ordering::Relaxed);
+        self.stats.totals.parse_errors.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Reports a rejected message to the rejection log, if there is one.
+    pub fn record_rejection(&self, reason: RejectReason, order: Option<&Order>, detail: &str) {
+        if let Some(log) = self.stats.rejections.get() {
+            log.record(reason, order, detail);
+        }
+    }
+
+    pub fn snapshot(&self) -> IngressSnapshot {
+        self.info.counters.snapshot()
+    }
+
+    /// Unregisters the connection, counting it if it ended abruptly, and
+    /// logs what it sent over its lifetime.
+    pub fn close(self, how: Disconnect) {
+        if how.is_abrupt() {
+            self.stats.abrupt_disconnects.fetch_add(1, Ordering::Relaxed);
+        }
+        let stats = self.snapshot();
+        info!(
+            "🔌 [GATEWAY] {} closed ({}) after {} ms: {} orders, {} parse errors, {} bytes",
+            self.info.peer,
+            how,
+            self.info.opened_at.elapsed().as_millis(),
+            stats.orders_parsed,
+            stats.parse_errors,
+            stats.bytes_read
+        );
+    }
+}
+
+impl Drop for ConnectionGuard {
+    fn drop(&mut self) {
+        self.stats.active.lock().unwrap().remove(&self.id);
+    }
+}
@@ -0,0 +1,66 @@
+// ============================================================================
+// LAST LOOK - Liquidity providers may decline a fill against their quote
+// ============================================================================
+// A match against a resting order from a flagged account is tentative: the
+// provider's handler sees it and accepts or rejects. An answer that takes
+// longer than the window (or no answer at all) counts as an accept. A
+// rejected quote stays on the book, but the taker moves on to the next
+// resting order, and whatever is left of it may rest through the declined
+// quote.
+
+use crate::price_units::Price;
+use serde::Serialize;
+use std::collections::HashSet;
+use std::time::{Duration, Instant};
+
+/// The tentative fill shown to the provider
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct LastLookRequest {
+    pub maker_order_id: u64,
+    pub maker_account_id: u64,
+    pub taker_order_id: u64,
+    pub price: Price,
+    pub quantity: u64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LastLookDecision {
+    Accept,
+    Reject,
+}
+
+/// Returns `None` when the provider does not answer
+pub type LastLookHandler = Box<dyn FnMut(&LastLookRequest) -> Option<LastLookDecision> + Send>;
+
+pub struct LastLook {
+    accounts: HashSet<u64>,
+    window: Duration,
+    handler: LastLookHandler,
+    rejections: u64,
+}
+
+impl LastLook {
+    pub fn new(accounts: impl IntoIterator<Item = u64>, window: Duration, handler: LastLookHandler) -> Self {
+        LastLook { accounts: accounts.into_iter().collect(), window, handler, rejections: 0 }
+    }
+
+    pub fn applies_to(&self, maker_account: Option<u64>) -> bool {
+        maker_account.is_some_and(|account| self.accounts.contains(&account))
+    }
+
+    /// Runs the provider's check; true means the fill must not happen.
+    pub(crate) fn rejects(&mut self, request: &LastLookRequest) -> bool {
+        let asked_at = Instant::now();
+        let decision = (self.handler)(request);
+        let rejected = decision == Some(LastLookDecision::Reject) && asked_at.elapsed() <= self.window;
+        if rejected {
+            self.rejections += 1;
+        }
+        rejected
+    }
+
+    /// Fills declined so far
+    pub fn rejections(&self) -> u64 {
+        self.rejections
+    }
+}
@@ -0,0 +1,79 @@
+// ============================================================================
+// LEVEL METADATA - Per-price-level bookkeeping for surveillance
+// ============================================================================
+// Optional: an order book only keeps this when asked to. Every order that
+// joins or leaves a level updates its entry, so reading the metadata never
+// walks the level's queue.
+
+use crate::clock::Clock;
+use crate::matching_engine::OrderSide;
+use crate::price_units::Price;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// What is known about one occupied price level
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct LevelMetadata {
+    /// Accounts with at least one order resting here (unattributed orders
+    /// don't count)
+    pub distinct_accounts: usize,
+    /// Clock time the level last went from empty to occupied
+    pub first_seen_ns: u64,
+}
+
+#[derive(Debug, Default)]
+struct LevelEntry {
+    first_seen_ns: u64,
+    orders: usize,
+    /// Resting orders per account
+    accounts: HashMap<u64, usize>,
+}
+
+/// Metadata state owned by an order book
+pub(crate) struct LevelMetadataTracker {
+    clock: Arc<dyn Clock>,
+    levels: HashMap<(OrderSide, Price), LevelEntry>,
+}
+
+impl LevelMetadataTracker {
+    pub(crate) fn new(clock: Arc<dyn Clock>) -> Self {
+        LevelMetadataTracker { clock, levels: HashMap::new() }
+    }
+
+    /// An order joined the level.
+    pub(crate) fn on_add(&mut self, side: OrderSide, price: Price, account_id: Option<u64>) {
+        let entry = self.levels.entry((side, price)).or_default();
+        if entry.orders == 0 {
+            entry.first_seen_ns = self.clock.now_ns();
+        }
+        entry.orders += 1;
+        if let Some(account) = account_id {
+            *entry.accounts.entry(account).or_default() += 1;
+        }
+    }
+
+    /// An order left the level for good (filled, cancelled or amended away).
+    pub(crate) fn on_remove(&mut self, side: OrderSide, price: Price, account_id: Option<u64>) {
+        let Some(entry) = self.levels.get_mut(&(side, price)) else { return };
+        entry.orders = entry.orders.saturating_sub(1);
+        if let Some(account) = account_id {
+            if let Some(count) = entry.accounts.get_mut(&account) {
+                *count -= 1;
+                if *count == 0 {
+                    entry.accounts.remove(&account);
+                }
+            }
+        }
+        if entry.orders == 0 {
+            self.levels.remove(&(side, price));
+        }
+    }
+
+    pub(crate) fn get(&self, side: OrderSide, price: Price) -> Option<LevelMetadata> {
+        self.levels.get(&(side, price)).map(|entry| LevelMetadata {
+            distinct_accounts: entry.accounts.len(),
+            first_seen_ns: entry.first_seen_ns,
+        })
+    }
+}
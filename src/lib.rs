@@ -0,0 +1,60 @@
+// ============================================================================
+// THE NANOSECOND ARBITER - Library Crate
+// ============================================================================
+// The binaries (`hft_ringbuffer`, `benchmark`, `replay`) and the integration
+// tests under `tests/` all link against these modules. The matching engine's
+// types are re-exported at the root, so embedding the book only takes
+// `use hft_ringbuffer::{Order, OrderBook, OrderSide};`.
+
+pub mod auth;
+pub mod bbo;
+pub mod blocking_ring;
+pub mod book_checksum;
+pub mod book_diff;
+pub mod clock;
+pub mod config;
+pub mod decision_bridge;
+pub mod engine;
+pub mod events;
+pub mod fees;
+pub mod funnel;
+pub mod gateway;
+pub mod histogram;
+pub mod http_server;
+pub mod iceberg;
+pub mod iceberg_detection;
+pub mod ingress;
+pub mod last_look;
+pub mod level_metadata;
+pub mod match_policy;
+pub mod matching_engine;
+pub mod metrics;
+pub mod order_generator;
+pub mod order_ids;
+pub mod order_results;
+pub mod pcap;
+pub mod positions;
+pub mod price_band;
+pub mod price_units;
+pub mod rejections;
+pub mod replay;
+pub mod replica;
+pub mod rng;
+pub mod self_bench;
+pub mod settlement;
+pub mod sharded_engine;
+pub mod shards;
+pub mod signal_store;
+pub mod signals;
+pub mod stop_orders;
+pub mod tick_dump;
+pub mod tick_size;
+pub mod trade_history;
+pub mod wait_strategy;
+pub mod wal;
+pub mod warm_start;
+pub mod wash_trade;
+pub mod websocket;
+
+pub use matching_engine::*;
+pub use price_units::Price;
@@ -2,24 +2,33 @@
 // LOCK-FREE RING BUFFER - The Nanosecond Arbiter (Phase 2: SPSC Pipeline)
 // ============================================================================
 
-mod matching_engine;
-use matching_engine::{Order, OrderBook, OrderSide, Packet};
-use std::thread;
-use std::time::Instant;
-
-// ============================================================================
-// PACKET STRUCTURE - The Protocol
-// ============================================================================
-// Packet moved to matching_engine.rs
-
-// ============================================================================
-// MAIN - The SPSC Pipeline Benchmark
-// ============================================================================
-
-mod gateway;
-mod http_server;
-use gateway::run_gateway;
-use http_server::start_http_server;
+use hft_ringbuffer::bbo::BboPublisher;
+use hft_ringbuffer::clock::{process_clock, MonotonicClock};
+use hft_ringbuffer::config::{CliArgs, Config};
+use hft_ringbuffer::decision_bridge::DecisionBridge;
+use hft_ringbuffer::engine::{spawn_engine, EngineHooks, PhasedShutdown, SymbolBooks};
+use hft_ringbuffer::events::{EventBus, FillNotifier};
+use hft_ringbuffer::fees::FeeSchedule;
+use hft_ringbuffer::funnel::spawn_funnel;
+use hft_ringbuffer::gateway::{bind_gateway, spawn_gateway, GatewayRoutes};
+use hft_ringbuffer::http_server::{bind_http_server, start_http_server};
+use hft_ringbuffer::iceberg_detection::spawn_iceberg_detector;
+use hft_ringbuffer::matching_engine::{OrderBook, Packet};
+use hft_ringbuffer::metrics::Metrics;
+use hft_ringbuffer::order_results::OrderResults;
+use hft_ringbuffer::rejections::{RejectionLog, DEFAULT_REJECTION_LOG_CAPACITY};
+use hft_ringbuffer::replay::Recorder;
+use hft_ringbuffer::replica::{replica_channel, seed_replica_book, spawn_replica};
+use hft_ringbuffer::self_bench::run_self_bench;
+use hft_ringbuffer::sharded_engine::{join_all, spawn_sharded_engines, ShardedEngineConfig};
+use hft_ringbuffer::signal_store::SignalStore;
+use hft_ringbuffer::signals::{install_signal_handlers, spawn_signal_watch};
+use hft_ringbuffer::tick_dump::TickDump;
+use hft_ringbuffer::trade_history::{TradeHistory, TradeSink, DEFAULT_TRADE_HISTORY_CAPACITY};
+use hft_ringbuffer::wal::WriteAheadLog;
+use hft_ringbuffer::warm_start::{load_journal, recover, recovered_order_ids, warm_start, BookSnapshotFile};
+use log::info;
+use std::io::Write;
 use std::sync::{Arc, Mutex};
 
 // ============================================================================
@@ -27,76 +36,322 @@ use std::sync::{Arc, Mutex};
 // ============================================================================
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
-    println!("🚀 NANOSECOND ARBITER - PRODUCTION MODE");
-    println!("============================================================\n");
+    // RUST_LOG filters the engine, gateway and HTTP logs (info by default);
+    // RUST_LOG=info,trades=off keeps everything but the per-trade lines
+    env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("info"))
+        .format(|buf, record| writeln!(buf, "{:<5} {}", record.level(), record.args()))
+        .init();
+    info!("🚀 NANOSECOND ARBITER - PRODUCTION MODE");
+    // Pin the shared clock's origin before any packet is stamped
+    process_clock();
     
     // Configuration
-    const RING_BUFFER_CAPACITY: usize = 4096;
+    // CONFIG_FILE=arbiter.json sets anything in `Config`; each setting's
+    // environment variable (RING_CAPACITY, ENGINE_BATCH, WAL_PATH, ...)
+    // overrides it and --buffer-size, --http-port, --gateway-port and
+    // --orders override both. Loading refuses bad or conflicting settings
+    let args = CliArgs::parse(std::env::args().skip(1))?;
+    let config_file = std::env::var("CONFIG_FILE").ok();
+    let mut config = Config::load(config_file.as_deref())?;
+    config.apply_args(&args)?;
+    let tick_schedule = config.tick_schedule()?;
+    let price_band = config.price_band()?;
+    let wash_trade_detection = config.wash_trade_detection();
+    let settlement_method = config.settlement_method()?;
+    let decision_bridge = config.decision_bridge();
+    let symbol_rings = config.symbol_rings();
+    let warm_start_action = config.warm_start_action();
+    let wal_snapshot = config.wal_snapshot_path();
+    let engine_wait = config.engine_wait()?;
+    let funnel_config = config.funnel_config();
+    let fill_notifications = config.fill_notifications();
+
+    // Bind up front so a port clash fails startup instead of a background thread
+    let (listener, gateway_addr) = bind_gateway(&config.gateway_addr)?;
+    let (server, http_addr) = bind_http_server(&config.http_addr).map_err(|e| e.to_string())?;
+    
+    info!("📊 Configuration:");
+    if let Some(path) = &config_file {
+        info!("   • Config File: {}", path);
+    }
+    info!("   • Ring Buffer Capacity: {}", config.ring_capacity);
+    info!("   • HTTP Address: {}", http_addr);
+    info!("   • Gateway Address: {}", gateway_addr);
+    if !config.api_keys.is_empty() {
+        info!("   • HTTP API Keys: {} (required on POST/DELETE)", config.api_keys.len());
+    }
+    if let Some(limit) = config.max_connections {
+        info!("   • Max Gateway Connections: {}", limit);
+    }
+    info!("   • BBO Interval: {} ns", config.bbo_interval_ns);
+    if let Some(schedule) = &tick_schedule {
+        info!("   • Tick Schedule: {:?}", schedule.bands());
+    }
+    if config.fee_schedule() != FeeSchedule::default() {
+        info!("   • Fees: maker {} bps, taker {} bps", config.maker_fee_bps, config.taker_fee_bps);
+    }
+    if let Some(band) = &price_band {
+        info!("   • Price Band: {}% around the last trade", band.max_band_pct());
+    }
+    if config.speed_bump_ns > 0 {
+        info!("   • Speed Bump: {} ns", config.speed_bump_ns);
+    }
+    if let Some(wash) = &wash_trade_detection {
+        info!("   • Wash Trade Detection: {} self-crosses / {} ns", wash.max_self_crosses, wash.window_ns);
+    }
+    if config.price_decimals > 0 {
+        info!("   • HTTP Price Decimals: {}", config.price_decimals);
+    }
+    info!("   • Match Policy: {}", config.match_policy);
+    if let Some(shards) = config.engine_shards {
+        info!("   • Engine Shards: {} over {} + {:?}", shards, config.book_symbol, config.symbols);
+        if !symbol_rings.capacities.is_empty() {
+            info!("   • Symbol Ring Capacities: {:?}", symbol_rings.capacities);
+        }
+    }
+    if let Some(bridge) = &decision_bridge {
+        info!("   • AI Execution: {} lots, {} past mid", bridge.quantity, bridge.price_offset);
+    }
+    info!("   • Settlement: {:?}", settlement_method);
+    if config.rejection_sample > 1 {
+        info!("   • Rejection Sampling: 1 in {}", config.rejection_sample);
+    }
+    if let Some(path) = &config.tick_dump {
+        info!("   • Tick Dump: {}", path);
+    }
+    if let Some(path) = &config.record_orders {
+        info!("   • Order Recording: {}", path);
+    }
+    if config.level_metadata {
+        info!("   • Level Metadata: on");
+    }
+    if let Some(path) = &config.warm_start_snapshot {
+        info!("   • Warm Start: {} ({:?} on checksum mismatch)", path, warm_start_action);
+    }
+    if let (Some(path), Some(snapshot)) = (&config.wal_path, &wal_snapshot) {
+        info!("   • Write-Ahead Log: {} (snapshot {} every {} entries)", path, snapshot, config.wal_snapshot_every);
+    }
+    info!("   • Engine Batch: {} orders per book lock", config.engine_batch);
+    info!("   • Engine Wait: {:?}", engine_wait);
+    info!("   • Funnel: {} in flight ({:?} when full)", funnel_config.max_in_flight, funnel_config.overflow);
+    info!("   • Read Replica: {}", if config.read_replica { "on" } else { "off" });
+    info!("   • Trade History: {}", if config.trade_history_inline { "inline" } else { "offloaded" });
+    info!("   • Recent Trades: {}", config.recent_trades);
+    info!("   • Duplicate Window: {} ids", config.duplicate_window);
+    info!("   • Architecture: Web UI + TCP Gateway -> Ring Buffer -> Engine");
+    
+    if args.self_bench {
+        info!("⏱️  Self-benchmark: {} orders through the matching core...", config.self_bench_orders);
+        let report = run_self_bench(config.self_bench_orders);
+        info!("   • p50: {} ns, p99: {} ns", report.p50_ns, report.p99_ns);
+        info!("   • Throughput: {:.0} orders/second ({} trades)", report.orders_per_second, report.trades);
+    }
     
-    println!("📊 Configuration:");
-    println!("   • Ring Buffer Capacity: {}", RING_BUFFER_CAPACITY);
-    println!("   • Architecture: Web UI + TCP Gateway -> Ring Buffer -> Engine");
-    println!();
+    let (producer, consumer) = rtrb::RingBuffer::<Packet>::new(config.ring_capacity);
     
-    let (producer, mut consumer) = rtrb::RingBuffer::<Packet>::new(RING_BUFFER_CAPACITY);
+    // Recovery replays into configured books, or orders would match and rest
+    // differently than they did live
+    let configured_book = || {
+        let mut book = OrderBook::new();
+        config.configure_book(&mut book);
+        book
+    };
     
     // Shared order book for HTTP API access
-    let order_book = Arc::new(Mutex::new(OrderBook::new()));
+    let mut wal = None;
+    let mut recovered_ids = Vec::new();
+    let mut book = match &config.warm_start_snapshot {
+        Some(path) => {
+            let snapshot = BookSnapshotFile::load(path)?;
+            let journal = match &config.warm_start_journal {
+                Some(path) => load_journal(path)?,
+                None => Vec::new(),
+            };
+            let expected = config.warm_start_checksum.unwrap_or(snapshot.checksum);
+            let book = warm_start(configured_book, &snapshot, &journal, expected, warm_start_action)?;
+            recovered_ids = recovered_order_ids(Some(&snapshot), &journal);
+            info!("♻️  Warm start: {} resting orders from {} (+{} journal entries)",
+                book.resting_orders().len(), path, journal.len());
+            book
+        }
+        None => match (&config.wal_path, &wal_snapshot) {
+            (Some(path), Some(snapshot_path)) => {
+                let (log, journal) = WriteAheadLog::open(path)?;
+                let snapshot = if std::path::Path::new(snapshot_path).exists() {
+                    Some(BookSnapshotFile::load(snapshot_path)?)
+                } else {
+                    None
+                };
+                let (book, sequence) = recover(configured_book, snapshot.as_ref(), &journal)?;
+                recovered_ids = recovered_order_ids(snapshot.as_ref(), &journal);
+                info!("♻️  Recovered {} resting orders from {} at journal sequence {}",
+                    book.resting_orders().len(), path, sequence);
+                wal = Some(log.resume_after(sequence).with_checkpoints(snapshot_path, config.wal_snapshot_every));
+                book
+            }
+            _ => configured_book(),
+        },
+    };
+    // Snapshots from before the window was saved only list their orders
+    book.remember_order_ids(recovered_ids.iter().copied());
+    // The replica starts from whatever the primary recovered, configured the same way
+    let replica_book = config.read_replica.then(|| seed_replica_book(&book, configured_book()));
+    let order_book = Arc::new(Mutex::new(book));
     let order_book_engine = order_book.clone();
     let order_book_http = order_book.clone();
+    let metrics = Arc::new(Metrics::new());
+    // The single book is the only market in /api/orderbook/all
+    metrics.register_symbol_book(&config.book_symbol, order_book.clone());
+    let metrics_engine = metrics.clone();
+    
+    // Engine events fan out to feed consumers over the bus
+    let event_bus = Arc::new(EventBus::with_retention(config.event_retention));
+    metrics.spread_history().lock().unwrap().set_capacity(config.spread_history);
+    // One BBO publisher and trade sink per engine thread
+    let new_bbo = || {
+        BboPublisher::new(config.bbo_interval_ns, Arc::new(MonotonicClock::new()), event_bus.clone())
+            .with_spread_history(metrics.spread_history().clone())
+    };
+    
+    let trade_history = Arc::new(Mutex::new(TradeHistory::new(DEFAULT_TRADE_HISTORY_CAPACITY)));
+    if !config.trade_history_inline {
+        // Only offloaded writers publish trades, so only they can feed /api/stream/trades
+        metrics.set_event_bus(event_bus.clone());
+    }
+    let new_trade_sink = || -> std::io::Result<TradeSink> {
+        if config.trade_history_inline {
+            return Ok(TradeSink::Inline(trade_history.clone()));
+        }
+        // The writer thread lives as long as the engine
+        let (sink, _writer) = TradeSink::offloaded(trade_history.clone(), Some(event_bus.clone()))?;
+        Ok(sink)
+    };
+    let fills = FillNotifier::new(event_bus.clone(), fill_notifications);
+    let rejections = Arc::new(RejectionLog::new(
+        Arc::new(MonotonicClock::new()),
+        Some(event_bus.clone()),
+        config.rejection_sample,
+        DEFAULT_REJECTION_LOG_CAPACITY,
+    ));
+    metrics.ingress().set_rejection_log(rejections.clone());
+    metrics.set_api_keys(config.api_keys());
+    if let Some(limit) = config.max_connections {
+        metrics.ingress().set_connection_limit(limit);
+    }
+    let tick_dump = match &config.tick_dump {
+        // The writer thread lives as long as the engine
+        Some(path) => Some(TickDump::create(path, Arc::new(MonotonicClock::new()))?.0),
+        None => None,
+    };
+    let recorder = config.record_orders.as_deref().map(Recorder::create).transpose()?;
+    
+    // Raised on SIGINT/SIGTERM. Producers stop first so the engine can drain
+    // what they already accepted
+    let phases = PhasedShutdown::new();
+    let shutdown = phases.engine_flag();
+    let shutdown_engine = shutdown.clone();
+    let shutdown_gateway = phases.ingress_flag();
+    
+    // Warm read replica behind /api/replica/*
+    let (replica_feed, replica) = if let Some(replica_book) = replica_book {
+        let (feed, replica) = replica_channel(replica_book, Arc::new(MonotonicClock::new()), config.replica_guard());
+        spawn_replica(replica.clone(), shutdown.clone())?;
+        (Some(feed), Some(replica))
+    } else {
+        (None, None)
+    };
+    // Surveillance: infer hidden size from the same feed clients see
+    if let Some(detector) = config.iceberg_detector() {
+        spawn_iceberg_detector(event_bus.subscribe(), detector, shutdown.clone())?;
+    }
+    // Lets the gateway ack each order with its fills or the engine's rejection
+    let order_results = Arc::new(OrderResults::new());
     
-    println!("✅ Ring buffer initialized\n");
+    
+    info!("✅ Ring buffer initialized");
     
     // ========================================================================
     // THREAD 1: MATCHING ENGINE (Consumer)
     // ========================================================================
     
-    thread::spawn(move || {
-        println!("⚙️  [ENGINE] Matching engine started on dedicated thread...");
-        
-        loop {
-            match consumer.pop() {
-                Ok(packet) => {
-                    // Process order and get executions
-                    let executions = {
-                        let mut book = order_book_engine.lock().unwrap();
-                        book.add_limit_order(packet.order)
-                    };
-                    
-                    // Print trade executions
-                    for exec in executions {
-                        println!("💰 TRADE: {} matched with {} @ {} (Qty: {})", 
-                            exec.taker_order_id, exec.maker_order_id, exec.price, exec.quantity);
-                    }
-                }
-                Err(_) => {
-                    // Busy wait
-                    std::hint::spin_loop();
-                }
+    // Each engine comes with the funnels that feed it
+    let (engine, forwarders, routes) = match config.engine_shards {
+        None => {
+            info!("⚙️  [ENGINE] Matching engine starting on dedicated thread...");
+            let hooks = EngineHooks { bbo: Some(new_bbo()), trades: Some(new_trade_sink()?), fills: Some(fills), replica: replica_feed, tick_dump, rejections: Some(rejections.clone()), results: Some(order_results.clone()), wal, recorder, drain_on_shutdown: config.drain_on_shutdown, max_batch: Some(config.engine_batch), wait: engine_wait };
+            let engine = spawn_engine(consumer, order_book_engine, shutdown_engine, metrics_engine, hooks)?;
+            let (funnel, forwarder) = spawn_funnel(producer, funnel_config, metrics.funnel().clone(), shutdown_gateway.clone())?;
+            (engine, vec![forwarder], GatewayRoutes::new(&config.book_symbol, funnel))
+        }
+        Some(shards) => {
+            info!("⚙️  [ENGINE] {} sharded matching engines starting...", shards);
+            let mut books = SymbolBooks::from([(config.book_symbol.clone(), order_book_engine)]);
+            for symbol in &config.symbols {
+                books.entry(symbol.clone()).or_insert_with(|| {
+                    let mut book = OrderBook::new();
+                    config.configure_book(&mut book);
+                    Arc::new(Mutex::new(book))
+                });
+            }
+            let shard_config = ShardedEngineConfig {
+                shards,
+                rings: symbol_rings,
+                default_symbol: config.book_symbol.clone(),
+                funnel: funnel_config,
+            };
+            let mut trade_sinks = (0..shards.max(1)).map(|_| new_trade_sink().map(Some)).collect::<std::io::Result<Vec<_>>>()?;
+            // Startup refused the hooks that follow a single book
+            let shard_hooks = |shard: usize| EngineHooks {
+                bbo: Some(new_bbo()),
+                trades: trade_sinks[shard].take(),
+                fills: Some(FillNotifier::new(event_bus.clone(), fill_notifications)),
+                rejections: Some(rejections.clone()),
+                results: Some(order_results.clone()),
+                drain_on_shutdown: config.drain_on_shutdown,
+                max_batch: Some(config.engine_batch),
+                wait: engine_wait,
+                ..Default::default()
+            };
+            let sharded = spawn_sharded_engines(&shard_config, books, shard_hooks, metrics_engine, shutdown_gateway.clone(), shutdown_engine)?;
+            for (index, symbols) in sharded.assignment.iter().enumerate() {
+                info!("   • Shard {}: {:?}", index, symbols);
             }
+            (join_all(sharded.engines)?, sharded.forwarders, sharded.routes)
         }
-    });
+    };
     
     // ========================================================================
     // THREAD 2: TCP GATEWAY (Producer)
     // ========================================================================
     
-    thread::spawn(move || {
-        println!("🌐 [GATEWAY] TCP server starting...");
-        if let Err(e) = run_gateway(producer) {
-            eprintln!("❌ [GATEWAY] Error: {}", e);
-        }
-    });
+    info!("🌐 [GATEWAY] TCP server starting...");
+    // AI decisions enter through the same funnel as gateway orders for the
+    // default symbol, numbered on from any bridge ids recovered above
+    let mut signals = SignalStore::new();
+    if let (Some(bridge), Some(funnel)) = (decision_bridge, routes.funnel_for(None)) {
+        signals = signals.with_bridge(DecisionBridge::new(bridge, funnel.clone()).continuing_after(&recovered_ids));
+    }
+    let routes = routes.with_results(order_results);
+    // HTTP orders, cancels and commands take the same path as gateway orders
+    metrics.set_order_entry(routes.clone());
+    let gateway = spawn_gateway(listener, routes, metrics.ingress().clone(), shutdown_gateway)?;
+
+    install_signal_handlers();
+    let mut ingress_threads = vec![gateway];
+    ingress_threads.extend(forwarders);
+    let signal_watch = spawn_signal_watch(phases, ingress_threads, engine)?;
     
     // ========================================================================
     // MAIN THREAD: HTTP SERVER + WEB DASHBOARD
     // ========================================================================
     
-    println!("🌐 [HTTP] Starting web dashboard...");
-    println!("📱 Open http://localhost:8082 in your browser\n");
+    info!("🌐 [HTTP] Starting web dashboard...");
+    info!("📱 Open http://localhost:{} in your browser", http_addr.port());
     
-    start_http_server(order_book_http)?;
+    // Returns once the engine flag is raised, i.e. mid-shutdown
+    start_http_server(server, order_book_http, Arc::new(signals), metrics, replica, shutdown)?;
+    let _ = signal_watch.join();
+    info!("👋 Shut down cleanly");
     
     Ok(())
 }
-
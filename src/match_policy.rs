@@ -0,0 +1,77 @@
+// ============================================================================
+// MATCH POLICY - How a taker's quantity is split across one price level
+// ============================================================================
+// Price priority is fixed: a taker always sweeps the best level first. What
+// varies by venue is the split inside a level. Price-time (FIFO) fills the
+// oldest order completely before touching the next; pro-rata gives every
+// resting order a share in proportion to its size. The book asks its policy
+// for shares each time it starts on a level, and re-asks whenever an order
+// it planned for is passed over (speed bump, last look, self-trade).
+
+use std::sync::Arc;
+
+/// Splits an incoming quantity across a level's resting orders.
+pub trait MatchPolicy: Send + Sync {
+    /// Shares for `resting` quantities in queue order. Each share is at most
+    /// its order's quantity and together they come to `min(incoming, total)`.
+    /// May stop early: orders past the last share are not visited this round.
+    fn allocate(&self, incoming: u64, resting: &mut dyn Iterator<Item = u64>) -> Vec<u64>;
+}
+
+/// Price-time priority: oldest order first, each filled before the next
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct FifoPolicy;
+
+impl MatchPolicy for FifoPolicy {
+    fn allocate(&self, incoming: u64, resting: &mut dyn Iterator<Item = u64>) -> Vec<u64> {
+        let mut left = incoming;
+        let mut shares = Vec::new();
+        for quantity in resting {
+            if left == 0 {
+                break;
+            }
+            let share = left.min(quantity);
+            shares.push(share);
+            left -= share;
+        }
+        shares
+    }
+}
+
+/// Pro-rata: each order gets `incoming * size / level_total`, rounded down;
+/// the lots lost to rounding go one at a time in queue order
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ProRataPolicy;
+
+impl MatchPolicy for ProRataPolicy {
+    fn allocate(&self, incoming: u64, resting: &mut dyn Iterator<Item = u64>) -> Vec<u64> {
+        let sizes: Vec<u64> = resting.collect();
+        let total: u64 = sizes.iter().sum();
+        let fill = incoming.min(total);
+        if fill == 0 {
+            return vec![0; sizes.len()];
+        }
+        let mut shares: Vec<u64> =
+            sizes.iter().map(|&size| (fill as u128 * size as u128 / total as u128) as u64).collect();
+        // Rounding down leaves fewer lots than orders, and at least that much room
+        let mut left = fill - shares.iter().sum::<u64>();
+        while left > 0 {
+            for (share, &size) in shares.iter_mut().zip(&sizes) {
+                if left > 0 && *share < size {
+                    *share += 1;
+                    left -= 1;
+                }
+            }
+        }
+        shares
+    }
+}
+
+/// `fifo` or `pro-rata`, as `MATCH_POLICY` spells them
+pub fn policy_by_name(name: &str) -> Result<Arc<dyn MatchPolicy>, String> {
+    match name.trim() {
+        "fifo" => Ok(Arc::new(FifoPolicy)),
+        "pro-rata" => Ok(Arc::new(ProRataPolicy)),
+        other => Err(format!("expected fifo or pro-rata, got {}", other)),
+    }
+}
@@ -2,42 +2,479 @@
 // MATCHING ENGINE MODULE
 // ============================================================================
 
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, HashMap, VecDeque};
 use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use std::io::Write;
+use std::path::Path;
+use crate::book_checksum::levels_checksum;
+use crate::book_diff::{LevelChange, OrderBookSnapshot};
+use crate::clock::{process_clock, Clock};
+use crate::fees::{FeeHistory, FeeSchedule};
+use crate::iceberg::{IcebergRefresh, IcebergState};
+use crate::last_look::{LastLook, LastLookRequest};
+use crate::level_metadata::{LevelMetadata, LevelMetadataTracker};
+use crate::match_policy::{FifoPolicy, MatchPolicy};
+use crate::order_ids::RecentOrderIds;
+use crate::positions::{PnlReport, Position, PositionTracker};
+use crate::price_units::Price;
+use crate::price_band::{BandViolation, PriceBand};
+use crate::rejections::{EntryError, RejectReason};
+use crate::settlement::{Settlement, SettlementMethod, SettlementTracker};
+use crate::stop_orders::StopBook;
+use crate::tick_size::{TickSchedule, TickViolation};
+use crate::trade_history::TradeHistory;
+use crate::warm_start::{recover, BookSnapshotFile, WarmStartError};
+use crate::wash_trade::{WashTradeConfig, WashTradeDetector, WashTradeFlag};
 
 // ============================================================================
 // ORDER STRUCTURE
 // ============================================================================
-#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum OrderSide {
     Buy,
     Sell,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// How long an order's unfilled quantity stays live
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TimeInForce {
+    /// Rest whatever does not fill
+    #[default]
+    GoodTillCancel,
+    /// Fill completely on arrival or not at all
+    FillOrKill,
+}
+
+/// Whether an order works right away or waits for a trigger
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum OrderType {
+    #[default]
+    Limit,
+    /// Becomes a market order once the last trade reaches `trigger`
+    Stop { trigger: Price },
+    /// Becomes a limit order at `limit` once the last trade reaches `trigger`
+    StopLimit { trigger: Price, limit: Price },
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Order {
     pub id: u64,
     pub side: OrderSide,
-    pub price: u64,
+    pub price: Price,
     pub quantity: u64,
+    /// Owning account, if the client identified itself
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub account_id: Option<u64>,
+    /// Iceberg slice size: only this much shows while resting
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub display_quantity: Option<u64>,
+    /// Iceberg reserve not yet shown; maintained by the book, never published
+    #[serde(skip)]
+    pub hidden_quantity: u64,
+    /// Speed bump: clock time before which aggressors pass this order by
+    #[serde(skip)]
+    pub matchable_at_ns: u64,
+    /// Pegged to the book's external reference price at this offset; `price`
+    /// is only used until a reference has been supplied
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reference_peg_offset: Option<i64>,
+    /// What happens to quantity that does not fill on arrival
+    #[serde(default, skip_serializing_if = "is_good_till_cancel")]
+    pub time_in_force: TimeInForce,
+    /// Limit orders work on arrival; stops wait off the book for their trigger
+    #[serde(default, skip_serializing_if = "is_limit")]
+    pub order_type: OrderType,
+    /// Instrument this order is for; `None` means the deployment's default book
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub symbol: Option<String>,
+}
+
+fn is_good_till_cancel(tif: &TimeInForce) -> bool {
+    *tif == TimeInForce::GoodTillCancel
+}
+
+fn is_limit(order_type: &OrderType) -> bool {
+    *order_type == OrderType::Limit
+}
+
+/// Size of `Order::to_bytes`: id, side, price, quantity
+pub const ORDER_WIRE_LEN: usize = 25;
+
+impl Order {
+    pub fn new(id: u64, side: OrderSide, price: impl Into<Price>, quantity: u64) -> Self {
+        Order {
+            id,
+            side,
+            price: price.into(),
+            quantity,
+            account_id: None,
+            display_quantity: None,
+            hidden_quantity: 0,
+            matchable_at_ns: 0,
+            reference_peg_offset: None,
+            time_in_force: TimeInForce::GoodTillCancel,
+            order_type: OrderType::Limit,
+            symbol: None,
+        }
+    }
+
+    pub fn with_account(mut self, account_id: u64) -> Self {
+        self.account_id = Some(account_id);
+        self
+    }
+
+    /// Makes this an iceberg that shows at most `display_quantity` at a time
+    pub fn with_display_quantity(mut self, display_quantity: u64) -> Self {
+        self.display_quantity = Some(display_quantity);
+        self
+    }
+
+    /// Pegs this order `offset` away from the external reference price
+    pub fn pegged_to_reference(mut self, offset: i64) -> Self {
+        self.reference_peg_offset = Some(offset);
+        self
+    }
+
+    /// Routes this order to `symbol`'s book instead of the default one
+    pub fn for_symbol(mut self, symbol: &str) -> Self {
+        self.symbol = Some(symbol.to_string());
+        self
+    }
+
+    /// Checks that need no book: a quantity to trade and, unless pegged, a
+    /// price. Stops need a trigger instead; a stop-limit needs both. Price
+    /// times quantity must also fit in a `u64`, so notionals never wrap.
+    pub fn check_fields(&self) -> Result<(), EntryError> {
+        if self.quantity == 0 {
+            return Err(EntryError::new(RejectReason::ZeroQuantity, "quantity must be positive"));
+        }
+        let price = match self.order_type {
+            OrderType::Stop { trigger: Price::ZERO } | OrderType::StopLimit { trigger: Price::ZERO, .. } => {
+                return Err(EntryError::new(RejectReason::InvalidPrice, "stop trigger must be positive"));
+            }
+            OrderType::StopLimit { limit: Price::ZERO, .. } => {
+                return Err(EntryError::new(RejectReason::InvalidPrice, "stop limit must be positive"));
+            }
+            OrderType::Limit if self.price == 0 && self.reference_peg_offset.is_none() => {
+                return Err(EntryError::new(RejectReason::InvalidPrice, "price must be positive"));
+            }
+            OrderType::Limit => self.price,
+            OrderType::Stop { trigger } => trigger,
+            OrderType::StopLimit { trigger, limit } => trigger.max(limit),
+        };
+        if price.notional(self.quantity).is_none() {
+            return Err(EntryError::new(RejectReason::NotionalOverflow,
+                format!("notional of {} x {} overflows u64", self.quantity, price)));
+        }
+        Ok(())
+    }
+
+    /// `check_fields` without the detail: just why the order is invalid.
+    pub fn validate(&self) -> Result<(), RejectReason> {
+        self.check_fields().map_err(|error| error.reason)
+    }
+
+    /// Packed binary form: little-endian `u64` id, `u8` side (0 buy, 1 sell),
+    /// `u64` price, `u64` quantity. Everything else is left off the wire.
+    pub fn to_bytes(&self) -> [u8; ORDER_WIRE_LEN] {
+        let mut bytes = [0u8; ORDER_WIRE_LEN];
+        bytes[0..8].copy_from_slice(&self.id.to_le_bytes());
+        bytes[8] = match self.side {
+            OrderSide::Buy => 0,
+            OrderSide::Sell => 1,
+        };
+        bytes[9..17].copy_from_slice(&self.price.units().to_le_bytes());
+        bytes[17..25].copy_from_slice(&self.quantity.to_le_bytes());
+        bytes
+    }
+
+    /// Decodes `to_bytes`; anything but exactly `ORDER_WIRE_LEN` bytes with
+    /// a known side is malformed.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Order, EntryError> {
+        let bytes: &[u8; ORDER_WIRE_LEN] = bytes.try_into().map_err(|_| {
+            EntryError::new(RejectReason::Malformed, format!("order is {} bytes, expected {}", bytes.len(), ORDER_WIRE_LEN))
+        })?;
+        let word = |at: usize| u64::from_le_bytes(bytes[at..at + 8].try_into().expect("8-byte field"));
+        let side = match bytes[8] {
+            0 => OrderSide::Buy,
+            1 => OrderSide::Sell,
+            other => return Err(EntryError::new(RejectReason::Malformed, format!("unknown side byte {}", other))),
+        };
+        Ok(Order::new(word(0), side, word(9), word(17)))
+    }
+
+    /// Makes this fill-or-kill: it trades its full quantity or does nothing
+    pub fn fill_or_kill(mut self) -> Self {
+        self.time_in_force = TimeInForce::FillOrKill;
+        self
+    }
+
+    /// Makes this a stop: it sweeps as a market order once the last trade
+    /// reaches `trigger` (at or above for a buy, at or below for a sell)
+    pub fn stop(mut self, trigger: impl Into<Price>) -> Self {
+        self.order_type = OrderType::Stop { trigger: trigger.into() };
+        self
+    }
+
+    /// Makes this a stop-limit: once `trigger` trades it works as a limit
+    /// order at its own price
+    pub fn stop_limit(mut self, trigger: impl Into<Price>) -> Self {
+        self.order_type = OrderType::StopLimit { trigger: trigger.into(), limit: self.price };
+        self
+    }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct TradeExecution {
     pub maker_order_id: u64,
     pub taker_order_id: u64,
-    pub price: u64,
+    pub price: Price,
+    pub quantity: u64,
+    /// How much better than its limit the taker filled: `|limit - price| * quantity`
+    pub price_improvement: u64,
+    /// Fee schedule version that priced this trade
+    #[serde(default)]
+    pub fee_version: u64,
+    /// Fee charged to the maker; negative is a rebate
+    #[serde(default)]
+    pub maker_fee: i64,
+    #[serde(default)]
+    pub taker_fee: i64,
+    /// Taker quantity still open after this fill
+    #[serde(default)]
+    pub remaining_quantity: u64,
+    /// Set on the taker's last fill of the sweep: nothing more trades for it
+    /// now, and `remaining_quantity` rests or is dropped
+    #[serde(default)]
+    pub is_taker_complete: bool,
+    /// Symbol of the book it traded in, if the taker named one; a sharded
+    /// engine names it for orders that did not
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub symbol: Option<String>,
+}
+
+/// What happens to a protected market order's unfilled quantity
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ProtectionRemainder {
+    /// Rest it as a limit order at the protection price
+    Rest,
+    /// Drop it
+    Cancel,
+}
+
+/// Whether a book is taking orders
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TradingState {
+    #[default]
+    Open,
+    /// Session over: new orders are refused until it reopens
+    Closed,
+}
+
+/// What happens when an incoming order would trade against a resting order
+/// from the same account
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SelfTradePrevention {
+    /// Let it trade
+    #[default]
+    Off,
+    /// Cancel the resting order and keep matching
+    CancelResting,
+    /// Stop matching and cancel whatever is left of the incoming order
+    CancelIncoming,
+}
+
+/// What self-trade prevention did while matching one incoming order
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct SelfTradeAction {
+    pub taker_order_id: u64,
+    pub mode: SelfTradePrevention,
+    /// Own resting orders removed instead of trading (`CancelResting`)
+    pub cancelled_resting: Vec<u64>,
+    /// Incoming quantity dropped when matching stopped (`CancelIncoming`)
+    pub cancelled_incoming_quantity: u64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AmendError {
+    UnknownOrder(u64),
+}
+
+impl std::fmt::Display for AmendError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AmendError::UnknownOrder(id) => write!(f, "no resting order with id {}", id),
+        }
+    }
+}
+
+impl std::error::Error for AmendError {}
+
+/// Outcome of a market-style order that may not fill completely
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct MarketOrderResult {
+    pub executions: Vec<TradeExecution>,
+    /// Quantity that did not trade
+    pub unfilled_quantity: u64,
+    /// Portion of `unfilled_quantity` left resting on the book
+    pub rested_quantity: u64,
+}
+
+/// Trade prices `OrderBook::pricing_inputs` keeps unless configured otherwise
+pub const DEFAULT_TRAILING_PRICES: usize = 64;
+
+/// Trades `OrderBook::recent_trades` keeps unless configured otherwise
+pub const DEFAULT_RECENT_TRADES: usize = 1_000;
+
+/// Inputs for a downstream pricer, see `OrderBook::pricing_inputs`
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PricingInputs {
+    pub mid: Option<f64>,
+    pub last: Option<Price>,
+    pub trailing_prices: Vec<Price>,
+}
+
+/// Short-horizon signals from the visible book, see `OrderBook::signals`
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct BookSignals {
+    /// Levels per side counted into `imbalance`
+    pub levels: usize,
+    pub imbalance: f64,
+    pub microprice: Option<f64>,
+}
+
+/// Session candle; prices are `None` until the session's first trade
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct Ohlcv {
+    pub open: Option<Price>,
+    pub high: Option<Price>,
+    pub low: Option<Price>,
+    pub close: Option<Price>,
+    pub volume: u64,
+}
+
+impl Ohlcv {
+    fn record(&mut self, price: Price, quantity: u64) {
+        self.open.get_or_insert(price);
+        self.high = Some(self.high.map_or(price, |high| high.max(price)));
+        self.low = Some(self.low.map_or(price, |low| low.min(price)));
+        self.close = Some(price);
+        self.volume += quantity;
+    }
+}
+
+/// Cumulative depth per side, `(price, cumulative_quantity)` from the touch out
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct DepthCurves {
+    pub bids: Vec<(Price, u64)>,
+    pub asks: Vec<(Price, u64)>,
+}
+
+/// One aggregated level with its metadata, if the book keeps any
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct LevelDetail {
+    pub price: Price,
     pub quantity: u64,
+    #[serde(flatten, skip_serializing_if = "Option::is_none")]
+    pub metadata: Option<LevelMetadata>,
+}
+
+/// Per-level depth on both sides, best price first
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct LevelDepth {
+    pub bids: Vec<LevelDetail>,
+    pub asks: Vec<LevelDetail>,
+}
+
+/// One aggregated price level in a `DepthSnapshot`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct DepthLevel {
+    pub price: Price,
+    /// Visible quantity resting at this price
+    pub qty: u64,
+    /// Orders resting at this price
+    pub count: usize,
+}
+
+/// L2 view of the book: the best levels per side, best price first
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct DepthSnapshot {
+    pub bids: Vec<DepthLevel>,
+    pub asks: Vec<DepthLevel>,
+    /// CRC32 of these levels, see `book_checksum` for the format
+    pub checksum: u32,
+}
+
+/// Symbol of a single-book deployment
+pub const DEFAULT_BOOK_SYMBOL: &str = "DEFAULT";
+
+/// Largest per-symbol depth `snapshot_all` will return
+pub const MAX_SNAPSHOT_DEPTH: usize = 100;
+
+/// One symbol's top of book and its best `depth` levels per side
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct SymbolDepth {
+    pub sequence: u64,
+    pub best_bid: Option<Price>,
+    pub best_ask: Option<Price>,
+    /// `(price, total_quantity)`, best first
+    pub bids: Vec<(Price, u64)>,
+    pub asks: Vec<(Price, u64)>,
+}
+
+/// What a packet asks of its book. Everything that changes a book goes
+/// through the engine this way, so it is journaled and replicated in order.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BookAction {
+    /// Match the packet's order and rest what is left
+    #[default]
+    Submit,
+    /// Take the order with the packet's order id off the book
+    Cancel,
+    /// Set the external reference price pegged orders follow
+    ReferencePrice(Price),
+    /// Open or close the session
+    TradingState(TradingState),
+}
+
+impl BookAction {
+    pub fn is_submit(&self) -> bool {
+        *self == BookAction::Submit
+    }
 }
 
 #[derive(Debug, Clone)]
 pub struct Packet {
+    /// The order to submit; for other actions only its id and symbol count
     pub order: Order,
+    /// `process_clock()` nanoseconds when the gateway took the order in;
+    /// the engine measures queue residence and end-to-end latency from here
+    pub recv_ns: u64,
+    pub action: BookAction,
+    /// `OrderResults` ticket the engine answers with the outcome; `None`
+    /// if nobody is waiting for it
+    pub ticket: Option<u64>,
 }
 
 impl Packet {
+    /// Stamps the order as received now.
     pub fn new(order: Order) -> Self {
-        Packet { order }
+        Self::received_at(order, process_clock().now_ns())
+    }
+
+    pub fn received_at(order: Order, recv_ns: u64) -> Self {
+        Packet { order, recv_ns, action: BookAction::Submit, ticket: None }
+    }
+
+    /// Applies `action` to `symbol`'s book (the default one if `None`).
+    /// `order_id` is what `Cancel` cancels; other actions ignore it.
+    pub fn command(action: BookAction, order_id: u64, symbol: Option<&str>) -> Self {
+        let mut order = Order::new(order_id, OrderSide::Buy, 0, 0);
+        order.symbol = symbol.map(str::to_string);
+        Packet { action, ..Self::new(order) }
     }
 }
 
@@ -45,8 +482,66 @@ impl Packet {
 // ORDER BOOK STRUCTURE
 // ============================================================================
 pub struct OrderBook {
-    bids: BTreeMap<u64, Vec<Order>>,
-    asks: BTreeMap<u64, Vec<Order>>,
+    /// Each level is a FIFO queue: oldest order at the front, filled first
+    bids: BTreeMap<Price, VecDeque<Order>>,
+    asks: BTreeMap<Price, VecDeque<Order>>,
+    /// Every resting order's side and price, so lookups by id skip the scan
+    order_index: HashMap<u64, (OrderSide, Price)>,
+    /// Price grid enforced on entry; `None` accepts any price
+    tick_schedule: Option<TickSchedule>,
+    /// How far from the last trade an order may be priced; `None` = no limit
+    price_band: Option<PriceBand>,
+    /// Decimal places in the human-facing price, see `price_units`
+    price_decimals: u32,
+    /// Run `validate()` every N mutations (debug builds only, 0 = never)
+    #[cfg_attr(not(debug_assertions), allow(dead_code))]
+    invariant_check_every: u64,
+    #[cfg_attr(not(debug_assertions), allow(dead_code))]
+    ops_since_check: u64,
+    /// Number of mutating operations applied so far
+    sequence: u64,
+    /// Top-of-book changes as `(sequence, bid, ask)`, oldest first
+    bbo_history: VecDeque<(u64, Option<Price>, Option<Price>)>,
+    /// 0 disables BBO history
+    bbo_history_capacity: usize,
+    icebergs: IcebergState,
+    fees: FeeHistory,
+    /// Net position per account, from fills where the account is known
+    positions: PositionTracker,
+    last_trade_price: Option<Price>,
+    /// Most recent trade prices, oldest first, for `pricing_inputs`
+    trailing_prices: VecDeque<Price>,
+    trailing_prices_capacity: usize,
+    /// Candle for the session since the last `reset_session`
+    session: Ohlcv,
+    /// Every execution this book produced, bounded, oldest first
+    recent_trades: TradeHistory,
+    self_trade_prevention: SelfTradePrevention,
+    /// Set when self-trade prevention fired on the most recent incoming order
+    last_self_trade: Option<SelfTradeAction>,
+    last_look: Option<LastLook>,
+    /// Delay before a new resting order becomes matchable, and the clock timing it
+    speed_bump: Option<(u64, Arc<dyn Clock>)>,
+    wash_trades: Option<WashTradeDetector>,
+    level_metadata: Option<LevelMetadataTracker>,
+    /// Last externally supplied reference price (index, another venue's mid)
+    reference_price: Option<Price>,
+    /// Resting reference-pegged orders: order id -> offset
+    reference_pegs: BTreeMap<u64, i64>,
+    trading_state: TradingState,
+    settlement: Option<SettlementTracker>,
+    /// Stop orders waiting for the last trade to reach their trigger
+    stops: StopBook,
+    /// How a taker is split across the orders of one level
+    match_policy: Arc<dyn MatchPolicy>,
+    /// Ids taken recently, so `check_order` can refuse a resent one
+    accepted_ids: RecentOrderIds,
+}
+
+impl Default for OrderBook {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl OrderBook {
@@ -54,42 +549,724 @@ impl OrderBook {
         OrderBook {
             bids: BTreeMap::new(),
             asks: BTreeMap::new(),
+            order_index: HashMap::new(),
+            tick_schedule: None,
+            price_band: None,
+            price_decimals: 0,
+            invariant_check_every: 0,
+            ops_since_check: 0,
+            sequence: 0,
+            bbo_history: VecDeque::new(),
+            bbo_history_capacity: 0,
+            icebergs: IcebergState::default(),
+            fees: FeeHistory::default(),
+            positions: PositionTracker::new(),
+            last_trade_price: None,
+            trailing_prices: VecDeque::new(),
+            trailing_prices_capacity: DEFAULT_TRAILING_PRICES,
+            session: Ohlcv::default(),
+            recent_trades: TradeHistory::new(DEFAULT_RECENT_TRADES),
+            self_trade_prevention: SelfTradePrevention::Off,
+            last_self_trade: None,
+            last_look: None,
+            speed_bump: None,
+            wash_trades: None,
+            level_metadata: None,
+            reference_price: None,
+            reference_pegs: BTreeMap::new(),
+            trading_state: TradingState::Open,
+            settlement: None,
+            stops: StopBook::default(),
+            match_policy: Arc::new(FifoPolicy),
+            accepted_ids: RecentOrderIds::default(),
+        }
+    }
+
+    /// An empty book that splits each level's fills by `policy` instead of
+    /// price-time priority.
+    pub fn with_match_policy(policy: Arc<dyn MatchPolicy>) -> Self {
+        OrderBook { match_policy: policy, ..Self::new() }
+    }
+
+    /// Swaps the allocation policy; orders already resting keep their place.
+    pub fn set_match_policy(&mut self, policy: Arc<dyn MatchPolicy>) {
+        self.match_policy = policy;
+    }
+
+    /// Sequence number of the last applied operation (0 = none yet)
+    pub fn sequence(&self) -> u64 {
+        self.sequence
+    }
+
+    /// Keeps the last `capacity` top-of-book changes for `bbo_at`.
+    /// Recording starts from the current state; 0 turns it off.
+    pub fn set_bbo_history_capacity(&mut self, capacity: usize) {
+        self.bbo_history_capacity = capacity;
+        self.bbo_history.clear();
+        if capacity > 0 {
+            self.bbo_history.push_back((self.sequence, self.best_bid(), self.best_ask()));
         }
     }
 
-    pub fn add_limit_order(&mut self, mut order: Order) -> Vec<TradeExecution> {
+    /// Best bid and ask as they stood right after operation `sequence`.
+    /// `None` if that point is older than the retained history, in the
+    /// future, or history is disabled.
+    pub fn bbo_at(&self, sequence: u64) -> Option<(Option<Price>, Option<Price>)> {
+        if sequence > self.sequence {
+            return None;
+        }
+        let idx = self.bbo_history.partition_point(|&(seq, _, _)| seq <= sequence);
+        let &(_, bid, ask) = self.bbo_history.get(idx.checked_sub(1)?)?;
+        Some((bid, ask))
+    }
+
+    /// Enables the periodic `validate()` sweep. Only debug builds run it;
+    /// release builds compile the hook away entirely.
+    pub fn set_invariant_check_interval(&mut self, every_n_ops: u64) {
+        self.invariant_check_every = every_n_ops;
+        self.ops_since_check = 0;
+    }
+
+    pub fn set_tick_schedule(&mut self, schedule: Option<TickSchedule>) {
+        self.tick_schedule = schedule;
+    }
+
+    pub fn tick_schedule(&self) -> Option<&TickSchedule> {
+        self.tick_schedule.as_ref()
+    }
+
+    pub fn set_price_band(&mut self, band: Option<PriceBand>) {
+        self.price_band = band;
+    }
+
+    pub fn price_band(&self) -> Option<PriceBand> {
+        self.price_band
+    }
+
+    /// Checks a price against the band around the last trade.
+    pub fn check_price_band(&self, price: impl Into<Price>) -> Result<(), BandViolation> {
+        let price = price.into();
+        match &self.price_band {
+            Some(band) => band.validate(price, self.last_trade_price),
+            None => Ok(()),
+        }
+    }
+
+    /// Every order-entry check, in the order they are applied: the order's
+    /// own fields, duplicate id, trading state, tick schedule and price band. Stops have
+    /// their trigger and limit tick-checked but skip the band, which is about
+    /// where the market is now rather than where it will be when they fire.
+    pub fn check_order(&self, order: &Order) -> Result<(), EntryError> {
+        order.check_fields()?;
+        if self.accepted_ids.contains(order.id) {
+            return Err(EntryError::new(RejectReason::Duplicate, format!("order id {} was already accepted", order.id)));
+        }
+        if self.trading_state == TradingState::Closed {
+            return Err(EntryError::new(RejectReason::MarketClosed, "market is closed"));
+        }
+        let tick_error = |violation: TickViolation| EntryError::new(RejectReason::TickSize, violation.to_string());
+        match order.order_type {
+            OrderType::Limit => {
+                self.check_tick(order.price).map_err(tick_error)?;
+                self.check_price_band(order.price).map_err(|violation| EntryError::new(RejectReason::PriceBand, violation.to_string()))
+            }
+            OrderType::Stop { trigger } => self.check_tick(trigger).map_err(tick_error),
+            OrderType::StopLimit { trigger, limit } => {
+                self.check_tick(trigger).map_err(tick_error)?;
+                self.check_tick(limit).map_err(tick_error)
+            }
+        }
+    }
+
+    /// `place_order` behind `check_order`: a refused order leaves the
+    /// book untouched and says why.
+    pub fn submit_order(&mut self, order: Order) -> Result<Vec<TradeExecution>, EntryError> {
+        self.check_order(&order)?;
+        self.place_order(order)
+    }
+
+    /// Remember the last `capacity` accepted order ids and refuse repeats;
+    /// 0 (the default) turns duplicate checks off.
+    pub fn set_duplicate_window(&mut self, capacity: usize) {
+        self.accepted_ids.set_capacity(capacity);
+    }
+
+    pub fn duplicate_window(&self) -> usize {
+        self.accepted_ids.capacity()
+    }
+
+    /// Seeds the duplicate window with ids accepted before a restart, oldest
+    /// first, so a resend that straddles the restart is still refused. Call
+    /// after `set_duplicate_window`; with no window nothing is kept.
+    pub fn remember_order_ids(&mut self, ids: impl IntoIterator<Item = u64>) {
+        for id in ids {
+            self.accepted_ids.insert(id);
+        }
+    }
+
+    /// Ids in the duplicate window, oldest first
+    pub fn accepted_order_ids(&self) -> Vec<u64> {
+        self.accepted_ids.iter().collect()
+    }
+
+    /// How many decimal places HTTP clients may send in `price`; a price of
+    /// 1.25 with 2 decimals is 125 in the book.
+    pub fn set_price_decimals(&mut self, decimals: u32) {
+        self.price_decimals = decimals;
+    }
+
+    pub fn price_decimals(&self) -> u32 {
+        self.price_decimals
+    }
+
+    /// Checks a price against the tick schedule of its band.
+    pub fn check_tick(&self, price: impl Into<Price>) -> Result<(), TickViolation> {
+        let price = price.into();
+        match &self.tick_schedule {
+            Some(schedule) => schedule.validate(price),
+            None => Ok(()),
+        }
+    }
+
+    /// Swaps in a new fee schedule, returning its version. Executions from
+    /// the next processed order onward use it; earlier ones keep theirs.
+    pub fn set_fee_schedule(&mut self, schedule: FeeSchedule) -> u64 {
+        self.fees.push(schedule, self.sequence + 1)
+    }
+
+    /// Every fee schedule this book has used, for auditing executions
+    pub fn fee_history(&self) -> &FeeHistory {
+        &self.fees
+    }
+
+    /// Applies to every path that matches: new orders, protected market
+    /// orders and amends that re-queue.
+    pub fn set_self_trade_prevention(&mut self, mode: SelfTradePrevention) {
+        self.self_trade_prevention = mode;
+    }
+
+    pub fn self_trade_prevention(&self) -> SelfTradePrevention {
+        self.self_trade_prevention
+    }
+
+    /// What self-trade prevention did to the most recently matched order
+    /// (new, market or re-queued amend); `None` if it did not fire.
+    pub fn last_self_trade(&self) -> Option<&SelfTradeAction> {
+        self.last_self_trade.as_ref()
+    }
+
+    /// Gives the configured provider accounts a last look at fills against
+    /// their resting orders; `None` turns it off.
+    pub fn set_last_look(&mut self, last_look: Option<LastLook>) {
+        self.last_look = last_look;
+    }
+
+    pub fn last_look(&self) -> Option<&LastLook> {
+        self.last_look.as_ref()
+    }
+
+    /// Newly rested orders can't be hit until `delay_ns` has passed on
+    /// `clock`; aggressors trade past them meanwhile. 0 turns it off.
+    pub fn set_speed_bump(&mut self, delay_ns: u64, clock: Arc<dyn Clock>) {
+        self.speed_bump = (delay_ns > 0).then_some((delay_ns, clock));
+    }
+
+    pub fn speed_bump_ns(&self) -> u64 {
+        self.speed_bump.as_ref().map_or(0, |(delay_ns, _)| *delay_ns)
+    }
+
+    /// Watches for accounts repeatedly crossing their own quotes, timed by
+    /// `clock`; `None` turns it off and forgets every flag.
+    pub fn set_wash_trade_detection(&mut self, config: Option<WashTradeConfig>, clock: Arc<dyn Clock>) {
+        self.wash_trades = config.map(|config| WashTradeDetector::new(config, clock));
+    }
+
+    pub fn wash_trade_flag(&self, account_id: u64) -> Option<&WashTradeFlag> {
+        self.wash_trades.as_ref()?.flag(account_id)
+    }
+
+    /// Every flagged account, by account id
+    pub fn wash_trade_flags(&self) -> Vec<WashTradeFlag> {
+        self.wash_trades.as_ref().map_or_else(Vec::new, |detector| detector.flags())
+    }
+
+    /// Lifts a flag (and any throttle) after review; false if it wasn't flagged.
+    pub fn clear_wash_trade_flag(&mut self, account_id: u64) -> bool {
+        self.wash_trades.as_mut().is_some_and(|detector| detector.clear(account_id))
+    }
+
+    /// Starts (or with `false`, stops) keeping per-level metadata. Orders
+    /// already resting count as first seen now.
+    pub fn set_level_metadata(&mut self, enabled: bool, clock: Arc<dyn Clock>) {
+        self.level_metadata = enabled.then(|| {
+            let mut tracker = LevelMetadataTracker::new(clock);
+            for order in self.bids.values().chain(self.asks.values()).flatten() {
+                tracker.on_add(order.side, order.price, order.account_id);
+            }
+            tracker
+        });
+    }
+
+    /// Metadata for one level; `None` if it is empty or none is kept.
+    pub fn level_metadata(&self, side: OrderSide, price: impl Into<Price>) -> Option<LevelMetadata> {
+        self.level_metadata.as_ref()?.get(side, price.into())
+    }
+
+    /// Computes a settlement price by `method` whenever the session closes.
+    pub fn set_settlement_method(&mut self, method: SettlementMethod, clock: Arc<dyn Clock>) {
+        self.settlement = Some(SettlementTracker::new(method, clock));
+    }
+
+    pub fn trading_state(&self) -> TradingState {
+        self.trading_state
+    }
+
+    /// Opens or closes the session. Going from `Open` to `Closed` fixes the
+    /// session's settlement price, which is returned.
+    pub fn set_trading_state(&mut self, state: TradingState) -> Option<Settlement> {
+        let closing = self.trading_state == TradingState::Open && state == TradingState::Closed;
+        self.trading_state = state;
+        if !closing {
+            return None;
+        }
+        let (bid, ask) = (self.best_bid(), self.best_ask());
+        self.settlement.as_mut()?.settle(bid, ask)
+    }
+
+    /// Settlement of the most recently closed session
+    pub fn settlement(&self) -> Option<Settlement> {
+        self.settlement.as_ref()?.last()
+    }
+
+    pub fn reference_price(&self) -> Option<Price> {
+        self.reference_price
+    }
+
+    /// Sets the external reference price and moves every resting
+    /// reference-pegged order to `price + offset`. A repriced peg that now
+    /// crosses goes back through matching like any amend.
+    pub fn update_reference_price(&mut self, price: impl Into<Price>) -> Vec<TradeExecution> {
+        let price = price.into();
+        self.reference_price = Some(price);
+        let pegs: Vec<(u64, i64)> = self.reference_pegs.iter().map(|(&id, &offset)| (id, offset)).collect();
         let mut executions = Vec::new();
+        for (id, offset) in pegs {
+            let Some((_, current, _)) = self.locate(id) else {
+                // Filled or cancelled since it was pegged
+                self.reference_pegs.remove(&id);
+                continue;
+            };
+            let target = peg_price(price, offset);
+            if target == current {
+                continue;
+            }
+            if let Ok(fills) = self.amend_order(id, Some(target), None) {
+                executions.extend(fills);
+            }
+        }
+        executions
+    }
+
+    /// Price of the most recent execution
+    pub fn last_trade_price(&self) -> Option<Price> {
+        self.last_trade_price
+    }
+
+    /// How many recent trade prices `pricing_inputs` keeps (0 = none)
+    pub fn set_trailing_prices_capacity(&mut self, capacity: usize) {
+        self.trailing_prices_capacity = capacity;
+        while self.trailing_prices.len() > capacity {
+            self.trailing_prices.pop_front();
+        }
+    }
+
+    /// Open, high, low, close and volume of the current session
+    pub fn ohlcv(&self) -> Ohlcv {
+        self.session
+    }
+
+    /// Starts a new candle window; the last trade price carries over.
+    pub fn reset_session(&mut self) {
+        self.session = Ohlcv::default();
+    }
+
+    /// How many executions `recent_trades` keeps (0 = none)
+    pub fn set_recent_trades_capacity(&mut self, capacity: usize) {
+        self.recent_trades.set_capacity(capacity);
+    }
+
+    /// Stop orders still waiting for their trigger, oldest first
+    pub fn pending_stops(&self) -> &[Order] {
+        self.stops.pending()
+    }
+
+    /// The last `n` executions on this book, oldest first
+    pub fn recent_trades(&self, n: usize) -> Vec<TradeExecution> {
+        self.recent_trades.recent(n)
+    }
+
+    /// VWAP over the last `n` retained executions, see `TradeHistory::vwap`
+    pub fn vwap(&self, last_n: usize) -> Option<f64> {
+        self.recent_trades.vwap(last_n)
+    }
+
+    /// What an options pricer needs from this book: mid, last trade and the
+    /// trailing trade prices, oldest first.
+    pub fn pricing_inputs(&self) -> PricingInputs {
+        PricingInputs {
+            mid: match (self.best_bid(), self.best_ask()) {
+                (Some(bid), Some(ask)) => Some((bid.units() + ask.units()) as f64 / 2.0),
+                _ => None,
+            },
+            last: self.last_trade_price,
+            trailing_prices: self.trailing_prices.iter().copied().collect(),
+        }
+    }
+
+    /// `(bid volume - ask volume) / total` over the best `levels` levels a
+    /// side: +1 is all bids, -1 all asks, 0 balanced or empty.
+    pub fn imbalance(&self, levels: usize) -> f64 {
+        let bids: u64 = self.walk_bids().take(levels).map(|(_, quantity)| quantity).sum();
+        let asks: u64 = self.walk_asks().take(levels).map(|(_, quantity)| quantity).sum();
+        if bids + asks == 0 {
+            return 0.0;
+        }
+        (bids as f64 - asks as f64) / (bids + asks) as f64
+    }
+
+    /// Mid weighted by the size on the other side of the touch, so it leans
+    /// toward the side more likely to trade through. `None` unless both
+    /// sides are quoted.
+    pub fn microprice(&self) -> Option<f64> {
+        let (bid, bid_quantity) = self.walk_bids().next()?;
+        let (ask, ask_quantity) = self.walk_asks().next()?;
+        let weighted = bid.units() as f64 * ask_quantity as f64 + ask.units() as f64 * bid_quantity as f64;
+        Some(weighted / (bid_quantity + ask_quantity) as f64)
+    }
+
+    pub fn signals(&self, levels: usize) -> BookSignals {
+        BookSignals { levels, imbalance: self.imbalance(levels), microprice: self.microprice() }
+    }
+
+    pub fn position(&self, account_id: u64) -> Option<&Position> {
+        self.positions.position(account_id)
+    }
+
+    /// Realized and unrealized PnL for an account that has traded here.
+    /// Open quantity is marked at `mark_price`, or the last trade price.
+    pub fn pnl(&self, account_id: u64, mark_price: Option<Price>) -> Option<PnlReport> {
+        self.positions.pnl(account_id, mark_price.or(self.last_trade_price))
+    }
+
+    /// Sets how iceberg slices are refreshed. `clock` times the refresh
+    /// interval; a parked iceberg comes back on the next order processed
+    /// after its interval (or on `refresh_icebergs`).
+    pub fn set_iceberg_refresh(&mut self, refresh: IcebergRefresh, clock: Arc<dyn Clock>) {
+        self.icebergs.configure(refresh, Some(clock));
+    }
+
+    /// Icebergs currently off the book waiting out their refresh interval
+    pub fn pending_iceberg_refreshes(&self) -> usize {
+        self.icebergs.pending_len()
+    }
+
+    /// The icebergs counted by `pending_iceberg_refreshes`, reserve included
+    pub fn parked_icebergs(&self) -> Vec<Order> {
+        self.icebergs.pending_orders().cloned().collect()
+    }
+
+    /// Re-queues every parked iceberg whose refresh interval has passed,
+    /// returning how many came back.
+    pub fn refresh_icebergs(&mut self) -> usize {
+        let released = self.release_due_icebergs();
+        if released > 0 {
+            self.after_mutation();
+        }
+        released
+    }
+
+    fn release_due_icebergs(&mut self) -> usize {
+        let due = self.icebergs.take_due();
+        let released = due.len();
+        for order in due {
+            self.requeue(order);
+        }
+        released
+    }
+
+    /// Puts a refreshed iceberg at the back of its level's queue.
+    fn requeue(&mut self, order: Order) {
+        let side = match order.side {
+            OrderSide::Buy => &mut self.bids,
+            OrderSide::Sell => &mut self.asks,
+        };
+        if let Some(tracker) = self.level_metadata.as_mut() {
+            tracker.on_add(order.side, order.price, order.account_id);
+        }
+        self.order_index.insert(order.id, (order.side, order.price));
+        side.entry(order.price).or_default().push_back(order);
+    }
+
+    /// `place_order` for callers with no one to tell: a refused order is
+    /// simply dropped.
+    pub fn add_limit_order(&mut self, order: Order) -> Vec<TradeExecution> {
+        self.place_order(order).unwrap_or_default()
+    }
+
+    /// Matches `order` and rests what is left, or says why the book refused
+    /// it on arrival (closed market, throttled wash-trading account, killed
+    /// fill-or-kill). Unlike `check_order`, the refusal may depend on and
+    /// change book state.
+    pub fn place_order(&mut self, mut order: Order) -> Result<Vec<TradeExecution>, EntryError> {
+        if self.trading_state == TradingState::Closed {
+            return Err(EntryError::new(RejectReason::MarketClosed, "market is closed"));
+        }
+        if let (Some(detector), Some(account)) = (self.wash_trades.as_mut(), order.account_id) {
+            if detector.throttle(account) {
+                return Err(EntryError::new(RejectReason::Throttled, "account throttled for wash trading"));
+            }
+        }
+        self.release_due_icebergs();
+        if order.order_type != OrderType::Limit {
+            self.accepted_ids.insert(order.id);
+            // Parked first, so a stop whose trigger already traded fires right away
+            self.stops.park(order);
+            let executions = self.trigger_stops();
+            self.after_mutation();
+            return Ok(executions);
+        }
+        if let (Some(offset), Some(reference)) = (order.reference_peg_offset, self.reference_price) {
+            order.price = peg_price(reference, offset);
+        }
+        if order.time_in_force == TimeInForce::FillOrKill {
+            let fillable = self.fillable_quantity(&order);
+            if fillable < order.quantity {
+                return Err(EntryError::new(
+                    RejectReason::Killed,
+                    format!("fill-or-kill order {} could fill {} of {}", order.id, fillable, order.quantity),
+                ));
+            }
+        }
+        // Only now, so a killed order can be sent again
+        self.accepted_ids.insert(order.id);
+        let mut executions = self.match_order(&mut order, true);
+
+        // If still quantity left, add to book
+        if order.quantity > 0 {
+            if let Some(offset) = order.reference_peg_offset {
+                self.reference_pegs.insert(order.id, offset);
+            }
+            self.rest(order);
+        }
+        executions.extend(self.trigger_stops());
+        self.after_mutation();
+        Ok(executions)
+    }
+
+    /// Injects every stop the last trade price has reached. Fills from one
+    /// stop move the last price, so this repeats until a pass fires nothing;
+    /// a stop can set off another in the same call.
+    fn trigger_stops(&mut self) -> Vec<TradeExecution> {
+        let mut executions = Vec::new();
+        while let Some(last_price) = self.last_trade_price {
+            let triggered = self.stops.take_triggered(last_price);
+            if triggered.is_empty() {
+                break;
+            }
+            for order in triggered {
+                executions.extend(self.activate_stop(order));
+            }
+        }
+        executions
+    }
+
+    /// Works a triggered stop: a plain stop sweeps like `add_market_order`
+    /// and drops what does not fill, a stop-limit matches and rests at its limit.
+    fn activate_stop(&mut self, mut order: Order) -> Vec<TradeExecution> {
+        match std::mem::take(&mut order.order_type) {
+            OrderType::Stop { .. } => {
+                let Some(deepest) = self.deepest_opposite(order.side) else {
+                    return Vec::new();
+                };
+                order.price = deepest;
+                self.match_order(&mut order, false)
+            }
+            OrderType::StopLimit { limit, .. } => {
+                order.price = limit;
+                let executions = self.match_order(&mut order, true);
+                if order.quantity > 0 {
+                    self.rest(order);
+                }
+                executions
+            }
+            OrderType::Limit => unreachable!("only stops are parked"),
+        }
+    }
+
+    /// Quantity `order` is certain to fill against right now, without touching
+    /// the book. Conservative: orders behind a speed bump or a last look, and
+    /// anything self-trade prevention would skip or stop at, do not count.
+    fn fillable_quantity(&self, order: &Order) -> u64 {
+        let levels: Box<dyn Iterator<Item = (&Price, &VecDeque<Order>)>> = match order.side {
+            OrderSide::Buy => Box::new(self.asks.range(..=order.price)),
+            OrderSide::Sell => Box::new(self.bids.range(order.price..).rev()),
+        };
+        let now_ns = self.speed_bump.as_ref().map(|(_, clock)| clock.now_ns());
+        let mut fillable = 0;
+        for resting in levels.flat_map(|(_, level)| level) {
+            if self.self_trade_prevention != SelfTradePrevention::Off
+                && order.account_id.is_some()
+                && order.account_id == resting.account_id
+            {
+                if self.self_trade_prevention == SelfTradePrevention::CancelIncoming {
+                    break;
+                }
+                continue;
+            }
+            if now_ns.is_some_and(|now| resting.matchable_at_ns > now)
+                || self.last_look.as_ref().is_some_and(|ll| ll.applies_to(resting.account_id))
+            {
+                continue;
+            }
+            // An iceberg's reserve refreshes into the same level, so it is reachable too
+            fillable += resting.quantity + resting.hidden_quantity;
+            if fillable >= order.quantity {
+                break;
+            }
+        }
+        fillable
+    }
+
+    /// Sweeps the opposite side at or better than `order.price`, reducing
+    /// `order.quantity` by whatever filled. `has_limit` is false when
+    /// `order.price` is only a sweep bound the client never chose, which
+    /// leaves nothing to measure price improvement against.
+    fn match_order(&mut self, order: &mut Order, has_limit: bool) -> Vec<TradeExecution> {
+        let mut executions = Vec::new();
+        let fee_version = self.fees.current().version;
+        let fees = self.fees.current().schedule;
+        let stp = self.self_trade_prevention;
+        let same_account = |taker: &Order, maker: &Order| taker.account_id.is_some() && taker.account_id == maker.account_id;
+        let is_self_trade = |taker: &Order, maker: &Order| stp != SelfTradePrevention::Off && same_account(taker, maker);
+        let mut self_crossed = false;
+        let mut stp_action: Option<SelfTradeAction> = None;
+        let now_ns = self.speed_bump.as_ref().map(|(_, clock)| clock.now_ns());
+        // Orders this taker passed over (speed bump, last look), restored once it is done
+        let mut skipped: Vec<(Price, Order)> = Vec::new();
+        let policy = self.match_policy.clone();
+        // Shares of the current level still to hand out, queue order, and the
+        // makers already visited this round that keep resting
+        let mut plan: VecDeque<u64> = VecDeque::new();
+        let mut aside: Vec<(Price, Order)> = Vec::new();
 
         match order.side {
             OrderSide::Buy => {
                 // Check for match against best ask
                 while order.quantity > 0 {
-                    if let Some((&best_ask_price, orders)) = self.asks.iter_mut().next() {
+                    if let Some(mut level) = self.asks.first_entry() {
+                        let best_ask_price = *level.key();
                         if order.price >= best_ask_price {
                             // MATCH!
-                            if let Some(mut matched_order) = orders.pop() {
-                                let match_quantity = std::cmp::min(order.quantity, matched_order.quantity);
+                            let orders = level.get_mut();
+                            if plan.is_empty() {
+                                for (_, maker) in aside.drain(..).rev() {
+                                    orders.push_front(maker);
+                                }
+                                plan = policy.allocate(order.quantity, &mut orders.iter().map(|resting| resting.quantity)).into();
+                                plan.truncate(orders.len());
+                                if plan.iter().sum::<u64>() == 0 {
+                                    // A policy that hands out nothing would leave the book crossed
+                                    plan = FifoPolicy.allocate(order.quantity, &mut orders.iter().map(|resting| resting.quantity)).into();
+                                }
+                            }
+                            if let Some(mut matched_order) = orders.pop_front() {
+                                if now_ns.is_some_and(|now| matched_order.matchable_at_ns > now) {
+                                    skipped.push((best_ask_price, matched_order));
+                                    plan.clear();
+                                    continue;
+                                }
+                                self_crossed |= same_account(order, &matched_order);
+                                if is_self_trade(order, &matched_order) {
+                                    let action = stp_action.get_or_insert_with(|| SelfTradeAction {
+                                        taker_order_id: order.id,
+                                        mode: stp,
+                                        cancelled_resting: Vec::new(),
+                                        cancelled_incoming_quantity: 0,
+                                    });
+                                    if stp == SelfTradePrevention::CancelIncoming {
+                                        orders.push_front(matched_order);
+                                        action.cancelled_incoming_quantity = order.quantity;
+                                        order.quantity = 0;
+                                    } else {
+                                        action.cancelled_resting.push(matched_order.id);
+                                        self.forget_resting(&matched_order);
+                                    }
+                                    plan.clear();
+                                    continue;
+                                }
+                                let share = plan.pop_front().unwrap_or(0);
+                                let match_quantity = order.quantity.min(matched_order.quantity).min(share);
+                                if match_quantity == 0 {
+                                    // No share this round; it keeps its place
+                                    aside.push((best_ask_price, matched_order));
+                                    continue;
+                                }
+                                if let Some(last_look) = self.last_look.as_mut().filter(|ll| ll.applies_to(matched_order.account_id)) {
+                                    let request = LastLookRequest {
+                                        maker_order_id: matched_order.id,
+                                        maker_account_id: matched_order.account_id.unwrap_or_default(),
+                                        taker_order_id: order.id,
+                                        price: best_ask_price,
+                                        quantity: match_quantity,
+                                    };
+                                    if last_look.rejects(&request) {
+                                        skipped.push((best_ask_price, matched_order));
+                                        plan.clear();
+                                        continue;
+                                    }
+                                }
                                 
                                 executions.push(TradeExecution {
                                     maker_order_id: matched_order.id,
                                     taker_order_id: order.id,
                                     price: best_ask_price,
                                     quantity: match_quantity,
+                                    price_improvement: if has_limit { (order.price - best_ask_price) * match_quantity } else { 0 },
+                                    fee_version,
+                                    maker_fee: fees.maker_fee(best_ask_price, match_quantity),
+                                    taker_fee: fees.taker_fee(best_ask_price, match_quantity),
+                                    remaining_quantity: order.quantity - match_quantity,
+                                    is_taker_complete: false,
+                                    symbol: order.symbol.clone(),
                                 });
 
                                 order.quantity -= match_quantity;
                                 matched_order.quantity -= match_quantity;
+                                self.last_trade_price = Some(best_ask_price);
+                                for (account, side) in [(order.account_id, OrderSide::Buy), (matched_order.account_id, OrderSide::Sell)] {
+                                    if let Some(account) = account {
+                                        self.positions.record_fill(account, side, best_ask_price, match_quantity);
+                                    }
+                                }
 
+                                let (maker_id, maker_account) = (matched_order.id, matched_order.account_id);
                                 if matched_order.quantity > 0 {
-                                    orders.push(matched_order); // Put back remaining
+                                    aside.push((best_ask_price, matched_order)); // Back in front once the round is over
+                                } else if let Some(refreshed) = self.icebergs.on_depleted(matched_order) {
+                                    orders.push_back(refreshed); // New slice loses priority
+                                } else {
+                                    self.order_index.remove(&maker_id);
+                                    if let Some(tracker) = self.level_metadata.as_mut() {
+                                        tracker.on_remove(OrderSide::Sell, best_ask_price, maker_account);
+                                    }
                                 }
 
-                                if orders.is_empty() {
-                                    // ideally remove key, but skipping for now to avoid borrow checker complexity in this simple loop
-                                    // In a real engine we'd handle the empty key removal carefully
+                                if orders.is_empty() && aside.is_empty() {
+                                    level.remove();
                                 }
                             } else {
-                                break; // Should be empty
+                                // Emptied by orders passed over above; they come back after the loop
+                                level.remove();
                             }
                         } else {
                             break; // No price match
@@ -98,39 +1275,113 @@ impl OrderBook {
                         break; // No asks
                     }
                 }
-                
-                // If still quantity left, add to book
-                if order.quantity > 0 {
-                    self.bids.entry(order.price)
-                        .or_insert_with(Vec::new)
-                        .push(order);
-                }
             }
             
             OrderSide::Sell => {
                 // Check for match against best bid
                 while order.quantity > 0 {
-                    if let Some((&best_bid_price, orders)) = self.bids.iter_mut().next_back() {
+                    if let Some(mut level) = self.bids.last_entry() {
+                        let best_bid_price = *level.key();
                         if order.price <= best_bid_price {
                             // MATCH!
-                            if let Some(mut matched_order) = orders.pop() {
-                                let match_quantity = std::cmp::min(order.quantity, matched_order.quantity);
+                            let orders = level.get_mut();
+                            if plan.is_empty() {
+                                for (_, maker) in aside.drain(..).rev() {
+                                    orders.push_front(maker);
+                                }
+                                plan = policy.allocate(order.quantity, &mut orders.iter().map(|resting| resting.quantity)).into();
+                                plan.truncate(orders.len());
+                                if plan.iter().sum::<u64>() == 0 {
+                                    // A policy that hands out nothing would leave the book crossed
+                                    plan = FifoPolicy.allocate(order.quantity, &mut orders.iter().map(|resting| resting.quantity)).into();
+                                }
+                            }
+                            if let Some(mut matched_order) = orders.pop_front() {
+                                if now_ns.is_some_and(|now| matched_order.matchable_at_ns > now) {
+                                    skipped.push((best_bid_price, matched_order));
+                                    plan.clear();
+                                    continue;
+                                }
+                                self_crossed |= same_account(order, &matched_order);
+                                if is_self_trade(order, &matched_order) {
+                                    let action = stp_action.get_or_insert_with(|| SelfTradeAction {
+                                        taker_order_id: order.id,
+                                        mode: stp,
+                                        cancelled_resting: Vec::new(),
+                                        cancelled_incoming_quantity: 0,
+                                    });
+                                    if stp == SelfTradePrevention::CancelIncoming {
+                                        orders.push_front(matched_order);
+                                        action.cancelled_incoming_quantity = order.quantity;
+                                        order.quantity = 0;
+                                    } else {
+                                        action.cancelled_resting.push(matched_order.id);
+                                        self.forget_resting(&matched_order);
+                                    }
+                                    plan.clear();
+                                    continue;
+                                }
+                                let share = plan.pop_front().unwrap_or(0);
+                                let match_quantity = order.quantity.min(matched_order.quantity).min(share);
+                                if match_quantity == 0 {
+                                    // No share this round; it keeps its place
+                                    aside.push((best_bid_price, matched_order));
+                                    continue;
+                                }
+                                if let Some(last_look) = self.last_look.as_mut().filter(|ll| ll.applies_to(matched_order.account_id)) {
+                                    let request = LastLookRequest {
+                                        maker_order_id: matched_order.id,
+                                        maker_account_id: matched_order.account_id.unwrap_or_default(),
+                                        taker_order_id: order.id,
+                                        price: best_bid_price,
+                                        quantity: match_quantity,
+                                    };
+                                    if last_look.rejects(&request) {
+                                        skipped.push((best_bid_price, matched_order));
+                                        plan.clear();
+                                        continue;
+                                    }
+                                }
                                 
                                 executions.push(TradeExecution {
                                     maker_order_id: matched_order.id,
                                     taker_order_id: order.id,
                                     price: best_bid_price,
                                     quantity: match_quantity,
+                                    price_improvement: if has_limit { (best_bid_price - order.price) * match_quantity } else { 0 },
+                                    fee_version,
+                                    maker_fee: fees.maker_fee(best_bid_price, match_quantity),
+                                    taker_fee: fees.taker_fee(best_bid_price, match_quantity),
+                                    remaining_quantity: order.quantity - match_quantity,
+                                    is_taker_complete: false,
+                                    symbol: order.symbol.clone(),
                                 });
 
                                 order.quantity -= match_quantity;
                                 matched_order.quantity -= match_quantity;
+                                self.last_trade_price = Some(best_bid_price);
+                                for (account, side) in [(order.account_id, OrderSide::Sell), (matched_order.account_id, OrderSide::Buy)] {
+                                    if let Some(account) = account {
+                                        self.positions.record_fill(account, side, best_bid_price, match_quantity);
+                                    }
+                                }
 
+                                let (maker_id, maker_account) = (matched_order.id, matched_order.account_id);
                                 if matched_order.quantity > 0 {
-                                    orders.push(matched_order);
+                                    aside.push((best_bid_price, matched_order));
+                                } else if let Some(refreshed) = self.icebergs.on_depleted(matched_order) {
+                                    orders.push_back(refreshed);
+                                } else {
+                                    self.order_index.remove(&maker_id);
+                                    if let Some(tracker) = self.level_metadata.as_mut() {
+                                        tracker.on_remove(OrderSide::Buy, best_bid_price, maker_account);
+                                    }
+                                }
+                                if orders.is_empty() && aside.is_empty() {
+                                    level.remove();
                                 }
                             } else {
-                                break;
+                                level.remove();
                             }
                         } else {
                             break;
@@ -139,18 +1390,577 @@ impl OrderBook {
                         break;
                     }
                 }
-                
-                // If still quantity left, add to book
-                if order.quantity > 0 {
-                    self.asks.entry(order.price)
-                        .or_insert_with(Vec::new)
-                        .push(order);
-                }
             }
         }
+        // Makers still resting from the last round, then skipped orders, go
+        // back to the front of their levels in their old order
+        let side = match order.side {
+            OrderSide::Buy => &mut self.asks,
+            OrderSide::Sell => &mut self.bids,
+        };
+        for (price, maker) in aside.into_iter().rev().chain(skipped.into_iter().rev()) {
+            side.entry(price).or_default().push_front(maker);
+        }
+        self.last_self_trade = stp_action;
+        if let Some(last) = executions.last_mut() {
+            last.is_taker_complete = true;
+        }
+        if let (true, Some(detector), Some(account)) = (self_crossed, self.wash_trades.as_mut(), order.account_id) {
+            detector.record_self_cross(account);
+        }
+        for execution in &executions {
+            if self.trailing_prices.len() == self.trailing_prices_capacity {
+                self.trailing_prices.pop_front();
+            }
+            if self.trailing_prices_capacity > 0 {
+                self.trailing_prices.push_back(execution.price);
+            }
+            if let Some(settlement) = self.settlement.as_mut() {
+                settlement.record_trade(execution.price, execution.quantity);
+            }
+            self.session.record(execution.price, execution.quantity);
+            self.recent_trades.push(execution.clone());
+        }
         executions
     }
+
+    fn rest(&mut self, mut order: Order) {
+        self.icebergs.on_rest(&mut order);
+        if let Some((delay_ns, clock)) = &self.speed_bump {
+            order.matchable_at_ns = clock.now_ns() + delay_ns;
+        }
+        if let Some(tracker) = self.level_metadata.as_mut() {
+            tracker.on_add(order.side, order.price, order.account_id);
+        }
+        self.order_index.insert(order.id, (order.side, order.price));
+        let side = match order.side {
+            OrderSide::Buy => &mut self.bids,
+            OrderSide::Sell => &mut self.asks,
+        };
+        side.entry(order.price).or_default().push_back(order);
+    }
+
+    /// Market order that sweeps the book but never fills beyond
+    /// `protection_price`. Whatever is left at the protection price either
+    /// rests there as a limit order or is cancelled, per `remainder`.
+    pub fn add_protected_market_order(
+        &mut self,
+        taker_id: u64,
+        side: OrderSide,
+        quantity: u64,
+        protection_price: impl Into<Price>,
+        remainder: ProtectionRemainder,
+    ) -> MarketOrderResult {
+        self.sweep(taker_id, side, quantity, protection_price.into(), remainder, true)
+    }
+
+    /// Matches a market-style order bounded at `bound`; see `match_order`
+    /// for `has_limit`.
+    fn sweep(
+        &mut self,
+        taker_id: u64,
+        side: OrderSide,
+        quantity: u64,
+        bound: Price,
+        remainder: ProtectionRemainder,
+        has_limit: bool,
+    ) -> MarketOrderResult {
+        if self.trading_state == TradingState::Closed {
+            return MarketOrderResult { executions: Vec::new(), unfilled_quantity: quantity, rested_quantity: 0 };
+        }
+        self.release_due_icebergs();
+        let mut order = Order::new(taker_id, side, bound, quantity);
+        let mut executions = self.match_order(&mut order, has_limit);
+
+        let unfilled_quantity = order.quantity;
+        let mut rested_quantity = 0;
+        if unfilled_quantity > 0 && remainder == ProtectionRemainder::Rest {
+            rested_quantity = unfilled_quantity;
+            self.rest(order);
+        }
+        executions.extend(self.trigger_stops());
+        self.after_mutation();
+
+        MarketOrderResult { executions, unfilled_quantity, rested_quantity }
+    }
+
+    /// Market order that sweeps the opposite side from the best price outward
+    /// until it fills or the side runs dry. Nothing ever rests; whatever the
+    /// book could not fill comes back as `unfilled_quantity`.
+    pub fn add_market_order(&mut self, side: OrderSide, quantity: u64, taker_id: u64) -> MarketOrderResult {
+        match self.deepest_opposite(side) {
+            // The deepest price is only a bound, not a limit the client chose
+            Some(price) => self.sweep(taker_id, side, quantity, price, ProtectionRemainder::Cancel, false),
+            None => MarketOrderResult { executions: Vec::new(), unfilled_quantity: quantity, rested_quantity: 0 },
+        }
+    }
+
+    /// Worst price resting on the side `side` trades against; bounding a
+    /// sweep there reaches every order.
+    fn deepest_opposite(&self, side: OrderSide) -> Option<Price> {
+        match side {
+            OrderSide::Buy => self.asks.keys().next_back().copied(),
+            OrderSide::Sell => self.bids.keys().next().copied(),
+        }
+    }
+
+    /// Protection price `max_slippage_bps` away from the current touch on the
+    /// side `side` would trade against, or `None` if that side is empty.
+    pub fn protection_price(&self, side: OrderSide, max_slippage_bps: u64) -> Option<Price> {
+        match side {
+            // Wide enough in u128 that any bps fits; the price tops out at u64::MAX
+            OrderSide::Buy => self.best_ask().map(|ask| {
+                let slippage = ask.units() as u128 * max_slippage_bps as u128 / 10_000;
+                Price(ask.units().saturating_add(slippage.min(u64::MAX as u128) as u64))
+            }),
+            OrderSide::Sell => self.best_bid().map(|bid| bid - bid.units() * max_slippage_bps.min(10_000) / 10_000),
+        }
+    }
+
+    /// Changes a resting order's price and/or total quantity. A pure size
+    /// decrease keeps its place in the queue (0 removes it); anything else
+    /// is a cancel-replace that goes back through matching, self-trade
+    /// prevention included, before resting again.
+    pub fn amend_order(
+        &mut self,
+        order_id: u64,
+        new_price: Option<Price>,
+        new_quantity: Option<u64>,
+    ) -> Result<Vec<TradeExecution>, AmendError> {
+        let (side, price, index) = self.locate(order_id).ok_or(AmendError::UnknownOrder(order_id))?;
+        let levels = match side {
+            OrderSide::Buy => &mut self.bids,
+            OrderSide::Sell => &mut self.asks,
+        };
+        let level = levels.get_mut(&price).expect("located order's level exists");
+        let current_total = level[index].quantity + level[index].hidden_quantity;
+        let target_total = new_quantity.unwrap_or(current_total);
+        let reprice = new_price.is_some_and(|p| p != price);
+
+        if !reprice && target_total <= current_total && target_total > 0 {
+            let order = &mut level[index];
+            order.quantity = order.quantity.min(target_total);
+            order.hidden_quantity = target_total - order.quantity;
+            self.after_mutation();
+            return Ok(Vec::new());
+        }
+
+        let mut order = level.remove(index).expect("located order is in its level");
+        if level.is_empty() {
+            levels.remove(&price);
+        }
+        self.order_index.remove(&order_id);
+        if let Some(tracker) = self.level_metadata.as_mut() {
+            tracker.on_remove(side, price, order.account_id);
+        }
+        let mut executions = Vec::new();
+        if target_total > 0 {
+            order.price = new_price.unwrap_or(price);
+            order.quantity = target_total;
+            order.hidden_quantity = 0;
+            executions = self.match_order(&mut order, true);
+            if order.quantity > 0 {
+                self.rest(order);
+            }
+            executions.extend(self.trigger_stops());
+        }
+        self.after_mutation();
+        Ok(executions)
+    }
+
+    /// Visible plus hidden quantity of a resting order; `None` if it is not
+    /// on the book
+    pub fn resting_quantity(&self, order_id: u64) -> Option<u64> {
+        let (side, price, index) = self.locate(order_id)?;
+        let levels = match side {
+            OrderSide::Buy => &self.bids,
+            OrderSide::Sell => &self.asks,
+        };
+        levels.get(&price)?.get(index).map(|order| order.quantity + order.hidden_quantity)
+    }
+
+    /// Side, price and queue position of a resting order
+    fn locate(&self, order_id: u64) -> Option<(OrderSide, Price, usize)> {
+        let &(side, price) = self.order_index.get(&order_id)?;
+        let levels = match side {
+            OrderSide::Buy => &self.bids,
+            OrderSide::Sell => &self.asks,
+        };
+        let index = levels.get(&price)?.iter().position(|o| o.id == order_id)?;
+        Some((side, price, index))
+    }
+
+    /// Removes a resting order from the book, dropping its level if it was
+    /// the last one there, or a stop still waiting for its trigger. `None`
+    /// if no order with this id is resting or pending.
+    pub fn cancel_order(&mut self, order_id: u64) -> Option<Order> {
+        let Some((side, price, index)) = self.locate(order_id) else {
+            let stop = self.stops.cancel(order_id)?;
+            self.after_mutation();
+            return Some(stop);
+        };
+        let levels = match side {
+            OrderSide::Buy => &mut self.bids,
+            OrderSide::Sell => &mut self.asks,
+        };
+        let level = levels.get_mut(&price)?;
+        let order = level.remove(index)?;
+        if level.is_empty() {
+            levels.remove(&price);
+        }
+        self.forget_resting(&order);
+        self.after_mutation();
+        Some(order)
+    }
+
+    /// Drops everything kept about a resting order taken off its level
+    /// without trading: its index entry, peg, iceberg timing and level metadata.
+    fn forget_resting(&mut self, order: &Order) {
+        self.order_index.remove(&order.id);
+        self.reference_pegs.remove(&order.id);
+        self.icebergs.on_cancel(order.id);
+        if let Some(tracker) = self.level_metadata.as_mut() {
+            tracker.on_remove(order.side, order.price, order.account_id);
+        }
+    }
+
+    /// Re-applies a journaled or replicated packet the way the engine did,
+    /// skipping the entry checks it already passed. Returns any executions.
+    pub fn apply_action(&mut self, order: Order, action: BookAction) -> Vec<TradeExecution> {
+        match action {
+            BookAction::Submit => self.add_limit_order(order),
+            BookAction::Cancel => {
+                self.cancel_order(order.id);
+                Vec::new()
+            }
+            BookAction::ReferencePrice(price) => self.update_reference_price(price),
+            BookAction::TradingState(state) => {
+                self.set_trading_state(state);
+                Vec::new()
+            }
+        }
+    }
+
+    /// Places an order on its side of the book without matching or any
+    /// validation. Meant for recovery tooling that restores known-good state.
+    pub fn rest_order_unchecked(&mut self, order: Order) {
+        self.rest(order);
+        self.after_mutation();
+    }
+
+    /// Every resting order, bids then asks, low price to high, each level in
+    /// its stored queue order. `restore` puts them back exactly.
+    pub fn resting_orders(&self) -> Vec<Order> {
+        self.bids.values().chain(self.asks.values()).flatten().cloned().collect()
+    }
+
+    /// A book holding `orders` (as from `resting_orders`) at `sequence`,
+    /// without matching. Meant for loading snapshots.
+    pub fn restore(sequence: u64, orders: Vec<Order>) -> Self {
+        let mut book = OrderBook::new();
+        book.load_resting(sequence, orders);
+        book
+    }
+
+    /// `restore` into this empty book, keeping its configuration. Pegged
+    /// orders follow the reference price again from here on.
+    pub fn load_resting(&mut self, sequence: u64, orders: Vec<Order>) {
+        for order in orders {
+            if let Some(offset) = order.reference_peg_offset {
+                self.reference_pegs.insert(order.id, offset);
+            }
+            if let Some(tracker) = self.level_metadata.as_mut() {
+                tracker.on_add(order.side, order.price, order.account_id);
+            }
+            let side = match order.side {
+                OrderSide::Buy => &mut self.bids,
+                OrderSide::Sell => &mut self.asks,
+            };
+            self.order_index.insert(order.id, (order.side, order.price));
+            side.entry(order.price).or_default().push_back(order);
+        }
+        self.sequence = sequence;
+    }
+
+    /// Parks a stop exactly as captured, without checks or a sequence bump.
+    /// Meant for loading snapshots.
+    pub(crate) fn park_stop(&mut self, order: Order) {
+        self.stops.park(order);
+    }
+
+    /// Parks an iceberg waiting for its next slice; it comes back on the
+    /// next order processed. Meant for loading snapshots.
+    pub(crate) fn park_iceberg(&mut self, order: Order) {
+        self.icebergs.park_due(order);
+    }
+
+    /// Sets the reference price without repricing anything. Meant for
+    /// loading snapshots, whose pegs already sit at it.
+    pub(crate) fn restore_reference_price(&mut self, price: Option<Price>) {
+        self.reference_price = price;
+    }
+
+    /// Writes the resting book and pending stops to `path`, recording
+    /// `sequence` as the journal position it reflects.
+    pub fn snapshot_to(&self, path: impl AsRef<Path>, sequence: u64) -> Result<(), WarmStartError> {
+        BookSnapshotFile::capture(self, sequence).save(path)
+    }
+
+    /// A book saved by `snapshot_to`, its sequence set to the recorded
+    /// journal position. Fails if the orders no longer match their checksum.
+    pub fn load_from(path: impl AsRef<Path>) -> Result<OrderBook, WarmStartError> {
+        recover(OrderBook::new, Some(&BookSnapshotFile::load(path)?), &[]).map(|(book, _)| book)
+    }
+
+    /// FNV-1a over every resting order's id, side, price, quantity and
+    /// account, in book order, then the icebergs parked off it. Iceberg
+    /// reserves are hashed too, only where there is one, so books without
+    /// icebergs hash as they always have. Equal books hash equal whatever
+    /// path built them.
+    pub fn checksum(&self) -> u64 {
+        const FNV_OFFSET: u64 = 0xcbf2_9ce4_8422_2325;
+        const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
+        let mut hash = FNV_OFFSET;
+        let mut feed = |value: u64| {
+            for byte in value.to_le_bytes() {
+                hash = (hash ^ byte as u64).wrapping_mul(FNV_PRIME);
+            }
+        };
+        for order in self.bids.values().chain(self.asks.values()).flatten() {
+            feed(order.id);
+            feed(order.side as u64);
+            feed(order.price.units());
+            feed(order.quantity);
+            feed(order.account_id.map_or(u64::MAX, |account| account));
+            if order.hidden_quantity > 0 {
+                feed(order.hidden_quantity);
+            }
+        }
+        for order in self.icebergs.pending_orders() {
+            feed(order.id);
+            feed(order.side as u64);
+            feed(order.price.units());
+            feed(order.hidden_quantity);
+        }
+        hash
+    }
+
+    /// Checks the structural invariants of the book:
+    /// no empty price levels, no zero-quantity orders, every order on the
+    /// right side under its own price, off-tick prices absent, and the book
+    /// not crossed.
+    pub fn validate(&self) -> Result<(), String> {
+        for (label, levels, side) in [("bid", &self.bids, OrderSide::Buy), ("ask", &self.asks, OrderSide::Sell)] {
+            for (&price, orders) in levels {
+                if orders.is_empty() {
+                    return Err(format!("empty {} level at {}", label, price));
+                }
+                for order in orders {
+                    if order.quantity == 0 {
+                        return Err(format!("order {} at {} {} has zero quantity", order.id, label, price));
+                    }
+                    if order.side != side {
+                        return Err(format!("order {} ({:?}) rests on the {} side", order.id, order.side, label));
+                    }
+                    if order.price != price {
+                        return Err(format!("order {} priced {} rests at {} level {}", order.id, order.price, label, price));
+                    }
+                    if let Err(violation) = self.check_tick(price) {
+                        return Err(format!("order {} off tick: {}", order.id, violation));
+                    }
+                    if self.order_index.get(&order.id) != Some(&(side, price)) {
+                        return Err(format!("order {} at {} {} is missing from the id index", order.id, label, price));
+                    }
+                }
+            }
+        }
+        let resting: usize = self.bids.values().chain(self.asks.values()).map(VecDeque::len).sum();
+        if resting != self.order_index.len() {
+            return Err(format!("id index holds {} orders but {} are resting", self.order_index.len(), resting));
+        }
+        if let (Some(bid), Some(ask)) = (self.best_bid(), self.best_ask()) {
+            if bid >= ask {
+                return Err(format!("book is crossed: best bid {} >= best ask {}", bid, ask));
+            }
+        }
+        Ok(())
+    }
+
+    #[inline]
+    fn after_mutation(&mut self) {
+        self.sequence += 1;
+        if self.bbo_history_capacity > 0 {
+            self.record_bbo();
+        }
+
+        #[cfg(debug_assertions)]
+        if self.invariant_check_every != 0 {
+            self.ops_since_check += 1;
+            if self.ops_since_check >= self.invariant_check_every {
+                self.ops_since_check = 0;
+                if let Err(violation) = self.validate() {
+                    panic!("order book invariant violated: {}\nbook: {}", violation, self.to_json());
+                }
+            }
+        }
+    }
     
+    /// Highest bid price with resting quantity
+    pub fn best_bid(&self) -> Option<Price> {
+        self.bids.keys().next_back().copied()
+    }
+
+    /// Lowest ask price with resting quantity
+    pub fn best_ask(&self) -> Option<Price> {
+        self.asks.keys().next().copied()
+    }
+
+    /// Best ask minus best bid; `None` unless both sides are quoted
+    pub fn spread(&self) -> Option<u64> {
+        match (self.best_bid(), self.best_ask()) {
+            (Some(bid), Some(ask)) => Some(ask.units().saturating_sub(bid.units())),
+            _ => None,
+        }
+    }
+
+    fn record_bbo(&mut self) {
+        let (bid, ask) = (self.best_bid(), self.best_ask());
+        if let Some(&(_, last_bid, last_ask)) = self.bbo_history.back() {
+            if (last_bid, last_ask) == (bid, ask) {
+                return;
+            }
+        }
+        if self.bbo_history.len() == self.bbo_history_capacity {
+            self.bbo_history.pop_front();
+        }
+        self.bbo_history.push_back((self.sequence, bid, ask));
+    }
+
+    /// Ask levels from the best price outward as `(price, total_quantity)`
+    pub fn walk_asks(&self) -> impl Iterator<Item = (Price, u64)> + '_ {
+        self.asks.iter()
+            .map(|(&price, orders)| (price, orders.iter().map(|o| o.quantity).sum::<u64>()))
+            .filter(|&(_, quantity)| quantity > 0)
+    }
+
+    /// Bid levels from the best price outward as `(price, total_quantity)`
+    pub fn walk_bids(&self) -> impl Iterator<Item = (Price, u64)> + '_ {
+        self.bids.iter().rev()
+            .map(|(&price, orders)| (price, orders.iter().map(|o| o.quantity).sum::<u64>()))
+            .filter(|&(_, quantity)| quantity > 0)
+    }
+
+    /// What an order on `side` for `target_qty` would cost if it swept the
+    /// opposite side right now: `(average_price, worst_price, filled_qty)`.
+    /// `filled_qty` falls short of the target when liquidity runs out;
+    /// `None` means there is nothing to trade against.
+    pub fn price_for_quantity(&self, side: OrderSide, target_qty: u64) -> Option<(f64, Price, u64)> {
+        let levels: Box<dyn Iterator<Item = (Price, u64)>> = match side {
+            OrderSide::Buy => Box::new(self.walk_asks()),
+            OrderSide::Sell => Box::new(self.walk_bids()),
+        };
+
+        let mut filled = 0u64;
+        let mut notional = 0u128;
+        let mut worst_price = None;
+        for (price, quantity) in levels {
+            if filled >= target_qty {
+                break;
+            }
+            let take = quantity.min(target_qty - filled);
+            filled += take;
+            notional += price.units() as u128 * take as u128;
+            worst_price = Some(price);
+        }
+
+        worst_price.map(|worst| (notional as f64 / filled as f64, worst, filled))
+    }
+
+    /// Aggregated levels on both sides, for diffing later with `diff`
+    pub fn snapshot(&self) -> OrderBookSnapshot {
+        OrderBookSnapshot {
+            sequence: self.sequence,
+            bids: self.walk_bids().collect(),
+            asks: self.walk_asks().collect(),
+        }
+    }
+
+    /// Level changes that bring `earlier` up to the current book.
+    pub fn diff(&self, earlier: &OrderBookSnapshot) -> Vec<LevelChange> {
+        earlier.diff(&self.snapshot())
+    }
+
+    /// Depth chart for one side of the book: `(price, cumulative_quantity)`
+    /// from the touch outward, at most `max_levels` levels.
+    pub fn depth_curve(&self, side: OrderSide, max_levels: usize) -> Vec<(Price, u64)> {
+        let levels: Box<dyn Iterator<Item = (Price, u64)>> = match side {
+            OrderSide::Buy => Box::new(self.walk_bids()),
+            OrderSide::Sell => Box::new(self.walk_asks()),
+        };
+        levels
+            .take(max_levels)
+            .scan(0u64, |cumulative, (price, quantity)| {
+                *cumulative += quantity;
+                Some((price, *cumulative))
+            })
+            .collect()
+    }
+
+    /// Aggregated levels from the touch outward, at most `max_levels` per
+    /// side, with their metadata when the book keeps it.
+    pub fn level_depth(&self, max_levels: usize) -> LevelDepth {
+        let detail = |side: OrderSide, (price, quantity): (Price, u64)| LevelDetail {
+            price,
+            quantity,
+            metadata: self.level_metadata(side, price),
+        };
+        LevelDepth {
+            bids: self.walk_bids().take(max_levels).map(|level| detail(OrderSide::Buy, level)).collect(),
+            asks: self.walk_asks().take(max_levels).map(|level| detail(OrderSide::Sell, level)).collect(),
+        }
+    }
+
+    /// The best `levels` price levels per side with their total visible
+    /// quantity and order count.
+    pub fn depth(&self, levels: usize) -> DepthSnapshot {
+        let level = |(&price, orders): (&Price, &VecDeque<Order>)| DepthLevel {
+            price,
+            qty: orders.iter().map(|o| o.quantity).sum(),
+            count: orders.len(),
+        };
+        let bids: Vec<DepthLevel> = self.bids.iter().rev().take(levels).map(level).collect();
+        let asks: Vec<DepthLevel> = self.asks.iter().take(levels).map(level).collect();
+        let checksum = levels_checksum(&asks, &bids);
+        DepthSnapshot { bids, asks, checksum }
+    }
+
+    /// CRC32 of the best `levels` levels per side, as `/api/depth` serves it
+    /// and a client holding the same levels would compute it.
+    pub fn depth_checksum(&self, levels: usize) -> u32 {
+        self.depth(levels).checksum
+    }
+
+    /// Top of book plus at most `depth` (capped at `MAX_SNAPSHOT_DEPTH`)
+    /// aggregated levels per side.
+    pub fn symbol_depth(&self, depth: usize) -> SymbolDepth {
+        let depth = depth.min(MAX_SNAPSHOT_DEPTH);
+        SymbolDepth {
+            sequence: self.sequence,
+            best_bid: self.best_bid(),
+            best_ask: self.best_ask(),
+            bids: self.walk_bids().take(depth).collect(),
+            asks: self.walk_asks().take(depth).collect(),
+        }
+    }
+
+    /// Both sides of the depth chart
+    pub fn depth_curves(&self, max_levels: usize) -> DepthCurves {
+        DepthCurves {
+            bids: self.depth_curve(OrderSide::Buy, max_levels),
+            asks: self.depth_curve(OrderSide::Sell, max_levels),
+        }
+    }
+
     pub fn to_json(&self) -> String {
         serde_json::json!({
             "bids": self.bids.iter().map(|(price, orders)| {
@@ -167,4 +1977,171 @@ impl OrderBook {
             }).collect::<Vec<_>>()
         }).to_string()
     }
+
+    /// Every resting order as one JSON object per line, in `resting_orders`
+    /// order. Written order by order, so the export is never held whole.
+    /// Returns how many orders were written.
+    pub fn export_ndjson(&self, writer: impl Write) -> std::io::Result<usize> {
+        write_ndjson(self.bids.values().chain(self.asks.values()).flatten(), writer)
+    }
+}
+
+/// `orders` as NDJSON, one line each, the format `export_ndjson` writes
+pub fn write_ndjson<'a>(orders: impl IntoIterator<Item = &'a Order>, mut writer: impl Write) -> std::io::Result<usize> {
+    let mut written = 0;
+    for order in orders {
+        serde_json::to_writer(&mut writer, order)?;
+        writer.write_all(b"\n")?;
+        written += 1;
+    }
+    writer.flush()?;
+    Ok(written)
+}
+
+/// Where a reference peg sits: `reference + offset`, never below 1
+fn peg_price(reference: Price, offset: i64) -> Price {
+    Price(reference.units().saturating_add_signed(offset).max(1))
+}
+
+// ============================================================================
+// MATCHING ENGINE - One order book per symbol
+// ============================================================================
+
+/// Independent books keyed by instrument symbol, plus any spread
+/// instruments implied from them.
+#[derive(Default)]
+pub struct MatchingEngine {
+    books: HashMap<String, OrderBook>,
+    spreads: HashMap<String, SpreadBook>,
+}
+
+impl MatchingEngine {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn get_or_create(&mut self, symbol: &str) -> &mut OrderBook {
+        self.books.entry(symbol.to_string()).or_default()
+    }
+
+    pub fn book(&self, symbol: &str) -> Option<&OrderBook> {
+        self.books.get(symbol)
+    }
+
+    /// `symbol_depth(depth)` for every symbol
+    pub fn snapshot_all(&self, depth: usize) -> BTreeMap<String, SymbolDepth> {
+        self.books.iter().map(|(symbol, book)| (symbol.clone(), book.symbol_depth(depth))).collect()
+    }
+
+    /// Symbols with a book, sorted
+    pub fn symbols(&self) -> Vec<String> {
+        let mut symbols: Vec<String> = self.books.keys().cloned().collect();
+        symbols.sort();
+        symbols
+    }
+
+    /// Pushes an external reference price into `symbol`'s book, repricing
+    /// its reference-pegged orders, and refreshes the spreads it is a leg of.
+    pub fn update_reference_price(&mut self, symbol: &str, price: impl Into<Price>) -> Vec<TradeExecution> {
+        let executions = self.get_or_create(symbol).update_reference_price(price);
+        self.refresh_spreads(symbol);
+        executions
+    }
+
+    /// Matches `order` in `symbol`'s book (creating it on first use) and
+    /// refreshes every spread that has `symbol` as a leg.
+    pub fn add_limit_order(&mut self, symbol: &str, order: Order) -> Vec<TradeExecution> {
+        let executions = self.get_or_create(symbol).add_limit_order(order);
+        self.refresh_spreads(symbol);
+        executions
+    }
+
+    /// `add_limit_order` on the order's own symbol, `DEFAULT_BOOK_SYMBOL`
+    /// if it names none.
+    pub fn route_order(&mut self, order: Order) -> Vec<TradeExecution> {
+        let symbol = order.symbol.clone().unwrap_or_else(|| DEFAULT_BOOK_SYMBOL.to_string());
+        self.add_limit_order(&symbol, order)
+    }
+
+    /// Registers a spread instrument; both legs get books if missing.
+    pub fn add_spread(&mut self, definition: SpreadDefinition) {
+        self.get_or_create(&definition.front_leg);
+        self.get_or_create(&definition.back_leg);
+        let name = definition.name.clone();
+        self.spreads.insert(name.clone(), SpreadBook { definition, implied: ImpliedTop::default() });
+        self.refresh_spread(&name);
+    }
+
+    /// Current implied top of book for a spread instrument
+    pub fn implied_top(&self, spread: &str) -> Option<ImpliedTop> {
+        self.spreads.get(spread).map(|book| book.implied)
+    }
+
+    fn refresh_spreads(&mut self, leg: &str) {
+        let affected: Vec<String> = self.spreads.values()
+            .filter(|spread| spread.definition.front_leg == leg || spread.definition.back_leg == leg)
+            .map(|spread| spread.definition.name.clone())
+            .collect();
+        for name in affected {
+            self.refresh_spread(&name);
+        }
+    }
+
+    fn refresh_spread(&mut self, name: &str) {
+        let Some(spread) = self.spreads.get(name) else {
+            return;
+        };
+        let front = self.books.get(&spread.definition.front_leg);
+        let back = self.books.get(&spread.definition.back_leg);
+        let implied = match (front, back) {
+            (Some(front), Some(back)) => ImpliedTop::from_legs(front, back),
+            _ => ImpliedTop::default(),
+        };
+        if let Some(spread) = self.spreads.get_mut(name) {
+            spread.implied = implied;
+        }
+    }
+}
+
+/// A calendar-style spread: buying one unit of the spread buys the front
+/// leg and sells the back leg, so its price is `front - back`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SpreadDefinition {
+    pub name: String,
+    pub front_leg: String,
+    pub back_leg: String,
+}
+
+/// Best implied prices for a spread as `(price, quantity)`. Spread prices
+/// can be negative.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct ImpliedTop {
+    pub bid: Option<(i64, u64)>,
+    pub ask: Option<(i64, u64)>,
+}
+
+impl ImpliedTop {
+    /// Implied-in pricing: selling the spread means hitting the front bid
+    /// and lifting the back ask, so the implied bid is `front_bid - back_ask`
+    /// (and symmetrically for the ask). Size is capped by the thinner leg.
+    fn from_legs(front: &OrderBook, back: &OrderBook) -> Self {
+        let bid = match (front.walk_bids().next(), back.walk_asks().next()) {
+            (Some((front_bid, front_qty)), Some((back_ask, back_qty))) => {
+                Some((front_bid.units() as i64 - back_ask.units() as i64, front_qty.min(back_qty)))
+            }
+            _ => None,
+        };
+        let ask = match (front.walk_asks().next(), back.walk_bids().next()) {
+            (Some((front_ask, front_qty)), Some((back_bid, back_qty))) => {
+                Some((front_ask.units() as i64 - back_bid.units() as i64, front_qty.min(back_qty)))
+            }
+            _ => None,
+        };
+        ImpliedTop { bid, ask }
+    }
+}
+
+struct SpreadBook {
+    definition: SpreadDefinition,
+    implied: ImpliedTop,
 }
@@ -0,0 +1,372 @@
+// ============================================================================
+// METRICS - Shared engine statistics read by the HTTP API
+// ============================================================================
+
+use crate::auth::ApiKeys;
+use crate::bbo::SpreadHistory;
+use crate::clock::{process_clock, Clock};
+use crate::events::EventBus;
+use crate::funnel::FunnelStats;
+use crate::gateway::GatewayRoutes;
+use crate::histogram::{fine_buckets_ns, LatencyHistogram};
+use crate::ingress::IngressStats;
+use crate::matching_engine::{OrderBook, SymbolDepth, TradeExecution};
+use crate::shards::{Shard, ShardOccupancy, ShardRouter};
+use std::collections::{BTreeMap, VecDeque};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::fmt::Write;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Span `/api/metrics` measures orders per second over
+pub const THROUGHPUT_WINDOW: Duration = Duration::from_secs(10);
+
+/// An engine heartbeat older than this makes `/readyz` report not ready
+pub const ENGINE_STALL_THRESHOLD: Duration = Duration::from_millis(500);
+
+/// Orders, trades and end-to-end latency through the engine since startup
+pub struct EngineCounters {
+    started_at: Instant,
+    orders_processed: AtomicU64,
+    trades_executed: AtomicU64,
+    /// Packets left in the ring after the engine's latest pop
+    ring_occupancy: AtomicU64,
+    /// `process_clock()` time of the engine loop's latest iteration; 0 until
+    /// an engine starts
+    heartbeat_ns: AtomicU64,
+    /// Gateway ingest to the engine picking the order up, 100ns to 1s
+    queue_latency: LatencyHistogram,
+    /// Gateway ingest to the end of matching, 100ns to 1s
+    end_to_end_latency: LatencyHistogram,
+    /// `(when, orders_processed)` at each throughput read, oldest first
+    samples: Mutex<VecDeque<(Instant, u64)>>,
+}
+
+impl Default for EngineCounters {
+    fn default() -> Self {
+        EngineCounters {
+            started_at: Instant::now(),
+            orders_processed: AtomicU64::new(0),
+            trades_executed: AtomicU64::new(0),
+            ring_occupancy: AtomicU64::new(0),
+            heartbeat_ns: AtomicU64::new(0),
+            queue_latency: LatencyHistogram::new(&fine_buckets_ns(100, 1_000_000_000)),
+            end_to_end_latency: LatencyHistogram::new(&fine_buckets_ns(100, 1_000_000_000)),
+            samples: Mutex::new(VecDeque::new()),
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct Metrics {
+    /// Total price improvement earned by each taker account
+    price_improvement: Mutex<BTreeMap<u64, u64>>,
+    /// 4xx responses
+    http_client_errors: AtomicU64,
+    /// 500 responses
+    http_server_errors: AtomicU64,
+    /// Responses that could not be written back to the client
+    http_respond_failures: AtomicU64,
+    /// Time spent inside the matching call for each order
+    match_latency: LatencyHistogram,
+    /// Gateway traffic, overall and per connection
+    ingress: Arc<IngressStats>,
+    /// Orders queued in the producer funnel ahead of the ring buffer
+    funnel: Arc<FunnelStats>,
+    /// Per-symbol rings, when the engine is sharded
+    shards: Mutex<Vec<Arc<Shard>>>,
+    /// Orders the engine matched after shutdown was requested
+    drained_on_shutdown: AtomicU64,
+    /// Spread at each published BBO, fed by the `BboPublisher`
+    spread_history: Arc<Mutex<SpreadHistory>>,
+    /// Books served by `/api/orderbook/all`, by symbol
+    symbol_books: Mutex<BTreeMap<String, Arc<Mutex<OrderBook>>>>,
+    /// Engine event feed behind `/api/stream/trades`
+    event_bus: Mutex<Option<Arc<EventBus>>>,
+    /// Keys the HTTP server requires on POST and DELETE
+    api_keys: Mutex<Arc<ApiKeys>>,
+    /// Where HTTP orders, cancels and commands enter the engines
+    order_entry: Mutex<Option<GatewayRoutes>>,
+    /// Orders matched, trades executed and uptime for `/api/metrics`
+    engine: EngineCounters,
+}
+
+impl Metrics {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Metrics with custom match-latency bucket bounds (nanoseconds).
+    pub fn with_latency_buckets(bounds_ns: &[u64]) -> Self {
+        Metrics { match_latency: LatencyHistogram::new(bounds_ns), ..Self::default() }
+    }
+
+    pub fn match_latency(&self) -> &LatencyHistogram {
+        &self.match_latency
+    }
+
+    pub fn queue_latency(&self) -> &LatencyHistogram {
+        &self.engine.queue_latency
+    }
+
+    pub fn end_to_end_latency(&self) -> &LatencyHistogram {
+        &self.engine.end_to_end_latency
+    }
+
+    pub fn ingress(&self) -> &Arc<IngressStats> {
+        &self.ingress
+    }
+
+    pub fn spread_history(&self) -> &Arc<Mutex<SpreadHistory>> {
+        &self.spread_history
+    }
+
+    pub fn funnel(&self) -> &Arc<FunnelStats> {
+        &self.funnel
+    }
+
+    /// Reports the router's rings from now on.
+    pub fn register_shards(&self, router: &ShardRouter) {
+        self.shards.lock().unwrap().extend(router.shards());
+    }
+
+    /// Includes `book` in `/api/orderbook/all` under `symbol`, and routes
+    /// HTTP orders and `?symbol=` reads for that symbol to it.
+    pub fn register_symbol_book(&self, symbol: &str, book: Arc<Mutex<OrderBook>>) {
+        self.symbol_books.lock().unwrap().insert(symbol.to_string(), book);
+    }
+
+    /// The book registered under `symbol`
+    pub fn symbol_book(&self, symbol: &str) -> Option<Arc<Mutex<OrderBook>>> {
+        self.symbol_books.lock().unwrap().get(symbol).cloned()
+    }
+
+    /// `OrderBook::symbol_depth` for every registered book. Each book is
+    /// locked only while its own snapshot is taken, so every symbol is
+    /// internally consistent without stalling all engines at once.
+    pub fn snapshot_all_symbols(&self, depth: usize) -> BTreeMap<String, SymbolDepth> {
+        let books: Vec<(String, Arc<Mutex<OrderBook>>)> =
+            self.symbol_books.lock().unwrap().iter().map(|(symbol, book)| (symbol.clone(), book.clone())).collect();
+        books.into_iter().map(|(symbol, book)| (symbol, book.lock().unwrap().symbol_depth(depth))).collect()
+    }
+
+    /// Serves `/api/stream/trades` from `bus`; trades must be published
+    /// there (an offloaded `TradeSink` with the same bus does it).
+    pub fn set_event_bus(&self, bus: Arc<EventBus>) {
+        *self.event_bus.lock().unwrap() = Some(bus);
+    }
+
+    pub fn event_bus(&self) -> Option<Arc<EventBus>> {
+        self.event_bus.lock().unwrap().clone()
+    }
+
+    /// Requires one of `keys` on mutating HTTP requests from now on.
+    pub fn set_api_keys(&self, keys: ApiKeys) {
+        *self.api_keys.lock().unwrap() = Arc::new(keys);
+    }
+
+    pub fn api_keys(&self) -> Arc<ApiKeys> {
+        self.api_keys.lock().unwrap().clone()
+    }
+
+    /// Sends HTTP book changes through `routes`, whose results the engines
+    /// must complete, so they are journaled and replicated like gateway orders.
+    pub fn set_order_entry(&self, routes: GatewayRoutes) {
+        *self.order_entry.lock().unwrap() = Some(routes);
+    }
+
+    pub fn order_entry(&self) -> Option<GatewayRoutes> {
+        self.order_entry.lock().unwrap().clone()
+    }
+
+    pub fn shard_occupancy(&self) -> Vec<ShardOccupancy> {
+        self.shards.lock().unwrap().iter().map(|shard| shard.occupancy()).collect()
+    }
+
+    /// Prometheus text exposition of everything scrapeable.
+    pub fn render_prometheus(&self) -> String {
+        let mut out = self.match_latency.render_prometheus(
+            "match_latency_seconds",
+            "Time spent matching a single order in the engine",
+        );
+        out.push_str(&self.engine.queue_latency.render_prometheus(
+            "queue_latency_seconds",
+            "Time from gateway ingest until the engine picks the order up",
+        ));
+        out.push_str(&self.engine.end_to_end_latency.render_prometheus(
+            "end_to_end_latency_seconds",
+            "Time from gateway ingest to the end of matching",
+        ));
+        let ingress = self.ingress.totals();
+        for (name, help, value) in [
+            ("orders_total", "Orders run through the matcher", self.orders_processed()),
+            ("trades_total", "Trades executed by the matcher", self.trades_executed()),
+            ("gateway_bytes_read_total", "Bytes read from gateway clients", ingress.bytes_read),
+            ("gateway_orders_parsed_total", "Orders parsed by the gateway", ingress.orders_parsed),
+            ("gateway_parse_errors_total", "Gateway lines that failed to parse", ingress.parse_errors),
+            ("gateway_abrupt_disconnects_total", "Gateway clients gone mid-message or on a read error", self.ingress.abrupt_disconnects()),
+            ("gateway_refused_connections_total", "Gateway connections refused at the connection limit", self.ingress.refused_connections()),
+            ("funnel_backpressure_total", "Orders turned away by the in-flight cap", self.funnel.rejected()),
+        ] {
+            let _ = writeln!(out, "# HELP {} {}\n# TYPE {} counter\n{} {}", name, help, name, name, value);
+        }
+        let _ = writeln!(
+            out,
+            "# HELP gateway_active_connections Open gateway connections\n# TYPE gateway_active_connections gauge\ngateway_active_connections {}",
+            self.ingress.active_connections()
+        );
+        let _ = writeln!(
+            out,
+            "# HELP funnel_in_flight Orders accepted by the funnel but not yet in the ring buffer\n# TYPE funnel_in_flight gauge\nfunnel_in_flight {}",
+            self.funnel.in_flight()
+        );
+        let _ = writeln!(
+            out,
+            "# HELP ring_buffer_occupancy Orders waiting in the engine's ring buffer\n# TYPE ring_buffer_occupancy gauge\nring_buffer_occupancy {}",
+            self.ring_occupancy()
+        );
+        let shards = self.shard_occupancy();
+        if !shards.is_empty() {
+            let _ = writeln!(out, "# HELP shard_ring_capacity Ring buffer capacity per symbol\n# TYPE shard_ring_capacity gauge");
+            for shard in &shards {
+                let _ = writeln!(out, "shard_ring_capacity{{symbol=\"{}\"}} {}", shard.symbol, shard.capacity);
+            }
+            let _ = writeln!(out, "# HELP shard_ring_occupancy Orders waiting in each symbol's ring\n# TYPE shard_ring_occupancy gauge");
+            for shard in &shards {
+                let _ = writeln!(out, "shard_ring_occupancy{{symbol=\"{}\"}} {}", shard.symbol, shard.occupied);
+            }
+            let _ = writeln!(out, "# HELP shard_orders_dropped_total Orders dropped because a symbol's ring was full\n# TYPE shard_orders_dropped_total counter");
+            for shard in &shards {
+                let _ = writeln!(out, "shard_orders_dropped_total{{symbol=\"{}\"}} {}", shard.symbol, shard.dropped);
+            }
+        }
+        out
+    }
+
+    /// Credits the taker's account with the improvement on its fills.
+    /// Anonymous orders are not aggregated.
+    pub fn record_price_improvement(&self, taker_account: Option<u64>, executions: &[TradeExecution]) {
+        let Some(account) = taker_account else {
+            return;
+        };
+        let improvement: u64 = executions.iter().map(|exec| exec.price_improvement).sum();
+        if improvement > 0 {
+            *self.price_improvement.lock().unwrap().entry(account).or_default() += improvement;
+        }
+    }
+
+    pub fn price_improvement_by_account(&self) -> BTreeMap<u64, u64> {
+        self.price_improvement.lock().unwrap().clone()
+    }
+
+    /// Counts one order through the matcher and the trades it produced.
+    pub fn record_order_processed(&self, trades: usize) {
+        self.engine.orders_processed.fetch_add(1, Ordering::Relaxed);
+        self.engine.trades_executed.fetch_add(trades as u64, Ordering::Relaxed);
+    }
+
+    /// Trades set off by something other than an incoming order, such as
+    /// pegs repriced by a reference price update
+    pub fn record_trades(&self, trades: usize) {
+        self.engine.trades_executed.fetch_add(trades as u64, Ordering::Relaxed);
+    }
+
+    pub fn orders_processed(&self) -> u64 {
+        self.engine.orders_processed.load(Ordering::Relaxed)
+    }
+
+    pub fn trades_executed(&self) -> u64 {
+        self.engine.trades_executed.load(Ordering::Relaxed)
+    }
+
+    /// Sampled by the engine after each pop. With per-symbol shards every
+    /// engine writes here; `shard_ring_occupancy` breaks it down.
+    pub fn record_ring_occupancy(&self, slots: usize) {
+        self.engine.ring_occupancy.store(slots as u64, Ordering::Relaxed);
+    }
+
+    pub fn ring_occupancy(&self) -> u64 {
+        self.engine.ring_occupancy.load(Ordering::Relaxed)
+    }
+
+    /// Called by the engine on every loop iteration.
+    pub fn record_engine_heartbeat(&self) {
+        // Never store 0, which means no engine has run yet
+        self.engine.heartbeat_ns.store(process_clock().now_ns().max(1), Ordering::Relaxed);
+    }
+
+    /// Time since the engine last went round its loop; `None` if it never has.
+    pub fn engine_heartbeat_age(&self) -> Option<Duration> {
+        match self.engine.heartbeat_ns.load(Ordering::Relaxed) {
+            0 => None,
+            beat => Some(Duration::from_nanos(process_clock().now_ns().saturating_sub(beat))),
+        }
+    }
+
+    pub fn uptime(&self) -> Duration {
+        self.engine.started_at.elapsed()
+    }
+
+    /// Orders per second since the newest earlier read at least
+    /// `THROUGHPUT_WINDOW` ago, or since startup if there is none.
+    pub fn orders_per_second(&self) -> f64 {
+        let now = Instant::now();
+        let orders = self.orders_processed();
+        let mut samples = self.engine.samples.lock().unwrap();
+        while samples.len() > 1 && now.duration_since(samples[1].0) >= THROUGHPUT_WINDOW {
+            samples.pop_front();
+        }
+        let (since, base) = match samples.front() {
+            Some(&(at, count)) if now.duration_since(at) >= THROUGHPUT_WINDOW => (at, count),
+            _ => (self.engine.started_at, 0),
+        };
+        samples.push_back((now, orders));
+        let elapsed = now.duration_since(since).as_secs_f64();
+        if elapsed > 0.0 { (orders - base) as f64 / elapsed } else { 0.0 }
+    }
+
+    pub fn record_drained_on_shutdown(&self, orders: u64) {
+        self.drained_on_shutdown.fetch_add(orders, Ordering::Relaxed);
+    }
+
+    pub fn drained_on_shutdown(&self) -> u64 {
+        self.drained_on_shutdown.load(Ordering::Relaxed)
+    }
+
+    pub fn record_http_client_error(&self) {
+        self.http_client_errors.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn record_http_server_error(&self) {
+        self.http_server_errors.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn record_http_respond_failure(&self) {
+        self.http_respond_failures.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn http_client_errors(&self) -> u64 {
+        self.http_client_errors.load(Ordering::Relaxed)
+    }
+
+    pub fn http_server_errors(&self) -> u64 {
+        self.http_server_errors.load(Ordering::Relaxed)
+    }
+
+    pub fn http_respond_failures(&self) -> u64 {
+        self.http_respond_failures.load(Ordering::Relaxed)
+    }
+}
+
+/// Prometheus gauges for the shape of `book`, appended to `render_prometheus`
+/// by whoever holds the book.
+pub fn render_book_prometheus(book: &OrderBook) -> String {
+    let mut out = String::new();
+    for (name, help, levels) in [
+        ("orderbook_bid_levels", "Bid price levels with resting quantity", book.walk_bids().count()),
+        ("orderbook_ask_levels", "Ask price levels with resting quantity", book.walk_asks().count()),
+    ] {
+        let _ = writeln!(out, "# HELP {} {}\n# TYPE {} gauge\n{} {}", name, help, name, name, levels);
+    }
+    out
+}
@@ -0,0 +1,132 @@
+// ============================================================================
+// ORDER GENERATOR - Deterministic synthetic flow for load tests
+// ============================================================================
+// The same seed and parameters always produce the same stream, so two
+// benchmark runs see identical traffic and a failing stress run can be
+// reproduced exactly.
+
+use crate::matching_engine::{Order, OrderSide};
+use crate::rng::SplitMix64;
+
+/// How far from the reference price orders land
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PriceDistribution {
+    /// Every offset in `-max_offset..=max_offset` equally likely
+    Uniform { max_offset: u64 },
+    /// Offsets cluster around the reference, thinning out linearly towards
+    /// `±max_offset`
+    Triangular { max_offset: u64 },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SizeDistribution {
+    Uniform { min: u64, max: u64 },
+    /// Mostly small orders with an occasional large one, averaging `mean`
+    Geometric { mean: u64 },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GeneratorParams {
+    pub reference_price: u64,
+    /// Share of new orders that are buys, `0.0..=1.0`
+    pub buy_ratio: f64,
+    pub prices: PriceDistribution,
+    pub sizes: SizeDistribution,
+    /// Chance that an event cancels an earlier order instead of adding one
+    pub cancel_rate: f64,
+}
+
+impl Default for GeneratorParams {
+    fn default() -> Self {
+        GeneratorParams {
+            reference_price: 10_000,
+            buy_ratio: 0.5,
+            prices: PriceDistribution::Uniform { max_offset: 50 },
+            sizes: SizeDistribution::Uniform { min: 1, max: 100 },
+            cancel_rate: 0.0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum GeneratedOrder {
+    New(Order),
+    /// Cancel an order this generator produced earlier
+    Cancel { order_id: u64 },
+}
+
+/// An endless, reproducible stream of `GeneratedOrder`s; order ids count up
+/// from 1.
+pub struct OrderGenerator {
+    rng: SplitMix64,
+    params: GeneratorParams,
+    next_id: u64,
+    /// Ids still eligible for a cancel
+    live: Vec<u64>,
+}
+
+impl OrderGenerator {
+    pub fn new(seed: u64, params: GeneratorParams) -> Self {
+        OrderGenerator { rng: SplitMix64::new(seed), params, next_id: 1, live: Vec::new() }
+    }
+
+    pub fn params(&self) -> &GeneratorParams {
+        &self.params
+    }
+
+    /// Only new orders, for drivers that have no use for cancels
+    pub fn new_orders(self) -> impl Iterator<Item = Order> {
+        self.filter_map(|generated| match generated {
+            GeneratedOrder::New(order) => Some(order),
+            GeneratedOrder::Cancel { .. } => None,
+        })
+    }
+
+    fn price(&mut self) -> u64 {
+        let reference = self.params.reference_price as i128;
+        let offset = match self.params.prices {
+            PriceDistribution::Uniform { max_offset } => {
+                self.rng.range(0, 2 * max_offset) as i128 - max_offset as i128
+            }
+            PriceDistribution::Triangular { max_offset } => {
+                self.rng.range(0, max_offset) as i128 - self.rng.range(0, max_offset) as i128
+            }
+        };
+        (reference + offset).max(1) as u64
+    }
+
+    fn size(&mut self) -> u64 {
+        match self.params.sizes {
+            SizeDistribution::Uniform { min, max } => self.rng.range(min.max(1), max.max(1)),
+            SizeDistribution::Geometric { mean } => {
+                // Inverse CDF of a geometric distribution on 1, 2, 3, ...
+                let p = 1.0 / mean.max(1) as f64;
+                if p >= 1.0 {
+                    return 1;
+                }
+                let u = 1.0 - self.rng.next_f64();
+                (u.ln() / (1.0 - p).ln()).floor() as u64 + 1
+            }
+        }
+    }
+}
+
+impl Iterator for OrderGenerator {
+    type Item = GeneratedOrder;
+
+    fn next(&mut self) -> Option<GeneratedOrder> {
+        if !self.live.is_empty() && self.rng.next_f64() < self.params.cancel_rate {
+            let index = self.rng.range(0, self.live.len() as u64 - 1) as usize;
+            let order_id = self.live.swap_remove(index);
+            return Some(GeneratedOrder::Cancel { order_id });
+        }
+
+        let id = self.next_id;
+        self.next_id += 1;
+        let side = if self.rng.next_f64() < self.params.buy_ratio { OrderSide::Buy } else { OrderSide::Sell };
+        let price = self.price();
+        let quantity = self.size();
+        self.live.push(id);
+        Some(GeneratedOrder::New(Order::new(id, side, price, quantity)))
+    }
+}
@@ -0,0 +1,77 @@
+// ============================================================================
+// ORDER IDS - Recently accepted ids, so retried orders are not booked twice
+// ============================================================================
+// A client that times out and resends cannot tell whether its first copy
+// arrived. The book remembers the ids it accepted and refuses a repeat as a
+// duplicate. The window is bounded: past its capacity the oldest id is
+// forgotten, so memory stays flat however long the session runs, and an id
+// that far back may be used again.
+
+use std::collections::{HashSet, VecDeque};
+
+/// Accepted ids remembered per book when `DUPLICATE_WINDOW` is not set
+pub const DEFAULT_DUPLICATE_WINDOW: usize = 100_000;
+
+#[derive(Debug, Clone, Default)]
+pub struct RecentOrderIds {
+    ids: HashSet<u64>,
+    /// The same ids, oldest first, for eviction
+    arrival: VecDeque<u64>,
+    /// 0 remembers nothing, so no id is ever a duplicate
+    capacity: usize,
+}
+
+impl RecentOrderIds {
+    pub fn new(capacity: usize) -> Self {
+        RecentOrderIds { ids: HashSet::new(), arrival: VecDeque::new(), capacity }
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Changes the cap, forgetting the oldest ids beyond it.
+    pub fn set_capacity(&mut self, capacity: usize) {
+        self.capacity = capacity;
+        while self.arrival.len() > capacity {
+            self.evict_oldest();
+        }
+    }
+
+    pub fn contains(&self, id: u64) -> bool {
+        self.ids.contains(&id)
+    }
+
+    /// Remembers `id`, forgetting the oldest once the cap is reached.
+    /// False if it was already remembered.
+    pub fn insert(&mut self, id: u64) -> bool {
+        if self.capacity == 0 || self.ids.contains(&id) {
+            return false;
+        }
+        if self.arrival.len() == self.capacity {
+            self.evict_oldest();
+        }
+        self.ids.insert(id);
+        self.arrival.push_back(id);
+        true
+    }
+
+    /// Remembered ids, oldest first
+    pub fn iter(&self) -> impl Iterator<Item = u64> + '_ {
+        self.arrival.iter().copied()
+    }
+
+    pub fn len(&self) -> usize {
+        self.arrival.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.arrival.is_empty()
+    }
+
+    fn evict_oldest(&mut self) {
+        if let Some(oldest) = self.arrival.pop_front() {
+            self.ids.remove(&oldest);
+        }
+    }
+}
@@ -0,0 +1,111 @@
+// ============================================================================
+// ORDER RESULTS - Handing the engine's outcome back to the submitter
+// ============================================================================
+// The gateway only sees an order up to the funnel; matching happens on the
+// engine thread. A submitter registers before pushing and puts the ticket it
+// gets on the packet; the engine completes that ticket once the book has
+// processed the packet, and the submitter picks the outcome up from its own
+// one-shot channel. Tickets are handed out here, never by clients, so two
+// submissions that share an order id (or commands that carry none) are
+// never answered with each other's outcome.
+
+use crate::matching_engine::{Order, TradeExecution};
+use crate::rejections::EntryError;
+use crate::settlement::Settlement;
+use crossbeam_channel::{bounded, Receiver, Sender};
+use serde_json::json;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+
+/// What the engine did with one submitted packet. A reference price
+/// update reports the repriced pegs' fills as `Executed`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum OrderOutcome {
+    /// Applied to the book: the order's own fills as taker (empty if it
+    /// rested or parked without trading) and how much of it is now resting
+    Executed { executions: Vec<TradeExecution>, rested_quantity: u64 },
+    /// Refused by the book's entry checks
+    Rejected(EntryError),
+    /// A cancel went through: the order taken off the book, or `None` if
+    /// nothing with that id was resting or parked
+    Cancelled(Option<Order>),
+    /// The session was opened or closed; closing it settles the session
+    TradingStateSet(Option<Settlement>),
+}
+
+/// The ack the gateway and HTTP both send for an order the book took:
+/// `filled` once all of `quantity` traded, `partially_filled` if some of
+/// it did, `accepted` if none did, always with both quantities.
+pub fn executed_ack(executions: &[TradeExecution], quantity: u64, rested_quantity: u64) -> serde_json::Value {
+    let filled: u64 = executions.iter().map(|e| e.quantity).sum();
+    let status = match filled {
+        0 => "accepted",
+        filled if filled >= quantity => "filled",
+        _ => "partially_filled",
+    };
+    json!({
+        "status": status,
+        "executions": executions,
+        "filled_quantity": filled,
+        "rested_quantity": rested_quantity
+    })
+}
+
+/// A registration waiting for the engine
+pub struct PendingResult {
+    ticket: u64,
+    rx: Receiver<OrderOutcome>,
+}
+
+impl PendingResult {
+    /// Goes on the packet as `Packet::ticket`
+    pub fn ticket(&self) -> u64 {
+        self.ticket
+    }
+
+    pub fn receiver(&self) -> &Receiver<OrderOutcome> {
+        &self.rx
+    }
+}
+
+/// Correlation map shared by submitters and the engine
+#[derive(Default)]
+pub struct OrderResults {
+    /// Senders by ticket
+    waiting: Mutex<HashMap<u64, Sender<OrderOutcome>>>,
+    next_ticket: AtomicU64,
+}
+
+impl OrderResults {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Call before submitting, so the engine cannot finish first.
+    pub fn register(&self) -> PendingResult {
+        let (tx, rx) = bounded(1);
+        let ticket = self.next_ticket.fetch_add(1, Ordering::Relaxed);
+        self.waiting.lock().unwrap().insert(ticket, tx);
+        PendingResult { ticket, rx }
+    }
+
+    /// Withdraws a registration whose packet never reached the engine.
+    pub fn forget(&self, pending: PendingResult) {
+        self.waiting.lock().unwrap().remove(&pending.ticket);
+    }
+
+    /// Answers the registration behind `ticket`, if it is still waiting.
+    pub fn complete(&self, ticket: u64, outcome: OrderOutcome) {
+        let sender = self.waiting.lock().unwrap().remove(&ticket);
+        if let Some(tx) = sender {
+            // The submitter may have given up waiting
+            let _ = tx.send(outcome);
+        }
+    }
+
+    /// Registrations still waiting for the engine
+    pub fn waiting(&self) -> usize {
+        self.waiting.lock().unwrap().len()
+    }
+}
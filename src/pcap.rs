@@ -0,0 +1,278 @@
+// ============================================================================
+// PCAP EXTRACTION - Recover gateway orders from a packet capture
+// ============================================================================
+// Reads a classic libpcap file, keeps the client-to-gateway direction of
+// every TCP connection to `gateway_port`, reassembles each stream in
+// sequence order (dropping retransmitted bytes and holding back segments
+// that arrive early), and splits it into newline-delimited orders exactly as
+// the gateway would. IPv4 over Ethernet, Linux cooked, loopback and raw IP
+// link types are understood.
+
+use crate::matching_engine::Order;
+use crate::replay::RecordedOrder;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+
+const LINKTYPE_NULL: u32 = 0;
+const LINKTYPE_ETHERNET: u32 = 1;
+const LINKTYPE_RAW: u32 = 101;
+const LINKTYPE_LINUX_SLL: u32 = 113;
+
+const ETHERTYPE_IPV4: u16 = 0x0800;
+const ETHERTYPE_VLAN: u16 = 0x8100;
+const IP_PROTO_TCP: u8 = 6;
+
+const TCP_FIN: u8 = 0x01;
+const TCP_SYN: u8 = 0x02;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PcapError {
+    /// The file ends in the middle of a header or record
+    Truncated(&'static str),
+    BadMagic(u32),
+    UnsupportedLinkType(u32),
+}
+
+impl fmt::Display for PcapError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PcapError::Truncated(what) => write!(f, "capture is truncated in {}", what),
+            PcapError::BadMagic(magic) => write!(f, "not a pcap file (magic {:#010x})", magic),
+            PcapError::UnsupportedLinkType(link) => write!(f, "unsupported link type {}", link),
+        }
+    }
+}
+
+impl std::error::Error for PcapError {}
+
+/// Orders sent to the gateway on port `gateway_port`, in the order the
+/// gateway would have read them, stamped with the capture time of the
+/// packet that completed each line.
+pub fn orders_from_pcap(data: &[u8], gateway_port: u16) -> Result<Vec<RecordedOrder>, PcapError> {
+    let header = data.get(..24).ok_or(PcapError::Truncated("file header"))?;
+    let magic = u32::from_le_bytes(header[0..4].try_into().unwrap());
+    let (big_endian, nanos) = match magic {
+        0xa1b2_c3d4 => (false, false),
+        0xa1b2_3c4d => (false, true),
+        0xd4c3_b2a1 => (true, false),
+        0x4d3c_b2a1 => (true, true),
+        other => return Err(PcapError::BadMagic(other)),
+    };
+    let read_u32 = |bytes: &[u8]| {
+        let bytes: [u8; 4] = bytes.try_into().unwrap();
+        if big_endian { u32::from_be_bytes(bytes) } else { u32::from_le_bytes(bytes) }
+    };
+    let link_type = read_u32(&header[20..24]);
+    if ![LINKTYPE_NULL, LINKTYPE_ETHERNET, LINKTYPE_RAW, LINKTYPE_LINUX_SLL].contains(&link_type) {
+        return Err(PcapError::UnsupportedLinkType(link_type));
+    }
+
+    let mut streams: HashMap<FlowKey, TcpStream> = HashMap::new();
+    let mut orders = Vec::new();
+    let mut offset = 24;
+    while offset < data.len() {
+        let record = data.get(offset..offset + 16).ok_or(PcapError::Truncated("record header"))?;
+        let ts_sec = read_u32(&record[0..4]) as u64;
+        let ts_frac = read_u32(&record[4..8]) as u64;
+        let captured = read_u32(&record[8..12]) as usize;
+        let frame = data.get(offset + 16..offset + 16 + captured).ok_or(PcapError::Truncated("packet data"))?;
+        offset += 16 + captured;
+
+        let recv_ns = ts_sec * 1_000_000_000 + if nanos { ts_frac } else { ts_frac * 1_000 };
+        let Some(segment) = ip_packet(frame, link_type).and_then(tcp_segment) else {
+            continue;
+        };
+        if segment.key.dst_port != gateway_port {
+            continue;
+        }
+        let stream = streams.entry(segment.key).or_default();
+        for order in stream.receive(&segment) {
+            orders.push(RecordedOrder { recv_ns, order });
+        }
+    }
+    Ok(orders)
+}
+
+/// Orders in an already reassembled client byte stream, e.g. a raw dump of
+/// what one client wrote. Lines that do not parse are skipped, as the
+/// gateway would have rejected them; a trailing unterminated line is still
+/// read, as the gateway does at end of stream.
+pub fn orders_from_stream(data: &[u8]) -> Vec<Order> {
+    let mut lines = LineSplitter::default();
+    let mut orders = lines.push(data);
+    orders.extend(lines.finish());
+    orders
+}
+
+// ============================================================================
+// PACKET DECODING
+// ============================================================================
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+struct FlowKey {
+    src: [u8; 4],
+    dst: [u8; 4],
+    src_port: u16,
+    dst_port: u16,
+}
+
+struct TcpSegment<'a> {
+    key: FlowKey,
+    seq: u32,
+    flags: u8,
+    payload: &'a [u8],
+}
+
+fn be16(bytes: &[u8], at: usize) -> Option<u16> {
+    Some(u16::from_be_bytes(bytes.get(at..at + 2)?.try_into().ok()?))
+}
+
+/// The IPv4 packet inside a link-layer frame, if it carries one
+fn ip_packet(frame: &[u8], link_type: u32) -> Option<&[u8]> {
+    match link_type {
+        LINKTYPE_ETHERNET => {
+            let mut ethertype = be16(frame, 12)?;
+            let mut start = 14;
+            if ethertype == ETHERTYPE_VLAN {
+                ethertype = be16(frame, 16)?;
+                start = 18;
+            }
+            (ethertype == ETHERTYPE_IPV4).then(|| frame.get(start..)).flatten()
+        }
+        LINKTYPE_LINUX_SLL => (be16(frame, 14)? == ETHERTYPE_IPV4).then(|| frame.get(16..)).flatten(),
+        // 4-byte address family in host order; AF_INET is 2 everywhere
+        LINKTYPE_NULL => frame.get(4..).filter(|packet| packet.first().is_some_and(|b| b >> 4 == 4)),
+        LINKTYPE_RAW => Some(frame),
+        _ => None,
+    }
+}
+
+fn tcp_segment(packet: &[u8]) -> Option<TcpSegment<'_>> {
+    let version_ihl = *packet.first()?;
+    if version_ihl >> 4 != 4 || *packet.get(9)? != IP_PROTO_TCP {
+        return None;
+    }
+    let ip_header_len = (version_ihl & 0x0f) as usize * 4;
+    // Total length bounds the payload; Ethernet may have padded the frame
+    let total_len = (be16(packet, 2)? as usize).min(packet.len());
+    let tcp = packet.get(ip_header_len..total_len)?;
+    let tcp_header_len = (*tcp.get(12)? >> 4) as usize * 4;
+    Some(TcpSegment {
+        key: FlowKey {
+            src: packet.get(12..16)?.try_into().ok()?,
+            dst: packet.get(16..20)?.try_into().ok()?,
+            src_port: be16(tcp, 0)?,
+            dst_port: be16(tcp, 2)?,
+        },
+        seq: u32::from_be_bytes(tcp.get(4..8)?.try_into().ok()?),
+        flags: *tcp.get(13)?,
+        payload: tcp.get(tcp_header_len..)?,
+    })
+}
+
+// ============================================================================
+// STREAM REASSEMBLY
+// ============================================================================
+
+/// One direction of a TCP connection
+#[derive(Default)]
+struct TcpStream {
+    /// Sequence number of the next byte we expect; unknown until the SYN or
+    /// the first data segment
+    next_seq: Option<u32>,
+    /// Segments that arrived ahead of a gap, keyed by sequence number
+    early: BTreeMap<u32, Vec<u8>>,
+    lines: LineSplitter,
+    finished: bool,
+}
+
+impl TcpStream {
+    /// Feeds one segment, returning any orders it completed.
+    fn receive(&mut self, segment: &TcpSegment) -> Vec<Order> {
+        if self.finished {
+            return Vec::new();
+        }
+        if segment.flags & TCP_SYN != 0 {
+            self.next_seq = Some(segment.seq.wrapping_add(1));
+            return Vec::new();
+        }
+        let next = *self.next_seq.get_or_insert(segment.seq);
+
+        let mut orders = Vec::new();
+        // Signed distance copes with sequence numbers wrapping around
+        let ahead = segment.seq.wrapping_sub(next) as i32;
+        if ahead > 0 {
+            if !segment.payload.is_empty() {
+                self.early.entry(segment.seq).or_insert_with(|| segment.payload.to_vec());
+            }
+        } else {
+            orders.extend(self.accept(segment.seq, segment.payload));
+            orders.extend(self.drain_early());
+        }
+
+        if segment.flags & TCP_FIN != 0 && self.early.is_empty() {
+            self.finished = true;
+            orders.extend(self.lines.finish());
+        }
+        orders
+    }
+
+    /// Appends the part of `payload` past `next_seq`; retransmitted bytes
+    /// we already have are dropped.
+    fn accept(&mut self, seq: u32, payload: &[u8]) -> Vec<Order> {
+        let next = self.next_seq.expect("set before accepting data");
+        let already_seen = next.wrapping_sub(seq) as usize;
+        if already_seen >= payload.len() {
+            return Vec::new();
+        }
+        let fresh = &payload[already_seen..];
+        self.next_seq = Some(next.wrapping_add(fresh.len() as u32));
+        self.lines.push(fresh)
+    }
+
+    fn drain_early(&mut self) -> Vec<Order> {
+        let mut orders = Vec::new();
+        loop {
+            let next = self.next_seq.expect("set before draining");
+            // Anything starting at or before `next` can now be applied
+            let ready = self.early.keys().copied().find(|&seq| next.wrapping_sub(seq) as i32 >= 0);
+            let Some(seq) = ready else {
+                break;
+            };
+            let payload = self.early.remove(&seq).unwrap();
+            orders.extend(self.accept(seq, &payload));
+        }
+        orders
+    }
+}
+
+/// Splits a byte stream into lines and parses each as an order
+#[derive(Default)]
+struct LineSplitter {
+    partial: Vec<u8>,
+}
+
+impl LineSplitter {
+    fn push(&mut self, bytes: &[u8]) -> Vec<Order> {
+        self.partial.extend_from_slice(bytes);
+        let mut orders = Vec::new();
+        while let Some(newline) = self.partial.iter().position(|&b| b == b'\n') {
+            let line: Vec<u8> = self.partial.drain(..=newline).collect();
+            orders.extend(parse_line(&line));
+        }
+        orders
+    }
+
+    fn finish(&mut self) -> Option<Order> {
+        let line = std::mem::take(&mut self.partial);
+        parse_line(&line)
+    }
+}
+
+fn parse_line(line: &[u8]) -> Option<Order> {
+    let text = std::str::from_utf8(line).ok()?.trim();
+    if text.is_empty() {
+        return None;
+    }
+    serde_json::from_str(text).ok()
+}
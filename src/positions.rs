@@ -0,0 +1,98 @@
+// ============================================================================
+// POSITIONS - Net position, average entry and PnL per account
+// ============================================================================
+// Positions are signed: positive is long, negative is short. Reducing a
+// position realizes PnL against the average entry price; trading through
+// zero closes the old position and opens the remainder at the fill price.
+
+use crate::matching_engine::OrderSide;
+use crate::price_units::Price;
+use serde::Serialize;
+use std::collections::HashMap;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize)]
+pub struct Position {
+    pub quantity: i64,
+    /// Average price of the open quantity; 0 when flat
+    pub avg_entry_price: f64,
+    pub realized_pnl: f64,
+}
+
+impl Position {
+    pub fn apply_fill(&mut self, side: OrderSide, price: Price, quantity: u64) {
+        let signed = match side {
+            OrderSide::Buy => quantity as i64,
+            OrderSide::Sell => -(quantity as i64),
+        };
+        let price = price.units() as f64;
+
+        if self.quantity == 0 || self.quantity.signum() == signed.signum() {
+            // Opening or adding: blend into the average entry
+            let open = self.quantity.unsigned_abs() as f64;
+            self.avg_entry_price = (open * self.avg_entry_price + quantity as f64 * price) / (open + quantity as f64);
+            self.quantity += signed;
+            return;
+        }
+
+        let closed = quantity.min(self.quantity.unsigned_abs());
+        self.realized_pnl += closed as f64 * (price - self.avg_entry_price) * self.quantity.signum() as f64;
+        self.quantity += signed;
+        if self.quantity == 0 {
+            self.avg_entry_price = 0.0;
+        } else if quantity > closed {
+            // Flipped sides: what is left was opened at this fill
+            self.avg_entry_price = price;
+        }
+    }
+
+    /// Open quantity marked against `mark_price`
+    pub fn unrealized_pnl(&self, mark_price: Price) -> f64 {
+        if self.quantity == 0 {
+            return 0.0;
+        }
+        self.quantity as f64 * (mark_price.units() as f64 - self.avg_entry_price)
+    }
+}
+
+/// Scoreboard line for one account
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct PnlReport {
+    pub account_id: u64,
+    pub position: i64,
+    pub avg_entry_price: f64,
+    pub realized_pnl: f64,
+    /// `None` when there is no mark price yet
+    pub mark_price: Option<Price>,
+    pub unrealized_pnl: f64,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct PositionTracker {
+    positions: HashMap<u64, Position>,
+}
+
+impl PositionTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn record_fill(&mut self, account_id: u64, side: OrderSide, price: Price, quantity: u64) {
+        self.positions.entry(account_id).or_default().apply_fill(side, price, quantity);
+    }
+
+    pub fn position(&self, account_id: u64) -> Option<&Position> {
+        self.positions.get(&account_id)
+    }
+
+    pub fn pnl(&self, account_id: u64, mark_price: Option<Price>) -> Option<PnlReport> {
+        let position = self.positions.get(&account_id)?;
+        Some(PnlReport {
+            account_id,
+            position: position.quantity,
+            avg_entry_price: position.avg_entry_price,
+            realized_pnl: position.realized_pnl,
+            mark_price,
+            unrealized_pnl: mark_price.map_or(0.0, |mark| position.unrealized_pnl(mark)),
+        })
+    }
+}
@@ -0,0 +1,58 @@
+// ============================================================================
+// PRICE BAND - Refuse orders priced too far from the last trade
+// ============================================================================
+// A fat-fingered price can sweep the book many levels deep before anyone
+// notices. The band only lets orders in within a percentage of the last
+// trade; until the book has traded there is nothing to anchor it to.
+
+use crate::price_units::Price;
+use serde::Serialize;
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PriceBand {
+    max_band_pct: f64,
+}
+
+/// A price outside the band around `reference`
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct BandViolation {
+    pub price: Price,
+    pub reference: Price,
+    pub max_band_pct: f64,
+}
+
+impl fmt::Display for BandViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "price {} is more than {}% away from the last trade {}", self.price, self.max_band_pct, self.reference)
+    }
+}
+
+impl std::error::Error for BandViolation {}
+
+impl PriceBand {
+    /// `max_band_pct` must be a positive, finite percentage.
+    pub fn new(max_band_pct: f64) -> Result<Self, String> {
+        if !max_band_pct.is_finite() || max_band_pct <= 0.0 {
+            return Err(format!("price band must be a positive percentage, got {}", max_band_pct));
+        }
+        Ok(PriceBand { max_band_pct })
+    }
+
+    pub fn max_band_pct(&self) -> f64 {
+        self.max_band_pct
+    }
+
+    /// Accepts anything while there is no reference price.
+    pub fn validate(&self, price: Price, reference: Option<Price>) -> Result<(), BandViolation> {
+        let Some(reference) = reference.filter(|&r| r > 0) else {
+            return Ok(());
+        };
+        let distance_pct = price.abs_diff(reference) as f64 / reference.units() as f64 * 100.0;
+        if distance_pct <= self.max_band_pct {
+            Ok(())
+        } else {
+            Err(BandViolation { price, reference, max_band_pct: self.max_band_pct })
+        }
+    }
+}
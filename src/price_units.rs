@@ -0,0 +1,284 @@
+// ============================================================================
+// PRICE UNITS - Decimal JSON prices to integer engine prices
+// ============================================================================
+// The engine prices in integers; people (and browsers) send decimals. With
+// `decimals` configured, a JSON price of 100.5 means 100.5 whole units and
+// becomes 100.5 * 10^decimals. The conversion works on the number's decimal
+// text, never on a float multiply, so it is exact or it is an error.
+//
+// Inside the engine a price is a `Price`: that scaled integer, wrapped so it
+// can't be mixed up with a quantity or an id. The scale is the book's
+// (`OrderBook::price_decimals`), not the value's, so two prices only compare
+// meaningfully at the same scale; `rescale` moves between them.
+
+use crate::matching_engine::Order;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::ops::{Add, AddAssign, Sub, SubAssign};
+
+/// A fixed-point price: whole units times `10^scale`, as an integer.
+/// Serializes as the bare integer, so the JSON is the same as a `u64`'s.
+/// `Price - Price` is the distance between them in the same units, and a
+/// price moves by a `u64` distance.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Price(pub u64);
+
+impl Price {
+    pub const ZERO: Price = Price(0);
+    pub const MAX: Price = Price(u64::MAX);
+
+    /// The scaled integer
+    pub const fn units(self) -> u64 {
+        self.0
+    }
+
+    /// `text` (like `100.5` or `1.25e2`) at `scale` decimals, exactly.
+    pub fn parse(text: &str, scale: u32) -> Result<Price, PriceParseError> {
+        scale_price(text, scale).map(Price)
+    }
+
+    /// Decimal text at `scale`, with all `scale` fractional digits.
+    pub fn to_decimal(self, scale: u32) -> String {
+        let digits = format!("{:0>width$}", self.0, width = scale as usize + 1);
+        let (whole, fraction) = digits.split_at(digits.len() - scale as usize);
+        if fraction.is_empty() {
+            whole.to_string()
+        } else {
+            format!("{}.{}", whole, fraction)
+        }
+    }
+
+    /// The same price at another scale; None if it would lose digits or overflow.
+    pub fn rescale(self, from: u32, to: u32) -> Option<Price> {
+        if to >= from {
+            10u64.checked_pow(to - from).and_then(|factor| self.0.checked_mul(factor)).map(Price)
+        } else {
+            let factor = 10u64.checked_pow(from - to)?;
+            self.0.is_multiple_of(factor).then_some(Price(self.0 / factor))
+        }
+    }
+
+    pub fn checked_add(self, distance: u64) -> Option<Price> {
+        self.0.checked_add(distance).map(Price)
+    }
+
+    pub fn checked_sub(self, distance: u64) -> Option<Price> {
+        self.0.checked_sub(distance).map(Price)
+    }
+
+    pub fn saturating_sub(self, distance: u64) -> Price {
+        Price(self.0.saturating_sub(distance))
+    }
+
+    /// Moved by a signed distance, as a peg offset does; None below zero or on overflow.
+    pub fn checked_offset(self, offset: i64) -> Option<Price> {
+        self.0.checked_add_signed(offset).map(Price)
+    }
+
+    /// Halfway between two prices, rounded down
+    pub fn midpoint(self, other: Price) -> Price {
+        Price(self.0.midpoint(other.0))
+    }
+
+    /// How far apart two prices are, whichever is higher
+    pub fn abs_diff(self, other: Price) -> u64 {
+        self.0.abs_diff(other.0)
+    }
+
+    /// Price times `quantity`, in price units; None on overflow.
+    pub fn notional(self, quantity: u64) -> Option<u64> {
+        self.0.checked_mul(quantity)
+    }
+
+    /// Whether this price sits on a grid of `tick` units
+    pub fn is_on_tick(self, tick: u64) -> bool {
+        tick != 0 && self.0.is_multiple_of(tick)
+    }
+}
+
+impl From<u64> for Price {
+    fn from(units: u64) -> Self {
+        Price(units)
+    }
+}
+
+impl From<Price> for u64 {
+    fn from(price: Price) -> Self {
+        price.0
+    }
+}
+
+impl PartialEq<u64> for Price {
+    fn eq(&self, units: &u64) -> bool {
+        self.0 == *units
+    }
+}
+
+impl PartialOrd<u64> for Price {
+    fn partial_cmp(&self, units: &u64) -> Option<std::cmp::Ordering> {
+        self.0.partial_cmp(units)
+    }
+}
+
+impl PartialEq<Price> for u64 {
+    fn eq(&self, price: &Price) -> bool {
+        *self == price.0
+    }
+}
+
+impl PartialOrd<Price> for u64 {
+    fn partial_cmp(&self, price: &Price) -> Option<std::cmp::Ordering> {
+        self.partial_cmp(&price.0)
+    }
+}
+
+impl fmt::Display for Price {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl Add<u64> for Price {
+    type Output = Price;
+
+    fn add(self, distance: u64) -> Price {
+        Price(self.0 + distance)
+    }
+}
+
+impl AddAssign<u64> for Price {
+    fn add_assign(&mut self, distance: u64) {
+        self.0 += distance;
+    }
+}
+
+impl Sub<u64> for Price {
+    type Output = Price;
+
+    fn sub(self, distance: u64) -> Price {
+        Price(self.0 - distance)
+    }
+}
+
+impl SubAssign<u64> for Price {
+    fn sub_assign(&mut self, distance: u64) {
+        self.0 -= distance;
+    }
+}
+
+impl Sub for Price {
+    type Output = u64;
+
+    /// The distance from `other` up to `self`; panics (in debug) if `other` is higher.
+    fn sub(self, other: Price) -> u64 {
+        self.0 - other.0
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PriceParseError {
+    /// Not a plain non-negative decimal number
+    Malformed(String),
+    /// More fractional digits than the configured decimals
+    TooPrecise { price: String, decimals: u32 },
+    /// Does not fit in a u64 once scaled
+    Overflow(String),
+}
+
+impl fmt::Display for PriceParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PriceParseError::Malformed(price) => write!(f, "price {} is not a non-negative number", price),
+            PriceParseError::TooPrecise { price, decimals } => {
+                write!(f, "price {} has more than {} decimal places", price, decimals)
+            }
+            PriceParseError::Overflow(price) => write!(f, "price {} is out of range", price),
+        }
+    }
+}
+
+impl std::error::Error for PriceParseError {}
+
+/// Exact `text * 10^decimals` for a decimal like `100.5` or `1.25e2`.
+pub fn scale_price(text: &str, decimals: u32) -> Result<u64, PriceParseError> {
+    let malformed = || PriceParseError::Malformed(text.to_string());
+    let (mantissa, exponent) = match text.split_once(['e', 'E']) {
+        Some((mantissa, exponent)) => (mantissa, exponent.parse::<i32>().map_err(|_| malformed())?),
+        None => (text, 0),
+    };
+    let (whole, fraction) = mantissa.split_once('.').unwrap_or((mantissa, ""));
+    if whole.is_empty() || !whole.bytes().chain(fraction.bytes()).all(|b| b.is_ascii_digit()) {
+        return Err(malformed());
+    }
+
+    // All digits as one integer, with the decimal point `shift` places from the right
+    let digits = format!("{}{}", whole, fraction);
+    let digits = digits.trim_start_matches('0');
+    let shift = fraction.len() as i64 - exponent as i64 - decimals as i64;
+    let (digits, shift) = if shift > 0 {
+        let cut = digits.len().saturating_sub(shift as usize);
+        if digits[cut..].bytes().any(|b| b != b'0') {
+            return Err(PriceParseError::TooPrecise { price: text.to_string(), decimals });
+        }
+        (&digits[..cut], 0)
+    } else {
+        (digits, -shift)
+    };
+
+    let overflow = || PriceParseError::Overflow(text.to_string());
+    let mut scaled = if digits.is_empty() { 0 } else { digits.parse::<u64>().map_err(|_| overflow())? };
+    if scaled > 0 {
+        for _ in 0..shift {
+            scaled = scaled.checked_mul(10).ok_or_else(overflow)?;
+        }
+    }
+    Ok(scaled)
+}
+
+/// Parses an HTTP order body, converting its JSON `price`, any stop
+/// `trigger` and stop-limit `limit`, and any `reference_peg_offset` with
+/// `scale_price`.
+/// With 0 decimals, integer prices pass through unchanged and `100.0` is
+/// accepted as 100.
+pub fn order_from_json(json: &str, decimals: u32) -> Result<Order, String> {
+    let mut order: serde_json::Map<String, serde_json::Value> = serde_json::from_str(json).map_err(|e| e.to_string())?;
+    if let Some(price) = order.get_mut("price") {
+        scale_json_price(price, decimals)?;
+    }
+    // `{"Stop": {"trigger": ..}}` or `{"StopLimit": {"trigger": .., "limit": ..}}`
+    if let Some(serde_json::Value::Object(order_type)) = order.get_mut("order_type") {
+        for stop in order_type.values_mut().filter_map(serde_json::Value::as_object_mut) {
+            for field in ["trigger", "limit"] {
+                if let Some(price) = stop.get_mut(field) {
+                    scale_json_price(price, decimals)?;
+                }
+            }
+        }
+    }
+    // A peg offset is a signed price distance, scaled the same way
+    if let Some(offset) = order.get_mut("reference_peg_offset") {
+        let serde_json::Value::Number(number) = offset else {
+            return Err(PriceParseError::Malformed(offset.to_string()).to_string());
+        };
+        let text = number.to_string();
+        let (negative, magnitude) = match text.strip_prefix('-') {
+            Some(magnitude) => (true, magnitude),
+            None => (false, text.as_str()),
+        };
+        let scaled = scale_price(magnitude, decimals).map_err(|e| e.to_string())?;
+        let scaled = i64::try_from(scaled).map_err(|_| PriceParseError::Overflow(text.clone()).to_string())?;
+        *offset = (if negative { -scaled } else { scaled }).into();
+    }
+    serde_json::from_value(serde_json::Value::Object(order)).map_err(|e| e.to_string())
+}
+
+/// Replaces a JSON number with its `scale_price` integer
+fn scale_json_price(price: &mut serde_json::Value, decimals: u32) -> Result<(), String> {
+    let serde_json::Value::Number(number) = price else {
+        return Err(PriceParseError::Malformed(price.to_string()).to_string());
+    };
+    let scaled = scale_price(&number.to_string(), decimals).map_err(|e| e.to_string())?;
+    *price = scaled.into();
+    Ok(())
+}
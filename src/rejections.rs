@@ -0,0 +1,181 @@
+// ============================================================================
+// REJECTIONS - Structured record of why orders were turned away
+// ============================================================================
+// Every rejection is counted by reason. One in `sample_every` of each reason
+// is also published on the event bus and kept in a short log, which bounds
+// the volume when a misbehaving client gets rejected in a tight loop.
+// Rejections never happen on the match path, so the locks here are cheap.
+
+use crate::clock::Clock;
+use crate::events::{BookEvent, EventBus};
+use crate::matching_engine::{Order, OrderSide};
+use crate::price_units::Price;
+use serde::Serialize;
+use std::collections::{BTreeMap, VecDeque};
+use std::fmt;
+use std::sync::{Arc, Mutex};
+
+/// Rejections kept for `/api/rejections` unless configured otherwise
+pub const DEFAULT_REJECTION_LOG_CAPACITY: usize = 1_000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RejectReason {
+    /// Not a valid order message
+    Malformed,
+    /// Nothing to trade
+    ZeroQuantity,
+    /// Price of zero on an order that is not pegged
+    InvalidPrice,
+    /// Price times quantity does not fit in a `u64`
+    NotionalOverflow,
+    /// The funnel was full
+    Backpressure,
+    /// Ingress is shutting down
+    ShuttingDown,
+    /// Price off the tick schedule
+    TickSize,
+    /// Price too far from the last trade
+    PriceBand,
+    /// Account is being throttled for wash trading
+    Throttled,
+    /// The session is closed
+    MarketClosed,
+    /// No book trades the order's symbol here
+    UnknownSymbol,
+    /// The gateway already has its maximum of open connections
+    TooManyConnections,
+    /// The order id was accepted recently; a resend is not booked again
+    Duplicate,
+    /// A fill-or-kill order could not fill in full on arrival
+    Killed,
+}
+
+impl RejectReason {
+    /// The `status` an ack carries for this reason: `duplicate` tells a
+    /// retrying client its first copy got through, `killed` that a
+    /// fill-or-kill order was valid but found too little to fill, anything
+    /// else is `error`
+    pub fn ack_status(self) -> &'static str {
+        match self {
+            RejectReason::Duplicate => "duplicate",
+            RejectReason::Killed => "killed",
+            _ => "error",
+        }
+    }
+}
+
+/// Why order entry refused an order
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct EntryError {
+    pub reason: RejectReason,
+    /// Human-readable specifics, e.g. the tick size the price missed
+    pub detail: String,
+}
+
+impl EntryError {
+    pub fn new(reason: RejectReason, detail: impl Into<String>) -> Self {
+        EntryError { reason, detail: detail.into() }
+    }
+}
+
+impl fmt::Display for EntryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.detail)
+    }
+}
+
+impl std::error::Error for EntryError {}
+
+/// The parts of a rejected order worth logging
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct OrderSummary {
+    pub id: u64,
+    pub side: OrderSide,
+    pub price: Price,
+    pub quantity: u64,
+}
+
+impl From<&Order> for OrderSummary {
+    fn from(order: &Order) -> Self {
+        OrderSummary { id: order.id, side: order.side, price: order.price, quantity: order.quantity }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Rejection {
+    pub timestamp_ns: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub account_id: Option<u64>,
+    pub reason: RejectReason,
+    /// Human-readable specifics, e.g. the parse error
+    pub detail: String,
+    /// `None` when the message never parsed into an order
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub order: Option<OrderSummary>,
+}
+
+pub struct RejectionLog {
+    clock: Arc<dyn Clock>,
+    bus: Option<Arc<EventBus>>,
+    sample_every: u64,
+    capacity: usize,
+    counts: Mutex<BTreeMap<RejectReason, u64>>,
+    recent: Mutex<VecDeque<Rejection>>,
+}
+
+impl RejectionLog {
+    /// Publishes and logs the 1st, (n+1)th, (2n+1)th... rejection of each
+    /// reason, with `n = sample_every` (0 is treated as 1).
+    pub fn new(clock: Arc<dyn Clock>, bus: Option<Arc<EventBus>>, sample_every: u64, capacity: usize) -> Self {
+        RejectionLog {
+            clock,
+            bus,
+            sample_every: sample_every.max(1),
+            capacity,
+            counts: Mutex::new(BTreeMap::new()),
+            recent: Mutex::new(VecDeque::new()),
+        }
+    }
+
+    pub fn record(&self, reason: RejectReason, order: Option<&Order>, detail: &str) {
+        let count = {
+            let mut counts = self.counts.lock().unwrap();
+            let count = counts.entry(reason).or_default();
+            *count += 1;
+            *count
+        };
+        if (count - 1) % self.sample_every != 0 {
+            return;
+        }
+
+        let rejection = Rejection {
+            timestamp_ns: self.clock.now_ns(),
+            account_id: order.and_then(|order| order.account_id),
+            reason,
+            detail: detail.to_string(),
+            order: order.map(OrderSummary::from),
+        };
+        if self.capacity > 0 {
+            let mut recent = self.recent.lock().unwrap();
+            if recent.len() == self.capacity {
+                recent.pop_front();
+            }
+            recent.push_back(rejection.clone());
+        }
+        if let Some(bus) = &self.bus {
+            bus.publish(BookEvent::Rejection(rejection));
+        }
+    }
+
+    /// Every rejection so far by reason, sampled or not
+    pub fn counts(&self) -> BTreeMap<RejectReason, u64> {
+        self.counts.lock().unwrap().clone()
+    }
+
+    /// The last `n` sampled rejections, oldest first
+    pub fn recent(&self, n: usize) -> Vec<Rejection> {
+        let recent = self.recent.lock().unwrap();
+        recent.iter().skip(recent.len().saturating_sub(n)).cloned().collect()
+    }
+}
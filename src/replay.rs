@@ -0,0 +1,222 @@
+// ============================================================================
+// REPLAY DRIVER - Re-run a recorded order stream through a fresh book
+// ============================================================================
+// Debugging aid: recorded orders are applied with their original spacing
+// (scaled by a speed multiplier), and the run can be paused, resumed or
+// stepped one order at a time. Matching is deterministic, so `replay_file`
+// reproduces a recorded session's executions exactly.
+
+use crate::matching_engine::{Order, OrderBook, TradeExecution};
+use crossbeam_channel::Receiver;
+use serde::{Deserialize, Serialize};
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::Path;
+use std::thread;
+use std::time::Duration;
+
+/// One order as it was seen at ingest
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecordedOrder {
+    pub recv_ns: u64,
+    pub order: Order,
+}
+
+/// Interactive controls, parsed from lines like `pause`, `step`, `speed 4`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ReplayCommand {
+    Pause,
+    Resume,
+    Step,
+    Speed(f64),
+    Quit,
+}
+
+impl ReplayCommand {
+    pub fn parse(line: &str) -> Result<Self, String> {
+        let mut parts = line.split_whitespace();
+        match (parts.next(), parts.next()) {
+            (Some("pause"), None) => Ok(ReplayCommand::Pause),
+            (Some("resume"), None) => Ok(ReplayCommand::Resume),
+            (Some("step"), None) => Ok(ReplayCommand::Step),
+            (Some("quit"), None) => Ok(ReplayCommand::Quit),
+            (Some("speed"), Some(value)) => match value.parse::<f64>() {
+                Ok(speed) if speed > 0.0 => Ok(ReplayCommand::Speed(speed)),
+                _ => Err(format!("invalid speed: {}", value)),
+            },
+            _ => Err(format!("unknown command: {}", line.trim())),
+        }
+    }
+}
+
+/// Reads a recording: one `RecordedOrder` JSON object per line.
+pub fn load_recording(path: impl AsRef<Path>) -> std::io::Result<Vec<RecordedOrder>> {
+    let reader = BufReader::new(File::open(path)?);
+    let mut orders = Vec::new();
+    for line in reader.lines() {
+        let line = line?;
+        if line.trim().is_empty() { continue; }
+        let recorded = serde_json::from_str(&line)
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
+        orders.push(recorded);
+    }
+    Ok(orders)
+}
+
+/// Appends every order the engine accepts to a recording, in the format
+/// `load_recording` reads.
+pub struct Recorder {
+    writer: BufWriter<File>,
+}
+
+impl Recorder {
+    pub fn create(path: impl AsRef<Path>) -> std::io::Result<Self> {
+        Ok(Recorder { writer: BufWriter::new(File::create(path)?) })
+    }
+
+    pub fn record(&mut self, recv_ns: u64, order: &Order) -> std::io::Result<()> {
+        let line = serde_json::to_string(&RecordedOrder { recv_ns, order: order.clone() })
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
+        writeln!(self.writer, "{}", line)
+    }
+
+    /// Buffered lines also go out when the recorder is dropped.
+    pub fn flush(&mut self) -> std::io::Result<()> {
+        self.writer.flush()
+    }
+}
+
+/// Every execution from applying the recording at `path` to a fresh book,
+/// as fast as possible.
+pub fn replay_file(path: impl AsRef<Path>) -> std::io::Result<Vec<TradeExecution>> {
+    let mut driver = ReplayDriver::new(load_recording(path)?);
+    while driver.step().is_some() {}
+    Ok(driver.executions)
+}
+
+pub struct ReplayDriver {
+    orders: Vec<RecordedOrder>,
+    cursor: usize,
+    book: OrderBook,
+    executions: Vec<TradeExecution>,
+    speed: f64,
+    paused: bool,
+}
+
+impl ReplayDriver {
+    pub fn new(orders: Vec<RecordedOrder>) -> Self {
+        ReplayDriver {
+            orders,
+            cursor: 0,
+            book: OrderBook::new(),
+            executions: Vec::new(),
+            speed: 1.0,
+            paused: false,
+        }
+    }
+
+    pub fn book(&self) -> &OrderBook {
+        &self.book
+    }
+
+    pub fn executions(&self) -> &[TradeExecution] {
+        &self.executions
+    }
+
+    /// Number of orders applied so far
+    pub fn position(&self) -> usize {
+        self.cursor
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.cursor >= self.orders.len()
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    pub fn speed(&self) -> f64 {
+        self.speed
+    }
+
+    /// Applies a control command. Returns `false` on `Quit`.
+    pub fn apply(&mut self, command: ReplayCommand) -> bool {
+        match command {
+            ReplayCommand::Pause => self.paused = true,
+            ReplayCommand::Resume => self.paused = false,
+            // Stepping implies staying paused afterwards
+            ReplayCommand::Step => {
+                self.paused = true;
+                self.step();
+            }
+            ReplayCommand::Speed(speed) => self.speed = speed,
+            ReplayCommand::Quit => return false,
+        }
+        true
+    }
+
+    /// Applies exactly one order regardless of pause state and returns the
+    /// executions it produced, or `None` when the recording is exhausted.
+    pub fn step(&mut self) -> Option<Vec<TradeExecution>> {
+        let recorded = self.orders.get(self.cursor)?;
+        let executions = self.book.add_limit_order(recorded.order.clone());
+        self.executions.extend(executions.iter().cloned());
+        self.cursor += 1;
+        Some(executions)
+    }
+
+    /// Applies the next order unless paused.
+    pub fn tick(&mut self) -> Option<Vec<TradeExecution>> {
+        if self.paused {
+            return None;
+        }
+        self.step()
+    }
+
+    /// Recorded gap before the next order, scaled by the speed multiplier.
+    pub fn delay_before_next(&self) -> Duration {
+        if self.cursor == 0 || self.is_finished() {
+            return Duration::ZERO;
+        }
+        let gap_ns = self.orders[self.cursor].recv_ns
+            .saturating_sub(self.orders[self.cursor - 1].recv_ns);
+        Duration::from_secs_f64(gap_ns as f64 / 1e9 / self.speed)
+    }
+
+    /// Drives the replay to completion, honouring commands as they arrive.
+    /// While paused it blocks on the command channel.
+    pub fn run(&mut self, commands: &Receiver<ReplayCommand>, mut on_step: impl FnMut(&Self, &[TradeExecution])) {
+        while !self.is_finished() {
+            let command = if self.paused {
+                match commands.recv() {
+                    Ok(command) => Some(command),
+                    // Nobody left to resume us
+                    Err(_) => return,
+                }
+            } else {
+                commands.try_recv().ok()
+            };
+
+            match command {
+                Some(ReplayCommand::Step) => {
+                    self.paused = true;
+                    if let Some(executions) = self.step() {
+                        on_step(self, &executions);
+                    }
+                }
+                Some(command) => {
+                    if !self.apply(command) {
+                        return;
+                    }
+                }
+                None => {
+                    thread::sleep(self.delay_before_next());
+                    if let Some(executions) = self.tick() {
+                        on_step(self, &executions);
+                    }
+                }
+            }
+        }
+    }
+}
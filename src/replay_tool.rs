@@ -0,0 +1,83 @@
+// ============================================================================
+// REPLAY TOOL - Interactive replay of a recorded order stream
+// ============================================================================
+// Usage: replay <recording.ndjson> [--speed X] [--paused]
+//        replay --pcap <capture.pcap> [--port N] [--speed X] [--paused]
+//        replay --raw <client-bytes> [--paused]
+// Commands on stdin: pause | resume | step | speed <X> | quit
+
+use hft_ringbuffer::gateway::DEFAULT_GATEWAY_ADDR;
+use hft_ringbuffer::pcap::{orders_from_pcap, orders_from_stream};
+use hft_ringbuffer::replay::{load_recording, RecordedOrder, ReplayCommand, ReplayDriver};
+use std::io::BufRead;
+use std::thread;
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    const USAGE: &str = "usage: replay [--pcap | --raw] <file> [--port N] [--speed X] [--paused]";
+    let mut args = std::env::args().skip(1);
+
+    let mut format = "recording";
+    let mut path = None;
+    let mut gateway_port: u16 = DEFAULT_GATEWAY_ADDR.rsplit(':').next().unwrap_or_default().parse()?;
+    let mut commands = Vec::new();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--pcap" => format = "pcap",
+            "--raw" => format = "raw",
+            "--port" => {
+                gateway_port = args.next().ok_or("--port needs a value")?.parse()?;
+            }
+            "--speed" => {
+                let value = args.next().ok_or("--speed needs a value")?;
+                commands.push(ReplayCommand::parse(&format!("speed {}", value))?);
+            }
+            "--paused" => commands.push(ReplayCommand::Pause),
+            other if other.starts_with("--") => return Err(format!("unknown argument: {}", other).into()),
+            other => path = Some(other.to_string()),
+        }
+    }
+    let path = path.ok_or(USAGE)?;
+
+    let orders = match format {
+        "pcap" => orders_from_pcap(&std::fs::read(&path)?, gateway_port)?,
+        // A bare byte stream has no timing, so orders replay back to back
+        "raw" => orders_from_stream(&std::fs::read(&path)?)
+            .into_iter()
+            .map(|order| RecordedOrder { recv_ns: 0, order })
+            .collect(),
+        _ => load_recording(&path)?,
+    };
+    let mut driver = ReplayDriver::new(orders);
+    for command in commands {
+        driver.apply(command);
+    }
+
+    println!("🎬 [REPLAY] {} ({}x{})", path, driver.speed(), if driver.is_paused() { ", paused" } else { "" });
+
+    let (tx, rx) = crossbeam_channel::unbounded();
+    thread::Builder::new().name("replay-stdin".to_string()).spawn(move || {
+        for line in std::io::stdin().lock().lines().map_while(Result::ok) {
+            if line.trim().is_empty() { continue; }
+            match ReplayCommand::parse(&line) {
+                Ok(command) => {
+                    if tx.send(command).is_err() {
+                        break;
+                    }
+                }
+                Err(e) => eprintln!("❌ [REPLAY] {}", e),
+            }
+        }
+    })?;
+
+    driver.run(&rx, |driver, executions| {
+        println!("▶️  [REPLAY] order {} applied", driver.position());
+        for exec in executions {
+            println!("💰 TRADE: {} matched with {} @ {} (Qty: {})",
+                exec.taker_order_id, exec.maker_order_id, exec.price, exec.quantity);
+        }
+    });
+
+    println!("✅ [REPLAY] {} orders, {} trades", driver.position(), driver.executions().len());
+    println!("{}", driver.book().to_json());
+    Ok(())
+}
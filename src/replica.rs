@@ -0,0 +1,185 @@
+// ============================================================================
+// READ REPLICA - A warm copy of the book for read traffic
+// ============================================================================
+// The engine forwards every order, cancel and book-wide command it applies,
+// tagged with the primary's sequence number; the replica re-applies them to
+// its own book. Matching is
+// deterministic, so the replica converges on the primary's state. Readers
+// can check how far behind it is and refuse to serve data that is too stale.
+
+use crate::clock::Clock;
+use crate::matching_engine::{BookAction, Order, OrderBook};
+use crate::warm_start::BookSnapshotFile;
+use crossbeam_channel::{unbounded, Receiver, Sender};
+use serde::Serialize;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+/// Name of the thread that applies updates to the replica
+pub const REPLICA_THREAD_NAME: &str = "replica";
+
+/// How long the apply loop waits for an update before re-checking shutdown
+const REPLICA_POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+struct ReplicaUpdate {
+    sequence: u64,
+    produced_ns: u64,
+    order: Order,
+    action: BookAction,
+}
+
+/// How far the replica trails the primary
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct ReplicaLag {
+    /// Primary operations not yet applied
+    pub sequences: u64,
+    /// Age of the replica's view: time between the primary's latest update
+    /// and the latest one the replica applied
+    pub ns: u64,
+}
+
+/// What a read does when the replica is staler than allowed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StaleAction {
+    /// Serve the data but flag it as stale
+    Warn,
+    /// Refuse the read
+    Reject,
+}
+
+/// Staleness threshold for replica reads; a zero limit is not checked.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StalenessGuard {
+    pub max_lag_sequences: u64,
+    pub max_lag_ns: u64,
+    pub action: StaleAction,
+}
+
+impl StalenessGuard {
+    pub fn is_stale(&self, lag: ReplicaLag) -> bool {
+        (self.max_lag_sequences > 0 && lag.sequences > self.max_lag_sequences)
+            || (self.max_lag_ns > 0 && lag.ns > self.max_lag_ns)
+    }
+}
+
+pub struct Replica {
+    book: Mutex<OrderBook>,
+    updates: Receiver<ReplicaUpdate>,
+    clock: Arc<dyn Clock>,
+    guard: Option<StalenessGuard>,
+    head_sequence: AtomicU64,
+    head_ns: AtomicU64,
+    applied_sequence: AtomicU64,
+    applied_ns: AtomicU64,
+}
+
+/// Primary-side handle the engine forwards applied orders through
+pub struct ReplicaFeed {
+    updates: Sender<ReplicaUpdate>,
+    replica: Arc<Replica>,
+}
+
+/// `primary`'s current state, e.g. as recovered at startup, loaded into
+/// `book`, which should carry the primary's configuration. What comes out
+/// is ready for `replica_channel`.
+pub fn seed_replica_book(primary: &OrderBook, book: OrderBook) -> OrderBook {
+    BookSnapshotFile::capture(primary, primary.sequence()).restore_into(book)
+}
+
+/// Creates a replica around `book`, which should start out identical to the
+/// primary (same contents and configuration, see `seed_replica_book`).
+pub fn replica_channel(book: OrderBook, clock: Arc<dyn Clock>, guard: Option<StalenessGuard>) -> (ReplicaFeed, Arc<Replica>) {
+    let (tx, rx) = unbounded();
+    let sequence = book.sequence();
+    let now = clock.now_ns();
+    let replica = Arc::new(Replica {
+        book: Mutex::new(book),
+        updates: rx,
+        clock,
+        guard,
+        head_sequence: AtomicU64::new(sequence),
+        head_ns: AtomicU64::new(now),
+        applied_sequence: AtomicU64::new(sequence),
+        applied_ns: AtomicU64::new(now),
+    });
+    (ReplicaFeed { updates: tx, replica: replica.clone() }, replica)
+}
+
+impl ReplicaFeed {
+    /// Forwards an order the primary applied as operation `sequence`.
+    pub fn publish(&self, sequence: u64, order: Order) {
+        self.publish_action(sequence, order, BookAction::Submit);
+    }
+
+    /// Forwards a packet's `action`, as the primary applied it, up to
+    /// operation `sequence`.
+    pub fn publish_action(&self, sequence: u64, order: Order, action: BookAction) {
+        let produced_ns = self.replica.clock.now_ns();
+        self.replica.head_sequence.store(sequence, Ordering::Release);
+        self.replica.head_ns.store(produced_ns, Ordering::Release);
+        // The replica may already be gone; the primary carries on regardless
+        let _ = self.updates.send(ReplicaUpdate { sequence, produced_ns, order, action });
+    }
+}
+
+impl Replica {
+    /// Applies every update received so far, returning how many.
+    pub fn apply_pending(&self) -> usize {
+        let mut applied = 0;
+        while let Ok(update) = self.updates.try_recv() {
+            self.apply(update);
+            applied += 1;
+        }
+        applied
+    }
+
+    fn apply(&self, update: ReplicaUpdate) {
+        self.book.lock().unwrap().apply_action(update.order, update.action);
+        self.applied_ns.store(update.produced_ns, Ordering::Release);
+        self.applied_sequence.store(update.sequence, Ordering::Release);
+    }
+
+    pub fn book(&self) -> MutexGuard<'_, OrderBook> {
+        self.book.lock().unwrap()
+    }
+
+    pub fn applied_sequence(&self) -> u64 {
+        self.applied_sequence.load(Ordering::Acquire)
+    }
+
+    pub fn lag(&self) -> ReplicaLag {
+        let applied_sequence = self.applied_sequence.load(Ordering::Acquire);
+        let applied_ns = self.applied_ns.load(Ordering::Acquire);
+        let head_sequence = self.head_sequence.load(Ordering::Acquire);
+        let head_ns = self.head_ns.load(Ordering::Acquire);
+        ReplicaLag {
+            sequences: head_sequence.saturating_sub(applied_sequence),
+            ns: head_ns.saturating_sub(applied_ns),
+        }
+    }
+
+    pub fn staleness_guard(&self) -> Option<StalenessGuard> {
+        self.guard
+    }
+
+    /// The configured guard's verdict for the current lag, if it is tripped
+    pub fn stale_action(&self) -> Option<StaleAction> {
+        self.guard.filter(|guard| guard.is_stale(self.lag())).map(|guard| guard.action)
+    }
+}
+
+/// Applies updates on a thread named `replica` until `shutdown` is raised.
+pub fn spawn_replica(replica: Arc<Replica>, shutdown: Arc<AtomicBool>) -> std::io::Result<JoinHandle<()>> {
+    thread::Builder::new()
+        .name(REPLICA_THREAD_NAME.to_string())
+        .spawn(move || {
+            while !shutdown.load(Ordering::Relaxed) {
+                if let Ok(update) = replica.updates.recv_timeout(REPLICA_POLL_INTERVAL) {
+                    replica.apply(update);
+                    replica.apply_pending();
+                }
+            }
+        })
+}
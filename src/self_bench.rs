@@ -0,0 +1,56 @@
+// ============================================================================
+// SELF BENCHMARK - Quick matching baseline for the host at startup
+// ============================================================================
+// Runs a seeded order flow straight through a private `OrderBook` (no ring,
+// no threads) and times every call, the same isolated harness the benchmark
+// binary uses for its generated-flow run.
+
+use crate::matching_engine::OrderBook;
+use crate::order_generator::{GeneratorParams, OrderGenerator};
+use serde::Serialize;
+use std::time::Instant;
+
+/// Orders pushed through the book by `--self-bench`
+pub const DEFAULT_SELF_BENCH_ORDERS: usize = 100_000;
+
+const SELF_BENCH_SEED: u64 = 42;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct SelfBenchReport {
+    pub orders: usize,
+    pub trades: usize,
+    pub p50_ns: u64,
+    pub p99_ns: u64,
+    pub total_ns: u64,
+    pub orders_per_second: f64,
+}
+
+/// Matches `orders` generated orders and reports per-order latency.
+pub fn run_self_bench(orders: usize) -> SelfBenchReport {
+    let flow: Vec<_> = OrderGenerator::new(SELF_BENCH_SEED, GeneratorParams::default())
+        .new_orders()
+        .take(orders)
+        .collect();
+    let mut book = OrderBook::new();
+    let mut latencies = Vec::with_capacity(flow.len());
+    let mut trades = 0;
+
+    let start = Instant::now();
+    for order in flow {
+        let match_start = Instant::now();
+        trades += book.add_limit_order(order).len();
+        latencies.push(match_start.elapsed().as_nanos() as u64);
+    }
+    let total_ns = start.elapsed().as_nanos() as u64;
+
+    latencies.sort_unstable();
+    let percentile = |p: usize| latencies.get((latencies.len() * p / 100).min(latencies.len().saturating_sub(1))).copied().unwrap_or(0);
+    SelfBenchReport {
+        orders: latencies.len(),
+        trades,
+        p50_ns: percentile(50),
+        p99_ns: percentile(99),
+        total_ns,
+        orders_per_second: latencies.len() as f64 / (total_ns.max(1) as f64 / 1e9),
+    }
+}
@@ -0,0 +1,122 @@
+// ============================================================================
+// SETTLEMENT - End-of-session settlement price
+// ============================================================================
+// When a book's session closes it fixes one settlement price for
+// mark-to-market and PnL. The method is configurable; a session that never
+// traded falls back to the mid at close, then to the prior settlement.
+
+use crate::clock::Clock;
+use crate::price_units::Price;
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::str::FromStr;
+use std::sync::Arc;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SettlementMethod {
+    /// Price of the session's last trade
+    LastTrade,
+    /// Volume-weighted average of the trades in the final `window_ns`
+    Vwap { window_ns: u64 },
+    /// Midpoint of the best bid and ask at close
+    MidAtClose,
+}
+
+impl FromStr for SettlementMethod {
+    type Err = String;
+
+    /// `last`, `mid`, or `vwap:<minutes>`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().split_once(':') {
+            None if s.trim() == "last" => Ok(SettlementMethod::LastTrade),
+            None if s.trim() == "mid" => Ok(SettlementMethod::MidAtClose),
+            Some(("vwap", minutes)) => {
+                let minutes: u64 = minutes.trim().parse().map_err(|e| format!("bad VWAP window {}: {}", minutes, e))?;
+                Ok(SettlementMethod::Vwap { window_ns: minutes * 60 * 1_000_000_000 })
+            }
+            _ => Err(format!("expected last, mid or vwap:<minutes>, got {}", s)),
+        }
+    }
+}
+
+/// Where a settlement price actually came from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SettlementSource {
+    LastTrade,
+    Vwap,
+    MidAtClose,
+    /// Nothing traded and the book was one-sided or empty
+    PriorSettlement,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct Settlement {
+    pub price: Price,
+    pub source: SettlementSource,
+    /// Clock time the session closed
+    pub settled_at_ns: u64,
+}
+
+/// Settlement state owned by an order book
+pub(crate) struct SettlementTracker {
+    method: SettlementMethod,
+    clock: Arc<dyn Clock>,
+    /// This session's trades as `(timestamp_ns, price, quantity)`; only the
+    /// VWAP window is kept when settling by VWAP
+    trades: VecDeque<(u64, Price, u64)>,
+    last: Option<Settlement>,
+}
+
+impl SettlementTracker {
+    pub(crate) fn new(method: SettlementMethod, clock: Arc<dyn Clock>) -> Self {
+        SettlementTracker { method, clock, trades: VecDeque::new(), last: None }
+    }
+
+    pub(crate) fn record_trade(&mut self, price: Price, quantity: u64) {
+        let now = self.clock.now_ns();
+        match self.method {
+            SettlementMethod::Vwap { window_ns } => {
+                while self.trades.front().is_some_and(|&(at, _, _)| now.saturating_sub(at) > window_ns) {
+                    self.trades.pop_front();
+                }
+            }
+            // Only the last trade matters
+            _ => self.trades.clear(),
+        }
+        self.trades.push_back((now, price, quantity));
+    }
+
+    /// Fixes the settlement for the session that just closed and starts a
+    /// fresh one. `None` only if there is nothing at all to settle on.
+    pub(crate) fn settle(&mut self, best_bid: Option<Price>, best_ask: Option<Price>) -> Option<Settlement> {
+        let now = self.clock.now_ns();
+        let mid = match (best_bid, best_ask) {
+            (Some(bid), Some(ask)) => Some(bid.midpoint(ask)),
+            _ => None,
+        };
+        let traded = match self.method {
+            SettlementMethod::LastTrade => self.trades.back().map(|&(_, price, _)| (price, SettlementSource::LastTrade)),
+            SettlementMethod::Vwap { window_ns } => {
+                let window = self.trades.iter().filter(|&&(at, _, _)| now.saturating_sub(at) <= window_ns);
+                let (notional, volume) = window.fold((0u128, 0u128), |(notional, volume), &(_, price, quantity)| {
+                    (notional + price.units() as u128 * quantity as u128, volume + quantity as u128)
+                });
+                (volume > 0).then(|| (Price((notional / volume) as u64), SettlementSource::Vwap))
+            }
+            SettlementMethod::MidAtClose => mid.map(|mid| (mid, SettlementSource::MidAtClose)),
+        };
+        let (price, source) = traded
+            .or_else(|| mid.map(|mid| (mid, SettlementSource::MidAtClose)))
+            .or_else(|| self.last.map(|prior| (prior.price, SettlementSource::PriorSettlement)))?;
+
+        self.trades.clear();
+        let settlement = Settlement { price, source, settled_at_ns: now };
+        self.last = Some(settlement);
+        Some(settlement)
+    }
+
+    pub(crate) fn last(&self) -> Option<Settlement> {
+        self.last
+    }
+}
@@ -0,0 +1,128 @@
+// ============================================================================
+// SHARDED ENGINE - K matching threads, symbols spread across them by hash
+// ============================================================================
+// Every symbol has its own funnel and ring, sized by a `ShardConfig` (see
+// shards.rs), and each engine thread drains the rings and keeps the books of
+// the symbols that hash to it. A symbol always lands on the same shard, so
+// its orders share one FIFO and keep their arrival order, while different
+// shards match in parallel. Symbols that share a shard still never trade
+// against each other.
+//
+// The books are fixed at startup: a symbol without one has no ring, so the
+// gateway refuses its orders as `unknown_symbol` rather than creating a book.
+//
+// Each shard takes its own BBO publisher and trade sink, which tag what they
+// publish with the book's symbol. Hooks that follow a single book (WAL,
+// replica, tick dump, recording) are not shard-aware; leave them out.
+
+use crate::engine::{spawn_symbol_engine, EngineHooks, SymbolBooks};
+use crate::funnel::{spawn_funnel, FunnelConfig};
+use crate::gateway::GatewayRoutes;
+use crate::metrics::Metrics;
+use crate::shards::{ShardConfig, ShardRouter};
+use std::sync::atomic::AtomicBool;
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+
+/// Name of the thread that waits for every shard engine to stop
+pub const SHARD_JOIN_THREAD_NAME: &str = "engine-shards";
+
+/// Shard that owns `symbol` out of `shards`. FNV-1a, so the assignment is
+/// the same in every process and on every run.
+pub fn shard_for(symbol: &str, shards: usize) -> usize {
+    let hash = symbol.bytes().fold(0xcbf2_9ce4_8422_2325u64, |hash, byte| (hash ^ byte as u64).wrapping_mul(0x0100_0000_01b3));
+    (hash % shards.max(1) as u64) as usize
+}
+
+/// How to lay out the shards
+#[derive(Debug, Clone)]
+pub struct ShardedEngineConfig {
+    pub shards: usize,
+    /// Slots in each symbol's ring
+    pub rings: ShardConfig,
+    /// Where orders without a symbol go; must be one of the books
+    pub default_symbol: String,
+    pub funnel: FunnelConfig,
+}
+
+/// Running shards. Orders go in through `routes`, exactly as the gateway
+/// would send them.
+pub struct ShardedEngines {
+    pub routes: GatewayRoutes,
+    /// Symbols owned by each shard, by shard index
+    pub assignment: Vec<Vec<String>>,
+    pub engines: Vec<JoinHandle<()>>,
+    pub forwarders: Vec<JoinHandle<()>>,
+}
+
+/// Starts a funnel and ring per book and `config.shards` engines over
+/// them, registering every book and ring with `metrics` for the HTTP API.
+/// `hooks` is called once per shard index. Funnels stop on
+/// `ingress_shutdown`, engines on `engine_shutdown`.
+pub fn spawn_sharded_engines(
+    config: &ShardedEngineConfig,
+    books: SymbolBooks,
+    mut hooks: impl FnMut(usize) -> EngineHooks,
+    metrics: Arc<Metrics>,
+    ingress_shutdown: Arc<AtomicBool>,
+    engine_shutdown: Arc<AtomicBool>,
+) -> std::io::Result<ShardedEngines> {
+    let shards = config.shards.max(1);
+    if !books.contains_key(&config.default_symbol) {
+        let detail = format!("no book for the default symbol {}", config.default_symbol);
+        return Err(std::io::Error::new(std::io::ErrorKind::InvalidInput, detail));
+    }
+    let symbols: Vec<&str> = books.keys().map(String::as_str).collect();
+    let (router, consumers) = ShardRouter::build(&config.rings, &symbols)
+        .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidInput, e))?;
+    metrics.register_shards(&router);
+
+    let mut shard_books = vec![SymbolBooks::new(); shards];
+    let mut shard_rings: Vec<Vec<_>> = (0..shards).map(|_| Vec::new()).collect();
+    for (symbol, consumer) in consumers {
+        let book = books[&symbol].clone();
+        metrics.register_symbol_book(&symbol, book.clone());
+        let shard = shard_for(&symbol, shards);
+        shard_books[shard].insert(symbol, book);
+        shard_rings[shard].push(consumer);
+    }
+
+    let mut engines = Vec::with_capacity(shards);
+    let mut assignment: Vec<Vec<String>> = Vec::with_capacity(shards);
+    for (index, (books, rings)) in shard_books.into_iter().zip(shard_rings).enumerate() {
+        assignment.push(books.keys().cloned().collect());
+        engines.push(spawn_symbol_engine(
+            index,
+            rings,
+            books,
+            &config.default_symbol,
+            engine_shutdown.clone(),
+            metrics.clone(),
+            hooks(index),
+        )?);
+    }
+
+    let mut forwarders = Vec::new();
+    let mut funnels = Vec::new();
+    for shard in router.shards() {
+        let (funnel, forwarder) = spawn_funnel(shard.clone(), config.funnel, metrics.funnel().clone(), ingress_shutdown.clone())?;
+        funnels.push((shard.symbol().to_string(), funnel));
+        forwarders.push(forwarder);
+    }
+    let default_funnel = funnels.iter().find(|(symbol, _)| *symbol == config.default_symbol).map(|(_, funnel)| funnel.clone());
+    let mut routes = GatewayRoutes::new(&config.default_symbol, default_funnel.expect("the default symbol has a book"));
+    for (symbol, funnel) in funnels {
+        routes = routes.with_symbol(&symbol, funnel);
+    }
+    Ok(ShardedEngines { routes, assignment, engines, forwarders })
+}
+
+/// One handle for several engine threads, for `PhasedShutdown::run`: a
+/// thread that returns once all of them have.
+pub fn join_all(engines: Vec<JoinHandle<()>>) -> std::io::Result<JoinHandle<()>> {
+    thread::Builder::new().name(SHARD_JOIN_THREAD_NAME.to_string()).spawn(move || {
+        for engine in engines {
+            let _ = engine.join();
+        }
+    })
+}
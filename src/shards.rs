@@ -0,0 +1,213 @@
+// ============================================================================
+// SYMBOL SHARDS - One ring buffer per symbol, each sized for its volume
+// ============================================================================
+// Sharding by symbol gives every instrument its own ring, drained by its own
+// engine thread or by the sharded engine its symbol hashes to. Busy symbols
+// get bigger rings than quiet ones; each ring reports its occupancy and
+// drops so an undersized one is easy to spot.
+
+use crate::funnel::RingInput;
+use crate::matching_engine::Packet;
+use rtrb::{Consumer, Producer, RingBuffer};
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+
+/// Ring capacity per symbol, plus a default for symbols not listed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ShardConfig {
+    pub default_capacity: usize,
+    pub capacities: BTreeMap<String, usize>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ShardConfigError {
+    NotPowerOfTwo { symbol: String, capacity: usize },
+    Malformed(String),
+}
+
+impl fmt::Display for ShardConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ShardConfigError::NotPowerOfTwo { symbol, capacity } => {
+                write!(f, "ring capacity {} for {} is not a power of two", capacity, symbol)
+            }
+            ShardConfigError::Malformed(entry) => write!(f, "expected SYMBOL:CAPACITY, got {:?}", entry),
+        }
+    }
+}
+
+impl std::error::Error for ShardConfigError {}
+
+impl ShardConfig {
+    pub fn new(default_capacity: usize) -> Self {
+        ShardConfig { default_capacity, capacities: BTreeMap::new() }
+    }
+
+    pub fn with_capacity(mut self, symbol: &str, capacity: usize) -> Self {
+        self.capacities.insert(symbol.to_string(), capacity);
+        self
+    }
+
+    pub fn capacity_for(&self, symbol: &str) -> usize {
+        self.capacities.get(symbol).copied().unwrap_or(self.default_capacity)
+    }
+
+    /// Every capacity, the default included, must be a power of two.
+    pub fn validate(&self) -> Result<(), ShardConfigError> {
+        let listed = self.capacities.iter().map(|(symbol, &capacity)| (symbol.as_str(), capacity));
+        for (symbol, capacity) in std::iter::once(("default", self.default_capacity)).chain(listed) {
+            if !capacity.is_power_of_two() {
+                return Err(ShardConfigError::NotPowerOfTwo { symbol: symbol.to_string(), capacity });
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Parses `BTC:8192,ETH:1024` (the default capacity is set separately).
+impl FromStr for ShardConfig {
+    type Err = ShardConfigError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut config = ShardConfig::new(DEFAULT_SHARD_CAPACITY);
+        for entry in s.split(',').map(str::trim).filter(|entry| !entry.is_empty()) {
+            let (symbol, capacity) = entry.split_once(':').ok_or_else(|| ShardConfigError::Malformed(entry.to_string()))?;
+            let capacity = capacity.trim().parse().map_err(|_| ShardConfigError::Malformed(entry.to_string()))?;
+            config.capacities.insert(symbol.trim().to_string(), capacity);
+        }
+        config.validate()?;
+        Ok(config)
+    }
+}
+
+pub const DEFAULT_SHARD_CAPACITY: usize = 4096;
+
+/// Producer side of one symbol's ring
+pub struct Shard {
+    symbol: String,
+    capacity: usize,
+    producer: Mutex<Producer<Packet>>,
+    accepted: AtomicU64,
+    dropped: AtomicU64,
+}
+
+/// Point-in-time view of a shard for metrics
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ShardOccupancy {
+    pub symbol: String,
+    pub capacity: usize,
+    pub occupied: usize,
+    pub accepted: u64,
+    pub dropped: u64,
+}
+
+impl Shard {
+    pub fn symbol(&self) -> &str {
+        &self.symbol
+    }
+
+    /// Pushes into the ring, handing the packet back if it is full.
+    #[allow(clippy::result_large_err)]
+    pub fn push(&self, packet: Packet) -> Result<(), Packet> {
+        let result = self.producer.lock().unwrap().push(packet);
+        match result {
+            Ok(()) => {
+                self.accepted.fetch_add(1, Ordering::Relaxed);
+                Ok(())
+            }
+            Err(rtrb::PushError::Full(packet)) => {
+                self.dropped.fetch_add(1, Ordering::Relaxed);
+                Err(packet)
+            }
+        }
+    }
+
+    pub fn occupancy(&self) -> ShardOccupancy {
+        let free = self.producer.lock().unwrap().slots();
+        ShardOccupancy {
+            symbol: self.symbol.clone(),
+            capacity: self.capacity,
+            occupied: self.capacity - free,
+            accepted: self.accepted.load(Ordering::Relaxed),
+            dropped: self.dropped.load(Ordering::Relaxed),
+        }
+    }
+}
+
+/// A funnel feeding a shard waits for room instead of dropping, so a full
+/// ring shows up in its occupancy rather than its drops.
+impl RingInput for Arc<Shard> {
+    fn push(&mut self, packet: Packet) -> Result<(), Packet> {
+        self.producer.lock().unwrap().push(packet).map_err(|rtrb::PushError::Full(packet)| packet)?;
+        self.accepted.fetch_add(1, Ordering::Relaxed);
+        Ok(())
+    }
+
+    fn is_abandoned(&self) -> bool {
+        self.producer.lock().unwrap().is_abandoned()
+    }
+}
+
+#[derive(Debug)]
+pub enum RouteError {
+    UnknownSymbol(Packet),
+    /// That symbol's ring is full; the order was dropped
+    Full(Packet),
+}
+
+/// Consumer end of each symbol's ring, for its engine thread
+pub type ShardConsumers = Vec<(String, Consumer<Packet>)>;
+
+/// Sends each order to its symbol's ring
+pub struct ShardRouter {
+    shards: HashMap<String, Arc<Shard>>,
+}
+
+impl ShardRouter {
+    /// Creates a ring per symbol, returning the router and each symbol's
+    /// consumer for its engine thread.
+    pub fn build(config: &ShardConfig, symbols: &[&str]) -> Result<(Self, ShardConsumers), ShardConfigError> {
+        config.validate()?;
+        let mut shards = HashMap::new();
+        let mut consumers = Vec::new();
+        for &symbol in symbols {
+            let capacity = config.capacity_for(symbol);
+            if !capacity.is_power_of_two() {
+                return Err(ShardConfigError::NotPowerOfTwo { symbol: symbol.to_string(), capacity });
+            }
+            let (producer, consumer) = RingBuffer::new(capacity);
+            shards.insert(symbol.to_string(), Arc::new(Shard {
+                symbol: symbol.to_string(),
+                capacity,
+                producer: Mutex::new(producer),
+                accepted: AtomicU64::new(0),
+                dropped: AtomicU64::new(0),
+            }));
+            consumers.push((symbol.to_string(), consumer));
+        }
+        Ok((ShardRouter { shards }, consumers))
+    }
+
+    #[allow(clippy::result_large_err)]
+    pub fn route(&self, symbol: &str, packet: Packet) -> Result<(), RouteError> {
+        match self.shards.get(symbol) {
+            Some(shard) => Shard::push(shard, packet).map_err(RouteError::Full),
+            None => Err(RouteError::UnknownSymbol(packet)),
+        }
+    }
+
+    pub fn shard(&self, symbol: &str) -> Option<&Arc<Shard>> {
+        self.shards.get(symbol)
+    }
+
+    /// Every shard, sorted by symbol
+    pub fn shards(&self) -> Vec<Arc<Shard>> {
+        let mut shards: Vec<Arc<Shard>> = self.shards.values().cloned().collect();
+        shards.sort_by(|a, b| a.symbol.cmp(&b.symbol));
+        shards
+    }
+}
@@ -0,0 +1,152 @@
+// ============================================================================
+// SIGNAL STORE - Latest AI and crypto decisions posted by the Python traders
+// ============================================================================
+// The traders POST their decision as JSON and the dashboard GETs it back.
+// A post must parse as the decision's shape; fields beyond the known ones
+// (balances, positions) are kept, and the whole thing is stored
+// re-serialized: compact, known fields first, the rest in key order.
+
+use crate::decision_bridge::{BridgedOrder, DecisionBridge};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+
+/// What the price-action trader posts to `/api/ai-decision`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AiDecision {
+    pub signal: String,
+    #[serde(default)]
+    pub reasoning: String,
+    /// Percent, 0 to 100
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub confidence: Option<f64>,
+    /// Balances, positions and whatever else the trader reports, kept as sent
+    #[serde(flatten)]
+    pub extra: BTreeMap<String, serde_json::Value>,
+}
+
+impl AiDecision {
+    /// Parses and checks a posted body
+    pub fn parse(json: &str) -> Result<AiDecision, String> {
+        let decision: AiDecision = serde_json::from_str(json).map_err(|e| format!("invalid AI decision: {}", e))?;
+        if decision.signal.trim().is_empty() {
+            return Err("signal must not be empty".to_string());
+        }
+        if decision.confidence.is_some_and(|confidence| !(0.0..=100.0).contains(&confidence)) {
+            return Err("confidence must be between 0 and 100".to_string());
+        }
+        Ok(decision)
+    }
+}
+
+impl Default for AiDecision {
+    fn default() -> Self {
+        AiDecision {
+            signal: "NEUTRAL".to_string(),
+            reasoning: "Waiting for AI analysis...".to_string(),
+            confidence: None,
+            extra: BTreeMap::new(),
+        }
+    }
+}
+
+/// What the crypto trader posts to `/api/crypto-decision`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CryptoDecision {
+    /// Signal per asset, e.g. `"btc": "HOLD"`
+    pub signals: BTreeMap<String, String>,
+    #[serde(default)]
+    pub reasoning: String,
+    #[serde(flatten)]
+    pub extra: BTreeMap<String, serde_json::Value>,
+}
+
+impl CryptoDecision {
+    /// Parses and checks a posted body
+    pub fn parse(json: &str) -> Result<CryptoDecision, String> {
+        let decision: CryptoDecision =
+            serde_json::from_str(json).map_err(|e| format!("invalid crypto decision: {}", e))?;
+        if decision.signals.iter().any(|(asset, signal)| asset.trim().is_empty() || signal.trim().is_empty()) {
+            return Err("signals need a non-empty asset and signal".to_string());
+        }
+        Ok(decision)
+    }
+}
+
+impl Default for CryptoDecision {
+    fn default() -> Self {
+        CryptoDecision {
+            signals: ["btc", "eth", "sol"].into_iter().map(|asset| (asset.to_string(), "HOLD".to_string())).collect(),
+            reasoning: "Waiting for crypto analysis...".to_string(),
+            extra: BTreeMap::new(),
+        }
+    }
+}
+
+/// Shared with the HTTP server behind `/api/ai-decision` and
+/// `/api/crypto-decision`. Decisions are kept re-serialized, so what is
+/// served back is always well-formed JSON.
+pub struct SignalStore {
+    ai_decision: Mutex<String>,
+    crypto_decision: Mutex<String>,
+    /// Trades AI decisions when execution is switched on
+    bridge: Option<DecisionBridge>,
+}
+
+impl Default for SignalStore {
+    fn default() -> Self {
+        SignalStore {
+            ai_decision: Mutex::new(json_string(&AiDecision::default())),
+            crypto_decision: Mutex::new(json_string(&CryptoDecision::default())),
+            bridge: None,
+        }
+    }
+}
+
+impl SignalStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Also turns AI decisions into orders through `bridge`.
+    pub fn with_bridge(mut self, bridge: DecisionBridge) -> Self {
+        self.bridge = Some(bridge);
+        self
+    }
+
+    pub fn bridge(&self) -> Option<&DecisionBridge> {
+        self.bridge.as_ref()
+    }
+
+    /// Stores `decision` and, with a bridge, trades it against `mid`. The
+    /// decision is kept even if its order could not be placed.
+    pub fn record_ai_decision(&self, decision: &AiDecision, mid: Option<f64>) -> Result<Option<BridgedOrder>, String> {
+        self.set_ai_decision(decision);
+        match &self.bridge {
+            Some(bridge) => bridge.execute(decision, mid),
+            None => Ok(None),
+        }
+    }
+
+    /// The latest AI decision as JSON
+    pub fn ai_decision(&self) -> String {
+        self.ai_decision.lock().unwrap().clone()
+    }
+
+    pub fn set_ai_decision(&self, decision: &AiDecision) {
+        *self.ai_decision.lock().unwrap() = json_string(decision);
+    }
+
+    /// The latest crypto decision as JSON
+    pub fn crypto_decision(&self) -> String {
+        self.crypto_decision.lock().unwrap().clone()
+    }
+
+    pub fn set_crypto_decision(&self, decision: &CryptoDecision) {
+        *self.crypto_decision.lock().unwrap() = json_string(decision);
+    }
+}
+
+fn json_string<T: Serialize>(value: &T) -> String {
+    serde_json::to_string(value).expect("decisions serialize to JSON")
+}
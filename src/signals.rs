@@ -0,0 +1,68 @@
+// ============================================================================
+// SIGNALS - Ctrl-C and SIGTERM become a shutdown request
+// ============================================================================
+// The handler only sets a flag; a normal thread polls `interrupted()` and
+// runs the phased shutdown, so nothing non-trivial happens in signal context.
+
+use crate::engine::PhasedShutdown;
+use log::{error, info};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+/// Name of the thread that waits for a signal and shuts the servers down
+pub const SIGNAL_THREAD_NAME: &str = "signal-watch";
+
+/// How often the watcher checks for a delivered signal
+const SIGNAL_POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+static INTERRUPTED: AtomicBool = AtomicBool::new(false);
+
+#[cfg(unix)]
+mod ffi {
+    pub const SIGINT: i32 = 2;
+    pub const SIGTERM: i32 = 15;
+
+    extern "C" {
+        // From the C library std already links; the handler is passed as an address
+        pub fn signal(signum: i32, handler: usize) -> usize;
+    }
+}
+
+#[cfg(unix)]
+extern "C" fn on_signal(_signum: i32) {
+    INTERRUPTED.store(true, Ordering::Relaxed);
+}
+
+/// Routes SIGINT and SIGTERM to `interrupted()` instead of killing the
+/// process. A no-op off Unix.
+pub fn install_signal_handlers() {
+    #[cfg(unix)]
+    unsafe {
+        ffi::signal(ffi::SIGINT, on_signal as extern "C" fn(i32) as usize);
+        ffi::signal(ffi::SIGTERM, on_signal as extern "C" fn(i32) as usize);
+    }
+}
+
+/// Whether SIGINT or SIGTERM has arrived since the handlers were installed
+pub fn interrupted() -> bool {
+    INTERRUPTED.load(Ordering::Relaxed)
+}
+
+/// Waits for a signal, then runs `phases` over the ingress threads and the
+/// engine. Joining the returned handle waits for the whole shutdown.
+pub fn spawn_signal_watch(
+    phases: PhasedShutdown,
+    ingress_threads: Vec<JoinHandle<()>>,
+    engine: JoinHandle<()>,
+) -> std::io::Result<JoinHandle<()>> {
+    thread::Builder::new().name(SIGNAL_THREAD_NAME.to_string()).spawn(move || {
+        while !interrupted() {
+            thread::sleep(SIGNAL_POLL_INTERVAL);
+        }
+        info!("🛑 Shutdown requested, stopping ingress and draining the engine...");
+        if phases.run(ingress_threads, engine).is_err() {
+            error!("❌ A server thread panicked during shutdown");
+        }
+    })
+}
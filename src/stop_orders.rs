@@ -0,0 +1,51 @@
+// ============================================================================
+// STOP ORDERS - Parked until the last trade crosses their trigger
+// ============================================================================
+// A buy stop wakes up once the market trades at or above its trigger, a sell
+// stop at or below. Until then it sits here, off the book and invisible to
+// depth; once triggered the book injects it as a market (`Stop`) or limit
+// (`StopLimit`) order.
+
+use crate::matching_engine::{Order, OrderSide, OrderType};
+use crate::price_units::Price;
+
+/// Stops waiting for their trigger, in arrival order
+#[derive(Debug, Default)]
+pub(crate) struct StopBook {
+    pending: Vec<Order>,
+}
+
+impl StopBook {
+    pub(crate) fn park(&mut self, order: Order) {
+        self.pending.push(order);
+    }
+
+    /// Removes and returns every stop `last_price` triggers, oldest first.
+    pub(crate) fn take_triggered(&mut self, last_price: Price) -> Vec<Order> {
+        let (triggered, waiting) = std::mem::take(&mut self.pending)
+            .into_iter()
+            .partition(|order| is_triggered(order, last_price));
+        self.pending = waiting;
+        triggered
+    }
+
+    pub(crate) fn cancel(&mut self, order_id: u64) -> Option<Order> {
+        let index = self.pending.iter().position(|order| order.id == order_id)?;
+        Some(self.pending.remove(index))
+    }
+
+    pub(crate) fn pending(&self) -> &[Order] {
+        &self.pending
+    }
+}
+
+fn is_triggered(order: &Order, last_price: Price) -> bool {
+    let trigger = match order.order_type {
+        OrderType::Stop { trigger } | OrderType::StopLimit { trigger, .. } => trigger,
+        OrderType::Limit => return true,
+    };
+    match order.side {
+        OrderSide::Buy => last_price >= trigger,
+        OrderSide::Sell => last_price <= trigger,
+    }
+}
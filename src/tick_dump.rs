@@ -0,0 +1,163 @@
+// ============================================================================
+// TICK DUMP - One CSV row per book mutation, for offline analysis
+// ============================================================================
+// The engine hands rows to a writer thread over an SPSC ring; the writer owns
+// the file and a buffered writer, so the match loop never touches disk.
+//
+// Columns (stable; new ones are only ever appended):
+//   timestamp_ns  engine clock when the order was applied
+//   event         `order` for an incoming order, `trade` for each execution
+//   side          `buy` / `sell`; for trades, the incoming (taker) side
+//   price         order limit price, or the execution price
+//   quantity      order quantity as submitted, or the executed quantity
+//   best_bid      best bid once the order was applied (empty if none)
+//   best_ask      best ask once the order was applied (empty if none)
+
+use crate::clock::Clock;
+use crate::matching_engine::{Order, OrderSide, TradeExecution};
+use crate::price_units::Price;
+use rtrb::{Consumer, Producer, RingBuffer};
+use log::error;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+pub const TICK_DUMP_HEADER: &str = "timestamp_ns,event,side,price,quantity,best_bid,best_ask";
+
+/// Capacity of the engine -> writer ring
+pub const TICK_RING_CAPACITY: usize = 4096;
+
+/// How long the writer sleeps when it finds the ring empty
+const WRITER_IDLE_SLEEP: Duration = Duration::from_micros(50);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TickEvent {
+    Order,
+    Trade,
+}
+
+/// One line of the dump
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TickRow {
+    pub timestamp_ns: u64,
+    pub event: TickEvent,
+    pub side: OrderSide,
+    pub price: Price,
+    pub quantity: u64,
+    pub best_bid: Option<Price>,
+    pub best_ask: Option<Price>,
+}
+
+impl TickRow {
+    pub fn to_csv(&self) -> String {
+        let event = match self.event {
+            TickEvent::Order => "order",
+            TickEvent::Trade => "trade",
+        };
+        let side = match self.side {
+            OrderSide::Buy => "buy",
+            OrderSide::Sell => "sell",
+        };
+        let price = |p: Option<Price>| p.map(|p| p.to_string()).unwrap_or_default();
+        format!(
+            "{},{},{},{},{},{},{}",
+            self.timestamp_ns, event, side, self.price, self.quantity, price(self.best_bid), price(self.best_ask)
+        )
+    }
+
+    /// Parses a line written by `to_csv`.
+    pub fn from_csv(line: &str) -> Result<Self, String> {
+        let fields: Vec<&str> = line.trim_end().split(',').collect();
+        if fields.len() < 7 {
+            return Err(format!("expected 7 columns, got {}: {}", fields.len(), line));
+        }
+        let number = |field: &str| field.parse::<u64>().map_err(|e| format!("{}: {}", field, e));
+        let price = |field: &str| if field.is_empty() { Ok(None) } else { number(field).map(|p| Some(Price(p))) };
+        Ok(TickRow {
+            timestamp_ns: number(fields[0])?,
+            event: match fields[1] {
+                "order" => TickEvent::Order,
+                "trade" => TickEvent::Trade,
+                other => return Err(format!("unknown event {}", other)),
+            },
+            side: match fields[2] {
+                "buy" => OrderSide::Buy,
+                "sell" => OrderSide::Sell,
+                other => return Err(format!("unknown side {}", other)),
+            },
+            price: Price(number(fields[3])?),
+            quantity: number(fields[4])?,
+            best_bid: price(fields[5])?,
+            best_ask: price(fields[6])?,
+        })
+    }
+}
+
+/// Engine side of the dump
+pub struct TickDump {
+    producer: Producer<TickRow>,
+    clock: Arc<dyn Clock>,
+}
+
+impl TickDump {
+    /// Creates `path`, writes the header and starts the writer thread. The
+    /// writer flushes and exits once the dump is dropped and the ring is empty.
+    pub fn create(path: impl AsRef<Path>, clock: Arc<dyn Clock>) -> std::io::Result<(TickDump, JoinHandle<()>)> {
+        let mut file = BufWriter::new(File::create(path)?);
+        writeln!(file, "{}", TICK_DUMP_HEADER)?;
+        let (producer, consumer) = RingBuffer::new(TICK_RING_CAPACITY);
+        let writer = thread::Builder::new()
+            .name("tick-dump".to_string())
+            .spawn(move || run_tick_writer(consumer, file))?;
+        Ok((TickDump { producer, clock }, writer))
+    }
+
+    /// Rows for one applied order: the order itself, then each execution.
+    /// `order` is the order as submitted, before matching.
+    pub fn record(&mut self, order: &Order, executions: &[TradeExecution], best_bid: Option<Price>, best_ask: Option<Price>) {
+        let timestamp_ns = self.clock.now_ns();
+        let row = |event, price, quantity| TickRow { timestamp_ns, event, side: order.side, price, quantity, best_bid, best_ask };
+        self.push(row(TickEvent::Order, order.price, order.quantity));
+        for exec in executions {
+            self.push(row(TickEvent::Trade, exec.price, exec.quantity));
+        }
+    }
+
+    fn push(&mut self, mut row: TickRow) {
+        // Never drop a row: wait for the writer to make room
+        while let Err(rtrb::PushError::Full(rejected)) = self.producer.push(row) {
+            row = rejected;
+            std::hint::spin_loop();
+        }
+    }
+}
+
+fn run_tick_writer(mut consumer: Consumer<TickRow>, mut file: BufWriter<File>) {
+    // After a write error rows are still drained (and dropped) so the engine
+    // never blocks on a full ring
+    let mut failed = false;
+    loop {
+        match consumer.pop() {
+            Ok(row) => {
+                if failed {
+                    continue;
+                }
+                if let Err(e) = writeln!(file, "{}", row.to_csv()) {
+                    error!("❌ [TICK DUMP] Write failed, dropping further rows: {}", e);
+                    failed = true;
+                }
+            }
+            Err(_) => {
+                // Idle: get what we have onto disk
+                let _ = file.flush();
+                if consumer.is_abandoned() && consumer.is_empty() {
+                    return;
+                }
+                thread::sleep(WRITER_IDLE_SLEEP);
+            }
+        }
+    }
+}
@@ -0,0 +1,123 @@
+// ============================================================================
+// TICK SIZE SCHEDULE - Minimum price increment per price band
+// ============================================================================
+// Real markets quote in coarser ticks at higher prices. A schedule maps price
+// bands to tick sizes; each band starts at `from_price` (inclusive) and runs
+// up to the next band's start, so a price sitting exactly on a boundary
+// always belongs to the higher band.
+
+use crate::matching_engine::OrderSide;
+use crate::price_units::Price;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TickBand {
+    pub from_price: Price,
+    pub tick_size: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TickSchedule {
+    bands: Vec<TickBand>,
+}
+
+/// A price that is not a multiple of its band's tick
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct TickViolation {
+    pub price: Price,
+    pub tick_size: u64,
+}
+
+impl fmt::Display for TickViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "price {} is not a multiple of tick size {}", self.price, self.tick_size)
+    }
+}
+
+impl std::error::Error for TickViolation {}
+
+impl TickSchedule {
+    /// The same tick everywhere.
+    pub fn uniform(tick_size: u64) -> Self {
+        TickSchedule { bands: vec![TickBand { from_price: Price::ZERO, tick_size: tick_size.max(1) }] }
+    }
+
+    /// Bands must start at 0, be strictly increasing, have non-zero ticks,
+    /// and start on a multiple of their own tick so every boundary is quotable.
+    pub fn new(bands: Vec<TickBand>) -> Result<Self, String> {
+        match bands.first() {
+            Some(first) if first.from_price == 0 => {}
+            _ => return Err("the first tick band must start at price 0".to_string()),
+        }
+        for (i, band) in bands.iter().enumerate() {
+            if band.tick_size == 0 {
+                return Err(format!("tick band at {} has a zero tick size", band.from_price));
+            }
+            if !band.from_price.is_on_tick(band.tick_size) {
+                return Err(format!("tick band at {} does not start on a multiple of {}", band.from_price, band.tick_size));
+            }
+            if i > 0 && band.from_price <= bands[i - 1].from_price {
+                return Err("tick bands must be sorted by strictly increasing from_price".to_string());
+            }
+        }
+        Ok(TickSchedule { bands })
+    }
+
+    pub fn bands(&self) -> &[TickBand] {
+        &self.bands
+    }
+
+    /// Tick size of the band containing `price`.
+    pub fn tick_for(&self, price: impl Into<Price>) -> u64 {
+        let price = price.into();
+        // partition_point finds the first band starting above `price`
+        let idx = self.bands.partition_point(|band| band.from_price <= price);
+        self.bands[idx.saturating_sub(1)].tick_size
+    }
+
+    pub fn validate(&self, price: impl Into<Price>) -> Result<(), TickViolation> {
+        let price = price.into();
+        let tick_size = self.tick_for(price);
+        if price.is_on_tick(tick_size) {
+            Ok(())
+        } else {
+            Err(TickViolation { price, tick_size })
+        }
+    }
+
+    /// Rounds passively onto the grid: buys down, sells up, so rounding
+    /// never makes an order more aggressive.
+    pub fn round(&self, price: impl Into<Price>, side: OrderSide) -> Price {
+        let price = price.into();
+        let tick_size = self.tick_for(price);
+        let below = price - price.units() % tick_size;
+        match side {
+            OrderSide::Buy => below,
+            OrderSide::Sell if below == price => price,
+            // Band starts are aligned to their own tick, so stepping up into
+            // the next band still lands on a valid price
+            OrderSide::Sell => below + tick_size,
+        }
+    }
+}
+
+/// Parses `from:tick` pairs separated by commas, e.g. `0:1,1000:5,10000:25`.
+impl FromStr for TickSchedule {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let bands = s
+            .split(',')
+            .map(|pair| {
+                let (from, tick) = pair.trim().split_once(':').ok_or_else(|| format!("expected from:tick, got {}", pair))?;
+                Ok(TickBand {
+                    from_price: Price(from.trim().parse().map_err(|e| format!("bad band start {}: {}", from, e))?),
+                    tick_size: tick.trim().parse().map_err(|e| format!("bad tick size {}: {}", tick, e))?,
+                })
+            })
+            .collect::<Result<Vec<_>, String>>()?;
+        TickSchedule::new(bands)
+    }
+}
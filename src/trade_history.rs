@@ -0,0 +1,176 @@
+// ============================================================================
+// TRADE HISTORY - Bounded ring of recent executions
+// ============================================================================
+// History can be appended inline (under the book lock) or offloaded: the
+// engine pushes executions into an SPSC ring and a writer thread drains them
+// into the history and onto the event bus, keeping the match critical
+// section minimal. Each trade is logged at info under the `trades` target
+// from the same place, so offloaded mode keeps that IO off the engine thread.
+
+use crate::events::{BookEvent, EventBus};
+use crate::matching_engine::TradeExecution;
+use log::info;
+use rtrb::{Consumer, Producer, RingBuffer};
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+pub const DEFAULT_TRADE_HISTORY_CAPACITY: usize = 10_000;
+
+/// Capacity of the engine -> writer ring in offloaded mode
+pub const TRADE_RING_CAPACITY: usize = 4096;
+
+/// Log target for executions: `RUST_LOG=info,trades=off` hides them alone
+pub const TRADE_LOG_TARGET: &str = "trades";
+
+/// How long the writer sleeps when it finds the ring empty
+const WRITER_IDLE_SLEEP: Duration = Duration::from_micros(50);
+
+pub struct TradeHistory {
+    trades: VecDeque<TradeExecution>,
+    capacity: usize,
+}
+
+impl TradeHistory {
+    pub fn new(capacity: usize) -> Self {
+        TradeHistory { trades: VecDeque::with_capacity(capacity), capacity }
+    }
+
+    /// Appends a trade, evicting the oldest once the cap is reached.
+    pub fn push(&mut self, trade: TradeExecution) {
+        if self.capacity == 0 {
+            return;
+        }
+        if self.trades.len() == self.capacity {
+            self.trades.pop_front();
+        }
+        self.trades.push_back(trade);
+    }
+
+    /// Changes the cap, evicting the oldest trades beyond it.
+    pub fn set_capacity(&mut self, capacity: usize) {
+        self.capacity = capacity;
+        while self.trades.len() > capacity {
+            self.trades.pop_front();
+        }
+    }
+
+    /// The last `n` trades, oldest first.
+    pub fn recent(&self, n: usize) -> Vec<TradeExecution> {
+        let skip = self.trades.len().saturating_sub(n);
+        self.trades.iter().skip(skip).cloned().collect()
+    }
+
+    /// Volume-weighted average price of the last `n` trades (fewer if
+    /// that is all there is); `None` before the first trade.
+    pub fn vwap(&self, n: usize) -> Option<f64> {
+        let (notional, volume) = self.trades.iter().rev().take(n).fold((0u128, 0u128), |(notional, volume), trade| {
+            (notional + trade.price.units() as u128 * trade.quantity as u128, volume + trade.quantity as u128)
+        });
+        (volume > 0).then(|| notional as f64 / volume as f64)
+    }
+
+    pub fn len(&self) -> usize {
+        self.trades.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.trades.is_empty()
+    }
+}
+
+/// Where the engine sends executions.
+pub enum TradeSink {
+    /// Appended to the history inside the book critical section
+    Inline(Arc<Mutex<TradeHistory>>),
+    /// Handed to the history writer thread over an SPSC ring
+    Offloaded(OffloadedTrades),
+}
+
+/// Engine side of an offloaded sink. Bus sequences are reserved here, on the
+/// engine thread, so the writer's trade events keep their place in the
+/// engine's order however late it publishes them.
+pub struct OffloadedTrades {
+    producer: Producer<(u64, TradeExecution)>,
+    bus: Option<Arc<EventBus>>,
+}
+
+impl TradeSink {
+    /// Builds an offloaded sink and starts the writer thread that drains it.
+    /// The writer exits once the sink is dropped and the ring is empty.
+    pub fn offloaded(history: Arc<Mutex<TradeHistory>>, bus: Option<Arc<EventBus>>) -> std::io::Result<(TradeSink, JoinHandle<()>)> {
+        let (producer, consumer) = RingBuffer::new(TRADE_RING_CAPACITY);
+        let writer = thread::Builder::new()
+            .name("trade-history".to_string())
+            .spawn({
+                let bus = bus.clone();
+                move || run_history_writer(consumer, history, bus)
+            })?;
+        Ok((TradeSink::Offloaded(OffloadedTrades { producer, bus }), writer))
+    }
+
+    pub fn record(&mut self, executions: &[TradeExecution]) {
+        match self {
+            TradeSink::Inline(history) => {
+                {
+                    let mut history = history.lock().unwrap();
+                    for exec in executions {
+                        history.push(exec.clone());
+                    }
+                }
+                executions.iter().for_each(log_trade);
+            }
+            TradeSink::Offloaded(OffloadedTrades { producer, bus }) => {
+                let first_sequence = match bus {
+                    Some(bus) if !executions.is_empty() => bus.reserve(executions.len() as u64),
+                    _ => 0,
+                };
+                for (sequence, exec) in (first_sequence..).zip(executions) {
+                    let mut exec = (sequence, exec.clone());
+                    // Never drop a trade: wait for the writer to make room
+                    while let Err(rtrb::PushError::Full(rejected)) = producer.push(exec) {
+                        exec = rejected;
+                        std::hint::spin_loop();
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn run_history_writer(mut consumer: Consumer<(u64, TradeExecution)>, history: Arc<Mutex<TradeHistory>>, bus: Option<Arc<EventBus>>) {
+    loop {
+        let available = consumer.slots();
+        if available == 0 {
+            if consumer.is_abandoned() && consumer.is_empty() {
+                return;
+            }
+            thread::sleep(WRITER_IDLE_SLEEP);
+            continue;
+        }
+
+        // Take the whole burst under one history lock
+        let Ok(chunk) = consumer.read_chunk(available) else {
+            continue;
+        };
+        let trades: Vec<(u64, TradeExecution)> = chunk.into_iter().collect();
+        {
+            let mut history = history.lock().unwrap();
+            for (_, trade) in &trades {
+                history.push(trade.clone());
+            }
+        }
+        trades.iter().for_each(|(_, trade)| log_trade(trade));
+        if let Some(bus) = &bus {
+            for (sequence, trade) in trades {
+                bus.publish_reserved(sequence, BookEvent::Trade(trade));
+            }
+        }
+    }
+}
+
+fn log_trade(exec: &TradeExecution) {
+    info!(target: TRADE_LOG_TARGET, "💰 TRADE: {} matched with {} @ {} (Qty: {})",
+        exec.taker_order_id, exec.maker_order_id, exec.price, exec.quantity);
+}
@@ -0,0 +1,107 @@
+// ============================================================================
+// WAIT STRATEGY - What the engine does when the ring is empty
+// ============================================================================
+// Busy spinning answers fastest but keeps a core at 100% even with no
+// orders. Yielding lets other threads run on that core, sleeping gives it
+// back entirely at the cost of up to one sleep of latency on the next order.
+// Backoff spins through short gaps, then yields, then sleeps once the ring
+// has stayed empty long enough, and drops back to spinning when work arrives.
+
+use std::thread;
+use std::time::Duration;
+
+/// Empty polls `backoff` spins through before it starts yielding
+pub const DEFAULT_BACKOFF_SPINS: u32 = 10_000;
+
+/// Further empty polls `backoff` yields through before it starts sleeping
+pub const DEFAULT_BACKOFF_YIELDS: u32 = 1_000;
+
+/// Sleep for `sleep` and `backoff` unless `ENGINE_IDLE_SLEEP_US` says otherwise
+pub const DEFAULT_IDLE_SLEEP: Duration = Duration::from_micros(100);
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum WaitStrategy {
+    /// `spin_loop` between polls; lowest latency, one core fully busy
+    #[default]
+    BusySpin,
+    /// `thread::yield_now` between polls
+    Yield,
+    /// Sleep this long between polls
+    Sleep(Duration),
+    /// Spin for `spins` empty polls, yield for the next `yields`, then sleep
+    Backoff { spins: u32, yields: u32, sleep: Duration },
+}
+
+impl WaitStrategy {
+    /// `busy-spin`, `yield`, `sleep` or `backoff`, as `ENGINE_WAIT` spells
+    /// them; `sleep` is how long the last two sleep for
+    pub fn by_name(name: &str, sleep: Duration) -> Result<WaitStrategy, String> {
+        match name.trim() {
+            "busy-spin" => Ok(WaitStrategy::BusySpin),
+            "yield" => Ok(WaitStrategy::Yield),
+            "sleep" => Ok(WaitStrategy::Sleep(sleep)),
+            "backoff" => Ok(WaitStrategy::Backoff { spins: DEFAULT_BACKOFF_SPINS, yields: DEFAULT_BACKOFF_YIELDS, sleep }),
+            other => Err(format!("expected busy-spin, yield, sleep or backoff, got {}", other)),
+        }
+    }
+}
+
+/// One way of waiting out an empty poll
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IdleAction {
+    Spin,
+    Yield,
+    Sleep(Duration),
+}
+
+/// Applies a `WaitStrategy`, counting the empty polls since work last arrived
+#[derive(Debug, Clone)]
+pub struct Idler {
+    strategy: WaitStrategy,
+    empty_polls: u64,
+}
+
+impl Idler {
+    pub fn new(strategy: WaitStrategy) -> Self {
+        Idler { strategy, empty_polls: 0 }
+    }
+
+    /// What the next empty poll will do
+    pub fn next_action(&self) -> IdleAction {
+        match self.strategy {
+            WaitStrategy::BusySpin => IdleAction::Spin,
+            WaitStrategy::Yield => IdleAction::Yield,
+            WaitStrategy::Sleep(sleep) => IdleAction::Sleep(sleep),
+            WaitStrategy::Backoff { spins, yields, sleep } => {
+                if self.empty_polls < spins as u64 {
+                    IdleAction::Spin
+                } else if self.empty_polls < spins as u64 + yields as u64 {
+                    IdleAction::Yield
+                } else {
+                    IdleAction::Sleep(sleep)
+                }
+            }
+        }
+    }
+
+    /// Waits once after an empty poll and says how
+    pub fn idle(&mut self) -> IdleAction {
+        let action = self.next_action();
+        self.empty_polls = self.empty_polls.saturating_add(1);
+        match action {
+            IdleAction::Spin => std::hint::spin_loop(),
+            IdleAction::Yield => thread::yield_now(),
+            IdleAction::Sleep(sleep) => thread::sleep(sleep),
+        }
+        action
+    }
+
+    /// Work arrived: backoff starts over from spinning
+    pub fn reset(&mut self) {
+        self.empty_polls = 0;
+    }
+
+    pub fn empty_polls(&self) -> u64 {
+        self.empty_polls
+    }
+}
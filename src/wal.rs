@@ -0,0 +1,88 @@
+// ============================================================================
+// WRITE-AHEAD LOG - Every accepted order on disk before it is matched
+// ============================================================================
+// Entries use the warm-start journal format, one `JournalEntry` per line, so
+// `read_journal` and `recover` rebuild the book after a restart. Cancels and
+// book-wide commands are journaled alongside orders, in the order the engine
+// applied them. Each entry goes out in a single write, which leaves at most
+// one torn line behind if the process dies mid-write; opening the log cuts
+// that line off before appending. Entries reach the OS on every append but
+// are not fsynced, so a power loss can still drop the last few.
+
+use crate::matching_engine::{BookAction, Order, OrderBook};
+use crate::warm_start::{read_journal, BookSnapshotFile, JournalEntry, WarmStartError};
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+/// Entries between snapshots when none is configured
+pub const DEFAULT_CHECKPOINT_EVERY: u64 = 10_000;
+
+pub struct WriteAheadLog {
+    file: File,
+    last_sequence: u64,
+    /// Where to write snapshots, and after how many entries
+    checkpoints: Option<(PathBuf, u64)>,
+}
+
+impl WriteAheadLog {
+    /// Opens (or creates) the log at `path` for appending and returns the
+    /// complete entries already in it.
+    pub fn open(path: impl AsRef<Path>) -> Result<(Self, Vec<JournalEntry>), WarmStartError> {
+        let path = path.as_ref();
+        let file = OpenOptions::new().read(true).append(true).create(true).open(path)?;
+        let (entries, valid_len) = read_journal(path)?;
+        if file.metadata()?.len() > valid_len {
+            file.set_len(valid_len)?;
+        }
+        let last_sequence = entries.last().map_or(0, |entry| entry.sequence);
+        Ok((WriteAheadLog { file, last_sequence, checkpoints: None }, entries))
+    }
+
+    /// Continues numbering after `sequence`, e.g. the position a recovered
+    /// snapshot reflects when the log itself was emptied.
+    pub fn resume_after(mut self, sequence: u64) -> Self {
+        self.last_sequence = self.last_sequence.max(sequence);
+        self
+    }
+
+    /// Snapshots the book to `path` every `every` entries (0 = never).
+    pub fn with_checkpoints(mut self, path: impl Into<PathBuf>, every: u64) -> Self {
+        self.checkpoints = (every > 0).then(|| (path.into(), every));
+        self
+    }
+
+    /// Journals `order` and returns its sequence.
+    pub fn append(&mut self, order: &Order) -> Result<u64, WarmStartError> {
+        self.append_action(order, BookAction::Submit)
+    }
+
+    /// Journals a packet's `action` on `order` and returns its sequence.
+    pub fn append_action(&mut self, order: &Order, action: BookAction) -> Result<u64, WarmStartError> {
+        let entry = JournalEntry { sequence: self.last_sequence + 1, order: order.clone(), action };
+        let mut line = serde_json::to_vec(&entry).map_err(|e| WarmStartError::Parse(e.to_string()))?;
+        line.push(b'\n');
+        self.file.write_all(&line)?;
+        self.last_sequence = entry.sequence;
+        Ok(entry.sequence)
+    }
+
+    pub fn last_sequence(&self) -> u64 {
+        self.last_sequence
+    }
+
+    /// A snapshot of `book` when the latest entry lands on a checkpoint,
+    /// to be saved with `save_checkpoint` once the book is unlocked.
+    pub fn checkpoint_due(&self, book: &OrderBook) -> Option<BookSnapshotFile> {
+        let (_, every) = self.checkpoints.as_ref()?;
+        (self.last_sequence > 0 && self.last_sequence.is_multiple_of(*every))
+            .then(|| BookSnapshotFile::capture(book, self.last_sequence))
+    }
+
+    pub fn save_checkpoint(&self, snapshot: &BookSnapshotFile) -> Result<(), WarmStartError> {
+        match &self.checkpoints {
+            Some((path, _)) => snapshot.save(path),
+            None => Ok(()),
+        }
+    }
+}
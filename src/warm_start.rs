@@ -0,0 +1,318 @@
+// ============================================================================
+// WARM START - Snapshot + journal tail, verified before going live
+// ============================================================================
+// A snapshot holds the resting book as of some journal sequence. On start
+// the journal entries after that sequence are replayed on top of it and the
+// resulting book's checksum is compared against the one recorded when the
+// pair was written, so a corrupt snapshot or journal is caught up front.
+// A journal whose last line was cut short by a crash is read up to that line.
+
+use crate::matching_engine::{BookAction, Order, OrderBook};
+use crate::price_units::Price;
+use serde::{Deserialize, Serialize};
+use log::warn;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Write};
+use std::path::Path;
+
+/// The resting book as of journal entry `sequence`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BookSnapshotFile {
+    pub sequence: u64,
+    /// `OrderBook::checksum` when the snapshot was taken: `orders`, their
+    /// reserves and `parked_icebergs`
+    pub checksum: u64,
+    pub orders: Vec<Order>,
+    /// Stops waiting for their trigger; not covered by `checksum`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub stops: Vec<Order>,
+    /// Hidden reserve by order id, for icebergs resting or parked. `Order`
+    /// never serializes it, so it is kept here instead
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub reserves: BTreeMap<u64, u64>,
+    /// Icebergs off the book waiting for their next slice
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub parked_icebergs: Vec<Order>,
+    /// External price the pegged orders in `orders` sit against
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reference_price: Option<Price>,
+    /// The book's duplicate window, oldest first
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub accepted_ids: Vec<u64>,
+}
+
+impl BookSnapshotFile {
+    pub fn capture(book: &OrderBook, sequence: u64) -> Self {
+        let orders = book.resting_orders();
+        let parked_icebergs = book.parked_icebergs();
+        let reserves = orders.iter().chain(&parked_icebergs)
+            .filter(|order| order.hidden_quantity > 0)
+            .map(|order| (order.id, order.hidden_quantity))
+            .collect();
+        BookSnapshotFile {
+            sequence,
+            checksum: book.checksum(),
+            orders,
+            stops: book.pending_stops().to_vec(),
+            reserves,
+            parked_icebergs,
+            reference_price: book.reference_price(),
+            accepted_ids: book.accepted_order_ids(),
+        }
+    }
+
+    /// The captured book at `sequence` in a plain `OrderBook`, see
+    /// `restore_into`.
+    pub fn restore(&self) -> OrderBook {
+        self.restore_into(OrderBook::new())
+    }
+
+    /// Loads the captured book into `book`, an empty book already carrying
+    /// the deployment's configuration: reserves, stops, parked icebergs and
+    /// pegs are put back, and the snapshot's ids seed its duplicate window.
+    /// Does not check the checksum.
+    pub fn restore_into(&self, mut book: OrderBook) -> OrderBook {
+        book.load_resting(self.sequence, self.orders.iter().map(|order| self.with_reserve(order)).collect());
+        for stop in &self.stops {
+            book.park_stop(stop.clone());
+        }
+        for order in &self.parked_icebergs {
+            book.park_iceberg(self.with_reserve(order));
+        }
+        book.restore_reference_price(self.reference_price);
+        book.remember_order_ids(recovered_order_ids(Some(self), &[]));
+        book
+    }
+
+    fn with_reserve(&self, order: &Order) -> Order {
+        let mut order = order.clone();
+        order.hidden_quantity = self.reserves.get(&order.id).copied().unwrap_or(0);
+        order
+    }
+
+    /// Writes to a temporary file beside `path` and renames it over `path`,
+    /// so a crash mid-write leaves the previous snapshot intact.
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), WarmStartError> {
+        let path = path.as_ref();
+        let mut partial = path.as_os_str().to_owned();
+        partial.push(".tmp");
+        let mut writer = BufWriter::new(File::create(&partial)?);
+        serde_json::to_writer(&mut writer, self).map_err(|e| WarmStartError::Parse(e.to_string()))?;
+        writer.into_inner().map_err(|e| e.into_error())?.sync_all()?;
+        std::fs::rename(&partial, path)?;
+        Ok(())
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, WarmStartError> {
+        let reader = BufReader::new(File::open(path)?);
+        serde_json::from_reader(reader).map_err(|e| WarmStartError::Parse(e.to_string()))
+    }
+}
+
+/// One journaled packet; sequences start at 1 and have no gaps.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JournalEntry {
+    pub sequence: u64,
+    pub order: Order,
+    /// Left out for plain orders, so journals from before cancels were
+    /// journaled still read
+    #[serde(default, skip_serializing_if = "BookAction::is_submit")]
+    pub action: BookAction,
+}
+
+/// Writes a journal: one `JournalEntry` JSON object per line.
+pub fn save_journal(path: impl AsRef<Path>, entries: &[JournalEntry]) -> Result<(), WarmStartError> {
+    let mut writer = BufWriter::new(File::create(path)?);
+    for entry in entries {
+        let line = serde_json::to_string(entry).map_err(|e| WarmStartError::Parse(e.to_string()))?;
+        writeln!(writer, "{}", line)?;
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+/// Reads a journal written by `save_journal` or a `WriteAheadLog`.
+pub fn load_journal(path: impl AsRef<Path>) -> Result<Vec<JournalEntry>, WarmStartError> {
+    read_journal(path).map(|(entries, _)| entries)
+}
+
+/// Like `load_journal`, also returning how many bytes of the file hold
+/// complete entries. A torn last entry (no newline, or unparseable with
+/// nothing after it) is skipped with a warning; damage anywhere else is an
+/// error.
+pub fn read_journal(path: impl AsRef<Path>) -> Result<(Vec<JournalEntry>, u64), WarmStartError> {
+    let bytes = std::fs::read(path)?;
+    let mut entries = Vec::new();
+    let mut valid_len = 0;
+    let mut rest = &bytes[..];
+    while !rest.is_empty() {
+        let Some(end) = rest.iter().position(|&b| b == b'\n') else {
+            warn!("⚠️  Journal ends in a torn entry; skipping its last {} bytes", rest.len());
+            break;
+        };
+        let line = &rest[..end];
+        rest = &rest[end + 1..];
+        if !line.trim_ascii().is_empty() {
+            match serde_json::from_slice(line) {
+                Ok(entry) => entries.push(entry),
+                Err(_) if rest.trim_ascii().is_empty() => {
+                    warn!("⚠️  Journal ends in an unreadable entry; skipping it");
+                    break;
+                }
+                Err(e) => return Err(WarmStartError::Parse(e.to_string())),
+            }
+        }
+        valid_len = bytes.len() - rest.len();
+    }
+    Ok((entries, valid_len as u64))
+}
+
+/// What startup does when verification fails.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MismatchAction {
+    /// Log the mismatch and start from the replayed book anyway
+    Warn,
+    /// Refuse to start
+    Fail,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WarmStartError {
+    /// The snapshot's orders don't hash to the checksum stored with them
+    SnapshotChecksum { expected: u64, actual: u64 },
+    /// The journal skips or repeats a sequence after the snapshot
+    JournalGap { expected: u64, found: u64 },
+    /// Snapshot + journal replay doesn't reach the expected checksum
+    ChecksumMismatch { expected: u64, actual: u64 },
+    Io(String),
+    Parse(String),
+}
+
+impl fmt::Display for WarmStartError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WarmStartError::SnapshotChecksum { expected, actual } => {
+                write!(f, "snapshot checksum {:#018x} does not match its orders ({:#018x})", expected, actual)
+            }
+            WarmStartError::JournalGap { expected, found } => {
+                write!(f, "journal expected sequence {} but found {}", expected, found)
+            }
+            WarmStartError::ChecksumMismatch { expected, actual } => {
+                write!(f, "replayed book checksum {:#018x} does not match expected {:#018x}", actual, expected)
+            }
+            WarmStartError::Io(e) => write!(f, "warm start I/O error: {}", e),
+            WarmStartError::Parse(e) => write!(f, "warm start parse error: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for WarmStartError {}
+
+impl From<std::io::Error> for WarmStartError {
+    fn from(e: std::io::Error) -> Self {
+        WarmStartError::Io(e.to_string())
+    }
+}
+
+/// Restores `snapshot` into a book from `new_book`, replays the journal
+/// entries after its sequence and checks the result against
+/// `expected_checksum`. Returns the replayed book and the last journal
+/// sequence applied.
+///
+/// `new_book` must build the book the way the live engine's was configured:
+/// match policy, speed bump and wash-trade detection all change what a
+/// replayed order does.
+pub fn verify_warm_start(
+    new_book: impl Fn() -> OrderBook,
+    snapshot: &BookSnapshotFile,
+    journal: &[JournalEntry],
+    expected_checksum: u64,
+) -> Result<(OrderBook, u64), WarmStartError> {
+    let book = restore_verified(snapshot, new_book())?;
+    let (book, sequence) = replay_tail(book, snapshot.sequence, journal)?;
+    let actual = book.checksum();
+    if actual != expected_checksum {
+        return Err(WarmStartError::ChecksumMismatch { expected: expected_checksum, actual });
+    }
+    Ok((book, sequence))
+}
+
+/// Applies the journal entries after `sequence`, which must follow on
+/// without gaps.
+pub fn replay_tail(
+    mut book: OrderBook,
+    mut sequence: u64,
+    journal: &[JournalEntry],
+) -> Result<(OrderBook, u64), WarmStartError> {
+    let start = sequence;
+    for entry in journal.iter().filter(|entry| entry.sequence > start) {
+        if entry.sequence != sequence + 1 {
+            return Err(WarmStartError::JournalGap { expected: sequence + 1, found: entry.sequence });
+        }
+        book.apply_action(entry.order.clone(), entry.action);
+        sequence = entry.sequence;
+    }
+    Ok((book, sequence))
+}
+
+/// Startup entry point: verifies the pair and, under `MismatchAction::Warn`,
+/// falls back to the snapshot plus every later journal entry when it fails.
+/// `new_book` is as for `verify_warm_start`.
+pub fn warm_start(
+    new_book: impl Fn() -> OrderBook,
+    snapshot: &BookSnapshotFile,
+    journal: &[JournalEntry],
+    expected_checksum: u64,
+    action: MismatchAction,
+) -> Result<OrderBook, WarmStartError> {
+    match verify_warm_start(&new_book, snapshot, journal, expected_checksum) {
+        Ok((book, _)) => Ok(book),
+        Err(e) if action == MismatchAction::Fail => Err(e),
+        Err(e) => {
+            warn!("⚠️  Warm start verification failed: {}", e);
+            let mut book = snapshot.restore_into(new_book());
+            for entry in journal.iter().filter(|entry| entry.sequence > snapshot.sequence) {
+                book.apply_action(entry.order.clone(), entry.action);
+            }
+            Ok(book)
+        }
+    }
+}
+
+/// Crash recovery from a write-ahead log: the snapshot (if any, checked
+/// against its own checksum) plus every journal entry after it, replayed
+/// into a book from `new_book` (as for `verify_warm_start`). Returns the
+/// book and the last journal sequence it reflects.
+pub fn recover(
+    new_book: impl Fn() -> OrderBook,
+    snapshot: Option<&BookSnapshotFile>,
+    journal: &[JournalEntry],
+) -> Result<(OrderBook, u64), WarmStartError> {
+    match snapshot {
+        Some(snapshot) => replay_tail(restore_verified(snapshot, new_book())?, snapshot.sequence, journal),
+        None => replay_tail(new_book(), 0, journal),
+    }
+}
+
+/// Ids the recovered book had accepted, oldest first: the snapshot's
+/// duplicate window, its orders and stops, then every order the journal
+/// submitted. A book with a duplicate window gets them back from
+/// `restore_into` and replay; this is for anything else numbering on from
+/// them, like the decision bridge.
+pub fn recovered_order_ids(snapshot: Option<&BookSnapshotFile>, journal: &[JournalEntry]) -> Vec<u64> {
+    let windowed = snapshot.into_iter().flat_map(|snapshot| snapshot.accepted_ids.iter().copied());
+    let snapshotted = snapshot.into_iter().flat_map(|snapshot| snapshot.orders.iter().chain(&snapshot.stops));
+    let journaled = journal.iter().filter(|entry| entry.action.is_submit()).map(|entry| &entry.order);
+    windowed.chain(snapshotted.chain(journaled).map(|order| order.id)).collect()
+}
+
+fn restore_verified(snapshot: &BookSnapshotFile, book: OrderBook) -> Result<OrderBook, WarmStartError> {
+    let book = snapshot.restore_into(book);
+    let actual = book.checksum();
+    if actual != snapshot.checksum {
+        return Err(WarmStartError::SnapshotChecksum { expected: snapshot.checksum, actual });
+    }
+    Ok(book)
+}
@@ -0,0 +1,97 @@
+// ============================================================================
+// WASH TRADE DETECTION - Accounts that keep crossing their own orders
+// ============================================================================
+// STP stops a single order from trading with its own account, but an account
+// that keeps firing orders into its own resting quotes is still suspect. Each
+// incoming order that meets its own account on the other side (prevented by
+// STP or not) counts as one self-cross; too many inside a sliding window flags
+// the account, and can optionally shut it out for a while.
+
+use crate::clock::Clock;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::sync::Arc;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WashTradeConfig {
+    /// Sliding window the self-crosses are counted over
+    pub window_ns: u64,
+    /// Self-crosses inside the window that flag the account
+    pub max_self_crosses: usize,
+    /// How long a flagged account's orders are refused (0 = flag only)
+    pub throttle_ns: u64,
+}
+
+/// An account caught crossing itself too often
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct WashTradeFlag {
+    pub account_id: u64,
+    /// Clock time the threshold was last crossed
+    pub flagged_at_ns: u64,
+    /// Self-crosses inside the window at that moment
+    pub self_crosses: usize,
+    /// Orders refused while throttled
+    pub throttled_orders: u64,
+}
+
+/// Detection state owned by an order book
+pub(crate) struct WashTradeDetector {
+    config: WashTradeConfig,
+    clock: Arc<dyn Clock>,
+    /// Self-cross times per account inside the window, oldest first
+    recent: HashMap<u64, VecDeque<u64>>,
+    flags: BTreeMap<u64, WashTradeFlag>,
+}
+
+impl WashTradeDetector {
+    pub(crate) fn new(config: WashTradeConfig, clock: Arc<dyn Clock>) -> Self {
+        WashTradeDetector { config, clock, recent: HashMap::new(), flags: BTreeMap::new() }
+    }
+
+    /// Notes one incoming order from `account_id` that met its own quote.
+    pub(crate) fn record_self_cross(&mut self, account_id: u64) {
+        let now = self.clock.now_ns();
+        let crosses = self.recent.entry(account_id).or_default();
+        crosses.push_back(now);
+        while crosses.front().is_some_and(|&at| now - at > self.config.window_ns) {
+            crosses.pop_front();
+        }
+        if crosses.len() >= self.config.max_self_crosses {
+            let count = crosses.len();
+            let flag = self.flags.entry(account_id).or_insert(WashTradeFlag {
+                account_id,
+                flagged_at_ns: now,
+                self_crosses: count,
+                throttled_orders: 0,
+            });
+            flag.flagged_at_ns = now;
+            flag.self_crosses = count;
+        }
+    }
+
+    /// True (and counted) if the account's orders are being refused right now.
+    pub(crate) fn throttle(&mut self, account_id: u64) -> bool {
+        let now = self.clock.now_ns();
+        let throttle_ns = self.config.throttle_ns;
+        match self.flags.get_mut(&account_id) {
+            Some(flag) if throttle_ns > 0 && now - flag.flagged_at_ns < throttle_ns => {
+                flag.throttled_orders += 1;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    pub(crate) fn flag(&self, account_id: u64) -> Option<&WashTradeFlag> {
+        self.flags.get(&account_id)
+    }
+
+    pub(crate) fn flags(&self) -> Vec<WashTradeFlag> {
+        self.flags.values().copied().collect()
+    }
+
+    pub(crate) fn clear(&mut self, account_id: u64) -> bool {
+        self.recent.remove(&account_id);
+        self.flags.remove(&account_id).is_some()
+    }
+}
@@ -0,0 +1,146 @@
+// ============================================================================
+// WEBSOCKET - Just enough RFC 6455 to push depth updates to browsers
+// ============================================================================
+// tiny_http hands over the raw socket after a 101 response; this module
+// supplies the handshake key, server-to-client text frames and the depth
+// stream itself. Nothing here reads client frames: the stream ends when a
+// write fails, which is how a closed browser tab shows up.
+
+use crate::book_diff::{LevelChange, OrderBookSnapshot};
+use crate::matching_engine::OrderBook;
+use serde::Serialize;
+use std::io::Write;
+use std::sync::Mutex;
+use std::thread;
+use std::time::Duration;
+
+/// Appended to the client's key before hashing, per RFC 6455
+const HANDSHAKE_GUID: &str = "258EAFA5-E914-47DA-95CA-C5AB0DC85B11";
+
+/// How often the depth stream looks for a new book sequence
+pub const DEPTH_STREAM_POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+/// One message on the depth stream: a full snapshot on connect, then only
+/// the levels that changed
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum DepthMessage {
+    Snapshot(OrderBookSnapshot),
+    Diff { sequence: u64, changes: Vec<LevelChange> },
+}
+
+/// `Sec-WebSocket-Accept` for a client's `Sec-WebSocket-Key`
+pub fn accept_key(client_key: &str) -> String {
+    base64(&sha1(format!("{}{}", client_key.trim(), HANDSHAKE_GUID).as_bytes()))
+}
+
+/// An unmasked, unfragmented text frame
+pub fn text_frame(payload: &str) -> Vec<u8> {
+    let payload = payload.as_bytes();
+    let mut frame = Vec::with_capacity(payload.len() + 10);
+    frame.push(0x81); // FIN + text
+    match payload.len() {
+        len @ 0..=125 => frame.push(len as u8),
+        len @ 126..=0xFFFF => {
+            frame.push(126);
+            frame.extend_from_slice(&(len as u16).to_be_bytes());
+        }
+        len => {
+            frame.push(127);
+            frame.extend_from_slice(&(len as u64).to_be_bytes());
+        }
+    }
+    frame.extend_from_slice(payload);
+    frame
+}
+
+/// Sends a snapshot, then a diff each time the book's sequence moves and
+/// a level actually changed. Returns once the client stops accepting writes.
+pub fn stream_depth(mut stream: impl Write, order_book: &Mutex<OrderBook>) {
+    let mut last = order_book.lock().unwrap().snapshot();
+    if send(&mut stream, &DepthMessage::Snapshot(last.clone())).is_err() {
+        return;
+    }
+    loop {
+        thread::sleep(DEPTH_STREAM_POLL_INTERVAL);
+        let current = {
+            let book = order_book.lock().unwrap();
+            if book.sequence() == last.sequence {
+                continue;
+            }
+            book.snapshot()
+        };
+        let changes = last.diff(&current);
+        let sequence = current.sequence;
+        last = current;
+        if !changes.is_empty() && send(&mut stream, &DepthMessage::Diff { sequence, changes }).is_err() {
+            return;
+        }
+    }
+}
+
+fn send(stream: &mut impl Write, message: &DepthMessage) -> std::io::Result<()> {
+    let json = serde_json::to_string(message).expect("depth messages serialize");
+    stream.write_all(&text_frame(&json))?;
+    stream.flush()
+}
+
+fn sha1(message: &[u8]) -> [u8; 20] {
+    let mut h: [u32; 5] = [0x6745_2301, 0xEFCD_AB89, 0x98BA_DCFE, 0x1032_5476, 0xC3D2_E1F0];
+    let mut padded = message.to_vec();
+    padded.push(0x80);
+    while padded.len() % 64 != 56 {
+        padded.push(0);
+    }
+    padded.extend_from_slice(&((message.len() as u64) * 8).to_be_bytes());
+
+    for block in padded.chunks(64) {
+        let mut w = [0u32; 80];
+        for (i, word) in block.chunks(4).enumerate() {
+            w[i] = u32::from_be_bytes([word[0], word[1], word[2], word[3]]);
+        }
+        for i in 16..80 {
+            w[i] = (w[i - 3] ^ w[i - 8] ^ w[i - 14] ^ w[i - 16]).rotate_left(1);
+        }
+        let [mut a, mut b, mut c, mut d, mut e] = h;
+        for (i, &word) in w.iter().enumerate() {
+            let (f, k) = match i {
+                0..=19 => ((b & c) | (!b & d), 0x5A82_7999),
+                20..=39 => (b ^ c ^ d, 0x6ED9_EBA1),
+                40..=59 => ((b & c) | (b & d) | (c & d), 0x8F1B_BCDC),
+                _ => (b ^ c ^ d, 0xCA62_C1D6),
+            };
+            let temp = a.rotate_left(5).wrapping_add(f).wrapping_add(e).wrapping_add(k).wrapping_add(word);
+            e = d;
+            d = c;
+            c = b.rotate_left(30);
+            b = a;
+            a = temp;
+        }
+        for (state, value) in h.iter_mut().zip([a, b, c, d, e]) {
+            *state = state.wrapping_add(value);
+        }
+    }
+
+    let mut digest = [0u8; 20];
+    for (chunk, word) in digest.chunks_mut(4).zip(h) {
+        chunk.copy_from_slice(&word.to_be_bytes());
+    }
+    digest
+}
+
+fn base64(bytes: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut out = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let n = (chunk[0] as u32) << 16 | (*chunk.get(1).unwrap_or(&0) as u32) << 8 | *chunk.get(2).unwrap_or(&0) as u32;
+        for i in 0..4 {
+            if i <= chunk.len() {
+                out.push(ALPHABET[(n >> (18 - 6 * i) & 0x3F) as usize] as char);
+            } else {
+                out.push('=');
+            }
+        }
+    }
+    out
+}
@@ -0,0 +1,55 @@
+// ============================================================================
+// AMEND ORDER - Size decreases keep queue priority, everything else loses it
+// ============================================================================
+
+use hft_ringbuffer::matching_engine::{AmendError, Order, OrderBook, OrderSide};
+use hft_ringbuffer::price_units::Price;
+
+/// Three bids queued at 100, oldest first
+fn queued_bids() -> OrderBook {
+    let mut book = OrderBook::new();
+    for id in 1..=3 {
+        book.add_limit_order(Order::new(id, OrderSide::Buy, 100, 5));
+    }
+    book
+}
+
+fn fill_order(book: &mut OrderBook, price: u64, quantity: u64) -> Vec<u64> {
+    let executions = book.add_limit_order(Order::new(99, OrderSide::Sell, price, quantity));
+    executions.iter().map(|e| e.maker_order_id).collect()
+}
+
+#[test]
+fn size_decrease_keeps_its_place() {
+    let mut book = queued_bids();
+    assert_eq!(book.amend_order(1, None, Some(2)), Ok(Vec::new()));
+    assert_eq!(fill_order(&mut book, 100, 3), vec![1, 2]);
+}
+
+#[test]
+fn size_increase_goes_to_the_back() {
+    let mut book = queued_bids();
+    book.amend_order(1, None, Some(8)).unwrap();
+    assert_eq!(fill_order(&mut book, 100, 11), vec![2, 3, 1]);
+}
+
+#[test]
+fn reprice_goes_to_the_back_of_the_new_level() {
+    let mut book = queued_bids();
+    book.add_limit_order(Order::new(4, OrderSide::Buy, 101, 1));
+    book.amend_order(1, Some(Price(101)), None).unwrap();
+    assert_eq!(book.best_bid(), Some(Price(101)));
+    assert_eq!(fill_order(&mut book, 101, 6), vec![4, 1]);
+
+    // Moving back to 100 does not restore the old place either
+    book.amend_order(2, Some(Price(99)), None).unwrap();
+    book.amend_order(2, Some(Price(100)), None).unwrap();
+    assert_eq!(fill_order(&mut book, 100, 10), vec![3, 2]);
+}
+
+#[test]
+fn unknown_order_is_a_typed_error() {
+    let mut book = queued_bids();
+    assert_eq!(book.amend_order(42, Some(Price(101)), None), Err(AmendError::UnknownOrder(42)));
+    assert_eq!(book.best_bid(), Some(Price(100)));
+}
@@ -0,0 +1,70 @@
+// ============================================================================
+// API KEYS - Mutating HTTP requests need a configured key
+// ============================================================================
+
+mod common;
+
+use common::{http_exchange_with_headers, http_request, TestServers};
+use hft_ringbuffer::auth::ApiKeys;
+use hft_ringbuffer::config::Config;
+use std::collections::HashMap;
+
+const ORDER: &str = r#"{"id":1,"side":"Buy","price":100,"quantity":5}"#;
+
+fn post(servers: &TestServers, path: &str, headers: &[(&str, &str)], body: &str) -> (u16, serde_json::Value) {
+    let (status, _, body) = http_exchange_with_headers(&servers.http_addr, "POST", path, headers, body);
+    (status, serde_json::from_str(&body).unwrap())
+}
+
+#[test]
+fn keys_are_checked_from_either_header() {
+    let keys = ApiKeys::new(["alpha", "beta"]);
+    assert!(keys.authorize(Some("alpha"), None));
+    assert!(keys.authorize(None, Some("Bearer beta")));
+    assert!(!keys.authorize(Some("gamma"), Some("Bearer gamma")));
+    assert!(!keys.authorize(None, Some("beta")));
+    assert!(!keys.authorize(Some("alph"), None));
+    assert!(!keys.authorize(None, None));
+    // No keys configured: nothing is checked
+    assert!(ApiKeys::default().authorize(None, None));
+}
+
+#[test]
+fn keys_come_from_the_file_or_the_environment() {
+    let config = Config::from_json(r#"{"api_keys": ["alpha"]}"#).unwrap();
+    assert_eq!(config.api_keys(), ApiKeys::new(["alpha"]));
+
+    let env: HashMap<&str, &str> = [("API_KEYS", " beta, gamma ,")].into();
+    let mut config = Config::default();
+    config.apply_env(|name| env.get(name).map(|value| value.to_string())).unwrap();
+    assert_eq!(config.api_keys, vec!["beta", "gamma"]);
+
+    assert!(Config::from_json(r#"{"api_keys": [" "]}"#).is_err());
+}
+
+#[test]
+fn mutating_requests_without_a_valid_key_get_401() {
+    let servers = TestServers::start();
+    servers.metrics.set_api_keys(ApiKeys::new(["secret"]));
+
+    for path in ["/api/order", "/api/ai-decision", "/api/crypto-decision"] {
+        let (status, body) = post(&servers, path, &[], ORDER);
+        assert_eq!(status, 401, "{}", path);
+        assert_eq!(body["status"], "error");
+        let (status, _) = post(&servers, path, &[("X-API-Key", "wrong")], ORDER);
+        assert_eq!(status, 401, "{}", path);
+    }
+    let (status, _, _) = http_exchange_with_headers(&servers.http_addr, "DELETE", "/api/order/1", &[], "");
+    assert_eq!(status, 401);
+    assert!(servers.order_book.lock().unwrap().resting_orders().is_empty());
+
+    let (status, body) = post(&servers, "/api/order", &[("X-API-Key", "secret")], ORDER);
+    assert_eq!((status, body["status"].as_str()), (200, Some("accepted")));
+    let (status, _) = post(&servers, "/api/ai-decision", &[("Authorization", "Bearer secret")], r#"{"signal":"BUY"}"#);
+    assert_eq!(status, 200);
+
+    // Reads stay open
+    let (status, body) = http_request(&servers.http_addr, "GET", "/api/ai-decision", "");
+    assert_eq!((status, body.as_str()), (200, r#"{"signal":"BUY","reasoning":""}"#));
+    servers.stop();
+}
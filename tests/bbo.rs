@@ -0,0 +1,79 @@
+// ============================================================================
+// BBO PUBLISHER - Throttling and coalescing
+// ============================================================================
+
+use hft_ringbuffer::bbo::BboPublisher;
+use hft_ringbuffer::clock::ManualClock;
+use hft_ringbuffer::engine::{spawn_engine, EngineHooks};
+use hft_ringbuffer::events::{BookEvent, EventBus};
+use hft_ringbuffer::matching_engine::{BookAction, Order, OrderBook, OrderSide, Packet};
+use hft_ringbuffer::metrics::Metrics;
+use hft_ringbuffer::price_units::Price;
+use std::sync::atomic::AtomicBool;
+use std::sync::{Arc, Mutex};
+
+const MS: u64 = 1_000_000;
+
+fn limit(id: u64, side: OrderSide, price: u64, quantity: u64) -> Order {
+    Order::new(id, side, price, quantity)
+}
+
+#[test]
+fn rapid_changes_within_one_interval_emit_a_single_latest_sample() {
+    let clock = Arc::new(ManualClock::new(0));
+    let bus = Arc::new(EventBus::new());
+    let rx = bus.subscribe();
+    let mut publisher = BboPublisher::new(10 * MS, clock.clone(), bus.clone());
+    let mut book = OrderBook::new();
+
+    for (i, price) in [100, 101, 102, 103].into_iter().enumerate() {
+        book.add_limit_order(limit(i as u64, OrderSide::Buy, price, 1));
+        publisher.on_book_change(&book);
+        clock.advance(MS);
+    }
+    book.add_limit_order(limit(10, OrderSide::Sell, 110, 1));
+    publisher.on_book_change(&book);
+    assert!(rx.try_recv().is_err(), "nothing may be published inside the interval");
+
+    clock.advance(10 * MS);
+    publisher.poll();
+
+    let events: Vec<_> = rx.try_iter().map(|message| message.event).collect();
+    assert_eq!(events, vec![BookEvent::Bbo { bid: Some(Price(103)), ask: Some(Price(110)), bid_quantity: 1, ask_quantity: 1, timestamp_ns: 14 * MS, symbol: None }]);
+}
+
+#[test]
+fn zero_interval_publishes_on_every_change() {
+    let clock = Arc::new(ManualClock::new(0));
+    let bus = Arc::new(EventBus::new());
+    let rx = bus.subscribe();
+    let mut publisher = BboPublisher::new(0, clock, bus.clone());
+
+    publisher.update(Some((Price(100), 1)), None);
+    publisher.update(Some((Price(101), 1)), None);
+    publisher.update(Some((Price(101), 1)), None); // unchanged, suppressed
+    publisher.update(Some((Price(101), 2)), None); // size change
+    publisher.update(Some((Price(101), 2)), Some((Price(105), 1)));
+
+    assert_eq!(rx.try_iter().count(), 4);
+}
+
+#[test]
+fn cancels_through_the_engine_move_the_bbo() {
+    let bus = Arc::new(EventBus::new());
+    let rx = bus.subscribe();
+    let publisher = BboPublisher::new(0, Arc::new(ManualClock::new(0)), bus.clone());
+    let (mut producer, consumer) = rtrb::RingBuffer::<Packet>::new(4);
+    producer.push(Packet::new(limit(1, OrderSide::Buy, 100, 1))).unwrap();
+    producer.push(Packet::command(BookAction::Cancel, 1, None)).unwrap();
+
+    let hooks = EngineHooks { bbo: Some(publisher), drain_on_shutdown: true, ..Default::default() };
+    let book = Arc::new(Mutex::new(OrderBook::new()));
+    spawn_engine(consumer, book, Arc::new(AtomicBool::new(true)), Arc::new(Metrics::new()), hooks).unwrap().join().unwrap();
+
+    let bids: Vec<_> = rx.try_iter().map(|message| match message.event {
+        BookEvent::Bbo { bid, .. } => bid,
+        other => panic!("unexpected {:?}", other),
+    }).collect();
+    assert_eq!(bids, vec![Some(Price(100)), None]);
+}
@@ -0,0 +1,48 @@
+// ============================================================================
+// BBO HISTORY - Top of book as of a past sequence number
+// ============================================================================
+
+use hft_ringbuffer::matching_engine::{Order, OrderBook, OrderSide};
+use hft_ringbuffer::price_units::Price;
+
+#[test]
+fn bbo_at_an_intermediate_sequence() {
+    let mut book = OrderBook::new();
+    book.set_bbo_history_capacity(16);
+
+    book.add_limit_order(Order::new(1, OrderSide::Buy, 100, 5)); // seq 1
+    book.add_limit_order(Order::new(2, OrderSide::Sell, 110, 5)); // seq 2
+    book.add_limit_order(Order::new(3, OrderSide::Buy, 90, 5)); // seq 3, top unchanged
+    book.add_limit_order(Order::new(4, OrderSide::Buy, 105, 5)); // seq 4
+    book.add_limit_order(Order::new(5, OrderSide::Sell, 100, 10)); // seq 5, takes 105 and 100
+
+    assert_eq!(book.sequence(), 5);
+    assert_eq!(book.bbo_at(0), Some((None, None)));
+    assert_eq!(book.bbo_at(1), Some((Some(Price(100)), None)));
+    assert_eq!(book.bbo_at(2), Some((Some(Price(100)), Some(Price(110)))));
+    assert_eq!(book.bbo_at(3), Some((Some(Price(100)), Some(Price(110)))));
+    assert_eq!(book.bbo_at(4), Some((Some(Price(105)), Some(Price(110)))));
+    assert_eq!(book.bbo_at(5), Some((Some(Price(90)), Some(Price(110)))));
+    assert_eq!(book.bbo_at(6), None);
+}
+
+#[test]
+fn history_is_bounded() {
+    let mut book = OrderBook::new();
+    book.set_bbo_history_capacity(3);
+    for id in 1..=10 {
+        book.add_limit_order(Order::new(id, OrderSide::Buy, 100 + id, 1));
+    }
+
+    // Only the last three changes survive
+    assert_eq!(book.bbo_at(7), None);
+    assert_eq!(book.bbo_at(8), Some((Some(Price(108)), None)));
+    assert_eq!(book.bbo_at(10), Some((Some(Price(110)), None)));
+}
+
+#[test]
+fn disabled_history_answers_nothing() {
+    let mut book = OrderBook::new();
+    book.add_limit_order(Order::new(1, OrderSide::Buy, 100, 5));
+    assert_eq!(book.bbo_at(1), None);
+}
@@ -0,0 +1,117 @@
+// ============================================================================
+// BINARY PROTOCOL - Packed orders behind a magic handshake byte
+// ============================================================================
+
+mod common;
+
+use common::{wait_until, GatewayClient, TestServers};
+use hft_ringbuffer::gateway::{binary_frame, BINARY_MAGIC, MAX_FRAME_LEN};
+use hft_ringbuffer::matching_engine::{Order, OrderSide, ORDER_WIRE_LEN};
+use hft_ringbuffer::rejections::RejectReason;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::TcpStream;
+use std::time::Duration;
+
+const PIPELINED: u64 = 2_000;
+
+fn connect_binary(addr: &str) -> (TcpStream, BufReader<TcpStream>) {
+    let mut stream = TcpStream::connect(addr).unwrap();
+    stream.set_read_timeout(Some(Duration::from_secs(5))).unwrap();
+    stream.write_all(&[BINARY_MAGIC]).unwrap();
+    let reader = BufReader::new(stream.try_clone().unwrap());
+    (stream, reader)
+}
+
+fn read_ack(reader: &mut BufReader<TcpStream>) -> serde_json::Value {
+    let mut ack = String::new();
+    reader.read_line(&mut ack).unwrap();
+    serde_json::from_str(&ack).unwrap()
+}
+
+#[test]
+fn orders_round_trip_through_bytes() {
+    for order in [Order::new(1, OrderSide::Buy, 100, 5), Order::new(u64::MAX, OrderSide::Sell, u64::MAX - 1, 1)] {
+        let bytes = order.to_bytes();
+        assert_eq!(bytes.len(), ORDER_WIRE_LEN);
+        assert_eq!(Order::from_bytes(&bytes).unwrap(), order);
+    }
+    // Only the packed fields travel
+    let decoded = Order::from_bytes(&Order::new(2, OrderSide::Buy, 5, 5).with_account(9).to_bytes()).unwrap();
+    assert_eq!(decoded.account_id, None);
+}
+
+#[test]
+fn short_long_and_unknown_side_payloads_are_malformed() {
+    let bytes = Order::new(1, OrderSide::Sell, 100, 5).to_bytes();
+    let mut long = bytes.to_vec();
+    long.push(0);
+    let mut bad_side = bytes;
+    bad_side[8] = 7;
+    for payload in [&bytes[..ORDER_WIRE_LEN - 1], &long[..], &bad_side[..], &[][..]] {
+        assert_eq!(Order::from_bytes(payload).unwrap_err().reason, RejectReason::Malformed);
+    }
+}
+
+#[test]
+fn binary_clients_trade_alongside_json_clients() {
+    let servers = TestServers::start();
+    let (mut stream, mut reader) = connect_binary(&servers.gateway_addr);
+    stream.write_all(&binary_frame(&Order::new(1, OrderSide::Sell, 100, 5))).unwrap();
+    assert_eq!(read_ack(&mut reader)["status"], "accepted");
+
+    // A bad frame is refused and the next one still parses
+    let mut bad = binary_frame(&Order::new(2, OrderSide::Buy, 100, 1));
+    bad[4 + 8] = 9;
+    stream.write_all(&bad).unwrap();
+    stream.write_all(&(3u32).to_le_bytes()).unwrap();
+    stream.write_all(&[1, 2, 3]).unwrap();
+    stream.write_all(&binary_frame(&Order::new(3, OrderSide::Buy, 100, 0))).unwrap();
+    stream.write_all(&binary_frame(&Order::new(4, OrderSide::Buy, 100, 2))).unwrap();
+    assert_eq!(read_ack(&mut reader)["reason"], "malformed");
+    assert_eq!(read_ack(&mut reader)["reason"], "malformed");
+    assert_eq!(read_ack(&mut reader)["reason"], "zero_quantity");
+    assert_eq!(read_ack(&mut reader)["status"], "filled");
+
+    let mut json = GatewayClient::connect(&servers.gateway_addr);
+    assert_eq!(json.send_line(r#"{"id": 5, "side": "Buy", "price": 100, "quantity": 3}"#)["status"], "filled");
+    assert!(wait_until(|| servers.order_book.lock().unwrap().best_ask().is_none()));
+    assert_eq!(servers.metrics.ingress().totals().parse_errors, 2);
+    servers.stop();
+}
+
+#[test]
+fn oversized_frames_drop_the_connection() {
+    let servers = TestServers::start();
+    let (mut stream, mut reader) = connect_binary(&servers.gateway_addr);
+    stream.write_all(&((MAX_FRAME_LEN + 1) as u32).to_le_bytes()).unwrap();
+    assert_eq!(read_ack(&mut reader)["reason"], "malformed");
+    let mut rest = Vec::new();
+    assert_eq!(reader.read_to_end(&mut rest).unwrap(), 0);
+
+    // A frame cut short by the client closing is simply dropped
+    let (mut stream, mut reader) = connect_binary(&servers.gateway_addr);
+    stream.write_all(&binary_frame(&Order::new(1, OrderSide::Buy, 100, 1))[..10]).unwrap();
+    stream.shutdown(std::net::Shutdown::Write).unwrap();
+    assert_eq!(reader.read_to_end(&mut rest).unwrap(), 0);
+    assert!(wait_until(|| servers.metrics.ingress().active_connections() == 0));
+    assert_eq!(servers.metrics.ingress().totals().orders_parsed, 0);
+    servers.stop();
+}
+
+#[test]
+fn pipelined_binary_orders_all_arrive() {
+    let servers = TestServers::start();
+    let orders: Vec<Order> = (1..=PIPELINED).map(|id| Order::new(id, OrderSide::Buy, id, 1)).collect();
+
+    // Every frame back to back in one write; each is still read on its own
+    let (mut stream, mut reader) = connect_binary(&servers.gateway_addr);
+    let frames: Vec<u8> = orders.iter().flat_map(binary_frame).collect();
+    stream.write_all(&frames).unwrap();
+    for _ in 0..PIPELINED {
+        assert_eq!(read_ack(&mut reader)["status"], "accepted");
+    }
+
+    assert!(wait_until(|| servers.order_book.lock().unwrap().resting_orders().len() == PIPELINED as usize));
+    assert_eq!(servers.order_book.lock().unwrap().resting_orders(), orders);
+    servers.stop();
+}
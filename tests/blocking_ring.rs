@@ -0,0 +1,127 @@
+// ============================================================================
+// BLOCKING RING - Sleeping ends wake on the other side's progress
+// ============================================================================
+
+use hft_ringbuffer::blocking_ring::blocking_ring;
+use hft_ringbuffer::clock::thread_cpu_time;
+use std::thread;
+use std::time::{Duration, Instant};
+
+#[test]
+fn blocked_popper_wakes_when_an_item_is_pushed() {
+    let (mut producer, mut consumer) = blocking_ring::<u64>(4);
+    let popper = thread::spawn(move || {
+        let start = Instant::now();
+        let item = consumer.pop_blocking(Duration::from_secs(10));
+        (item, start.elapsed())
+    });
+
+    thread::sleep(Duration::from_millis(100));
+    producer.push(7).unwrap();
+    let (item, waited) = popper.join().unwrap();
+
+    assert_eq!(item, Some(7));
+    assert!(waited >= Duration::from_millis(90), "returned before the push: {:?}", waited);
+    assert!(waited < Duration::from_secs(5), "slept through the push: {:?}", waited);
+}
+
+#[test]
+fn blocked_popper_sleeps_instead_of_spinning() {
+    let (producer, mut consumer) = blocking_ring::<u64>(4);
+    let popper = thread::spawn(move || {
+        let before = thread_cpu_time();
+        assert_eq!(consumer.pop_blocking(Duration::from_millis(300)), None);
+        thread_cpu_time().zip(before).map(|(after, before)| after - before)
+    });
+
+    if let Some(cpu) = popper.join().unwrap() {
+        assert!(cpu < Duration::from_millis(100), "burned {:?} waiting 300ms", cpu);
+    }
+    drop(producer);
+}
+
+#[test]
+fn pop_blocking_times_out_on_an_empty_ring() {
+    let (_producer, mut consumer) = blocking_ring::<u64>(4);
+    let start = Instant::now();
+
+    assert_eq!(consumer.pop_blocking(Duration::from_millis(50)), None);
+    assert!(start.elapsed() >= Duration::from_millis(50));
+}
+
+#[test]
+fn blocked_pusher_wakes_when_the_consumer_makes_room() {
+    let (mut producer, mut consumer) = blocking_ring::<u64>(2);
+    producer.push(1).unwrap();
+    producer.push(2).unwrap();
+    assert_eq!(producer.push(3), Err(3));
+
+    let pusher = thread::spawn(move || {
+        let result = producer.push_blocking(3, Duration::from_secs(10));
+        (result, producer)
+    });
+    thread::sleep(Duration::from_millis(50));
+    assert_eq!(consumer.pop(), Some(1));
+
+    let (result, _producer) = pusher.join().unwrap();
+    assert_eq!(result, Ok(()));
+    assert_eq!(consumer.pop(), Some(2));
+    assert_eq!(consumer.pop(), Some(3));
+}
+
+#[test]
+fn push_blocking_hands_the_item_back_on_timeout() {
+    let (mut producer, _consumer) = blocking_ring::<u64>(1);
+    producer.push(1).unwrap();
+
+    assert_eq!(producer.push_blocking(2, Duration::from_millis(20)), Err(2));
+}
+
+#[test]
+fn dropping_the_producer_wakes_a_sleeping_popper() {
+    let (mut producer, mut consumer) = blocking_ring::<u64>(4);
+    producer.push(1).unwrap();
+    let popper = thread::spawn(move || {
+        let first = consumer.pop_blocking(Duration::from_secs(10));
+        let start = Instant::now();
+        let second = consumer.pop_blocking(Duration::from_secs(10));
+        (first, second, start.elapsed())
+    });
+
+    thread::sleep(Duration::from_millis(50));
+    drop(producer);
+    let (first, second, waited) = popper.join().unwrap();
+
+    assert_eq!(first, Some(1));
+    assert_eq!(second, None);
+    assert!(waited < Duration::from_secs(5), "waited out the timeout instead: {:?}", waited);
+}
+
+#[test]
+fn items_pushed_before_the_producer_drops_are_still_delivered() {
+    let (mut producer, mut consumer) = blocking_ring::<u64>(8);
+    for i in 0..5 {
+        producer.push(i).unwrap();
+    }
+    drop(producer);
+
+    let drained: Vec<u64> = std::iter::from_fn(|| consumer.pop_blocking(Duration::from_secs(1))).collect();
+    assert_eq!(drained, vec![0, 1, 2, 3, 4]);
+    assert!(consumer.is_abandoned());
+}
+
+#[test]
+fn a_stream_crosses_a_small_ring_in_order() {
+    const ITEMS: u64 = 50_000;
+    let (mut producer, mut consumer) = blocking_ring::<u64>(8);
+    let writer = thread::spawn(move || {
+        for i in 0..ITEMS {
+            producer.push_blocking(i, Duration::from_secs(10)).unwrap();
+        }
+    });
+
+    for expected in 0..ITEMS {
+        assert_eq!(consumer.pop_blocking(Duration::from_secs(10)), Some(expected));
+    }
+    writer.join().unwrap();
+}
@@ -0,0 +1,68 @@
+// ============================================================================
+// BOOK CHECKSUM - CRC32 over the canonical top-of-book levels
+// ============================================================================
+
+mod common;
+
+use common::{http_request, TestServers};
+use hft_ringbuffer::book_checksum::{canonical_levels, crc32, levels_checksum};
+use hft_ringbuffer::matching_engine::{Order, OrderBook, OrderSide};
+
+fn known_book(book: &mut OrderBook) {
+    book.add_limit_order(Order::new(1, OrderSide::Sell, 101, 5));
+    book.add_limit_order(Order::new(2, OrderSide::Sell, 102, 2));
+    book.add_limit_order(Order::new(3, OrderSide::Buy, 99, 3));
+}
+
+#[test]
+fn crc32_matches_the_standard_check_value() {
+    assert_eq!(crc32(b"123456789"), 0xCBF4_3926);
+    assert_eq!(crc32(b""), 0);
+}
+
+#[test]
+fn canonical_string_lists_asks_then_bids_best_first() {
+    let mut book = OrderBook::new();
+    let empty = book.depth(10);
+    assert_eq!(canonical_levels(&empty.asks, &empty.bids), "");
+    assert_eq!(book.depth_checksum(10), 0);
+
+    known_book(&mut book);
+    book.add_limit_order(Order::new(4, OrderSide::Buy, 99, 4));
+    let depth = book.depth(10);
+    assert_eq!(canonical_levels(&depth.asks, &depth.bids), "101:5|102:2|99:7");
+    assert_eq!(depth.checksum, crc32(b"101:5|102:2|99:7"));
+    assert_eq!(depth.checksum, levels_checksum(&depth.asks, &depth.bids));
+}
+
+#[test]
+fn checksum_follows_the_levels_not_the_orders() {
+    let mut one = OrderBook::new();
+    known_book(&mut one);
+
+    // Same levels built from different orders
+    let mut other = OrderBook::new();
+    other.add_limit_order(Order::new(7, OrderSide::Buy, 99, 1));
+    other.add_limit_order(Order::new(8, OrderSide::Buy, 99, 2));
+    other.add_limit_order(Order::new(9, OrderSide::Sell, 102, 2));
+    other.add_limit_order(Order::new(10, OrderSide::Sell, 101, 5));
+    assert_eq!(one.depth_checksum(10), other.depth_checksum(10));
+
+    let before = one.depth_checksum(10);
+    one.add_limit_order(Order::new(4, OrderSide::Buy, 98, 1));
+    assert_ne!(one.depth_checksum(10), before);
+    // Only the levels inside the window count
+    assert_eq!(one.depth_checksum(1), other.depth_checksum(1));
+}
+
+#[test]
+fn depth_response_carries_the_checksum() {
+    let servers = TestServers::start();
+    known_book(&mut servers.order_book.lock().unwrap());
+
+    let (status, body) = http_request(&servers.http_addr, "GET", "/api/depth", "");
+    assert_eq!(status, 200);
+    let depth: serde_json::Value = serde_json::from_str(&body).unwrap();
+    assert_eq!(depth["checksum"].as_u64(), Some(crc32(b"101:5|102:2|99:3") as u64));
+    servers.stop();
+}
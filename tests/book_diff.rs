@@ -0,0 +1,61 @@
+// ============================================================================
+// BOOK DIFF - Changes between snapshots reproduce the later book
+// ============================================================================
+
+use hft_ringbuffer::book_diff::LevelChange;
+use hft_ringbuffer::matching_engine::{Order, OrderBook, OrderSide};
+use hft_ringbuffer::price_units::Price;
+
+#[test]
+fn diff_reproduces_the_later_state() {
+    let mut book = OrderBook::new();
+    for (id, side, price, quantity) in [
+        (1, OrderSide::Buy, 99, 3),
+        (2, OrderSide::Buy, 98, 2),
+        (3, OrderSide::Buy, 97, 1),
+        (4, OrderSide::Sell, 101, 5),
+        (5, OrderSide::Sell, 103, 1),
+    ] {
+        book.add_limit_order(Order::new(id, side, price, quantity));
+    }
+    let stale = book.snapshot();
+    assert!(book.diff(&stale).is_empty());
+
+    book.add_limit_order(Order::new(6, OrderSide::Sell, 99, 3)); // takes out the 99 bid
+    book.add_limit_order(Order::new(7, OrderSide::Buy, 98, 4)); // 98 grows to 6
+    book.add_limit_order(Order::new(8, OrderSide::Sell, 102, 2)); // new ask level
+    book.add_limit_order(Order::new(9, OrderSide::Buy, 96, 1)); // new deeper bid
+    book.amend_order(5, None, Some(0)).unwrap(); // 103 goes away
+
+    let changes = book.diff(&stale);
+    assert_eq!(
+        changes,
+        vec![
+            LevelChange::Removed { side: OrderSide::Buy, price: Price(99) },
+            LevelChange::Resized { side: OrderSide::Buy, price: Price(98), quantity: 6 },
+            LevelChange::Added { side: OrderSide::Buy, price: Price(96), quantity: 1 },
+            LevelChange::Added { side: OrderSide::Sell, price: Price(102), quantity: 2 },
+            LevelChange::Removed { side: OrderSide::Sell, price: Price(103) },
+        ]
+    );
+
+    let mut resynced = stale.clone();
+    resynced.apply(&changes);
+    let current = book.snapshot();
+    assert_eq!(resynced.bids, current.bids);
+    assert_eq!(resynced.asks, current.asks);
+    assert!(current.sequence > stale.sequence);
+}
+
+#[test]
+fn diff_against_an_empty_snapshot_lists_every_level() {
+    let mut book = OrderBook::new();
+    book.add_limit_order(Order::new(1, OrderSide::Buy, 99, 3));
+    book.add_limit_order(Order::new(2, OrderSide::Sell, 101, 5));
+
+    let empty = OrderBook::new().snapshot();
+    let changes = book.diff(&empty);
+    assert_eq!(changes.len(), 2);
+    assert!(changes.iter().all(|change| matches!(change, LevelChange::Added { .. })));
+    assert!(book.snapshot().diff(&empty).iter().all(|change| matches!(change, LevelChange::Removed { .. })));
+}
@@ -0,0 +1,79 @@
+// ============================================================================
+// BOOK SIGNALS - Imbalance and microprice from the visible book
+// ============================================================================
+
+mod common;
+
+use common::{http_request, TestServers};
+use hft_ringbuffer::matching_engine::{Order, OrderBook, OrderSide};
+
+/// Heavy bid at the touch, heavy ask one level out
+fn known_book(book: &mut OrderBook) {
+    book.add_limit_order(Order::new(1, OrderSide::Buy, 100, 30));
+    book.add_limit_order(Order::new(2, OrderSide::Buy, 99, 10));
+    book.add_limit_order(Order::new(3, OrderSide::Sell, 102, 10));
+    book.add_limit_order(Order::new(4, OrderSide::Sell, 103, 50));
+}
+
+#[test]
+fn imbalance_sign_follows_the_heavier_side_within_the_window() {
+    let mut book = OrderBook::new();
+    known_book(&mut book);
+
+    // Touch only: 30 bid vs 10 ask
+    assert_eq!(book.imbalance(1), 0.5);
+    // Two levels: 40 bid vs 60 ask tips it the other way
+    assert_eq!(book.imbalance(2), -0.2);
+    assert_eq!(book.imbalance(10), book.imbalance(2));
+    assert_eq!(book.imbalance(0), 0.0);
+}
+
+#[test]
+fn microprice_leans_toward_the_thin_side() {
+    let mut book = OrderBook::new();
+    known_book(&mut book);
+
+    // (100 * 10 + 102 * 30) / 40: the big bid pulls it above the 101 mid
+    assert_eq!(book.microprice(), Some(101.5));
+    assert_eq!(book.pricing_inputs().mid, Some(101.0));
+
+    // Equal sizes put it on the mid
+    book.add_limit_order(Order::new(5, OrderSide::Sell, 102, 20));
+    assert_eq!(book.microprice(), Some(101.0));
+}
+
+#[test]
+fn empty_and_one_sided_books_have_no_microprice() {
+    let mut book = OrderBook::new();
+    assert_eq!(book.imbalance(5), 0.0);
+    assert_eq!(book.microprice(), None);
+
+    book.add_limit_order(Order::new(1, OrderSide::Buy, 100, 3));
+    assert_eq!(book.imbalance(5), 1.0);
+    assert_eq!(book.microprice(), None);
+
+    book.cancel_order(1);
+    book.add_limit_order(Order::new(2, OrderSide::Sell, 101, 3));
+    assert_eq!(book.imbalance(5), -1.0);
+    assert_eq!(book.microprice(), None);
+}
+
+#[test]
+fn signals_are_served_over_http() {
+    let servers = TestServers::start();
+    known_book(&mut servers.order_book.lock().unwrap());
+
+    let (status, body) = http_request(&servers.http_addr, "GET", "/api/signals?levels=1", "");
+    assert_eq!(status, 200);
+    let signals: serde_json::Value = serde_json::from_str(&body).unwrap();
+    assert_eq!(signals, serde_json::json!({"levels": 1, "imbalance": 0.5, "microprice": 101.5}));
+
+    let (status, body) = http_request(&servers.http_addr, "GET", "/api/signals", "");
+    assert_eq!(status, 200);
+    let signals: serde_json::Value = serde_json::from_str(&body).unwrap();
+    assert_eq!((signals["levels"].as_u64(), signals["imbalance"].as_f64()), (Some(5), Some(-0.2)));
+
+    let (status, _) = http_request(&servers.http_addr, "GET", "/api/signals?levels=many", "");
+    assert_eq!(status, 400);
+    servers.stop();
+}
@@ -0,0 +1,97 @@
+// ============================================================================
+// CANCEL ORDER - Remove a resting order by id
+// ============================================================================
+
+mod common;
+
+use common::{http_request, TestServers};
+use hft_ringbuffer::matching_engine::{Order, OrderBook, OrderSide};
+use hft_ringbuffer::price_units::Price;
+
+#[test]
+fn cancels_a_resting_order_and_keeps_the_rest_of_its_level() {
+    let mut book = OrderBook::new();
+    book.add_limit_order(Order::new(1, OrderSide::Buy, 100, 5));
+    book.add_limit_order(Order::new(2, OrderSide::Buy, 100, 3));
+    book.add_limit_order(Order::new(3, OrderSide::Sell, 105, 2));
+
+    let cancelled = book.cancel_order(1).unwrap();
+    assert_eq!((cancelled.id, cancelled.side, cancelled.price.units(), cancelled.quantity), (1, OrderSide::Buy, 100, 5));
+    assert_eq!(book.best_bid(), Some(Price(100)));
+    book.validate().unwrap();
+
+    // Order 2 is now first in the queue
+    let executions = book.add_limit_order(Order::new(4, OrderSide::Sell, 100, 3));
+    assert_eq!(executions.iter().map(|e| e.maker_order_id).collect::<Vec<_>>(), vec![2]);
+
+    // A filled order is gone too
+    assert_eq!(book.cancel_order(2), None);
+}
+
+#[test]
+fn unknown_id_is_none_and_changes_nothing() {
+    let mut book = OrderBook::new();
+    book.add_limit_order(Order::new(1, OrderSide::Sell, 105, 2));
+    let sequence = book.sequence();
+
+    assert_eq!(book.cancel_order(42), None);
+    assert_eq!(book.sequence(), sequence);
+    assert_eq!(book.best_ask(), Some(Price(105)));
+}
+
+#[test]
+fn cancelling_the_last_order_at_a_level_removes_the_level() {
+    let mut book = OrderBook::new();
+    book.add_limit_order(Order::new(1, OrderSide::Sell, 105, 2));
+    book.add_limit_order(Order::new(2, OrderSide::Sell, 107, 2));
+
+    book.cancel_order(1).unwrap();
+    assert_eq!(book.best_ask(), Some(Price(107)));
+    book.validate().unwrap();
+
+    book.cancel_order(2).unwrap();
+    assert_eq!(book.best_ask(), None);
+    book.validate().unwrap();
+}
+
+#[test]
+fn partially_filled_and_amended_orders_stay_cancellable() {
+    let mut book = OrderBook::new();
+    book.add_limit_order(Order::new(1, OrderSide::Buy, 100, 5));
+    book.add_limit_order(Order::new(2, OrderSide::Sell, 100, 2));
+    book.amend_order(1, Some(Price(98)), None).unwrap();
+
+    let cancelled = book.cancel_order(1).unwrap();
+    assert_eq!((cancelled.price.units(), cancelled.quantity), (98, 3));
+    assert_eq!(book.best_bid(), None);
+    book.validate().unwrap();
+}
+
+#[test]
+fn delete_over_http_returns_the_cancelled_order() {
+    let servers = TestServers::start();
+    let (status, _) = http_request(&servers.http_addr, "POST", "/api/order", r#"{"id":7,"side":"Buy","price":100,"quantity":4}"#);
+    assert_eq!(status, 200);
+
+    let (status, body) = http_request(&servers.http_addr, "DELETE", "/api/order/7", "");
+    assert_eq!(status, 200, "{}", body);
+    let body: serde_json::Value = serde_json::from_str(&body).unwrap();
+    assert_eq!(body["status"], "cancelled");
+    assert_eq!((body["order"]["id"].as_u64(), body["order"]["quantity"].as_u64()), (Some(7), Some(4)));
+    assert_eq!(servers.order_book.lock().unwrap().best_bid(), None);
+
+    // Already gone
+    let (status, body) = http_request(&servers.http_addr, "DELETE", "/api/order/7", "");
+    assert_eq!(status, 404);
+    assert!(body.contains("order 7 is not on the book"), "{}", body);
+    servers.stop();
+}
+
+#[test]
+fn delete_with_a_malformed_id_is_a_bad_request() {
+    let servers = TestServers::start();
+    let (status, body) = http_request(&servers.http_addr, "DELETE", "/api/order/abc", "");
+    assert_eq!(status, 400);
+    assert!(body.contains("order id must be a number"), "{}", body);
+    servers.stop();
+}
@@ -0,0 +1,222 @@
+// ============================================================================
+// SHARED TEST HARNESS - Real servers on ephemeral ports
+// ============================================================================
+#![allow(dead_code)]
+
+use hft_ringbuffer::clock::ManualClock;
+use hft_ringbuffer::decision_bridge::{DecisionBridge, DecisionBridgeConfig};
+use hft_ringbuffer::engine::{spawn_engine, spawn_shard_engine, EngineHooks};
+use hft_ringbuffer::events::EventBus;
+use hft_ringbuffer::funnel::{spawn_funnel, FunnelConfig};
+use hft_ringbuffer::gateway::{bind_gateway, spawn_gateway, GatewayRoutes};
+use hft_ringbuffer::http_server::{bind_http_server, start_http_server};
+use hft_ringbuffer::matching_engine::{OrderBook, Packet, DEFAULT_BOOK_SYMBOL};
+use hft_ringbuffer::metrics::Metrics;
+use hft_ringbuffer::order_results::OrderResults;
+use hft_ringbuffer::replica::{replica_channel, Replica, StalenessGuard};
+use hft_ringbuffer::signal_store::SignalStore;
+use hft_ringbuffer::trade_history::{TradeHistory, TradeSink, DEFAULT_TRADE_HISTORY_CAPACITY};
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::TcpStream;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+/// Engine, gateway and HTTP server wired together exactly like `main.rs`.
+pub struct TestServers {
+    pub gateway_addr: String,
+    pub http_addr: String,
+    pub order_book: Arc<Mutex<OrderBook>>,
+    /// What the HTTP server serves behind the decision endpoints
+    pub signals: Arc<SignalStore>,
+    pub metrics: Arc<Metrics>,
+    /// Only set by `start_with_replica`; nothing applies its updates until
+    /// the test calls `apply_pending`
+    pub replica: Option<Arc<Replica>>,
+    shutdown: Arc<AtomicBool>,
+    handles: Vec<JoinHandle<()>>,
+}
+
+impl TestServers {
+    pub fn start() -> Self {
+        Self::start_inner(EngineHooks::default(), None, None)
+    }
+
+    /// Also trades posted AI decisions through the gateway's funnel.
+    pub fn start_with_decision_bridge(config: DecisionBridgeConfig) -> Self {
+        Self::start_inner(EngineHooks::default(), None, Some(config))
+    }
+
+    /// Also wires up a read replica whose lag is timed by `clock`.
+    pub fn start_with_replica(clock: Arc<ManualClock>, guard: Option<StalenessGuard>) -> Self {
+        let (feed, replica) = replica_channel(OrderBook::new(), clock, guard);
+        let hooks = EngineHooks { replica: Some(feed), ..Default::default() };
+        Self::start_inner(hooks, Some(replica), None)
+    }
+
+    fn start_inner(mut hooks: EngineHooks, replica: Option<Arc<Replica>>, bridge: Option<DecisionBridgeConfig>) -> Self {
+        let (listener, gateway_addr) = bind_gateway("127.0.0.1:0").unwrap();
+        let (server, http_addr) = bind_http_server("127.0.0.1:0").unwrap();
+        let (producer, consumer) = rtrb::RingBuffer::<Packet>::new(1024);
+        let order_book = Arc::new(Mutex::new(OrderBook::new()));
+        let metrics = Arc::new(Metrics::new());
+        let shutdown = Arc::new(AtomicBool::new(false));
+        let results = Arc::new(OrderResults::new());
+        hooks.results = Some(results.clone());
+        // Trades reach the event bus through the offloaded writer, as in main.rs
+        let event_bus = Arc::new(EventBus::new());
+        let history = Arc::new(Mutex::new(TradeHistory::new(DEFAULT_TRADE_HISTORY_CAPACITY)));
+        let (sink, writer) = TradeSink::offloaded(history, Some(event_bus.clone())).unwrap();
+        hooks.trades = Some(sink);
+        metrics.set_event_bus(event_bus);
+
+        let mut handles = Vec::new();
+        let mut signals = SignalStore::new();
+        {
+            let book = order_book.clone();
+            let metrics = metrics.clone();
+            let shutdown = shutdown.clone();
+            handles.push(spawn_engine(consumer, book, shutdown, metrics, hooks).unwrap());
+            // Exits once the engine drops its sink
+            handles.push(writer);
+        }
+        {
+            let shutdown = shutdown.clone();
+            let (funnel, forwarder) =
+                spawn_funnel(producer, FunnelConfig::default(), metrics.funnel().clone(), shutdown.clone()).unwrap();
+            handles.push(forwarder);
+            if let Some(config) = bridge {
+                signals = signals.with_bridge(DecisionBridge::new(config, funnel.clone()));
+            }
+            let ingress = metrics.ingress().clone();
+            let routes = GatewayRoutes::new(DEFAULT_BOOK_SYMBOL, funnel).with_results(results);
+            metrics.set_order_entry(routes.clone());
+            handles.push(spawn_gateway(listener, routes, ingress, shutdown).unwrap());
+        }
+        let signals = Arc::new(signals);
+        {
+            let book = order_book.clone();
+            let signals = signals.clone();
+            let metrics = metrics.clone();
+            let replica = replica.clone();
+            let shutdown = shutdown.clone();
+            handles.push(
+                thread::Builder::new()
+                    .name("http-accept".to_string())
+                    .spawn(move || start_http_server(server, book, signals, metrics, replica, shutdown).unwrap())
+                    .unwrap(),
+            );
+        }
+
+        TestServers {
+            gateway_addr: gateway_addr.to_string(),
+            http_addr: http_addr.to_string(),
+            order_book,
+            signals,
+            metrics,
+            replica,
+            shutdown,
+            handles,
+        }
+    }
+
+    /// Starts an engine for `symbol` and sends HTTP orders for it there; the
+    /// gateway keeps the routes it started with.
+    pub fn add_symbol_engine(&mut self, symbol: &str) -> Arc<Mutex<OrderBook>> {
+        let routes = self.metrics.order_entry().unwrap();
+        let (producer, consumer) = rtrb::RingBuffer::<Packet>::new(1024);
+        let book = Arc::new(Mutex::new(OrderBook::new()));
+        let hooks = EngineHooks { results: routes.results().cloned(), ..Default::default() };
+        self.handles.push(spawn_shard_engine(symbol, consumer, book.clone(), self.shutdown.clone(), self.metrics.clone(), hooks).unwrap());
+        let (funnel, forwarder) =
+            spawn_funnel(producer, FunnelConfig::default(), self.metrics.funnel().clone(), self.shutdown.clone()).unwrap();
+        self.handles.push(forwarder);
+        self.metrics.set_order_entry(routes.with_symbol(symbol, funnel));
+        self.metrics.register_symbol_book(symbol, book.clone());
+        book
+    }
+
+    /// Raises the shutdown flag and joins every server thread.
+    pub fn stop(mut self) {
+        self.shutdown.store(true, Ordering::Relaxed);
+        for handle in self.handles.drain(..) {
+            handle.join().unwrap();
+        }
+    }
+}
+
+/// A gateway session that writes one JSON line and reads one ack line.
+pub struct GatewayClient {
+    stream: TcpStream,
+    reader: BufReader<TcpStream>,
+}
+
+impl GatewayClient {
+    pub fn connect(addr: &str) -> Self {
+        let stream = TcpStream::connect(addr).unwrap();
+        stream.set_read_timeout(Some(Duration::from_secs(5))).unwrap();
+        let reader = BufReader::new(stream.try_clone().unwrap());
+        GatewayClient { stream, reader }
+    }
+
+    pub fn send_line(&mut self, line: &str) -> serde_json::Value {
+        self.stream.write_all(line.as_bytes()).unwrap();
+        self.stream.write_all(b"\n").unwrap();
+        let mut ack = String::new();
+        self.reader.read_line(&mut ack).unwrap();
+        serde_json::from_str(&ack).unwrap()
+    }
+}
+
+/// Minimal HTTP/1.1 client: returns the status code and the raw body.
+pub fn http_request(addr: &str, method: &str, path: &str, body: &str) -> (u16, String) {
+    let (status, _, body) = http_exchange(addr, method, path, body);
+    (status, body)
+}
+
+/// Like `http_request`, with the response header lines as well.
+pub fn http_exchange(addr: &str, method: &str, path: &str, body: &str) -> (u16, Vec<String>, String) {
+    http_exchange_with_headers(addr, method, path, &[], body)
+}
+
+/// Like `http_exchange`, sending `headers` with the request.
+pub fn http_exchange_with_headers(
+    addr: &str,
+    method: &str,
+    path: &str,
+    headers: &[(&str, &str)],
+    body: &str,
+) -> (u16, Vec<String>, String) {
+    let mut stream = TcpStream::connect(addr).unwrap();
+    stream.set_read_timeout(Some(Duration::from_secs(5))).unwrap();
+    let extra: String = headers.iter().map(|(name, value)| format!("{}: {}\r\n", name, value)).collect();
+    let request = format!(
+        "{} {} HTTP/1.1\r\nHost: {}\r\n{}Content-Length: {}\r\nConnection: close\r\n\r\n{}",
+        method, path, addr, extra, body.len(), body
+    );
+    stream.write_all(request.as_bytes()).unwrap();
+
+    let mut response = String::new();
+    stream.read_to_string(&mut response).unwrap();
+    let status = response
+        .split_whitespace()
+        .nth(1)
+        .and_then(|code| code.parse().ok())
+        .unwrap();
+    let (head, body) = response.split_once("\r\n\r\n").unwrap_or((&response, ""));
+    let headers = head.lines().skip(1).map(str::to_string).collect();
+    (status, headers, body.to_string())
+}
+
+/// Polls `condition` until it holds or the deadline passes.
+pub fn wait_until(mut condition: impl FnMut() -> bool) -> bool {
+    let deadline = Instant::now() + Duration::from_secs(5);
+    while Instant::now() < deadline {
+        if condition() {
+            return true;
+        }
+        thread::sleep(Duration::from_millis(10));
+    }
+    false
+}
@@ -0,0 +1,198 @@
+// ============================================================================
+// CONFIG - File settings, environment overrides and what they configure
+// ============================================================================
+
+use hft_ringbuffer::config::{CliArgs, Config, DEFAULT_RING_CAPACITY};
+use hft_ringbuffer::fees::FeeSchedule;
+use hft_ringbuffer::funnel::OverflowPolicy;
+use hft_ringbuffer::matching_engine::{Order, OrderBook, OrderSide};
+use hft_ringbuffer::rejections::RejectReason;
+use std::collections::HashMap;
+
+const SAMPLE: &str = r#"{
+    "ring_capacity": 1024,
+    "gateway_addr": "0.0.0.0:9083",
+    "http_addr": "0.0.0.0:9082",
+    "maker_fee_bps": -1,
+    "taker_fee_bps": 3,
+    "tick_size": 5
+}"#;
+
+#[test]
+fn sample_file_is_parsed_and_applied_to_the_book() {
+    let config = Config::from_json(SAMPLE).unwrap();
+    assert_eq!(config.ring_capacity, 1024);
+    assert_eq!((config.gateway_addr.as_str(), config.http_addr.as_str()), ("0.0.0.0:9083", "0.0.0.0:9082"));
+    assert_eq!(config.fee_schedule(), FeeSchedule::new(-1, 3));
+
+    let mut book = OrderBook::new();
+    config.configure_book(&mut book);
+    assert_eq!(book.fee_history().current().schedule, FeeSchedule::new(-1, 3));
+    let error = book.submit_order(Order::new(1, OrderSide::Buy, 102, 1)).unwrap_err();
+    assert_eq!(error.reason, RejectReason::TickSize);
+
+    book.submit_order(Order::new(2, OrderSide::Sell, 1000, 100)).unwrap();
+    let executions = book.submit_order(Order::new(3, OrderSide::Buy, 1000, 100)).unwrap();
+    assert_eq!((executions[0].maker_fee, executions[0].taker_fee), (-10, 30));
+}
+
+#[test]
+fn missing_settings_keep_their_defaults() {
+    let config = Config::from_json(r#"{"taker_fee_bps": 2}"#).unwrap();
+    assert_eq!(config, Config { taker_fee_bps: 2, ..Config::default() });
+    assert_eq!(config.ring_capacity, DEFAULT_RING_CAPACITY);
+
+    // A default config leaves the book's fee history alone
+    let mut book = OrderBook::new();
+    Config::default().configure_book(&mut book);
+    assert_eq!(book.fee_history().versions().len(), 1);
+}
+
+#[test]
+fn environment_overrides_the_file() {
+    let env: HashMap<&str, &str> = [("RING_CAPACITY", "64"), ("HTTP_ADDR", "127.0.0.1:0"), ("TICK_SIZE", "10")].into();
+    let mut config = Config::from_json(SAMPLE).unwrap();
+    config.apply_env(|name| env.get(name).map(|value| value.to_string())).unwrap();
+    assert_eq!(config.ring_capacity, 64);
+    assert_eq!(config.http_addr, "127.0.0.1:0");
+    assert_eq!(config.gateway_addr, "0.0.0.0:9083");
+    assert_eq!(config.tick_size, Some(10));
+}
+
+#[test]
+fn bad_settings_are_refused() {
+    assert!(Config::from_json(r#"{"ring_capacity": 0}"#).unwrap_err().contains("ring_capacity"));
+    assert!(Config::from_json(r#"{"tick_size": 0}"#).unwrap_err().contains("tick_size"));
+    assert!(Config::from_json(r#"{"ring_size": 16}"#).unwrap_err().contains("unknown field"));
+
+    let mut config = Config::default();
+    let error = config.apply_env(|name| (name == "MAKER_FEE_BPS").then(|| "cheap".to_string())).unwrap_err();
+    assert!(error.contains("MAKER_FEE_BPS"), "{}", error);
+}
+
+#[test]
+fn the_file_sets_engine_and_market_settings_too() {
+    let config = Config::from_json(
+        r#"{
+            "engine_batch": 8,
+            "match_policy": "pro-rata",
+            "duplicate_window": 0,
+            "funnel_overflow": "block",
+            "symbol_ring_capacities": {"BTC": 8192},
+            "ai_execution": true,
+            "ai_order_size": 4
+        }"#,
+    )
+    .unwrap();
+    assert_eq!(config.engine_batch, 8);
+    assert_eq!(config.funnel_config().overflow, OverflowPolicy::Block);
+    assert_eq!(config.symbol_rings().capacity_for("BTC"), 8192);
+    assert_eq!(config.symbol_rings().capacity_for("ETH"), DEFAULT_RING_CAPACITY);
+    assert_eq!(config.decision_bridge().unwrap().quantity, 4);
+
+    // With the duplicate window off, a resent id is taken again
+    let mut book = OrderBook::new();
+    config.configure_book(&mut book);
+    book.submit_order(Order::new(1, OrderSide::Buy, 100, 1)).unwrap();
+    book.submit_order(Order::new(1, OrderSide::Buy, 100, 1)).unwrap();
+
+    let mut book = OrderBook::new();
+    Config::default().configure_book(&mut book);
+    book.submit_order(Order::new(1, OrderSide::Buy, 100, 1)).unwrap();
+    let error = book.submit_order(Order::new(1, OrderSide::Buy, 100, 1)).unwrap_err();
+    assert_eq!(error.reason, RejectReason::Duplicate);
+}
+
+#[test]
+fn every_setting_has_an_environment_variable() {
+    let env: HashMap<&str, &str> = [
+        ("SYMBOLS", "BTC, ETH"),
+        ("SYMBOL_RING_CAPACITIES", "BTC:64"),
+        ("WARM_START_CHECKSUM", "0xff"),
+        ("DRAIN_ON_SHUTDOWN", "0"),
+        ("LEVEL_METADATA", "1"),
+        ("ENGINE_WAIT", "yield"),
+    ]
+    .into();
+    let mut config = Config::default();
+    config.apply_env(|name| env.get(name).map(|value| value.to_string())).unwrap();
+    assert_eq!(config.symbols, ["BTC", "ETH"]);
+    assert_eq!(config.symbol_rings().capacity_for("BTC"), 64);
+    assert_eq!(config.warm_start_checksum, Some(255));
+    assert!(!config.funnel_config().drain_on_shutdown);
+    assert!(config.level_metadata);
+    assert_eq!(config.engine_wait, "yield");
+}
+
+#[test]
+fn bad_or_conflicting_engine_settings_are_refused() {
+    let refused = |json: &str, setting: &str| {
+        let error = Config::from_json(json).unwrap_err();
+        assert!(error.contains(setting), "{}: {}", json, error);
+    };
+    refused(r#"{"match_policy": "lifo"}"#, "match_policy");
+    refused(r#"{"funnel_overflow": "drop"}"#, "funnel_overflow");
+    refused(r#"{"engine_wait": "nap"}"#, "engine_wait");
+    refused(r#"{"settlement_method": "vwap:soon"}"#, "settlement_method");
+    refused(r#"{"tick_schedule": "0:0"}"#, "tick_schedule");
+    refused(r#"{"price_band_pct": -5}"#, "band");
+    refused(r#"{"rejection_sample": 0}"#, "rejection_sample");
+    refused(r#"{"symbol_ring_capacities": {"BTC": 100}}"#, "symbol_ring_capacities");
+    refused(r#"{"wal_path": "orders.wal", "warm_start_snapshot": "book.json"}"#, "warm_start_snapshot");
+    refused(r#"{"engine_shards": 2, "read_replica": true}"#, "read_replica");
+
+    let mut config = Config::default();
+    let error = config.apply_env(|name| (name == "MATCH_POLICY").then(|| "lifo".to_string())).unwrap_err();
+    assert!(error.contains("match_policy"), "{}", error);
+}
+
+fn args(line: &str) -> Result<CliArgs, String> {
+    CliArgs::parse(line.split_whitespace().map(str::to_string))
+}
+
+#[test]
+fn flags_map_onto_the_config() {
+    let parsed = args("--buffer-size 256 --orders 5000 --http-port 9182 --gateway-port 9183 --self-bench --csv out.csv").unwrap();
+    assert_eq!(
+        parsed,
+        CliArgs {
+            buffer_size: Some(256),
+            orders: Some(5000),
+            http_port: Some(9182),
+            gateway_port: Some(9183),
+            self_bench: true,
+            csv: Some("out.csv".to_string()),
+        }
+    );
+
+    // Flags win over the file and the environment; the hosts are kept
+    let mut config = Config::from_json(SAMPLE).unwrap();
+    config.apply_env(|name| (name == "RING_CAPACITY").then(|| "64".to_string())).unwrap();
+    config.apply_args(&parsed).unwrap();
+    assert_eq!(config.ring_capacity, 256);
+    assert_eq!(config.self_bench_orders, 5000);
+    assert_eq!((config.http_addr.as_str(), config.gateway_addr.as_str()), ("0.0.0.0:9182", "0.0.0.0:9183"));
+}
+
+#[test]
+fn no_flags_keep_todays_defaults() {
+    let parsed = args("").unwrap();
+    assert_eq!(parsed, CliArgs::default());
+
+    let mut config = Config::default();
+    config.apply_args(&parsed).unwrap();
+    assert_eq!(config, Config::default());
+    assert_eq!(config.ring_capacity, DEFAULT_RING_CAPACITY);
+}
+
+#[test]
+fn bad_flags_are_refused() {
+    assert!(args("--buffer-size lots").unwrap_err().contains("--buffer-size"));
+    assert!(args("--buffer-size -4").unwrap_err().contains("--buffer-size"));
+    assert!(args("--buffer-size 0").unwrap_err().contains("at least 1"));
+    assert!(args("--orders 0").unwrap_err().contains("at least 1"));
+    assert!(args("--http-port 70000").unwrap_err().contains("--http-port"));
+    assert!(args("--gateway-port").unwrap_err().contains("needs a value"));
+    assert!(args("--csv").unwrap_err().contains("needs a value"));
+    assert!(args("--verbose").unwrap_err().contains("unknown argument"));
+}
@@ -0,0 +1,66 @@
+// ============================================================================
+// CONNECTION LIMIT - Gateway clients past the cap are refused, slots free up
+// ============================================================================
+
+mod common;
+
+use common::{http_request, wait_until, GatewayClient, TestServers};
+use hft_ringbuffer::ingress::IngressStats;
+use std::io::{BufRead, BufReader};
+use std::net::TcpStream;
+use std::sync::Arc;
+use std::time::Duration;
+
+const ORDER: &str = r#"{"id":1,"side":"Buy","price":100,"quantity":5}"#;
+
+#[test]
+fn try_open_stops_at_the_limit_until_a_guard_drops() {
+    let stats = Arc::new(IngressStats::new());
+    stats.set_connection_limit(2);
+    let a = stats.try_open("10.0.0.1:1000").unwrap();
+    let _b = stats.try_open("10.0.0.2:2000").unwrap();
+    assert!(stats.try_open("10.0.0.3:3000").is_none());
+    assert_eq!((stats.active_connections(), stats.refused_connections()), (2, 1));
+
+    drop(a);
+    assert!(stats.try_open("10.0.0.3:3000").is_some());
+    // No limit configured: never refuses
+    let open = Arc::new(IngressStats::new());
+    let guards: Vec<_> = (0..100).map(|i| open.try_open(&format!("10.0.0.{}:1", i)).unwrap()).collect();
+    assert_eq!(open.active_connections(), guards.len());
+}
+
+#[test]
+fn gateway_refuses_the_connection_past_the_limit() {
+    let servers = TestServers::start();
+    let ingress = servers.metrics.ingress().clone();
+    ingress.set_connection_limit(2);
+
+    let mut first = GatewayClient::connect(&servers.gateway_addr);
+    let mut second = GatewayClient::connect(&servers.gateway_addr);
+    assert_eq!(first.send_line(ORDER)["status"], "accepted");
+    assert_eq!(second.send_line(ORDER)["status"], "accepted");
+
+    // The third is told why, then closed
+    let third = TcpStream::connect(&servers.gateway_addr).unwrap();
+    third.set_read_timeout(Some(Duration::from_secs(5))).unwrap();
+    let mut reader = BufReader::new(third);
+    let mut line = String::new();
+    reader.read_line(&mut line).unwrap();
+    let refusal: serde_json::Value = serde_json::from_str(&line).unwrap();
+    assert_eq!(refusal["reason"], "too_many_connections");
+    line.clear();
+    assert_eq!(reader.read_line(&mut line).unwrap(), 0);
+    assert_eq!(ingress.refused_connections(), 1);
+
+    // A slot frees up once a client goes away
+    drop(first);
+    assert!(wait_until(|| ingress.active_connections() == 1));
+    let mut fourth = GatewayClient::connect(&servers.gateway_addr);
+    assert_eq!(fourth.send_line(ORDER)["status"], "accepted");
+    assert_eq!(second.send_line(ORDER)["status"], "accepted");
+
+    let (_, prometheus) = http_request(&servers.http_addr, "GET", "/metrics", "");
+    assert!(prometheus.contains("gateway_refused_connections_total 1"));
+    servers.stop();
+}
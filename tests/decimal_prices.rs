@@ -0,0 +1,71 @@
+// ============================================================================
+// DECIMAL PRICES - JSON decimals convert exactly to integer engine prices
+// ============================================================================
+
+mod common;
+
+use common::{http_request, TestServers};
+use hft_ringbuffer::matching_engine::OrderType;
+use hft_ringbuffer::price_units::{Price, order_from_json, scale_price, PriceParseError};
+use hft_ringbuffer::tick_size::TickSchedule;
+
+#[test]
+fn decimals_scale_exactly() {
+    assert_eq!(scale_price("100.5", 2), Ok(10050));
+    assert_eq!(scale_price("100", 2), Ok(10000));
+    assert_eq!(scale_price("0.07", 2), Ok(7));
+    assert_eq!(scale_price("100.50", 1), Ok(1005));
+    assert_eq!(scale_price("1.25e2", 0), Ok(125));
+    assert_eq!(scale_price("0.000", 0), Ok(0));
+    // 0.1 + 0.2 style float noise would show up here with a float multiply
+    assert_eq!(scale_price("1.15", 2), Ok(115));
+
+    assert_eq!(scale_price("100.505", 2), Err(PriceParseError::TooPrecise { price: "100.505".to_string(), decimals: 2 }));
+    assert!(matches!(scale_price("-1", 2), Err(PriceParseError::Malformed(_))));
+    assert!(matches!(scale_price("1e30", 0), Err(PriceParseError::Overflow(_))));
+
+    let order = order_from_json(r#"{"id":1,"side":"Buy","price":100.5,"quantity":3}"#, 2).unwrap();
+    assert_eq!(order.price, 10050);
+    let order = order_from_json(r#"{"id":1,"side":"Buy","price":100,"quantity":3}"#, 0).unwrap();
+    assert_eq!(order.price, 100);
+    assert!(order_from_json(r#"{"id":1,"side":"Buy","price":"100","quantity":3}"#, 0).is_err());
+}
+
+#[test]
+fn stop_triggers_and_limits_scale_like_prices() {
+    let stop = order_from_json(r#"{"id":1,"side":"Buy","price":0,"quantity":3,"order_type":{"Stop":{"trigger":101.25}}}"#, 2).unwrap();
+    assert_eq!(stop.order_type, OrderType::Stop { trigger: Price(10125) });
+
+    let body = r#"{"id":2,"side":"Sell","price":0,"quantity":3,"order_type":{"StopLimit":{"trigger":99.5,"limit":99.45}}}"#;
+    let stop_limit = order_from_json(body, 2).unwrap();
+    assert_eq!(stop_limit.order_type, OrderType::StopLimit { trigger: Price(9950), limit: Price(9945) });
+
+    let too_precise = r#"{"id":3,"side":"Buy","price":0,"quantity":3,"order_type":{"Stop":{"trigger":101.255}}}"#;
+    assert!(order_from_json(too_precise, 2).unwrap_err().contains("more than 2 decimal places"));
+}
+
+#[test]
+fn http_orders_accept_decimal_prices() {
+    let servers = TestServers::start();
+    {
+        let mut book = servers.order_book.lock().unwrap();
+        book.set_price_decimals(2);
+        book.set_tick_schedule(Some(TickSchedule::uniform(5)));
+    }
+
+    let (status, _) = http_request(&servers.http_addr, "POST", "/api/order", r#"{"id":1,"side":"Buy","price":100.5,"quantity":3}"#);
+    assert_eq!(status, 200);
+    assert_eq!(servers.order_book.lock().unwrap().best_bid(), Some(Price(10050)));
+
+    // Finer than a cent
+    let (status, body) = http_request(&servers.http_addr, "POST", "/api/order", r#"{"id":2,"side":"Buy","price":100.505,"quantity":3}"#);
+    assert_eq!(status, 400);
+    assert!(body.contains("more than 2 decimal places"), "{}", body);
+
+    // Representable, but off the 5-cent tick
+    let (status, body) = http_request(&servers.http_addr, "POST", "/api/order", r#"{"id":3,"side":"Buy","price":100.51,"quantity":3}"#);
+    assert_eq!(status, 400);
+    assert!(body.contains("not a multiple of tick size 5"), "{}", body);
+    assert_eq!(servers.order_book.lock().unwrap().walk_bids().count(), 1);
+    servers.stop();
+}
@@ -0,0 +1,120 @@
+// ============================================================================
+// DECISION BRIDGE - AI decisions become orders through the gateway's ring
+// ============================================================================
+
+mod common;
+
+use common::{http_request, wait_until, TestServers};
+use hft_ringbuffer::decision_bridge::{DecisionBridge, DecisionBridgeConfig, DECISION_ORDER_ID_BASE};
+use hft_ringbuffer::funnel::{spawn_funnel, FunnelConfig, FunnelStats};
+use hft_ringbuffer::matching_engine::{Order, OrderSide, Packet};
+use hft_ringbuffer::price_units::Price;
+use hft_ringbuffer::signal_store::AiDecision;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+fn decision(signal: &str) -> AiDecision {
+    AiDecision::parse(&format!(r#"{{"signal":"{}"}}"#, signal)).unwrap()
+}
+
+#[test]
+fn signals_map_to_sides_and_prices_straddle_the_mid() {
+    assert_eq!(DecisionBridge::side_for("buy"), Some(OrderSide::Buy));
+    assert_eq!(DecisionBridge::side_for(" LONG "), Some(OrderSide::Buy));
+    assert_eq!(DecisionBridge::side_for("SHORT"), Some(OrderSide::Sell));
+    for signal in ["NEUTRAL", "HOLD", "Trend Momentum"] {
+        assert_eq!(DecisionBridge::side_for(signal), None);
+    }
+}
+
+#[test]
+fn bridged_orders_come_out_of_the_ring() {
+    let (producer, mut consumer) = rtrb::RingBuffer::<Packet>::new(16);
+    let shutdown = Arc::new(AtomicBool::new(false));
+    let (funnel, forwarder) =
+        spawn_funnel(producer, FunnelConfig::default(), Arc::new(FunnelStats::new()), shutdown.clone()).unwrap();
+    let config = DecisionBridgeConfig { quantity: 4, price_offset: 2, account_id: Some(9) };
+    let bridge = DecisionBridge::new(config, funnel);
+
+    assert_eq!(bridge.price_for(OrderSide::Buy, 100.5), Price(102));
+    assert_eq!(bridge.price_for(OrderSide::Sell, 100.5), Price(99));
+    assert_eq!(bridge.price_for(OrderSide::Sell, 1.0), Price(1));
+
+    assert_eq!(bridge.execute(&decision("NEUTRAL"), Some(100.5)), Ok(None));
+    assert!(bridge.execute(&decision("BUY"), None).is_err());
+    let bridged = bridge.execute(&decision("BUY"), Some(100.5)).unwrap().unwrap();
+    assert_eq!(bridged.id, DECISION_ORDER_ID_BASE);
+
+    let deadline = Instant::now() + Duration::from_secs(5);
+    let packet = loop {
+        if let Ok(packet) = consumer.pop() {
+            break packet;
+        }
+        assert!(Instant::now() < deadline, "order never reached the ring");
+        std::thread::yield_now();
+    };
+    assert_eq!(packet.order, Order::new(DECISION_ORDER_ID_BASE, OrderSide::Buy, 102, 4).with_account(9));
+    assert!(consumer.pop().is_err());
+
+    shutdown.store(true, Ordering::Relaxed);
+    forwarder.join().unwrap();
+}
+
+#[test]
+fn a_restarted_bridge_numbers_on_from_recovered_bridge_ids() {
+    let (producer, _consumer) = rtrb::RingBuffer::<Packet>::new(16);
+    let shutdown = Arc::new(AtomicBool::new(false));
+    let (funnel, forwarder) =
+        spawn_funnel(producer, FunnelConfig::default(), Arc::new(FunnelStats::new()), shutdown.clone()).unwrap();
+    let recovered = [7, DECISION_ORDER_ID_BASE + 4, 3, DECISION_ORDER_ID_BASE];
+    let bridge = DecisionBridge::new(DecisionBridgeConfig::default(), funnel.clone()).continuing_after(&recovered);
+    assert_eq!(bridge.execute(&decision("SELL"), Some(100.0)).unwrap().unwrap().id, DECISION_ORDER_ID_BASE + 5);
+
+    // Client ids alone leave the base alone
+    let bridge = DecisionBridge::new(DecisionBridgeConfig::default(), funnel).continuing_after(&[7, 3]);
+    assert_eq!(bridge.execute(&decision("SELL"), Some(100.0)).unwrap().unwrap().id, DECISION_ORDER_ID_BASE);
+
+    shutdown.store(true, Ordering::Relaxed);
+    forwarder.join().unwrap();
+}
+
+#[test]
+fn a_buy_decision_trades_against_the_book() {
+    let config = DecisionBridgeConfig { quantity: 3, price_offset: 1, account_id: None };
+    let servers = TestServers::start_with_decision_bridge(config);
+    {
+        let mut book = servers.order_book.lock().unwrap();
+        book.add_limit_order(Order::new(1, OrderSide::Buy, 99, 5));
+        book.add_limit_order(Order::new(2, OrderSide::Sell, 101, 5));
+    }
+
+    // Mid 100, one tick past it reaches the offer
+    let (status, body) = http_request(&servers.http_addr, "POST", "/api/ai-decision", r#"{"signal":"BUY"}"#);
+    assert_eq!(status, 200);
+    let response: serde_json::Value = serde_json::from_str(&body).unwrap();
+    assert_eq!(response["order"]["side"], "Buy");
+    assert_eq!((response["order"]["price"].as_u64(), response["order"]["quantity"].as_u64()), (Some(101), Some(3)));
+
+    let book = &servers.order_book;
+    assert!(wait_until(|| book.lock().unwrap().resting_quantity(2) == Some(2)));
+    let trade = book.lock().unwrap().recent_trades(1)[0].clone();
+    assert_eq!((trade.taker_order_id, trade.maker_order_id, trade.quantity), (DECISION_ORDER_ID_BASE, 2, 3));
+
+    // No trade signal, no order
+    let (_, body) = http_request(&servers.http_addr, "POST", "/api/ai-decision", r#"{"signal":"NEUTRAL"}"#);
+    assert_eq!(serde_json::from_str::<serde_json::Value>(&body).unwrap()["order"], serde_json::Value::Null);
+    servers.stop();
+}
+
+#[test]
+fn without_the_bridge_decisions_only_get_stored() {
+    let servers = TestServers::start();
+    servers.order_book.lock().unwrap().add_limit_order(Order::new(2, OrderSide::Sell, 101, 5));
+
+    let (status, body) = http_request(&servers.http_addr, "POST", "/api/ai-decision", r#"{"signal":"BUY"}"#);
+    assert_eq!(status, 200);
+    assert_eq!(serde_json::from_str::<serde_json::Value>(&body).unwrap()["order"], serde_json::Value::Null);
+    assert_eq!(servers.order_book.lock().unwrap().resting_quantity(2), Some(5));
+    servers.stop();
+}
@@ -0,0 +1,69 @@
+// ============================================================================
+// DEPTH - L2 snapshot: aggregated quantity and order count per level
+// ============================================================================
+
+mod common;
+
+use common::{http_request, TestServers};
+use hft_ringbuffer::matching_engine::{DepthLevel, Order, OrderBook, OrderSide};
+use hft_ringbuffer::price_units::Price;
+
+fn level(price: u64, qty: u64, count: usize) -> DepthLevel {
+    DepthLevel { price: Price(price), qty, count }
+}
+
+#[test]
+fn orders_at_one_price_aggregate_into_one_level() {
+    let mut book = OrderBook::new();
+    book.add_limit_order(Order::new(1, OrderSide::Buy, 100, 5));
+    book.add_limit_order(Order::new(2, OrderSide::Buy, 100, 3));
+    book.add_limit_order(Order::new(3, OrderSide::Buy, 99, 4));
+    book.add_limit_order(Order::new(4, OrderSide::Sell, 102, 1));
+    book.add_limit_order(Order::new(5, OrderSide::Sell, 102, 1));
+    book.add_limit_order(Order::new(6, OrderSide::Sell, 102, 6));
+    book.add_limit_order(Order::new(7, OrderSide::Sell, 105, 2));
+
+    let depth = book.depth(10);
+    assert_eq!(depth.bids, vec![level(100, 8, 2), level(99, 4, 1)]);
+    assert_eq!(depth.asks, vec![level(102, 8, 3), level(105, 2, 1)]);
+}
+
+#[test]
+fn levels_caps_each_side_from_the_touch() {
+    let mut book = OrderBook::new();
+    for (id, price) in [(1, 95), (2, 96), (3, 97)] {
+        book.add_limit_order(Order::new(id, OrderSide::Buy, price, 1));
+    }
+    book.add_limit_order(Order::new(4, OrderSide::Sell, 101, 1));
+
+    let depth = book.depth(2);
+    assert_eq!(depth.bids, vec![level(97, 1, 1), level(96, 1, 1)]);
+    assert_eq!(depth.asks, vec![level(101, 1, 1)]);
+    assert!(OrderBook::new().depth(5).bids.is_empty());
+}
+
+#[test]
+fn depth_endpoint_defaults_to_ten_levels() {
+    let servers = TestServers::start();
+    {
+        let mut book = servers.order_book.lock().unwrap();
+        for id in 0..12 {
+            book.add_limit_order(Order::new(id, OrderSide::Sell, 100 + id, 1));
+        }
+        book.add_limit_order(Order::new(20, OrderSide::Sell, 100, 2));
+    }
+    let (status, body) = http_request(&servers.http_addr, "GET", "/api/depth", "");
+    assert_eq!(status, 200);
+    let body: serde_json::Value = serde_json::from_str(&body).unwrap();
+    assert_eq!(body["asks"].as_array().unwrap().len(), 10);
+    assert_eq!(body["asks"][0], serde_json::json!({"price": 100, "qty": 3, "count": 2}));
+    assert_eq!(body["bids"], serde_json::json!([]));
+
+    let (_, body) = http_request(&servers.http_addr, "GET", "/api/depth?levels=3", "");
+    let body: serde_json::Value = serde_json::from_str(&body).unwrap();
+    assert_eq!(body["asks"].as_array().unwrap().len(), 3);
+
+    let (status, _) = http_request(&servers.http_addr, "GET", "/api/depth?levels=many", "");
+    assert_eq!(status, 400);
+    servers.stop();
+}
@@ -0,0 +1,66 @@
+// ============================================================================
+// DEPTH CURVE - Cumulative quantity from the touch outward
+// ============================================================================
+
+mod common;
+
+use common::{http_request, TestServers};
+use hft_ringbuffer::matching_engine::{Order, OrderBook, OrderSide};
+use hft_ringbuffer::price_units::Price;
+
+/// Bids 99x3, 98x2+4, 96x1; asks 101x5, 102x1, 105x10
+fn known_book(book: &mut OrderBook) {
+    for (id, side, price, quantity) in [
+        (1, OrderSide::Buy, 99, 3),
+        (2, OrderSide::Buy, 98, 2),
+        (3, OrderSide::Buy, 98, 4),
+        (4, OrderSide::Buy, 96, 1),
+        (5, OrderSide::Sell, 101, 5),
+        (6, OrderSide::Sell, 102, 1),
+        (7, OrderSide::Sell, 105, 10),
+    ] {
+        book.add_limit_order(Order::new(id, side, price, quantity));
+    }
+}
+
+#[test]
+fn curves_accumulate_from_the_touch() {
+    let mut book = OrderBook::new();
+    known_book(&mut book);
+
+    let bids = book.depth_curve(OrderSide::Buy, 10);
+    assert_eq!(bids, vec![(Price(99), 3), (Price(98), 9), (Price(96), 10)]);
+    let asks = book.depth_curve(OrderSide::Sell, 10);
+    assert_eq!(asks, vec![(Price(101), 5), (Price(102), 6), (Price(105), 16)]);
+    for curve in [&bids, &asks] {
+        assert!(curve.windows(2).all(|pair| pair[1].1 > pair[0].1));
+    }
+
+    assert_eq!(book.depth_curve(OrderSide::Sell, 2), vec![(Price(101), 5), (Price(102), 6)]);
+    let both = book.depth_curves(1);
+    assert_eq!(both.bids, vec![(Price(99), 3)]);
+    assert_eq!(both.asks, vec![(Price(101), 5)]);
+
+    // A level emptied by a trade drops out of the curve
+    book.add_limit_order(Order::new(8, OrderSide::Buy, 101, 5));
+    assert_eq!(book.depth_curve(OrderSide::Sell, 10), vec![(Price(102), 1), (Price(105), 11)]);
+}
+
+#[test]
+fn depth_curve_is_served_over_http() {
+    let servers = TestServers::start();
+    known_book(&mut servers.order_book.lock().unwrap());
+
+    let (status, body) = http_request(&servers.http_addr, "GET", "/api/depth-curve?levels=2", "");
+    assert_eq!(status, 200);
+    let curves: serde_json::Value = serde_json::from_str(&body).unwrap();
+    assert_eq!(curves, serde_json::json!({"bids": [[99, 3], [98, 9]], "asks": [[101, 5], [102, 6]]}));
+
+    let (status, body) = http_request(&servers.http_addr, "GET", "/api/depth-curve?side=ask", "");
+    assert_eq!(status, 200);
+    assert_eq!(body, "[[101,5],[102,6],[105,16]]");
+
+    assert_eq!(http_request(&servers.http_addr, "GET", "/api/depth-curve?side=mid", "").0, 400);
+    assert_eq!(http_request(&servers.http_addr, "GET", "/api/depth-curve?levels=x", "").0, 400);
+    servers.stop();
+}
@@ -0,0 +1,124 @@
+// ============================================================================
+// DUPLICATE IDS - A resent order id is refused, not booked twice
+// ============================================================================
+
+mod common;
+
+use common::{http_request, GatewayClient, TestServers};
+use hft_ringbuffer::matching_engine::{BookAction, Order, OrderBook, OrderSide};
+use hft_ringbuffer::order_ids::RecentOrderIds;
+use hft_ringbuffer::rejections::RejectReason;
+use hft_ringbuffer::wal::WriteAheadLog;
+use hft_ringbuffer::warm_start::{load_journal, recover, BookSnapshotFile};
+use serde_json::json;
+
+fn bid(id: u64) -> Order {
+    Order::new(id, OrderSide::Buy, 100, 1)
+}
+
+#[test]
+fn the_window_forgets_its_oldest_ids() {
+    let mut ids = RecentOrderIds::new(2);
+    assert!(ids.insert(1));
+    assert!(!ids.insert(1));
+    assert!(ids.insert(2));
+    assert!(ids.insert(3));
+    assert!(!ids.contains(1));
+    assert!(ids.contains(2) && ids.contains(3));
+
+    ids.set_capacity(1);
+    assert_eq!((ids.len(), ids.contains(3)), (1, true));
+
+    // No window, nothing remembered
+    let mut off = RecentOrderIds::default();
+    assert!(!off.insert(1));
+    assert!(off.is_empty());
+}
+
+#[test]
+fn a_repeat_within_the_window_is_rejected_and_one_past_eviction_is_accepted() {
+    let mut book = OrderBook::new();
+    book.set_duplicate_window(3);
+
+    book.submit_order(bid(1)).unwrap();
+    let error = book.submit_order(bid(1)).unwrap_err();
+    assert_eq!(error.reason, RejectReason::Duplicate);
+    assert_eq!(book.resting_quantity(1), Some(1));
+
+    // Three newer ids push 1 out of the window
+    for id in 2..=4 {
+        book.submit_order(bid(id)).unwrap();
+    }
+    assert!(book.submit_order(bid(2)).is_err());
+    book.cancel_order(1);
+    book.submit_order(bid(1)).unwrap();
+    assert_eq!(book.resting_quantity(1), Some(1));
+}
+
+#[test]
+fn refused_orders_do_not_use_up_their_id() {
+    let mut book = OrderBook::new();
+    book.set_duplicate_window(10);
+    assert_eq!(book.submit_order(Order::new(1, OrderSide::Buy, 100, 0)).unwrap_err().reason, RejectReason::ZeroQuantity);
+    book.submit_order(bid(1)).unwrap();
+
+    // Off by default
+    let mut book = OrderBook::new();
+    book.submit_order(bid(1)).unwrap();
+    book.submit_order(bid(1)).unwrap();
+}
+
+#[test]
+fn ids_accepted_before_a_restart_are_still_duplicates_after_it() {
+    let wal_path = std::env::temp_dir().join(format!("duplicate_ids_{}.wal", std::process::id()));
+    let _ = std::fs::remove_file(&wal_path);
+    let mut before = OrderBook::new();
+    before.set_duplicate_window(10);
+    let (mut wal, _) = WriteAheadLog::open(&wal_path).unwrap();
+    // 1 and 2 cross away entirely, 3 rests in the snapshot, 4 comes and goes after it
+    for order in [bid(1), Order::new(2, OrderSide::Sell, 100, 1), bid(3)] {
+        wal.append(&order).unwrap();
+        before.submit_order(order).unwrap();
+    }
+    let snapshot = BookSnapshotFile::capture(&before, 3);
+    wal.append(&bid(4)).unwrap();
+    wal.append_action(&Order::new(4, OrderSide::Buy, 0, 0), BookAction::Cancel).unwrap();
+    drop(wal);
+
+    let journal = load_journal(&wal_path).unwrap();
+    std::fs::remove_file(&wal_path).unwrap();
+    let windowed = || {
+        let mut book = OrderBook::new();
+        book.set_duplicate_window(10);
+        book
+    };
+    let (mut book, _) = recover(windowed, Some(&snapshot), &journal).unwrap();
+
+    for id in 1..=4 {
+        assert_eq!(book.submit_order(bid(id)).unwrap_err().reason, RejectReason::Duplicate, "id {}", id);
+    }
+    book.submit_order(bid(5)).unwrap();
+}
+
+#[test]
+fn gateway_and_http_both_refuse_a_resend() {
+    let servers = TestServers::start();
+    servers.order_book.lock().unwrap().set_duplicate_window(100);
+    let mut client = GatewayClient::connect(&servers.gateway_addr);
+    let order = json!({"id": 7, "side": "Buy", "price": 100, "quantity": 2}).to_string();
+
+    assert_eq!(client.send_line(&order)["status"], "accepted");
+    let ack = client.send_line(&order);
+    assert_eq!((&ack["status"], &ack["reason"]), (&json!("duplicate"), &json!("duplicate")));
+
+    let (status, body) = http_request(&servers.http_addr, "POST", "/api/order", &order);
+    assert_eq!(status, 409);
+    assert_eq!(serde_json::from_str::<serde_json::Value>(&body).unwrap()["status"], "duplicate");
+
+    // Booked once, for the original quantity
+    let book = servers.order_book.lock().unwrap();
+    assert_eq!(book.resting_orders().len(), 1);
+    assert_eq!(book.resting_quantity(7), Some(2));
+    drop(book);
+    servers.stop();
+}
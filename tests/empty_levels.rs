@@ -0,0 +1,32 @@
+// ============================================================================
+// EMPTY LEVELS - Fully filled price levels leave the book
+// ============================================================================
+
+use hft_ringbuffer::matching_engine::{Order, OrderBook, OrderSide};
+use hft_ringbuffer::price_units::Price;
+
+fn level_prices(book: &OrderBook, side: &str) -> Vec<u64> {
+    let json: serde_json::Value = serde_json::from_str(&book.to_json()).unwrap();
+    json[side].as_array().unwrap().iter().map(|level| level["price"].as_u64().unwrap()).collect()
+}
+
+#[test]
+fn crossing_away_a_whole_level_removes_its_key() {
+    let mut book = OrderBook::new();
+    book.add_limit_order(Order::new(1, OrderSide::Sell, 100, 2));
+    book.add_limit_order(Order::new(2, OrderSide::Sell, 100, 3));
+    book.add_limit_order(Order::new(3, OrderSide::Sell, 101, 4));
+    book.add_limit_order(Order::new(4, OrderSide::Buy, 99, 1));
+
+    book.add_limit_order(Order::new(5, OrderSide::Buy, 100, 5));
+    assert_eq!(level_prices(&book, "asks"), vec![101]);
+    assert_eq!(book.best_ask(), Some(Price(101)));
+
+    // Filling the last order on either side leaves no level behind
+    book.add_limit_order(Order::new(6, OrderSide::Sell, 99, 1));
+    assert_eq!(level_prices(&book, "bids"), Vec::<u64>::new());
+    assert_eq!(book.best_bid(), None);
+    book.add_limit_order(Order::new(7, OrderSide::Buy, 105, 4));
+    assert_eq!(level_prices(&book, "asks"), Vec::<u64>::new());
+    assert_eq!(book.validate(), Ok(()));
+}
@@ -0,0 +1,85 @@
+// ============================================================================
+// END-TO-END: TCP gateway + HTTP API -> Ring Buffer -> Engine -> Order Book
+// ============================================================================
+
+mod common;
+
+use common::{http_request, wait_until, GatewayClient, TestServers};
+use hft_ringbuffer::matching_engine::{Order, OrderSide};
+use serde_json::{json, Value};
+
+fn book_has(http_addr: &str, side: &str, price: u64) -> bool {
+    let (status, body) = http_request(http_addr, "GET", "/api/orderbook", "");
+    if status != 200 {
+        return false;
+    }
+    let book: Value = serde_json::from_str(&body).unwrap();
+    book[side]
+        .as_array()
+        .unwrap()
+        .iter()
+        .any(|level| level["price"] == price && !level["orders"].as_array().unwrap().is_empty())
+}
+
+#[test]
+fn orders_from_tcp_and_http_both_reach_the_book() {
+    let servers = TestServers::start();
+
+    let mut client = GatewayClient::connect(&servers.gateway_addr);
+    let ack = client.send_line(r#"{"id":1,"side":"Buy","price":100,"quantity":5}"#);
+    assert_eq!(ack, json!({"status": "accepted", "executions": [], "filled_quantity": 0, "rested_quantity": 5}));
+
+    let (status, body) = http_request(
+        &servers.http_addr,
+        "POST",
+        "/api/order",
+        r#"{"id":2,"side":"Sell","price":105,"quantity":3}"#,
+    );
+    assert_eq!(status, 200);
+    let ack: Value = serde_json::from_str(&body).unwrap();
+    assert_eq!(ack, json!({"status": "accepted", "executions": [], "filled_quantity": 0, "rested_quantity": 3}));
+
+    assert!(wait_until(|| book_has(&servers.http_addr, "bids", 100)), "TCP order never reached the book");
+    assert!(book_has(&servers.http_addr, "asks", 105), "HTTP order missing from the book");
+
+    servers.stop();
+}
+
+#[test]
+fn tcp_and_http_ack_fills_the_same_way() {
+    let servers = TestServers::start();
+    servers.order_book.lock().unwrap().add_limit_order(Order::new(1, OrderSide::Sell, 100, 3));
+
+    let (_, body) = http_request(&servers.http_addr, "POST", "/api/order", r#"{"id":2,"side":"Buy","price":100,"quantity":2}"#);
+    let ack: Value = serde_json::from_str(&body).unwrap();
+    assert_eq!((&ack["status"], &ack["filled_quantity"], &ack["rested_quantity"]), (&json!("filled"), &json!(2), &json!(0)));
+
+    let mut client = GatewayClient::connect(&servers.gateway_addr);
+    let ack = client.send_line(r#"{"id":3,"side":"Buy","price":100,"quantity":4}"#);
+    assert_eq!((&ack["status"], &ack["filled_quantity"], &ack["rested_quantity"]), (&json!("partially_filled"), &json!(1), &json!(3)));
+    assert_eq!(ack["executions"].as_array().unwrap().len(), 1);
+
+    servers.stop();
+}
+
+#[test]
+fn malformed_orders_get_an_error_ack_on_both_paths() {
+    let servers = TestServers::start();
+
+    let mut client = GatewayClient::connect(&servers.gateway_addr);
+    let ack = client.send_line(r#"{"id":1,"side":"Sideways"}"#);
+    assert_eq!(ack["status"], "error");
+    assert!(ack["reason"].is_string());
+
+    // The connection survives a bad line
+    let ack = client.send_line(r#"{"id":2,"side":"Buy","price":100,"quantity":1}"#);
+    assert_eq!(ack["status"], "accepted");
+
+    let (status, body) = http_request(&servers.http_addr, "POST", "/api/order", "not json");
+    assert_eq!(status, 400);
+    let ack: Value = serde_json::from_str(&body).unwrap();
+    assert_eq!(ack["status"], "error");
+    assert!(ack["reason"].is_string());
+
+    servers.stop();
+}
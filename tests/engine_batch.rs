@@ -0,0 +1,89 @@
+// ============================================================================
+// ENGINE BATCHING - Many packets per book lock, none lost or reordered
+// ============================================================================
+
+use hft_ringbuffer::engine::{drain_batch, spawn_engine, EngineHooks};
+use hft_ringbuffer::matching_engine::{Order, OrderBook, OrderSide, Packet};
+use hft_ringbuffer::metrics::Metrics;
+use hft_ringbuffer::wal::WriteAheadLog;
+use hft_ringbuffer::warm_start::load_journal;
+use std::fs;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+fn order(id: u64) -> Order {
+    // Crossing flow at a handful of prices, so arrival order decides who trades with whom
+    let side = if id.is_multiple_of(3) { OrderSide::Buy } else { OrderSide::Sell };
+    Order::new(id, side, 100 + id % 5, 1 + id % 4)
+}
+
+fn ids(batch: &[Packet]) -> Vec<u64> {
+    batch.iter().map(|packet| packet.order.id).collect()
+}
+
+#[test]
+fn drain_batch_takes_at_most_max_oldest_first() {
+    let (mut producer, mut consumer) = rtrb::RingBuffer::<Packet>::new(16);
+    for id in 0..10 {
+        producer.push(Packet::new(order(id))).unwrap();
+    }
+
+    assert_eq!(ids(&drain_batch(&mut consumer, 4)), vec![0, 1, 2, 3]);
+    assert_eq!(ids(&drain_batch(&mut consumer, 4)), vec![4, 5, 6, 7]);
+    assert_eq!(ids(&drain_batch(&mut consumer, 4)), vec![8, 9]);
+    assert!(drain_batch(&mut consumer, 4).is_empty());
+}
+
+#[test]
+fn drain_batch_reads_across_the_ring_wraparound() {
+    let (mut producer, mut consumer) = rtrb::RingBuffer::<Packet>::new(8);
+    for id in 0..6 {
+        producer.push(Packet::new(order(id))).unwrap();
+    }
+    assert_eq!(drain_batch(&mut consumer, 6).len(), 6);
+    for id in 6..12 {
+        producer.push(Packet::new(order(id))).unwrap();
+    }
+
+    assert_eq!(ids(&drain_batch(&mut consumer, 8)), (6..12).collect::<Vec<_>>());
+}
+
+#[test]
+fn batched_engine_applies_every_packet_in_arrival_order() {
+    const ORDERS: u64 = 5_000;
+    let mut expected = OrderBook::new();
+    for id in 0..ORDERS {
+        expected.add_limit_order(order(id));
+    }
+
+    for max_batch in [Some(0), Some(1), Some(17), None] {
+        let wal_path = std::env::temp_dir().join(format!("engine_batch_{}_{:?}", std::process::id(), max_batch));
+        let _ = fs::remove_file(&wal_path);
+        let (wal, _) = WriteAheadLog::open(&wal_path).unwrap();
+
+        // A small ring keeps the engine catching up in partial batches
+        let (mut producer, consumer) = rtrb::RingBuffer::<Packet>::new(64);
+        let book = Arc::new(Mutex::new(OrderBook::new()));
+        let shutdown = Arc::new(AtomicBool::new(false));
+        let hooks = EngineHooks { wal: Some(wal), max_batch, drain_on_shutdown: true, ..Default::default() };
+        let engine = spawn_engine(consumer, book.clone(), shutdown.clone(), Arc::new(Metrics::new()), hooks).unwrap();
+
+        for id in 0..ORDERS {
+            let mut packet = Packet::new(order(id));
+            while let Err(rtrb::PushError::Full(rejected)) = producer.push(packet) {
+                packet = rejected;
+                thread::sleep(Duration::from_micros(10));
+            }
+        }
+        shutdown.store(true, Ordering::Relaxed);
+        engine.join().unwrap();
+
+        let journal = load_journal(&wal_path).unwrap();
+        assert_eq!(journal.iter().map(|entry| entry.order.id).collect::<Vec<_>>(), (0..ORDERS).collect::<Vec<_>>(),
+            "max_batch {:?}", max_batch);
+        assert_eq!(book.lock().unwrap().to_json(), expected.to_json(), "max_batch {:?}", max_batch);
+        fs::remove_file(&wal_path).unwrap();
+    }
+}
@@ -0,0 +1,40 @@
+// ============================================================================
+// ENGINE METRICS - /api/metrics reports what the engine actually did
+// ============================================================================
+
+mod common;
+
+use common::{http_request, wait_until, GatewayClient, TestServers};
+use hft_ringbuffer::metrics::Metrics;
+
+#[test]
+fn counters_reflect_processed_orders() {
+    const ORDERS: u64 = 40;
+    let servers = TestServers::start();
+    let mut client = GatewayClient::connect(&servers.gateway_addr);
+    // Alternate resting sells with buys that take them out
+    for id in 1..=ORDERS {
+        let side = if id % 2 == 1 { "Sell" } else { "Buy" };
+        client.send_line(&format!(r#"{{"id": {}, "side": "{}", "price": 100, "quantity": 1}}"#, id, side));
+    }
+    assert!(wait_until(|| servers.metrics.orders_processed() == ORDERS));
+
+    let (status, body) = http_request(&servers.http_addr, "GET", "/api/metrics", "");
+    assert_eq!(status, 200);
+    let metrics: serde_json::Value = serde_json::from_str(&body).unwrap();
+    assert_eq!(metrics["orders_processed"], ORDERS);
+    assert_eq!(metrics["trades_executed"], ORDERS / 2);
+    assert!(metrics["throughput"].as_u64().unwrap() > 0);
+    assert!(metrics["latency"].as_u64().unwrap() > 0);
+    servers.stop();
+}
+
+#[test]
+fn idle_engine_reports_zero_throughput() {
+    let metrics = Metrics::new();
+    assert_eq!(metrics.orders_processed(), 0);
+    assert_eq!(metrics.orders_per_second(), 0.0);
+    metrics.record_order_processed(3);
+    assert_eq!((metrics.orders_processed(), metrics.trades_executed()), (1, 3));
+    assert!(metrics.orders_per_second() > 0.0);
+}
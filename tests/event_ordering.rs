@@ -0,0 +1,122 @@
+// ============================================================================
+// EVENT ORDERING - Bus messages arrive in engine order, gaps can be resynced
+// ============================================================================
+
+use hft_ringbuffer::bbo::BboPublisher;
+use hft_ringbuffer::clock::ManualClock;
+use hft_ringbuffer::engine::{run_engine, EngineHooks};
+use hft_ringbuffer::events::{BookEvent, BusMessage, EventBus, FillNotificationMode, FillNotifier, SequenceCheck, SequenceTracker};
+use hft_ringbuffer::matching_engine::{OrderBook, Packet};
+use hft_ringbuffer::metrics::Metrics;
+use hft_ringbuffer::order_generator::{GeneratorParams, OrderGenerator};
+use hft_ringbuffer::trade_history::{TradeHistory, TradeSink};
+use hft_ringbuffer::price_units::Price;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+#[test]
+fn mixed_workload_arrives_strictly_in_sequence() {
+    const ORDERS: usize = 5_000;
+    let bus = Arc::new(EventBus::new());
+    let feed = bus.subscribe();
+    let (mut producer, consumer) = rtrb::RingBuffer::<Packet>::new(1024);
+    let shutdown = Arc::new(AtomicBool::new(false));
+
+    // Trades come from the writer thread, BBOs and fills from the engine
+    let (trades, writer) = TradeSink::offloaded(Arc::new(Mutex::new(TradeHistory::new(16))), Some(bus.clone())).unwrap();
+    let hooks = EngineHooks {
+        bbo: Some(BboPublisher::new(0, Arc::new(ManualClock::new(0)), bus.clone())),
+        trades: Some(trades),
+        fills: Some(FillNotifier::new(bus.clone(), FillNotificationMode::Coalesced)),
+        ..Default::default()
+    };
+    let engine = {
+        let shutdown = shutdown.clone();
+        let book = Arc::new(Mutex::new(OrderBook::new()));
+        thread::spawn(move || run_engine(consumer, book, shutdown, Arc::new(Metrics::new()), hooks))
+    };
+
+    for order in OrderGenerator::new(7, GeneratorParams::default()).new_orders().take(ORDERS) {
+        let mut packet = Packet::new(order);
+        while let Err(rtrb::PushError::Full(rejected)) = producer.push(packet) {
+            packet = rejected;
+            thread::yield_now();
+        }
+    }
+    while producer.slots() < producer.buffer().capacity() {
+        thread::yield_now();
+    }
+    shutdown.store(true, Ordering::Relaxed);
+    engine.join().unwrap();
+    writer.join().unwrap();
+
+    let messages: Vec<BusMessage> = feed.try_iter().collect();
+    let mut tracker = SequenceTracker::new();
+    for message in &messages {
+        assert_eq!(tracker.observe(message.sequence), SequenceCheck::InOrder);
+    }
+
+    // Every fill comes right after the trades it summarises
+    let mut trades_since_fill = 0;
+    let (mut fills, mut bbos) = (0, 0);
+    for message in &messages {
+        match &message.event {
+            BookEvent::Trade(_) => trades_since_fill += 1,
+            BookEvent::Fill(fill) => {
+                assert_eq!(fill.legs, trades_since_fill, "fill at sequence {}", message.sequence);
+                trades_since_fill = 0;
+                fills += 1;
+            }
+            BookEvent::Bbo { .. } => bbos += 1,
+            BookEvent::Rejection(rejection) => panic!("unexpected rejection {:?}", rejection),
+        }
+    }
+    assert_eq!(trades_since_fill, 0);
+    assert!(fills > 0 && bbos > 0);
+}
+
+#[test]
+fn reserved_sequences_hold_back_later_events() {
+    let bus = EventBus::new();
+    let feed = bus.subscribe();
+    let first = bus.reserve(2);
+    bus.publish(BookEvent::Bbo { bid: Some(Price(1)), ask: None, bid_quantity: 1, ask_quantity: 0, timestamp_ns: 0, symbol: None });
+    assert!(feed.try_recv().is_err(), "must wait for the reserved sequences");
+
+    bus.publish_reserved(first + 1, BookEvent::Bbo { bid: Some(Price(2)), ask: None, bid_quantity: 1, ask_quantity: 0, timestamp_ns: 0, symbol: None });
+    assert!(feed.try_recv().is_err());
+    bus.publish_reserved(first, BookEvent::Bbo { bid: Some(Price(3)), ask: None, bid_quantity: 1, ask_quantity: 0, timestamp_ns: 0, symbol: None });
+
+    let received: Vec<(u64, Option<Price>)> = feed.try_iter()
+        .map(|message| match message.event {
+            BookEvent::Bbo { bid, .. } => (message.sequence, bid),
+            other => panic!("unexpected {:?}", other),
+        })
+        .collect();
+    assert_eq!(received, vec![(1, Some(Price(3))), (2, Some(Price(2))), (3, Some(Price(1)))]);
+}
+
+#[test]
+fn consumer_resyncs_a_gap_from_retained_messages() {
+    let bus = EventBus::with_retention(3);
+    for bid in 1..=5 {
+        bus.publish(BookEvent::Bbo { bid: Some(Price(bid)), ask: None, bid_quantity: 1, ask_quantity: 0, timestamp_ns: 0, symbol: None });
+    }
+
+    // A consumer that last applied 3 and then sees 5 asks for what it missed
+    let mut tracker = SequenceTracker::resume_after(3);
+    assert_eq!(tracker.observe(5), SequenceCheck::Gap { expected: 4, received: 5 });
+    let missed = bus.replay_since(tracker.last_applied()).unwrap();
+    let sequences: Vec<u64> = missed.iter().map(|message| message.sequence).collect();
+    assert_eq!(sequences, vec![4, 5]);
+    for message in &missed {
+        assert_eq!(tracker.observe(message.sequence), SequenceCheck::InOrder);
+    }
+    assert_eq!(tracker.observe(5), SequenceCheck::Stale { expected: 6, received: 5 });
+
+    // Only 3..=5 are retained
+    assert!(bus.replay_since(2).is_ok());
+    assert_eq!(bus.replay_since(1).unwrap_err().oldest_retained, Some(3));
+    assert!(bus.replay_since(5).unwrap().is_empty());
+}
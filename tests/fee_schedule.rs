@@ -0,0 +1,38 @@
+// ============================================================================
+// FEE SCHEDULES - Each execution priced by the schedule active when it traded
+// ============================================================================
+
+use hft_ringbuffer::fees::FeeSchedule;
+use hft_ringbuffer::matching_engine::{Order, OrderBook, OrderSide};
+
+#[test]
+fn swapping_the_schedule_only_affects_later_executions() {
+    let mut book = OrderBook::new();
+    assert_eq!(book.set_fee_schedule(FeeSchedule::new(-1, 5)), 1);
+
+    book.add_limit_order(Order::new(1, OrderSide::Sell, 10_000, 10));
+    let before = book.add_limit_order(Order::new(2, OrderSide::Buy, 10_000, 4));
+
+    assert_eq!(book.set_fee_schedule(FeeSchedule::new(2, 10)), 2);
+    let after = book.add_limit_order(Order::new(3, OrderSide::Buy, 10_000, 6));
+
+    // Notional 40_000: maker rebate 1bp, taker 5bp
+    assert_eq!(before[0].fee_version, 1);
+    assert_eq!((before[0].maker_fee, before[0].taker_fee), (-4, 20));
+    // Notional 60_000: maker 2bp, taker 10bp
+    assert_eq!(after[0].fee_version, 2);
+    assert_eq!((after[0].maker_fee, after[0].taker_fee), (12, 60));
+
+    let history = book.fee_history();
+    assert_eq!(history.versions().len(), 3);
+    assert_eq!(history.version(2).unwrap().effective_from_sequence, 3);
+    assert_eq!(history.version(before[0].fee_version).unwrap().schedule, FeeSchedule::new(-1, 5));
+}
+
+#[test]
+fn books_start_on_the_fee_free_version() {
+    let mut book = OrderBook::new();
+    book.add_limit_order(Order::new(1, OrderSide::Buy, 100, 1));
+    let executions = book.add_limit_order(Order::new(2, OrderSide::Sell, 100, 1));
+    assert_eq!((executions[0].fee_version, executions[0].maker_fee, executions[0].taker_fee), (0, 0, 0));
+}
@@ -0,0 +1,74 @@
+// ============================================================================
+// FILL NOTIFICATIONS - Coalescing a multi-level sweep into one client update
+// ============================================================================
+
+use hft_ringbuffer::engine::{run_engine, EngineHooks};
+use hft_ringbuffer::events::{BookEvent, EventBus, FillNotification, FillNotificationMode, FillNotifier};
+use hft_ringbuffer::matching_engine::{Order, OrderBook, OrderSide, Packet};
+use hft_ringbuffer::metrics::Metrics;
+use hft_ringbuffer::trade_history::{TradeHistory, TradeSink};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+/// Three ask levels swept by one buy, run through a real engine thread.
+/// Returns the fill notifications published and the trades recorded.
+fn sweep(mode: FillNotificationMode) -> (Vec<FillNotification>, usize) {
+    let (mut producer, consumer) = rtrb::RingBuffer::<Packet>::new(16);
+    let bus = Arc::new(EventBus::new());
+    let feed = bus.subscribe();
+    let history = Arc::new(Mutex::new(TradeHistory::new(16)));
+    let shutdown = Arc::new(AtomicBool::new(false));
+    let engine = {
+        let shutdown = shutdown.clone();
+        let hooks = EngineHooks {
+            trades: Some(TradeSink::Inline(history.clone())),
+            fills: Some(FillNotifier::new(bus, mode)),
+            ..Default::default()
+        };
+        let book = Arc::new(Mutex::new(OrderBook::new()));
+        thread::spawn(move || run_engine(consumer, book, shutdown, Arc::new(Metrics::new()), hooks))
+    };
+
+    for order in [
+        Order::new(1, OrderSide::Sell, 100, 2),
+        Order::new(2, OrderSide::Sell, 101, 3),
+        Order::new(3, OrderSide::Sell, 102, 5),
+        Order::new(4, OrderSide::Buy, 102, 7),
+    ] {
+        producer.push(Packet::new(order)).unwrap();
+    }
+    while producer.slots() < producer.buffer().capacity() {
+        thread::yield_now();
+    }
+    shutdown.store(true, Ordering::Relaxed);
+    engine.join().unwrap();
+
+    let fills = feed.try_iter()
+        .filter_map(|message| match message.event {
+            BookEvent::Fill(fill) => Some(fill),
+            _ => None,
+        })
+        .collect();
+    let trades = history.lock().unwrap().len();
+    (fills, trades)
+}
+
+#[test]
+fn multi_level_sweep_produces_one_coalesced_notification() {
+    let (fills, trades) = sweep(FillNotificationMode::Coalesced);
+
+    // 2 @ 100 + 3 @ 101 + 2 @ 102 = 707 over 7
+    assert_eq!(fills, vec![FillNotification { order_id: 4, filled_quantity: 7, average_price: 707.0 / 7.0, legs: 3 }]);
+    assert_eq!(trades, 3);
+}
+
+#[test]
+fn per_execution_mode_notifies_every_leg() {
+    let (fills, trades) = sweep(FillNotificationMode::PerExecution);
+
+    let legs: Vec<(u64, f64)> = fills.iter().map(|f| (f.filled_quantity, f.average_price)).collect();
+    assert_eq!(legs, vec![(2, 100.0), (3, 101.0), (2, 102.0)]);
+    assert!(fills.iter().all(|f| f.order_id == 4 && f.legs == 1));
+    assert_eq!(trades, 3);
+}
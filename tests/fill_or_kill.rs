@@ -0,0 +1,96 @@
+// ============================================================================
+// FILL-OR-KILL - Trade the full quantity on arrival or leave the book alone
+// ============================================================================
+
+mod common;
+
+use common::{http_request, GatewayClient, TestServers};
+use hft_ringbuffer::matching_engine::{Order, OrderBook, OrderSide, TimeInForce};
+use hft_ringbuffer::price_units::Price;
+use hft_ringbuffer::rejections::RejectReason;
+
+fn book_with_asks() -> OrderBook {
+    let mut book = OrderBook::new();
+    book.add_limit_order(Order::new(1, OrderSide::Sell, 100, 3));
+    book.add_limit_order(Order::new(2, OrderSide::Sell, 101, 4));
+    book.add_limit_order(Order::new(3, OrderSide::Sell, 105, 10));
+    book.add_limit_order(Order::new(4, OrderSide::Buy, 95, 5));
+    book
+}
+
+#[test]
+fn fully_fillable_order_trades_across_levels() {
+    let mut book = book_with_asks();
+    let executions = book.add_limit_order(Order::new(10, OrderSide::Buy, 101, 7).fill_or_kill());
+
+    let fills: Vec<(u64, u64)> = executions.iter().map(|e| (e.price.units(), e.quantity)).collect();
+    assert_eq!(fills, vec![(100, 3), (101, 4)]);
+    assert_eq!(book.best_ask(), Some(Price(105)));
+    assert_eq!(book.best_bid(), Some(Price(95)));
+}
+
+#[test]
+fn under_filled_order_is_killed_and_the_book_is_untouched() {
+    let mut book = book_with_asks();
+    let before = book.level_depth(10);
+
+    // Only 7 is available at 101 or better
+    let executions = book.add_limit_order(Order::new(10, OrderSide::Buy, 101, 8).fill_or_kill());
+    assert!(executions.is_empty());
+    assert_eq!(book.level_depth(10), before);
+
+    let executions = book.add_limit_order(Order::new(11, OrderSide::Sell, 90, 6).fill_or_kill());
+    assert!(executions.is_empty());
+    assert_eq!(book.level_depth(10), before);
+}
+
+#[test]
+fn a_killed_order_can_be_sent_again() {
+    let mut book = book_with_asks();
+    book.set_duplicate_window(16);
+    let checksum = book.checksum();
+    let killed = book.submit_order(Order::new(10, OrderSide::Buy, 101, 8).fill_or_kill()).unwrap_err();
+    assert_eq!(killed.reason, RejectReason::Killed);
+    assert_eq!(book.checksum(), checksum);
+
+    // Once there is enough, the same id goes through rather than counting as a resend
+    book.add_limit_order(Order::new(5, OrderSide::Sell, 101, 1));
+    let executions = book.submit_order(Order::new(10, OrderSide::Buy, 101, 8).fill_or_kill()).unwrap();
+    assert_eq!(executions.iter().map(|e| e.quantity).sum::<u64>(), 8);
+}
+
+#[test]
+fn iceberg_reserve_counts_towards_the_fill() {
+    let mut book = OrderBook::new();
+    book.add_limit_order(Order::new(1, OrderSide::Sell, 100, 10).with_display_quantity(2));
+
+    let executions = book.add_limit_order(Order::new(10, OrderSide::Buy, 100, 10).fill_or_kill());
+    assert_eq!(executions.iter().map(|e| e.quantity).sum::<u64>(), 10);
+    assert_eq!(book.best_ask(), None);
+}
+
+#[test]
+fn time_in_force_comes_through_json() {
+    let order: Order =
+        serde_json::from_str(r#"{"id": 1, "side": "Buy", "price": 100, "quantity": 2, "time_in_force": "FillOrKill"}"#).unwrap();
+    assert_eq!(order.time_in_force, TimeInForce::FillOrKill);
+    let order: Order = serde_json::from_str(r#"{"id": 1, "side": "Buy", "price": 100, "quantity": 2}"#).unwrap();
+    assert_eq!(order.time_in_force, TimeInForce::GoodTillCancel);
+}
+
+#[test]
+fn clients_hear_the_order_was_killed() {
+    let servers = TestServers::start();
+    servers.order_book.lock().unwrap().add_limit_order(Order::new(1, OrderSide::Sell, 100, 3));
+    let order = r#"{"id":2,"side":"Buy","price":100,"quantity":5,"time_in_force":"FillOrKill"}"#;
+
+    let mut client = GatewayClient::connect(&servers.gateway_addr);
+    let ack = client.send_line(order);
+    assert_eq!((ack["status"].as_str(), ack["reason"].as_str()), (Some("killed"), Some("killed")));
+
+    let (status, body) = http_request(&servers.http_addr, "POST", "/api/order", order);
+    let ack: serde_json::Value = serde_json::from_str(&body).unwrap();
+    assert_eq!((status, ack["status"].as_str()), (200, Some("killed")));
+    assert_eq!(servers.order_book.lock().unwrap().resting_quantity(1), Some(3));
+    servers.stop();
+}
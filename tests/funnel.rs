@@ -0,0 +1,188 @@
+// ============================================================================
+// ORDER FUNNEL - In-flight cap under saturating producers
+// ============================================================================
+
+use hft_ringbuffer::funnel::{spawn_funnel, FunnelConfig, FunnelStats, OverflowPolicy, SubmitError};
+use hft_ringbuffer::matching_engine::{Order, OrderSide, Packet};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+const RING_CAPACITY: usize = 4;
+const MAX_IN_FLIGHT: usize = 8;
+
+fn packet(id: u64) -> Packet {
+    Packet::new(Order::new(id, OrderSide::Buy, 100, 1))
+}
+
+/// Samples the in-flight depth until `stop`, returning the highest seen.
+fn watch_depth(stats: Arc<FunnelStats>, stop: Arc<AtomicBool>) -> thread::JoinHandle<usize> {
+    thread::spawn(move || {
+        let mut peak = 0;
+        while !stop.load(Ordering::Relaxed) {
+            peak = peak.max(stats.in_flight());
+            thread::yield_now();
+        }
+        peak
+    })
+}
+
+#[test]
+fn rejecting_producers_are_capped_while_the_engine_is_stalled() {
+    let (producer, mut consumer) = rtrb::RingBuffer::<Packet>::new(RING_CAPACITY);
+    let stats = Arc::new(FunnelStats::new());
+    let shutdown = Arc::new(AtomicBool::new(false));
+    let config = FunnelConfig { max_in_flight: MAX_IN_FLIGHT, overflow: OverflowPolicy::Reject, ..Default::default() };
+    let (funnel, forwarder) = spawn_funnel(producer, config, stats.clone(), shutdown.clone()).unwrap();
+
+    let accepted = Arc::new(AtomicUsize::new(0));
+    let producers: Vec<_> = (0..4u64)
+        .map(|p| {
+            let funnel = funnel.clone();
+            let accepted = accepted.clone();
+            thread::spawn(move || {
+                for i in 0..50 {
+                    match funnel.submit(packet(p * 1_000 + i)) {
+                        Ok(()) => {
+                            accepted.fetch_add(1, Ordering::Relaxed);
+                        }
+                        Err(SubmitError::Backpressure(_)) => {}
+                        Err(SubmitError::Closed(_)) => panic!("funnel closed early"),
+                    }
+                }
+            })
+        })
+        .collect();
+    for producer in producers {
+        producer.join().unwrap();
+    }
+
+    // Nothing drains the ring, so at most ring + cap orders got in
+    let accepted = accepted.load(Ordering::Relaxed);
+    assert!(accepted <= RING_CAPACITY + MAX_IN_FLIGHT, "{}", accepted);
+    assert!(stats.in_flight() <= MAX_IN_FLIGHT);
+    assert_eq!(stats.rejected() as usize, 200 - accepted);
+
+    // Once the engine catches up every accepted order comes through
+    let mut received = 0;
+    let deadline = Instant::now() + Duration::from_secs(5);
+    while received < accepted && Instant::now() < deadline {
+        if consumer.pop().is_ok() {
+            received += 1;
+        }
+    }
+    assert_eq!(received, accepted);
+    assert_eq!(stats.in_flight(), 0);
+
+    shutdown.store(true, Ordering::Relaxed);
+    forwarder.join().unwrap();
+}
+
+#[test]
+fn blocking_producers_respect_the_cap_and_everything_drains() {
+    const PRODUCERS: u64 = 4;
+    const PER_PRODUCER: u64 = 250;
+
+    let (producer, mut consumer) = rtrb::RingBuffer::<Packet>::new(RING_CAPACITY);
+    let stats = Arc::new(FunnelStats::new());
+    let shutdown = Arc::new(AtomicBool::new(false));
+    let config = FunnelConfig { max_in_flight: MAX_IN_FLIGHT, overflow: OverflowPolicy::Block, ..Default::default() };
+    let (funnel, forwarder) = spawn_funnel(producer, config, stats.clone(), shutdown.clone()).unwrap();
+
+    let stop_watching = Arc::new(AtomicBool::new(false));
+    let watcher = watch_depth(stats.clone(), stop_watching.clone());
+
+    // A slow engine
+    let engine = thread::spawn(move || {
+        let mut received = Vec::new();
+        while received.len() < (PRODUCERS * PER_PRODUCER) as usize {
+            if let Ok(packet) = consumer.pop() {
+                received.push(packet.order.id);
+            }
+            thread::sleep(Duration::from_micros(20));
+        }
+        received
+    });
+
+    let producers: Vec<_> = (0..PRODUCERS)
+        .map(|p| {
+            let funnel = funnel.clone();
+            thread::spawn(move || {
+                for i in 0..PER_PRODUCER {
+                    funnel.submit(packet(p * 1_000 + i)).unwrap();
+                }
+            })
+        })
+        .collect();
+    for producer in producers {
+        producer.join().unwrap();
+    }
+
+    let mut received = engine.join().unwrap();
+    stop_watching.store(true, Ordering::Relaxed);
+    let peak = watcher.join().unwrap();
+    assert!(peak <= MAX_IN_FLIGHT, "peak in flight {}", peak);
+    assert_eq!(stats.rejected(), 0);
+
+    received.sort_unstable();
+    let mut expected: Vec<u64> = (0..PRODUCERS).flat_map(|p| (0..PER_PRODUCER).map(move |i| p * 1_000 + i)).collect();
+    expected.sort_unstable();
+    assert_eq!(received, expected);
+
+    shutdown.store(true, Ordering::Relaxed);
+    forwarder.join().unwrap();
+}
+
+#[test]
+fn draining_on_shutdown_forwards_every_accepted_order() {
+    const PRODUCERS: u64 = 4;
+
+    // Shutdown lands while producers are still submitting
+    for _ in 0..20 {
+        let (producer, mut consumer) = rtrb::RingBuffer::<Packet>::new(1_024);
+        let stats = Arc::new(FunnelStats::new());
+        let shutdown = Arc::new(AtomicBool::new(false));
+        let config = FunnelConfig { max_in_flight: 1_024, overflow: OverflowPolicy::Block, drain_on_shutdown: true };
+        let (funnel, forwarder) = spawn_funnel(producer, config, stats.clone(), shutdown.clone()).unwrap();
+
+        let engine_stop = Arc::new(AtomicBool::new(false));
+        let engine = {
+            let engine_stop = engine_stop.clone();
+            thread::spawn(move || {
+                let mut received = 0;
+                while !engine_stop.load(Ordering::Relaxed) || !consumer.is_empty() {
+                    if consumer.pop().is_ok() {
+                        received += 1;
+                    }
+                }
+                received
+            })
+        };
+        let producers: Vec<_> = (0..PRODUCERS)
+            .map(|p| {
+                let funnel = funnel.clone();
+                thread::spawn(move || {
+                    let mut accepted = 0;
+                    for i in 0.. {
+                        match funnel.submit(packet(p * 1_000_000 + i)) {
+                            Ok(()) => accepted += 1,
+                            Err(SubmitError::Closed(_)) => return accepted,
+                            Err(SubmitError::Backpressure(_)) => panic!("blocking funnel rejected"),
+                        }
+                    }
+                    accepted
+                })
+            })
+            .collect();
+
+        thread::sleep(Duration::from_millis(2));
+        shutdown.store(true, Ordering::Relaxed);
+        let accepted: usize = producers.into_iter().map(|p| p.join().unwrap()).sum();
+        forwarder.join().unwrap();
+        engine_stop.store(true, Ordering::Relaxed);
+
+        assert_eq!(engine.join().unwrap(), accepted);
+        assert_eq!(stats.in_flight(), 0);
+    }
+}
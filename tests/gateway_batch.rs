@@ -0,0 +1,77 @@
+// ============================================================================
+// GATEWAY BATCHES - A JSON array of orders on one line, one status each
+// ============================================================================
+
+mod common;
+
+use common::{wait_until, GatewayClient, TestServers};
+use hft_ringbuffer::funnel::{spawn_funnel, FunnelConfig, FunnelStats, OverflowPolicy};
+use hft_ringbuffer::gateway::{bind_gateway, spawn_gateway, GatewayRoutes};
+use hft_ringbuffer::ingress::IngressStats;
+use hft_ringbuffer::matching_engine::Packet;
+use serde_json::json;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+#[test]
+fn an_invalid_element_fails_alone_and_the_rest_keep_their_order() {
+    let servers = TestServers::start();
+    let mut client = GatewayClient::connect(&servers.gateway_addr);
+
+    let batch = json!([
+        {"id": 1, "side": "Buy", "price": 100, "quantity": 2},
+        {"id": 2, "side": "Hold", "price": 100, "quantity": 2},
+        {"id": 3, "side": "Buy", "price": 100, "quantity": 5},
+    ]);
+    let acks = client.send_line(&batch.to_string());
+    let acks = acks.as_array().expect("a batch is acked with an array");
+    assert_eq!(acks.len(), 3);
+    assert_eq!((&acks[0]["status"], &acks[0]["rested_quantity"]), (&json!("accepted"), &json!(2)));
+    assert_eq!((&acks[1]["status"], &acks[1]["reason"]), (&json!("error"), &json!("malformed")));
+    assert_eq!((&acks[2]["status"], &acks[2]["rested_quantity"]), (&json!("accepted"), &json!(5)));
+
+    let ids: Vec<u64> = servers.order_book.lock().unwrap().resting_orders().iter().map(|order| order.id).collect();
+    assert_eq!(ids, vec![1, 3]);
+    let totals = servers.metrics.ingress().totals();
+    assert_eq!((totals.orders_parsed, totals.parse_errors), (2, 1));
+
+    // Single objects still get a single ack, and an empty array an empty one
+    assert_eq!(client.send_line(r#"{"id": 4, "side": "Sell", "price": 100, "quantity": 2}"#)["status"], "filled");
+    assert_eq!(client.send_line("[]"), json!([]));
+    assert_eq!(client.send_line("[{\"id\": 5,")["status"], "error");
+    servers.stop();
+}
+
+#[test]
+fn elements_past_a_full_buffer_are_reported_dropped() {
+    // A one-slot ring nobody drains and room for two orders in flight
+    let (producer, consumer) = rtrb::RingBuffer::<Packet>::new(1);
+    let shutdown = Arc::new(AtomicBool::new(false));
+    let stats = Arc::new(FunnelStats::new());
+    let config = FunnelConfig { max_in_flight: 2, overflow: OverflowPolicy::Reject, ..Default::default() };
+    let (funnel, forwarder) = spawn_funnel(producer, config, stats.clone(), shutdown.clone()).unwrap();
+    let (listener, addr) = bind_gateway("127.0.0.1:0").unwrap();
+    let gateway = spawn_gateway(listener, GatewayRoutes::new("BTC", funnel), Arc::new(IngressStats::new()), shutdown.clone()).unwrap();
+    let mut client = GatewayClient::connect(&addr.to_string());
+
+    // The first fills the ring, the second is held by the forwarder
+    assert_eq!(client.send_line(r#"{"id": 1, "side": "Buy", "price": 100, "quantity": 1}"#)["status"], "accepted");
+    assert!(wait_until(|| consumer.slots() == 1 && stats.in_flight() == 0));
+    assert_eq!(client.send_line(r#"{"id": 2, "side": "Buy", "price": 100, "quantity": 1}"#)["status"], "accepted");
+
+    let batch = json!([
+        {"id": 3, "side": "Buy", "price": 100, "quantity": 1},
+        {"id": 4, "side": "Buy", "quantity": 1},
+        {"id": 5, "side": "Buy", "price": 100, "quantity": 1},
+    ]);
+    let acks = client.send_line(&batch.to_string());
+    assert_eq!(acks[0], json!({"status": "accepted"}));
+    assert_eq!(acks[1]["status"], "error");
+    assert_eq!(acks[2], json!({"status": "dropped", "reason": "backpressure"}));
+    assert_eq!(stats.rejected(), 1);
+
+    shutdown.store(true, Ordering::Relaxed);
+    drop(client);
+    gateway.join().unwrap();
+    forwarder.join().unwrap();
+}
@@ -0,0 +1,131 @@
+// ============================================================================
+// GATEWAY BURSTS - Pipelining clients, acks batched per read
+// ============================================================================
+
+mod common;
+
+use common::{wait_until, TestServers};
+use hft_ringbuffer::funnel::{spawn_funnel, FunnelConfig};
+use hft_ringbuffer::gateway::{bind_gateway, spawn_gateway, GatewayRoutes, MAX_ACK_BURST, RESULT_TIMEOUT};
+use hft_ringbuffer::matching_engine::{Packet, DEFAULT_BOOK_SYMBOL};
+use hft_ringbuffer::metrics::Metrics;
+use hft_ringbuffer::order_results::OrderResults;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::TcpStream;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+const CLIENTS: u64 = 8;
+const ORDERS_PER_CLIENT: u64 = 500;
+
+#[test]
+fn concurrent_pipelining_clients_lose_no_orders() {
+    let servers = TestServers::start();
+
+    let clients: Vec<_> = (0..CLIENTS)
+        .map(|client| {
+            let addr = servers.gateway_addr.clone();
+            thread::spawn(move || {
+                let mut stream = TcpStream::connect(&addr).unwrap();
+                stream.set_read_timeout(Some(Duration::from_secs(5))).unwrap();
+                // Bids only, one price per order, so every order rests
+                let burst: String = (0..ORDERS_PER_CLIENT)
+                    .map(|i| {
+                        let id = client * ORDERS_PER_CLIENT + i + 1;
+                        format!("{{\"id\": {}, \"side\": \"Buy\", \"price\": {}, \"quantity\": 1}}\n", id, id)
+                    })
+                    .collect();
+                stream.write_all(burst.as_bytes()).unwrap();
+
+                let mut reader = BufReader::new(stream);
+                let mut ack = String::new();
+                for _ in 0..ORDERS_PER_CLIENT {
+                    ack.clear();
+                    reader.read_line(&mut ack).unwrap();
+                    let ack: serde_json::Value = serde_json::from_str(&ack).unwrap();
+                    assert_eq!((&ack["status"], &ack["rested_quantity"]), (&"accepted".into(), &1.into()));
+                }
+            })
+        })
+        .collect();
+    for client in clients {
+        client.join().unwrap();
+    }
+
+    let total = (CLIENTS * ORDERS_PER_CLIENT) as usize;
+    assert!(wait_until(|| servers.order_book.lock().unwrap().resting_orders().len() == total));
+    assert_eq!(servers.metrics.ingress().totals().orders_parsed, total as u64);
+    servers.stop();
+}
+
+#[test]
+fn a_burst_read_in_one_go_is_acked_in_one_write_in_order() {
+    // Well inside one socket read and one ack burst
+    const BURST: u64 = 32;
+    assert!(BURST as usize <= MAX_ACK_BURST);
+    let servers = TestServers::start();
+    let mut stream = TcpStream::connect(&servers.gateway_addr).unwrap();
+    stream.set_read_timeout(Some(Duration::from_secs(5))).unwrap();
+    let mut buffer = vec![0u8; 64 * 1024];
+
+    // One ask for the whole burst to fill against
+    writeln!(stream, r#"{{"id": 1000, "side": "Sell", "price": 100, "quantity": {}}}"#, BURST).unwrap();
+    let read = stream.read(&mut buffer).unwrap();
+    let ack: serde_json::Value = serde_json::from_slice(&buffer[..read]).unwrap();
+    assert_eq!((&ack["status"], &ack["rested_quantity"]), (&"accepted".into(), &BURST.into()));
+
+    let burst: String = (1..=BURST)
+        .map(|id| format!("{{\"id\": {}, \"side\": \"Buy\", \"price\": 100, \"quantity\": 1}}\n", id))
+        .collect();
+    stream.write_all(burst.as_bytes()).unwrap();
+
+    // A single read returns every ack, so they left in a single write
+    let read = stream.read(&mut buffer).unwrap();
+    let acks = std::str::from_utf8(&buffer[..read]).unwrap();
+    let takers: Vec<u64> = acks
+        .lines()
+        .map(|line| {
+            let ack: serde_json::Value = serde_json::from_str(line).unwrap();
+            assert_eq!(ack["status"], "filled");
+            ack["executions"][0]["taker_order_id"].as_u64().unwrap()
+        })
+        .collect();
+    assert_eq!(takers, (1..=BURST).collect::<Vec<_>>());
+    servers.stop();
+}
+
+#[test]
+fn an_unanswered_burst_waits_out_one_timeout_not_one_per_order() {
+    const BURST: u64 = 4;
+    // Orders reach the ring but no engine ever answers them
+    let (listener, addr) = bind_gateway("127.0.0.1:0").unwrap();
+    let (producer, _consumer) = rtrb::RingBuffer::<Packet>::new(64);
+    let metrics = Metrics::new();
+    let shutdown = Arc::new(AtomicBool::new(false));
+    let (funnel, forwarder) = spawn_funnel(producer, FunnelConfig::default(), metrics.funnel().clone(), shutdown.clone()).unwrap();
+    let routes = GatewayRoutes::new(DEFAULT_BOOK_SYMBOL, funnel).with_results(Arc::new(OrderResults::new()));
+    let gateway = spawn_gateway(listener, routes, metrics.ingress().clone(), shutdown.clone()).unwrap();
+
+    let mut stream = TcpStream::connect(addr).unwrap();
+    stream.set_read_timeout(Some(Duration::from_secs(10))).unwrap();
+    let burst: String = (1..=BURST)
+        .map(|id| format!("{{\"id\": {}, \"side\": \"Buy\", \"price\": 100, \"quantity\": 1}}\n", id))
+        .collect();
+    let started = Instant::now();
+    stream.write_all(burst.as_bytes()).unwrap();
+    let mut reader = BufReader::new(stream);
+    let mut ack = String::new();
+    for _ in 0..BURST {
+        ack.clear();
+        reader.read_line(&mut ack).unwrap();
+        assert_eq!(ack.trim(), r#"{"status":"accepted"}"#);
+    }
+    // Well short of the BURST timeouts a per-ack wait would add up to
+    assert!(started.elapsed() < RESULT_TIMEOUT * 2, "acks took {:?}", started.elapsed());
+
+    shutdown.store(true, Ordering::Relaxed);
+    gateway.join().unwrap();
+    forwarder.join().unwrap();
+}
@@ -0,0 +1,128 @@
+// ============================================================================
+// GATEWAY EXECUTIONS - Acks carry the order's fills
+// ============================================================================
+
+mod common;
+
+use common::{GatewayClient, TestServers};
+use hft_ringbuffer::engine::{spawn_engine, EngineHooks};
+use hft_ringbuffer::matching_engine::{Order, OrderBook, OrderSide, Packet, TradeExecution};
+use hft_ringbuffer::metrics::Metrics;
+use hft_ringbuffer::order_results::{OrderOutcome, OrderResults};
+use hft_ringbuffer::price_band::PriceBand;
+use hft_ringbuffer::rejections::{EntryError, RejectReason};
+use hft_ringbuffer::price_units::Price;
+use serde_json::json;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+fn order(id: u64, side: &str, price: u64, quantity: u64) -> String {
+    json!({"id": id, "side": side, "price": price, "quantity": quantity}).to_string()
+}
+
+#[test]
+fn crossing_order_gets_its_executions_back() {
+    let servers = TestServers::start();
+    let mut maker = GatewayClient::connect(&servers.gateway_addr);
+    let mut taker = GatewayClient::connect(&servers.gateway_addr);
+
+    let resting = json!({"status": "accepted", "executions": [], "filled_quantity": 0, "rested_quantity": 2});
+    assert_eq!(maker.send_line(&order(1, "Sell", 100, 2)), resting);
+    assert_eq!(maker.send_line(&order(2, "Sell", 101, 4))["rested_quantity"], 4);
+
+    let ack = taker.send_line(&order(10, "Buy", 101, 5));
+    assert_eq!(ack["status"], "filled");
+    let executions: Vec<TradeExecution> = serde_json::from_value(ack["executions"].clone()).unwrap();
+    let fills: Vec<(u64, u64, u64, u64)> =
+        executions.iter().map(|e| (e.maker_order_id, e.taker_order_id, e.price.units(), e.quantity)).collect();
+    assert_eq!(fills, vec![(1, 10, 100, 2), (2, 10, 101, 3)]);
+
+    let ack = taker.send_line(&order(11, "Buy", 101, 4));
+    assert_eq!(ack["status"], "partially_filled");
+    assert_eq!(ack["executions"].as_array().unwrap().len(), 1);
+    assert_eq!(ack["executions"][0]["quantity"], 1);
+    assert_eq!((&ack["filled_quantity"], &ack["rested_quantity"]), (&json!(1), &json!(3)));
+    assert_eq!(servers.order_book.lock().unwrap().best_bid(), Some(Price(101)));
+    servers.stop();
+}
+
+#[test]
+fn engine_rejections_reach_the_client() {
+    let servers = TestServers::start();
+    let mut client = GatewayClient::connect(&servers.gateway_addr);
+    servers.order_book.lock().unwrap().set_price_band(Some(PriceBand::new(5.0).unwrap()));
+
+    assert_eq!(client.send_line(&order(1, "Sell", 100, 1))["status"], "accepted");
+    assert_eq!(client.send_line(&order(2, "Buy", 100, 1))["status"], "filled");
+    let ack = client.send_line(&order(3, "Buy", 200, 1));
+    assert_eq!(ack["status"], "error");
+    assert_eq!(ack["reason"], "price_band");
+    servers.stop();
+}
+
+#[test]
+fn registrations_are_answered_by_ticket() {
+    let results = OrderResults::new();
+    let first = results.register();
+    let dropped = results.register();
+    let second = results.register();
+    assert_ne!(first.ticket(), second.ticket());
+    results.forget(dropped);
+    assert_eq!(results.waiting(), 2);
+
+    // Answered out of registration order, and a stale ticket goes nowhere
+    let rejected = OrderOutcome::Rejected(EntryError::new(RejectReason::TickSize, "off tick"));
+    results.complete(second.ticket(), rejected.clone());
+    results.complete(first.ticket(), OrderOutcome::Executed { executions: Vec::new(), rested_quantity: 0 });
+    results.complete(second.ticket(), OrderOutcome::Executed { executions: Vec::new(), rested_quantity: 0 });
+    assert_eq!(first.receiver().try_recv().unwrap(), OrderOutcome::Executed { executions: Vec::new(), rested_quantity: 0 });
+    assert_eq!(second.receiver().try_recv().unwrap(), rejected);
+    assert!(second.receiver().try_recv().is_err());
+    assert_eq!(results.waiting(), 0);
+}
+
+#[test]
+fn clients_reusing_an_order_id_each_get_their_own_outcome() {
+    let servers = TestServers::start();
+    servers.order_book.lock().unwrap().set_duplicate_window(0);
+    let mut maker = GatewayClient::connect(&servers.gateway_addr);
+    let mut taker = GatewayClient::connect(&servers.gateway_addr);
+
+    // Same id from both connections: one rests, the other trades against it
+    assert_eq!(maker.send_line(&order(5, "Sell", 100, 2))["status"], "accepted");
+    let ack = taker.send_line(&order(5, "Buy", 100, 1));
+    assert_eq!(ack["status"], "filled");
+    assert_eq!(ack["executions"][0]["quantity"], 1);
+    // An order outside the band with the same id is refused, and only its sender hears it
+    servers.order_book.lock().unwrap().set_price_band(Some(PriceBand::new(5.0).unwrap()));
+    assert_eq!(taker.send_line(&order(5, "Buy", 200, 1))["reason"], "price_band");
+    assert_eq!(maker.send_line(&order(5, "Sell", 100, 1))["status"], "accepted");
+    servers.stop();
+}
+
+#[test]
+fn the_engine_answers_the_packet_that_carries_the_ticket() {
+    let results = Arc::new(OrderResults::new());
+    let (mut producer, consumer) = rtrb::RingBuffer::new(16);
+    let shutdown = Arc::new(AtomicBool::new(false));
+    let hooks = EngineHooks { results: Some(results.clone()), ..Default::default() };
+    let book = Arc::new(Mutex::new(OrderBook::new()));
+    let engine = spawn_engine(consumer, book, shutdown.clone(), Arc::new(Metrics::new()), hooks).unwrap();
+
+    // Nobody waits on the first order; the second reuses its id
+    let pending = results.register();
+    let mut taker = Packet::new(Order::new(5, OrderSide::Buy, 100, 1));
+    taker.ticket = Some(pending.ticket());
+    producer.push(Packet::new(Order::new(5, OrderSide::Sell, 100, 1))).unwrap();
+    producer.push(taker).unwrap();
+
+    let outcome = pending.receiver().recv_timeout(Duration::from_secs(5)).unwrap();
+    let OrderOutcome::Executed { executions, rested_quantity } = outcome else {
+        panic!("expected the taker's executions, got {:?}", outcome);
+    };
+    assert_eq!((executions.len(), executions[0].quantity, rested_quantity), (1, 1, 0));
+    assert_eq!(results.waiting(), 0);
+    shutdown.store(true, Ordering::Relaxed);
+    engine.join().unwrap();
+}
@@ -0,0 +1,94 @@
+// ============================================================================
+// GATEWAY FRAMING - Oversized lines, bad bytes and peers that hang up early
+// ============================================================================
+
+mod common;
+
+use common::{http_request, wait_until, GatewayClient, TestServers};
+use hft_ringbuffer::gateway::MAX_LINE_LEN;
+use std::io::{BufRead, BufReader, Write};
+use std::net::{Shutdown, TcpStream};
+use std::time::Duration;
+
+const ORDER: &str = r#"{"id":1,"side":"Buy","price":100,"quantity":5}"#;
+
+fn connect(addr: &str) -> (TcpStream, BufReader<TcpStream>) {
+    let stream = TcpStream::connect(addr).unwrap();
+    stream.set_read_timeout(Some(Duration::from_secs(5))).unwrap();
+    let reader = BufReader::new(stream.try_clone().unwrap());
+    (stream, reader)
+}
+
+fn read_ack(reader: &mut BufReader<TcpStream>) -> serde_json::Value {
+    let mut ack = String::new();
+    reader.read_line(&mut ack).unwrap();
+    serde_json::from_str(&ack).unwrap()
+}
+
+#[test]
+fn oversized_line_is_rejected_and_the_connection_survives() {
+    let servers = TestServers::start();
+    let mut client = GatewayClient::connect(&servers.gateway_addr);
+
+    // Far past the cap; the gateway skips it rather than buffering it
+    let huge = "x".repeat(4 * 1024 * 1024);
+    let ack = client.send_line(&huge);
+    assert_eq!(ack["status"], "error");
+    assert_eq!(ack["reason"], "malformed");
+    assert!(ack["detail"].as_str().unwrap().contains(&MAX_LINE_LEN.to_string()));
+
+    assert_eq!(client.send_line(ORDER)["status"], "accepted");
+    // A line right at the cap is still read and parsed
+    let padded = format!("{}{}", ORDER, " ".repeat(MAX_LINE_LEN - ORDER.len()));
+    assert_eq!(client.send_line(&padded)["status"], "accepted");
+
+    let totals = servers.metrics.ingress().totals();
+    assert_eq!((totals.orders_parsed, totals.parse_errors), (2, 1));
+    assert_eq!(totals.bytes_read, (huge.len() + ORDER.len() + padded.len() + 3) as u64);
+    servers.stop();
+}
+
+#[test]
+fn invalid_utf8_is_a_bad_line_not_a_disconnect() {
+    let servers = TestServers::start();
+    let (mut stream, mut reader) = connect(&servers.gateway_addr);
+
+    stream.write_all(b"{\"id\":\xff\xfe}\n").unwrap();
+    assert_eq!(read_ack(&mut reader)["status"], "error");
+    stream.write_all(format!("{}\n", ORDER).as_bytes()).unwrap();
+    assert_eq!(read_ack(&mut reader)["status"], "accepted");
+
+    let ingress = servers.metrics.ingress().clone();
+    assert_eq!(ingress.totals().parse_errors, 1);
+    drop((stream, reader));
+    assert!(wait_until(|| ingress.active_connections() == 0));
+    assert_eq!(ingress.abrupt_disconnects(), 0);
+    servers.stop();
+}
+
+#[test]
+fn hanging_up_mid_line_is_counted_as_abrupt() {
+    let servers = TestServers::start();
+    let ingress = servers.metrics.ingress().clone();
+
+    // Whole lines, then a clean close
+    let mut client = GatewayClient::connect(&servers.gateway_addr);
+    assert_eq!(client.send_line(ORDER)["status"], "accepted");
+    drop(client);
+    assert!(wait_until(|| ingress.active_connections() == 0));
+    assert_eq!(ingress.abrupt_disconnects(), 0);
+
+    // Half a line, then the write side closes: still rejected, and counted
+    let (mut stream, mut reader) = connect(&servers.gateway_addr);
+    stream.write_all(br#"{"id":2,"side":"Bu"#).unwrap();
+    stream.shutdown(Shutdown::Write).unwrap();
+    let ack = read_ack(&mut reader);
+    assert_eq!(ack["status"], "error");
+    assert!(wait_until(|| ingress.active_connections() == 0));
+    assert_eq!(ingress.abrupt_disconnects(), 1);
+    assert_eq!(ingress.totals().parse_errors, 1);
+
+    let (_, prometheus) = http_request(&servers.http_addr, "GET", "/metrics", "");
+    assert!(prometheus.contains("gateway_abrupt_disconnects_total 1"));
+    servers.stop();
+}
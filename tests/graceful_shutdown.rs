@@ -0,0 +1,89 @@
+// ============================================================================
+// GRACEFUL SHUTDOWN - Servers return once the flag is raised
+// ============================================================================
+
+use hft_ringbuffer::engine::PhasedShutdown;
+use hft_ringbuffer::funnel::{spawn_funnel, FunnelConfig, FunnelStats};
+use hft_ringbuffer::gateway::{bind_gateway, run_gateway, GatewayRoutes};
+use hft_ringbuffer::http_server::{bind_http_server, start_http_server};
+use hft_ringbuffer::ingress::IngressStats;
+use hft_ringbuffer::matching_engine::{OrderBook, Packet, DEFAULT_BOOK_SYMBOL};
+use hft_ringbuffer::metrics::Metrics;
+use hft_ringbuffer::signals::{install_signal_handlers, interrupted, spawn_signal_watch};
+use std::net::TcpStream;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+const RETURN_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Runs `server` on a thread and reports how it returned over a channel,
+/// so a server that never returns fails the test instead of hanging it.
+fn run_server(server: impl FnOnce() -> bool + Send + 'static) -> mpsc::Receiver<bool> {
+    let (tx, rx) = mpsc::channel();
+    thread::spawn(move || tx.send(server()).unwrap());
+    rx
+}
+
+#[test]
+fn gateway_returns_ok_with_a_client_still_connected() {
+    let (listener, addr) = bind_gateway("127.0.0.1:0").unwrap();
+    let (producer, _consumer) = rtrb::RingBuffer::<Packet>::new(16);
+    let shutdown = Arc::new(AtomicBool::new(false));
+    let (funnel, forwarder) =
+        spawn_funnel(producer, FunnelConfig::default(), Arc::new(FunnelStats::new()), shutdown.clone()).unwrap();
+    let routes = GatewayRoutes::new(DEFAULT_BOOK_SYMBOL, funnel);
+    let flag = shutdown.clone();
+    let returned = run_server(move || run_gateway(listener, routes, Arc::new(IngressStats::new()), flag).is_ok());
+
+    let _idle = TcpStream::connect(addr).unwrap();
+    thread::sleep(Duration::from_millis(50));
+    shutdown.store(true, Ordering::Relaxed);
+    assert!(returned.recv_timeout(RETURN_TIMEOUT).unwrap());
+    forwarder.join().unwrap();
+}
+
+#[test]
+fn http_server_returns_ok() {
+    let (server, _) = bind_http_server("127.0.0.1:0").unwrap();
+    let shutdown = Arc::new(AtomicBool::new(false));
+    let book = Arc::new(Mutex::new(OrderBook::new()));
+    let flag = shutdown.clone();
+    let returned = run_server(move || start_http_server(server, book, Default::default(), Arc::new(Metrics::new()), None, flag).is_ok());
+
+    thread::sleep(Duration::from_millis(50));
+    shutdown.store(true, Ordering::Relaxed);
+    assert!(returned.recv_timeout(RETURN_TIMEOUT).unwrap());
+}
+
+#[cfg(unix)]
+extern "C" {
+    fn raise(signum: i32) -> i32;
+}
+
+#[cfg(unix)]
+#[test]
+fn sigint_runs_the_phased_shutdown() {
+    let phases = PhasedShutdown::new();
+    let wait_for = |flag: Arc<AtomicBool>| {
+        thread::spawn(move || {
+            while !flag.load(Ordering::Relaxed) {
+                thread::sleep(Duration::from_millis(1));
+            }
+        })
+    };
+    let ingress = wait_for(phases.ingress_flag());
+    let engine = wait_for(phases.engine_flag());
+    let engine_flag = phases.engine_flag();
+    let watch = spawn_signal_watch(phases, vec![ingress], engine).unwrap();
+
+    install_signal_handlers();
+    assert!(!interrupted());
+    // Caught by the handler rather than terminating the test process
+    assert_eq!(unsafe { raise(2) }, 0);
+    assert!(interrupted());
+    watch.join().unwrap();
+    assert!(engine_flag.load(Ordering::Relaxed));
+}
@@ -0,0 +1,59 @@
+// ============================================================================
+// HEALTH - Liveness and engine-heartbeat readiness
+// ============================================================================
+
+mod common;
+
+use common::{http_request, TestServers};
+use hft_ringbuffer::http_server::{bind_http_server, start_http_server};
+use hft_ringbuffer::matching_engine::OrderBook;
+use hft_ringbuffer::metrics::{Metrics, ENGINE_STALL_THRESHOLD};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+#[test]
+fn running_engine_is_alive_and_ready() {
+    let servers = TestServers::start();
+    assert_eq!(http_request(&servers.http_addr, "GET", "/healthz", "").0, 200);
+    let (status, body) = http_request(&servers.http_addr, "GET", "/readyz", "");
+    assert_eq!(status, 200, "{}", body);
+    assert!(body.contains(r#""status":"ready""#), "{}", body);
+    servers.stop();
+}
+
+#[test]
+fn readiness_follows_the_engine_heartbeat() {
+    // No engine: the test plays its part by beating by hand
+    let metrics = Arc::new(Metrics::new());
+    let shutdown = Arc::new(AtomicBool::new(false));
+    let (server, addr) = bind_http_server("127.0.0.1:0").unwrap();
+    let http = {
+        let metrics = metrics.clone();
+        let shutdown = shutdown.clone();
+        thread::spawn(move || start_http_server(server, Arc::new(Mutex::new(OrderBook::new())), Default::default(), metrics, None, shutdown).unwrap())
+    };
+    let addr = addr.to_string();
+
+    let (status, body) = http_request(&addr, "GET", "/readyz", "");
+    assert_eq!(status, 503);
+    assert!(body.contains("engine has not started"), "{}", body);
+    assert_eq!(http_request(&addr, "GET", "/healthz", "").0, 200);
+
+    metrics.record_engine_heartbeat();
+    assert_eq!(http_request(&addr, "GET", "/readyz", "").0, 200);
+
+    // A stalled engine stops beating
+    thread::sleep(ENGINE_STALL_THRESHOLD + Duration::from_millis(100));
+    let (status, body) = http_request(&addr, "GET", "/readyz", "");
+    assert_eq!(status, 503);
+    assert!(body.contains("engine heartbeat is"), "{}", body);
+    assert_eq!(http_request(&addr, "GET", "/healthz", "").0, 200);
+
+    metrics.record_engine_heartbeat();
+    assert_eq!(http_request(&addr, "GET", "/readyz", "").0, 200);
+
+    shutdown.store(true, Ordering::Relaxed);
+    http.join().unwrap();
+}
@@ -0,0 +1,54 @@
+// ============================================================================
+// HTTP ERROR HANDLING - 4xx vs 500, always with a JSON body
+// ============================================================================
+
+mod common;
+
+use common::{http_request, TestServers};
+use serde_json::Value;
+use std::thread;
+
+fn error_body(body: &str) -> Value {
+    let value: Value = serde_json::from_str(body).expect("error responses are JSON");
+    assert_eq!(value["status"], "error");
+    assert!(value["reason"].is_string());
+    value
+}
+
+#[test]
+fn internal_error_returns_500_json_instead_of_hanging() {
+    let servers = TestServers::start();
+
+    // Poison the order book lock the way a panicking handler would
+    let book = servers.order_book.clone();
+    let _ = thread::spawn(move || {
+        let _guard = book.lock().unwrap();
+        panic!("simulated handler crash");
+    })
+    .join();
+
+    let (status, body) = http_request(&servers.http_addr, "GET", "/api/orderbook", "");
+    assert_eq!(status, 500);
+    error_body(&body);
+    assert_eq!(servers.metrics.http_server_errors(), 1);
+
+    servers.stop();
+}
+
+#[test]
+fn client_errors_are_4xx_json() {
+    let servers = TestServers::start();
+
+    let (status, body) = http_request(&servers.http_addr, "GET", "/api/nope", "");
+    assert_eq!(status, 404);
+    error_body(&body);
+
+    let (status, body) = http_request(&servers.http_addr, "POST", "/api/order", "{\"id\":");
+    assert_eq!(status, 400);
+    error_body(&body);
+
+    assert_eq!(servers.metrics.http_client_errors(), 2);
+    assert_eq!(servers.metrics.http_server_errors(), 0);
+
+    servers.stop();
+}
@@ -0,0 +1,78 @@
+// ============================================================================
+// ICEBERG DETECTION - Refills inferred from the public feed
+// ============================================================================
+
+use hft_ringbuffer::bbo::BboPublisher;
+use hft_ringbuffer::clock::ManualClock;
+use hft_ringbuffer::events::{BookEvent, EventBus};
+use hft_ringbuffer::iceberg_detection::{IcebergDetector, IcebergDetectorConfig, IcebergSignal};
+use hft_ringbuffer::matching_engine::{Order, OrderBook, OrderSide};
+use hft_ringbuffer::price_units::Price;
+use std::sync::Arc;
+
+/// Rests `resting`, then sends `takers` buys of `clip` at 100, publishing the
+/// BBO and trades exactly as the engine would, and runs the detector over
+/// what the bus delivered.
+fn replay(resting: &[Order], takers: u64, clip: u64) -> Vec<IcebergSignal> {
+    let bus = Arc::new(EventBus::new());
+    let feed = bus.subscribe();
+    let mut publisher = BboPublisher::new(0, Arc::new(ManualClock::new(0)), bus.clone());
+    let mut book = OrderBook::new();
+    for order in resting {
+        book.add_limit_order(order.clone());
+        publisher.on_book_change(&book);
+    }
+    for i in 0..takers {
+        let executions = book.add_limit_order(Order::new(1_000 + i, OrderSide::Buy, 100, clip));
+        publisher.on_book_change(&book);
+        for trade in executions {
+            bus.publish(BookEvent::Trade(trade));
+        }
+    }
+
+    let mut detector = IcebergDetector::new(IcebergDetectorConfig { min_refills: 1 });
+    feed.try_iter().filter_map(|message| detector.observe(&message.event)).collect()
+}
+
+#[test]
+fn refilling_level_is_flagged_with_rising_confidence() {
+    // Shows 5 at a time out of 30
+    let iceberg = Order::new(1, OrderSide::Sell, 100, 30).with_display_quantity(5);
+    let signals = replay(&[iceberg], 5, 5);
+
+    let refills: Vec<u32> = signals.iter().map(|s| s.refills).collect();
+    assert_eq!(refills, vec![1, 2, 3, 4]);
+    assert!(signals.iter().all(|s| s.side == OrderSide::Sell && s.price == 100));
+    assert!(signals.windows(2).all(|pair| pair[1].confidence > pair[0].confidence));
+    assert_eq!(signals[0].confidence, 0.5);
+    assert_eq!(signals.last().unwrap().hidden_quantity, 20);
+}
+
+#[test]
+fn plain_level_traded_down_is_not_flagged() {
+    // The same 15 at 100, all of it on display
+    let resting: Vec<Order> = (1..=3).map(|id| Order::new(id, OrderSide::Sell, 100, 5)).collect();
+    assert!(replay(&resting, 3, 5).is_empty());
+}
+
+#[test]
+fn signals_wait_for_the_configured_refills() {
+    let mut detector = IcebergDetector::new(IcebergDetectorConfig { min_refills: 3 });
+    let bbo = BookEvent::Bbo { bid: Some(Price(99)), ask: Some(Price(100)), bid_quantity: 1, ask_quantity: 2, timestamp_ns: 0, symbol: None };
+    assert!(detector.observe(&bbo).is_none());
+    let trade = |quantity| {
+        let mut book = OrderBook::new();
+        book.add_limit_order(Order::new(1, OrderSide::Sell, 100, quantity));
+        BookEvent::Trade(book.add_limit_order(Order::new(2, OrderSide::Buy, 100, quantity)).remove(0))
+    };
+
+    assert!(detector.observe(&trade(2)).is_none()); // exactly the display
+    assert!(detector.observe(&trade(2)).is_none()); // refill 1
+    assert!(detector.observe(&trade(2)).is_none()); // refill 2
+    let signal = detector.observe(&trade(2)).unwrap();
+    assert_eq!((signal.refills, signal.hidden_quantity), (3, 6));
+
+    // Once the level has left the touch, trades there no longer count
+    detector.observe(&BookEvent::Bbo { bid: Some(Price(99)), ask: Some(Price(101)), bid_quantity: 1, ask_quantity: 2, timestamp_ns: 0, symbol: None });
+    assert!(detector.observe(&trade(2)).is_none());
+}
@@ -0,0 +1,50 @@
+// ============================================================================
+// ICEBERG DISPLAY - Only the visible slice is ever shown
+// ============================================================================
+
+use hft_ringbuffer::matching_engine::{Order, OrderBook, OrderSide};
+use hft_ringbuffer::price_units::Price;
+
+fn book_with_iceberg() -> OrderBook {
+    let mut book = OrderBook::new();
+    book.add_limit_order(Order::new(1, OrderSide::Sell, 100, 50).with_display_quantity(10));
+    book.add_limit_order(Order::new(2, OrderSide::Sell, 100, 4));
+    book
+}
+
+/// Every way the book can be read from outside, reduced to the ask quantity at 100
+fn shown_at_100(book: &OrderBook) -> Vec<u64> {
+    let json: serde_json::Value = serde_json::from_str(&book.to_json()).unwrap();
+    let json_total: u64 = json["asks"][0]["orders"].as_array().unwrap().iter().map(|o| o["quantity"].as_u64().unwrap()).sum();
+    vec![
+        book.depth(1).asks[0].qty,
+        book.level_depth(1).asks[0].quantity,
+        book.symbol_depth(1).asks[0].1,
+        book.walk_asks().next().unwrap().1,
+        json_total,
+    ]
+}
+
+#[test]
+fn depth_never_reveals_the_reserve() {
+    let book = book_with_iceberg();
+    assert_eq!(shown_at_100(&book), vec![14; 5]);
+    assert!(!book.to_json().contains("hidden"));
+}
+
+#[test]
+fn large_iceberg_fills_in_visible_sized_chunks() {
+    let mut book = book_with_iceberg();
+
+    // Each slice re-queues behind order 2, so the taker alternates between them
+    let executions = book.add_limit_order(Order::new(10, OrderSide::Buy, 100, 30));
+    let fills: Vec<(u64, u64)> = executions.iter().map(|e| (e.maker_order_id, e.quantity)).collect();
+    assert_eq!(fills, vec![(1, 10), (2, 4), (1, 10), (1, 6)]);
+    assert_eq!(shown_at_100(&book), vec![4; 5]);
+
+    let executions = book.add_limit_order(Order::new(11, OrderSide::Buy, 100, 100));
+    assert!(executions.iter().all(|e| e.maker_order_id == 1 && e.quantity <= 10));
+    assert_eq!(executions.iter().map(|e| e.quantity).sum::<u64>(), 24);
+    assert_eq!(book.best_ask(), None);
+    assert_eq!(book.best_bid(), Some(Price(100)));
+}
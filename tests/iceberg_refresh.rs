@@ -0,0 +1,93 @@
+// ============================================================================
+// ICEBERG REFRESH - Randomized slices and rate-limited replenishment
+// ============================================================================
+
+use hft_ringbuffer::clock::ManualClock;
+use hft_ringbuffer::iceberg::IcebergRefresh;
+use hft_ringbuffer::matching_engine::{Order, OrderBook, OrderSide};
+use hft_ringbuffer::price_units::Price;
+use std::sync::Arc;
+
+fn iceberg_book(refresh: IcebergRefresh, clock: Arc<ManualClock>) -> OrderBook {
+    let mut book = OrderBook::new();
+    book.set_iceberg_refresh(refresh, clock);
+    book.add_limit_order(Order::new(1, OrderSide::Sell, 100, 100).with_display_quantity(10));
+    book
+}
+
+/// Lifts exactly the visible slice until the iceberg is gone, returning
+/// every slice size it showed.
+fn drain_slices(book: &mut OrderBook) -> Vec<u64> {
+    let mut slices = Vec::new();
+    let mut next_id = 100;
+    loop {
+        let Some((_, visible)) = book.walk_asks().next() else {
+            break;
+        };
+        slices.push(visible);
+        let executions = book.add_limit_order(Order::new(next_id, OrderSide::Buy, 100, visible));
+        assert_eq!(executions.iter().map(|e| e.quantity).sum::<u64>(), visible);
+        next_id += 1;
+    }
+    slices
+}
+
+#[test]
+fn seeded_slices_are_reproducible_and_deplete_the_reserve_exactly() {
+    let refresh = IcebergRefresh { slice_variance: 3, min_refresh_interval_ns: 0, seed: 42 };
+
+    let first = drain_slices(&mut iceberg_book(refresh, Arc::new(ManualClock::new(0))));
+    let second = drain_slices(&mut iceberg_book(refresh, Arc::new(ManualClock::new(0))));
+    assert_eq!(first, second);
+
+    // The initial slice is the nominal size; refreshes are jittered
+    assert_eq!(first[0], 10);
+    let (last, refreshed) = first[1..].split_last().unwrap();
+    assert!(refreshed.iter().all(|&slice| (7..=13).contains(&slice)), "{:?}", first);
+    assert!(*last <= 13);
+    assert!(refreshed.iter().any(|&slice| slice != 10), "{:?}", first);
+    assert_eq!(first.iter().sum::<u64>(), 100);
+
+    let other_seed = IcebergRefresh { seed: 7, ..refresh };
+    let third = drain_slices(&mut iceberg_book(other_seed, Arc::new(ManualClock::new(0))));
+    assert_ne!(first, third);
+    assert_eq!(third.iter().sum::<u64>(), 100);
+}
+
+#[test]
+fn one_taker_can_trade_through_several_refreshes() {
+    let mut book = iceberg_book(IcebergRefresh::default(), Arc::new(ManualClock::new(0)));
+
+    let executions = book.add_limit_order(Order::new(2, OrderSide::Buy, 100, 25));
+    let fills: Vec<u64> = executions.iter().map(|e| e.quantity).collect();
+    assert_eq!(fills, vec![10, 10, 5]);
+    assert_eq!(book.walk_asks().next(), Some((Price(100), 5)));
+    assert!(!book.to_json().contains("hidden"));
+}
+
+#[test]
+fn refresh_waits_for_the_minimum_interval() {
+    let clock = Arc::new(ManualClock::new(0));
+    let refresh = IcebergRefresh { slice_variance: 0, min_refresh_interval_ns: 1_000, seed: 0 };
+    let mut book = iceberg_book(refresh, clock.clone());
+
+    book.add_limit_order(Order::new(2, OrderSide::Buy, 100, 10));
+    assert_eq!(book.best_ask(), None);
+    assert_eq!(book.pending_iceberg_refreshes(), 1);
+
+    clock.set(999);
+    assert_eq!(book.refresh_icebergs(), 0);
+    assert_eq!(book.best_ask(), None);
+
+    clock.set(1_000);
+    assert_eq!(book.refresh_icebergs(), 1);
+    assert_eq!(book.walk_asks().next(), Some((Price(100), 10)));
+    assert_eq!(book.pending_iceberg_refreshes(), 0);
+
+    // A parked iceberg also comes back ahead of the next incoming order
+    book.add_limit_order(Order::new(3, OrderSide::Buy, 100, 10));
+    clock.set(2_000);
+    let executions = book.add_limit_order(Order::new(4, OrderSide::Buy, 100, 4));
+    assert_eq!(executions.len(), 1);
+    assert_eq!(executions[0].maker_order_id, 1);
+}
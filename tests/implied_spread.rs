@@ -0,0 +1,57 @@
+// ============================================================================
+// IMPLIED SPREADS - Spread quotes derived from the leg books
+// ============================================================================
+
+use hft_ringbuffer::matching_engine::{ImpliedTop, MatchingEngine, Order, OrderSide, SpreadDefinition};
+
+fn engine_with_spread() -> MatchingEngine {
+    let mut engine = MatchingEngine::new();
+    engine.add_spread(SpreadDefinition {
+        name: "BTC-MAR/JUN".to_string(),
+        front_leg: "BTC-MAR".to_string(),
+        back_leg: "BTC-JUN".to_string(),
+    });
+    engine
+}
+
+#[test]
+fn leg_quotes_produce_the_implied_spread_top() {
+    let mut engine = engine_with_spread();
+    engine.add_limit_order("BTC-MAR", Order::new(1, OrderSide::Buy, 105, 3));
+    engine.add_limit_order("BTC-MAR", Order::new(2, OrderSide::Sell, 107, 5));
+    engine.add_limit_order("BTC-JUN", Order::new(3, OrderSide::Buy, 100, 4));
+    engine.add_limit_order("BTC-JUN", Order::new(4, OrderSide::Sell, 101, 2));
+
+    assert_eq!(
+        engine.implied_top("BTC-MAR/JUN"),
+        Some(ImpliedTop { bid: Some((4, 2)), ask: Some((7, 4)) })
+    );
+}
+
+#[test]
+fn spread_updates_when_either_leg_top_changes() {
+    let mut engine = engine_with_spread();
+    engine.add_limit_order("BTC-MAR", Order::new(1, OrderSide::Buy, 105, 3));
+    assert_eq!(engine.implied_top("BTC-MAR/JUN"), Some(ImpliedTop::default()));
+
+    engine.add_limit_order("BTC-JUN", Order::new(2, OrderSide::Sell, 108, 6));
+    // Spread prices go negative when the back leg is richer
+    assert_eq!(engine.implied_top("BTC-MAR/JUN").unwrap().bid, Some((-3, 3)));
+
+    // A better back-leg offer improves the implied bid
+    engine.add_limit_order("BTC-JUN", Order::new(3, OrderSide::Sell, 104, 1));
+    assert_eq!(engine.implied_top("BTC-MAR/JUN").unwrap().bid, Some((1, 1)));
+
+    // Trading away the front bid removes it
+    engine.add_limit_order("BTC-MAR", Order::new(4, OrderSide::Sell, 105, 3));
+    assert_eq!(engine.implied_top("BTC-MAR/JUN").unwrap().bid, None);
+}
+
+#[test]
+fn symbols_do_not_cross_each_other() {
+    let mut engine = MatchingEngine::new();
+    engine.add_limit_order("A", Order::new(1, OrderSide::Buy, 100, 1));
+    let executions = engine.add_limit_order("B", Order::new(2, OrderSide::Sell, 90, 1));
+    assert!(executions.is_empty());
+    assert_eq!(engine.symbols(), vec!["A".to_string(), "B".to_string()]);
+}
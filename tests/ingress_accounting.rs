@@ -0,0 +1,68 @@
+// ============================================================================
+// INGRESS ACCOUNTING - Gateway bytes/orders/errors, per connection and total
+// ============================================================================
+
+mod common;
+
+use common::{http_request, wait_until, GatewayClient, TestServers};
+use hft_ringbuffer::ingress::{IngressSnapshot, IngressStats};
+use std::sync::Arc;
+
+#[test]
+fn guard_updates_connection_and_totals_and_unregisters_on_drop() {
+    let stats = Arc::new(IngressStats::new());
+    let a = stats.open("10.0.0.1:1000");
+    let b = stats.open("10.0.0.2:2000");
+
+    a.record_bytes(10);
+    a.record_order();
+    b.record_bytes(5);
+    b.record_parse_error();
+
+    assert_eq!(a.snapshot(), IngressSnapshot { bytes_read: 10, orders_parsed: 1, parse_errors: 0 });
+    assert_eq!(stats.totals(), IngressSnapshot { bytes_read: 15, orders_parsed: 1, parse_errors: 1 });
+    assert_eq!(stats.active_connections(), 2);
+
+    drop(a);
+    let connections = stats.connections();
+    assert_eq!(connections.len(), 1);
+    assert_eq!(connections[0].peer, "10.0.0.2:2000");
+    // Totals survive the connection closing
+    assert_eq!(stats.totals().bytes_read, 15);
+}
+
+#[test]
+fn gateway_counts_known_payloads() {
+    let servers = TestServers::start();
+    let mut client = GatewayClient::connect(&servers.gateway_addr);
+
+    let order = r#"{"id":1,"side":"Buy","price":100,"quantity":5}"#;
+    let garbage = "not json";
+    assert_eq!(client.send_line(order)["status"], "accepted");
+    assert_eq!(client.send_line(garbage)["status"], "error");
+    let expected_bytes = (order.len() + 1 + garbage.len() + 1) as u64;
+
+    let ingress = servers.metrics.ingress().clone();
+    assert_eq!(
+        ingress.totals(),
+        IngressSnapshot { bytes_read: expected_bytes, orders_parsed: 1, parse_errors: 1 }
+    );
+
+    let (status, body) = http_request(&servers.http_addr, "GET", "/api/connections", "");
+    assert_eq!(status, 200);
+    let json: serde_json::Value = serde_json::from_str(&body).unwrap();
+    let connections = json["connections"].as_array().unwrap();
+    assert_eq!(connections.len(), 1);
+    assert_eq!(connections[0]["bytes_read"], expected_bytes);
+    assert_eq!(connections[0]["orders_parsed"], 1);
+    assert_eq!(connections[0]["parse_errors"], 1);
+    assert_eq!(json["totals"]["bytes_read"], expected_bytes);
+
+    let (_, prometheus) = http_request(&servers.http_addr, "GET", "/metrics", "");
+    assert!(prometheus.contains(&format!("gateway_bytes_read_total {}", expected_bytes)));
+    assert!(prometheus.contains("gateway_active_connections 1"));
+
+    drop(client);
+    assert!(wait_until(|| ingress.active_connections() == 0));
+    servers.stop();
+}
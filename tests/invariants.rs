@@ -0,0 +1,57 @@
+// ============================================================================
+// ORDER BOOK INVARIANTS - Periodic validate() sweep in debug builds
+// ============================================================================
+
+use hft_ringbuffer::matching_engine::{Order, OrderBook, OrderSide};
+use std::panic::{catch_unwind, AssertUnwindSafe};
+
+fn two_sided_book() -> OrderBook {
+    let mut book = OrderBook::new();
+    book.add_limit_order(Order::new(1, OrderSide::Buy, 100, 5));
+    book.add_limit_order(Order::new(2, OrderSide::Sell, 105, 5));
+    book
+}
+
+#[test]
+fn healthy_book_validates() {
+    let book = two_sided_book();
+    assert_eq!(book.validate(), Ok(()));
+}
+
+#[test]
+fn validate_reports_a_crossed_book() {
+    let mut book = two_sided_book();
+    book.rest_order_unchecked(Order::new(3, OrderSide::Buy, 110, 1));
+    assert_eq!(book.validate(), Err("book is crossed: best bid 110 >= best ask 105".to_string()));
+}
+
+#[test]
+#[should_panic(expected = "order book invariant violated: book is crossed")]
+fn enabled_check_panics_on_corruption() {
+    let mut book = two_sided_book();
+    book.set_invariant_check_interval(1);
+    book.rest_order_unchecked(Order::new(3, OrderSide::Buy, 110, 1));
+}
+
+#[test]
+fn check_only_runs_every_nth_operation() {
+    let mut book = two_sided_book();
+    book.set_invariant_check_interval(3);
+
+    book.rest_order_unchecked(Order::new(3, OrderSide::Buy, 110, 1));
+    book.add_limit_order(Order::new(4, OrderSide::Buy, 90, 1));
+
+    let third = catch_unwind(AssertUnwindSafe(|| {
+        book.add_limit_order(Order::new(5, OrderSide::Buy, 91, 1));
+    }));
+    assert!(third.is_err(), "the third operation must trigger the sweep");
+}
+
+#[test]
+fn disabled_check_never_panics() {
+    let mut book = two_sided_book();
+    book.set_invariant_check_interval(0);
+    book.rest_order_unchecked(Order::new(3, OrderSide::Buy, 110, 1));
+    book.add_limit_order(Order::new(4, OrderSide::Buy, 90, 1));
+    assert!(book.validate().is_err());
+}
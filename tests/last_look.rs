@@ -0,0 +1,75 @@
+// ============================================================================
+// LAST LOOK - Provider rejects re-route the taker to the next resting order
+// ============================================================================
+
+use hft_ringbuffer::last_look::{LastLook, LastLookDecision, LastLookRequest};
+use hft_ringbuffer::matching_engine::{Order, OrderBook, OrderSide};
+use hft_ringbuffer::price_units::Price;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+const PROVIDER: u64 = 7;
+const OTHER: u64 = 8;
+
+/// Provider quotes 100, another account quotes 101.
+fn book_with_provider(decision: Option<LastLookDecision>) -> (OrderBook, Arc<Mutex<Vec<LastLookRequest>>>) {
+    let asked = Arc::new(Mutex::new(Vec::new()));
+    let mut book = OrderBook::new();
+    {
+        let asked = asked.clone();
+        book.set_last_look(Some(LastLook::new(
+            [PROVIDER],
+            Duration::from_secs(1),
+            Box::new(move |request| {
+                asked.lock().unwrap().push(*request);
+                decision
+            }),
+        )));
+    }
+    book.add_limit_order(Order::new(1, OrderSide::Sell, 100, 5).with_account(PROVIDER));
+    book.add_limit_order(Order::new(2, OrderSide::Sell, 101, 5).with_account(OTHER));
+    (book, asked)
+}
+
+#[test]
+fn rejected_quote_stays_and_taker_fills_at_the_next_level() {
+    let (mut book, asked) = book_with_provider(Some(LastLookDecision::Reject));
+
+    let executions = book.add_limit_order(Order::new(3, OrderSide::Buy, 101, 3));
+    assert_eq!(executions.len(), 1);
+    assert_eq!(executions[0].maker_order_id, 2);
+    assert_eq!(executions[0].price, 101);
+    assert_eq!(executions[0].quantity, 3);
+
+    assert_eq!(
+        *asked.lock().unwrap(),
+        vec![LastLookRequest { maker_order_id: 1, maker_account_id: PROVIDER, taker_order_id: 3, price: Price(100), quantity: 3 }]
+    );
+    assert_eq!(book.last_look().unwrap().rejections(), 1);
+    assert_eq!(book.best_ask(), Some(Price(100)));
+    assert_eq!(book.walk_asks().next(), Some((Price(100), 5)));
+    assert_eq!(book.position(PROVIDER), None);
+}
+
+#[test]
+fn unanswered_last_look_is_accepted() {
+    let (mut book, _) = book_with_provider(None);
+
+    let executions = book.add_limit_order(Order::new(3, OrderSide::Buy, 101, 3));
+    assert_eq!(executions.len(), 1);
+    assert_eq!(executions[0].maker_order_id, 1);
+    assert_eq!(executions[0].price, 100);
+    assert_eq!(book.last_look().unwrap().rejections(), 0);
+}
+
+#[test]
+fn other_accounts_skip_the_last_look() {
+    let (mut book, asked) = book_with_provider(Some(LastLookDecision::Reject));
+
+    let executions = book.add_limit_order(Order::new(3, OrderSide::Buy, 101, 8));
+    // Provider declines, so only the other account's 5 trade; 3 rest at 101
+    assert_eq!(executions.len(), 1);
+    assert_eq!(executions[0].maker_order_id, 2);
+    assert_eq!(asked.lock().unwrap().len(), 1);
+    assert_eq!(book.best_bid(), Some(Price(101)));
+}
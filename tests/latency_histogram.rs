@@ -0,0 +1,156 @@
+// ============================================================================
+// MATCH LATENCY HISTOGRAM - Prometheus exposition
+// ============================================================================
+
+mod common;
+
+use common::{http_request, wait_until, GatewayClient, TestServers};
+use hft_ringbuffer::histogram::{fine_buckets_ns, percentile_of_sorted, LatencyHistogram, LatencySamples, LatencySummary};
+
+/// Pulls `(le, value)` pairs for `<name>_bucket` lines out of an exposition.
+fn buckets(exposition: &str, name: &str) -> Vec<(String, u64)> {
+    let prefix = format!("{}_bucket{{le=\"", name);
+    exposition
+        .lines()
+        .filter_map(|line| line.strip_prefix(&prefix))
+        .map(|rest| {
+            let (le, value) = rest.split_once("\"} ").unwrap();
+            (le.to_string(), value.parse().unwrap())
+        })
+        .collect()
+}
+
+fn sample_value(exposition: &str, series: &str) -> f64 {
+    exposition
+        .lines()
+        .find_map(|line| line.strip_prefix(series)?.strip_prefix(' '))
+        .unwrap()
+        .parse()
+        .unwrap()
+}
+
+#[test]
+fn exposition_buckets_are_cumulative_and_monotonic() {
+    let histogram = LatencyHistogram::new(&[100, 1_000, 10_000]);
+    for ns in [50, 100, 150, 900, 5_000, 20_000, 20_000] {
+        histogram.record(ns);
+    }
+    let text = histogram.render_prometheus("match_latency_seconds", "test");
+
+    let buckets = buckets(&text, "match_latency_seconds");
+    let values: Vec<u64> = buckets.iter().map(|(_, v)| *v).collect();
+    assert_eq!(values, vec![2, 4, 5, 7]);
+    assert!(values.windows(2).all(|w| w[0] <= w[1]));
+    assert_eq!(buckets.last().unwrap().0, "+Inf");
+    assert_eq!(buckets[0].0, "0.0000001");
+
+    assert_eq!(sample_value(&text, "match_latency_seconds_count"), 7.0);
+    assert!((sample_value(&text, "match_latency_seconds_sum") - 46_200e-9).abs() < 1e-12);
+    assert!(text.contains("# TYPE match_latency_seconds histogram"));
+}
+
+#[test]
+fn metrics_endpoint_exposes_engine_match_latency() {
+    let servers = TestServers::start();
+    let mut client = GatewayClient::connect(&servers.gateway_addr);
+    for id in 0..5 {
+        client.send_line(&format!(r#"{{"id":{},"side":"Buy","price":100,"quantity":1}}"#, id));
+    }
+    assert!(wait_until(|| servers.metrics.match_latency().count() == 5));
+
+    let (status, body) = http_request(&servers.http_addr, "GET", "/metrics", "");
+    assert_eq!(status, 200);
+    let values: Vec<u64> = buckets(&body, "match_latency_seconds").iter().map(|(_, v)| *v).collect();
+    assert!(values.windows(2).all(|w| w[0] <= w[1]));
+    assert_eq!(*values.last().unwrap(), 5);
+
+    servers.stop();
+}
+
+#[test]
+fn percentiles_come_from_bucket_bounds_and_are_ordered() {
+    let histogram = LatencyHistogram::new(&fine_buckets_ns(100, 1_000_000));
+    assert_eq!(histogram.percentile(0.5), None);
+    for ns in 1..=1_000 {
+        histogram.record(ns * 100);
+    }
+
+    let percentiles: Vec<u64> = [0.5, 0.9, 0.99, 0.999].iter().map(|&q| histogram.percentile(q).unwrap()).collect();
+    assert!(percentiles.windows(2).all(|w| w[0] <= w[1]), "{:?}", percentiles);
+    // Each sits at the bound just above the exact value
+    assert_eq!(percentiles[0], 50_000);
+    assert_eq!(percentiles[1], 100_000);
+    assert_eq!(percentiles[3], 100_000);
+    assert_eq!(histogram.percentile(1.0), Some(100_000));
+    assert_eq!(histogram.max_ns(), 100_000);
+}
+
+#[test]
+fn overflow_percentiles_report_the_largest_sample() {
+    let histogram = LatencyHistogram::new(&[100, 1_000]);
+    for ns in [50, 5_000, 7_000] {
+        histogram.record(ns);
+    }
+    assert_eq!(histogram.percentile(0.0), Some(100));
+    assert_eq!(histogram.percentile(0.99), Some(7_000));
+}
+
+#[test]
+fn latency_endpoint_reports_end_to_end_percentiles() {
+    let servers = TestServers::start();
+    let mut client = GatewayClient::connect(&servers.gateway_addr);
+    for id in 0..20 {
+        client.send_line(&format!(r#"{{"id":{},"side":"Sell","price":{},"quantity":1}}"#, id, 100 + id));
+    }
+    assert!(wait_until(|| servers.metrics.end_to_end_latency().count() == 20));
+
+    let (status, body) = http_request(&servers.http_addr, "GET", "/api/latency", "");
+    assert_eq!(status, 200);
+    let latency: serde_json::Value = serde_json::from_str(&body).unwrap();
+    assert_eq!(latency["count"], 20);
+    let percentiles: Vec<u64> = ["p50_ns", "p90_ns", "p99_ns", "p999_ns", "max_ns"]
+        .iter()
+        .map(|key| latency[key].as_u64().unwrap())
+        .collect();
+    assert!(percentiles[0] > 0);
+    assert!(percentiles.windows(2).all(|w| w[0] <= w[1]), "{:?}", percentiles);
+    servers.stop();
+}
+
+#[test]
+fn exact_percentiles_use_nearest_rank() {
+    let sorted: Vec<u64> = (1..=1_000).collect();
+    assert_eq!(percentile_of_sorted(&sorted, 0.50), Some(500));
+    assert_eq!(percentile_of_sorted(&sorted, 0.99), Some(990));
+    assert_eq!(percentile_of_sorted(&sorted, 0.999), Some(999));
+    assert_eq!(percentile_of_sorted(&sorted, 1.0), Some(1_000));
+    assert_eq!(percentile_of_sorted(&sorted, 0.0), Some(1));
+    assert_eq!(percentile_of_sorted(&[42], 0.999), Some(42));
+    assert_eq!(percentile_of_sorted(&[], 0.5), None);
+}
+
+#[test]
+fn samples_summarize_out_of_order_input_and_keep_it_for_csv() {
+    // 1..=1000 shuffled by a stride coprime to the length
+    let mut samples = LatencySamples::with_capacity(1_000);
+    for i in 0..1_000u64 {
+        samples.record(i * 7 % 1_000 + 1);
+    }
+    samples.record(1_000_000);
+
+    assert_eq!(
+        samples.summary(),
+        Some(LatencySummary { count: 1_000, min_ns: 1, p50_ns: 500, p99_ns: 990, p999_ns: 999, max_ns: 1_000 })
+    );
+    assert_eq!(samples.dropped(), 1, "recording past the capacity must not grow the buffer");
+    assert!(LatencySamples::with_capacity(4).summary().is_none());
+
+    let mut csv = Vec::new();
+    samples.write_csv(&mut csv).unwrap();
+    let csv = String::from_utf8(csv).unwrap();
+    let mut lines = csv.lines();
+    assert_eq!(lines.next(), Some("sample,latency_ns"));
+    assert_eq!(lines.next(), Some("0,1"));
+    assert_eq!(lines.next(), Some("1,8"));
+    assert_eq!(lines.count(), 998);
+}
@@ -0,0 +1,64 @@
+// ============================================================================
+// LEVEL METADATA - Distinct accounts and first-seen time per price level
+// ============================================================================
+
+use hft_ringbuffer::clock::ManualClock;
+use hft_ringbuffer::level_metadata::LevelMetadata;
+use hft_ringbuffer::matching_engine::{Order, OrderBook, OrderSide};
+use std::sync::Arc;
+
+fn tracked_book() -> (OrderBook, Arc<ManualClock>) {
+    let clock = Arc::new(ManualClock::new(1_000));
+    let mut book = OrderBook::new();
+    book.set_level_metadata(true, clock.clone());
+    (book, clock)
+}
+
+#[test]
+fn two_accounts_at_one_level_report_two_and_the_first_arrival() {
+    let (mut book, clock) = tracked_book();
+    book.add_limit_order(Order::new(1, OrderSide::Buy, 100, 5).with_account(7));
+    clock.advance(50);
+    book.add_limit_order(Order::new(2, OrderSide::Buy, 100, 5).with_account(8));
+    clock.advance(50);
+    book.add_limit_order(Order::new(3, OrderSide::Buy, 100, 5).with_account(7));
+    book.add_limit_order(Order::new(4, OrderSide::Buy, 100, 5).with_account(8));
+
+    assert_eq!(
+        book.level_metadata(OrderSide::Buy, 100),
+        Some(LevelMetadata { distinct_accounts: 2, first_seen_ns: 1_000 })
+    );
+    let depth = book.level_depth(10);
+    assert_eq!(depth.bids[0].quantity, 20);
+    assert_eq!(depth.bids[0].metadata.unwrap().distinct_accounts, 2);
+}
+
+#[test]
+fn fills_and_amends_keep_the_metadata_current() {
+    let (mut book, clock) = tracked_book();
+    book.add_limit_order(Order::new(1, OrderSide::Sell, 100, 5).with_account(7));
+    book.add_limit_order(Order::new(2, OrderSide::Sell, 100, 5).with_account(8));
+
+    // One of them is filled away, leaving a single-account level
+    book.add_limit_order(Order::new(3, OrderSide::Buy, 100, 5));
+    assert_eq!(book.level_metadata(OrderSide::Sell, 100).unwrap().distinct_accounts, 1);
+
+    // Emptying the level drops it; refilling starts a new first-seen time
+    let remaining = book.resting_orders()[0].id;
+    book.amend_order(remaining, None, Some(0)).unwrap();
+    assert_eq!(book.level_metadata(OrderSide::Sell, 100), None);
+    clock.advance(500);
+    book.add_limit_order(Order::new(4, OrderSide::Sell, 100, 5).with_account(9));
+    assert_eq!(
+        book.level_metadata(OrderSide::Sell, 100),
+        Some(LevelMetadata { distinct_accounts: 1, first_seen_ns: 1_500 })
+    );
+}
+
+#[test]
+fn untracked_books_report_no_metadata() {
+    let mut book = OrderBook::new();
+    book.add_limit_order(Order::new(1, OrderSide::Buy, 100, 5).with_account(7));
+    assert_eq!(book.level_metadata(OrderSide::Buy, 100), None);
+    assert_eq!(book.level_depth(10).bids[0].metadata, None);
+}
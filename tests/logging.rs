@@ -0,0 +1,92 @@
+// ============================================================================
+// LOGGING - Engine messages go through the `log` facade
+// ============================================================================
+
+mod common;
+
+use common::wait_until;
+use hft_ringbuffer::engine::{spawn_engine, EngineHooks};
+use hft_ringbuffer::matching_engine::{Order, OrderBook, OrderSide, Packet};
+use hft_ringbuffer::metrics::Metrics;
+use hft_ringbuffer::trade_history::{TradeHistory, TradeSink, TRADE_LOG_TARGET};
+use log::{Level, LevelFilter, Log, Metadata, Record};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, Once};
+
+/// Every record logged in this test binary, as `(level, target, message)`
+struct Capture(Mutex<Vec<(Level, String, String)>>);
+
+impl Log for Capture {
+    fn enabled(&self, _: &Metadata) -> bool {
+        true
+    }
+
+    fn log(&self, record: &Record) {
+        self.0.lock().unwrap().push((record.level(), record.target().to_string(), record.args().to_string()));
+    }
+
+    fn flush(&self) {}
+}
+
+static CAPTURE: Capture = Capture(Mutex::new(Vec::new()));
+
+fn capture() -> &'static Capture {
+    static INSTALL: Once = Once::new();
+    INSTALL.call_once(|| {
+        log::set_logger(&CAPTURE).unwrap();
+        log::set_max_level(LevelFilter::Trace);
+    });
+    &CAPTURE
+}
+
+fn logged_trade(taker: u64, maker: u64) -> bool {
+    let needle = format!("TRADE: {} matched with {} @", taker, maker);
+    capture().0.lock().unwrap().iter().any(|(level, target, message)| {
+        *level == Level::Info && target == TRADE_LOG_TARGET && message.contains(&needle)
+    })
+}
+
+/// Crosses `taker` against `maker` on an engine writing trades through `sink`
+fn cross(sink: TradeSink, maker: u64, taker: u64) {
+    let (mut producer, consumer) = rtrb::RingBuffer::<Packet>::new(16);
+    let book = Arc::new(Mutex::new(OrderBook::new()));
+    let shutdown = Arc::new(AtomicBool::new(false));
+    let hooks = EngineHooks { trades: Some(sink), ..Default::default() };
+    let engine = spawn_engine(consumer, book.clone(), shutdown.clone(), Arc::new(Metrics::new()), hooks).unwrap();
+
+    producer.push(Packet::new(Order::new(maker, OrderSide::Sell, 100, 3))).unwrap();
+    producer.push(Packet::new(Order::new(taker, OrderSide::Buy, 100, 3))).unwrap();
+    assert!(wait_until(|| logged_trade(taker, maker)), "no trade record for {} against {}", taker, maker);
+
+    shutdown.store(true, Ordering::Relaxed);
+    engine.join().unwrap();
+}
+
+#[test]
+fn an_offloaded_trade_emits_an_info_record() {
+    capture();
+    let (sink, writer) = TradeSink::offloaded(Arc::new(Mutex::new(TradeHistory::new(16))), None).unwrap();
+    cross(sink, 1, 2);
+    writer.join().unwrap();
+}
+
+#[test]
+fn an_inline_trade_emits_an_info_record() {
+    capture();
+    cross(TradeSink::Inline(Arc::new(Mutex::new(TradeHistory::new(16)))), 11, 12);
+}
+
+#[test]
+fn rejections_are_logged_as_warnings() {
+    capture();
+    let (mut producer, consumer) = rtrb::RingBuffer::<Packet>::new(16);
+    let shutdown = Arc::new(AtomicBool::new(false));
+    let engine = spawn_engine(consumer, Arc::new(Mutex::new(OrderBook::new())), shutdown.clone(), Arc::new(Metrics::new()), EngineHooks::default()).unwrap();
+
+    producer.push(Packet::new(Order::new(21, OrderSide::Buy, 100, 0))).unwrap();
+    assert!(wait_until(|| {
+        capture().0.lock().unwrap().iter().any(|(level, _, message)| *level == Level::Warn && message.contains("Order 21 rejected"))
+    }));
+    shutdown.store(true, Ordering::Relaxed);
+    engine.join().unwrap();
+}
@@ -0,0 +1,44 @@
+// ============================================================================
+// MARKET ORDERS - Sweep the opposite side, never rest
+// ============================================================================
+
+use hft_ringbuffer::matching_engine::{Order, OrderBook, OrderSide};
+use hft_ringbuffer::price_units::Price;
+
+fn three_ask_levels() -> OrderBook {
+    let mut book = OrderBook::new();
+    book.add_limit_order(Order::new(1, OrderSide::Sell, 100, 3));
+    book.add_limit_order(Order::new(2, OrderSide::Sell, 102, 4));
+    book.add_limit_order(Order::new(3, OrderSide::Sell, 105, 5));
+    book.add_limit_order(Order::new(4, OrderSide::Buy, 90, 5));
+    book
+}
+
+#[test]
+fn market_buy_walks_the_ask_levels() {
+    let mut book = three_ask_levels();
+    let result = book.add_market_order(OrderSide::Buy, 9, 10);
+
+    let fills: Vec<(u64, u64, u64)> = result.executions.iter().map(|e| (e.maker_order_id, e.price.units(), e.quantity)).collect();
+    assert_eq!(fills, vec![(1, 100, 3), (2, 102, 4), (3, 105, 2)]);
+    assert!(result.executions.iter().all(|e| e.taker_order_id == 10));
+    assert_eq!(result.unfilled_quantity, 0);
+    assert_eq!(book.best_ask(), Some(Price(105)));
+}
+
+#[test]
+fn remainder_of_an_exhausted_book_is_reported_not_rested() {
+    let mut book = three_ask_levels();
+    let result = book.add_market_order(OrderSide::Buy, 20, 10);
+
+    assert_eq!(result.executions.iter().map(|e| e.quantity).sum::<u64>(), 12);
+    assert_eq!(result.unfilled_quantity, 8);
+    assert_eq!(result.rested_quantity, 0);
+    assert_eq!(book.best_ask(), None);
+    assert_eq!(book.best_bid(), Some(Price(90)));
+
+    let result = book.add_market_order(OrderSide::Sell, 7, 11);
+    assert_eq!(result.executions.len(), 1);
+    assert_eq!(result.unfilled_quantity, 2);
+    assert_eq!(book.best_bid(), None);
+}
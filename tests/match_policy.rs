@@ -0,0 +1,118 @@
+// ============================================================================
+// MATCH POLICY - FIFO vs pro-rata allocation inside a crossed level
+// ============================================================================
+
+use hft_ringbuffer::match_policy::{policy_by_name, FifoPolicy, MatchPolicy, ProRataPolicy};
+use hft_ringbuffer::matching_engine::{Order, OrderBook, OrderSide, TradeExecution};
+use hft_ringbuffer::price_units::Price;
+use std::sync::Arc;
+
+fn allocate(policy: &dyn MatchPolicy, incoming: u64, resting: &[u64]) -> Vec<u64> {
+    policy.allocate(incoming, &mut resting.iter().copied())
+}
+
+fn fills(executions: &[TradeExecution]) -> Vec<(u64, Price, u64)> {
+    executions.iter().map(|e| (e.maker_order_id, e.price, e.quantity)).collect()
+}
+
+fn pro_rata_book() -> OrderBook {
+    OrderBook::with_match_policy(Arc::new(ProRataPolicy))
+}
+
+#[test]
+fn fifo_fills_the_queue_in_order_and_stops_when_done() {
+    assert_eq!(allocate(&FifoPolicy, 12, &[5, 5, 5, 5]), vec![5, 5, 2]);
+    assert_eq!(allocate(&FifoPolicy, 30, &[5, 5]), vec![5, 5]);
+    assert!(allocate(&FifoPolicy, 0, &[5]).is_empty());
+}
+
+#[test]
+fn pro_rata_shares_are_proportional_and_rounding_goes_in_queue_order() {
+    assert_eq!(allocate(&ProRataPolicy, 10, &[10, 10]), vec![5, 5]);
+    assert_eq!(allocate(&ProRataPolicy, 20, &[30, 10]), vec![15, 5]);
+    assert_eq!(allocate(&ProRataPolicy, 3, &[10, 10]), vec![2, 1]);
+    assert_eq!(allocate(&ProRataPolicy, 2, &[1, 1, 1]), vec![1, 1, 0]);
+    // More than the level holds fills every order completely
+    assert_eq!(allocate(&ProRataPolicy, 50, &[7, 3]), vec![7, 3]);
+
+    for (incoming, resting) in [(17, vec![3, 9, 1, 4, 8]), (1, vec![5, 5, 5]), (99, vec![33, 34, 32])] {
+        let shares = allocate(&ProRataPolicy, incoming, &resting);
+        let total: u64 = resting.iter().sum();
+        assert_eq!(shares.iter().sum::<u64>(), incoming.min(total), "{} over {:?}", incoming, resting);
+        assert!(shares.iter().zip(&resting).all(|(share, size)| share <= size));
+    }
+}
+
+#[test]
+fn pro_rata_splits_a_taker_across_two_equal_resting_orders() {
+    let mut book = pro_rata_book();
+    book.submit_order(Order::new(1, OrderSide::Sell, 100, 10)).unwrap();
+    book.submit_order(Order::new(2, OrderSide::Sell, 100, 10)).unwrap();
+
+    let executions = book.submit_order(Order::new(3, OrderSide::Buy, 100, 10)).unwrap();
+    assert_eq!(fills(&executions), vec![(1, Price(100), 5), (2, Price(100), 5)]);
+    assert_eq!(executions.last().map(|e| (e.remaining_quantity, e.is_taker_complete)), Some((0, true)));
+
+    // Both keep their remainder and their queue order
+    assert_eq!(book.resting_quantity(1), Some(5));
+    assert_eq!(book.resting_quantity(2), Some(5));
+    let queue: Vec<u64> = book.resting_orders().iter().map(|order| order.id).collect();
+    assert_eq!(queue, vec![1, 2]);
+    book.validate().unwrap();
+}
+
+#[test]
+fn the_same_flow_under_fifo_takes_the_oldest_order_first() {
+    let mut book = OrderBook::new();
+    book.submit_order(Order::new(1, OrderSide::Sell, 100, 10)).unwrap();
+    book.submit_order(Order::new(2, OrderSide::Sell, 100, 10)).unwrap();
+
+    let executions = book.submit_order(Order::new(3, OrderSide::Buy, 100, 10)).unwrap();
+    assert_eq!(fills(&executions), vec![(1, Price(100), 10)]);
+    assert_eq!(book.resting_quantity(2), Some(10));
+}
+
+#[test]
+fn pro_rata_takers_sweep_level_by_level() {
+    let mut book = pro_rata_book();
+    book.submit_order(Order::new(1, OrderSide::Buy, 101, 30)).unwrap();
+    book.submit_order(Order::new(2, OrderSide::Buy, 101, 10)).unwrap();
+    book.submit_order(Order::new(3, OrderSide::Buy, 100, 6)).unwrap();
+    book.submit_order(Order::new(4, OrderSide::Buy, 100, 2)).unwrap();
+
+    // Clears 101 completely, then splits the last 4 lots 3:1 at 100
+    let executions = book.submit_order(Order::new(5, OrderSide::Sell, 100, 44)).unwrap();
+    assert_eq!(
+        fills(&executions),
+        vec![(1, Price(101), 30), (2, Price(101), 10), (3, Price(100), 3), (4, Price(100), 1)]
+    );
+    assert_eq!(book.best_bid(), Some(Price(100)));
+    assert_eq!((book.resting_quantity(3), book.resting_quantity(4)), (Some(3), Some(1)));
+    book.validate().unwrap();
+}
+
+#[test]
+fn pro_rata_small_takers_leave_zero_share_orders_in_place() {
+    let mut book = pro_rata_book();
+    for id in 1..=3 {
+        book.submit_order(Order::new(id, OrderSide::Sell, 100, 1)).unwrap();
+    }
+
+    let executions = book.submit_order(Order::new(9, OrderSide::Buy, 100, 2)).unwrap();
+    assert_eq!(fills(&executions), vec![(1, Price(100), 1), (2, Price(100), 1)]);
+    assert_eq!(book.resting_orders().iter().map(|order| order.id).collect::<Vec<_>>(), vec![3]);
+    book.validate().unwrap();
+}
+
+#[test]
+fn policies_are_chosen_by_name() {
+    let mut book = OrderBook::new();
+    book.set_match_policy(policy_by_name("pro-rata").unwrap());
+    book.submit_order(Order::new(1, OrderSide::Sell, 100, 4)).unwrap();
+    book.submit_order(Order::new(2, OrderSide::Sell, 100, 4)).unwrap();
+    let executions = book.submit_order(Order::new(3, OrderSide::Buy, 100, 4)).unwrap();
+    assert_eq!(fills(&executions), vec![(1, Price(100), 2), (2, Price(100), 2)]);
+
+    assert!(policy_by_name(" fifo ").is_ok());
+    assert!(policy_by_name("lifo").is_err_and(|e| e.contains("lifo")));
+}
@@ -0,0 +1,78 @@
+// ============================================================================
+// NDJSON EXPORT - One JSON line per resting order
+// ============================================================================
+
+mod common;
+
+use common::{http_exchange, http_request, TestServers};
+use hft_ringbuffer::matching_engine::{Order, OrderBook, OrderSide};
+use std::sync::{Arc, Mutex};
+
+fn parse_lines(export: &str) -> Vec<Order> {
+    export.lines().map(|line| serde_json::from_str(line).unwrap()).collect()
+}
+
+#[test]
+fn export_writes_one_line_per_resting_order() {
+    let mut book = OrderBook::new();
+    book.add_limit_order(Order::new(1, OrderSide::Buy, 99, 5));
+    book.add_limit_order(Order::new(2, OrderSide::Buy, 100, 3));
+    book.add_limit_order(Order::new(3, OrderSide::Sell, 102, 4));
+    // Partly fills order 3, which keeps resting
+    book.add_limit_order(Order::new(4, OrderSide::Buy, 102, 1));
+
+    let mut out = Vec::new();
+    assert_eq!(book.export_ndjson(&mut out).unwrap(), 3);
+    let export = String::from_utf8(out).unwrap();
+    assert!(export.ends_with('\n'));
+    assert_eq!(parse_lines(&export), book.resting_orders());
+    assert_eq!(book.resting_quantity(3), Some(3));
+}
+
+#[test]
+fn an_empty_book_exports_nothing() {
+    let mut out = Vec::new();
+    assert_eq!(OrderBook::new().export_ndjson(&mut out).unwrap(), 0);
+    assert!(out.is_empty());
+}
+
+#[test]
+fn large_books_export_every_order() {
+    let mut book = OrderBook::new();
+    for id in 1..=20_000 {
+        let side = if id % 2 == 0 { OrderSide::Buy } else { OrderSide::Sell };
+        let price = if side == OrderSide::Buy { 1_000 - id % 100 } else { 1_001 + id % 100 };
+        book.add_limit_order(Order::new(id, side, price, 1));
+    }
+
+    let mut out = Vec::new();
+    assert_eq!(book.export_ndjson(&mut out).unwrap(), 20_000);
+    let ids: Vec<u64> = parse_lines(std::str::from_utf8(&out).unwrap()).iter().map(|order| order.id).collect();
+    assert_eq!(ids, book.resting_orders().iter().map(|order| order.id).collect::<Vec<_>>());
+}
+
+#[test]
+fn export_is_streamed_over_http() {
+    let servers = TestServers::start();
+    {
+        let mut book = servers.order_book.lock().unwrap();
+        book.add_limit_order(Order::new(1, OrderSide::Buy, 100, 2));
+        book.add_limit_order(Order::new(2, OrderSide::Sell, 101, 7));
+    }
+
+    let (status, headers, body) = http_exchange(&servers.http_addr, "GET", "/api/export", "");
+    assert_eq!(status, 200);
+    assert!(headers.iter().any(|h| h == "Content-Type: application/x-ndjson"));
+    assert_eq!(parse_lines(&body), servers.order_book.lock().unwrap().resting_orders());
+
+    let eth = Arc::new(Mutex::new(OrderBook::new()));
+    eth.lock().unwrap().add_limit_order(Order::new(9, OrderSide::Sell, 3_000, 1));
+    servers.metrics.register_symbol_book("ETH", eth);
+    let (status, body) = http_request(&servers.http_addr, "GET", "/api/export?symbol=ETH", "");
+    assert_eq!(status, 200);
+    assert_eq!(parse_lines(&body).iter().map(|order| order.id).collect::<Vec<_>>(), vec![9]);
+
+    let (status, _) = http_request(&servers.http_addr, "GET", "/api/export?symbol=LTC", "");
+    assert_eq!(status, 404);
+    servers.stop();
+}
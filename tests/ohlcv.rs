@@ -0,0 +1,67 @@
+// ============================================================================
+// OHLCV - Running session candle from executions
+// ============================================================================
+
+mod common;
+
+use common::{http_request, TestServers};
+use hft_ringbuffer::matching_engine::{Ohlcv, Order, OrderBook, OrderSide};
+use hft_ringbuffer::price_units::Price;
+
+fn cross(book: &mut OrderBook, id: u64, price: u64, quantity: u64) {
+    book.add_limit_order(Order::new(id, OrderSide::Sell, price, quantity));
+    book.add_limit_order(Order::new(id + 1, OrderSide::Buy, price, quantity));
+}
+
+#[test]
+fn trades_build_the_session_candle() {
+    let mut book = OrderBook::new();
+    assert_eq!(book.ohlcv(), Ohlcv::default());
+
+    for (i, (price, quantity)) in [(100, 2), (104, 1), (97, 5), (101, 3)].into_iter().enumerate() {
+        cross(&mut book, 10 * i as u64, price, quantity);
+    }
+    // One aggressor sweeping two levels counts both fills
+    book.add_limit_order(Order::new(50, OrderSide::Buy, 95, 3));
+    book.add_limit_order(Order::new(51, OrderSide::Buy, 96, 1));
+    book.add_limit_order(Order::new(52, OrderSide::Sell, 95, 4));
+
+    assert_eq!(
+        book.ohlcv(),
+        Ohlcv { open: Some(Price(100)), high: Some(Price(104)), low: Some(Price(95)), close: Some(Price(95)), volume: 15 }
+    );
+    assert_eq!(book.last_trade_price(), Some(Price(95)));
+}
+
+#[test]
+fn reset_session_starts_a_new_candle() {
+    let mut book = OrderBook::new();
+    cross(&mut book, 1, 100, 2);
+    book.reset_session();
+    assert_eq!(book.ohlcv(), Ohlcv::default());
+    assert_eq!(book.last_trade_price(), Some(Price(100)));
+
+    cross(&mut book, 3, 90, 1);
+    assert_eq!(book.ohlcv(), Ohlcv { open: Some(Price(90)), high: Some(Price(90)), low: Some(Price(90)), close: Some(Price(90)), volume: 1 });
+}
+
+#[test]
+fn ohlcv_endpoint_reports_last_and_session() {
+    let servers = TestServers::start();
+    {
+        let mut book = servers.order_book.lock().unwrap();
+        cross(&mut book, 1, 100, 2);
+        cross(&mut book, 3, 102, 1);
+    }
+    let (status, body) = http_request(&servers.http_addr, "GET", "/api/ohlcv", "");
+    assert_eq!(status, 200);
+    let body: serde_json::Value = serde_json::from_str(&body).unwrap();
+    assert_eq!(
+        body,
+        serde_json::json!({
+            "last": 102,
+            "session": {"open": 100, "high": 102, "low": 100, "close": 102, "volume": 3}
+        })
+    );
+    servers.stop();
+}
@@ -0,0 +1,138 @@
+// ============================================================================
+// ORDER ENTRY - Refused orders come back with a typed reason
+// ============================================================================
+
+mod common;
+
+use common::{http_request, wait_until, GatewayClient, TestServers};
+use hft_ringbuffer::clock::ManualClock;
+use hft_ringbuffer::matching_engine::{Order, OrderBook, OrderSide, TradingState};
+use hft_ringbuffer::rejections::{EntryError, RejectReason, RejectionLog};
+use hft_ringbuffer::tick_size::TickSchedule;
+use hft_ringbuffer::price_units::Price;
+use std::sync::Arc;
+
+fn reason(result: Result<impl Sized, EntryError>) -> Option<RejectReason> {
+    result.err().map(|error| error.reason)
+}
+
+#[test]
+fn zero_quantity_is_rejected_not_silently_added() {
+    let mut book = OrderBook::new();
+    let sequence = book.sequence();
+    assert_eq!(reason(book.submit_order(Order::new(1, OrderSide::Buy, 100, 0))), Some(RejectReason::ZeroQuantity));
+    assert_eq!(book.sequence(), sequence);
+    assert_eq!(book.best_bid(), None);
+}
+
+#[test]
+fn each_check_has_its_own_reason() {
+    let mut book = OrderBook::new();
+    book.set_tick_schedule(Some(TickSchedule::uniform(5)));
+    assert_eq!(reason(book.submit_order(Order::new(1, OrderSide::Buy, 0, 1))), Some(RejectReason::InvalidPrice));
+    assert_eq!(reason(book.submit_order(Order::new(2, OrderSide::Buy, 101, 1))), Some(RejectReason::TickSize));
+
+    let executions = book.submit_order(Order::new(3, OrderSide::Buy, 100, 1)).unwrap();
+    assert!(executions.is_empty());
+    assert_eq!(book.best_bid(), Some(Price(100)));
+
+    book.set_trading_state(TradingState::Closed);
+    assert_eq!(reason(book.submit_order(Order::new(4, OrderSide::Buy, 100, 1))), Some(RejectReason::MarketClosed));
+}
+
+#[test]
+fn gateway_acks_the_reason_before_the_engine_sees_the_order() {
+    let servers = TestServers::start();
+    let log = Arc::new(RejectionLog::new(Arc::new(ManualClock::new(0)), None, 1, 16));
+    servers.metrics.ingress().set_rejection_log(log.clone());
+
+    let mut client = GatewayClient::connect(&servers.gateway_addr);
+    let ack = client.send_line(r#"{"id": 1, "side": "Buy", "price": 100, "quantity": 0}"#);
+    assert_eq!(ack["status"], "error");
+    assert_eq!(ack["reason"], "zero_quantity");
+    assert!(wait_until(|| log.counts().get(&RejectReason::ZeroQuantity) == Some(&1)));
+
+    let ack = client.send_line(r#"{"id": 2, "side": "Buy", "price": 0, "quantity": 3}"#);
+    assert_eq!(ack["reason"], "invalid_price");
+    servers.stop();
+}
+
+#[test]
+fn http_serializes_the_reason() {
+    let servers = TestServers::start();
+    let order = r#"{"id": 1, "side": "Sell", "price": 100, "quantity": 0}"#;
+    let (status, body) = http_request(&servers.http_addr, "POST", "/api/order", order);
+    assert_eq!(status, 400);
+    let body: serde_json::Value = serde_json::from_str(&body).unwrap();
+    assert_eq!(body["reason"], "zero_quantity");
+    assert_eq!(body["detail"], "quantity must be positive");
+    assert_eq!(servers.order_book.lock().unwrap().best_ask(), None);
+    servers.stop();
+}
+
+#[test]
+fn validate_names_what_is_wrong_with_the_order() {
+    assert_eq!(Order::new(1, OrderSide::Buy, 100, 0).validate(), Err(RejectReason::ZeroQuantity));
+    assert_eq!(Order::new(1, OrderSide::Buy, 0, 5).validate(), Err(RejectReason::InvalidPrice));
+    assert_eq!(Order::new(1, OrderSide::Buy, 0, 5).stop(0).validate(), Err(RejectReason::InvalidPrice));
+    assert_eq!(Order::new(1, OrderSide::Sell, u64::MAX / 2 + 1, 2).validate(), Err(RejectReason::NotionalOverflow));
+    assert_eq!(Order::new(1, OrderSide::Sell, 2, u64::MAX / 2 + 1).validate(), Err(RejectReason::NotionalOverflow));
+    assert_eq!(Order::new(1, OrderSide::Buy, 0, 3).stop(u64::MAX).validate(), Err(RejectReason::NotionalOverflow));
+
+    assert_eq!(Order::new(1, OrderSide::Buy, u64::MAX, 1).validate(), Ok(()));
+    assert_eq!(Order::new(1, OrderSide::Buy, u64::MAX / 2, 2).validate(), Ok(()));
+}
+
+#[test]
+fn gateway_refuses_invalid_orders_before_the_ring() {
+    let servers = TestServers::start();
+    let mut client = GatewayClient::connect(&servers.gateway_addr);
+
+    for (line, expected) in [
+        (r#"{"id": 1, "side": "Buy", "price": 100, "quantity": 0}"#, "zero_quantity"),
+        (r#"{"id": 2, "side": "Buy", "price": 0, "quantity": 3}"#, "invalid_price"),
+        (r#"{"id": 3, "side": "Buy", "price": 9223372036854775808, "quantity": 2}"#, "notional_overflow"),
+    ] {
+        let ack = client.send_line(line);
+        assert_eq!(ack["status"], "error", "{}", line);
+        assert_eq!(ack["reason"], expected, "{}", line);
+    }
+    let ack = client.send_line(r#"{"id": 4, "side": "Buy", "price": 100, "quantity": 3}"#);
+    assert_eq!(ack["status"], "accepted");
+
+    // Only the valid order was ever matched
+    assert_eq!(servers.metrics.orders_processed(), 1);
+    assert_eq!(servers.order_book.lock().unwrap().resting_orders().len(), 1);
+    servers.stop();
+}
+
+#[test]
+fn http_rejects_an_overflowing_notional() {
+    let servers = TestServers::start();
+    let order = r#"{"id": 1, "side": "Sell", "price": 10, "quantity": 18446744073709551615}"#;
+    let (status, body) = http_request(&servers.http_addr, "POST", "/api/order", order);
+    assert_eq!(status, 400);
+    let body: serde_json::Value = serde_json::from_str(&body).unwrap();
+    assert_eq!(body["reason"], "notional_overflow");
+    assert_eq!(servers.order_book.lock().unwrap().best_ask(), None);
+    servers.stop();
+}
+
+#[test]
+fn http_refuses_malformed_orders_before_the_engine() {
+    let servers = TestServers::start();
+    let orders = [
+        (r#"{"id": 1, "side": "Buy", "price": 100, "quantity": 0}"#, "zero_quantity"),
+        (r#"{"id": 2, "side": "Buy", "price": 0, "quantity": 3}"#, "invalid_price"),
+        (r#"{"id": 3, "side": "Sell", "price": 9223372036854775808, "quantity": 2}"#, "notional_overflow"),
+    ];
+    for (order, expected) in orders {
+        let (status, body) = http_request(&servers.http_addr, "POST", "/api/order", order);
+        assert_eq!(status, 400, "{}", body);
+        let body: serde_json::Value = serde_json::from_str(&body).unwrap();
+        assert_eq!(body["reason"], expected);
+    }
+    assert_eq!(servers.metrics.orders_processed(), 0);
+    assert_eq!(servers.metrics.queue_latency().count(), 0);
+    servers.stop();
+}
@@ -0,0 +1,65 @@
+// ============================================================================
+// ORDER GENERATOR - Same seed, same flow
+// ============================================================================
+
+use hft_ringbuffer::matching_engine::OrderSide;
+use hft_ringbuffer::order_generator::{GeneratedOrder, GeneratorParams, OrderGenerator, PriceDistribution, SizeDistribution};
+
+fn params() -> GeneratorParams {
+    GeneratorParams {
+        reference_price: 1_000,
+        buy_ratio: 0.7,
+        prices: PriceDistribution::Triangular { max_offset: 20 },
+        sizes: SizeDistribution::Geometric { mean: 10 },
+        cancel_rate: 0.2,
+    }
+}
+
+#[test]
+fn same_seed_produces_identical_sequences() {
+    let a: Vec<GeneratedOrder> = OrderGenerator::new(7, params()).take(5_000).collect();
+    let b: Vec<GeneratedOrder> = OrderGenerator::new(7, params()).take(5_000).collect();
+    assert_eq!(a, b);
+
+    let c: Vec<GeneratedOrder> = OrderGenerator::new(8, params()).take(5_000).collect();
+    assert_ne!(a, c);
+}
+
+#[test]
+fn generated_flow_respects_the_parameters() {
+    let events: Vec<GeneratedOrder> = OrderGenerator::new(1, params()).take(20_000).collect();
+
+    let mut issued = std::collections::HashSet::new();
+    let mut cancelled = std::collections::HashSet::new();
+    let (mut buys, mut news) = (0, 0);
+    for event in &events {
+        match event {
+            GeneratedOrder::New(order) => {
+                news += 1;
+                assert_eq!(order.id, news);
+                assert!((980..=1_020).contains(&order.price), "{:?}", order);
+                assert!(order.quantity >= 1);
+                if order.side == OrderSide::Buy {
+                    buys += 1;
+                }
+                issued.insert(order.id);
+            }
+            GeneratedOrder::Cancel { order_id } => {
+                // Only ever cancels something it issued, and only once
+                assert!(issued.contains(order_id));
+                assert!(cancelled.insert(*order_id));
+            }
+        }
+    }
+
+    let cancel_share = cancelled.len() as f64 / events.len() as f64;
+    assert!((0.17..0.23).contains(&cancel_share), "{}", cancel_share);
+    let buy_share = buys as f64 / news as f64;
+    assert!((0.67..0.73).contains(&buy_share), "{}", buy_share);
+}
+
+#[test]
+fn new_orders_skips_cancels() {
+    let orders: Vec<u64> = OrderGenerator::new(3, params()).new_orders().take(100).map(|o| o.id).collect();
+    assert_eq!(orders, (1..=100).collect::<Vec<u64>>());
+}
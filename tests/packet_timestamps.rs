@@ -0,0 +1,43 @@
+// ============================================================================
+// PACKET TIMESTAMPS - Receive time stamped at ingest, read by the engine
+// ============================================================================
+
+use hft_ringbuffer::clock::{process_clock, Clock};
+use hft_ringbuffer::engine::{spawn_engine, EngineHooks};
+use hft_ringbuffer::matching_engine::{Order, OrderBook, OrderSide, Packet};
+use hft_ringbuffer::metrics::Metrics;
+use std::sync::atomic::AtomicBool;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+#[test]
+fn packets_carry_a_nonzero_monotonic_receive_time() {
+    let before = process_clock().now_ns();
+    thread::sleep(Duration::from_millis(1));
+    let first = Packet::new(Order::new(1, OrderSide::Buy, 100, 1));
+    let second = Packet::new(Order::new(2, OrderSide::Buy, 100, 1));
+    let after = process_clock().now_ns();
+
+    assert!(first.recv_ns > 0);
+    assert!(before < first.recv_ns && first.recv_ns <= second.recv_ns && second.recv_ns <= after);
+}
+
+#[test]
+fn engine_measures_queue_residence_from_the_receive_time() {
+    const WAITED_NS: u64 = 5_000_000;
+    let (mut producer, consumer) = rtrb::RingBuffer::<Packet>::new(4);
+    // Received well before the engine can see it
+    let recv_ns = process_clock().now_ns();
+    producer.push(Packet::received_at(Order::new(1, OrderSide::Buy, 100, 1), recv_ns)).unwrap();
+    thread::sleep(Duration::from_nanos(WAITED_NS));
+
+    let metrics = Arc::new(Metrics::new());
+    let hooks = EngineHooks { drain_on_shutdown: true, ..Default::default() };
+    let engine = spawn_engine(consumer, Arc::new(Mutex::new(OrderBook::new())), Arc::new(AtomicBool::new(true)), metrics.clone(), hooks).unwrap();
+    engine.join().unwrap();
+
+    assert_eq!(metrics.queue_latency().count(), 1);
+    assert!(metrics.queue_latency().max_ns() >= WAITED_NS);
+    assert!(metrics.end_to_end_latency().max_ns() >= metrics.queue_latency().max_ns());
+}
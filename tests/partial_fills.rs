@@ -0,0 +1,55 @@
+// ============================================================================
+// PARTIAL FILLS - One taker across several levels, and what it leaves behind
+// ============================================================================
+
+mod common;
+
+use common::{http_request, GatewayClient, TestServers};
+use hft_ringbuffer::matching_engine::{Order, OrderBook, OrderSide};
+use hft_ringbuffer::price_units::Price;
+
+#[test]
+fn sweep_across_two_levels_reports_what_remains_after_each_fill() {
+    let mut book = OrderBook::new();
+    book.add_limit_order(Order::new(1, OrderSide::Sell, 100, 3));
+    book.add_limit_order(Order::new(2, OrderSide::Sell, 101, 4));
+    book.add_limit_order(Order::new(3, OrderSide::Sell, 103, 5));
+
+    let executions = book.add_limit_order(Order::new(10, OrderSide::Buy, 101, 10));
+    let fills: Vec<(u64, u64, u64, bool)> =
+        executions.iter().map(|e| (e.price.units(), e.quantity, e.remaining_quantity, e.is_taker_complete)).collect();
+    assert_eq!(fills, vec![(100, 3, 7, false), (101, 4, 3, true)]);
+    assert_eq!(book.resting_quantity(10), Some(3));
+    assert_eq!(book.best_bid(), Some(Price(101)));
+}
+
+#[test]
+fn a_taker_filled_in_full_completes_with_nothing_remaining() {
+    let mut book = OrderBook::new();
+    book.add_limit_order(Order::new(1, OrderSide::Buy, 100, 2));
+    book.add_limit_order(Order::new(2, OrderSide::Buy, 100, 2));
+
+    let executions = book.add_limit_order(Order::new(3, OrderSide::Sell, 99, 4));
+    assert_eq!(executions.iter().map(|e| (e.remaining_quantity, e.is_taker_complete)).collect::<Vec<_>>(), vec![(2, false), (0, true)]);
+    assert_eq!(book.resting_quantity(3), None);
+}
+
+#[test]
+fn gateway_and_http_acks_split_filled_from_rested() {
+    let servers = TestServers::start();
+    let mut client = GatewayClient::connect(&servers.gateway_addr);
+    client.send_line(r#"{"id":1,"side":"Sell","price":100,"quantity":3}"#);
+    client.send_line(r#"{"id":2,"side":"Sell","price":101,"quantity":4}"#);
+
+    let ack = client.send_line(r#"{"id":3,"side":"Buy","price":101,"quantity":10}"#);
+    assert_eq!(ack["status"], "partially_filled");
+    assert_eq!((ack["filled_quantity"].as_u64(), ack["rested_quantity"].as_u64()), (Some(7), Some(3)));
+    assert_eq!(ack["executions"][1]["remaining_quantity"], 3);
+
+    let (status, body) = http_request(&servers.http_addr, "POST", "/api/order", r#"{"id":4,"side":"Sell","price":101,"quantity":5}"#);
+    assert_eq!(status, 200);
+    let ack: serde_json::Value = serde_json::from_str(&body).unwrap();
+    assert_eq!((ack["filled_quantity"].as_u64(), ack["rested_quantity"].as_u64()), (Some(3), Some(2)));
+    assert_eq!(ack["executions"][0]["is_taker_complete"], true);
+    servers.stop();
+}
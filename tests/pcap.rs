@@ -0,0 +1,126 @@
+// ============================================================================
+// PCAP EXTRACTION - Reassembling gateway orders from captured packets
+// ============================================================================
+
+use hft_ringbuffer::pcap::{orders_from_pcap, orders_from_stream, PcapError};
+
+const GATEWAY_PORT: u16 = 8083;
+const CLIENT_PORT: u16 = 50_000;
+const SYN: u8 = 0x02;
+const ACK: u8 = 0x10;
+const FIN: u8 = 0x01;
+
+/// Writes a little-endian, microsecond pcap with Ethernet framing.
+struct CaptureWriter {
+    bytes: Vec<u8>,
+}
+
+impl CaptureWriter {
+    fn new() -> Self {
+        let mut bytes = Vec::new();
+        bytes.extend_from_slice(&0xa1b2_c3d4u32.to_le_bytes());
+        bytes.extend_from_slice(&2u16.to_le_bytes());
+        bytes.extend_from_slice(&4u16.to_le_bytes());
+        bytes.extend_from_slice(&[0; 8]);
+        bytes.extend_from_slice(&65_535u32.to_le_bytes());
+        bytes.extend_from_slice(&1u32.to_le_bytes());
+        CaptureWriter { bytes }
+    }
+
+    fn packet(&mut self, ts_usec: u32, src_port: u16, dst_port: u16, seq: u32, flags: u8, payload: &[u8]) {
+        let mut tcp = Vec::new();
+        tcp.extend_from_slice(&src_port.to_be_bytes());
+        tcp.extend_from_slice(&dst_port.to_be_bytes());
+        tcp.extend_from_slice(&seq.to_be_bytes());
+        tcp.extend_from_slice(&0u32.to_be_bytes());
+        tcp.push(5 << 4);
+        tcp.push(flags);
+        tcp.extend_from_slice(&[0; 6]);
+        tcp.extend_from_slice(payload);
+
+        let mut ip = vec![0x45, 0];
+        ip.extend_from_slice(&((20 + tcp.len()) as u16).to_be_bytes());
+        ip.extend_from_slice(&[0, 0, 0, 0, 64, 6, 0, 0]);
+        ip.extend_from_slice(&[127, 0, 0, 1, 127, 0, 0, 1]);
+        ip.extend_from_slice(&tcp);
+
+        let mut frame = vec![0; 12];
+        frame.extend_from_slice(&0x0800u16.to_be_bytes());
+        frame.extend_from_slice(&ip);
+
+        self.bytes.extend_from_slice(&0u32.to_le_bytes());
+        self.bytes.extend_from_slice(&ts_usec.to_le_bytes());
+        self.bytes.extend_from_slice(&(frame.len() as u32).to_le_bytes());
+        self.bytes.extend_from_slice(&(frame.len() as u32).to_le_bytes());
+        self.bytes.extend_from_slice(&frame);
+    }
+
+    fn client(&mut self, ts_usec: u32, seq: u32, flags: u8, payload: &[u8]) {
+        self.packet(ts_usec, CLIENT_PORT, GATEWAY_PORT, seq, flags, payload);
+    }
+}
+
+#[test]
+fn extracts_orders_across_split_reordered_and_retransmitted_segments() {
+    let first = br#"{"id":1,"side":"Buy","price":100,"quantity":5}"#.to_vec();
+    let second = br#"{"id":2,"side":"Sell","price":101,"quantity":3}"#.to_vec();
+    let third = br#"{"id":3,"side":"Buy","price":99,"quantity":1}"#.to_vec();
+    let mut wire = Vec::new();
+    for order in [&first, &second, &third] {
+        wire.extend_from_slice(order);
+        wire.push(b'\n');
+    }
+    let isn = 1_000;
+    let at = |offset: usize| isn + 1 + offset as u32;
+    let split = 20;
+    let second_end = first.len() + 1 + second.len() + 1;
+
+    let mut capture = CaptureWriter::new();
+    capture.client(1, isn, SYN, b"");
+    // Order 1 arrives in two pieces
+    capture.client(2, at(0), ACK, &wire[..split]);
+    // The gateway's ack goes the other way and must be ignored
+    capture.packet(3, GATEWAY_PORT, CLIENT_PORT, 9_000, ACK, b"{\"status\":\"accepted\"}\n");
+    // Order 3 overtakes the tail of orders 1 and 2
+    capture.client(4, at(second_end), ACK, &wire[second_end..]);
+    capture.client(5, at(split), ACK, &wire[split..second_end]);
+    // A retransmission overlapping bytes already seen
+    capture.client(6, at(split - 5), ACK, &wire[split - 5..split + 10]);
+    capture.client(7, at(wire.len()), FIN | ACK, b"");
+
+    let orders = orders_from_pcap(&capture.bytes, GATEWAY_PORT).unwrap();
+    let ids: Vec<u64> = orders.iter().map(|r| r.order.id).collect();
+    assert_eq!(ids, vec![1, 2, 3]);
+    // Each order is stamped when its final byte arrived
+    let stamps: Vec<u64> = orders.iter().map(|r| r.recv_ns).collect();
+    assert_eq!(stamps, vec![5_000, 5_000, 5_000]);
+    assert_eq!(orders[1].order.price, 101);
+
+    assert!(orders_from_pcap(&capture.bytes, 9_999).unwrap().is_empty());
+}
+
+#[test]
+fn trailing_partial_line_is_flushed_on_fin() {
+    let mut capture = CaptureWriter::new();
+    capture.client(1, 41, SYN, b"");
+    capture.client(2, 42, ACK, br#"{"id":7,"side":"Buy","price":1,"quantity":1}"#);
+    assert!(orders_from_pcap(&capture.bytes, GATEWAY_PORT).unwrap().is_empty());
+
+    capture.client(3, 42 + 45, FIN | ACK, b"");
+    let orders = orders_from_pcap(&capture.bytes, GATEWAY_PORT).unwrap();
+    assert_eq!(orders.len(), 1);
+    assert_eq!(orders[0].order.id, 7);
+}
+
+#[test]
+fn raw_stream_skips_lines_the_gateway_would_reject() {
+    let stream = b"{\"id\":1,\"side\":\"Buy\",\"price\":100,\"quantity\":5}\n\nnot json\n{\"id\":2,\"side\":\"Sell\",\"price\":100,\"quantity\":5}";
+    let ids: Vec<u64> = orders_from_stream(stream).iter().map(|o| o.id).collect();
+    assert_eq!(ids, vec![1, 2]);
+}
+
+#[test]
+fn rejects_files_that_are_not_captures() {
+    assert_eq!(orders_from_pcap(b"{\"id\":1}\n and more bytes ...", GATEWAY_PORT).unwrap_err(), PcapError::BadMagic(0x6469_227b));
+    assert_eq!(orders_from_pcap(b"short", GATEWAY_PORT).unwrap_err(), PcapError::Truncated("file header"));
+}
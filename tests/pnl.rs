@@ -0,0 +1,81 @@
+// ============================================================================
+// PNL - Average entry, realized and unrealized PnL per account
+// ============================================================================
+
+mod common;
+
+use common::{http_request, wait_until, GatewayClient, TestServers};
+use hft_ringbuffer::matching_engine::{Order, OrderBook, OrderSide};
+use hft_ringbuffer::positions::Position;
+use hft_ringbuffer::price_units::Price;
+
+const TRADER: u64 = 7;
+const STREET: u64 = 99;
+
+/// `TRADER` takes liquidity that `STREET` rests at `price`.
+fn trade(book: &mut OrderBook, id: u64, trader_side: OrderSide, price: u64, quantity: u64) {
+    let street_side = match trader_side {
+        OrderSide::Buy => OrderSide::Sell,
+        OrderSide::Sell => OrderSide::Buy,
+    };
+    book.add_limit_order(Order::new(id, street_side, price, quantity).with_account(STREET));
+    let executions = book.add_limit_order(Order::new(id + 1, trader_side, price, quantity).with_account(TRADER));
+    assert_eq!(executions.iter().map(|e| e.quantity).sum::<u64>(), quantity);
+}
+
+#[test]
+fn fills_accumulate_realize_and_flip() {
+    let mut book = OrderBook::new();
+    trade(&mut book, 10, OrderSide::Buy, 100, 10);
+    trade(&mut book, 20, OrderSide::Buy, 110, 10);
+    // Long 20 @ 105
+    assert_eq!(book.position(TRADER).unwrap(), &Position { quantity: 20, avg_entry_price: 105.0, realized_pnl: 0.0 });
+
+    // Sell 5 @ 120: realize 5 * 15
+    trade(&mut book, 30, OrderSide::Sell, 120, 5);
+    let report = book.pnl(TRADER, Some(Price(130))).unwrap();
+    assert_eq!(report.position, 15);
+    assert_eq!(report.avg_entry_price, 105.0);
+    assert_eq!(report.realized_pnl, 75.0);
+    assert_eq!(report.unrealized_pnl, 15.0 * 25.0);
+
+    // Sell 25 @ 100: close 15 (realize -75) and go short 10 @ 100
+    trade(&mut book, 40, OrderSide::Sell, 100, 25);
+    let report = book.pnl(TRADER, Some(Price(90))).unwrap();
+    assert_eq!(report.position, -10);
+    assert_eq!(report.avg_entry_price, 100.0);
+    assert_eq!(report.realized_pnl, 0.0);
+    assert_eq!(report.unrealized_pnl, 100.0);
+
+    // Without an explicit mark, the last trade price (100) is used
+    let report = book.pnl(TRADER, None).unwrap();
+    assert_eq!(report.mark_price, Some(Price(100)));
+    assert_eq!(report.unrealized_pnl, 0.0);
+
+    // The counterparty is the mirror image
+    let street = book.pnl(STREET, Some(Price(90))).unwrap();
+    assert_eq!(street.position, 10);
+    assert_eq!(street.realized_pnl + report.realized_pnl, 0.0);
+
+    assert!(book.pnl(12345, None).is_none());
+}
+
+#[test]
+fn pnl_is_served_over_http() {
+    let servers = TestServers::start();
+    let mut client = GatewayClient::connect(&servers.gateway_addr);
+    client.send_line(r#"{"id":1,"side":"Sell","price":100,"quantity":4,"account_id":2}"#);
+    client.send_line(r#"{"id":2,"side":"Buy","price":100,"quantity":4,"account_id":1}"#);
+    assert!(wait_until(|| servers.order_book.lock().unwrap().last_trade_price().is_some()));
+
+    let (status, body) = http_request(&servers.http_addr, "GET", "/api/pnl?account=1&mark=103", "");
+    assert_eq!(status, 200);
+    let report: serde_json::Value = serde_json::from_str(&body).unwrap();
+    assert_eq!(report["position"], 4);
+    assert_eq!(report["unrealized_pnl"], 12.0);
+
+    assert_eq!(http_request(&servers.http_addr, "GET", "/api/pnl", "").0, 400);
+    assert_eq!(http_request(&servers.http_addr, "GET", "/api/pnl?account=x", "").0, 400);
+    assert_eq!(http_request(&servers.http_addr, "GET", "/api/pnl?account=3", "").0, 404);
+    servers.stop();
+}
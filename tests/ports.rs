@@ -0,0 +1,26 @@
+// ============================================================================
+// CONFIGURABLE BIND ADDRESSES
+// ============================================================================
+
+use hft_ringbuffer::gateway::{bind_gateway, DEFAULT_GATEWAY_ADDR};
+use hft_ringbuffer::http_server::{bind_http_server, DEFAULT_HTTP_ADDR};
+
+#[test]
+fn gateway_on_port_zero_reports_the_real_port() {
+    let (listener, addr) = bind_gateway("127.0.0.1:0").unwrap();
+    assert_ne!(addr.port(), 0);
+    assert_eq!(listener.local_addr().unwrap(), addr);
+}
+
+#[test]
+fn http_server_on_port_zero_reports_the_real_port() {
+    let (_server, addr) = bind_http_server("127.0.0.1:0").unwrap();
+    assert_ne!(addr.port(), 0);
+    assert!(addr.ip().is_loopback());
+}
+
+#[test]
+fn defaults_match_the_historical_ports() {
+    assert_eq!(DEFAULT_GATEWAY_ADDR, "127.0.0.1:8083");
+    assert_eq!(DEFAULT_HTTP_ADDR, "0.0.0.0:8082");
+}
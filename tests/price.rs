@@ -0,0 +1,100 @@
+// ============================================================================
+// PRICE - Fixed-point arithmetic, ordering across scales, unchanged JSON
+// ============================================================================
+
+use hft_ringbuffer::matching_engine::{Order, OrderSide};
+use hft_ringbuffer::price_units::Price;
+use hft_ringbuffer::tick_size::{TickBand, TickSchedule};
+use std::collections::BTreeMap;
+
+const PRICES: [&str; 9] = ["0.01", "0.1", "0.99", "1", "1.05", "9.99", "10", "100.5", "1000"];
+
+#[test]
+fn tick_arithmetic_stays_on_the_grid() {
+    let price = Price(1_000);
+    assert_eq!(price + 5, Price(1_005));
+    assert_eq!(price - 5, Price(995));
+    assert_eq!(Price(1_005) - price, 5);
+    assert_eq!(price.abs_diff(Price(1_005)), 5);
+
+    let mut stepped = price;
+    for _ in 0..4 {
+        stepped += 25;
+    }
+    assert_eq!(stepped, Price(1_100));
+    assert!(stepped.is_on_tick(25) && stepped.is_on_tick(100));
+    assert!(!(stepped + 1).is_on_tick(25));
+    assert!(!stepped.is_on_tick(0));
+
+    assert_eq!(Price(3).checked_sub(5), None);
+    assert_eq!(Price(3).saturating_sub(5), Price::ZERO);
+    assert_eq!(Price::MAX.checked_add(1), None);
+    assert_eq!(price.checked_offset(-250), Some(Price(750)));
+    assert_eq!(Price(10).checked_offset(-11), None);
+    assert_eq!(Price(99).midpoint(Price(102)), Price(100));
+    assert_eq!(Price(250).notional(4), Some(1_000));
+    assert_eq!(Price::MAX.notional(2), None);
+}
+
+#[test]
+fn tick_rounding_uses_price_arithmetic_across_bands() {
+    let schedule = TickSchedule::new(vec![
+        TickBand { from_price: Price(0), tick_size: 1 },
+        TickBand { from_price: Price(1_000), tick_size: 25 },
+    ])
+    .unwrap();
+
+    assert_eq!(schedule.round(Price(1_010), OrderSide::Buy), Price(1_000));
+    assert_eq!(schedule.round(Price(1_010), OrderSide::Sell), Price(1_025));
+    assert_eq!(schedule.round(Price(999), OrderSide::Sell), Price(999));
+    assert!(schedule.validate(Price(1_050)).is_ok());
+    assert!(schedule.validate(Price(1_051)).is_err());
+}
+
+#[test]
+fn btree_order_matches_numeric_order_at_every_scale() {
+    let mut numeric: Vec<&str> = PRICES.to_vec();
+    numeric.sort_by(|a, b| a.parse::<f64>().unwrap().total_cmp(&b.parse::<f64>().unwrap()));
+
+    for scale in [2, 4, 8] {
+        // Insert in reverse so the map, not the input, does the ordering
+        let levels: BTreeMap<Price, &str> =
+            PRICES.iter().rev().map(|text| (Price::parse(text, scale).unwrap(), *text)).collect();
+        assert_eq!(levels.values().copied().collect::<Vec<_>>(), numeric, "scale {}", scale);
+    }
+}
+
+#[test]
+fn rescaling_keeps_the_value_and_the_order() {
+    for text in PRICES {
+        let cents = Price::parse(text, 2).unwrap();
+        let fine = Price::parse(text, 8).unwrap();
+        assert_eq!(cents.rescale(2, 8), Some(fine), "{}", text);
+        assert_eq!(fine.rescale(8, 2), Some(cents), "{}", text);
+        assert_eq!(Price::parse(&cents.to_decimal(2), 2), Ok(cents));
+    }
+
+    let fine: Vec<Price> = PRICES.iter().map(|text| Price::parse(text, 2).unwrap().rescale(2, 8).unwrap()).collect();
+    assert!(fine.windows(2).all(|pair| pair[0] < pair[1]));
+
+    assert_eq!(Price(10_050).to_decimal(2), "100.50");
+    assert_eq!(Price(7).to_decimal(3), "0.007");
+    assert_eq!(Price(42).to_decimal(0), "42");
+    // Dropping digits would change the price
+    assert_eq!(Price(10_055).rescale(2, 1), None);
+    assert_eq!(Price::MAX.rescale(0, 2), None);
+}
+
+#[test]
+fn json_is_the_bare_integer() {
+    assert_eq!(serde_json::to_string(&Price(10_050)).unwrap(), "10050");
+    assert_eq!(serde_json::from_str::<Price>("10050").unwrap(), Price(10_050));
+
+    // Orders written before prices were typed still read back
+    let order: Order = serde_json::from_str(r#"{"id":1,"side":"Buy","price":100,"quantity":3}"#).unwrap();
+    assert_eq!(order.price, Price(100));
+    let json = serde_json::to_value(Order::new(1, OrderSide::Buy, 100, 3)).unwrap();
+    assert_eq!(json["price"], 100);
+    let stop = serde_json::to_value(Order::new(2, OrderSide::Sell, 0, 1).stop(95)).unwrap();
+    assert_eq!(stop["order_type"]["Stop"]["trigger"], 95);
+}
@@ -0,0 +1,91 @@
+// ============================================================================
+// PRICE BAND - Orders far from the last trade are refused at entry
+// ============================================================================
+
+mod common;
+
+use common::{http_request, TestServers};
+use hft_ringbuffer::clock::ManualClock;
+use hft_ringbuffer::engine::{spawn_engine, EngineHooks};
+use hft_ringbuffer::matching_engine::{Order, OrderBook, OrderSide, Packet};
+use hft_ringbuffer::metrics::Metrics;
+use hft_ringbuffer::price_band::{BandViolation, PriceBand};
+use hft_ringbuffer::rejections::{RejectReason, RejectionLog};
+use hft_ringbuffer::tick_size::TickSchedule;
+use hft_ringbuffer::price_units::Price;
+use std::sync::atomic::AtomicBool;
+use std::sync::{Arc, Mutex};
+
+fn traded_at_100() -> OrderBook {
+    let mut book = OrderBook::new();
+    book.set_price_band(Some(PriceBand::new(5.0).unwrap()));
+    book.add_limit_order(Order::new(1, OrderSide::Sell, 100, 1));
+    book.add_limit_order(Order::new(2, OrderSide::Buy, 100, 1));
+    book
+}
+
+#[test]
+fn band_is_anchored_on_the_last_trade() {
+    let mut book = OrderBook::new();
+    book.set_price_band(Some(PriceBand::new(5.0).unwrap()));
+    // Nothing has traded yet
+    assert_eq!(book.check_price_band(1_000), Ok(()));
+
+    book = traded_at_100();
+    assert_eq!(book.check_price_band(95), Ok(()));
+    assert_eq!(book.check_price_band(105), Ok(()));
+    assert_eq!(
+        book.check_price_band(106),
+        Err(BandViolation { price: Price(106), reference: Price(100), max_band_pct: 5.0 })
+    );
+    assert!(book.check_price_band(94).is_err());
+    assert!(PriceBand::new(0.0).is_err());
+}
+
+#[test]
+fn engine_rejects_misaligned_and_out_of_band_prices() {
+    let mut book = traded_at_100();
+    book.set_tick_schedule(Some(TickSchedule::uniform(2)));
+    let log = Arc::new(RejectionLog::new(Arc::new(ManualClock::new(0)), None, 1, 16));
+
+    let (mut producer, consumer) = rtrb::RingBuffer::<Packet>::new(8);
+    for order in [
+        Order::new(3, OrderSide::Buy, 101, 1), // off the 2-tick grid
+        Order::new(4, OrderSide::Sell, 120, 1), // 20% away
+        Order::new(5, OrderSide::Buy, 98, 1),
+    ] {
+        producer.push(Packet::new(order)).unwrap();
+    }
+    let book = Arc::new(Mutex::new(book));
+    let hooks = EngineHooks { rejections: Some(log.clone()), drain_on_shutdown: true, ..Default::default() };
+    spawn_engine(consumer, book.clone(), Arc::new(AtomicBool::new(true)), Arc::new(Metrics::new()), hooks)
+        .unwrap()
+        .join()
+        .unwrap();
+
+    let reasons: Vec<(RejectReason, u64)> = log.recent(10).iter().map(|r| (r.reason, r.order.unwrap().id)).collect();
+    assert_eq!(reasons, vec![(RejectReason::TickSize, 3), (RejectReason::PriceBand, 4)]);
+    let book = book.lock().unwrap();
+    assert_eq!(book.best_bid(), Some(Price(98)));
+    assert_eq!(book.best_ask(), None);
+}
+
+#[test]
+fn http_reports_the_reject_reason() {
+    let servers = TestServers::start();
+    {
+        let mut book = servers.order_book.lock().unwrap();
+        *book = traded_at_100();
+        book.set_tick_schedule(Some(TickSchedule::uniform(2)));
+    }
+    let order = r#"{"id": 3, "side": "Buy", "price": 101, "quantity": 1}"#;
+    let (status, body) = http_request(&servers.http_addr, "POST", "/api/order", order);
+    assert_eq!(status, 400);
+    assert!(body.contains("tick size 2"), "{}", body);
+
+    let order = r#"{"id": 4, "side": "Buy", "price": 80, "quantity": 1}"#;
+    let (status, body) = http_request(&servers.http_addr, "POST", "/api/order", order);
+    assert_eq!(status, 400);
+    assert!(body.contains("more than 5% away from the last trade 100"), "{}", body);
+    servers.stop();
+}
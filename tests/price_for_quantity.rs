@@ -0,0 +1,59 @@
+// ============================================================================
+// PRICE FOR QUANTITY - Slippage estimation by walking the book
+// ============================================================================
+
+use hft_ringbuffer::matching_engine::{Order, OrderBook, OrderSide};
+use hft_ringbuffer::price_units::Price;
+
+fn multi_level_book() -> OrderBook {
+    let mut book = OrderBook::new();
+    book.add_limit_order(Order::new(1, OrderSide::Sell, 100, 10));
+    book.add_limit_order(Order::new(2, OrderSide::Sell, 101, 5));
+    book.add_limit_order(Order::new(3, OrderSide::Sell, 101, 5));
+    book.add_limit_order(Order::new(4, OrderSide::Sell, 103, 20));
+    book.add_limit_order(Order::new(5, OrderSide::Buy, 99, 8));
+    book.add_limit_order(Order::new(6, OrderSide::Buy, 97, 4));
+    book
+}
+
+#[test]
+fn buy_spanning_two_levels() {
+    let book = multi_level_book();
+    // 10 @ 100 + 5 @ 101 = 1505 / 15
+    let (avg, worst, filled) = book.price_for_quantity(OrderSide::Buy, 15).unwrap();
+    assert!((avg - 1505.0 / 15.0).abs() < 1e-9);
+    assert_eq!(worst, 101);
+    assert_eq!(filled, 15);
+}
+
+#[test]
+fn sell_walks_bids_downward() {
+    let book = multi_level_book();
+    // 8 @ 99 + 2 @ 97 = 986 / 10
+    let (avg, worst, filled) = book.price_for_quantity(OrderSide::Sell, 10).unwrap();
+    assert!((avg - 98.6).abs() < 1e-9);
+    assert_eq!(worst, 97);
+    assert_eq!(filled, 10);
+}
+
+#[test]
+fn stops_at_available_liquidity() {
+    let book = multi_level_book();
+    let (_, worst, filled) = book.price_for_quantity(OrderSide::Sell, 100).unwrap();
+    assert_eq!(worst, 97);
+    assert_eq!(filled, 12);
+}
+
+#[test]
+fn empty_side_has_no_price() {
+    let mut book = OrderBook::new();
+    book.add_limit_order(Order::new(1, OrderSide::Buy, 99, 8));
+    assert_eq!(book.price_for_quantity(OrderSide::Buy, 1), None);
+}
+
+#[test]
+fn walkers_aggregate_levels_from_the_touch() {
+    let book = multi_level_book();
+    assert_eq!(book.walk_asks().collect::<Vec<_>>(), vec![(Price(100), 10), (Price(101), 10), (Price(103), 20)]);
+    assert_eq!(book.walk_bids().collect::<Vec<_>>(), vec![(Price(99), 8), (Price(97), 4)]);
+}
@@ -0,0 +1,89 @@
+// ============================================================================
+// PRICE IMPROVEMENT - Reported per execution, aggregated per account
+// ============================================================================
+
+mod common;
+
+use common::{wait_until, GatewayClient, TestServers};
+use hft_ringbuffer::matching_engine::{Order, OrderBook, OrderSide};
+use hft_ringbuffer::metrics::Metrics;
+
+#[test]
+fn marketable_buy_records_improvement_against_a_better_ask() {
+    let mut book = OrderBook::new();
+    book.add_limit_order(Order::new(1, OrderSide::Sell, 100, 5));
+
+    let executions = book.add_limit_order(Order::new(2, OrderSide::Buy, 101, 3));
+    assert_eq!(executions.len(), 1);
+    assert_eq!(executions[0].price, 100);
+    assert_eq!(executions[0].price_improvement, 3);
+
+    let json = serde_json::to_value(&executions[0]).unwrap();
+    assert_eq!(json["price_improvement"], 3);
+}
+
+#[test]
+fn marketable_sell_records_improvement_against_a_better_bid() {
+    let mut book = OrderBook::new();
+    book.add_limit_order(Order::new(1, OrderSide::Buy, 105, 2));
+
+    let executions = book.add_limit_order(Order::new(2, OrderSide::Sell, 100, 2));
+    assert_eq!(executions[0].price_improvement, 10);
+}
+
+#[test]
+fn fill_at_the_limit_has_no_improvement() {
+    let mut book = OrderBook::new();
+    book.add_limit_order(Order::new(1, OrderSide::Sell, 100, 5));
+
+    let executions = book.add_limit_order(Order::new(2, OrderSide::Buy, 100, 5));
+    assert_eq!(executions[0].price_improvement, 0);
+}
+
+#[test]
+fn market_orders_and_triggered_stops_have_no_limit_to_improve_on() {
+    let mut book = OrderBook::new();
+    book.add_limit_order(Order::new(1, OrderSide::Sell, 100, 2));
+    book.add_limit_order(Order::new(2, OrderSide::Sell, 110, 1));
+    book.add_limit_order(Order::new(3, OrderSide::Sell, 120, 1));
+    book.add_limit_order(Order::new(4, OrderSide::Sell, 130, 5));
+
+    let result = book.add_market_order(OrderSide::Buy, 2, 5);
+    assert_eq!(result.executions[0].price, 100);
+    assert_eq!(result.executions[0].price_improvement, 0);
+
+    // Fires on the trade at 110 and takes the 120 ask, well inside its sweep bound
+    book.add_limit_order(Order::new(6, OrderSide::Buy, 0, 1).stop(110));
+    let executions = book.add_limit_order(Order::new(7, OrderSide::Buy, 110, 1));
+    let stop_fill = executions.iter().find(|exec| exec.taker_order_id == 6).expect("stop fired");
+    assert_eq!(stop_fill.price, 120);
+    assert_eq!(stop_fill.price_improvement, 0);
+}
+
+#[test]
+fn improvement_is_aggregated_per_taker_account() {
+    let metrics = Metrics::new();
+    let mut book = OrderBook::new();
+    book.add_limit_order(Order::new(1, OrderSide::Sell, 100, 10));
+
+    let taker = Order::new(2, OrderSide::Buy, 102, 4).with_account(7);
+    let executions = book.add_limit_order(taker.clone());
+    metrics.record_price_improvement(taker.account_id, &executions);
+
+    let taker = Order::new(3, OrderSide::Buy, 101, 1).with_account(7);
+    let executions = book.add_limit_order(taker.clone());
+    metrics.record_price_improvement(taker.account_id, &executions);
+
+    assert_eq!(metrics.price_improvement_by_account().get(&7), Some(&9));
+}
+
+#[test]
+fn engine_feeds_improvement_into_shared_metrics() {
+    let servers = TestServers::start();
+    let mut client = GatewayClient::connect(&servers.gateway_addr);
+    client.send_line(r#"{"id":1,"side":"Sell","price":100,"quantity":5}"#);
+    client.send_line(r#"{"id":2,"side":"Buy","price":103,"quantity":2,"account_id":42}"#);
+
+    assert!(wait_until(|| servers.metrics.price_improvement_by_account().get(&42) == Some(&6)));
+    servers.stop();
+}
@@ -0,0 +1,34 @@
+// ============================================================================
+// PRICE-TIME PRIORITY - Oldest order at a price fills first
+// ============================================================================
+
+use hft_ringbuffer::matching_engine::{Order, OrderBook, OrderSide};
+
+#[test]
+fn resting_asks_at_one_price_fill_oldest_first() {
+    let mut book = OrderBook::new();
+    for id in 1..=3 {
+        book.add_limit_order(Order::new(id, OrderSide::Sell, 100, 5));
+    }
+
+    let executions = book.add_limit_order(Order::new(10, OrderSide::Buy, 100, 7));
+    let makers: Vec<(u64, u64)> = executions.iter().map(|e| (e.maker_order_id, e.quantity)).collect();
+    assert_eq!(makers, vec![(1, 5), (2, 2)]);
+
+    // The partly filled order keeps its place ahead of id 3
+    let executions = book.add_limit_order(Order::new(11, OrderSide::Buy, 100, 4));
+    let makers: Vec<(u64, u64)> = executions.iter().map(|e| (e.maker_order_id, e.quantity)).collect();
+    assert_eq!(makers, vec![(2, 3), (3, 1)]);
+}
+
+#[test]
+fn resting_bids_at_one_price_fill_oldest_first() {
+    let mut book = OrderBook::new();
+    for id in 1..=3 {
+        book.add_limit_order(Order::new(id, OrderSide::Buy, 100, 1));
+    }
+
+    let executions = book.add_limit_order(Order::new(10, OrderSide::Sell, 100, 3));
+    let makers: Vec<u64> = executions.iter().map(|e| e.maker_order_id).collect();
+    assert_eq!(makers, vec![1, 2, 3]);
+}
@@ -0,0 +1,52 @@
+// ============================================================================
+// PRICING INPUTS - Mid, last and a bounded trailing trade-price series
+// ============================================================================
+
+mod common;
+
+use common::{http_request, TestServers};
+use hft_ringbuffer::matching_engine::{Order, OrderBook, OrderSide};
+use hft_ringbuffer::price_units::Price;
+
+/// One unit trades at `price`.
+fn trade_at(book: &mut OrderBook, id: u64, price: u64) {
+    book.add_limit_order(Order::new(id, OrderSide::Sell, price, 1));
+    assert_eq!(book.add_limit_order(Order::new(id + 1, OrderSide::Buy, price, 1)).len(), 1);
+}
+
+#[test]
+fn trailing_series_keeps_the_last_n_trades_in_order() {
+    let mut book = OrderBook::new();
+    book.set_trailing_prices_capacity(3);
+    for (i, price) in [100, 101, 99, 102, 104].into_iter().enumerate() {
+        trade_at(&mut book, 10 * i as u64, price);
+    }
+
+    let inputs = book.pricing_inputs();
+    assert_eq!(inputs.trailing_prices, vec![99, 102, 104]);
+    assert_eq!(inputs.last, Some(Price(104)));
+    assert_eq!(inputs.mid, None);
+
+    book.add_limit_order(Order::new(100, OrderSide::Buy, 103, 1));
+    book.add_limit_order(Order::new(101, OrderSide::Sell, 106, 1));
+    assert_eq!(book.pricing_inputs().mid, Some(104.5));
+
+    book.set_trailing_prices_capacity(1);
+    assert_eq!(book.pricing_inputs().trailing_prices, vec![104]);
+}
+
+#[test]
+fn pricing_inputs_are_served_over_http() {
+    let servers = TestServers::start();
+    {
+        let mut book = servers.order_book.lock().unwrap();
+        trade_at(&mut book, 1, 100);
+        trade_at(&mut book, 3, 101);
+    }
+
+    let (status, body) = http_request(&servers.http_addr, "GET", "/api/pricing-inputs", "");
+    assert_eq!(status, 200);
+    let inputs: serde_json::Value = serde_json::from_str(&body).unwrap();
+    assert_eq!(inputs, serde_json::json!({"mid": null, "last": 101, "trailing_prices": [100, 101]}));
+    servers.stop();
+}
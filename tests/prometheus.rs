@@ -0,0 +1,78 @@
+// ============================================================================
+// PROMETHEUS EXPOSITION - /metrics is scrapeable text format
+// ============================================================================
+
+mod common;
+
+use common::{http_exchange, wait_until, GatewayClient, TestServers};
+use std::collections::HashMap;
+
+fn is_metric_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars.next().is_some_and(|c| c.is_ascii_alphabetic() || c == '_' || c == ':')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_' || c == ':')
+}
+
+/// Checks every line against the text exposition format and returns the
+/// unlabelled samples by name. Each sample's family must have a `# TYPE`.
+fn parse_exposition(text: &str) -> HashMap<String, f64> {
+    let mut types = HashMap::new();
+    let mut samples = HashMap::new();
+    for line in text.lines().filter(|line| !line.is_empty()) {
+        if let Some(comment) = line.strip_prefix("# ") {
+            let mut parts = comment.splitn(3, ' ');
+            let (keyword, name, rest) = (parts.next().unwrap(), parts.next().unwrap(), parts.next().unwrap());
+            assert!(is_metric_name(name), "bad name in {:?}", line);
+            match keyword {
+                "HELP" => {}
+                "TYPE" => {
+                    assert!(["counter", "gauge", "histogram"].contains(&rest), "bad type in {:?}", line);
+                    types.insert(name.to_string(), rest.to_string());
+                }
+                _ => panic!("unknown comment {:?}", line),
+            }
+            continue;
+        }
+        let (series, value) = line.rsplit_once(' ').unwrap_or_else(|| panic!("no value in {:?}", line));
+        let value: f64 = value.parse().unwrap_or_else(|_| panic!("bad value in {:?}", line));
+        let name = match series.split_once('{') {
+            Some((name, labels)) => {
+                assert!(labels.ends_with('}'), "unterminated labels in {:?}", line);
+                name
+            }
+            None => {
+                samples.insert(series.to_string(), value);
+                series
+            }
+        };
+        assert!(is_metric_name(name), "bad name in {:?}", line);
+        let family = ["_bucket", "_sum", "_count"]
+            .iter()
+            .find_map(|suffix| name.strip_suffix(suffix).filter(|base| types.get(*base).is_some_and(|t| t == "histogram")))
+            .unwrap_or(name);
+        assert!(types.contains_key(family), "no TYPE for {:?}", line);
+    }
+    samples
+}
+
+#[test]
+fn exposition_parses_and_reports_engine_and_book_state() {
+    let servers = TestServers::start();
+    let mut client = GatewayClient::connect(&servers.gateway_addr);
+    for (id, side, price) in [(1, "Sell", 101), (2, "Sell", 102), (3, "Buy", 99), (4, "Buy", 101)] {
+        client.send_line(&format!(r#"{{"id":{},"side":"{}","price":{},"quantity":1}}"#, id, side, price));
+    }
+    assert!(wait_until(|| servers.metrics.orders_processed() == 4));
+
+    let (status, headers, body) = http_exchange(&servers.http_addr, "GET", "/metrics", "");
+    assert_eq!(status, 200);
+    assert!(headers.iter().any(|h| h == "Content-Type: text/plain; version=0.0.4"), "{:?}", headers);
+
+    let samples = parse_exposition(&body);
+    assert_eq!(samples["orders_total"], 4.0);
+    assert_eq!(samples["trades_total"], 1.0);
+    assert_eq!(samples["orderbook_bid_levels"], 1.0);
+    assert_eq!(samples["orderbook_ask_levels"], 1.0);
+    assert_eq!(samples["ring_buffer_occupancy"], 0.0);
+    servers.stop();
+}
@@ -0,0 +1,71 @@
+// ============================================================================
+// PROTECTED MARKET ORDERS - Sweep to fill, never past the protection price
+// ============================================================================
+
+use hft_ringbuffer::matching_engine::{Order, OrderBook, OrderSide, ProtectionRemainder};
+use hft_ringbuffer::price_units::Price;
+
+/// Two shallow near levels, then a gap to an absurd price
+fn thin_book() -> OrderBook {
+    let mut book = OrderBook::new();
+    book.add_limit_order(Order::new(1, OrderSide::Sell, 100, 2));
+    book.add_limit_order(Order::new(2, OrderSide::Sell, 101, 2));
+    book.add_limit_order(Order::new(3, OrderSide::Sell, 150, 10));
+    book.add_limit_order(Order::new(4, OrderSide::Buy, 95, 5));
+    book
+}
+
+#[test]
+fn fills_near_levels_and_cancels_at_the_protection_price() {
+    let mut book = thin_book();
+    let result = book.add_protected_market_order(10, OrderSide::Buy, 10, 105, ProtectionRemainder::Cancel);
+
+    let fills: Vec<(u64, u64)> = result.executions.iter().map(|e| (e.price.units(), e.quantity)).collect();
+    assert_eq!(fills, vec![(100, 2), (101, 2)]);
+    assert_eq!(result.unfilled_quantity, 6);
+    assert_eq!(result.rested_quantity, 0);
+    assert_eq!(book.best_ask(), Some(Price(150)));
+    assert_eq!(book.best_bid(), Some(Price(95)));
+}
+
+#[test]
+fn remainder_can_rest_at_the_protection_price() {
+    let mut book = thin_book();
+    let result = book.add_protected_market_order(10, OrderSide::Buy, 10, 105, ProtectionRemainder::Rest);
+
+    assert_eq!(result.unfilled_quantity, 6);
+    assert_eq!(result.rested_quantity, 6);
+    assert_eq!(book.best_bid(), Some(Price(105)));
+    assert_eq!(book.walk_bids().next(), Some((Price(105), 6)));
+}
+
+#[test]
+fn fully_fills_when_liquidity_is_inside_protection() {
+    let mut book = thin_book();
+    let result = book.add_protected_market_order(10, OrderSide::Sell, 5, 90, ProtectionRemainder::Cancel);
+
+    assert_eq!(result.executions.len(), 1);
+    assert_eq!(result.executions[0].price, 95);
+    assert_eq!(result.unfilled_quantity, 0);
+}
+
+#[test]
+fn protection_price_from_slippage_cap() {
+    let book = thin_book();
+    // 5% above the best ask of 100
+    assert_eq!(book.protection_price(OrderSide::Buy, 500), Some(Price(105)));
+    // 10% below the best bid of 95, rounded toward the bid
+    assert_eq!(book.protection_price(OrderSide::Sell, 1_000), Some(Price(86)));
+    assert_eq!(OrderBook::new().protection_price(OrderSide::Buy, 500), None);
+}
+
+#[test]
+fn huge_slippage_caps_saturate_instead_of_overflowing() {
+    let book = thin_book();
+    assert_eq!(book.protection_price(OrderSide::Buy, u64::MAX), Some(Price(100 + u64::MAX / 100)));
+    assert_eq!(book.protection_price(OrderSide::Sell, u64::MAX), Some(Price(0)));
+
+    let mut book = OrderBook::new();
+    book.add_limit_order(Order::new(1, OrderSide::Sell, u64::MAX / 2, 1));
+    assert_eq!(book.protection_price(OrderSide::Buy, 30_000), Some(Price(u64::MAX)));
+}
@@ -0,0 +1,33 @@
+// ============================================================================
+// PUBLIC API - Embedding the engine through the crate-root re-exports
+// ============================================================================
+
+use hft_ringbuffer::{DepthLevel, Order, OrderBook, OrderSide, Price, TradeExecution};
+
+#[test]
+fn a_book_can_be_driven_from_the_crate_root() {
+    let mut book = OrderBook::new();
+    book.submit_order(Order::new(1, OrderSide::Sell, 101, 5)).unwrap();
+    book.submit_order(Order::new(2, OrderSide::Sell, 102, 5)).unwrap();
+    book.submit_order(Order::new(3, OrderSide::Buy, 99, 4)).unwrap();
+
+    let executions: Vec<TradeExecution> = book.submit_order(Order::new(4, OrderSide::Buy, 102, 7)).unwrap();
+    let fills: Vec<(u64, Price, u64)> = executions.iter().map(|e| (e.maker_order_id, e.price, e.quantity)).collect();
+    assert_eq!(fills, vec![(1, Price(101), 5), (2, Price(102), 2)]);
+
+    let depth = book.depth(5);
+    assert_eq!(depth.bids, vec![DepthLevel { price: Price(99), qty: 4, count: 1 }]);
+    assert_eq!(depth.asks, vec![DepthLevel { price: Price(102), qty: 3, count: 1 }]);
+
+    let cancelled = book.cancel_order(3).unwrap();
+    assert_eq!((cancelled.id, cancelled.quantity), (3, 4));
+    assert_eq!(book.best_bid(), None);
+    assert_eq!(book.best_ask(), Some(Price(102)));
+}
+
+#[test]
+fn module_paths_and_root_paths_name_the_same_types() {
+    let root: hft_ringbuffer::Order = hft_ringbuffer::matching_engine::Order::new(1, OrderSide::Buy, 100, 1);
+    let price: hft_ringbuffer::price_units::Price = root.price;
+    assert_eq!(price, Price(100));
+}
@@ -0,0 +1,163 @@
+// ============================================================================
+// READ REPLICA - Lag tracking and the staleness guard
+// ============================================================================
+
+mod common;
+
+use common::{http_request, wait_until, GatewayClient, TestServers};
+use hft_ringbuffer::clock::ManualClock;
+use hft_ringbuffer::engine::{spawn_engine, EngineHooks};
+use hft_ringbuffer::match_policy::ProRataPolicy;
+use hft_ringbuffer::matching_engine::{Order, OrderBook, OrderSide, Packet, TradingState};
+use hft_ringbuffer::metrics::Metrics;
+use hft_ringbuffer::replica::{replica_channel, seed_replica_book, ReplicaLag, StaleAction, StalenessGuard};
+use hft_ringbuffer::wash_trade::WashTradeConfig;
+use std::sync::atomic::AtomicBool;
+use std::sync::{Arc, Mutex};
+
+#[test]
+fn lag_grows_while_updates_are_held_back_and_clears_once_applied() {
+    let clock = Arc::new(ManualClock::new(0));
+    let guard = StalenessGuard { max_lag_sequences: 2, max_lag_ns: 0, action: StaleAction::Warn };
+    let (feed, replica) = replica_channel(OrderBook::new(), clock.clone(), Some(guard));
+    let mut primary = OrderBook::new();
+
+    for (i, order) in [
+        Order::new(1, OrderSide::Sell, 101, 5),
+        Order::new(2, OrderSide::Buy, 99, 5),
+        Order::new(3, OrderSide::Buy, 101, 2),
+    ].into_iter().enumerate() {
+        clock.advance(1_000);
+        primary.add_limit_order(order.clone());
+        feed.publish(primary.sequence(), order);
+        assert_eq!(replica.lag().sequences, i as u64 + 1);
+    }
+    // The replica still shows the empty book it started from
+    assert_eq!(replica.lag(), ReplicaLag { sequences: 3, ns: 3_000 });
+    assert_eq!(replica.stale_action(), Some(StaleAction::Warn));
+    assert_eq!(replica.book().best_ask(), None);
+
+    assert_eq!(replica.apply_pending(), 3);
+    assert_eq!(replica.lag(), ReplicaLag::default());
+    assert_eq!(replica.stale_action(), None);
+    assert_eq!(replica.applied_sequence(), primary.sequence());
+    assert_eq!(replica.book().to_json(), primary.to_json());
+}
+
+#[test]
+fn time_threshold_trips_independently_of_sequence_lag() {
+    let clock = Arc::new(ManualClock::new(0));
+    let guard = StalenessGuard { max_lag_sequences: 0, max_lag_ns: 5_000, action: StaleAction::Reject };
+    let (feed, replica) = replica_channel(OrderBook::new(), clock.clone(), Some(guard));
+
+    feed.publish(1, Order::new(1, OrderSide::Buy, 100, 1));
+    clock.advance(10_000);
+    feed.publish(2, Order::new(2, OrderSide::Buy, 100, 1));
+    assert_eq!(replica.lag().ns, 10_000);
+    assert_eq!(replica.stale_action(), Some(StaleAction::Reject));
+}
+
+#[test]
+fn stale_replica_reads_are_rejected_over_http() {
+    let guard = StalenessGuard { max_lag_sequences: 1, max_lag_ns: 0, action: StaleAction::Reject };
+    let servers = TestServers::start_with_replica(Arc::new(ManualClock::new(0)), Some(guard));
+    let replica = servers.replica.clone().unwrap();
+    let mut client = GatewayClient::connect(&servers.gateway_addr);
+
+    for id in 1..=3 {
+        let order = format!(r#"{{"id":{},"side":"Buy","price":{},"quantity":1}}"#, id, 100 + id);
+        assert_eq!(client.send_line(&order)["status"], "accepted");
+    }
+    assert!(wait_until(|| replica.lag().sequences == 3));
+
+    let (status, body) = http_request(&servers.http_addr, "GET", "/api/replica/lag", "");
+    assert_eq!(status, 200);
+    let lag: serde_json::Value = serde_json::from_str(&body).unwrap();
+    assert_eq!(lag["lag"]["sequences"], 3);
+    assert_eq!(lag["stale"], true);
+
+    let (status, _) = http_request(&servers.http_addr, "GET", "/api/replica/orderbook", "");
+    assert_eq!(status, 503);
+
+    replica.apply_pending();
+    let (status, body) = http_request(&servers.http_addr, "GET", "/api/replica/orderbook", "");
+    assert_eq!(status, 200);
+    assert_eq!(body, servers.order_book.lock().unwrap().to_json());
+    servers.stop();
+}
+
+#[test]
+fn http_orders_cancels_and_commands_reach_the_replica() {
+    let servers = TestServers::start_with_replica(Arc::new(ManualClock::new(0)), None);
+    let replica = servers.replica.clone().unwrap();
+    for id in 1..=2 {
+        let order = format!(r#"{{"id":{},"side":"Buy","price":100,"quantity":1}}"#, id);
+        assert_eq!(http_request(&servers.http_addr, "POST", "/api/order", &order).0, 200);
+    }
+    assert_eq!(http_request(&servers.http_addr, "DELETE", "/api/order/1", "").0, 200);
+    assert_eq!(http_request(&servers.http_addr, "POST", "/api/trading-state", r#"{"state":"closed"}"#).0, 200);
+
+    replica.apply_pending();
+    let primary = servers.order_book.lock().unwrap().to_json();
+    assert_eq!(replica.book().to_json(), primary);
+    assert_eq!(replica.book().resting_quantity(1), None);
+    assert_eq!(replica.book().trading_state(), TradingState::Closed);
+    // Counted like gateway orders; the cancel and the command are not orders
+    assert_eq!(servers.metrics.orders_processed(), 2);
+    servers.stop();
+}
+
+#[test]
+fn replica_routes_are_absent_without_a_replica() {
+    let servers = TestServers::start();
+    let (status, _) = http_request(&servers.http_addr, "GET", "/api/replica/orderbook", "");
+    assert_eq!(status, 404);
+    servers.stop();
+}
+
+#[test]
+fn a_seeded_replica_starts_from_the_primary_and_keeps_its_allocation() {
+    let mut primary = OrderBook::with_match_policy(Arc::new(ProRataPolicy));
+    primary.add_limit_order(Order::new(1, OrderSide::Sell, 100, 10));
+    primary.add_limit_order(Order::new(2, OrderSide::Sell, 100, 30));
+    let seeded = seed_replica_book(&primary, OrderBook::with_match_policy(Arc::new(ProRataPolicy)));
+    let (feed, replica) = replica_channel(seeded, Arc::new(ManualClock::new(0)), None);
+    assert_eq!(replica.applied_sequence(), primary.sequence());
+
+    let taker = Order::new(3, OrderSide::Buy, 100, 16);
+    primary.add_limit_order(taker.clone());
+    feed.publish(primary.sequence(), taker);
+    replica.apply_pending();
+    assert_eq!(replica.book().to_json(), primary.to_json());
+    assert_eq!(replica.lag(), ReplicaLag::default());
+}
+
+#[test]
+fn orders_the_primary_throttled_are_not_forwarded() {
+    let clock = Arc::new(ManualClock::new(0));
+    let mut primary = OrderBook::new();
+    primary.set_wash_trade_detection(
+        Some(WashTradeConfig { window_ns: 1_000, max_self_crosses: 1, throttle_ns: 1_000 }),
+        clock.clone(),
+    );
+    let primary = Arc::new(Mutex::new(primary));
+    // A replica without the detector would book whatever it is sent
+    let (feed, replica) = replica_channel(OrderBook::new(), clock, None);
+    let (mut producer, consumer) = rtrb::RingBuffer::<Packet>::new(8);
+    for order in [
+        Order::new(1, OrderSide::Sell, 100, 1).with_account(5),
+        Order::new(2, OrderSide::Buy, 100, 1).with_account(5),
+        Order::new(3, OrderSide::Buy, 90, 1).with_account(5),
+    ] {
+        producer.push(Packet::new(order)).unwrap();
+    }
+    let hooks = EngineHooks { replica: Some(feed), drain_on_shutdown: true, ..Default::default() };
+    let engine = spawn_engine(consumer, primary.clone(), Arc::new(AtomicBool::new(true)), Arc::new(Metrics::new()), hooks).unwrap();
+    engine.join().unwrap();
+
+    assert_eq!(replica.apply_pending(), 2);
+    let primary = primary.lock().unwrap();
+    assert_eq!(primary.wash_trade_flag(5).unwrap().throttled_orders, 1);
+    assert_eq!(replica.book().to_json(), primary.to_json());
+    assert_eq!(replica.applied_sequence(), primary.sequence());
+}
@@ -0,0 +1,66 @@
+// ============================================================================
+// RECENT TRADES - The book keeps a bounded history of its executions
+// ============================================================================
+
+mod common;
+
+use common::{http_request, TestServers};
+use hft_ringbuffer::matching_engine::{Order, OrderBook, OrderSide};
+
+fn cross(book: &mut OrderBook, maker_id: u64, price: u64) {
+    book.add_limit_order(Order::new(maker_id, OrderSide::Sell, price, 1));
+    book.add_limit_order(Order::new(maker_id + 1, OrderSide::Buy, price, 1));
+}
+
+#[test]
+fn trades_are_recorded_in_order() {
+    let mut book = OrderBook::new();
+    book.add_limit_order(Order::new(1, OrderSide::Sell, 100, 2));
+    book.add_limit_order(Order::new(2, OrderSide::Sell, 101, 2));
+    book.add_limit_order(Order::new(3, OrderSide::Buy, 101, 4));
+    cross(&mut book, 4, 102);
+
+    let trades: Vec<(u64, u64, u64)> =
+        book.recent_trades(10).iter().map(|t| (t.maker_order_id, t.price.units(), t.quantity)).collect();
+    assert_eq!(trades, vec![(1, 100, 2), (2, 101, 2), (4, 102, 1)]);
+    assert_eq!(book.recent_trades(1)[0].maker_order_id, 4);
+}
+
+#[test]
+fn cap_evicts_the_oldest_trades() {
+    let mut book = OrderBook::new();
+    book.set_recent_trades_capacity(3);
+    for (i, price) in (100..105).enumerate() {
+        cross(&mut book, 10 * i as u64, price);
+    }
+    let prices: Vec<u64> = book.recent_trades(10).iter().map(|t| t.price.units()).collect();
+    assert_eq!(prices, vec![102, 103, 104]);
+
+    book.set_recent_trades_capacity(1);
+    assert_eq!(book.recent_trades(10).len(), 1);
+    book.set_recent_trades_capacity(0);
+    cross(&mut book, 100, 110);
+    assert!(book.recent_trades(10).is_empty());
+}
+
+#[test]
+fn trades_endpoint_serves_the_most_recent() {
+    let servers = TestServers::start();
+    {
+        let mut book = servers.order_book.lock().unwrap();
+        for (i, price) in (100..160).enumerate() {
+            cross(&mut book, 10 * i as u64, price);
+        }
+    }
+    let (status, body) = http_request(&servers.http_addr, "GET", "/api/trades", "");
+    assert_eq!(status, 200);
+    let body: serde_json::Value = serde_json::from_str(&body).unwrap();
+    let trades = body.as_array().unwrap();
+    assert_eq!(trades.len(), 50);
+    assert_eq!(trades[49]["price"], 159);
+
+    let (_, body) = http_request(&servers.http_addr, "GET", "/api/trades?n=2", "");
+    let body: serde_json::Value = serde_json::from_str(&body).unwrap();
+    assert_eq!(body.as_array().unwrap().iter().map(|t| t["price"].as_u64().unwrap()).collect::<Vec<_>>(), vec![158, 159]);
+    servers.stop();
+}
@@ -0,0 +1,87 @@
+// ============================================================================
+// REFERENCE PEGS - Orders that track an externally supplied price
+// ============================================================================
+
+mod common;
+
+use common::{http_request, TestServers};
+use hft_ringbuffer::matching_engine::{MatchingEngine, Order, OrderBook, OrderSide};
+use hft_ringbuffer::price_units::Price;
+
+#[test]
+fn a_reference_update_reprices_and_fills_a_pegged_order() {
+    let mut book = OrderBook::new();
+    book.update_reference_price(100);
+    book.add_limit_order(Order::new(1, OrderSide::Sell, 105, 5));
+    book.add_limit_order(Order::new(2, OrderSide::Buy, 0, 3).pegged_to_reference(-1));
+    assert_eq!(book.best_bid(), Some(Price(99)));
+
+    // The reference moves up; the peg follows and now crosses the ask
+    let executions = book.update_reference_price(106);
+    assert_eq!(executions.len(), 1);
+    assert_eq!((executions[0].maker_order_id, executions[0].taker_order_id), (1, 2));
+    assert_eq!((executions[0].price.units(), executions[0].quantity), (105, 3));
+    assert_eq!(book.best_bid(), None);
+    assert_eq!(book.walk_asks().collect::<Vec<_>>(), vec![(Price(105), 2)]);
+}
+
+#[test]
+fn pegs_wait_on_their_own_price_until_a_reference_arrives() {
+    let mut engine = MatchingEngine::new();
+    engine.add_limit_order("BTC", Order::new(1, OrderSide::Sell, 110, 1).pegged_to_reference(2));
+    assert_eq!(engine.book("BTC").unwrap().best_ask(), Some(Price(110)));
+
+    let executions = engine.update_reference_price("BTC", 100);
+    assert!(executions.is_empty());
+    let book = engine.book("BTC").unwrap();
+    assert_eq!(book.reference_price(), Some(Price(100)));
+    assert_eq!(book.walk_asks().collect::<Vec<_>>(), vec![(Price(102), 1)]);
+}
+
+#[test]
+fn the_reference_price_endpoint_moves_pegs_over_http() {
+    let servers = TestServers::start();
+    servers.order_book.lock().unwrap().add_limit_order(Order::new(1, OrderSide::Buy, 95, 4));
+    let (status, _) = http_request(
+        &servers.http_addr,
+        "POST",
+        "/api/order",
+        r#"{"id": 2, "side": "Sell", "price": 120, "quantity": 4, "reference_peg_offset": 5}"#,
+    );
+    assert_eq!(status, 200);
+
+    let (status, body) = http_request(&servers.http_addr, "POST", "/api/reference-price", r#"{"price": 90}"#);
+    assert_eq!(status, 200);
+    let body: serde_json::Value = serde_json::from_str(&body).unwrap();
+    assert_eq!(body["executions"][0]["price"], 95);
+    assert_eq!(body["executions"][0]["quantity"], 4);
+
+    let (status, _) = http_request(&servers.http_addr, "POST", "/api/reference-price", r#"{"price": "high"}"#);
+    assert_eq!(status, 400);
+    servers.stop();
+}
+
+#[test]
+fn concurrent_commands_each_get_their_own_outcome() {
+    let servers = TestServers::start();
+    // None of these carry an order id; each must still hear its own answer
+    let requests: Vec<_> = (0..16)
+        .map(|i| {
+            let addr = servers.http_addr.clone();
+            std::thread::spawn(move || match i % 2 {
+                0 => http_request(&addr, "POST", "/api/reference-price", &format!(r#"{{"price": {}}}"#, 100 + i)),
+                _ => http_request(&addr, "POST", "/api/trading-state", r#"{"state": "open"}"#),
+            })
+        })
+        .collect();
+    for (i, request) in requests.into_iter().enumerate() {
+        let (status, body) = request.join().unwrap();
+        assert_eq!(status, 200, "{}", body);
+        let body: serde_json::Value = serde_json::from_str(&body).unwrap();
+        match i % 2 {
+            0 => assert_eq!(body["status"], "accepted"),
+            _ => assert_eq!(body["state"], "open"),
+        }
+    }
+    servers.stop();
+}
@@ -0,0 +1,142 @@
+// ============================================================================
+// REJECTIONS - Structured, sampled records of refused orders
+// ============================================================================
+
+mod common;
+
+use common::{wait_until, GatewayClient, TestServers};
+use crossbeam_channel::Receiver;
+use hft_ringbuffer::clock::ManualClock;
+use hft_ringbuffer::engine::{spawn_engine, EngineHooks};
+use hft_ringbuffer::events::{BookEvent, BusMessage, EventBus};
+use hft_ringbuffer::matching_engine::{Order, OrderBook, OrderSide, Packet, SelfTradePrevention};
+use hft_ringbuffer::metrics::Metrics;
+use hft_ringbuffer::rejections::{OrderSummary, RejectReason, Rejection, RejectionLog};
+use hft_ringbuffer::tick_size::TickSchedule;
+use hft_ringbuffer::wash_trade::WashTradeConfig;
+use hft_ringbuffer::price_units::Price;
+use serde_json::json;
+use std::sync::atomic::AtomicBool;
+use std::sync::{Arc, Mutex};
+
+fn rejections_on(receiver: &Receiver<BusMessage>) -> Vec<Rejection> {
+    receiver
+        .try_iter()
+        .filter_map(|message| match message.event {
+            BookEvent::Rejection(rejection) => Some(rejection),
+            _ => None,
+        })
+        .collect()
+}
+
+#[test]
+fn engine_rejections_carry_reason_account_and_order() {
+    let bus = Arc::new(EventBus::new());
+    let events = bus.subscribe();
+    let clock = Arc::new(ManualClock::new(7_000));
+    let log = Arc::new(RejectionLog::new(clock.clone(), Some(bus.clone()), 1, 16));
+
+    let mut book = OrderBook::new();
+    book.set_tick_schedule(Some(TickSchedule::uniform(5)));
+    book.set_self_trade_prevention(SelfTradePrevention::CancelIncoming);
+    book.set_wash_trade_detection(
+        Some(WashTradeConfig { window_ns: 1_000, max_self_crosses: 1, throttle_ns: 1_000 }),
+        clock.clone(),
+    );
+
+    let (mut producer, consumer) = rtrb::RingBuffer::<Packet>::new(16);
+    for order in [
+        Order::new(1, OrderSide::Buy, 101, 2).with_account(9),
+        // Account 4 crosses itself once, which flags and throttles it
+        Order::new(2, OrderSide::Sell, 100, 1).with_account(4),
+        Order::new(3, OrderSide::Buy, 100, 1).with_account(4),
+        Order::new(4, OrderSide::Buy, 95, 1).with_account(4),
+    ] {
+        producer.push(Packet::new(order)).unwrap();
+    }
+    let hooks = EngineHooks { rejections: Some(log.clone()), drain_on_shutdown: true, ..Default::default() };
+    spawn_engine(consumer, Arc::new(Mutex::new(book)), Arc::new(AtomicBool::new(true)), Arc::new(Metrics::new()), hooks)
+        .unwrap()
+        .join()
+        .unwrap();
+
+    let rejections = rejections_on(&events);
+    assert_eq!(rejections.len(), 2);
+    assert_eq!(rejections[0].reason, RejectReason::TickSize);
+    assert_eq!(rejections[0].account_id, Some(9));
+    assert_eq!(rejections[0].timestamp_ns, 7_000);
+    assert_eq!(
+        rejections[0].order,
+        Some(OrderSummary { id: 1, side: OrderSide::Buy, price: Price(101), quantity: 2 })
+    );
+    assert_eq!(rejections[1].reason, RejectReason::Throttled);
+    assert_eq!(rejections[1].account_id, Some(4));
+    assert_eq!(rejections[1].order.unwrap().id, 4);
+    assert_eq!(log.recent(10), rejections);
+}
+
+#[test]
+fn sampling_bounds_what_is_published_but_not_the_counts() {
+    let bus = Arc::new(EventBus::new());
+    let events = bus.subscribe();
+    let log = RejectionLog::new(Arc::new(ManualClock::new(0)), Some(bus.clone()), 3, 16);
+
+    for id in 0..7 {
+        log.record(RejectReason::Backpressure, Some(&Order::new(id, OrderSide::Sell, 100, 1)), "funnel full");
+    }
+    log.record(RejectReason::Malformed, None, "expected value");
+
+    let published: Vec<(RejectReason, Option<u64>)> =
+        rejections_on(&events).iter().map(|r| (r.reason, r.order.map(|o| o.id))).collect();
+    assert_eq!(
+        published,
+        vec![
+            (RejectReason::Backpressure, Some(0)),
+            (RejectReason::Backpressure, Some(3)),
+            (RejectReason::Backpressure, Some(6)),
+            (RejectReason::Malformed, None),
+        ]
+    );
+    let counts = log.counts();
+    assert_eq!(counts[&RejectReason::Backpressure], 7);
+    assert_eq!(counts[&RejectReason::Malformed], 1);
+}
+
+#[test]
+fn malformed_gateway_messages_are_logged() {
+    let servers = TestServers::start();
+    let log = Arc::new(RejectionLog::new(Arc::new(ManualClock::new(0)), None, 1, 16));
+    servers.metrics.ingress().set_rejection_log(log.clone());
+
+    let mut client = GatewayClient::connect(&servers.gateway_addr);
+    let ack = client.send_line("{\"id\": 1, \"side\": \"Sideways\"}");
+    assert_eq!(ack["status"], "error");
+    assert!(wait_until(|| !log.recent(1).is_empty()));
+
+    let rejection = &log.recent(1)[0];
+    assert_eq!(rejection.reason, RejectReason::Malformed);
+    assert_eq!(rejection.order, None);
+    assert!(!rejection.detail.is_empty());
+    servers.stop();
+}
+
+#[test]
+fn a_throttled_client_is_told_so() {
+    let servers = TestServers::start();
+    {
+        let mut book = servers.order_book.lock().unwrap();
+        book.set_self_trade_prevention(SelfTradePrevention::CancelIncoming);
+        book.set_wash_trade_detection(
+            Some(WashTradeConfig { window_ns: 1_000, max_self_crosses: 1, throttle_ns: 1_000 }),
+            Arc::new(ManualClock::new(0)),
+        );
+    }
+    let mut client = GatewayClient::connect(&servers.gateway_addr);
+    client.send_line(r#"{"id":1,"side":"Sell","price":100,"quantity":1,"account_id":4}"#);
+    client.send_line(r#"{"id":2,"side":"Buy","price":100,"quantity":1,"account_id":4}"#);
+
+    let ack = client.send_line(r#"{"id":3,"side":"Buy","price":95,"quantity":1,"account_id":4}"#);
+    assert_eq!((&ack["status"], &ack["reason"]), (&json!("error"), &json!("throttled")));
+    assert_eq!(servers.order_book.lock().unwrap().resting_quantity(3), None);
+    servers.stop();
+}
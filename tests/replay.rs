@@ -0,0 +1,150 @@
+// ============================================================================
+// REPLAY DRIVER - Speed, pause/resume and step controls
+// ============================================================================
+
+use hft_ringbuffer::engine::{spawn_engine, EngineHooks};
+use hft_ringbuffer::matching_engine::{Order, OrderBook, OrderSide, Packet};
+use hft_ringbuffer::metrics::Metrics;
+use hft_ringbuffer::order_generator::{GeneratorParams, OrderGenerator};
+use hft_ringbuffer::replay::{load_recording, replay_file, RecordedOrder, Recorder, ReplayCommand, ReplayDriver};
+use hft_ringbuffer::trade_history::{TradeHistory, TradeSink};
+use std::path::PathBuf;
+use std::sync::atomic::AtomicBool;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+fn recording() -> Vec<RecordedOrder> {
+    let orders = [
+        (1, OrderSide::Sell, 101, 5),
+        (2, OrderSide::Sell, 102, 5),
+        (3, OrderSide::Buy, 101, 3),
+        (4, OrderSide::Buy, 102, 2),
+    ];
+    orders
+        .into_iter()
+        .enumerate()
+        .map(|(i, (id, side, price, quantity))| RecordedOrder {
+            recv_ns: i as u64 * 1_000_000,
+            order: Order::new(id, side, price, quantity),
+        })
+        .collect()
+}
+
+#[test]
+fn step_applies_exactly_one_order() {
+    let mut driver = ReplayDriver::new(recording());
+    driver.apply(ReplayCommand::Pause);
+
+    for expected in 1..=4 {
+        assert!(driver.apply(ReplayCommand::Step));
+        assert_eq!(driver.position(), expected);
+        assert!(driver.is_paused());
+    }
+    assert!(driver.step().is_none());
+    assert_eq!(driver.executions().len(), 2);
+}
+
+#[test]
+fn pause_halts_progress_until_resumed() {
+    let mut driver = ReplayDriver::new(recording());
+    driver.tick();
+    assert_eq!(driver.position(), 1);
+
+    driver.apply(ReplayCommand::Pause);
+    for _ in 0..10 {
+        assert!(driver.tick().is_none());
+    }
+    assert_eq!(driver.position(), 1);
+
+    driver.apply(ReplayCommand::Resume);
+    driver.tick();
+    assert_eq!(driver.position(), 2);
+}
+
+#[test]
+fn speed_scales_the_recorded_gaps() {
+    let mut driver = ReplayDriver::new(recording());
+    driver.tick();
+    assert_eq!(driver.delay_before_next(), Duration::from_millis(1));
+
+    driver.apply(ReplayCommand::parse("speed 4").unwrap());
+    assert_eq!(driver.delay_before_next(), Duration::from_micros(250));
+}
+
+#[test]
+fn command_parser_rejects_garbage() {
+    assert_eq!(ReplayCommand::parse("pause"), Ok(ReplayCommand::Pause));
+    assert!(ReplayCommand::parse("speed -1").is_err());
+    assert!(ReplayCommand::parse("rewind").is_err());
+}
+
+#[test]
+fn run_blocks_while_paused_and_finishes_after_resume() {
+    let mut driver = ReplayDriver::new(recording());
+    driver.apply(ReplayCommand::Pause);
+
+    let (tx, rx) = crossbeam_channel::unbounded();
+    tx.send(ReplayCommand::Step).unwrap();
+    tx.send(ReplayCommand::Speed(1000.0)).unwrap();
+    tx.send(ReplayCommand::Resume).unwrap();
+
+    let mut steps = 0;
+    driver.run(&rx, |_, _| steps += 1);
+    assert!(driver.is_finished());
+    assert_eq!(steps, 4);
+}
+
+fn scratch_path(name: &str) -> PathBuf {
+    std::env::temp_dir().join(format!("replay_{}_{}", std::process::id(), name))
+}
+
+#[test]
+fn replaying_a_fixed_recording_matches_the_golden_executions() {
+    let path = scratch_path("golden.jsonl");
+    let mut recorder = Recorder::create(&path).unwrap();
+    for recorded in recording() {
+        recorder.record(recorded.recv_ns, &recorded.order).unwrap();
+    }
+    recorder.flush().unwrap();
+
+    let executions = replay_file(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(
+        serde_json::to_string(&executions).unwrap(),
+        concat!(
+            r#"[{"maker_order_id":1,"taker_order_id":3,"price":101,"quantity":3,"price_improvement":0,"fee_version":0,"maker_fee":0,"taker_fee":0,"remaining_quantity":0,"is_taker_complete":true},"#,
+            r#"{"maker_order_id":1,"taker_order_id":4,"price":101,"quantity":2,"price_improvement":2,"fee_version":0,"maker_fee":0,"taker_fee":0,"remaining_quantity":0,"is_taker_complete":true}]"#,
+        )
+    );
+}
+
+#[test]
+fn a_recorded_engine_session_replays_byte_for_byte() {
+    const ORDERS: usize = 2_000;
+    let path = scratch_path("session.jsonl");
+    let (mut producer, consumer) = rtrb::RingBuffer::<Packet>::new(ORDERS);
+    for order in OrderGenerator::new(11, GeneratorParams::default()).new_orders().take(ORDERS) {
+        producer.push(Packet::new(order)).unwrap();
+    }
+
+    let history = Arc::new(Mutex::new(TradeHistory::new(ORDERS * 10)));
+    let hooks = EngineHooks {
+        trades: Some(TradeSink::Inline(history.clone())),
+        recorder: Some(Recorder::create(&path).unwrap()),
+        drain_on_shutdown: true,
+        ..Default::default()
+    };
+    let book = Arc::new(Mutex::new(OrderBook::new()));
+    let engine = spawn_engine(consumer, book.clone(), Arc::new(AtomicBool::new(true)), Arc::new(Metrics::new()), hooks).unwrap();
+    engine.join().unwrap();
+
+    let recording = load_recording(&path).unwrap();
+    assert_eq!(recording.len(), ORDERS);
+    assert!(recording.windows(2).all(|pair| pair[0].recv_ns <= pair[1].recv_ns));
+
+    let live = history.lock().unwrap().recent(usize::MAX);
+    assert!(!live.is_empty());
+    let replayed = replay_file(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(serde_json::to_vec(&replayed).unwrap(), serde_json::to_vec(&live).unwrap());
+}
@@ -0,0 +1,16 @@
+// ============================================================================
+// SELF BENCHMARK - Startup baseline reports plausible numbers
+// ============================================================================
+
+use hft_ringbuffer::self_bench::run_self_bench;
+
+#[test]
+fn self_bench_reports_nonzero_latency_and_throughput() {
+    let report = run_self_bench(10_000);
+    assert_eq!(report.orders, 10_000);
+    assert!(report.trades > 0);
+    assert!(report.p50_ns > 0);
+    assert!(report.p99_ns >= report.p50_ns);
+    assert!(report.total_ns >= report.p99_ns);
+    assert!(report.orders_per_second > 0.0 && report.orders_per_second.is_finite());
+}
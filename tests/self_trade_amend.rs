@@ -0,0 +1,72 @@
+// ============================================================================
+// SELF-TRADE ON AMEND - STP applies when an amend re-queues into a cross
+// ============================================================================
+
+use hft_ringbuffer::matching_engine::{AmendError, Order, OrderBook, OrderSide, SelfTradePrevention};
+use hft_ringbuffer::price_units::Price;
+
+const ACCOUNT: u64 = 1;
+
+/// Account 1 quotes 99 / 101; its bid is then amended up through its ask.
+fn amend_into_own_ask(mode: SelfTradePrevention) -> (OrderBook, usize) {
+    let mut book = OrderBook::new();
+    book.set_self_trade_prevention(mode);
+    book.add_limit_order(Order::new(1, OrderSide::Sell, 101, 5).with_account(ACCOUNT));
+    book.add_limit_order(Order::new(2, OrderSide::Buy, 99, 5).with_account(ACCOUNT));
+
+    let executions = book.amend_order(2, Some(Price(101)), None).unwrap();
+    (book, executions.len())
+}
+
+#[test]
+fn cancel_resting_removes_the_own_ask_instead_of_trading() {
+    let (book, trades) = amend_into_own_ask(SelfTradePrevention::CancelResting);
+    assert_eq!(trades, 0);
+    assert_eq!(book.best_ask(), None);
+    assert_eq!(book.walk_bids().next(), Some((Price(101), 5)));
+    assert_eq!(book.position(ACCOUNT), None);
+}
+
+#[test]
+fn cancel_incoming_drops_the_amended_order() {
+    let (book, trades) = amend_into_own_ask(SelfTradePrevention::CancelIncoming);
+    assert_eq!(trades, 0);
+    assert_eq!(book.best_bid(), None);
+    assert_eq!(book.walk_asks().next(), Some((Price(101), 5)));
+}
+
+#[test]
+fn without_stp_the_amend_self_trades() {
+    let (book, trades) = amend_into_own_ask(SelfTradePrevention::Off);
+    assert_eq!(trades, 1);
+    assert_eq!((book.best_bid(), book.best_ask()), (None, None));
+}
+
+#[test]
+fn amend_matches_other_accounts_normally() {
+    let mut book = OrderBook::new();
+    book.set_self_trade_prevention(SelfTradePrevention::CancelIncoming);
+    book.add_limit_order(Order::new(1, OrderSide::Sell, 101, 3).with_account(2));
+    book.add_limit_order(Order::new(2, OrderSide::Buy, 99, 5).with_account(ACCOUNT));
+
+    let executions = book.amend_order(2, Some(Price(101)), None).unwrap();
+    assert_eq!(executions.len(), 1);
+    assert_eq!(executions[0].quantity, 3);
+    assert_eq!(book.walk_bids().next(), Some((Price(101), 2)));
+}
+
+#[test]
+fn fresh_submissions_use_the_same_policy() {
+    let mut book = OrderBook::new();
+    book.set_self_trade_prevention(SelfTradePrevention::CancelResting);
+    book.add_limit_order(Order::new(1, OrderSide::Sell, 101, 5).with_account(ACCOUNT));
+    let executions = book.add_limit_order(Order::new(2, OrderSide::Buy, 101, 5).with_account(ACCOUNT));
+    assert!(executions.is_empty());
+    assert_eq!(book.walk_bids().next(), Some((Price(101), 5)));
+}
+
+#[test]
+fn amending_an_unknown_order_fails() {
+    let mut book = OrderBook::new();
+    assert_eq!(book.amend_order(42, Some(Price(1)), None), Err(AmendError::UnknownOrder(42)));
+}
@@ -0,0 +1,97 @@
+// ============================================================================
+// SELF-TRADE PREVENTION - Same-account crosses never trade
+// ============================================================================
+
+use hft_ringbuffer::clock::ManualClock;
+use hft_ringbuffer::iceberg::IcebergRefresh;
+use hft_ringbuffer::matching_engine::{Order, OrderBook, OrderSide, SelfTradeAction, SelfTradePrevention};
+use hft_ringbuffer::price_units::Price;
+use std::sync::Arc;
+
+const ACCOUNT: u64 = 7;
+
+/// Asks at 100 from another account, then two of our own at 100 and 101
+fn book_with_own_asks(mode: SelfTradePrevention) -> OrderBook {
+    let mut book = OrderBook::new();
+    book.set_self_trade_prevention(mode);
+    book.add_limit_order(Order::new(1, OrderSide::Sell, 100, 2).with_account(3));
+    book.add_limit_order(Order::new(2, OrderSide::Sell, 100, 2).with_account(ACCOUNT));
+    book.add_limit_order(Order::new(3, OrderSide::Sell, 101, 2).with_account(ACCOUNT));
+    book.add_limit_order(Order::new(4, OrderSide::Sell, 102, 2).with_account(3));
+    book
+}
+
+#[test]
+fn cancel_resting_removes_own_orders_and_keeps_matching() {
+    let mut book = book_with_own_asks(SelfTradePrevention::CancelResting);
+    let executions = book.add_limit_order(Order::new(10, OrderSide::Buy, 102, 4).with_account(ACCOUNT));
+
+    let makers: Vec<u64> = executions.iter().map(|e| e.maker_order_id).collect();
+    assert_eq!(makers, vec![1, 4]);
+    assert_eq!(
+        book.last_self_trade(),
+        Some(&SelfTradeAction {
+            taker_order_id: 10,
+            mode: SelfTradePrevention::CancelResting,
+            cancelled_resting: vec![2, 3],
+            cancelled_incoming_quantity: 0,
+        })
+    );
+    assert_eq!(book.cancel_order(2), None);
+    assert_eq!(book.best_ask(), None);
+    book.validate().unwrap();
+}
+
+#[test]
+fn cancel_incoming_stops_at_the_own_order_and_drops_the_rest() {
+    let mut book = book_with_own_asks(SelfTradePrevention::CancelIncoming);
+    let executions = book.add_limit_order(Order::new(10, OrderSide::Buy, 102, 5).with_account(ACCOUNT));
+
+    let makers: Vec<u64> = executions.iter().map(|e| e.maker_order_id).collect();
+    assert_eq!(makers, vec![1]);
+    let action = book.last_self_trade().unwrap();
+    assert_eq!(action.mode, SelfTradePrevention::CancelIncoming);
+    assert!(action.cancelled_resting.is_empty());
+    assert_eq!(action.cancelled_incoming_quantity, 3);
+    // Nothing of the incoming order rests, and our asks are untouched
+    assert_eq!(book.best_bid(), None);
+    assert_eq!(book.walk_asks().next(), Some((Price(100), 2)));
+}
+
+#[test]
+fn no_action_is_reported_when_nothing_self_crosses() {
+    let mut book = book_with_own_asks(SelfTradePrevention::CancelResting);
+    book.add_limit_order(Order::new(10, OrderSide::Buy, 102, 5).with_account(ACCOUNT));
+    book.add_limit_order(Order::new(11, OrderSide::Sell, 102, 1).with_account(ACCOUNT));
+    book.add_limit_order(Order::new(12, OrderSide::Buy, 102, 1).with_account(5));
+    assert_eq!(book.last_self_trade(), None);
+}
+
+#[test]
+fn cancel_resting_forgets_an_iceberg_and_its_peg() {
+    let clock = Arc::new(ManualClock::new(5_000));
+    let mut book = OrderBook::new();
+    book.set_self_trade_prevention(SelfTradePrevention::CancelResting);
+    book.set_iceberg_refresh(IcebergRefresh { slice_variance: 0, min_refresh_interval_ns: 1_000, seed: 0 }, clock.clone());
+    book.update_reference_price(100);
+    book.add_limit_order(Order::new(1, OrderSide::Sell, 0, 50).with_display_quantity(10).pegged_to_reference(0).with_account(ACCOUNT));
+    // Another account lifts the first slice once it may refresh, so it does
+    clock.advance(1_000);
+    book.add_limit_order(Order::new(2, OrderSide::Buy, 100, 10).with_account(3));
+    assert_eq!(book.walk_asks().next(), Some((Price(100), 10)));
+
+    book.add_limit_order(Order::new(3, OrderSide::Buy, 100, 5).with_account(ACCOUNT));
+    assert_eq!(book.last_self_trade().unwrap().cancelled_resting, vec![1]);
+    assert_eq!(book.best_ask(), None);
+    assert_eq!(book.resting_quantity(1), None);
+
+    // The hidden reserve went with it, however long we wait
+    clock.advance(10_000);
+    assert!(book.add_limit_order(Order::new(4, OrderSide::Buy, 100, 40).with_account(5)).is_empty());
+
+    // A later order reusing the id is not moved by the old peg
+    book.add_limit_order(Order::new(1, OrderSide::Sell, 105, 10).with_account(3));
+    book.update_reference_price(110);
+    assert_eq!(book.best_ask(), Some(Price(105)));
+    book.validate().unwrap();
+}
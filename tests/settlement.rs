@@ -0,0 +1,95 @@
+// ============================================================================
+// SETTLEMENT - Session close fixes a settlement price
+// ============================================================================
+
+mod common;
+
+use common::{http_request, TestServers};
+use hft_ringbuffer::clock::ManualClock;
+use hft_ringbuffer::matching_engine::{Order, OrderBook, OrderSide, TradingState};
+use hft_ringbuffer::settlement::{Settlement, SettlementMethod, SettlementSource};
+use hft_ringbuffer::price_units::Price;
+use std::sync::Arc;
+
+const MINUTE_NS: u64 = 60 * 1_000_000_000;
+
+fn trade(book: &mut OrderBook, id: u64, price: u64, quantity: u64) {
+    book.add_limit_order(Order::new(id, OrderSide::Sell, price, quantity));
+    book.add_limit_order(Order::new(id + 1, OrderSide::Buy, price, quantity));
+}
+
+#[test]
+fn vwap_settlement_uses_only_the_closing_window() {
+    let clock = Arc::new(ManualClock::new(0));
+    let mut book = OrderBook::new();
+    book.set_settlement_method("vwap:1".parse().unwrap(), clock.clone());
+
+    trade(&mut book, 1, 90, 10); // Too early to count
+    clock.advance(2 * MINUTE_NS);
+    trade(&mut book, 3, 101, 2);
+    clock.advance(MINUTE_NS / 2);
+    trade(&mut book, 5, 104, 1);
+    clock.advance(MINUTE_NS / 10);
+
+    let settlement = book.set_trading_state(TradingState::Closed).unwrap();
+    // (101 * 2 + 104 * 1) / 3 = 102
+    assert_eq!(
+        settlement,
+        Settlement { price: Price(102), source: SettlementSource::Vwap, settled_at_ns: 2 * MINUTE_NS + 6 * MINUTE_NS / 10 }
+    );
+    assert_eq!(book.settlement(), Some(settlement));
+
+    // Closed books take no orders
+    assert!(book.add_limit_order(Order::new(7, OrderSide::Buy, 100, 1)).is_empty());
+    assert_eq!(book.best_bid(), None);
+}
+
+#[test]
+fn sessions_without_trades_fall_back_to_mid_then_prior() {
+    let clock = Arc::new(ManualClock::new(0));
+    let mut book = OrderBook::new();
+    book.set_settlement_method(SettlementMethod::LastTrade, clock);
+    book.add_limit_order(Order::new(1, OrderSide::Buy, 98, 1));
+    book.add_limit_order(Order::new(2, OrderSide::Sell, 104, 1));
+
+    let first = book.set_trading_state(TradingState::Closed).unwrap();
+    assert_eq!((first.price.units(), first.source), (101, SettlementSource::MidAtClose));
+
+    // Next session: the ask is taken and nothing else happens
+    assert_eq!(book.set_trading_state(TradingState::Open), None);
+    trade(&mut book, 3, 104, 1);
+    book.add_limit_order(Order::new(5, OrderSide::Sell, 98, 1));
+    let second = book.set_trading_state(TradingState::Closed).unwrap();
+    assert_eq!((second.price.units(), second.source), (98, SettlementSource::LastTrade));
+
+    // A third session with no trades and an empty book keeps the prior price
+    book.set_trading_state(TradingState::Open);
+    let third = book.set_trading_state(TradingState::Closed).unwrap();
+    assert_eq!((third.price.units(), third.source), (98, SettlementSource::PriorSettlement));
+}
+
+#[test]
+fn closing_over_http_serves_the_settlement() {
+    let servers = TestServers::start();
+    {
+        let mut book = servers.order_book.lock().unwrap();
+        book.set_settlement_method(SettlementMethod::LastTrade, Arc::new(ManualClock::new(0)));
+        trade(&mut book, 1, 250, 3);
+    }
+    let (status, _) = http_request(&servers.http_addr, "GET", "/api/settlement", "");
+    assert_eq!(status, 404);
+
+    let (status, body) = http_request(&servers.http_addr, "POST", "/api/trading-state", r#"{"state": "closed"}"#);
+    assert_eq!(status, 200);
+    let body: serde_json::Value = serde_json::from_str(&body).unwrap();
+    assert_eq!(body["settlement"]["price"], 250);
+
+    let (status, body) = http_request(&servers.http_addr, "GET", "/api/settlement", "");
+    assert_eq!(status, 200);
+    let body: serde_json::Value = serde_json::from_str(&body).unwrap();
+    assert_eq!(body["source"], "last_trade");
+
+    let order = r#"{"id": 9, "side": "Buy", "price": 250, "quantity": 1}"#;
+    assert_eq!(http_request(&servers.http_addr, "POST", "/api/order", order).0, 400);
+    servers.stop();
+}
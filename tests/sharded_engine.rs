@@ -0,0 +1,275 @@
+// ============================================================================
+// SHARDED ENGINE - Symbols hashed across K engine threads
+// ============================================================================
+
+mod common;
+
+use common::GatewayClient;
+use hft_ringbuffer::bbo::BboPublisher;
+use hft_ringbuffer::clock::ManualClock;
+use hft_ringbuffer::engine::{EngineHooks, SymbolBooks};
+use hft_ringbuffer::events::{BookEvent, EventBus};
+use hft_ringbuffer::funnel::{FunnelConfig, SubmitError};
+use hft_ringbuffer::gateway::{bind_gateway, spawn_gateway};
+use hft_ringbuffer::matching_engine::{Order, OrderBook, OrderSide, Packet};
+use hft_ringbuffer::metrics::Metrics;
+use hft_ringbuffer::rejections::{RejectReason, RejectionLog};
+use hft_ringbuffer::sharded_engine::{join_all, shard_for, spawn_sharded_engines, ShardedEngineConfig, ShardedEngines};
+use hft_ringbuffer::shards::ShardConfig;
+use hft_ringbuffer::trade_history::{TradeHistory, TradeSink};
+use std::collections::BTreeSet;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+const SYMBOLS: [&str; 6] = ["BTC", "ETH", "SOL", "DOGE", "ADA", "XRP"];
+
+struct Running {
+    sharded: ShardedEngines,
+    books: SymbolBooks,
+    rejections: Arc<RejectionLog>,
+    metrics: Arc<Metrics>,
+    ingress_shutdown: Arc<AtomicBool>,
+    engine_shutdown: Arc<AtomicBool>,
+}
+
+fn start(shards: usize) -> Running {
+    start_with_rings(shards, ShardConfig::new(1024))
+}
+
+fn start_with_rings(shards: usize, rings: ShardConfig) -> Running {
+    start_with_hooks(shards, rings, |_| EngineHooks::default())
+}
+
+/// `hooks` for each shard, plus the shared rejection log
+fn start_with_hooks(shards: usize, rings: ShardConfig, mut hooks: impl FnMut(usize) -> EngineHooks) -> Running {
+    let books: SymbolBooks =
+        SYMBOLS.iter().map(|symbol| (symbol.to_string(), Arc::new(Mutex::new(OrderBook::new())))).collect();
+    let rejections = Arc::new(RejectionLog::new(Arc::new(ManualClock::new(0)), None, 1, 64));
+    let ingress_shutdown = Arc::new(AtomicBool::new(false));
+    let engine_shutdown = Arc::new(AtomicBool::new(false));
+    let config = ShardedEngineConfig {
+        shards,
+        rings,
+        default_symbol: "BTC".to_string(),
+        funnel: FunnelConfig::default(),
+    };
+    let hooks = |shard| EngineHooks { rejections: Some(rejections.clone()), ..hooks(shard) };
+    let metrics = Arc::new(Metrics::new());
+    let sharded = spawn_sharded_engines(
+        &config,
+        books.clone(),
+        hooks,
+        metrics.clone(),
+        ingress_shutdown.clone(),
+        engine_shutdown.clone(),
+    )
+    .unwrap();
+    Running { sharded, books, rejections, metrics, ingress_shutdown, engine_shutdown }
+}
+
+fn submit(running: &Running, symbol: &str, order: Order) {
+    let funnel = running.sharded.routes.funnel_for(Some(symbol)).expect("symbol is routed");
+    let mut packet = Packet::new(order.for_symbol(symbol));
+    loop {
+        match funnel.submit(packet) {
+            Ok(()) => return,
+            Err(SubmitError::Backpressure(back)) => {
+                packet = back;
+                thread::yield_now();
+            }
+            Err(SubmitError::Closed(_)) => panic!("funnel for {} closed", symbol),
+        }
+    }
+}
+
+fn resting_ids(book: &Arc<Mutex<OrderBook>>) -> Vec<u64> {
+    book.lock().unwrap().resting_orders().iter().map(|order| order.id).collect()
+}
+
+fn wait_for(mut done: impl FnMut() -> bool) {
+    let deadline = Instant::now() + Duration::from_secs(5);
+    while !done() {
+        assert!(Instant::now() < deadline, "engines did not catch up");
+        thread::sleep(Duration::from_millis(5));
+    }
+}
+
+fn stop(running: Running) -> SymbolBooks {
+    running.ingress_shutdown.store(true, Ordering::Relaxed);
+    for forwarder in running.sharded.forwarders {
+        forwarder.join().unwrap();
+    }
+    running.engine_shutdown.store(true, Ordering::Relaxed);
+    join_all(running.sharded.engines).unwrap().join().unwrap();
+    running.books
+}
+
+#[test]
+fn shard_for_is_stable_and_in_range() {
+    for shards in 1..8 {
+        for symbol in SYMBOLS {
+            let shard = shard_for(symbol, shards);
+            assert!(shard < shards);
+            assert_eq!(shard, shard_for(symbol, shards));
+        }
+    }
+    assert!(SYMBOLS.iter().all(|symbol| shard_for(symbol, 1) == 0));
+    // Zero shards is treated as one
+    assert_eq!(shard_for("BTC", 0), 0);
+}
+
+#[test]
+fn every_book_lands_on_its_hashed_shard() {
+    let running = start(3);
+    let assignment = running.sharded.assignment.clone();
+    assert_eq!(assignment.len(), 3);
+    for (index, symbols) in assignment.iter().enumerate() {
+        assert!(symbols.iter().all(|symbol| shard_for(symbol, 3) == index));
+    }
+    assert_eq!(assignment.iter().map(Vec::len).sum::<usize>(), SYMBOLS.len());
+
+    let names: Vec<_> = running.sharded.engines.iter().map(|engine| engine.thread().name().map(str::to_string)).collect();
+    assert_eq!(names, (0..3).map(|index| Some(format!("engine-shard-{}", index))).collect::<Vec<_>>());
+    stop(running);
+}
+
+#[test]
+fn interleaved_symbols_keep_their_own_books_and_arrival_order() {
+    const PER_SYMBOL: u64 = 500;
+    let running = start(3);
+
+    // Resting bids at one price, so each book's queue is its arrival order
+    let mut expected = vec![Vec::new(); SYMBOLS.len()];
+    for round in 0..PER_SYMBOL {
+        for (index, symbol) in SYMBOLS.iter().enumerate() {
+            let id = round * SYMBOLS.len() as u64 + index as u64 + 1;
+            submit(&running, symbol, Order::new(id, OrderSide::Buy, 100, 1));
+            expected[index].push(id);
+        }
+    }
+
+    wait_for(|| running.books.values().all(|book| resting_ids(book).len() == PER_SYMBOL as usize));
+    let books = stop(running);
+    for (index, symbol) in SYMBOLS.iter().enumerate() {
+        assert_eq!(resting_ids(&books[*symbol]), expected[index], "{}", symbol);
+    }
+}
+
+#[test]
+fn symbols_on_the_same_shard_never_cross() {
+    let running = start(1);
+    submit(&running, "ETH", Order::new(1, OrderSide::Sell, 100, 5));
+    submit(&running, "SOL", Order::new(2, OrderSide::Buy, 100, 5));
+
+    wait_for(|| resting_ids(&running.books["SOL"]) == vec![2]);
+    let books = stop(running);
+    assert_eq!(resting_ids(&books["ETH"]), vec![1]);
+    assert!(books["ETH"].lock().unwrap().recent_trades(10).is_empty());
+}
+
+#[test]
+fn orders_for_a_book_the_shard_does_not_hold_are_rejected() {
+    let running = start(2);
+    // Reaches BTC's engine, which has no LTC book
+    let funnel = running.sharded.routes.funnel_for(None).unwrap();
+    funnel.submit(Packet::new(Order::new(7, OrderSide::Buy, 100, 1).for_symbol("LTC"))).unwrap();
+
+    wait_for(|| running.rejections.counts().get(&RejectReason::UnknownSymbol) == Some(&1));
+    stop(running);
+}
+
+#[test]
+fn symbols_without_a_startup_book_are_refused_at_the_gateway() {
+    let running = start(2);
+    assert!(running.sharded.routes.funnel_for(Some("LTC")).is_none());
+    let (listener, addr) = bind_gateway("127.0.0.1:0").unwrap();
+    let gateway = spawn_gateway(listener, running.sharded.routes.clone(), running.metrics.ingress().clone(), running.ingress_shutdown.clone()).unwrap();
+
+    let mut client = GatewayClient::connect(&addr.to_string());
+    let ack = client.send_line(r#"{"id":1,"side":"Buy","price":100,"quantity":1,"symbol":"LTC"}"#);
+    assert_eq!(ack["reason"], "unknown_symbol");
+    drop(client);
+    let books = stop(running);
+    gateway.join().unwrap();
+    assert!(!books.contains_key("LTC"));
+}
+
+#[test]
+fn each_shard_publishes_bbos_and_trades_tagged_with_their_symbol() {
+    let bus = Arc::new(EventBus::new());
+    let events = bus.subscribe();
+    let history = Arc::new(Mutex::new(TradeHistory::new(16)));
+    let running = start_with_hooks(3, ShardConfig::new(1024), |_| EngineHooks {
+        bbo: Some(BboPublisher::new(0, Arc::new(ManualClock::new(0)), bus.clone())),
+        trades: Some(TradeSink::Inline(history.clone())),
+        ..Default::default()
+    });
+    for symbol in ["ETH", "SOL"] {
+        submit(&running, symbol, Order::new(1, OrderSide::Sell, 100, 1));
+        submit(&running, symbol, Order::new(2, OrderSide::Buy, 100, 1));
+    }
+    // Orders for the default book need not name it
+    let funnel = running.sharded.routes.funnel_for(None).unwrap();
+    funnel.submit(Packet::new(Order::new(1, OrderSide::Sell, 100, 1))).unwrap();
+    funnel.submit(Packet::new(Order::new(2, OrderSide::Buy, 100, 1))).unwrap();
+    wait_for(|| history.lock().unwrap().len() == 3);
+    stop(running);
+
+    let traded: BTreeSet<Option<String>> = history.lock().unwrap().recent(3).into_iter().map(|trade| trade.symbol).collect();
+    assert_eq!(traded, ["BTC", "ETH", "SOL"].map(|symbol| Some(symbol.to_string())).into());
+    let quoted: BTreeSet<Option<String>> = events
+        .try_iter()
+        .filter_map(|message| match message.event {
+            BookEvent::Bbo { symbol, .. } => Some(symbol),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(quoted, traded);
+}
+
+#[test]
+fn a_missing_default_book_is_an_error() {
+    let books = SymbolBooks::from([("ETH".to_string(), Arc::new(Mutex::new(OrderBook::new())))]);
+    let config = ShardedEngineConfig {
+        shards: 2,
+        rings: ShardConfig::new(16),
+        default_symbol: "BTC".to_string(),
+        funnel: FunnelConfig::default(),
+    };
+    let shutdown = Arc::new(AtomicBool::new(false));
+    let result = spawn_sharded_engines(&config, books, |_| EngineHooks::default(), Arc::new(Metrics::new()), shutdown.clone(), shutdown);
+    assert!(result.is_err_and(|error| error.to_string().contains("BTC")));
+}
+
+#[test]
+fn every_symbol_gets_a_ring_sized_by_the_shard_config() {
+    let running = start_with_rings(2, ShardConfig::new(64).with_capacity("BTC", 2048).with_capacity("DOGE", 16));
+    for round in 0..10 {
+        submit(&running, "DOGE", Order::new(round + 1, OrderSide::Buy, 100, 1));
+    }
+    wait_for(|| resting_ids(&running.books["DOGE"]).len() == 10);
+
+    let occupancy = running.metrics.shard_occupancy();
+    let capacities: Vec<_> = occupancy.iter().map(|shard| (shard.symbol.as_str(), shard.capacity)).collect();
+    assert_eq!(capacities, [("ADA", 64), ("BTC", 2048), ("DOGE", 16), ("ETH", 64), ("SOL", 64), ("XRP", 64)]);
+    let doge = occupancy.iter().find(|shard| shard.symbol == "DOGE").unwrap();
+    assert_eq!((doge.accepted, doge.dropped), (10, 0));
+    assert!(running.metrics.render_prometheus().contains("shard_ring_capacity{symbol=\"BTC\"} 2048"));
+    stop(running);
+}
+
+#[test]
+fn ring_capacities_must_be_powers_of_two() {
+    let books = SymbolBooks::from([("BTC".to_string(), Arc::new(Mutex::new(OrderBook::new())))]);
+    let config = ShardedEngineConfig {
+        shards: 1,
+        rings: ShardConfig::new(16).with_capacity("BTC", 1000),
+        default_symbol: "BTC".to_string(),
+        funnel: FunnelConfig::default(),
+    };
+    let shutdown = Arc::new(AtomicBool::new(false));
+    let result = spawn_sharded_engines(&config, books, |_| EngineHooks::default(), Arc::new(Metrics::new()), shutdown.clone(), shutdown);
+    assert!(result.is_err_and(|error| error.to_string().contains("1000")));
+}
@@ -0,0 +1,84 @@
+// ============================================================================
+// SHUTDOWN DRAIN - Buffered orders are matched before the engine stops
+// ============================================================================
+
+use hft_ringbuffer::engine::{spawn_engine, EngineHooks, PhasedShutdown};
+use hft_ringbuffer::funnel::{spawn_funnel, FunnelConfig, FunnelStats};
+use hft_ringbuffer::matching_engine::{Order, OrderBook, OrderSide, Packet};
+use hft_ringbuffer::metrics::Metrics;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+const RING_CAPACITY: usize = 16;
+
+fn resting_quantity(book: &Mutex<OrderBook>) -> u64 {
+    book.lock().unwrap().walk_bids().map(|(_, quantity)| quantity).sum()
+}
+
+#[test]
+fn full_ring_is_drained_before_the_engine_joins() {
+    let (mut producer, consumer) = rtrb::RingBuffer::<Packet>::new(RING_CAPACITY);
+    for id in 0..RING_CAPACITY as u64 {
+        producer.push(Packet::new(Order::new(id, OrderSide::Buy, 100 + id, 1))).unwrap();
+    }
+    assert!(producer.is_full());
+
+    // Shutdown is already requested when the engine first looks
+    let book = Arc::new(Mutex::new(OrderBook::new()));
+    let metrics = Arc::new(Metrics::new());
+    let hooks = EngineHooks { drain_on_shutdown: true, ..Default::default() };
+    let engine = spawn_engine(consumer, book.clone(), Arc::new(AtomicBool::new(true)), metrics.clone(), hooks).unwrap();
+    engine.join().unwrap();
+
+    assert_eq!(resting_quantity(&book), RING_CAPACITY as u64);
+    assert_eq!(metrics.drained_on_shutdown(), RING_CAPACITY as u64);
+}
+
+#[test]
+fn without_draining_buffered_orders_are_left_behind() {
+    let (mut producer, consumer) = rtrb::RingBuffer::<Packet>::new(RING_CAPACITY);
+    producer.push(Packet::new(Order::new(1, OrderSide::Buy, 100, 1))).unwrap();
+
+    let book = Arc::new(Mutex::new(OrderBook::new()));
+    let metrics = Arc::new(Metrics::new());
+    let engine = spawn_engine(consumer, book.clone(), Arc::new(AtomicBool::new(true)), metrics.clone(), EngineHooks::default()).unwrap();
+    engine.join().unwrap();
+
+    assert_eq!(resting_quantity(&book), 0);
+    assert_eq!(metrics.drained_on_shutdown(), 0);
+}
+
+#[test]
+fn phased_shutdown_flushes_the_funnel_and_the_ring() {
+    const ORDERS: u64 = 200;
+    let (producer, consumer) = rtrb::RingBuffer::<Packet>::new(RING_CAPACITY);
+    let phases = PhasedShutdown::new();
+    let book = Arc::new(Mutex::new(OrderBook::new()));
+    let metrics = Arc::new(Metrics::new());
+
+    let hooks = EngineHooks { drain_on_shutdown: true, ..Default::default() };
+    let engine = spawn_engine(consumer, book.clone(), phases.engine_flag(), metrics.clone(), hooks).unwrap();
+    let config = FunnelConfig { drain_on_shutdown: true, ..Default::default() };
+    let stats = Arc::new(FunnelStats::new());
+    let (funnel, forwarder) = spawn_funnel(producer, config, stats.clone(), phases.ingress_flag()).unwrap();
+
+    // Stall the engine so orders pile up in the ring and behind it in the funnel
+    let stalled = book.lock().unwrap();
+    for id in 0..ORDERS {
+        funnel.submit(Packet::new(Order::new(id, OrderSide::Buy, 100, 1))).unwrap();
+    }
+    let stopper = {
+        let phases = phases.clone();
+        thread::spawn(move || phases.run(vec![forwarder], engine))
+    };
+    thread::sleep(Duration::from_millis(50));
+    assert!(funnel.submit(Packet::new(Order::new(ORDERS, OrderSide::Buy, 100, 1))).is_err(), "ingress is stopped");
+    drop(stalled);
+
+    stopper.join().unwrap().unwrap();
+    assert_eq!(resting_quantity(&book), ORDERS);
+    assert_eq!(stats.in_flight(), 0);
+    assert!(phases.engine_flag().load(Ordering::Relaxed));
+}
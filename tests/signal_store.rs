@@ -0,0 +1,87 @@
+// ============================================================================
+// SIGNAL STORE - Trader decisions live in injected state, not globals
+// ============================================================================
+
+mod common;
+
+use common::{http_request, TestServers};
+use hft_ringbuffer::signal_store::{AiDecision, CryptoDecision, SignalStore};
+
+#[test]
+fn store_starts_neutral_and_keeps_the_latest_post() {
+    let store = SignalStore::new();
+    let initial: serde_json::Value = serde_json::from_str(&store.ai_decision()).unwrap();
+    assert_eq!(initial["signal"], "NEUTRAL");
+    let initial: serde_json::Value = serde_json::from_str(&store.crypto_decision()).unwrap();
+    assert_eq!(initial["signals"]["btc"], "HOLD");
+
+    store.set_ai_decision(&AiDecision::parse(r#"{"signal":"BUY"}"#).unwrap());
+    store.set_ai_decision(&AiDecision::parse(r#"{"signal":"SELL"}"#).unwrap());
+    assert_eq!(store.ai_decision(), r#"{"signal":"SELL","reasoning":""}"#);
+}
+
+#[test]
+fn decisions_are_checked_and_stored_in_canonical_form() {
+    // Extra fields survive; known fields come back first, compact
+    let posted = r#"{ "signal": "BUY", "confidence": 90, "positions": {"btc": {"qty": 1}}, "reasoning": "trend" }"#;
+    let decision = AiDecision::parse(posted).unwrap();
+    assert_eq!(decision.confidence, Some(90.0));
+    assert_eq!(
+        serde_json::to_string(&decision).unwrap(),
+        r#"{"signal":"BUY","reasoning":"trend","confidence":90.0,"positions":{"btc":{"qty":1}}}"#
+    );
+    assert_eq!(AiDecision::parse(&serde_json::to_string(&decision).unwrap()).unwrap(), decision);
+
+    for bad in ["", "not json", "[1,2]", r#"{"reasoning":"no signal"}"#, r#"{"signal":""}"#, r#"{"signal":7}"#, r#"{"signal":"BUY","confidence":150}"#] {
+        assert!(AiDecision::parse(bad).is_err(), "{}", bad);
+    }
+    assert!(CryptoDecision::parse(r#"{"signals":{"btc":"BUY"}}"#).is_ok());
+    for bad in [r#"{"signals":"BUY"}"#, r#"{"signals":{"btc":""}}"#, r#"{"reasoning":"x"}"#] {
+        assert!(CryptoDecision::parse(bad).is_err(), "{}", bad);
+    }
+}
+
+#[test]
+fn posted_decisions_land_in_the_servers_own_store() {
+    let servers = TestServers::start();
+    let other = TestServers::start();
+
+    let (status, _) = http_request(&servers.http_addr, "POST", "/api/ai-decision", r#"{"signal":"BUY"}"#);
+    assert_eq!(status, 200);
+    assert_eq!(servers.signals.ai_decision(), r#"{"signal":"BUY","reasoning":""}"#);
+    let (_, body) = http_request(&servers.http_addr, "GET", "/api/ai-decision", "");
+    assert_eq!(body, r#"{"signal":"BUY","reasoning":""}"#);
+
+    let (status, _) = http_request(&servers.http_addr, "POST", "/api/crypto-decision", r#"{"signals":{"btc":"BUY"}}"#);
+    assert_eq!(status, 200);
+    let (_, body) = http_request(&servers.http_addr, "GET", "/api/crypto-decision", "");
+    assert_eq!(body, r#"{"signals":{"btc":"BUY"},"reasoning":""}"#);
+
+    // A second server in the same process is untouched
+    assert_eq!(other.signals.ai_decision(), SignalStore::new().ai_decision());
+    let (_, body) = http_request(&other.http_addr, "GET", "/api/ai-decision", "");
+    assert_eq!(body, SignalStore::new().ai_decision());
+    servers.stop();
+    other.stop();
+}
+
+#[test]
+fn garbage_posts_get_400_and_leave_the_stored_decision_alone() {
+    let servers = TestServers::start();
+    let before = servers.signals.ai_decision();
+
+    for (path, body) in [
+        ("/api/ai-decision", "<script>alert(1)</script>"),
+        ("/api/ai-decision", r#"{"signal": "BUY""#),
+        ("/api/crypto-decision", r#"{"signals": ["btc"]}"#),
+    ] {
+        let (status, response) = http_request(&servers.http_addr, "POST", path, body);
+        assert_eq!(status, 400, "{} {}", path, body);
+        let error: serde_json::Value = serde_json::from_str(&response).unwrap();
+        assert_eq!(error["status"], "error");
+    }
+    assert_eq!(servers.signals.ai_decision(), before);
+    let (_, body) = http_request(&servers.http_addr, "GET", "/api/crypto-decision", "");
+    assert_eq!(body, SignalStore::new().crypto_decision());
+    servers.stop();
+}
@@ -0,0 +1,59 @@
+// ============================================================================
+// BULK SNAPSHOT - Top-N depth for every symbol in one response
+// ============================================================================
+
+mod common;
+
+use common::{http_request, TestServers};
+use hft_ringbuffer::matching_engine::{MatchingEngine, Order, OrderBook, OrderSide, MAX_SNAPSHOT_DEPTH};
+use hft_ringbuffer::price_units::Price;
+use std::sync::{Arc, Mutex};
+
+fn quote(book: &mut OrderBook, base: u64) {
+    for i in 0..3 {
+        book.add_limit_order(Order::new(base + i, OrderSide::Buy, base - i, 1 + i));
+        book.add_limit_order(Order::new(base + 10 + i, OrderSide::Sell, base + 1 + i, 1 + i));
+    }
+}
+
+#[test]
+fn snapshot_all_covers_every_symbol_at_the_requested_depth() {
+    let mut engine = MatchingEngine::new();
+    quote(engine.get_or_create("BTC"), 100);
+    quote(engine.get_or_create("ETH"), 50);
+
+    let snapshot = engine.snapshot_all(2);
+    assert_eq!(snapshot.keys().collect::<Vec<_>>(), vec!["BTC", "ETH"]);
+    let btc = &snapshot["BTC"];
+    assert_eq!((btc.best_bid, btc.best_ask), (Some(Price(100)), Some(Price(101))));
+    assert_eq!(btc.bids, vec![(Price(100), 1), (Price(99), 2)]);
+    assert_eq!(btc.asks, vec![(Price(101), 1), (Price(102), 2)]);
+    assert_eq!((snapshot["ETH"].best_bid, snapshot["ETH"].best_ask), (Some(Price(50)), Some(Price(51))));
+
+    // Depth is bounded however much is asked for
+    let book = engine.get_or_create("BTC");
+    for i in 0..MAX_SNAPSHOT_DEPTH as u64 + 10 {
+        book.add_limit_order(Order::new(1_000 + i, OrderSide::Sell, 200 + i, 1));
+    }
+    assert_eq!(engine.snapshot_all(usize::MAX)["BTC"].asks.len(), MAX_SNAPSHOT_DEPTH);
+}
+
+#[test]
+fn orderbook_all_endpoint_serves_each_registered_book() {
+    let servers = TestServers::start();
+    for (symbol, base) in [("BTC", 100), ("ETH", 50)] {
+        let mut book = OrderBook::new();
+        quote(&mut book, base);
+        servers.metrics.register_symbol_book(symbol, Arc::new(Mutex::new(book)));
+    }
+
+    let (status, body) = http_request(&servers.http_addr, "GET", "/api/orderbook/all?depth=1", "");
+    assert_eq!(status, 200);
+    let body: serde_json::Value = serde_json::from_str(&body).unwrap();
+    assert_eq!(body["BTC"]["best_bid"], 100);
+    assert_eq!(body["BTC"]["best_ask"], 101);
+    assert_eq!(body["ETH"]["best_bid"], 50);
+    assert_eq!(body["ETH"]["best_ask"], 51);
+    assert_eq!(body["ETH"]["bids"], serde_json::json!([[50, 1]]));
+    servers.stop();
+}
@@ -0,0 +1,65 @@
+// ============================================================================
+// SPEED BUMP - New quotes are not matchable until the delay has passed
+// ============================================================================
+
+use hft_ringbuffer::clock::ManualClock;
+use hft_ringbuffer::matching_engine::{MatchingEngine, Order, OrderBook, OrderSide};
+use hft_ringbuffer::price_units::Price;
+use std::sync::Arc;
+
+const DELAY_NS: u64 = 500;
+
+fn bumped_book() -> (OrderBook, Arc<ManualClock>) {
+    let clock = Arc::new(ManualClock::new(1_000));
+    let mut book = OrderBook::new();
+    book.set_speed_bump(DELAY_NS, clock.clone());
+    (book, clock)
+}
+
+#[test]
+fn aggressor_inside_the_window_skips_the_new_quote() {
+    let (mut book, clock) = bumped_book();
+    book.add_limit_order(Order::new(1, OrderSide::Sell, 101, 5));
+    clock.advance(DELAY_NS);
+    // Fresh quote at a better price, still behind its speed bump
+    book.add_limit_order(Order::new(2, OrderSide::Sell, 100, 5));
+    assert_eq!(book.best_ask(), Some(Price(100)));
+
+    clock.advance(DELAY_NS - 1);
+    let executions = book.add_limit_order(Order::new(3, OrderSide::Buy, 101, 2));
+    assert_eq!(executions.len(), 1);
+    assert_eq!(executions[0].maker_order_id, 1);
+    assert_eq!(executions[0].price, 101);
+    assert_eq!(book.walk_asks().next(), Some((Price(100), 5)));
+
+    clock.advance(1);
+    let executions = book.add_limit_order(Order::new(4, OrderSide::Buy, 101, 2));
+    assert_eq!(executions.len(), 1);
+    assert_eq!(executions[0].maker_order_id, 2);
+    assert_eq!(executions[0].price, 100);
+}
+
+#[test]
+fn quote_cancelled_during_the_delay_never_trades() {
+    let (mut book, clock) = bumped_book();
+    book.add_limit_order(Order::new(1, OrderSide::Buy, 100, 5));
+    book.amend_order(1, None, Some(0)).unwrap();
+    assert_eq!(book.best_bid(), None);
+
+    clock.advance(DELAY_NS);
+    assert!(book.add_limit_order(Order::new(2, OrderSide::Sell, 100, 5)).is_empty());
+    assert_eq!(book.best_ask(), Some(Price(100)));
+}
+
+#[test]
+fn speed_bump_is_per_symbol() {
+    let clock = Arc::new(ManualClock::new(0));
+    let mut engine = MatchingEngine::new();
+    engine.get_or_create("BTC").set_speed_bump(DELAY_NS, clock.clone());
+    for symbol in ["BTC", "ETH"] {
+        engine.add_limit_order(symbol, Order::new(1, OrderSide::Sell, 100, 1));
+    }
+
+    assert!(engine.add_limit_order("BTC", Order::new(2, OrderSide::Buy, 100, 1)).is_empty());
+    assert_eq!(engine.add_limit_order("ETH", Order::new(2, OrderSide::Buy, 100, 1)).len(), 1);
+}
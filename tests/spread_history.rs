@@ -0,0 +1,72 @@
+// ============================================================================
+// SPREAD HISTORY - Spread sampled at each published top of book
+// ============================================================================
+
+mod common;
+
+use common::{http_request, TestServers};
+use hft_ringbuffer::bbo::{BboPublisher, SpreadHistory};
+use hft_ringbuffer::clock::ManualClock;
+use hft_ringbuffer::events::EventBus;
+use hft_ringbuffer::matching_engine::{Order, OrderBook, OrderSide};
+use hft_ringbuffer::tick_size::TickSchedule;
+use hft_ringbuffer::price_units::Price;
+use std::sync::{Arc, Mutex};
+
+#[test]
+fn top_of_book_changes_produce_spread_samples_in_order() {
+    let clock = Arc::new(ManualClock::new(0));
+    let history = Arc::new(Mutex::new(SpreadHistory::new(3)));
+    let mut publisher = BboPublisher::new(0, clock.clone(), Arc::new(EventBus::new())).with_spread_history(history.clone());
+    let mut book = OrderBook::new();
+    book.set_tick_schedule(Some(TickSchedule::uniform(5)));
+
+    for (id, side, price) in [
+        (1, OrderSide::Buy, 9_990),   // one-sided, no spread yet
+        (2, OrderSide::Sell, 10_010), // 20 wide
+        (3, OrderSide::Buy, 9_995),   // 15
+        (4, OrderSide::Sell, 10_000), // 5
+        (5, OrderSide::Buy, 9_980),   // not at the top, no sample
+    ] {
+        clock.advance(10);
+        book.add_limit_order(Order::new(id, side, price, 1));
+        publisher.on_book_change(&book);
+    }
+
+    let samples = history.lock().unwrap().recent(10);
+    let summary: Vec<(u64, u64, u64, u64)> = samples.iter().map(|s| (s.timestamp_ns, s.bid.units(), s.ask.units(), s.spread_ticks)).collect();
+    assert_eq!(summary, vec![(20, 9_990, 10_010, 4), (30, 9_995, 10_010, 3), (40, 9_995, 10_000, 1)]);
+    assert_eq!(samples[0].spread_bps, 20.0);
+
+    // The oldest sample falls off past the bound
+    book.add_limit_order(Order::new(6, OrderSide::Sell, 10_000, 1));
+    book.add_limit_order(Order::new(7, OrderSide::Buy, 10_000, 2)); // takes both asks at 10_000
+    clock.advance(10);
+    publisher.on_book_change(&book);
+    let history = history.lock().unwrap();
+    assert_eq!(history.len(), 3);
+    assert_eq!(history.recent(1)[0].ask, 10_010);
+    assert_eq!(history.recent(3)[0].timestamp_ns, 30);
+}
+
+#[test]
+fn spread_history_is_served_with_a_limit() {
+    let servers = TestServers::start();
+    {
+        let mut history = servers.metrics.spread_history().lock().unwrap();
+        for (ts, bid) in [(1, 98), (2, 99), (3, 97)] {
+            history.record(ts, Some(Price(bid)), Some(Price(101)), 1);
+        }
+    }
+
+    let (status, body) = http_request(&servers.http_addr, "GET", "/api/spread-history?limit=2", "");
+    assert_eq!(status, 200);
+    let samples: Vec<serde_json::Value> = serde_json::from_str(&body).unwrap();
+    let ticks: Vec<u64> = samples.iter().map(|s| s["spread_ticks"].as_u64().unwrap()).collect();
+    assert_eq!(ticks, vec![2, 4]);
+
+    let (_, body) = http_request(&servers.http_addr, "GET", "/api/spread-history", "");
+    assert_eq!(serde_json::from_str::<Vec<serde_json::Value>>(&body).unwrap().len(), 3);
+    assert_eq!(http_request(&servers.http_addr, "GET", "/api/spread-history?limit=-1", "").0, 400);
+    servers.stop();
+}
@@ -0,0 +1,98 @@
+// ============================================================================
+// STOP ORDERS - Triggered by the last trade price
+// ============================================================================
+
+use hft_ringbuffer::matching_engine::{Order, OrderBook, OrderSide, OrderType};
+use hft_ringbuffer::price_units::Price;
+
+fn trade(book: &mut OrderBook, id: u64, price: u64, quantity: u64) {
+    book.add_limit_order(Order::new(id, OrderSide::Sell, price, quantity));
+    book.add_limit_order(Order::new(id + 1, OrderSide::Buy, price, quantity));
+}
+
+#[test]
+fn upward_move_activates_a_buy_stop() {
+    let mut book = OrderBook::new();
+    trade(&mut book, 1, 100, 1);
+    book.add_limit_order(Order::new(3, OrderSide::Sell, 106, 2));
+    book.add_limit_order(Order::new(4, OrderSide::Sell, 108, 5));
+
+    assert!(book.add_limit_order(Order::new(10, OrderSide::Buy, 0, 4).stop(105)).is_empty());
+    assert_eq!(book.pending_stops().len(), 1);
+    assert_eq!(book.best_bid(), None);
+
+    // 104 is still short of the trigger
+    trade(&mut book, 20, 104, 1);
+    assert_eq!(book.pending_stops().len(), 1);
+
+    // 105 trades: the stop sweeps the asks as a market order
+    book.add_limit_order(Order::new(30, OrderSide::Sell, 105, 1));
+    let executions = book.add_limit_order(Order::new(31, OrderSide::Buy, 105, 1));
+    let stop_fills: Vec<(u64, u64, u64)> =
+        executions.iter().filter(|e| e.taker_order_id == 10).map(|e| (e.maker_order_id, e.price.units(), e.quantity)).collect();
+    assert_eq!(stop_fills, vec![(3, 106, 2), (4, 108, 2)]);
+    assert!(book.pending_stops().is_empty());
+    assert_eq!(book.last_trade_price(), Some(Price(108)));
+    assert_eq!(book.best_bid(), None);
+}
+
+#[test]
+fn stop_limit_rests_what_it_cannot_fill_at_its_limit() {
+    let mut book = OrderBook::new();
+    book.add_limit_order(Order::new(1, OrderSide::Buy, 97, 3));
+    book.add_limit_order(Order::new(2, OrderSide::Buy, 95, 3));
+    book.add_limit_order(Order::new(3, OrderSide::Sell, 96, 5).stop_limit(98));
+    assert_eq!(book.pending_stops()[0].order_type, OrderType::StopLimit { trigger: Price(98), limit: Price(96) });
+
+    trade(&mut book, 10, 98, 1);
+    assert!(book.pending_stops().is_empty());
+    assert_eq!(book.best_bid(), Some(Price(95)));
+    assert_eq!(book.best_ask(), Some(Price(96)));
+    assert_eq!(book.depth(1).asks[0].qty, 2);
+}
+
+#[test]
+fn one_stop_can_trigger_the_next() {
+    let mut book = OrderBook::new();
+    trade(&mut book, 1, 100, 1);
+    book.add_limit_order(Order::new(3, OrderSide::Buy, 99, 1));
+    book.add_limit_order(Order::new(4, OrderSide::Buy, 97, 1));
+    book.add_limit_order(Order::new(5, OrderSide::Buy, 94, 1));
+    // The second stop only fires off the first one's fill at 97
+    book.add_limit_order(Order::new(20, OrderSide::Sell, 0, 1).stop(97));
+    book.add_limit_order(Order::new(21, OrderSide::Sell, 0, 1).stop(99));
+    book.add_limit_order(Order::new(22, OrderSide::Sell, 0, 1).stop(90));
+
+    let executions = book.add_limit_order(Order::new(30, OrderSide::Sell, 99, 1));
+    let fills: Vec<(u64, u64)> = executions.iter().map(|e| (e.taker_order_id, e.price.units())).collect();
+    assert_eq!(fills, vec![(30, 99), (21, 97), (20, 94)]);
+    assert_eq!(book.pending_stops().iter().map(|o| o.id).collect::<Vec<_>>(), vec![22]);
+    assert_eq!(book.best_bid(), None);
+}
+
+#[test]
+fn pending_stops_can_be_cancelled() {
+    let mut book = OrderBook::new();
+    book.add_limit_order(Order::new(1, OrderSide::Buy, 0, 2).stop(110).with_account(7));
+    let cancelled = book.cancel_order(1).unwrap();
+    assert_eq!((cancelled.id, cancelled.account_id), (1, Some(7)));
+    assert!(book.pending_stops().is_empty());
+    assert_eq!(book.cancel_order(1), None);
+
+    trade(&mut book, 2, 110, 1);
+    assert_eq!(book.best_ask(), None);
+    assert_eq!(book.recent_trades(10).len(), 1);
+}
+
+#[test]
+fn stop_checks_need_a_trigger_not_a_price() {
+    let book = OrderBook::new();
+    assert!(book.check_order(&Order::new(1, OrderSide::Buy, 0, 1).stop(105)).is_ok());
+    assert!(book.check_order(&Order::new(1, OrderSide::Buy, 0, 1).stop(0)).is_err());
+    assert!(book.check_order(&Order::new(1, OrderSide::Buy, 0, 1).stop_limit(105)).is_err());
+
+    let json = serde_json::to_string(&Order::new(1, OrderSide::Buy, 0, 1).stop(105)).unwrap();
+    let parsed: Order = serde_json::from_str(&json).unwrap();
+    assert_eq!(parsed.order_type, OrderType::Stop { trigger: Price(105) });
+    assert!(!serde_json::to_string(&Order::new(1, OrderSide::Buy, 1, 1)).unwrap().contains("order_type"));
+}
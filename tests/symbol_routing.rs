@@ -0,0 +1,68 @@
+// ============================================================================
+// SYMBOL ROUTING - Orders only ever meet orders for the same instrument
+// ============================================================================
+
+mod common;
+
+use common::{http_request, GatewayClient, TestServers};
+use hft_ringbuffer::matching_engine::{MatchingEngine, Order, OrderSide, DEFAULT_BOOK_SYMBOL};
+use hft_ringbuffer::price_units::Price;
+
+#[test]
+fn orders_for_different_symbols_do_not_cross() {
+    let mut engine = MatchingEngine::new();
+    engine.route_order(Order::new(1, OrderSide::Sell, 100, 5).for_symbol("BTCUSD"));
+    let executions = engine.route_order(Order::new(2, OrderSide::Buy, 105, 5).for_symbol("ETHUSD"));
+    assert!(executions.is_empty());
+    assert_eq!(engine.book("BTCUSD").unwrap().best_ask(), Some(Price(100)));
+    assert_eq!(engine.book("ETHUSD").unwrap().best_bid(), Some(Price(105)));
+
+    // Same symbol does cross; no symbol goes to the default book
+    let executions = engine.route_order(Order::new(3, OrderSide::Buy, 100, 2).for_symbol("BTCUSD"));
+    assert_eq!(executions.len(), 1);
+    engine.route_order(Order::new(4, OrderSide::Buy, 100, 1));
+    assert_eq!(engine.book(DEFAULT_BOOK_SYMBOL).unwrap().best_bid(), Some(Price(100)));
+    assert_eq!(engine.symbols(), vec!["BTCUSD", "DEFAULT", "ETHUSD"]);
+}
+
+#[test]
+fn http_routes_orders_and_reads_by_symbol() {
+    let mut servers = TestServers::start();
+    let btc = servers.add_symbol_engine("BTCUSD");
+
+    let order = r#"{"id": 1, "side": "Sell", "price": 100, "quantity": 5, "symbol": "BTCUSD"}"#;
+    assert_eq!(http_request(&servers.http_addr, "POST", "/api/order", order).0, 200);
+    let order = r#"{"id": 2, "side": "Buy", "price": 101, "quantity": 5}"#;
+    assert_eq!(http_request(&servers.http_addr, "POST", "/api/order", order).0, 200);
+
+    assert_eq!(btc.lock().unwrap().best_ask(), Some(Price(100)));
+    assert_eq!(servers.order_book.lock().unwrap().best_ask(), None);
+    assert_eq!(servers.order_book.lock().unwrap().best_bid(), Some(Price(101)));
+
+    let (status, body) = http_request(&servers.http_addr, "GET", "/api/orderbook?symbol=BTCUSD", "");
+    assert_eq!(status, 200);
+    let body: serde_json::Value = serde_json::from_str(&body).unwrap();
+    assert_eq!(body["asks"].as_array().unwrap().len(), 1);
+    assert!(body["bids"].as_array().unwrap().is_empty());
+
+    assert_eq!(http_request(&servers.http_addr, "GET", "/api/orderbook?symbol=XRPUSD", "").0, 404);
+    let order = r#"{"id": 3, "side": "Buy", "price": 100, "quantity": 1, "symbol": "XRPUSD"}"#;
+    assert_eq!(http_request(&servers.http_addr, "POST", "/api/order", order).0, 404);
+    servers.stop();
+}
+
+#[test]
+fn gateway_refuses_symbols_it_has_no_engine_for() {
+    let servers = TestServers::start();
+    let mut client = GatewayClient::connect(&servers.gateway_addr);
+
+    let ack = client.send_line(r#"{"id": 1, "side": "Buy", "price": 100, "quantity": 1, "symbol": "XRPUSD"}"#);
+    assert_eq!(ack["status"], "error");
+    assert_eq!(ack["reason"], "unknown_symbol");
+
+    let ack = client.send_line(r#"{"id": 2, "side": "Buy", "price": 100, "quantity": 1, "symbol": "DEFAULT"}"#);
+    assert_eq!(ack["status"], "accepted");
+    let ack = client.send_line(r#"{"id": 3, "side": "Buy", "price": 100, "quantity": 1}"#);
+    assert_eq!(ack["status"], "accepted");
+    servers.stop();
+}
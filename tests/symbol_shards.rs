@@ -0,0 +1,95 @@
+// ============================================================================
+// SYMBOL SHARDS - Independently sized rings per symbol
+// ============================================================================
+
+use hft_ringbuffer::engine::{spawn_shard_engine, EngineHooks};
+use hft_ringbuffer::matching_engine::{Order, OrderBook, OrderSide, Packet};
+use hft_ringbuffer::metrics::Metrics;
+use hft_ringbuffer::shards::{RouteError, ShardConfig, ShardConfigError, ShardRouter};
+use hft_ringbuffer::price_units::Price;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+fn packet(id: u64) -> Packet {
+    Packet::new(Order::new(id, OrderSide::Buy, 100, 1))
+}
+
+#[test]
+fn each_symbol_drops_according_to_its_own_capacity() {
+    let config = ShardConfig::new(8).with_capacity("BTC", 16).with_capacity("DOGE", 4);
+    // Consumers are held but never drained, as if both engines stalled
+    let (router, _consumers) = ShardRouter::build(&config, &["BTC", "DOGE"]).unwrap();
+    let metrics = Metrics::new();
+    metrics.register_shards(&router);
+
+    let mut accepted = [0, 0];
+    for id in 0..20 {
+        for (i, symbol) in ["BTC", "DOGE"].iter().enumerate() {
+            match router.route(symbol, packet(id)) {
+                Ok(()) => accepted[i] += 1,
+                Err(RouteError::Full(_)) => {}
+                Err(RouteError::UnknownSymbol(_)) => panic!("{} should be routed", symbol),
+            }
+        }
+    }
+    assert_eq!(accepted, [16, 4]);
+
+    let occupancy = metrics.shard_occupancy();
+    assert_eq!(occupancy[0].symbol, "BTC");
+    assert_eq!((occupancy[0].capacity, occupancy[0].occupied, occupancy[0].dropped), (16, 16, 4));
+    assert_eq!((occupancy[1].capacity, occupancy[1].occupied, occupancy[1].dropped), (4, 4, 16));
+
+    let text = metrics.render_prometheus();
+    assert!(text.contains("shard_ring_occupancy{symbol=\"DOGE\"} 4"));
+    assert!(text.contains("shard_orders_dropped_total{symbol=\"BTC\"} 4"));
+
+    assert!(matches!(router.route("ETH", packet(99)), Err(RouteError::UnknownSymbol(_))));
+}
+
+#[test]
+fn capacities_must_be_powers_of_two() {
+    let config = ShardConfig::new(8).with_capacity("BTC", 1000);
+    assert_eq!(
+        ShardRouter::build(&config, &["BTC"]).err(),
+        Some(ShardConfigError::NotPowerOfTwo { symbol: "BTC".to_string(), capacity: 1000 })
+    );
+    assert!("BTC:8192,ETH:1024".parse::<ShardConfig>().is_ok());
+    assert!("BTC:3000".parse::<ShardConfig>().is_err());
+    assert!("BTC".parse::<ShardConfig>().is_err());
+}
+
+#[test]
+fn shard_engines_drain_into_their_own_books() {
+    let config = ShardConfig::new(8);
+    let (router, consumers) = ShardRouter::build(&config, &["BTC", "ETH"]).unwrap();
+    let shutdown = Arc::new(AtomicBool::new(false));
+    let metrics = Arc::new(Metrics::new());
+
+    let mut books = Vec::new();
+    let mut engines = Vec::new();
+    for (symbol, consumer) in consumers {
+        let book = Arc::new(Mutex::new(OrderBook::new()));
+        let engine = spawn_shard_engine(&symbol, consumer, book.clone(), shutdown.clone(), metrics.clone(), EngineHooks::default()).unwrap();
+        assert_eq!(engine.thread().name(), Some(format!("engine-{}", symbol).as_str()));
+        books.push((symbol, book));
+        engines.push(engine);
+    }
+
+    router.route("BTC", Packet::new(Order::new(1, OrderSide::Buy, 30_000, 1))).unwrap();
+    router.route("ETH", Packet::new(Order::new(2, OrderSide::Sell, 2_000, 1))).unwrap();
+
+    let deadline = Instant::now() + Duration::from_secs(5);
+    while router.shards().iter().any(|shard| shard.occupancy().occupied > 0) && Instant::now() < deadline {
+        std::thread::yield_now();
+    }
+    shutdown.store(true, Ordering::Relaxed);
+    for engine in engines {
+        engine.join().unwrap();
+    }
+
+    let (btc, eth) = (&books[0].1, &books[1].1);
+    assert_eq!(btc.lock().unwrap().best_bid(), Some(Price(30_000)));
+    assert_eq!(btc.lock().unwrap().best_ask(), None);
+    assert_eq!(eth.lock().unwrap().best_ask(), Some(Price(2_000)));
+}
@@ -0,0 +1,81 @@
+// ============================================================================
+// THREAD NAMING - Named threads in handles and panic reports
+// ============================================================================
+
+use hft_ringbuffer::engine::{spawn_engine, EngineHooks, ENGINE_THREAD_NAME};
+use hft_ringbuffer::funnel::{spawn_funnel, FUNNEL_THREAD_NAME};
+use hft_ringbuffer::gateway::{bind_gateway, spawn_gateway, GatewayRoutes, GATEWAY_THREAD_NAME};
+use hft_ringbuffer::matching_engine::{Order, OrderBook, OrderSide, Packet, DEFAULT_BOOK_SYMBOL};
+use hft_ringbuffer::metrics::Metrics;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+#[test]
+fn engine_funnel_and_gateway_threads_are_named() {
+    let (_producer, consumer) = rtrb::RingBuffer::<Packet>::new(8);
+    let (listener, _) = bind_gateway("127.0.0.1:0").unwrap();
+    let (gateway_producer, _gateway_consumer) = rtrb::RingBuffer::<Packet>::new(8);
+    let shutdown = Arc::new(AtomicBool::new(false));
+
+    let engine = spawn_engine(
+        consumer,
+        Arc::new(Mutex::new(OrderBook::new())),
+        shutdown.clone(),
+        Arc::new(Metrics::new()),
+        EngineHooks::default(),
+    )
+    .unwrap();
+    let (funnel, forwarder) = spawn_funnel(gateway_producer, Default::default(), Default::default(), shutdown.clone()).unwrap();
+    let routes = GatewayRoutes::new(DEFAULT_BOOK_SYMBOL, funnel);
+    let gateway = spawn_gateway(listener, routes, Default::default(), shutdown.clone()).unwrap();
+
+    assert_eq!(engine.thread().name(), Some(ENGINE_THREAD_NAME));
+    assert_eq!(forwarder.thread().name(), Some(FUNNEL_THREAD_NAME));
+    assert_eq!(gateway.thread().name(), Some(GATEWAY_THREAD_NAME));
+
+    shutdown.store(true, Ordering::Relaxed);
+    engine.join().unwrap();
+    gateway.join().unwrap();
+    forwarder.join().unwrap();
+}
+
+#[test]
+fn engine_panic_reports_the_thread_name() {
+    let panicked_in = Arc::new(Mutex::new(None));
+    {
+        let panicked_in = panicked_in.clone();
+        let default_hook = std::panic::take_hook();
+        std::panic::set_hook(Box::new(move |info| {
+            if let Some(name) = thread::current().name() {
+                *panicked_in.lock().unwrap() = Some(name.to_string());
+            }
+            default_hook(info);
+        }));
+    }
+
+    // A poisoned book makes the engine's lock().unwrap() panic
+    let book = Arc::new(Mutex::new(OrderBook::new()));
+    {
+        let book = book.clone();
+        let _ = thread::spawn(move || {
+            let _guard = book.lock().unwrap();
+            panic!("poison the book");
+        })
+        .join();
+    }
+
+    let (mut producer, consumer) = rtrb::RingBuffer::<Packet>::new(8);
+    let engine = spawn_engine(
+        consumer,
+        book,
+        Arc::new(AtomicBool::new(false)),
+        Arc::new(Metrics::new()),
+        EngineHooks::default(),
+    )
+    .unwrap();
+    producer.push(Packet::new(Order::new(1, OrderSide::Buy, 100, 1))).unwrap();
+
+    assert!(engine.join().is_err());
+    assert_eq!(panicked_in.lock().unwrap().as_deref(), Some(ENGINE_THREAD_NAME));
+}
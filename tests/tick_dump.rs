@@ -0,0 +1,66 @@
+// ============================================================================
+// TICK DUMP - CSV rows written off the engine thread reconstruct the run
+// ============================================================================
+
+use hft_ringbuffer::clock::ManualClock;
+use hft_ringbuffer::engine::{spawn_engine, EngineHooks};
+use hft_ringbuffer::matching_engine::{Order, OrderBook, OrderSide, Packet};
+use hft_ringbuffer::metrics::Metrics;
+use hft_ringbuffer::tick_dump::{TickDump, TickEvent, TickRow, TICK_DUMP_HEADER};
+use hft_ringbuffer::price_units::Price;
+use std::sync::atomic::AtomicBool;
+use std::sync::{Arc, Mutex};
+
+#[test]
+fn a_short_run_dumps_one_row_per_order_and_execution() {
+    let path = std::env::temp_dir().join(format!("tick_dump_{}.csv", std::process::id()));
+    let clock = Arc::new(ManualClock::new(5_000));
+    let (dump, writer) = TickDump::create(&path, clock).unwrap();
+
+    let (mut producer, consumer) = rtrb::RingBuffer::<Packet>::new(16);
+    for order in [
+        Order::new(1, OrderSide::Buy, 100, 10),
+        Order::new(2, OrderSide::Sell, 103, 4),
+        Order::new(3, OrderSide::Sell, 100, 6),
+        Order::new(4, OrderSide::Buy, 104, 5),
+    ] {
+        producer.push(Packet::new(order)).unwrap();
+    }
+
+    // Shutdown is already raised, so the drain runs the whole ring and exits
+    let hooks = EngineHooks { tick_dump: Some(dump), drain_on_shutdown: true, ..Default::default() };
+    let book = Arc::new(Mutex::new(OrderBook::new()));
+    spawn_engine(consumer, book, Arc::new(AtomicBool::new(true)), Arc::new(Metrics::new()), hooks)
+        .unwrap()
+        .join()
+        .unwrap();
+    writer.join().unwrap();
+
+    let csv = std::fs::read_to_string(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    let mut lines = csv.lines();
+    assert_eq!(lines.next(), Some(TICK_DUMP_HEADER));
+    let rows: Vec<TickRow> = lines.map(|line| TickRow::from_csv(line).unwrap()).collect();
+
+    let row = |event, side, price, quantity, best_bid, best_ask| TickRow {
+        timestamp_ns: 5_000,
+        event,
+        side,
+        price: Price(price),
+        quantity,
+        best_bid,
+        best_ask,
+    };
+    assert_eq!(
+        rows,
+        vec![
+            row(TickEvent::Order, OrderSide::Buy, 100, 10, Some(Price(100)), None),
+            row(TickEvent::Order, OrderSide::Sell, 103, 4, Some(Price(100)), Some(Price(103))),
+            row(TickEvent::Order, OrderSide::Sell, 100, 6, Some(Price(100)), Some(Price(103))),
+            row(TickEvent::Trade, OrderSide::Sell, 100, 6, Some(Price(100)), Some(Price(103))),
+            // One unit is left over and rests at 104
+            row(TickEvent::Order, OrderSide::Buy, 104, 5, Some(Price(104)), None),
+            row(TickEvent::Trade, OrderSide::Buy, 103, 4, Some(Price(104)), None),
+        ]
+    );
+}
@@ -0,0 +1,77 @@
+// ============================================================================
+// TICK SIZE SCHEDULE - Per-band validation and rounding
+// ============================================================================
+
+mod common;
+
+use common::{http_request, TestServers};
+use hft_ringbuffer::matching_engine::OrderSide;
+use hft_ringbuffer::tick_size::{TickBand, TickSchedule, TickViolation};
+use hft_ringbuffer::price_units::Price;
+use serde_json::Value;
+
+fn two_bands() -> TickSchedule {
+    "0:1,1000:5".parse().unwrap()
+}
+
+#[test]
+fn prices_are_validated_against_their_own_band() {
+    let schedule = two_bands();
+    assert_eq!(schedule.validate(999), Ok(()));
+    assert_eq!(schedule.validate(1005), Ok(()));
+    assert_eq!(schedule.validate(1003), Err(TickViolation { price: Price(1003), tick_size: 5 }));
+}
+
+#[test]
+fn boundary_price_belongs_to_the_upper_band() {
+    let schedule = two_bands();
+    assert_eq!(schedule.tick_for(999), 1);
+    assert_eq!(schedule.tick_for(1000), 5);
+    assert_eq!(schedule.validate(1000), Ok(()));
+}
+
+#[test]
+fn rounding_is_passive_and_stays_on_the_grid() {
+    let schedule: TickSchedule = "0:1,1000:10,2000:50".parse().unwrap();
+    assert_eq!(schedule.round(1234, OrderSide::Buy), 1230);
+    assert_eq!(schedule.round(1234, OrderSide::Sell), 1240);
+    // Rounding a sell up out of its band lands on the next band's start
+    assert_eq!(schedule.round(1995, OrderSide::Sell), 2000);
+    assert_eq!(schedule.validate(2000), Ok(()));
+}
+
+#[test]
+fn malformed_schedules_are_rejected() {
+    assert!("1000:5".parse::<TickSchedule>().is_err(), "must start at 0");
+    assert!("0:1,1002:5".parse::<TickSchedule>().is_err(), "boundary off its own tick");
+    assert!("0:5,0:10".parse::<TickSchedule>().is_err(), "not strictly increasing");
+    assert!(TickSchedule::new(vec![TickBand { from_price: Price(0), tick_size: 0 }]).is_err());
+}
+
+#[test]
+fn http_rejects_an_off_tick_price() {
+    let servers = TestServers::start();
+    servers.order_book.lock().unwrap().set_tick_schedule(Some(two_bands()));
+
+    let (status, body) = http_request(
+        &servers.http_addr,
+        "POST",
+        "/api/order",
+        r#"{"id":1,"side":"Buy","price":1003,"quantity":1}"#,
+    );
+    assert_eq!(status, 400);
+    let ack: Value = serde_json::from_str(&body).unwrap();
+    assert_eq!(ack["status"], "error");
+    assert_eq!(servers.order_book.lock().unwrap().best_bid(), None);
+
+    let (status, _) = http_request(
+        &servers.http_addr,
+        "POST",
+        "/api/order",
+        r#"{"id":2,"side":"Buy","price":1005,"quantity":1}"#,
+    );
+    assert_eq!(status, 200);
+    assert_eq!(servers.order_book.lock().unwrap().best_bid(), Some(Price(1005)));
+
+    servers.stop();
+}
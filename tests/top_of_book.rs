@@ -0,0 +1,58 @@
+// ============================================================================
+// TOP OF BOOK - Best bid, best ask and spread without a full snapshot
+// ============================================================================
+
+mod common;
+
+use common::{http_request, TestServers};
+use hft_ringbuffer::matching_engine::{Order, OrderBook, OrderSide};
+use hft_ringbuffer::price_units::Price;
+
+fn top(book: &OrderBook) -> (Option<Price>, Option<Price>, Option<u64>) {
+    (book.best_bid(), book.best_ask(), book.spread())
+}
+
+#[test]
+fn empty_book_has_no_top() {
+    assert_eq!(top(&OrderBook::new()), (None, None, None));
+}
+
+#[test]
+fn one_sided_book_has_no_spread() {
+    let mut book = OrderBook::new();
+    book.add_limit_order(Order::new(1, OrderSide::Buy, 99, 1));
+    book.add_limit_order(Order::new(2, OrderSide::Buy, 98, 1));
+    assert_eq!(top(&book), (Some(Price(99)), None, None));
+}
+
+#[test]
+fn crossing_order_trades_away_and_leaves_an_uncrossed_top() {
+    let mut book = OrderBook::new();
+    book.add_limit_order(Order::new(1, OrderSide::Buy, 99, 1));
+    book.add_limit_order(Order::new(2, OrderSide::Sell, 101, 1));
+    book.add_limit_order(Order::new(3, OrderSide::Sell, 104, 1));
+    assert_eq!(top(&book), (Some(Price(99)), Some(Price(101)), Some(2)));
+
+    // Lifts 101 and rests the rest at 102
+    book.add_limit_order(Order::new(4, OrderSide::Buy, 102, 2));
+    assert_eq!(top(&book), (Some(Price(102)), Some(Price(104)), Some(2)));
+}
+
+#[test]
+fn top_of_book_endpoint_returns_the_three_numbers() {
+    let servers = TestServers::start();
+    let (status, body) = http_request(&servers.http_addr, "GET", "/api/top-of-book", "");
+    assert_eq!(status, 200);
+    let body: serde_json::Value = serde_json::from_str(&body).unwrap();
+    assert_eq!(body, serde_json::json!({"best_bid": null, "best_ask": null, "spread": null}));
+
+    {
+        let mut book = servers.order_book.lock().unwrap();
+        book.add_limit_order(Order::new(1, OrderSide::Buy, 97, 1));
+        book.add_limit_order(Order::new(2, OrderSide::Sell, 100, 1));
+    }
+    let (_, body) = http_request(&servers.http_addr, "GET", "/api/top-of-book", "");
+    let body: serde_json::Value = serde_json::from_str(&body).unwrap();
+    assert_eq!(body, serde_json::json!({"best_bid": 97, "best_ask": 100, "spread": 3}));
+    servers.stop();
+}
@@ -0,0 +1,87 @@
+// ============================================================================
+// TRADE HISTORY - Bounded retention, inline and offloaded recording
+// ============================================================================
+
+use hft_ringbuffer::engine::{run_engine, EngineHooks};
+use hft_ringbuffer::events::{BookEvent, EventBus};
+use hft_ringbuffer::matching_engine::{Order, OrderBook, OrderSide, Packet, TradeExecution};
+use hft_ringbuffer::metrics::Metrics;
+use hft_ringbuffer::trade_history::{TradeHistory, TradeSink};
+use hft_ringbuffer::price_units::Price;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+fn trade(id: u64) -> TradeExecution {
+    TradeExecution { maker_order_id: id, taker_order_id: id + 1, price: Price(100), quantity: 1, price_improvement: 0, fee_version: 0, maker_fee: 0, taker_fee: 0, remaining_quantity: 0, is_taker_complete: true, symbol: None }
+}
+
+/// Runs `pairs` crossing sell/buy pairs through a real engine thread.
+fn run_trade_heavy_workload(pairs: u64, sink: TradeSink) {
+    let (mut producer, consumer) = rtrb::RingBuffer::<Packet>::new(1024);
+    let book = Arc::new(Mutex::new(OrderBook::new()));
+    let shutdown = Arc::new(AtomicBool::new(false));
+    let engine = {
+        let shutdown = shutdown.clone();
+        let hooks = EngineHooks { trades: Some(sink), ..Default::default() };
+        thread::spawn(move || run_engine(consumer, book, shutdown, Arc::new(Metrics::new()), hooks))
+    };
+
+    for i in 0..pairs {
+        for order in [Order::new(2 * i, OrderSide::Sell, 100, 1), Order::new(2 * i + 1, OrderSide::Buy, 100, 1)] {
+            let mut packet = Packet::new(order);
+            while let Err(rtrb::PushError::Full(rejected)) = producer.push(packet) {
+                packet = rejected;
+                thread::yield_now();
+            }
+        }
+    }
+    while producer.slots() < producer.buffer().capacity() {
+        thread::yield_now();
+    }
+    shutdown.store(true, Ordering::Relaxed);
+    engine.join().unwrap();
+}
+
+#[test]
+fn history_evicts_oldest_past_the_cap() {
+    let mut history = TradeHistory::new(3);
+    for id in 0..5 {
+        history.push(trade(id));
+    }
+    assert_eq!(history.len(), 3);
+    let ids: Vec<u64> = history.recent(10).iter().map(|t| t.maker_order_id).collect();
+    assert_eq!(ids, vec![2, 3, 4]);
+    let ids: Vec<u64> = history.recent(2).iter().map(|t| t.maker_order_id).collect();
+    assert_eq!(ids, vec![3, 4]);
+}
+
+#[test]
+fn offloaded_history_eventually_contains_every_trade_in_order() {
+    const PAIRS: u64 = 5_000;
+    let history = Arc::new(Mutex::new(TradeHistory::new(PAIRS as usize)));
+    let bus = Arc::new(EventBus::new());
+    let feed = bus.subscribe();
+
+    let (sink, writer) = TradeSink::offloaded(history.clone(), Some(bus.clone())).unwrap();
+    run_trade_heavy_workload(PAIRS, sink);
+    // Dropping the sink with the engine lets the writer drain and exit
+    writer.join().unwrap();
+
+    let trades = history.lock().unwrap().recent(PAIRS as usize);
+    assert_eq!(trades.len(), PAIRS as usize);
+    for (i, trade) in trades.iter().enumerate() {
+        assert_eq!(trade.maker_order_id, 2 * i as u64);
+        assert_eq!(trade.taker_order_id, 2 * i as u64 + 1);
+    }
+
+    let published = feed.try_iter().filter(|message| matches!(message.event, BookEvent::Trade(_))).count();
+    assert_eq!(published, PAIRS as usize);
+}
+
+#[test]
+fn inline_history_records_under_the_book_lock() {
+    let history = Arc::new(Mutex::new(TradeHistory::new(100)));
+    run_trade_heavy_workload(10, TradeSink::Inline(history.clone()));
+    assert_eq!(history.lock().unwrap().len(), 10);
+}
@@ -0,0 +1,99 @@
+// ============================================================================
+// TRADE STREAM - Server-Sent Events for every execution
+// ============================================================================
+
+mod common;
+
+use common::{http_request, wait_until, GatewayClient, TestServers};
+use hft_ringbuffer::matching_engine::TradeExecution;
+use std::io::{BufRead, BufReader, Write};
+use std::net::TcpStream;
+use std::time::Duration;
+
+fn subscribe(addr: &str) -> BufReader<TcpStream> {
+    let mut stream = TcpStream::connect(addr).unwrap();
+    stream.set_read_timeout(Some(Duration::from_secs(5))).unwrap();
+    write!(stream, "GET /api/stream/trades HTTP/1.1\r\nHost: {}\r\nAccept: text/event-stream\r\n\r\n", addr).unwrap();
+    BufReader::new(stream)
+}
+
+fn read_line(reader: &mut BufReader<TcpStream>) -> String {
+    let mut line = String::new();
+    reader.read_line(&mut line).unwrap();
+    line.trim_end().to_string()
+}
+
+/// Reads up to the blank line ending the next event: `(id, data)`
+fn read_event(reader: &mut BufReader<TcpStream>) -> (u64, TradeExecution) {
+    let id = read_line(reader).strip_prefix("id: ").unwrap().parse().unwrap();
+    let data = serde_json::from_str(read_line(reader).strip_prefix("data: ").unwrap()).unwrap();
+    assert_eq!(read_line(reader), "");
+    (id, data)
+}
+
+#[test]
+fn subscriber_receives_each_cross_as_an_event() {
+    let servers = TestServers::start();
+    let mut reader = subscribe(&servers.http_addr);
+    assert_eq!(read_line(&mut reader), "HTTP/1.1 200 OK");
+    let mut head = Vec::new();
+    loop {
+        match read_line(&mut reader) {
+            line if line.is_empty() => break,
+            line => head.push(line),
+        }
+    }
+    assert!(head.contains(&"Content-Type: text/event-stream".to_string()), "{:?}", head);
+
+    let mut client = GatewayClient::connect(&servers.gateway_addr);
+    client.send_line(r#"{"id": 1, "side": "Sell", "price": 100, "quantity": 2}"#);
+    client.send_line(r#"{"id": 2, "side": "Sell", "price": 101, "quantity": 2}"#);
+    client.send_line(r#"{"id": 3, "side": "Buy", "price": 101, "quantity": 3}"#);
+
+    let (first_id, first) = read_event(&mut reader);
+    let (second_id, second) = read_event(&mut reader);
+    assert_eq!((first.maker_order_id, first.taker_order_id, first.price.units(), first.quantity), (1, 3, 100, 2));
+    assert_eq!((second.maker_order_id, second.price.units(), second.quantity), (2, 101, 1));
+    assert_eq!(second_id, first_id + 1);
+    servers.stop();
+}
+
+#[test]
+fn http_orders_and_repriced_pegs_are_streamed_too() {
+    let servers = TestServers::start();
+    let mut reader = subscribe(&servers.http_addr);
+    while !read_line(&mut reader).is_empty() {}
+
+    let post = |path: &str, body: &str| assert_eq!(http_request(&servers.http_addr, "POST", path, body).0, 200);
+    post("/api/order", r#"{"id": 1, "side": "Sell", "price": 100, "quantity": 3}"#);
+    post("/api/order", r#"{"id": 2, "side": "Buy", "price": 100, "quantity": 1}"#);
+    let (_, trade) = read_event(&mut reader);
+    assert_eq!((trade.maker_order_id, trade.taker_order_id, trade.quantity), (1, 2, 1));
+
+    // Pegged at the reference; moving the reference up to 100 makes it cross
+    post("/api/order", r#"{"id": 3, "side": "Buy", "price": 90, "quantity": 1, "reference_peg_offset": 0}"#);
+    post("/api/reference-price", r#"{"price": 100}"#);
+    let (_, trade) = read_event(&mut reader);
+    assert_eq!((trade.maker_order_id, trade.taker_order_id, trade.price.units()), (1, 3, 100));
+    servers.stop();
+}
+
+#[test]
+fn disconnected_subscribers_are_dropped() {
+    let servers = TestServers::start();
+    let bus = servers.metrics.event_bus().unwrap();
+    let reader = subscribe(&servers.http_addr);
+    assert!(wait_until(|| bus.subscriber_count() == 1));
+    drop(reader);
+
+    // The closed socket fails a write and the stream lets go of its receiver
+    let mut client = GatewayClient::connect(&servers.gateway_addr);
+    let mut id = 0;
+    assert!(wait_until(|| {
+        id += 2;
+        client.send_line(&format!(r#"{{"id": {}, "side": "Sell", "price": 100, "quantity": 1}}"#, id));
+        client.send_line(&format!(r#"{{"id": {}, "side": "Buy", "price": 100, "quantity": 1}}"#, id + 1));
+        bus.subscriber_count() == 0
+    }));
+    servers.stop();
+}
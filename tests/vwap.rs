@@ -0,0 +1,60 @@
+// ============================================================================
+// VWAP - Volume-weighted average over the retained trade history
+// ============================================================================
+
+mod common;
+
+use common::{http_request, TestServers};
+use hft_ringbuffer::matching_engine::{Order, OrderBook, OrderSide};
+
+/// Trades 100 x 2, 101 x 2, then 102 x 1
+fn traded_book(book: &mut OrderBook) {
+    book.add_limit_order(Order::new(1, OrderSide::Sell, 100, 2));
+    book.add_limit_order(Order::new(2, OrderSide::Sell, 101, 2));
+    book.add_limit_order(Order::new(3, OrderSide::Buy, 101, 4));
+    book.add_limit_order(Order::new(4, OrderSide::Sell, 102, 1));
+    book.add_limit_order(Order::new(5, OrderSide::Buy, 102, 1));
+}
+
+#[test]
+fn vwap_matches_the_hand_computed_average() {
+    let mut book = OrderBook::new();
+    traded_book(&mut book);
+
+    // (100*2 + 101*2 + 102*1) / 5
+    assert_eq!(book.vwap(3), Some(504.0 / 5.0));
+    // Last two trades: (101*2 + 102*1) / 3
+    assert_eq!(book.vwap(2), Some(304.0 / 3.0));
+    assert_eq!(book.vwap(1), Some(102.0));
+}
+
+#[test]
+fn vwap_uses_what_history_holds() {
+    let mut book = OrderBook::new();
+    assert_eq!(book.vwap(100), None);
+
+    traded_book(&mut book);
+    assert_eq!(book.vwap(100), book.vwap(3));
+    assert_eq!(book.vwap(0), None);
+
+    // Evicted trades no longer count
+    book.set_recent_trades_capacity(1);
+    assert_eq!(book.vwap(100), Some(102.0));
+}
+
+#[test]
+fn vwap_is_served_over_http() {
+    let servers = TestServers::start();
+    let (status, body) = http_request(&servers.http_addr, "GET", "/api/vwap", "");
+    assert_eq!(status, 200);
+    assert_eq!(serde_json::from_str::<serde_json::Value>(&body).unwrap(), serde_json::json!({"n": 100, "vwap": null}));
+
+    traded_book(&mut servers.order_book.lock().unwrap());
+    let (status, body) = http_request(&servers.http_addr, "GET", "/api/vwap?n=1", "");
+    assert_eq!(status, 200);
+    assert_eq!(serde_json::from_str::<serde_json::Value>(&body).unwrap(), serde_json::json!({"n": 1, "vwap": 102.0}));
+
+    let (status, _) = http_request(&servers.http_addr, "GET", "/api/vwap?n=-1", "");
+    assert_eq!(status, 400);
+    servers.stop();
+}
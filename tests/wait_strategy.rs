@@ -0,0 +1,100 @@
+// ============================================================================
+// WAIT STRATEGY - Spin, yield, sleep or back off while the ring is empty
+// ============================================================================
+
+mod common;
+
+use common::wait_until;
+use hft_ringbuffer::engine::{spawn_shard_engine, EngineHooks};
+use hft_ringbuffer::matching_engine::{Order, OrderBook, OrderSide, Packet};
+use hft_ringbuffer::metrics::Metrics;
+use hft_ringbuffer::wait_strategy::{IdleAction, Idler, WaitStrategy, DEFAULT_BACKOFF_SPINS};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+const SLEEP: Duration = Duration::from_micros(1);
+
+#[test]
+fn backoff_escalates_after_the_configured_empty_polls() {
+    let mut idler = Idler::new(WaitStrategy::Backoff { spins: 3, yields: 2, sleep: SLEEP });
+    let actions: Vec<IdleAction> = (0..7).map(|_| idler.idle()).collect();
+    assert_eq!(actions, vec![
+        IdleAction::Spin,
+        IdleAction::Spin,
+        IdleAction::Spin,
+        IdleAction::Yield,
+        IdleAction::Yield,
+        IdleAction::Sleep(SLEEP),
+        IdleAction::Sleep(SLEEP),
+    ]);
+    assert_eq!(idler.empty_polls(), 7);
+
+    // Work arriving starts it over
+    idler.reset();
+    assert_eq!(idler.next_action(), IdleAction::Spin);
+}
+
+#[test]
+fn fixed_strategies_never_change() {
+    for (strategy, action) in [
+        (WaitStrategy::BusySpin, IdleAction::Spin),
+        (WaitStrategy::Yield, IdleAction::Yield),
+        (WaitStrategy::Sleep(SLEEP), IdleAction::Sleep(SLEEP)),
+    ] {
+        let mut idler = Idler::new(strategy);
+        assert!((0..100).all(|_| idler.idle() == action), "{:?}", strategy);
+    }
+}
+
+#[test]
+fn strategies_are_chosen_by_name() {
+    assert_eq!(WaitStrategy::by_name("busy-spin", SLEEP), Ok(WaitStrategy::BusySpin));
+    assert_eq!(WaitStrategy::by_name(" sleep ", SLEEP), Ok(WaitStrategy::Sleep(SLEEP)));
+    let Ok(WaitStrategy::Backoff { spins, sleep, .. }) = WaitStrategy::by_name("backoff", SLEEP) else {
+        panic!("backoff should parse");
+    };
+    assert_eq!((spins, sleep), (DEFAULT_BACKOFF_SPINS, SLEEP));
+    assert!(WaitStrategy::by_name("nap", SLEEP).is_err_and(|e| e.contains("nap")));
+    assert_eq!(WaitStrategy::default(), WaitStrategy::BusySpin);
+}
+
+struct IdleEngine {
+    book: Arc<Mutex<OrderBook>>,
+    producer: rtrb::Producer<Packet>,
+    shutdown: Arc<AtomicBool>,
+    engine: thread::JoinHandle<()>,
+}
+
+/// An engine on a thread named `engine-<name>` that nothing feeds yet
+fn idle_engine(name: &str, wait: WaitStrategy) -> IdleEngine {
+    let (producer, consumer) = rtrb::RingBuffer::<Packet>::new(16);
+    let book = Arc::new(Mutex::new(OrderBook::new()));
+    let shutdown = Arc::new(AtomicBool::new(false));
+    let hooks = EngineHooks { wait, ..Default::default() };
+    let engine = spawn_shard_engine(name, consumer, book.clone(), shutdown.clone(), Arc::new(Metrics::new()), hooks).unwrap();
+    IdleEngine { book, producer, shutdown, engine }
+}
+
+impl IdleEngine {
+    fn stop(self) {
+        self.shutdown.store(true, Ordering::Relaxed);
+        self.engine.join().unwrap();
+    }
+}
+
+#[test]
+fn sleeping_engines_still_pick_up_orders() {
+    for (name, wait) in [
+        ("sleeping", WaitStrategy::Sleep(Duration::from_millis(1))),
+        ("backing-off", WaitStrategy::Backoff { spins: 10, yields: 10, sleep: Duration::from_millis(1) }),
+    ] {
+        let mut engine = idle_engine(name, wait);
+        // Long enough to be well into the sleeping phase
+        thread::sleep(Duration::from_millis(20));
+        engine.producer.push(Packet::new(Order::new(1, OrderSide::Buy, 100, 1))).unwrap();
+        assert!(wait_until(|| engine.book.lock().unwrap().resting_quantity(1) == Some(1)), "{}", name);
+        engine.stop();
+    }
+}
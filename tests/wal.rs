@@ -0,0 +1,216 @@
+// ============================================================================
+// WRITE-AHEAD LOG - Snapshot + WAL recovery, torn tails, engine journaling
+// ============================================================================
+
+use hft_ringbuffer::engine::{spawn_engine, EngineHooks};
+use hft_ringbuffer::match_policy::ProRataPolicy;
+use hft_ringbuffer::matching_engine::{BookAction, Order, OrderBook, OrderSide, Packet, TradingState};
+use hft_ringbuffer::metrics::Metrics;
+use hft_ringbuffer::price_units::Price;
+use hft_ringbuffer::wal::WriteAheadLog;
+use hft_ringbuffer::warm_start::{load_journal, read_journal, recover, BookSnapshotFile, WarmStartError};
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::atomic::AtomicBool;
+use std::sync::{Arc, Mutex};
+
+fn scratch_path(name: &str) -> PathBuf {
+    let path = std::env::temp_dir().join(format!("wal_{}_{}", std::process::id(), name));
+    let _ = fs::remove_file(&path);
+    path
+}
+
+fn seeded_book() -> OrderBook {
+    let mut book = OrderBook::new();
+    book.add_limit_order(Order::new(1, OrderSide::Buy, 100, 10).with_account(7));
+    book.add_limit_order(Order::new(2, OrderSide::Buy, 100, 4));
+    book.add_limit_order(Order::new(3, OrderSide::Sell, 105, 5));
+    book.add_limit_order(Order::new(4, OrderSide::Sell, 104, 3));
+    book.add_limit_order(Order::new(5, OrderSide::Buy, 104, 1));
+    book.add_limit_order(Order::new(6, OrderSide::Buy, 120, 2).stop(110));
+    book
+}
+
+#[test]
+fn snapshot_mutate_reload_reproduces_the_snapshotted_book() {
+    let path = scratch_path("round_trip.snapshot");
+    let mut book = seeded_book();
+    let saved = book.to_json();
+    book.snapshot_to(&path, 6).unwrap();
+
+    book.add_limit_order(Order::new(7, OrderSide::Sell, 100, 12));
+    book.cancel_order(3);
+    assert_ne!(book.to_json(), saved);
+
+    let reloaded = OrderBook::load_from(&path).unwrap();
+    fs::remove_file(&path).unwrap();
+    assert_eq!(reloaded.to_json(), saved);
+    assert_eq!(reloaded.sequence(), 6);
+    assert_eq!(reloaded.pending_stops().iter().map(|o| o.id).collect::<Vec<_>>(), vec![6]);
+}
+
+#[test]
+fn recovery_replays_the_wal_after_the_last_snapshot() {
+    let wal_path = scratch_path("replay.wal");
+    let snapshot_path = scratch_path("replay.snapshot");
+    let orders = [
+        Order::new(1, OrderSide::Buy, 100, 10),
+        Order::new(2, OrderSide::Sell, 103, 5),
+        Order::new(3, OrderSide::Sell, 100, 4),
+        Order::new(4, OrderSide::Buy, 101, 2),
+        Order::new(5, OrderSide::Sell, 99, 9),
+    ];
+
+    let mut book = OrderBook::new();
+    let (wal, _) = WriteAheadLog::open(&wal_path).unwrap();
+    let mut wal = wal.with_checkpoints(&snapshot_path, 3);
+    for order in &orders {
+        wal.append(order).unwrap();
+        book.add_limit_order(order.clone());
+        if let Some(snapshot) = wal.checkpoint_due(&book) {
+            wal.save_checkpoint(&snapshot).unwrap();
+        }
+    }
+    drop(wal);
+
+    let snapshot = BookSnapshotFile::load(&snapshot_path).unwrap();
+    assert_eq!(snapshot.sequence, 3);
+    let journal = load_journal(&wal_path).unwrap();
+    let (recovered, sequence) = recover(OrderBook::new, Some(&snapshot), &journal).unwrap();
+    assert_eq!(sequence, 5);
+    assert_eq!(recovered.to_json(), book.to_json());
+
+    // Without the snapshot the whole log is replayed to the same book
+    let (from_scratch, _) = recover(OrderBook::new, None, &journal).unwrap();
+    assert_eq!(from_scratch.to_json(), book.to_json());
+    fs::remove_file(&wal_path).unwrap();
+    fs::remove_file(&snapshot_path).unwrap();
+}
+
+#[test]
+fn a_torn_tail_is_skipped_and_cut_off_before_appending() {
+    let path = scratch_path("torn.wal");
+    let (mut wal, existing) = WriteAheadLog::open(&path).unwrap();
+    assert!(existing.is_empty());
+    wal.append(&Order::new(1, OrderSide::Buy, 100, 1)).unwrap();
+    wal.append(&Order::new(2, OrderSide::Buy, 101, 1)).unwrap();
+    drop(wal);
+    let intact_len = fs::metadata(&path).unwrap().len();
+    // A crash halfway through the third entry
+    OpenOptions::new().append(true).open(&path).unwrap().write_all(br#"{"sequence":3,"order":{"id":3,"si"#).unwrap();
+
+    let (entries, valid_len) = read_journal(&path).unwrap();
+    assert_eq!(entries.len(), 2);
+    assert_eq!(valid_len, intact_len);
+
+    let (mut wal, entries) = WriteAheadLog::open(&path).unwrap();
+    assert_eq!(entries.len(), 2);
+    assert_eq!(fs::metadata(&path).unwrap().len(), intact_len);
+    assert_eq!(wal.append(&Order::new(3, OrderSide::Sell, 105, 1)).unwrap(), 3);
+    drop(wal);
+
+    let sequences: Vec<u64> = load_journal(&path).unwrap().iter().map(|entry| entry.sequence).collect();
+    assert_eq!(sequences, vec![1, 2, 3]);
+    fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn damage_before_the_tail_is_an_error() {
+    let path = scratch_path("damaged.wal");
+    let (mut wal, _) = WriteAheadLog::open(&path).unwrap();
+    wal.append(&Order::new(1, OrderSide::Buy, 100, 1)).unwrap();
+    drop(wal);
+    let mut file = OpenOptions::new().append(true).open(&path).unwrap();
+    file.write_all(b"not an entry\n").unwrap();
+    file.write_all(br#"{"sequence":2,"order":{"id":2,"side":"Buy","price":100,"quantity":1}}"#).unwrap();
+    file.write_all(b"\n").unwrap();
+
+    assert!(matches!(load_journal(&path), Err(WarmStartError::Parse(_))));
+    fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn the_engine_journals_orders_and_checkpoints_the_book() {
+    let wal_path = scratch_path("engine.wal");
+    let snapshot_path = scratch_path("engine.snapshot");
+    let (wal, _) = WriteAheadLog::open(&wal_path).unwrap();
+    let (mut producer, consumer) = rtrb::RingBuffer::<Packet>::new(16);
+    for (id, side, price) in [(1, OrderSide::Buy, 100), (2, OrderSide::Sell, 102), (3, OrderSide::Sell, 100), (4, OrderSide::Buy, 99), (5, OrderSide::Sell, 101)] {
+        producer.push(Packet::new(Order::new(id, side, price, 2))).unwrap();
+    }
+
+    let book = Arc::new(Mutex::new(OrderBook::new()));
+    let hooks = EngineHooks { wal: Some(wal.with_checkpoints(&snapshot_path, 2)), drain_on_shutdown: true, ..Default::default() };
+    let engine = spawn_engine(consumer, book.clone(), Arc::new(AtomicBool::new(true)), Arc::new(Metrics::new()), hooks).unwrap();
+    engine.join().unwrap();
+
+    let journal = load_journal(&wal_path).unwrap();
+    assert_eq!(journal.iter().map(|entry| entry.order.id).collect::<Vec<_>>(), vec![1, 2, 3, 4, 5]);
+    let snapshot = BookSnapshotFile::load(&snapshot_path).unwrap();
+    assert_eq!(snapshot.sequence, 4);
+    let (recovered, _) = recover(OrderBook::new, Some(&snapshot), &journal).unwrap();
+    assert_eq!(recovered.to_json(), book.lock().unwrap().to_json());
+    fs::remove_file(&wal_path).unwrap();
+    fs::remove_file(&snapshot_path).unwrap();
+}
+
+#[test]
+fn cancels_and_commands_are_journaled_and_replayed() {
+    let wal_path = scratch_path("commands.wal");
+    let (wal, _) = WriteAheadLog::open(&wal_path).unwrap();
+    let (mut producer, consumer) = rtrb::RingBuffer::<Packet>::new(16);
+    producer.push(Packet::new(Order::new(1, OrderSide::Buy, 100, 2))).unwrap();
+    producer.push(Packet::new(Order::new(2, OrderSide::Sell, 110, 2).pegged_to_reference(5))).unwrap();
+    producer.push(Packet::command(BookAction::Cancel, 1, None)).unwrap();
+    producer.push(Packet::command(BookAction::ReferencePrice(Price(120)), 0, None)).unwrap();
+    producer.push(Packet::command(BookAction::TradingState(TradingState::Closed), 0, None)).unwrap();
+
+    let book = Arc::new(Mutex::new(OrderBook::new()));
+    let hooks = EngineHooks { wal: Some(wal), drain_on_shutdown: true, ..Default::default() };
+    let engine = spawn_engine(consumer, book.clone(), Arc::new(AtomicBool::new(true)), Arc::new(Metrics::new()), hooks).unwrap();
+    engine.join().unwrap();
+
+    let journal = load_journal(&wal_path).unwrap();
+    assert_eq!(journal.iter().map(|entry| entry.action).collect::<Vec<_>>(), vec![
+        BookAction::Submit,
+        BookAction::Submit,
+        BookAction::Cancel,
+        BookAction::ReferencePrice(Price(120)),
+        BookAction::TradingState(TradingState::Closed),
+    ]);
+    let (recovered, sequence) = recover(OrderBook::new, None, &journal).unwrap();
+    assert_eq!(sequence, 5);
+    assert_eq!(recovered.to_json(), book.lock().unwrap().to_json());
+    // The cancelled order stays gone and the peg followed the reference
+    assert_eq!(recovered.resting_quantity(1), None);
+    assert_eq!(recovered.best_ask(), Some(Price(125)));
+    assert_eq!(recovered.trading_state(), TradingState::Closed);
+    fs::remove_file(&wal_path).unwrap();
+}
+
+#[test]
+fn recovery_replays_under_the_live_match_policy() {
+    let wal_path = scratch_path("pro_rata.wal");
+    let pro_rata = || OrderBook::with_match_policy(Arc::new(ProRataPolicy));
+    let mut book = pro_rata();
+    let (mut wal, _) = WriteAheadLog::open(&wal_path).unwrap();
+    for order in [
+        Order::new(1, OrderSide::Sell, 100, 10),
+        Order::new(2, OrderSide::Sell, 100, 30),
+        Order::new(3, OrderSide::Buy, 100, 16),
+    ] {
+        wal.append(&order).unwrap();
+        book.add_limit_order(order);
+    }
+    drop(wal);
+    assert_eq!((book.resting_quantity(1), book.resting_quantity(2)), (Some(6), Some(18)));
+
+    let journal = load_journal(&wal_path).unwrap();
+    let (recovered, _) = recover(pro_rata, None, &journal).unwrap();
+    assert_eq!(recovered.to_json(), book.to_json());
+    // A default book would have filled 1 first
+    let (fifo, _) = recover(OrderBook::new, None, &journal).unwrap();
+    assert_eq!((fifo.resting_quantity(1), fifo.resting_quantity(2)), (None, Some(24)));
+    fs::remove_file(&wal_path).unwrap();
+}
@@ -0,0 +1,139 @@
+// ============================================================================
+// WARM START - Snapshot + journal replay checked against a known checksum
+// ============================================================================
+
+use hft_ringbuffer::clock::ManualClock;
+use hft_ringbuffer::iceberg::IcebergRefresh;
+use hft_ringbuffer::matching_engine::{BookAction, Order, OrderBook, OrderSide};
+use hft_ringbuffer::price_units::Price;
+use hft_ringbuffer::warm_start::{
+    load_journal, save_journal, verify_warm_start, warm_start, BookSnapshotFile, JournalEntry, MismatchAction,
+    WarmStartError,
+};
+use std::path::PathBuf;
+use std::sync::Arc;
+
+fn journal() -> Vec<JournalEntry> {
+    let orders = vec![
+        Order::new(1, OrderSide::Buy, 100, 10),
+        Order::new(2, OrderSide::Sell, 105, 5),
+        Order::new(3, OrderSide::Buy, 101, 7),
+        Order::new(4, OrderSide::Sell, 101, 3),
+        Order::new(5, OrderSide::Sell, 106, 8),
+        Order::new(6, OrderSide::Buy, 105, 2),
+    ];
+    orders.into_iter().enumerate().map(|(i, order)| JournalEntry { sequence: i as u64 + 1, order, action: BookAction::Submit }).collect()
+}
+
+/// Snapshot after the first `at` entries, plus the checksum of the full run.
+fn snapshot_and_checksum(journal: &[JournalEntry], at: usize) -> (BookSnapshotFile, u64) {
+    let mut book = OrderBook::new();
+    for entry in &journal[..at] {
+        book.add_limit_order(entry.order.clone());
+    }
+    let snapshot = BookSnapshotFile::capture(&book, at as u64);
+    for entry in &journal[at..] {
+        book.add_limit_order(entry.order.clone());
+    }
+    (snapshot, book.checksum())
+}
+
+fn scratch_path(name: &str) -> PathBuf {
+    std::env::temp_dir().join(format!("warm_start_{}_{}", std::process::id(), name))
+}
+
+#[test]
+fn a_consistent_snapshot_and_journal_pass_from_disk() {
+    let journal = journal();
+    let (snapshot, expected) = snapshot_and_checksum(&journal, 3);
+
+    let snapshot_path = scratch_path("consistent.snapshot");
+    let journal_path = scratch_path("consistent.journal");
+    snapshot.save(&snapshot_path).unwrap();
+    save_journal(&journal_path, &journal).unwrap();
+    let snapshot = BookSnapshotFile::load(&snapshot_path).unwrap();
+    let loaded = load_journal(&journal_path).unwrap();
+    std::fs::remove_file(&snapshot_path).unwrap();
+    std::fs::remove_file(&journal_path).unwrap();
+
+    let (book, sequence) = verify_warm_start(OrderBook::new, &snapshot, &loaded, expected).unwrap();
+    assert_eq!(sequence, 6);
+    assert_eq!(book.checksum(), expected);
+}
+
+#[test]
+fn a_tampered_journal_is_a_checksum_mismatch() {
+    let mut journal = journal();
+    let (snapshot, expected) = snapshot_and_checksum(&journal, 3);
+    journal[4].order.quantity += 1;
+
+    match verify_warm_start(OrderBook::new, &snapshot, &journal, expected) {
+        Err(WarmStartError::ChecksumMismatch { expected: e, actual }) => {
+            assert_eq!(e, expected);
+            assert_ne!(actual, expected);
+        }
+        other => panic!("expected a checksum mismatch, got {:?}", other.map(|(_, seq)| seq)),
+    }
+    assert!(warm_start(OrderBook::new, &snapshot, &journal, expected, MismatchAction::Fail).is_err());
+    // Warn starts anyway, from the tampered replay
+    let book = warm_start(OrderBook::new, &snapshot, &journal, expected, MismatchAction::Warn).unwrap();
+    assert_ne!(book.checksum(), expected);
+}
+
+#[test]
+fn a_tampered_snapshot_or_a_journal_gap_is_caught() {
+    let journal = journal();
+    let (mut snapshot, expected) = snapshot_and_checksum(&journal, 3);
+
+    let mut gapped = journal.clone();
+    gapped.remove(4);
+    assert_eq!(
+        verify_warm_start(OrderBook::new, &snapshot, &gapped, expected).err(),
+        Some(WarmStartError::JournalGap { expected: 5, found: 6 })
+    );
+
+    snapshot.orders[0].quantity += 1;
+    assert!(matches!(
+        verify_warm_start(OrderBook::new, &snapshot, &journal, expected),
+        Err(WarmStartError::SnapshotChecksum { .. })
+    ));
+}
+
+#[test]
+fn icebergs_pegs_and_the_duplicate_window_survive_a_snapshot() {
+    let clock = Arc::new(ManualClock::new(0));
+    let mut book = OrderBook::new();
+    book.set_duplicate_window(10);
+    book.set_iceberg_refresh(IcebergRefresh { min_refresh_interval_ns: 1_000, ..Default::default() }, clock.clone());
+    book.update_reference_price(200);
+    book.add_limit_order(Order::new(1, OrderSide::Buy, 100, 10).with_display_quantity(2));
+    book.add_limit_order(Order::new(2, OrderSide::Sell, 110, 6).with_display_quantity(2));
+    book.add_limit_order(Order::new(3, OrderSide::Sell, 0, 4).pegged_to_reference(5));
+    // Takes 2's slice, parking it until its refresh interval passes
+    book.add_limit_order(Order::new(4, OrderSide::Buy, 110, 2));
+    assert_eq!(book.pending_iceberg_refreshes(), 1);
+
+    let path = scratch_path("iceberg.snapshot");
+    BookSnapshotFile::capture(&book, 4).save(&path).unwrap();
+    let snapshot = BookSnapshotFile::load(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    let mut windowed = OrderBook::new();
+    windowed.set_duplicate_window(10);
+    let mut restored = snapshot.restore_into(windowed);
+    assert_eq!(restored.checksum(), snapshot.checksum);
+    assert_eq!(restored.resting_quantity(1), Some(10));
+    assert_eq!(restored.parked_icebergs()[0].hidden_quantity, 4);
+    assert_eq!(restored.reference_price(), Some(Price::from(200)));
+
+    assert_eq!(restored.accepted_order_ids(), vec![1, 2, 3, 4]);
+    restored.update_reference_price(300);
+    assert_eq!(restored.best_ask(), Some(Price::from(305)));
+
+    // A reserve lost on the way is caught by the checksum
+    let mut stripped = snapshot.clone();
+    stripped.reserves.remove(&1);
+    assert!(matches!(
+        verify_warm_start(OrderBook::new, &stripped, &[], snapshot.checksum),
+        Err(WarmStartError::SnapshotChecksum { .. })
+    ));
+}
@@ -0,0 +1,85 @@
+// ============================================================================
+// WASH TRADE DETECTION - Repeated self-crossing flags (and throttles) an account
+// ============================================================================
+
+use hft_ringbuffer::clock::ManualClock;
+use hft_ringbuffer::matching_engine::{Order, OrderBook, OrderSide, SelfTradePrevention};
+use hft_ringbuffer::wash_trade::WashTradeConfig;
+use hft_ringbuffer::price_units::Price;
+use std::sync::Arc;
+
+const WASHER: u64 = 1;
+const QUOTER: u64 = 2;
+
+fn watched_book(throttle_ns: u64) -> (OrderBook, Arc<ManualClock>) {
+    let clock = Arc::new(ManualClock::new(0));
+    let mut book = OrderBook::new();
+    book.set_self_trade_prevention(SelfTradePrevention::CancelIncoming);
+    book.set_wash_trade_detection(
+        Some(WashTradeConfig { window_ns: 1_000, max_self_crosses: 3, throttle_ns }),
+        clock.clone(),
+    );
+    (book, clock)
+}
+
+/// `account` posts a sell and then a buy that crosses it.
+fn crossing_pair(book: &mut OrderBook, id: u64, account: u64) {
+    book.add_limit_order(Order::new(id, OrderSide::Sell, 100, 1).with_account(account));
+    book.add_limit_order(Order::new(id + 1, OrderSide::Buy, 100, 1).with_account(account));
+}
+
+#[test]
+fn rapid_self_crossing_trips_the_flag() {
+    let (mut book, clock) = watched_book(0);
+    for i in 0..2 {
+        crossing_pair(&mut book, 10 * i, WASHER);
+        clock.advance(100);
+    }
+    assert!(book.wash_trade_flag(WASHER).is_none());
+
+    crossing_pair(&mut book, 20, WASHER);
+    let flag = book.wash_trade_flag(WASHER).unwrap();
+    assert_eq!(flag.self_crosses, 3);
+    assert_eq!(flag.flagged_at_ns, 200);
+    // STP still kept every pair from printing
+    assert_eq!(book.position(WASHER), None);
+
+    assert!(book.clear_wash_trade_flag(WASHER));
+    assert!(book.wash_trade_flags().is_empty());
+}
+
+#[test]
+fn two_sided_quoting_and_slow_crossing_are_not_flagged() {
+    let (mut book, clock) = watched_book(0);
+    for i in 0..10 {
+        book.add_limit_order(Order::new(10 * i, OrderSide::Buy, 99, 1).with_account(QUOTER));
+        book.add_limit_order(Order::new(10 * i + 1, OrderSide::Sell, 101, 1).with_account(QUOTER));
+        clock.advance(10);
+    }
+    // Self-crosses spaced wider than the window never pile up
+    for i in 0..5 {
+        crossing_pair(&mut book, 1_000 + 10 * i, WASHER);
+        clock.advance(600);
+    }
+    assert!(book.wash_trade_flags().is_empty());
+}
+
+#[test]
+fn flagged_account_is_throttled_for_a_while() {
+    let (mut book, clock) = watched_book(500);
+    for i in 0..3 {
+        crossing_pair(&mut book, 10 * i, WASHER);
+    }
+    assert!(book.wash_trade_flag(WASHER).is_some());
+
+    book.add_limit_order(Order::new(100, OrderSide::Buy, 90, 1).with_account(WASHER));
+    assert_eq!(book.best_bid(), None);
+    assert_eq!(book.wash_trade_flag(WASHER).unwrap().throttled_orders, 1);
+    // Other accounts are unaffected
+    book.add_limit_order(Order::new(101, OrderSide::Buy, 91, 1).with_account(QUOTER));
+    assert_eq!(book.best_bid(), Some(Price(91)));
+
+    clock.advance(500);
+    book.add_limit_order(Order::new(102, OrderSide::Buy, 92, 1).with_account(WASHER));
+    assert_eq!(book.best_bid(), Some(Price(92)));
+}
@@ -0,0 +1,107 @@
+// ============================================================================
+// WEBSOCKET DEPTH STREAM - Handshake, snapshot, then level diffs
+// ============================================================================
+
+mod common;
+
+use common::{http_request, GatewayClient, TestServers};
+use hft_ringbuffer::websocket::{accept_key, text_frame};
+use serde_json::{json, Value};
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::TcpStream;
+use std::time::Duration;
+
+/// Sends the upgrade request and returns the response head, one line per header
+fn handshake(addr: &str, key: &str) -> (BufReader<TcpStream>, Vec<String>) {
+    let mut stream = TcpStream::connect(addr).unwrap();
+    stream.set_read_timeout(Some(Duration::from_secs(5))).unwrap();
+    let request = format!(
+        "GET /ws/depth HTTP/1.1\r\nHost: {}\r\nUpgrade: websocket\r\nConnection: Upgrade\r\nSec-WebSocket-Key: {}\r\nSec-WebSocket-Version: 13\r\n\r\n",
+        addr, key
+    );
+    stream.write_all(request.as_bytes()).unwrap();
+    let mut reader = BufReader::new(stream);
+    let mut head = Vec::new();
+    loop {
+        let mut line = String::new();
+        reader.read_line(&mut line).unwrap();
+        if line == "\r\n" {
+            return (reader, head);
+        }
+        head.push(line.trim_end().to_string());
+    }
+}
+
+/// Reads one unmasked server text frame as JSON
+fn read_message(reader: &mut impl Read) -> Value {
+    let mut header = [0u8; 2];
+    reader.read_exact(&mut header).unwrap();
+    assert_eq!(header[0], 0x81);
+    let len = match header[1] {
+        126 => {
+            let mut extended = [0u8; 2];
+            reader.read_exact(&mut extended).unwrap();
+            u16::from_be_bytes(extended) as usize
+        }
+        127 => {
+            let mut extended = [0u8; 8];
+            reader.read_exact(&mut extended).unwrap();
+            u64::from_be_bytes(extended) as usize
+        }
+        len => len as usize,
+    };
+    let mut payload = vec![0u8; len];
+    reader.read_exact(&mut payload).unwrap();
+    serde_json::from_slice(&payload).unwrap()
+}
+
+#[test]
+fn accept_key_matches_the_rfc_example() {
+    assert_eq!(accept_key("dGhlIHNhbXBsZSBub25jZQ=="), "s3pPLMBiTxaQ9kYGzzhZRbK+xOo=");
+}
+
+#[test]
+fn frames_pick_the_shortest_length_encoding() {
+    assert_eq!(text_frame("hi"), vec![0x81, 2, b'h', b'i']);
+    let medium = "x".repeat(300);
+    assert_eq!(&text_frame(&medium)[..4], &[0x81, 126, 1, 44]);
+    let large = "x".repeat(70_000);
+    assert_eq!(&text_frame(&large)[..10], &[0x81, 127, 0, 0, 0, 0, 0, 1, 0x11, 0x70]);
+}
+
+#[test]
+fn subscriber_gets_a_snapshot_then_diffs() {
+    let servers = TestServers::start();
+    let mut client = GatewayClient::connect(&servers.gateway_addr);
+    client.send_line(r#"{"id": 1, "side": "Buy", "price": 99, "quantity": 5}"#);
+
+    let (mut reader, head) = handshake(&servers.http_addr, "dGhlIHNhbXBsZSBub25jZQ==");
+    assert!(head[0].starts_with("HTTP/1.1 101"), "{:?}", head);
+    assert!(head.iter().any(|h| h == "Sec-WebSocket-Accept: s3pPLMBiTxaQ9kYGzzhZRbK+xOo="), "{:?}", head);
+
+    let snapshot = read_message(&mut reader);
+    assert_eq!(snapshot["type"], "snapshot");
+    assert_eq!(snapshot["bids"], json!([[99, 5]]));
+
+    client.send_line(r#"{"id": 2, "side": "Sell", "price": 101, "quantity": 3}"#);
+    let diff = read_message(&mut reader);
+    assert_eq!(diff["type"], "diff");
+    assert_eq!(diff["changes"], json!([{"type": "Added", "side": "Sell", "price": 101, "quantity": 3}]));
+
+    // Only the level that changed is sent
+    client.send_line(r#"{"id": 3, "side": "Sell", "price": 99, "quantity": 2}"#);
+    let diff = read_message(&mut reader);
+    assert_eq!(diff["changes"], json!([{"type": "Resized", "side": "Buy", "price": 99, "quantity": 3}]));
+    assert!(diff["sequence"].as_u64().unwrap() > snapshot["sequence"].as_u64().unwrap());
+    servers.stop();
+}
+
+#[test]
+fn plain_requests_to_the_stream_are_refused() {
+    let servers = TestServers::start();
+    let (status, body) = http_request(&servers.http_addr, "GET", "/ws/depth", "");
+    assert_eq!(status, 400);
+    assert!(body.contains("WebSocket"));
+    assert_eq!(servers.metrics.http_client_errors(), 1);
+    servers.stop();
+}